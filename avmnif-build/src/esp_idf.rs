@@ -0,0 +1,313 @@
+//! ESP-IDF component generation
+//!
+//! AtomVM's ESP32 build registers NIF collections and port drivers with the
+//! `REGISTER_NIF_COLLECTION` and `REGISTER_PORT_DRIVER` C macros, which put
+//! a descriptor in a section the VM walks at startup. `EspIdfComponent`
+//! writes a component directory holding:
+//!
+//! - `CMakeLists.txt`, registering the component and linking the crate's
+//!   static library
+//! - `<name>_registration.c`, calling the macros for the Rust exports of
+//!   each `nif_collection!` and `port_collection!`
+//!
+//! The stub is the only registration path: build the static library with
+//! avmnif-rs's `esp-idf` feature, which leaves out the link-section records
+//! that call `REGISTER_*` from Rust. The stub declares the Rust exports
+//! with their Rust signatures and adapts them where AtomVM's callbacks
+//! differ: a collection's init gets a context of its own, and the resolver's
+//! function is wrapped in the `struct Nif` AtomVM looks up.
+//!
+//! `struct Nif` has no room for the `dirty_cpu`/`dirty_io` flags of an
+//! entry, so the stub stores them, read through `<moniker>_get_nif_flags`,
+//! beside each wrapped function. The platform's scheduler glue asks for
+//! them with the stub's one non-static function:
+//!
+//! ```text
+//! uint32_t <component>_nif_flags(const struct Nif *nif);
+//! ```
+//!
+//! which returns the `NifFlags` value of a NIF the stub resolved, and 0
+//! (`Normal`) for any other.
+//!
+//! The static library does not exist yet when `build.rs` runs; the
+//! component refers to where cargo will put it, and CMake picks it up when
+//! the firmware is built. Adding the component is then one line in the
+//! AtomVM ESP32 build:
+//!
+//! ```text
+//! idf.py -DEXTRA_COMPONENT_DIRS=/path/to/my_nifs/esp-idf build
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! // build.rs of a crate with `nif_collection!(my_nifs, ...)` and
+//! // `port_collection!(gpio, init = ..., destroy = ..., ...)`
+//! use avmnif_build::{EspIdfComponent, PortDriver};
+//!
+//! EspIdfComponent::for_package()
+//!     .nif_collection("my_nifs")
+//!     .port_driver(PortDriver::new("gpio").with_init_and_destroy())
+//!     .write("esp-idf")
+//!     .expect("could not write the ESP-IDF component");
+//! ```
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A port driver exported by `port_collection!`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortDriver {
+    name: String,
+    init_and_destroy: bool,
+}
+
+impl PortDriver {
+    /// A driver declared without `init`/`destroy`
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), init_and_destroy: false }
+    }
+
+    /// The driver's `port_collection!` names `init` and `destroy`
+    pub fn with_init_and_destroy(mut self) -> Self {
+        self.init_and_destroy = true;
+        self
+    }
+}
+
+/// An ESP-IDF component wrapping a NIF crate's static library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EspIdfComponent {
+    name: String,
+    library: PathBuf,
+    nif_collections: Vec<String>,
+    port_drivers: Vec<PortDriver>,
+    requires: Vec<String>,
+}
+
+impl EspIdfComponent {
+    /// A component named `name` linking the static library at `library`
+    pub fn new(name: impl Into<String>, library: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            library: library.into(),
+            nif_collections: Vec::new(),
+            port_drivers: Vec::new(),
+            requires: vec!["libatomvm".into(), "avm_sys".into()],
+        }
+    }
+
+    /// The component for the crate whose `build.rs` is running
+    ///
+    /// Named after the crate, linking the `.a` cargo builds for the current
+    /// target and profile.
+    ///
+    /// # Panics
+    /// Outside a build script, where cargo's environment is missing.
+    pub fn for_package() -> Self {
+        let package = env::var("CARGO_PKG_NAME").expect("CARGO_PKG_NAME is set by cargo for build scripts");
+        let out_dir = env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo for build scripts");
+        let name = package.replace('-', "_");
+        let library = artifact_dir(Path::new(&out_dir)).join(format!("lib{}.a", name));
+        Self::new(name, library)
+    }
+
+    /// Register the collection of `nif_collection!(moniker, ...)`
+    pub fn nif_collection(mut self, moniker: impl Into<String>) -> Self {
+        self.nif_collections.push(moniker.into());
+        self
+    }
+
+    /// Register the driver of a `port_collection!`
+    pub fn port_driver(mut self, driver: PortDriver) -> Self {
+        self.port_drivers.push(driver);
+        self
+    }
+
+    /// Another ESP-IDF component the library needs, such as `driver`
+    pub fn requires(mut self, component: impl Into<String>) -> Self {
+        self.requires.push(component.into());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn library(&self) -> &Path {
+        &self.library
+    }
+
+    fn source_name(&self) -> String {
+        format!("{}_registration.c", self.name)
+    }
+
+    /// The component's `CMakeLists.txt`
+    pub fn cmake_lists(&self) -> String {
+        let library = self.library.display().to_string().replace('\\', "/");
+        let mut cmake = String::new();
+        cmake.push_str("# Generated by avmnif-build; changes are overwritten on the next cargo build\n\n");
+        let _ = writeln!(cmake, "idf_component_register(");
+        let _ = writeln!(cmake, "    SRCS \"{}\"", self.source_name());
+        let _ = writeln!(cmake, "    PRIV_REQUIRES {}", self.requires.join(" "));
+        // The registration descriptors are referenced by nothing but the VM's section walk
+        let _ = writeln!(cmake, "    WHOLE_ARCHIVE");
+        let _ = writeln!(cmake, ")\n");
+        let _ = writeln!(cmake, "add_prebuilt_library({}_rust \"{}\"", self.name, library);
+        let _ = writeln!(cmake, "    PRIV_REQUIRES {})", self.requires.join(" "));
+        let _ = writeln!(cmake, "target_link_libraries(${{COMPONENT_LIB}} PRIVATE {}_rust)", self.name);
+        cmake
+    }
+
+    /// The C stub registering every collection and driver
+    pub fn registration_source(&self) -> String {
+        let mut c = String::new();
+        c.push_str("// Generated by avmnif-build; changes are overwritten on the next cargo build\n\n");
+        c.push_str("#include <stdatomic.h>\n#include <stddef.h>\n#include <stdint.h>\n#include <stdio.h>\n#include <stdlib.h>\n\n");
+        c.push_str("#include <context.h>\n#include <esp32_sys.h>\n#include <exportedfunction.h>\n");
+        c.push_str("#include <globalcontext.h>\n#include <nifs.h>\n#include <port.h>\n#include <term.h>\n");
+
+        if !self.nif_collections.is_empty() {
+            c.push_str(NIF_ADAPTERS);
+            let _ = write!(
+                c,
+                "\n// The flags of a NIF resolved here, for the platform's scheduler\n\
+                 uint32_t {}_nif_flags(const struct Nif *nif)\n\
+                 {{\n    return avmnif_flags(nif);\n}}\n",
+                self.name
+            );
+        }
+
+        for moniker in &self.nif_collections {
+            let _ = write!(
+                c,
+                "\n// nif_collection!({m})\n\
+                 void {m}_nif_init(Context *ctx);\n\
+                 void {m}_nif_destroy(GlobalContext *global);\n\
+                 const void *{m}_get_nif(const char *name);\n\
+                 uint32_t {m}_get_nif_flags(const char *name);\n\
+                 \n\
+                 static void {m}_avmnif_init(GlobalContext *global)\n\
+                 {{\n    avmnif_init_collection(global, \"{m}\", {m}_nif_init);\n}}\n\
+                 \n\
+                 static const struct Nif *{m}_avmnif_resolve(const char *name)\n\
+                 {{\n    return avmnif_nif({m}_get_nif(name), {m}_get_nif_flags(name));\n}}\n\
+                 \n\
+                 REGISTER_NIF_COLLECTION({m}, {m}_avmnif_init, {m}_nif_destroy, {m}_avmnif_resolve)\n",
+                m = moniker
+            );
+        }
+
+        for driver in &self.port_drivers {
+            let p = &driver.name;
+            let _ = write!(c, "\n// port_collection!({})\n", p);
+            if driver.init_and_destroy {
+                let _ = writeln!(c, "void {}_init(GlobalContext *global);", p);
+                let _ = writeln!(c, "void {}_destroy(GlobalContext *global);", p);
+            }
+            let _ = writeln!(c, "Context *{}_create_port(const GlobalContext *global, term opts);", p);
+            let _ = write!(
+                c,
+                "\nstatic Context *{p}_avmnif_create_port(GlobalContext *global, term opts)\n\
+                 {{\n    return {p}_create_port(global, opts);\n}}\n\n",
+                p = p
+            );
+            let (init, destroy) = if driver.init_and_destroy {
+                (format!("{}_init", p), format!("{}_destroy", p))
+            } else {
+                ("NULL".into(), "NULL".into())
+            };
+            let _ = writeln!(c, "REGISTER_PORT_DRIVER({}, {}, {}, {}_avmnif_create_port)", p, init, destroy, p);
+        }
+        c
+    }
+
+    /// Write the component to `dir/<name>`, returning that directory
+    ///
+    /// Files are only rewritten when their contents change, so CMake does
+    /// not rebuild the component on every cargo build.
+    pub fn write(&self, dir: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let component_dir = dir.as_ref().join(&self.name);
+        fs::create_dir_all(&component_dir)?;
+        write_if_changed(&component_dir.join("CMakeLists.txt"), &self.cmake_lists())?;
+        write_if_changed(&component_dir.join(self.source_name()), &self.registration_source())?;
+        Ok(component_dir)
+    }
+}
+
+/// Adapters between the Rust exports of a `nif_collection!` and AtomVM's callbacks
+const NIF_ADAPTERS: &str = r#"
+// A collection's Rust init takes a process context, AtomVM's a global one
+static void avmnif_init_collection(GlobalContext *global, const char *name, void (*init)(Context *ctx))
+{
+    Context *ctx = context_new(global);
+    if (ctx == NULL) {
+        fprintf(stderr, "avmnif: no memory to initialize NIF collection %s\n", name);
+        return;
+    }
+    init(ctx);
+    context_destroy(ctx);
+}
+
+// The Rust resolvers return the NIF function, AtomVM's a struct Nif. One
+// record is kept per function, with the flags struct Nif has no field for;
+// a race may add a duplicate, which is harmless.
+struct AvmnifNif
+{
+    struct Nif nif;
+    uint32_t flags;
+    struct AvmnifNif *next;
+};
+
+static _Atomic(struct AvmnifNif *) avmnif_nifs;
+
+static const struct Nif *avmnif_nif(const void *function, uint32_t flags)
+{
+    if (function == NULL) {
+        return NULL;
+    }
+    struct AvmnifNif *head = atomic_load(&avmnif_nifs);
+    for (struct AvmnifNif *record = head; record != NULL; record = record->next) {
+        if (record->nif.nif_ptr == (NifImpl) function) {
+            return &record->nif;
+        }
+    }
+    struct AvmnifNif *record = malloc(sizeof(struct AvmnifNif));
+    if (record == NULL) {
+        return NULL;
+    }
+    record->nif.base.type = NIFFunctionType;
+    record->nif.nif_ptr = (NifImpl) function;
+    record->flags = flags;
+    do {
+        record->next = head;
+    } while (!atomic_compare_exchange_weak(&avmnif_nifs, &head, record));
+    return &record->nif;
+}
+
+static uint32_t avmnif_flags(const struct Nif *nif)
+{
+    for (struct AvmnifNif *record = atomic_load(&avmnif_nifs); record != NULL; record = record->next) {
+        if (&record->nif == nif) {
+            return record->flags;
+        }
+    }
+    return 0;
+}
+"#;
+
+/// `target/<triple>/<profile>` from a build script's `OUT_DIR`
+/// (`target/<triple>/<profile>/build/<package>-<hash>/out`)
+pub fn artifact_dir(out_dir: &Path) -> PathBuf {
+    out_dir.ancestors().nth(3).unwrap_or(out_dir).to_path_buf()
+}
+
+fn write_if_changed(path: &Path, contents: &str) -> io::Result<()> {
+    if fs::read_to_string(path).is_ok_and(|current| current == contents) {
+        return Ok(());
+    }
+    fs::write(path, contents)
+}
@@ -0,0 +1,8 @@
+//! Build script helpers for crates using avmnif-rs
+//!
+//! Runs on the host from a NIF crate's `build.rs`, so unlike avmnif-rs it
+//! uses `std`.
+
+pub mod esp_idf;
+
+pub use esp_idf::{EspIdfComponent, PortDriver};
@@ -0,0 +1,488 @@
+//! Derive macros for avmnif-rs
+//!
+//! `#[derive(TaggedMap)]` implements `avmnif_rs::tagged::TaggedMap` and
+//! `TaggedField` for structs with named fields and for enums with unit and
+//! struct variants. Use it through the `derive` feature of avmnif-rs.
+//!
+//! `NifStruct`, `NifMap`, `NifTuple` and `NifUnitEnum` take rustler's
+//! attributes and produce rustler's encodings, for code moving from rustler
+//! to AtomVM (`rustler-compat` feature).
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Expr, Fields, Lit, Meta};
+
+/// Same rule as `avmnif_rs::tagged::to_snake_case`
+fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut result = String::new();
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch.is_uppercase() {
+            if i > 0 && chars[i - 1].is_lowercase() {
+                result.push('_');
+            }
+            result.extend(ch.to_lowercase());
+        } else {
+            result.push(ch);
+        }
+    }
+    result
+}
+
+fn expand(input: DeriveInput, generate: fn(&DeriveInput) -> syn::Result<Codec>) -> TokenStream {
+    match generate(&input) {
+        Ok(codec) => codec.into_impls(&input).into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Bodies of the generated `TaggedMap` methods
+struct Codec {
+    type_name: String,
+    encode: TokenStream2,
+    decode: TokenStream2,
+}
+
+impl Codec {
+    fn into_impls(self, input: &DeriveInput) -> TokenStream2 {
+        let name = &input.ident;
+        let mut generics = input.generics.clone();
+        for param in generics.type_params_mut() {
+            param.bounds.push(syn::parse_quote!(::avmnif_rs::tagged::TaggedField));
+        }
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        let Codec { type_name, encode, decode } = self;
+
+        quote! {
+            impl #impl_generics ::avmnif_rs::tagged::TaggedMap for #name #ty_generics #where_clause {
+                fn to_tagged_map<__T: ::avmnif_rs::atom::AtomTableOps>(
+                    &self,
+                    table: &__T,
+                ) -> ::avmnif_rs::tagged::TaggedResult<::avmnif_rs::term::TermValue> {
+                    #encode
+                }
+
+                fn from_tagged_map<__T: ::avmnif_rs::atom::AtomTableOps>(
+                    map: ::avmnif_rs::term::TermValue,
+                    table: &__T,
+                ) -> ::avmnif_rs::tagged::TaggedResult<Self> {
+                    let map = &map;
+                    #decode
+                }
+
+                fn type_name() -> &'static str {
+                    #type_name
+                }
+            }
+
+            impl #impl_generics ::avmnif_rs::tagged::TaggedField for #name #ty_generics #where_clause {
+                fn to_field<__T: ::avmnif_rs::atom::AtomTableOps>(
+                    &self,
+                    table: &__T,
+                ) -> ::avmnif_rs::tagged::TaggedResult<::avmnif_rs::term::TermValue> {
+                    ::avmnif_rs::tagged::TaggedMap::to_tagged_map(self, table)
+                }
+
+                fn from_field<__T: ::avmnif_rs::atom::AtomTableOps>(
+                    value: &::avmnif_rs::term::TermValue,
+                    table: &__T,
+                ) -> ::avmnif_rs::tagged::TaggedResult<Self> {
+                    <Self as ::avmnif_rs::tagged::TaggedMap>::from_tagged_map(value.clone(), table)
+                }
+            }
+        }
+    }
+}
+
+/// A named field and its `#[tagged(...)]` options
+struct Field {
+    ident: syn::Ident,
+    /// Key in the map: the field name, or `rename`
+    name: String,
+    /// Left out of the map and decoded as `Default::default()`
+    skip: bool,
+    /// Decoded as `Default::default()` when the map lacks it
+    default: bool,
+}
+
+impl Field {
+    fn parse(field: &syn::Field) -> syn::Result<Self> {
+        let ident = field.ident.clone().expect("named field");
+        let mut parsed = Field {
+            name: ident.to_string().trim_start_matches("r#").to_string(),
+            ident,
+            skip: false,
+            default: false,
+        };
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("tagged")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    parsed.name = meta.value()?.parse::<syn::LitStr>()?.value();
+                } else if meta.path.is_ident("skip") {
+                    parsed.skip = true;
+                } else if meta.path.is_ident("default") {
+                    parsed.default = true;
+                } else {
+                    return Err(meta.error("expected `rename = \"...\"`, `skip` or `default`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(parsed)
+    }
+}
+
+/// Named fields with their options
+fn named_fields(fields: &Fields, what: &str) -> syn::Result<Vec<Field>> {
+    match fields {
+        Fields::Named(named) => named.named.iter().map(Field::parse).collect(),
+        Fields::Unit => Ok(Vec::new()),
+        Fields::Unnamed(unnamed) => Err(Error::new_spanned(
+            unnamed,
+            format!("{} with unnamed fields are not supported; name the fields", what),
+        )),
+    }
+}
+
+/// `push_field` calls for fields bound to their own names
+fn push_fields(fields: &[Field], receiver: Option<TokenStream2>) -> TokenStream2 {
+    let pushes = fields.iter().filter(|field| !field.skip).map(|Field { ident, name, .. }| {
+        let value = match &receiver {
+            Some(receiver) => quote!(&#receiver.#ident),
+            None => quote!(#ident),
+        };
+        quote! { ::avmnif_rs::tagged::push_field(&mut pairs, #name, #value, table)?; }
+    });
+    quote!(#(#pushes)*)
+}
+
+/// Field initializers decoding each field from `map`
+fn decode_fields(fields: &[Field]) -> TokenStream2 {
+    let inits = fields.iter().map(|Field { ident, name, skip, default }| {
+        if *skip {
+            quote! { #ident: ::core::default::Default::default() }
+        } else if *default {
+            quote! { #ident: ::avmnif_rs::tagged::field_or_default(map, #name, table)? }
+        } else {
+            quote! { #ident: ::avmnif_rs::tagged::field(map, #name, table)? }
+        }
+    });
+    quote!(#(#inits),*)
+}
+
+/// How a derived type is encoded, from `#[tagged(...)]` on the type
+enum Mode {
+    /// `#{type => snake_name, ...}`
+    Map,
+    /// `{snake_name, ...}`
+    Record,
+    /// `%Module{...}`, with the full module atom name
+    Struct(String),
+}
+
+fn container_mode(input: &DeriveInput) -> syn::Result<Mode> {
+    let mut mode = Mode::Map;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("tagged")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("record") {
+                mode = Mode::Record;
+            } else if meta.path.is_ident("module") {
+                let module = meta.value()?.parse::<syn::LitStr>()?.value();
+                mode = match module.starts_with("Elixir.") {
+                    true => Mode::Struct(module),
+                    false => Mode::Struct(format!("Elixir.{}", module)),
+                };
+            } else {
+                return Err(meta.error("expected `record` or `module = \"...\"`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(mode)
+}
+
+/// `#[tagged(record)]` structs: `{snake_name, field, ...}` in declaration order
+fn tagged_record(input: &DeriveInput, type_name: String) -> syn::Result<Codec> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(Span::call_site(), "#[tagged(record)] is only supported on structs"));
+    };
+    let fields = named_fields(&data.fields, "structs")?;
+    let encoded: Vec<&Field> = fields.iter().filter(|field| !field.skip).collect();
+    let arity = encoded.len();
+    let pushes = encoded.iter().map(|Field { ident, name, .. }| {
+        quote! { ::avmnif_rs::tagged::push_element(&mut elements, #name, &self.#ident, table)?; }
+    });
+    let mut index = 0usize;
+    let inits = fields.iter().map(|Field { ident, name, skip, default }| {
+        if *skip {
+            return quote! { #ident: ::core::default::Default::default() };
+        }
+        let position = index;
+        index += 1;
+        if *default {
+            quote! { #ident: ::avmnif_rs::tagged::record_field_or_default(fields, #position, #name, table)? }
+        } else {
+            quote! { #ident: ::avmnif_rs::tagged::record_field(fields, #position, #name, table)? }
+        }
+    });
+    let inits: Vec<TokenStream2> = inits.collect();
+    Ok(Codec {
+        encode: quote! {
+            let mut elements = ::avmnif_rs::tagged::record_elements(#type_name, table)?;
+            #(#pushes)*
+            Ok(::avmnif_rs::term::TermValue::Tuple(elements))
+        },
+        decode: quote! {
+            #[allow(unused_variables)]
+            let fields = ::avmnif_rs::tagged::record_fields(map, #type_name, #arity, table)?;
+            Ok(Self { #(#inits),* })
+        },
+        type_name,
+    })
+}
+
+fn tagged_map(input: &DeriveInput) -> syn::Result<Codec> {
+    let type_name = to_snake_case(&input.ident.to_string());
+    match container_mode(input)? {
+        Mode::Map => {}
+        Mode::Record => return tagged_record(input, type_name),
+        Mode::Struct(module) => {
+            let Data::Struct(data) = &input.data else {
+                return Err(Error::new(Span::call_site(), "#[tagged(module)] is only supported on structs"));
+            };
+            return Ok(elixir_struct(&named_fields(&data.fields, "structs")?, module));
+        }
+    }
+    match &input.data {
+        Data::Struct(data) => {
+            let fields = named_fields(&data.fields, "structs")?;
+            let pushes = push_fields(&fields, Some(quote!(self)));
+            let inits = decode_fields(&fields);
+            Ok(Codec {
+                encode: quote! {
+                    let mut pairs = ::avmnif_rs::tagged::tagged_pairs(#type_name, table)?;
+                    #pushes
+                    Ok(::avmnif_rs::term::TermValue::Map(pairs))
+                },
+                decode: quote! {
+                    ::avmnif_rs::tagged::validate_type_discriminator(map, #type_name, table)?;
+                    Ok(Self { #inits })
+                },
+                type_name,
+            })
+        }
+        Data::Enum(data) => {
+            let enum_name = input.ident.to_string();
+            let mut encode_arms = Vec::new();
+            let mut decode_arms = Vec::new();
+            for variant in &data.variants {
+                let ident = &variant.ident;
+                let variant_name = to_snake_case(&ident.to_string());
+                let fields = named_fields(&variant.fields, "enum variants")?;
+                let bindings = fields.iter().filter(|field| !field.skip).map(|field| &field.ident);
+                let pushes = push_fields(&fields, None);
+                let inits = decode_fields(&fields);
+                encode_arms.push(quote! {
+                    Self::#ident { #(#bindings,)* .. } => {
+                        ::avmnif_rs::tagged::push_variant(&mut pairs, #variant_name, table)?;
+                        #pushes
+                    }
+                });
+                decode_arms.push(quote! {
+                    #variant_name => Ok(Self::#ident { #inits }),
+                });
+            }
+            Ok(Codec {
+                encode: quote! {
+                    let mut pairs = ::avmnif_rs::tagged::tagged_pairs(#type_name, table)?;
+                    match self {
+                        #(#encode_arms)*
+                    }
+                    Ok(::avmnif_rs::term::TermValue::Map(pairs))
+                },
+                decode: quote! {
+                    ::avmnif_rs::tagged::validate_type_discriminator(map, #type_name, table)?;
+                    let variant = ::avmnif_rs::tagged::variant_name(map, table)?;
+                    match variant.as_str() {
+                        #(#decode_arms)*
+                        other => Err(::avmnif_rs::tagged::TaggedError::invalid_variant(#enum_name, other)),
+                    }
+                },
+                type_name,
+            })
+        }
+        Data::Union(data) => Err(Error::new_spanned(data.union_token, "unions cannot derive TaggedMap")),
+    }
+}
+
+/// `#[derive(TaggedMap)]`: `#{type => snake_name, field => value, ...}`
+///
+/// Enum variants add `variant => snake_name` and their own fields.
+///
+/// `#[tagged(record)]` on a struct encodes it as a record instead:
+/// `{snake_name, field, ...}` with the fields in declaration order.
+/// `#[tagged(module = "Elixir.Point")]` encodes it as that Elixir struct,
+/// `%Point{field => value, ...}`; the `Elixir.` prefix may be left off.
+///
+/// Field attributes:
+/// - `#[tagged(rename = "key")]`: use `key` in the map instead of the field name
+/// - `#[tagged(skip)]`: leave the field out; decoded as `Default::default()`
+/// - `#[tagged(default)]`: decode a missing field as `Default::default()`
+#[proc_macro_derive(TaggedMap, attributes(tagged))]
+pub fn derive_tagged_map(input: TokenStream) -> TokenStream {
+    expand(parse_macro_input!(input as DeriveInput), tagged_map)
+}
+
+// ── rustler compatibility ──────────────────────────────────────────────────
+
+/// The string of `#[name = "..."]`, if present
+fn string_attribute(input: &DeriveInput, name: &str) -> syn::Result<Option<String>> {
+    for attr in &input.attrs {
+        if let Meta::NameValue(meta) = &attr.meta {
+            if meta.path.is_ident(name) {
+                return match &meta.value {
+                    Expr::Lit(expr) => match &expr.lit {
+                        Lit::Str(value) => Ok(Some(value.value())),
+                        _ => Err(Error::new_spanned(&meta.value, format!("`{}` must be a string", name))),
+                    },
+                    _ => Err(Error::new_spanned(&meta.value, format!("`{}` must be a string", name))),
+                };
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn struct_fields<'a>(input: &'a DeriveInput, derive: &str) -> syn::Result<&'a Fields> {
+    match &input.data {
+        Data::Struct(data) => Ok(&data.fields),
+        _ => Err(Error::new(Span::call_site(), format!("{} can only be derived for structs", derive))),
+    }
+}
+
+/// `%Module{field => value, ...}`, shared by `NifStruct` and `#[tagged(module)]`
+fn elixir_struct(fields: &[Field], module: String) -> Codec {
+    let pushes = push_fields(fields, Some(quote!(self)));
+    let inits = decode_fields(fields);
+    Codec {
+        encode: quote! {
+            let mut pairs = ::avmnif_rs::tagged::struct_pairs(#module, table)?;
+            #pushes
+            Ok(::avmnif_rs::term::TermValue::Map(pairs))
+        },
+        decode: quote! {
+            ::avmnif_rs::tagged::validate_struct_module(map, #module, table)?;
+            Ok(Self { #inits })
+        },
+        type_name: module,
+    }
+}
+
+fn nif_struct(input: &DeriveInput) -> syn::Result<Codec> {
+    let fields = named_fields(struct_fields(input, "NifStruct")?, "structs")?;
+    let module = string_attribute(input, "module")?.ok_or_else(|| {
+        Error::new(Span::call_site(), "NifStruct needs the Elixir module: #[module = \"Elixir.Name\"]")
+    })?;
+    Ok(elixir_struct(&fields, module))
+}
+
+fn nif_map(input: &DeriveInput) -> syn::Result<Codec> {
+    let fields = named_fields(struct_fields(input, "NifMap")?, "structs")?;
+    let pushes = push_fields(&fields, Some(quote!(self)));
+    let inits = decode_fields(&fields);
+    Ok(Codec {
+        encode: quote! {
+            let mut pairs = ::avmnif_rs::tagged::__private::Vec::new();
+            #pushes
+            Ok(::avmnif_rs::term::TermValue::Map(pairs))
+        },
+        decode: quote! {
+            Ok(Self { #inits })
+        },
+        type_name: to_snake_case(&input.ident.to_string()),
+    })
+}
+
+fn nif_tuple(input: &DeriveInput) -> syn::Result<Codec> {
+    let fields = struct_fields(input, "NifTuple")?;
+    let members: Vec<syn::Member> = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => syn::Member::Named(ident.clone()),
+            None => syn::Member::Unnamed(index.into()),
+        })
+        .collect();
+    let arity = members.len();
+    let indexes = 0..arity;
+    Ok(Codec {
+        encode: quote! {
+            Ok(::avmnif_rs::term::TermValue::Tuple(::avmnif_rs::tagged::__private::vec![
+                #( ::avmnif_rs::tagged::TaggedField::to_field(&self.#members, table)? ),*
+            ]))
+        },
+        decode: quote! {
+            let elements = ::avmnif_rs::tagged::tuple_elements(map, #arity)?;
+            Ok(Self {
+                #( #members: ::avmnif_rs::tagged::TaggedField::from_field(&elements[#indexes], table)? ),*
+            })
+        },
+        type_name: to_snake_case(&input.ident.to_string()),
+    })
+}
+
+fn nif_unit_enum(input: &DeriveInput) -> syn::Result<Codec> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new(Span::call_site(), "NifUnitEnum can only be derived for enums"));
+    };
+    let enum_name = input.ident.to_string();
+    let mut encode_arms = Vec::new();
+    let mut decode_arms = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(&variant.fields, "NifUnitEnum variants cannot have fields"));
+        }
+        let ident = &variant.ident;
+        let atom = to_snake_case(&ident.to_string());
+        encode_arms.push(quote! { Self::#ident => #atom, });
+        decode_arms.push(quote! { #atom => Ok(Self::#ident), });
+    }
+    Ok(Codec {
+        encode: quote! {
+            let name = match self { #(#encode_arms)* };
+            Ok(::avmnif_rs::term::TermValue::Atom(::avmnif_rs::tagged::get_type_atom(name, table)?))
+        },
+        decode: quote! {
+            match ::avmnif_rs::tagged::atom_name(map, table)?.as_str() {
+                #(#decode_arms)*
+                other => Err(::avmnif_rs::tagged::TaggedError::invalid_variant(#enum_name, other)),
+            }
+        },
+        type_name: to_snake_case(&enum_name),
+    })
+}
+
+/// rustler's `NifStruct`: `%Module{field: value, ...}`, with `#[module = "Elixir.Module"]`
+#[proc_macro_derive(NifStruct, attributes(module, rustler))]
+pub fn derive_nif_struct(input: TokenStream) -> TokenStream {
+    expand(parse_macro_input!(input as DeriveInput), nif_struct)
+}
+
+/// rustler's `NifMap`: `%{field: value, ...}` without a type key
+#[proc_macro_derive(NifMap, attributes(rustler))]
+pub fn derive_nif_map(input: TokenStream) -> TokenStream {
+    expand(parse_macro_input!(input as DeriveInput), nif_map)
+}
+
+/// rustler's `NifTuple`: the fields as a tuple, in order
+#[proc_macro_derive(NifTuple, attributes(rustler))]
+pub fn derive_nif_tuple(input: TokenStream) -> TokenStream {
+    expand(parse_macro_input!(input as DeriveInput), nif_tuple)
+}
+
+/// rustler's `NifUnitEnum`: each variant as a snake_case atom
+#[proc_macro_derive(NifUnitEnum, attributes(rustler))]
+pub fn derive_nif_unit_enum(input: TokenStream) -> TokenStream {
+    expand(parse_macro_input!(input as DeriveInput), nif_unit_enum)
+}
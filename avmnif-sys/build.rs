@@ -0,0 +1,105 @@
+//! Generates the AtomVM declarations from the VM's headers
+//!
+//! Only with the `bindgen` feature and `ATOMVM_INCLUDE_DIR` set to the
+//! directory holding `atom_table.h`, `erl_nif.h` and `port.h` (AtomVM's
+//! `src/libAtomVM`). Otherwise the hand-written `src/atomvm.rs` is used.
+//! `ATOMVM_PLATFORM_INCLUDE_DIR` adds the platform's include directory,
+//! and `ATOMVM_CLANG_ARGS` any further clang arguments (target, defines).
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=ATOMVM_INCLUDE_DIR");
+    println!("cargo:rerun-if-env-changed=ATOMVM_PLATFORM_INCLUDE_DIR");
+    println!("cargo:rerun-if-env-changed=ATOMVM_CLANG_ARGS");
+    println!("cargo:rustc-check-cfg=cfg(avmnif_sys_bindgen)");
+
+    #[cfg(feature = "bindgen")]
+    generate::run();
+}
+
+#[cfg(feature = "bindgen")]
+mod generate {
+    use std::env;
+    use std::path::PathBuf;
+
+    /// Functions taken from the headers; the rest of the API is platform glue
+    const FUNCTIONS: &[&str] = &[
+        "atom_table_get_atom_string",
+        "atom_table_ensure_atom",
+        "atom_table_ensure_atoms",
+        "atom_table_count",
+        "atom_table_is_equal_to_atom_string",
+        "atom_table_cmp_using_atom_index",
+        "enif_init_resource_type",
+        "enif_alloc_resource",
+        "enif_make_resource",
+        "enif_get_resource",
+        "enif_keep_resource",
+        "enif_release_resource",
+        "enif_select",
+        "enif_monitor_process",
+        "enif_demonitor_process",
+        "port_send_reply",
+    ];
+
+    /// Defined in `types.rs`; the generated code refers to those instead
+    const TYPES: &[&str] = &[
+        "term",
+        "ERL_NIF_TERM",
+        "atom_index_t",
+        "Context",
+        "GlobalContext",
+        "Heap",
+        "AtomTable",
+        "Message",
+        "ErlNifEnv",
+        "ErlNifResourceType",
+        "ErlNifPid",
+        "ErlNifEvent",
+        "ErlNifMonitor",
+        "ErlNifResourceTypeInit",
+        "ErlNifResourceFlags",
+        "ErlNifSelectFlags",
+        "ErlNifResourceDtor",
+        "ErlNifResourceStop",
+        "ErlNifResourceDown",
+    ];
+
+    const HEADER: &str =
+        "#include \"atom_table.h\"\n#include \"erl_nif.h\"\n#include \"port.h\"\n";
+
+    pub fn run() {
+        let Some(include_dir) = env::var_os("ATOMVM_INCLUDE_DIR") else {
+            println!("cargo:warning=avmnif-sys: ATOMVM_INCLUDE_DIR is not set, using the hand-written declarations");
+            return;
+        };
+
+        let mut builder = bindgen::Builder::default()
+            .header_contents("avmnif_sys.h", HEADER)
+            .clang_arg(format!("-I{}", PathBuf::from(include_dir).display()))
+            .use_core()
+            .ctypes_prefix("core::ffi")
+            .size_t_is_usize(true)
+            .layout_tests(false)
+            .generate_comments(false)
+            .default_enum_style(bindgen::EnumVariation::Consts)
+            .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()));
+        if let Some(platform_dir) = env::var_os("ATOMVM_PLATFORM_INCLUDE_DIR") {
+            builder = builder.clang_arg(format!("-I{}", PathBuf::from(platform_dir).display()));
+        }
+        if let Ok(args) = env::var("ATOMVM_CLANG_ARGS") {
+            builder = builder.clang_args(args.split_whitespace());
+        }
+        for function in FUNCTIONS {
+            builder = builder.allowlist_function(function);
+        }
+        for ty in TYPES {
+            builder = builder.blocklist_type(ty);
+        }
+
+        let bindings = builder.generate().expect("avmnif-sys: bindgen failed on the AtomVM headers");
+        let out = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("atomvm.rs");
+        bindings.write_to_file(out).expect("avmnif-sys: could not write the generated declarations");
+        println!("cargo:rustc-cfg=avmnif_sys_bindgen");
+    }
+}
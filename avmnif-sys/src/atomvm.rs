@@ -0,0 +1,127 @@
+//! Hand-written declarations of AtomVM's own C API
+//!
+//! Used when the declarations are not generated. Every function here is
+//! declared in AtomVM's headers (`atom_table.h`, `erl_nif.h`, `port.h`),
+//! and the `bindgen` feature replaces this module with declarations
+//! generated from them, so a signature that no longer matches the VM fails
+//! to compile instead of misbehaving at run time.
+
+use crate::types::*;
+use core::ffi::{c_char, c_int, c_uint, c_void};
+
+// AtomVM atom table FFI declarations (atom_table.h)
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    pub fn atom_table_get_atom_string(
+        table: *mut AtomTable,
+        index: atom_index_t,
+        out_size: *mut usize,
+    ) -> *const c_char;
+
+    pub fn atom_table_ensure_atom(
+        table: *mut AtomTable,
+        atom_data: *const c_char,
+        atom_len: usize,
+        opts: c_uint,
+        result: *mut atom_index_t,
+    ) -> c_uint;
+
+    pub fn atom_table_ensure_atoms(
+        table: *mut AtomTable,
+        atoms: *const c_void,
+        count: usize,
+        translate_table: *mut atom_index_t,
+        opts: c_uint,
+    ) -> c_uint;
+
+    pub fn atom_table_count(table: *mut AtomTable) -> usize;
+
+    pub fn atom_table_is_equal_to_atom_string(
+        table: *mut AtomTable,
+        atom_index: atom_index_t,
+        string_data: *const c_char,
+        string_len: usize,
+    ) -> bool;
+
+    pub fn atom_table_cmp_using_atom_index(
+        table: *mut AtomTable,
+        atom1: atom_index_t,
+        atom2: atom_index_t,
+    ) -> c_int;
+}
+
+// AtomVM Resource NIF FFI declarations (exact signatures from erl_nif.h)
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    /// Create or take over a resource type
+    pub fn enif_init_resource_type(
+        env: *mut ErlNifEnv,
+        name: *const c_char,
+        init: *const ErlNifResourceTypeInit,
+        flags: ErlNifResourceFlags,
+        tried: *mut ErlNifResourceFlags,
+    ) -> *mut ErlNifResourceType;
+
+    /// Allocate a new resource of the specified type and size
+    pub fn enif_alloc_resource(
+        resource_type: *mut ErlNifResourceType,
+        size: c_uint,
+    ) -> *mut c_void;
+
+    /// Create an Erlang term from a resource pointer
+    pub fn enif_make_resource(
+        env: *mut ErlNifEnv,
+        obj: *mut c_void,
+    ) -> ERL_NIF_TERM;
+
+    /// Extract a resource from an Erlang term
+    pub fn enif_get_resource(
+        env: *mut ErlNifEnv,
+        t: ERL_NIF_TERM,
+        resource_type: *mut ErlNifResourceType,
+        objp: *mut *mut c_void,
+    ) -> c_int;
+
+    /// Increment resource reference count
+    pub fn enif_keep_resource(obj: *mut c_void) -> c_int;
+
+    /// Decrement resource reference count
+    pub fn enif_release_resource(obj: *mut c_void) -> c_int;
+
+    /// Select on file descriptors
+    pub fn enif_select(
+        env: *mut ErlNifEnv,
+        event: ErlNifEvent,
+        mode: ErlNifSelectFlags,
+        obj: *mut c_void,
+        pid: *const ErlNifPid,
+        reference: ERL_NIF_TERM,
+    ) -> c_int;
+
+    /// Monitor a process using a resource
+    pub fn enif_monitor_process(
+        env: *mut ErlNifEnv,
+        obj: *mut c_void,
+        target_pid: *const ErlNifPid,
+        mon: *mut ErlNifMonitor,
+    ) -> c_int;
+
+    /// Remove a process monitor
+    pub fn enif_demonitor_process(
+        caller_env: *mut ErlNifEnv,
+        obj: *mut c_void,
+        mon: *const ErlNifMonitor,
+    ) -> c_int;
+}
+
+// AtomVM Port API FFI declarations (port.h)
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    /// Send a reply to an Erlang process from port context
+    pub fn port_send_reply(
+        ctx: *mut Context,
+        pid: ERL_NIF_TERM,
+        reference: ERL_NIF_TERM,
+        reply: ERL_NIF_TERM,
+    );
+}
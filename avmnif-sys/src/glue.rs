@@ -0,0 +1,166 @@
+//! Declarations of the platform glue
+//!
+//! These functions are not part of AtomVM's headers. Each platform's C
+//! glue implements them on top of VM internals, so they are never
+//! generated and stay hand-written with or without `bindgen`.
+
+use crate::types::*;
+use core::ffi::{c_char, c_int, c_void};
+
+// AtomVM Context API FFI declarations
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    /// Create a new port context
+    pub fn create_port_context(global: *const GlobalContext) -> *mut Context;
+
+    /// Destroy a port context and clean up resources
+    pub fn destroy_port_context(ctx: *mut Context);
+
+    /// Check if a port is still alive
+    pub fn port_is_alive(ctx: *const Context) -> i32;
+
+    /// Get platform data from context
+    pub fn context_get_platform_data(ctx: *const Context) -> *mut c_void;
+
+    /// Set platform data in context
+    pub fn context_set_platform_data(ctx: *mut Context, data: *mut c_void);
+
+    /// Get user data from context (for storing Erlang terms)
+    pub fn context_get_user_data(ctx: *const Context) -> u64;
+
+    /// Set user data in context
+    pub fn context_set_user_data(ctx: *mut Context, data: u64);
+
+    /// Get the global context pointer (for ISR use)
+    pub fn global_context_ptr() -> *mut GlobalContext;
+
+    /// Get the global context a context belongs to
+    ///
+    /// C contract: `GlobalContext *context_get_global(const Context *ctx)`
+    /// returns `ctx->global`. It never fails for a live context and may be
+    /// called from any scheduler thread; the result stays valid for the
+    /// lifetime of the VM.
+    pub fn context_get_global(ctx: *const Context) -> *mut GlobalContext;
+}
+
+// Heap and exception glue
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    /// Get the heap of a process context
+    pub fn context_heap(ctx: *mut Context) -> *mut Heap;
+
+    /// Allocate `size` words on a heap; the caller must have ensured free space
+    ///
+    /// C contract: wraps `memory_heap_alloc(heap, size)`, which `memory.h`
+    /// defines `static inline` and so has no symbol to link against.
+    pub fn avmnif_memory_heap_alloc(heap: *mut Heap, size: usize) -> *mut term;
+
+    /// Make sure `size` words are free on the context's heap, running GC if needed
+    ///
+    /// C contract: wraps `memory_ensure_free(ctx, size)` and returns 0 when
+    /// it returns `MEMORY_GC_OK`, nonzero for any other `MemoryGCResult`.
+    pub fn avmnif_memory_ensure_free(ctx: *mut Context, size: usize) -> c_int;
+
+    /// Store a pending exception in the context (x[0] = class atom, x[1] = reason)
+    ///
+    /// `class` is 0 for error, 1 for throw and 2 for exit.
+    pub fn context_raise_exception(ctx: *mut Context, class: c_int, reason: term);
+
+    /// Allocate a standalone heap of `size` words, not owned by any process
+    pub fn owned_heap_create(size: usize) -> *mut Heap;
+
+    /// Free everything allocated on a standalone heap, keeping it usable
+    pub fn owned_heap_clear(heap: *mut Heap);
+
+    /// Free a standalone heap
+    pub fn owned_heap_destroy(heap: *mut Heap);
+}
+
+// Logging glue
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    /// Write one NUL-terminated log line to the platform's console
+    pub fn avmnif_log(msg: *const c_char);
+}
+
+// NIF collection private data glue
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    /// Private data a NIF collection keeps on the VM, null if none is set
+    pub fn global_context_get_nif_priv(global: *mut GlobalContext, collection: *const c_char) -> *mut c_void;
+
+    /// Set the private data of a NIF collection, replacing the previous pointer
+    pub fn global_context_set_nif_priv(global: *mut GlobalContext, collection: *const c_char, data: *mut c_void);
+}
+
+// Atom table glue
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    /// The atom table of the running VM
+    pub fn atomvm_get_global_atom_table() -> *mut AtomTable;
+}
+
+// AtomVM Port API FFI declarations
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    /// Send an async message to an Erlang process from any context (ISR-safe)
+    pub fn port_send_message_from_task(
+        global: *mut GlobalContext,
+        pid: u32,
+        message: ERL_NIF_TERM,
+    );
+
+    /// Decode an ETF message into the target process's mailbox
+    ///
+    /// Copies the term onto the receiver's heap; returns nonzero on success,
+    /// zero if the process does not exist or memory is exhausted.
+    ///
+    /// C contract: `int port_send_external_term(GlobalContext *global,
+    /// uint32_t pid, const uint8_t *data, size_t len)`. `data` is `len`
+    /// bytes of ETF starting with the version byte 131, and is only
+    /// borrowed for the call. The glue decodes it onto a heap it owns
+    /// (`externalterm_to_term` on a scratch heap), hands the term to
+    /// `globalcontext_send_message(global, pid, term)`, which copies it
+    /// into the mailbox, and frees the scratch heap. It may be called from
+    /// a scheduler thread or a task, but not from an interrupt handler;
+    /// those go through `port_send_message_from_task`.
+    pub fn port_send_external_term(
+        global: *mut GlobalContext,
+        pid: u32,
+        data: *const u8,
+        len: usize,
+    ) -> c_int;
+
+    /// Reply to a gen_call with an ETF-encoded term, decoded onto the caller's heap
+    ///
+    /// Returns nonzero on success.
+    pub fn port_send_external_reply(
+        ctx: *mut Context,
+        pid: ERL_NIF_TERM,
+        reference: ERL_NIF_TERM,
+        data: *const u8,
+        len: usize,
+    ) -> c_int;
+
+    /// Monitor a process from the port
+    ///
+    /// Returns zero if the process does not exist.
+    pub fn port_monitor_process(ctx: *mut Context, pid: u32) -> c_int;
+
+    /// Remove a monitor set with `port_monitor_process`
+    pub fn port_demonitor_process(ctx: *mut Context, pid: u32);
+
+    /// Local id of the port, as used in port terms
+    pub fn port_get_id(ctx: *const Context) -> u32;
+
+    /// Parse a generic port message into components
+    pub fn parse_port_message(
+        message: *const Message,
+        pid: *mut ERL_NIF_TERM,
+        reference: *mut ERL_NIF_TERM,
+        command: *mut ERL_NIF_TERM,
+    ) -> c_int;
+
+    /// The whole term carried by a port message
+    pub fn port_message_term(message: *const Message) -> ERL_NIF_TERM;
+}
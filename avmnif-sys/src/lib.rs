@@ -0,0 +1,40 @@
+//! Raw FFI declarations for AtomVM, used by `avmnif-rs`
+//!
+//! The declarations are in three parts:
+//!
+//! - `types`: the VM's types, opaque where Rust never looks inside
+//! - the AtomVM C API (atom table, `enif_*` resources, port replies): with
+//!   the `bindgen` feature and `ATOMVM_INCLUDE_DIR` pointing at AtomVM's
+//!   `src/libAtomVM`, generated from the headers at build time; otherwise
+//!   the hand-written fallback in `atomvm.rs`
+//! - `glue`: functions implemented by each platform's C glue, always
+//!   hand-written
+//!
+//! Building with `bindgen` against the headers of the VM being shipped is
+//! how drifted signatures are caught: code calling a function whose
+//! declaration changed stops compiling.
+
+#![no_std]
+
+mod types;
+pub use types::*;
+
+mod glue;
+pub use glue::*;
+
+#[cfg(not(avmnif_sys_bindgen))]
+mod atomvm;
+#[cfg(not(avmnif_sys_bindgen))]
+pub use atomvm::*;
+
+#[cfg(avmnif_sys_bindgen)]
+#[allow(non_camel_case_types, non_snake_case, non_upper_case_globals, dead_code, clippy::all)]
+mod atomvm {
+    use crate::types::*;
+    include!(concat!(env!("OUT_DIR"), "/atomvm.rs"));
+}
+#[cfg(avmnif_sys_bindgen)]
+pub use atomvm::*;
+
+/// Whether the AtomVM declarations were generated from headers
+pub const GENERATED: bool = cfg!(avmnif_sys_bindgen);
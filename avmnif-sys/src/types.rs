@@ -0,0 +1,99 @@
+//! Types shared by the declarations, generated or not
+//!
+//! These are not generated even with `bindgen`: the VM's structs stay
+//! opaque to Rust, and the generated declarations are made to refer to
+//! these definitions instead of their own.
+
+use core::ffi::{c_int, c_void};
+
+/// `term`, as wide as a pointer like AtomVM's `uintptr_t` typedef
+#[allow(non_camel_case_types)]
+pub type term = usize;
+
+/// `typedef term ERL_NIF_TERM`
+#[allow(non_camel_case_types)]
+pub type ERL_NIF_TERM = term;
+
+/// Index into the atom table (`atom_index_t`)
+#[allow(non_camel_case_types)]
+pub type atom_index_t = u32;
+
+/// Opaque context structure that matches AtomVM's internal representation
+#[repr(C)]
+pub struct Context {
+    _private: [u8; 0],
+}
+
+/// Global AtomVM context
+pub type GlobalContext = c_void;
+
+/// A process heap, or a standalone one not owned by any process
+#[repr(C)]
+pub struct Heap {
+    _private: [u8; 0],
+}
+
+/// The VM's atom table
+pub type AtomTable = c_void;
+
+/// Port message type
+pub type Message = c_void;
+
+pub type ErlNifEnv = c_void; // Opaque struct
+pub type ErlNifResourceType = c_void; // Opaque struct
+pub type ErlNifPid = i32;
+pub type ErlNifEvent = c_int;
+
+/// Resource destructor callback type
+pub type ErlNifResourceDtor = unsafe extern "C" fn(caller_env: *mut ErlNifEnv, obj: *mut c_void);
+
+/// Select stop callback type
+pub type ErlNifResourceStop = unsafe extern "C" fn(
+    caller_env: *mut ErlNifEnv,
+    obj: *mut c_void,
+    event: ErlNifEvent,
+    is_direct_call: c_int
+);
+
+/// Resource monitor callback type
+pub type ErlNifResourceDown = unsafe extern "C" fn(
+    caller_env: *mut ErlNifEnv,
+    obj: *mut c_void,
+    pid: *mut ErlNifPid,
+    mon: *mut ErlNifMonitor
+);
+
+/// Monitor type
+#[repr(C)]
+pub struct ErlNifMonitor {
+    pub resource_type: *mut ErlNifResourceType,
+    pub ref_ticks: u64,
+}
+
+/// Resource type initialization callbacks
+#[repr(C)]
+pub struct ErlNifResourceTypeInit {
+    pub members: c_int,
+    pub dtor: Option<ErlNifResourceDtor>,
+    pub stop: Option<ErlNifResourceStop>,
+    pub down: Option<ErlNifResourceDown>,
+}
+
+/// Resource creation flags
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum ErlNifResourceFlags {
+    ERL_NIF_RT_CREATE = 1,
+    // ERL_NIF_RT_TAKEOVER not supported yet
+}
+
+/// Select mode flags
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum ErlNifSelectFlags {
+    ERL_NIF_SELECT_READ = 1,
+    ERL_NIF_SELECT_WRITE = 2,
+    ERL_NIF_SELECT_STOP = 4,
+}
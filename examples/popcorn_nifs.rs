@@ -0,0 +1,74 @@
+//! NIF collection for popcorn/AtomVM on wasm32
+//!
+//! Build into a static library and link it into the same wasm module as
+//! AtomVM:
+//!
+//! ```text
+//! cargo build --release --example popcorn_nifs --target wasm32-unknown-emscripten
+//! ```
+//!
+//! Then call `avmnif_register_nif_collections()` once from the popcorn
+//! startup code, before any Erlang module that uses these NIFs is loaded.
+//! From Erlang/Elixir the functions are then reached like any other NIF,
+//! e.g. `popcorn_math:add(1, 2)`.
+
+use avmnif_rs::term::{raise_badarg, Context, Env, Term, TermValue};
+use avmnif_rs::{nif_collection, register_nif_collections};
+
+fn small_int(term: Term<'_>) -> Option<i32> {
+    match term.to_value() {
+        Ok(TermValue::SmallInt(n)) => Some(n),
+        _ => None,
+    }
+}
+
+/// Build `value` on the call's heap; raises badarg if it does not fit
+fn reply(env: &mut Env<'_>, value: i32) -> Term<'static> {
+    let value = TermValue::int(value);
+    let words = Term::heap_words(&value);
+    match env.heap(words).and_then(|mut heap| heap.encode(value)) {
+        Ok(term) => Term::from_raw(term.raw()),
+        Err(_) => raise_badarg(env.context()),
+    }
+}
+
+/// popcorn_math:add/2
+pub extern "C" fn add_nif(ctx: *mut Context, argc: i32, argv: *const Term<'static>) -> Term<'static> {
+    let mut env = unsafe { Env::from_raw(ctx) };
+    let args = unsafe { env.args(argc, argv) };
+    match args {
+        [a, b] => match (small_int(*a), small_int(*b)) {
+            (Some(a), Some(b)) => reply(&mut env, a.wrapping_add(b)),
+            _ => raise_badarg(env.context()),
+        },
+        _ => raise_badarg(env.context()),
+    }
+}
+
+/// popcorn_math:negate/1
+pub extern "C" fn negate_nif(ctx: *mut Context, argc: i32, argv: *const Term<'static>) -> Term<'static> {
+    let mut env = unsafe { Env::from_raw(ctx) };
+    let args = unsafe { env.args(argc, argv) };
+    match args {
+        [n] => match small_int(*n) {
+            Some(n) => reply(&mut env, n.wrapping_neg()),
+            None => raise_badarg(env.context()),
+        },
+        _ => raise_badarg(env.context()),
+    }
+}
+
+fn popcorn_math_init(_ctx: &mut avmnif_rs::Context) {}
+
+nif_collection!(
+    popcorn_math,
+    init = popcorn_math_init,
+    info = env!("CARGO_PKG_VERSION"),
+    nifs = [
+        ("add", 2, add_nif),
+        ("negate", 1, negate_nif),
+    ]
+);
+
+// Only expands on wasm32; native builds register through link sections.
+register_nif_collections!(popcorn_math);
@@ -0,0 +1,137 @@
+//! Minimal GPIO port for AtomVM on the Raspberry Pi Pico
+//!
+//! Build into a static library for the Pico and link it into AtomVM's rp2
+//! platform build:
+//!
+//! ```text
+//! cargo build --release --example rp2040_gpio --target thumbv6m-none-eabi --features rp2040
+//! ```
+//!
+//! The driver registers itself through `.init_array`, so the firmware needs
+//! no C glue. From Erlang:
+//!
+//! ```text
+//! Port = erlang:open_port({spawn, "pico_gpio"}, []),
+//! {ok, active} = port:call(Port, start),
+//! ok = port:call(Port, {output, 25}),
+//! ok = port:call(Port, {write, 25, 1}),
+//! {ok, 1} = port:call(Port, {read, 25}).
+//! ```
+
+use avmnif_rs::atom::AtomTable;
+use avmnif_rs::context::{Context, GlobalContext, PlatformData};
+use avmnif_rs::port::{create_port_with_data, handle_standard_message, parse_gen_message, Message, PortData, PortResult};
+use avmnif_rs::port_collection;
+use avmnif_rs::term::{Term, TermValue};
+
+/// GPIOs wired to the RP2040 pins (GPIO0..GPIO29)
+const PIN_COUNT: i32 = 30;
+
+// SIO registers, one bit per GPIO
+const SIO_GPIO_IN: usize = 0xD000_0004;
+const SIO_GPIO_OUT_SET: usize = 0xD000_0014;
+const SIO_GPIO_OUT_CLR: usize = 0xD000_0018;
+const SIO_GPIO_OE_SET: usize = 0xD000_0024;
+const SIO_GPIO_OE_CLR: usize = 0xD000_0028;
+
+// pico-sdk FFI declarations
+extern "C" {
+    /// Select the SIO function for `gpio`, as input, driving low
+    fn gpio_init(gpio: u32);
+}
+
+fn sio_write(register: usize, pin: i32) {
+    unsafe { core::ptr::write_volatile(register as *mut u32, 1 << pin) }
+}
+
+fn sio_read(register: usize) -> u32 {
+    unsafe { core::ptr::read_volatile(register as *const u32) }
+}
+
+/// Bitmask of the pins handed to the SIO by this port
+#[derive(Default)]
+struct PicoGpio {
+    claimed: u32,
+}
+
+impl PicoGpio {
+    fn claim(&mut self, pin: i32) {
+        if self.claimed & (1 << pin) == 0 {
+            unsafe { gpio_init(pin as u32) };
+            self.claimed |= 1 << pin;
+        }
+    }
+
+    fn command(&mut self, command: &TermValue, table: &AtomTable) -> Option<PortResult> {
+        let [name, pin, rest @ ..] = command.as_tuple()? else {
+            return None;
+        };
+        let pin = pin.as_int().filter(|pin| (0..PIN_COUNT).contains(pin))?;
+        let ok = || PortResult::Reply(TermValue::atom("ok", table));
+
+        if name.is_atom_str("output", table) && rest.is_empty() {
+            self.claim(pin);
+            sio_write(SIO_GPIO_OE_SET, pin);
+            Some(ok())
+        } else if name.is_atom_str("input", table) && rest.is_empty() {
+            self.claim(pin);
+            sio_write(SIO_GPIO_OE_CLR, pin);
+            Some(ok())
+        } else if name.is_atom_str("write", table) {
+            let level = match rest {
+                [level] => level.as_int()?,
+                _ => return None,
+            };
+            self.claim(pin);
+            sio_write(if level == 0 { SIO_GPIO_OUT_CLR } else { SIO_GPIO_OUT_SET }, pin);
+            Some(ok())
+        } else if name.is_atom_str("read", table) && rest.is_empty() {
+            let level = (sio_read(SIO_GPIO_IN) >> pin) & 1;
+            Some(PortResult::Reply(TermValue::tuple(vec![
+                TermValue::atom("ok", table),
+                TermValue::int(level as i32),
+            ])))
+        } else {
+            None
+        }
+    }
+}
+
+impl PlatformData for PicoGpio {
+    fn cleanup(&mut self) {
+        // Leave claimed pins as inputs rather than driving whatever they held
+        for pin in (0..PIN_COUNT).filter(|pin| self.claimed & (1 << pin) != 0) {
+            sio_write(SIO_GPIO_OE_CLR, pin);
+        }
+    }
+}
+
+impl PortData for PicoGpio {
+    fn handle_message(&mut self, message: &Message) -> PortResult {
+        let table = AtomTable::from_global();
+        let command = parse_gen_message(message).ok().and_then(|(_, _, command)| command.to_value().ok());
+        command
+            .and_then(|command| self.command(&command, &table))
+            .unwrap_or_else(|| PortResult::ReplyError(TermValue::atom("badarg", &table)))
+    }
+}
+
+fn gpio_setup(_global: &mut GlobalContext) {}
+
+fn gpio_teardown(_global: &mut GlobalContext) {}
+
+fn gpio_create(global: &GlobalContext, _opts: Term) -> *mut Context {
+    create_port_with_data(global, PicoGpio::default())
+}
+
+fn gpio_handler(ctx: &mut Context, message: &Message) -> PortResult {
+    handle_standard_message::<PicoGpio>(ctx, message)
+}
+
+port_collection!(
+    pico_gpio,
+    init = gpio_setup,
+    destroy = gpio_teardown,
+    create_port = gpio_create,
+    handler = gpio_handler
+);
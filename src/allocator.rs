@@ -0,0 +1,225 @@
+//! Rust heap allocations through AtomVM's allocator
+//!
+//! Rust code linked into AtomVM needs a `#[global_allocator]`. With the
+//! `global-allocator` feature this crate provides one that calls the same
+//! `malloc`/`realloc`/`free` as the VM. Rust `Box`es and `Vec`s then come
+//! out of the heap the VM uses, and they show up in its statistics, such as
+//! `erlang:memory/0` on ports that count `malloc` or the ESP-IDF heap
+//! reports. On ESP32 the default `malloc` capabilities apply, so Rust
+//! allocations go wherever the VM's own allocations go, PSRAM included
+//! when the VM is configured for it.
+//!
+//! The allocator also keeps its own counters, read with `stats`, for what
+//! Rust code alone has allocated.
+//!
+//! Alignments above what `malloc` guarantees are handled by allocating
+//! extra bytes and keeping the original pointer just before the block.
+
+use core::alloc::{GlobalAlloc, Layout};
+use crate::sync::atomic::{AtomicUsize, Ordering};
+
+/// The C heap the allocator draws from
+///
+/// # Safety
+/// Blocks returned by `malloc` and `realloc` must be aligned to `ALIGN`,
+/// and `ALIGN` must be a power of two of at least `size_of::<usize>()`.
+pub unsafe trait RawHeap {
+    /// Alignment every block is guaranteed to have
+    const ALIGN: usize;
+
+    /// A block of `size` bytes, null when out of memory
+    ///
+    /// # Safety
+    /// `size` must not be zero.
+    unsafe fn malloc(&self, size: usize) -> *mut u8;
+
+    /// Grow or shrink a block, keeping its contents; null leaves it untouched
+    ///
+    /// # Safety
+    /// `ptr` must be a live block from this heap and `size` must not be zero.
+    unsafe fn realloc(&self, ptr: *mut u8, size: usize) -> *mut u8;
+
+    /// # Safety
+    /// `ptr` must be a live block from this heap; it is dead afterwards.
+    unsafe fn free(&self, ptr: *mut u8);
+}
+
+// Allocator FFI declarations
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    fn malloc(size: usize) -> *mut core::ffi::c_void;
+    fn realloc(ptr: *mut core::ffi::c_void, size: usize) -> *mut core::ffi::c_void;
+    fn free(ptr: *mut core::ffi::c_void);
+}
+
+/// The `malloc` family the VM itself uses
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CHeap;
+
+unsafe impl RawHeap for CHeap {
+    // Every libc AtomVM runs on (newlib, picolibc, glibc, emscripten) gives
+    // at least word alignment, and 8 bytes on 64-bit targets
+    const ALIGN: usize = core::mem::size_of::<usize>();
+
+    unsafe fn malloc(&self, size: usize) -> *mut u8 {
+        malloc(size).cast()
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, size: usize) -> *mut u8 {
+        realloc(ptr.cast(), size).cast()
+    }
+
+    unsafe fn free(&self, ptr: *mut u8) {
+        free(ptr.cast())
+    }
+}
+
+/// What Rust code has allocated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AllocStats {
+    /// Bytes in live allocations, as requested (without `malloc` overhead)
+    pub in_use: usize,
+    /// Highest `in_use` seen
+    pub peak: usize,
+    /// Allocations made, reallocations included
+    pub allocations: usize,
+    /// Allocations that failed for lack of memory
+    pub failures: usize,
+}
+
+/// `GlobalAlloc` over a `RawHeap`, counting what goes through it
+#[derive(Debug)]
+pub struct AtomVMAllocator<H: RawHeap = CHeap> {
+    heap: H,
+    in_use: AtomicUsize,
+    peak: AtomicUsize,
+    allocations: AtomicUsize,
+    failures: AtomicUsize,
+}
+
+impl AtomVMAllocator<CHeap> {
+    pub const fn new() -> Self {
+        Self::with_heap(CHeap)
+    }
+}
+
+impl Default for AtomVMAllocator<CHeap> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: RawHeap> AtomVMAllocator<H> {
+    pub const fn with_heap(heap: H) -> Self {
+        Self {
+            heap,
+            in_use: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            allocations: AtomicUsize::new(0),
+            failures: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn heap(&self) -> &H {
+        &self.heap
+    }
+
+    pub fn stats(&self) -> AllocStats {
+        AllocStats {
+            in_use: self.in_use.load(Ordering::Relaxed),
+            peak: self.peak.load(Ordering::Relaxed),
+            allocations: self.allocations.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+        }
+    }
+
+    fn count(&self, ptr: *mut u8, size: usize) -> *mut u8 {
+        if ptr.is_null() {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.allocations.fetch_add(1, Ordering::Relaxed);
+            let in_use = self.in_use.fetch_add(size, Ordering::Relaxed) + size;
+            self.peak.fetch_max(in_use, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    fn uncount(&self, size: usize) {
+        self.in_use.fetch_sub(size, Ordering::Relaxed);
+    }
+
+    fn over_aligned(layout: &Layout) -> bool {
+        layout.align() > H::ALIGN
+    }
+
+    /// Allocate `align` extra bytes, align inside them and store the
+    /// original pointer in the word before the aligned block
+    unsafe fn alloc_over_aligned(&self, layout: Layout) -> *mut u8 {
+        let Some(size) = layout.size().checked_add(layout.align()) else {
+            return core::ptr::null_mut();
+        };
+        let raw = self.heap.malloc(size);
+        if raw.is_null() {
+            return raw;
+        }
+        // `raw` is ALIGN-aligned, so there are at least ALIGN bytes, one word, before `aligned`
+        let offset = layout.align() - (raw as usize & (layout.align() - 1));
+        let aligned = raw.add(offset);
+        aligned.cast::<*mut u8>().sub(1).write(raw);
+        aligned
+    }
+
+    unsafe fn free_over_aligned(&self, ptr: *mut u8) {
+        let raw = ptr.cast::<*mut u8>().sub(1).read();
+        self.heap.free(raw);
+    }
+}
+
+unsafe impl<H: RawHeap + Sync> GlobalAlloc for AtomVMAllocator<H> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = if Self::over_aligned(&layout) {
+            self.alloc_over_aligned(layout)
+        } else {
+            self.heap.malloc(layout.size())
+        };
+        self.count(ptr, layout.size())
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if Self::over_aligned(&layout) {
+            self.free_over_aligned(ptr);
+        } else {
+            self.heap.free(ptr);
+        }
+        self.uncount(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if Self::over_aligned(&layout) {
+            // `realloc` would lose the alignment; move the block by hand
+            let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+            let new_ptr = self.alloc(new_layout);
+            if !new_ptr.is_null() {
+                core::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+                self.dealloc(ptr, layout);
+            }
+            return new_ptr;
+        }
+        let new_ptr = self.heap.realloc(ptr, new_size);
+        if !new_ptr.is_null() {
+            self.uncount(layout.size());
+        }
+        self.count(new_ptr, new_size)
+    }
+}
+
+/// The crate's `#[global_allocator]`
+#[cfg(all(feature = "global-allocator", not(test)))]
+#[global_allocator]
+static ALLOCATOR: AtomVMAllocator = AtomVMAllocator::new();
+
+/// What Rust code has allocated through the global allocator
+#[cfg(all(feature = "global-allocator", not(test)))]
+pub fn stats() -> AllocStats {
+    ALLOCATOR.stats()
+}
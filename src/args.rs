@@ -0,0 +1,276 @@
+//! Decoding NIF arguments by position
+//!
+//! `Args` wraps a NIF's argument slice and decodes arguments in order,
+//! several per call. A wrong argument is reported with its position and
+//! what was expected, `argument 2: expected integer`, and converts into
+//! `NifError::BadArg` so `?` works in a NIF returning `NifResult`.
+//!
+//! Any type implementing `Decode` can be taken: integers, floats,
+//! `TermValue`, raw `Term`s, proper lists as `Vec`, and binaries borrowed
+//! as `&[u8]` or `&str` for the rest of the call.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use avmnif_rs::args::Args;
+//! use avmnif_rs::term::{NifResult, Term, TermValue};
+//!
+//! // uart:write(Port, Data, Options...)
+//! fn write(argv: &[Term]) -> NifResult<TermValue> {
+//!     let mut args = Args::new(argv);
+//!     let (port, data) = args.decode2::<u32, &[u8]>()?;
+//!     let options = args.rest_as_list::<TermValue>()?;
+//!     Ok(TermValue::int((port as usize + data.len() + options.len()) as i32))
+//! }
+//! # use avmnif_rs::testing::mocks::MockHeap;
+//! # let mut heap = MockHeap::new(32);
+//! # let mut arg = |value| heap.encode(value).unwrap();
+//! # let argv = [arg(TermValue::int(1)), arg(TermValue::binary(b"AT".to_vec())), arg(TermValue::int(0))];
+//! # assert_eq!(write(&argv), Ok(TermValue::int(4)));
+//! # }
+//! ```
+
+extern crate alloc;
+
+use crate::term::{Env, NifError, NifResult, Term, TermValue};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A NIF argument decoded by `Args`
+///
+/// Borrowed results (`&str`, `&[u8]`) live as long as the arguments.
+pub trait Decode<'a>: Sized {
+    /// What a wrong argument should have been, as in "expected integer"
+    const EXPECTED: &'static str;
+
+    fn decode(term: Term<'a>) -> NifResult<Self>;
+}
+
+impl<'a> Decode<'a> for Term<'a> {
+    const EXPECTED: &'static str = "term";
+
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        Ok(term)
+    }
+}
+
+impl<'a> Decode<'a> for TermValue {
+    const EXPECTED: &'static str = "term";
+
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        term.to_value()
+    }
+}
+
+impl<'a> Decode<'a> for i64 {
+    const EXPECTED: &'static str = "integer";
+
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        term.to_i64().map_err(|_| NifError::BadArg)
+    }
+}
+
+impl<'a> Decode<'a> for i32 {
+    const EXPECTED: &'static str = "32-bit integer";
+
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        i32::try_from(i64::decode(term)?).map_err(|_| NifError::BadArg)
+    }
+}
+
+impl<'a> Decode<'a> for u32 {
+    const EXPECTED: &'static str = "non-negative 32-bit integer";
+
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        u32::try_from(i64::decode(term)?).map_err(|_| NifError::BadArg)
+    }
+}
+
+impl<'a> Decode<'a> for usize {
+    const EXPECTED: &'static str = "non-negative integer";
+
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        usize::try_from(i64::decode(term)?).map_err(|_| NifError::BadArg)
+    }
+}
+
+impl<'a> Decode<'a> for f64 {
+    const EXPECTED: &'static str = "float";
+
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        match term.to_value()? {
+            TermValue::Float(value) => Ok(value),
+            _ => Err(NifError::BadArg),
+        }
+    }
+}
+
+impl<'a> Decode<'a> for &'a [u8] {
+    const EXPECTED: &'static str = "binary";
+
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        // The argument terms stay in place until the NIF returns
+        unsafe { term.binary_bytes::<'a>() }
+    }
+}
+
+impl<'a> Decode<'a> for &'a str {
+    const EXPECTED: &'static str = "UTF-8 binary";
+
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        core::str::from_utf8(<&[u8]>::decode(term)?).map_err(|_| NifError::BadArg)
+    }
+}
+
+impl<'a, T: Decode<'a>> Decode<'a> for Vec<T> {
+    const EXPECTED: &'static str = "proper list";
+
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let mut elements = Vec::new();
+        let mut rest = term;
+        while let Some((head, tail)) = rest.list_cell()? {
+            elements.push(T::decode(head)?);
+            rest = tail;
+        }
+        Ok(elements)
+    }
+}
+
+/// A NIF argument that could not be decoded
+///
+/// Positions count from 1, as Erlang does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgError {
+    /// The NIF was called with fewer arguments than it decodes
+    Missing { position: usize },
+    /// The argument has the wrong type or is out of range
+    Invalid { position: usize, expected: &'static str },
+    /// The NIF was called with the wrong number of arguments
+    Arity { expected: usize, found: usize },
+}
+
+impl ArgError {
+    /// The position of the argument at fault, if there is one
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            ArgError::Missing { position } | ArgError::Invalid { position, .. } => Some(*position),
+            ArgError::Arity { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgError::Missing { position } => write!(f, "argument {}: missing", position),
+            ArgError::Invalid { position, expected } => write!(f, "argument {}: expected {}", position, expected),
+            ArgError::Arity { expected, found } => write!(f, "expected {} arguments, got {}", expected, found),
+        }
+    }
+}
+
+impl From<ArgError> for NifError {
+    fn from(_error: ArgError) -> Self {
+        NifError::BadArg
+    }
+}
+
+/// A NIF's arguments, decoded front to back
+#[derive(Debug, Clone, Copy)]
+pub struct Args<'a> {
+    terms: &'a [Term<'a>],
+    next: usize,
+}
+
+impl<'a> Args<'a> {
+    pub fn new(terms: &'a [Term<'a>]) -> Self {
+        Self { terms, next: 0 }
+    }
+
+    /// The arguments of the current NIF call
+    ///
+    /// # Safety
+    /// `argv` must point to `argc` valid terms, as `Env::args` requires.
+    pub unsafe fn from_raw(env: &Env<'a>, argc: i32, argv: *const Term<'_>) -> Self {
+        Self::new(env.args(argc, argv))
+    }
+
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &'a [Term<'a>] {
+        self.terms
+    }
+
+    /// The argument at `index` (from 0), undecoded
+    pub fn get(&self, index: usize) -> Option<Term<'a>> {
+        self.terms.get(index).copied()
+    }
+
+    /// How many arguments were decoded so far
+    pub fn position(&self) -> usize {
+        self.next
+    }
+
+    /// Arguments not decoded yet
+    pub fn remaining(&self) -> usize {
+        self.terms.len() - self.next
+    }
+
+    /// Fail unless there are exactly `count` arguments
+    pub fn expect_arity(&self, count: usize) -> Result<(), ArgError> {
+        if self.terms.len() == count {
+            Ok(())
+        } else {
+            Err(ArgError::Arity { expected: count, found: self.terms.len() })
+        }
+    }
+
+    /// Decode the argument at `index` (from 0), leaving the position alone
+    pub fn arg<T: Decode<'a>>(&self, index: usize) -> Result<T, ArgError> {
+        let position = index + 1;
+        let term = self.get(index).ok_or(ArgError::Missing { position })?;
+        T::decode(term).map_err(|_| ArgError::Invalid { position, expected: T::EXPECTED })
+    }
+
+    /// Decode the next argument
+    pub fn decode<T: Decode<'a>>(&mut self) -> Result<T, ArgError> {
+        let value = self.arg(self.next)?;
+        self.next += 1;
+        Ok(value)
+    }
+
+    pub fn decode2<A: Decode<'a>, B: Decode<'a>>(&mut self) -> Result<(A, B), ArgError> {
+        Ok((self.decode()?, self.decode()?))
+    }
+
+    pub fn decode3<A: Decode<'a>, B: Decode<'a>, C: Decode<'a>>(&mut self) -> Result<(A, B, C), ArgError> {
+        Ok((self.decode()?, self.decode()?, self.decode()?))
+    }
+
+    pub fn decode4<A: Decode<'a>, B: Decode<'a>, C: Decode<'a>, D: Decode<'a>>(
+        &mut self,
+    ) -> Result<(A, B, C, D), ArgError> {
+        Ok((self.decode()?, self.decode()?, self.decode()?, self.decode()?))
+    }
+
+    /// Decode every argument not decoded yet
+    pub fn rest_as_list<T: Decode<'a>>(&mut self) -> Result<Vec<T>, ArgError> {
+        let mut rest = Vec::with_capacity(self.remaining());
+        while self.remaining() > 0 {
+            rest.push(self.decode()?);
+        }
+        Ok(rest)
+    }
+
+    /// Fail if any argument was left undecoded
+    pub fn finish(&self) -> Result<(), ArgError> {
+        self.expect_arity(self.next)
+    }
+}
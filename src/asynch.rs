@@ -0,0 +1,243 @@
+//! `async` port handlers and NIF background work
+//!
+//! Drivers built around an async executor can keep that structure. Two
+//! pieces connect AtomVM to futures:
+//!
+//! - `Mailbox` carries messages from a port's handler into async code. The
+//!   handler calls `deliver`, which wakes the task awaiting `recv`.
+//! - `AsyncJobs` runs futures started by NIFs. `spawn_and_reply_async`
+//!   queues a future and returns a reference; when the future completes,
+//!   `{Ref, Result}` is sent to the calling process, as with
+//!   `task::spawn_and_reply`.
+//!
+//! Neither depends on a particular executor. With embassy, spawn
+//! `run_jobs` once at startup to drive the job queue.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::asynch::{self, AsyncJobs, Mailbox};
+//!
+//! static INBOX: Mailbox = Mailbox::new();
+//! static JOBS: AsyncJobs = AsyncJobs::new();
+//!
+//! // Port message handler
+//! fn handle_message(_port: &mut Sensor, message: &TermValue) -> PortResult {
+//!     INBOX.deliver(message.clone());
+//!     PortResult::Continue
+//! }
+//!
+//! #[embassy_executor::task]
+//! async fn sensor_loop() {
+//!     loop {
+//!         let command = INBOX.recv().await;
+//!         // ...
+//!     }
+//! }
+//!
+//! // sensor:read_async() -> Ref
+//! fn read_async(env: &mut Env) -> NifResult<TermValue> {
+//!     asynch::spawn_and_reply_async(&JOBS, env, async { TermValue::int(read_sensor().await) })
+//! }
+//!
+//! // main
+//! spawner.spawn(asynch::run_jobs(&JOBS)).unwrap();
+//! spawner.spawn(sensor_loop()).unwrap();
+//! ```
+
+extern crate alloc;
+
+use crate::sync::SpinLock;
+use crate::task::{AtomVMReplySender, ReplySender};
+use crate::term::{Env, NifResult, RefId, TermValue};
+use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+/// The waker of the one task waiting on something
+struct WakerSlot(SpinLock<Option<Waker>>);
+
+impl WakerSlot {
+    const fn new() -> Self {
+        Self(SpinLock::new(None))
+    }
+
+    fn register(&self, waker: &Waker) {
+        self.0.with(|slot| match slot {
+            Some(current) if current.will_wake(waker) => {}
+            _ => *slot = Some(waker.clone()),
+        });
+    }
+
+    fn wake(&self) {
+        if let Some(waker) = self.0.with(Option::take) {
+            waker.wake();
+        }
+    }
+}
+
+/// Messages handed from a port's handler to async code
+///
+/// Meant for one receiving task; a second task awaiting `recv` takes over
+/// the wakeups from the first.
+pub struct Mailbox {
+    messages: SpinLock<VecDeque<TermValue>>,
+    waker: WakerSlot,
+}
+
+// Messages may hold resource pointers (`ResourceRef`), which are only
+// handed from the port to the receiving task, never dereferenced here
+unsafe impl Sync for Mailbox {}
+
+impl Default for Mailbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mailbox {
+    pub const fn new() -> Self {
+        Self {
+            messages: SpinLock::new(VecDeque::new()),
+            waker: WakerSlot::new(),
+        }
+    }
+
+    /// Queue a message and wake the receiving task
+    pub fn deliver(&self, message: TermValue) {
+        self.messages.with(|messages| messages.push_back(message));
+        self.waker.wake();
+    }
+
+    /// The next message, without waiting
+    pub fn try_recv(&self) -> Option<TermValue> {
+        self.messages.with(VecDeque::pop_front)
+    }
+
+    /// Wait for the next message
+    pub fn recv(&self) -> Recv<'_> {
+        Recv { mailbox: self }
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.with(|messages| messages.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Future returned by `Mailbox::recv`
+pub struct Recv<'a> {
+    mailbox: &'a Mailbox,
+}
+
+impl Future for Recv<'_> {
+    type Output = TermValue;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<TermValue> {
+        if let Some(message) = self.mailbox.try_recv() {
+            return Poll::Ready(message);
+        }
+        // Register before looking again, so a delivery in between still wakes us
+        self.mailbox.waker.register(cx.waker());
+        match self.mailbox.try_recv() {
+            Some(message) => Poll::Ready(message),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// A future queued on `AsyncJobs`
+pub type AsyncJob = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// Futures started by NIFs, driven by one `run` future
+///
+/// `run` polls every unfinished job whenever any of them is woken, which
+/// suits the handful of jobs a driver has in flight.
+pub struct AsyncJobs {
+    queued: SpinLock<Vec<AsyncJob>>,
+    waker: WakerSlot,
+}
+
+impl Default for AsyncJobs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AsyncJobs {
+    pub const fn new() -> Self {
+        Self {
+            queued: SpinLock::new(Vec::new()),
+            waker: WakerSlot::new(),
+        }
+    }
+
+    /// Queue a future; it starts on the next poll of `run`
+    pub fn spawn<F: Future<Output = ()> + Send + 'static>(&self, job: F) {
+        self.queued.with(|queued| queued.push(Box::pin(job)));
+        self.waker.wake();
+    }
+
+    /// Drive the queued jobs; never completes
+    pub fn run(&self) -> Run<'_> {
+        Run {
+            jobs: self,
+            running: Vec::new(),
+        }
+    }
+}
+
+/// Future returned by `AsyncJobs::run`
+pub struct Run<'a> {
+    jobs: &'a AsyncJobs,
+    running: Vec<AsyncJob>,
+}
+
+impl Future for Run<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = &mut *self;
+        this.jobs.waker.register(cx.waker());
+        this.jobs.queued.with(|queued| this.running.append(queued));
+        this.running.retain_mut(|job| job.as_mut().poll(cx).is_pending());
+        Poll::Pending
+    }
+}
+
+/// Queue `work` on `jobs` and send `{Ref, Result}` to `caller` when it completes
+pub fn spawn_async_with<S, F>(jobs: &AsyncJobs, sender: S, caller: u32, reference: RefId, work: F)
+where
+    S: ReplySender,
+    F: Future<Output = TermValue> + Send + 'static,
+{
+    jobs.spawn(async move {
+        let result = work.await;
+        // The caller may be gone; there is no one left to tell
+        let message = TermValue::tuple(alloc::vec![TermValue::Reference(reference), result]);
+        let _ = sender.send(caller, &message);
+    });
+}
+
+/// Run `work` on `jobs` and send `{Ref, Result}` to the caller
+///
+/// Returns `Ref`, for the NIF to hand back.
+pub fn spawn_and_reply_async<F>(jobs: &AsyncJobs, env: &mut Env<'_>, work: F) -> NifResult<TermValue>
+where
+    F: Future<Output = TermValue> + Send + 'static,
+{
+    let caller = crate::names::port_pid(unsafe { &*(env.as_ptr() as *const crate::context::Context) });
+    let reference = crate::task::fresh_reference();
+    spawn_async_with(jobs, AtomVMReplySender, caller, reference, work);
+    Ok(TermValue::Reference(reference))
+}
+
+/// Embassy task driving `jobs`; spawn it once at startup
+#[embassy_executor::task]
+pub async fn run_jobs(jobs: &'static AsyncJobs) {
+    jobs.run().await
+}
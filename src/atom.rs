@@ -11,24 +11,32 @@
 //!
 //! # Examples
 //!
-//! ```rust,ignore
+//! ```
+//! # #[cfg(feature = "sim")] {
 //! use avmnif_rs::atom::{AtomTableOps, AtomTable};
+//! use avmnif_rs::testing::mocks::MockAtomTable;
+//! # let _vm = avmnif_rs::testing::mocks::MockGlobalContext::new();
 //!
 //! // In production - use real AtomVM table
-//! let atom_table = AtomTable::new(context);
+//! let atom_table = AtomTable::from_global();
 //! let hello_atom = atom_table.ensure_atom_str("hello")?;
+//! # assert!(atom_table.atom_equals_str(hello_atom, "hello"));
 //!
 //! // In testing - use mock table
 //! let atom_table = MockAtomTable::new();
 //! let hello_atom = atom_table.ensure_atom_str("hello")?;
+//! # assert!(atom_table.atom_equals_str(hello_atom, "hello"));
 //!
 //! // Both work the same way!
+//! # }
+//! # Ok::<(), avmnif_rs::atom::AtomError>(())
 //! ```
 
 extern crate alloc;
 
 use core::fmt;
 use core::str;
+use crate::sync::atomic::{AtomicU32, Ordering};
 use alloc::vec::Vec;
 
 // ── Core Types and Errors ───────────────────────────────────────────────────
@@ -232,47 +240,11 @@ enum AtomTableResult {
     InvalidLength,
 }
 
-// FFI declarations - Note: These expect raw u32 values, not AtomIndex structs
-extern "C" {
-    fn atom_table_get_atom_string(
-        table: *mut c_void,
-        index: u32,  // Raw u32, not AtomIndex
-        out_size: *mut usize,
-    ) -> *const u8;
-
-    fn atom_table_ensure_atom(
-        table: *mut c_void,
-        atom_data: *const u8,
-        atom_len: usize,
-        opts: u32,
-        result: *mut u32,  // Raw u32, not AtomIndex
-    ) -> u32;
-
-    fn atom_table_ensure_atoms(
-        table: *mut c_void,
-        atoms: *const c_void,
-        count: usize,
-        translate_table: *mut u32,  // Raw u32, not AtomIndex
-        opt: u32,
-    ) -> u32;
-
-    fn atom_table_count(table: *mut c_void) -> usize;
-
-    fn atom_table_is_equal_to_atom_string(
-        table: *mut c_void,
-        atom_index: u32,  // Raw u32, not AtomIndex
-        string_data: *const u8,
-        string_len: usize,
-    ) -> bool;
-
-    fn atom_table_cmp_using_atom_index(
-        table: *mut c_void,
-        atom1: u32,  // Raw u32, not AtomIndex
-        atom2: u32,  // Raw u32, not AtomIndex
-    ) -> i32;
-
-    fn atomvm_get_global_atom_table() -> *mut c_void;
-}
+// AtomVM atom table FFI declarations
+use avmnif_sys::{
+    atom_table_cmp_using_atom_index, atom_table_count, atom_table_ensure_atom, atom_table_ensure_atoms,
+    atom_table_get_atom_string, atom_table_is_equal_to_atom_string, atomvm_get_global_atom_table,
+};
 
 // Helper to convert C result to Rust enum
 fn result_from_c(result: u32) -> AtomTableResult {
@@ -325,7 +297,7 @@ impl AtomTableOps for AtomTable {
             return Err(AtomError::InvalidIndex);
         }
 
-        let data = unsafe { slice::from_raw_parts(ptr, size) };
+        let data = unsafe { slice::from_raw_parts(ptr.cast::<u8>(), size) };
         Ok(AtomRef::new(data, index))
     }
 
@@ -334,7 +306,7 @@ impl AtomTableOps for AtomTable {
         let status = unsafe {
             atom_table_ensure_atom(
                 self.0,
-                atom_data.as_ptr(),
+                atom_data.as_ptr().cast(),
                 atom_data.len(),
                 AtomCopyOpt::Copy as u32,
                 &mut result,
@@ -354,7 +326,7 @@ impl AtomTableOps for AtomTable {
         let status = unsafe {
             atom_table_ensure_atom(
                 self.0,
-                atom_data.as_ptr(),
+                atom_data.as_ptr().cast(),
                 atom_data.len(),
                 AtomCopyOpt::AlreadyExisting as u32,
                 &mut result,
@@ -374,7 +346,7 @@ impl AtomTableOps for AtomTable {
             atom_table_is_equal_to_atom_string(
                 self.0,
                 atom_index.0,  // Extract raw u32
-                data.as_ptr(),
+                data.as_ptr().cast(),
                 data.len(),
             )
         }
@@ -480,4 +452,260 @@ pub mod atoms {
     pub fn badarg<T: AtomTableOps>(table: &T) -> Result<AtomIndex, AtomError> {
         table.ensure_atom_str("badarg")
     }
-}
\ No newline at end of file
+}
+// ── Static Atoms ────────────────────────────────────────────────────────────
+
+/// Cached atom index filled in once at load time
+///
+/// Backing storage for the accessors generated by `atoms!`; reading it is a
+/// single relaxed load.
+pub struct AtomCell(AtomicU32);
+
+impl AtomCell {
+    const UNSET: u32 = u32::MAX;
+
+    /// Create an empty cell
+    pub const fn new() -> Self {
+        AtomCell(AtomicU32::new(Self::UNSET))
+    }
+
+    /// Cached index; only meaningful once the cell has been set
+    #[inline]
+    pub fn get(&self) -> AtomIndex {
+        let index = self.0.load(Ordering::Relaxed);
+        debug_assert!(index != Self::UNSET, "atom used before intern_atoms()");
+        AtomIndex(index)
+    }
+
+    /// Store the interned index
+    pub fn set(&self, index: AtomIndex) {
+        self.0.store(index.0, Ordering::Relaxed);
+    }
+
+    /// Check whether the cell has been set
+    pub fn is_set(&self) -> bool {
+        self.0.load(Ordering::Relaxed) != Self::UNSET
+    }
+}
+
+impl Default for AtomCell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Declare atoms that are interned once and then read without table lookups
+///
+/// Generates one accessor per atom returning its cached `AtomIndex`, plus
+/// `intern_atoms(table)` which fills the cache. Pass `intern_atoms` to
+/// `nif_collection!` as `atoms = ...` so it runs at collection init. Use
+/// `name = "text"` when the atom is not a valid Rust identifier.
+///
+/// Each invocation defines private helper items, so give it a module of
+/// its own:
+///
+/// ```
+/// # #[cfg(feature = "sim")] {
+/// use avmnif_rs::nif_collection;
+/// use avmnif_rs::term::{Context, NifReturn, Term, TermValue};
+/// # fn sensors_init(_ctx: &mut avmnif_rs::Context) {}
+///
+/// mod atoms {
+///     avmnif_rs::atoms! { ok, error, sensor_reading, kind = "type" }
+/// }
+///
+/// extern "C" fn read_nif(ctx: *mut Context, _argc: i32, _argv: *const Term) -> Term<'static> {
+///     let reading = TermValue::int(21);
+///     let reply = TermValue::tuple(vec![TermValue::Atom(atoms::ok()), reading]);
+///     Term::from_raw(NifReturn::Value(reply).into_term(unsafe { &mut *ctx }).raw())
+/// }
+///
+/// nif_collection!(
+///     sensors,
+///     init = sensors_init,
+///     atoms = atoms::intern_atoms,
+///     nifs = [("read", 0, read_nif)]
+/// );
+/// # let global = avmnif_rs::testing::mocks::MockGlobalContext::new();
+/// # let mut process = global.new_context();
+/// # let ctx = process.as_context() as *mut avmnif_rs::Context;
+/// # sensors_nif_init(ctx);
+/// # let ok = TermValue::atom("ok", global.atoms());
+/// # assert_eq!(read_nif(ctx.cast(), 0, core::ptr::null()).to_value(), Ok(TermValue::tuple(vec![ok, TermValue::int(21)])));
+/// # assert_eq!(TermValue::Atom(atoms::kind()), TermValue::atom("type", global.atoms()));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! atoms {
+    (@text $name:ident) => { stringify!($name) };
+    (@text $name:ident = $text:literal) => { $text };
+
+    ( $( $name:ident $( = $text:literal )? ),* $(,)? ) => {
+        #[allow(non_camel_case_types, dead_code)]
+        enum __AtomSlot {
+            $( $name, )*
+            __Count,
+        }
+
+        #[allow(clippy::declare_interior_mutable_const)]
+        const __UNSET: $crate::atom::AtomCell = $crate::atom::AtomCell::new();
+        static __ATOM_CELLS: [$crate::atom::AtomCell; __AtomSlot::__Count as usize] =
+            [__UNSET; __AtomSlot::__Count as usize];
+
+        /// Intern every declared atom into `table`
+        pub fn intern_atoms<T: $crate::atom::AtomTableOps>(
+            table: &T,
+        ) -> Result<(), $crate::atom::AtomError> {
+            $(
+                __ATOM_CELLS[__AtomSlot::$name as usize]
+                    .set(table.ensure_atom_str($crate::atoms!(@text $name $( = $text )?))?);
+            )*
+            Ok(())
+        }
+
+        $(
+            #[inline]
+            #[allow(dead_code)]
+            pub fn $name() -> $crate::atom::AtomIndex {
+                __ATOM_CELLS[__AtomSlot::$name as usize].get()
+            }
+        )*
+    };
+}
+
+// ── Cross-Table Atom Translation ───────────────────────────────────────────
+
+/// Translation of atom indices between two atom tables
+///
+/// Atom indices are only meaningful within the table that produced them.
+/// When a term decoded against one table (ETF from another node, persisted
+/// state, etc.) is used with another table, every atom must be rewritten
+/// through its name. The translator caches each mapping it resolves.
+pub mod translate {
+    use super::*;
+    use crate::term::{FunctionRef, TermValue};
+    use alloc::{boxed::Box, collections::BTreeMap};
+    use core::cell::RefCell;
+
+    /// How missing atoms are handled in the target table
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum TranslateMode {
+        /// Create atoms in the target table as needed
+        Ensure,
+        /// Only map to atoms that already exist in the target table
+        ExistingOnly,
+    }
+
+    /// Rewrites atom indices from a source table into a target table
+    pub struct AtomTranslator<'a, S: AtomTableOps, D: AtomTableOps> {
+        source: &'a S,
+        target: &'a D,
+        mode: TranslateMode,
+        cache: RefCell<BTreeMap<AtomIndex, AtomIndex>>,
+    }
+
+    impl<'a, S: AtomTableOps, D: AtomTableOps> AtomTranslator<'a, S, D> {
+        /// Create a translator that creates missing atoms in the target table
+        pub fn new(source: &'a S, target: &'a D) -> Self {
+            Self::with_mode(source, target, TranslateMode::Ensure)
+        }
+
+        /// Create a translator that fails on atoms unknown to the target table
+        pub fn existing_only(source: &'a S, target: &'a D) -> Self {
+            Self::with_mode(source, target, TranslateMode::ExistingOnly)
+        }
+
+        /// Create a translator with an explicit mode
+        pub fn with_mode(source: &'a S, target: &'a D, mode: TranslateMode) -> Self {
+            Self {
+                source,
+                target,
+                mode,
+                cache: RefCell::new(BTreeMap::new()),
+            }
+        }
+
+        /// Translate a single atom index
+        pub fn translate_atom(&self, index: AtomIndex) -> Result<AtomIndex, AtomError> {
+            if let Some(&cached) = self.cache.borrow().get(&index) {
+                return Ok(cached);
+            }
+
+            let name = self.source.get_atom_string(index)?;
+            let translated = match self.mode {
+                TranslateMode::Ensure => self.target.ensure_atom(name.as_bytes())?,
+                TranslateMode::ExistingOnly => self.target.find_atom(name.as_bytes())?,
+            };
+
+            self.cache.borrow_mut().insert(index, translated);
+            Ok(translated)
+        }
+
+        /// Translate every atom inside a term, returning the rewritten term
+        pub fn translate_term(&self, term: &TermValue) -> Result<TermValue, AtomError> {
+            match term {
+                TermValue::Atom(index) => Ok(TermValue::Atom(self.translate_atom(*index)?)),
+                TermValue::Tuple(elements) => {
+                    let translated: Result<Vec<TermValue>, AtomError> = elements
+                        .iter()
+                        .map(|elem| self.translate_term(elem))
+                        .collect();
+                    Ok(TermValue::Tuple(translated?))
+                }
+                TermValue::List(_, _) => self.translate_list(term),
+                TermValue::Map(pairs) => {
+                    let translated: Result<Vec<(TermValue, TermValue)>, AtomError> = pairs
+                        .iter()
+                        .map(|(k, v)| Ok((self.translate_term(k)?, self.translate_term(v)?)))
+                        .collect();
+                    Ok(TermValue::Map(translated?))
+                }
+                TermValue::Function(fun) => Ok(TermValue::Function(FunctionRef {
+                    module: self.translate_atom(fun.module)?,
+                    function: self.translate_atom(fun.function)?,
+                    arity: fun.arity,
+                })),
+                other => Ok(other.clone()),
+            }
+        }
+
+        /// Translate a list spine iteratively so long lists don't recurse per cell
+        fn translate_list(&self, list: &TermValue) -> Result<TermValue, AtomError> {
+            let mut heads = Vec::new();
+            let mut current = list;
+            while let TermValue::List(head, tail) = current {
+                heads.push(self.translate_term(head)?);
+                current = tail;
+            }
+
+            let tail = self.translate_term(current)?;
+            Ok(heads.into_iter().rev().fold(tail, |acc, head| {
+                TermValue::List(Box::new(head), Box::new(acc))
+            }))
+        }
+
+        /// Number of cached atom mappings
+        pub fn cache_len(&self) -> usize {
+            self.cache.borrow().len()
+        }
+
+        /// Forget all cached mappings (e.g. after the target table was reset)
+        pub fn clear_cache(&self) {
+            self.cache.borrow_mut().clear();
+        }
+
+        /// Get the translation mode
+        pub fn mode(&self) -> TranslateMode {
+            self.mode
+        }
+    }
+
+    /// Translate a term between tables without keeping a cache around
+    pub fn translate_term<S: AtomTableOps, D: AtomTableOps>(
+        term: &TermValue,
+        source: &S,
+        target: &D,
+    ) -> Result<TermValue, AtomError> {
+        AtomTranslator::new(source, target).translate_term(term)
+    }
+}
@@ -0,0 +1,356 @@
+//! Binary views and buffers
+//!
+//! `Term::to_value` copies a binary's bytes into a `Vec`. `BinarySlice`
+//! borrows them instead, for as long as the NIF context is borrowed. Any
+//! call that can run a garbage collection (`HeapGuard::ensure_free`) needs
+//! the context mutably, so the borrow checker rules out reading through
+//! a view after its binary may have moved.
+//!
+//! `OwnedBinary` goes the other way: an off-heap buffer that Rust fills
+//! (from DMA, a camera, an ADC) and then hands to the VM as a refc binary
+//! term, without copying the bytes again.
+//!
+//! `BinaryBuilder` (from `Env::alloc_binary`) picks the cheaper of the two:
+//! short binaries are laid out directly on the process heap, long ones in
+//! an `OwnedBinary`. Either way the bytes are written once, in place.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use avmnif_rs::binary::{BinarySlice, OwnedBinary};
+//! use avmnif_rs::term::{Context, Env, HeapGuard, NifError, NifResult, Term};
+//! # const FRAME_LEN: usize = 320;
+//! # fn capture_into(frame: &mut [u8]) { frame.fill(0x55) }
+//! # fn spi_read_into(buffer: &mut [u8]) { buffer.fill(0xA5) }
+//!
+//! // The payload of a frame, as a sub-binary of the argument, without copying
+//! fn payload<'a>(ctx: &'a mut Context, frame: Term<'a>) -> NifResult<Term<'a>> {
+//!     let range = {
+//!         let frame = BinarySlice::from_term(frame, ctx)?;
+//!         let header = frame.slice(0..4).ok_or(NifError::BadArg)?;
+//!         let payload_len = u16::from_be_bytes([header[2], header[3]]) as usize;
+//!         frame.slice(4..4 + payload_len).ok_or(NifError::BadArg)?.range()
+//!     };
+//!     let words = Term::sub_binary_heap_words(frame, range.len())?;
+//!     let mut heap = HeapGuard::ensure_free(ctx, words)?;
+//!     heap.sub_binary(frame, range)
+//! }
+//!
+//! // Fill a buffer in place, then return it
+//! fn capture(ctx: &mut Context) -> NifResult<Term<'_>> {
+//!     let mut frame = OwnedBinary::new(FRAME_LEN).ok_or(NifError::OutOfMemory)?;
+//!     capture_into(&mut frame);
+//!     let mut heap = HeapGuard::ensure_free(ctx, Term::REFC_BINARY_WORDS)?;
+//!     frame.release(&mut heap)
+//! }
+//!
+//! // Let the builder decide where the bytes go
+//! fn read<'e>(env: &'e mut Env, len: usize) -> NifResult<Term<'e>> {
+//!     let mut reply = env.alloc_binary(len)?;
+//!     spi_read_into(&mut reply);
+//!     reply.seal()
+//! }
+//! # use avmnif_rs::term::TermValue;
+//! # use avmnif_rs::testing::mocks::{MockGlobalContext, MockHeap};
+//! # let global = MockGlobalContext::new();
+//! # let mut process = global.new_context();
+//! # let ctx = process.as_context() as *mut _ as *mut Context;
+//! # let mut args = MockHeap::new(16);
+//! # let frame = args.encode(TermValue::binary(vec![1, 0, 0, 3, 7, 8, 9, 0xFF])).unwrap();
+//! # let ctx = unsafe { &mut *ctx };
+//! # assert_eq!(payload(ctx, frame).unwrap().to_value(), Ok(TermValue::binary(vec![7, 8, 9])));
+//! # assert_eq!(capture(ctx).unwrap().to_value(), Ok(TermValue::binary(vec![0x55; FRAME_LEN])));
+//! # let mut env = unsafe { Env::from_raw(ctx) };
+//! # assert_eq!(read(&mut env, 4).unwrap().to_value(), Ok(TermValue::binary(vec![0xA5; 4])));
+//! # }
+//! ```
+
+use crate::context::{global_context_ptr, GlobalContext};
+use crate::term::{Context, Heap, HeapGuard, NifError, NifResult, RefcBinary, Term};
+use core::ffi::c_void;
+use core::ops::{Deref, DerefMut, Range};
+use core::ptr::NonNull;
+
+// AtomVM refc binary FFI declarations
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    /// Allocate an off-heap binary with a reference count of one
+    fn refc_binary_create(size: usize, resource_type: *mut c_void) -> *mut RefcBinary;
+
+    /// Drop one reference, freeing the binary when none are left
+    fn refc_binary_decrement_refcount(refc: *mut RefcBinary, global: *mut GlobalContext) -> bool;
+
+    /// Box a refc binary on the heap and add it to the heap's off-heap list
+    ///
+    /// The new term takes over the caller's reference. Needs
+    /// `Term::REFC_BINARY_WORDS` free words.
+    fn term_from_refc_binary(refc: *mut RefcBinary, heap: *mut Heap) -> usize;
+}
+
+/// Borrowed, zero-copy view of part of a binary term
+#[derive(Debug, Clone, Copy)]
+pub struct BinarySlice<'env> {
+    parent: Term<'env>,
+    offset: usize,
+    bytes: &'env [u8],
+}
+
+impl<'env> BinarySlice<'env> {
+    /// View the whole of a binary term
+    ///
+    /// Fails with `BadArg` if the term is not a binary. The view cannot
+    /// outlive the shared borrow of `env`.
+    pub fn from_term(term: Term<'env>, _env: &'env Context) -> NifResult<Self> {
+        Ok(Self {
+            parent: term,
+            offset: 0,
+            bytes: unsafe { term.binary_bytes()? },
+        })
+    }
+
+    /// The viewed bytes
+    pub fn as_bytes(&self) -> &'env [u8] {
+        self.bytes
+    }
+
+    /// The viewed bytes as UTF-8 text
+    pub fn as_str(&self) -> Option<&'env str> {
+        core::str::from_utf8(self.bytes).ok()
+    }
+
+    /// Number of bytes in the view
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Check if the view is empty
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Narrow the view to `range`, relative to this view, without copying
+    pub fn slice(&self, range: Range<usize>) -> Option<Self> {
+        let bytes = self.bytes.get(range.clone())?;
+        Some(Self {
+            parent: self.parent,
+            offset: self.offset + range.start,
+            bytes,
+        })
+    }
+
+    /// Split into two views at `mid`
+    pub fn split_at(&self, mid: usize) -> Option<(Self, Self)> {
+        Some((self.slice(0..mid)?, self.slice(mid..self.len())?))
+    }
+
+    /// The binary term this view was taken from
+    pub fn parent(&self) -> Term<'env> {
+        self.parent
+    }
+
+    /// Offset of the view within the parent binary
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Byte range of the view within the parent, for `HeapGuard::sub_binary`
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.bytes.len()
+    }
+}
+
+impl Deref for BinarySlice<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.bytes
+    }
+}
+
+impl AsRef<[u8]> for BinarySlice<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self.bytes
+    }
+}
+
+impl PartialEq<[u8]> for BinarySlice<'_> {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.bytes == other
+    }
+}
+
+/// Writable off-heap binary, handed to the VM without copying
+///
+/// Dropping an unreleased buffer frees it.
+#[derive(Debug)]
+pub struct OwnedBinary {
+    refc: NonNull<RefcBinary>,
+    len: usize,
+}
+
+impl OwnedBinary {
+    /// Allocate a zeroed buffer of `len` bytes; `None` when out of memory
+    pub fn new(len: usize) -> Option<Self> {
+        let refc = NonNull::new(unsafe { refc_binary_create(len, core::ptr::null_mut()) })?;
+        let mut binary = Self { refc, len };
+        binary.as_mut_slice().fill(0);
+        Some(binary)
+    }
+
+    /// Allocate a buffer holding a copy of `bytes`
+    pub fn from_slice(bytes: &[u8]) -> Option<Self> {
+        let mut binary = Self::new(bytes.len())?;
+        binary.as_mut_slice().copy_from_slice(bytes);
+        Some(binary)
+    }
+
+    /// Number of bytes in the buffer
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if the buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The buffer's bytes
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts((*self.refc.as_ptr()).data.as_ptr(), self.len) }
+    }
+
+    /// The buffer's bytes, writable
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut((*self.refc.as_ptr()).data.as_mut_ptr(), self.len) }
+    }
+
+    /// Turn the buffer into a binary term
+    ///
+    /// The bytes stay where they are; the term takes over the buffer.
+    /// Needs `Term::REFC_BINARY_WORDS` words of the reservation.
+    pub fn release<'a>(self, heap: &mut HeapGuard<'a>) -> NifResult<Term<'a>> {
+        let heap = heap.claim(Term::REFC_BINARY_WORDS)?;
+        let term = unsafe { term_from_refc_binary(self.refc.as_ptr(), heap) };
+        if term == 0 {
+            return Err(NifError::OutOfMemory);
+        }
+        core::mem::forget(self);
+        Ok(Term::from_raw(term))
+    }
+}
+
+// The buffer is exclusively owned until released
+unsafe impl Send for OwnedBinary {}
+
+impl Deref for OwnedBinary {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl DerefMut for OwnedBinary {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+impl Drop for OwnedBinary {
+    fn drop(&mut self) {
+        unsafe {
+            refc_binary_decrement_refcount(self.refc.as_ptr(), global_context_ptr());
+        }
+    }
+}
+
+/// A binary being filled in place, sealed into a term when done
+///
+/// Below `Term::REFC_BINARY_MIN` bytes the binary already sits on the
+/// process heap; above it, the bytes are in an off-heap buffer that
+/// `seal` hands over. The builder holds the heap reservation, so nothing
+/// else can run a collection before the binary is sealed. The bytes start
+/// out zeroed.
+pub struct BinaryBuilder<'a> {
+    heap: HeapGuard<'a>,
+    storage: Storage<'a>,
+}
+
+enum Storage<'a> {
+    Heap { term: Term<'a>, len: usize },
+    Refc(OwnedBinary),
+}
+
+impl<'a> BinaryBuilder<'a> {
+    /// Allocate `len` bytes from a reservation of `Term::binary_heap_words(len)` words
+    pub fn new(mut heap: HeapGuard<'a>, len: usize) -> NifResult<Self> {
+        let storage = if len < Term::REFC_BINARY_MIN {
+            let ptr = heap.alloc(Term::binary_heap_words(len))?;
+            let term = unsafe { Term::write_heap_binary(ptr, len) };
+            Storage::Heap { term, len }
+        } else {
+            if heap.remaining() < Term::REFC_BINARY_WORDS {
+                return Err(NifError::OutOfMemory);
+            }
+            Storage::Refc(OwnedBinary::new(len).ok_or(NifError::OutOfMemory)?)
+        };
+        Ok(Self { heap, storage })
+    }
+
+    /// Number of bytes in the binary
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Heap { len, .. } => *len,
+            Storage::Refc(buffer) => buffer.len(),
+        }
+    }
+
+    /// Check if the binary is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The binary's bytes
+    pub fn as_slice(&self) -> &[u8] {
+        match &self.storage {
+            Storage::Heap { term, len } => unsafe { core::slice::from_raw_parts(heap_data(*term), *len) },
+            Storage::Refc(buffer) => buffer.as_slice(),
+        }
+    }
+
+    /// The binary's bytes, writable
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        match &mut self.storage {
+            Storage::Heap { term, len } => unsafe { core::slice::from_raw_parts_mut(heap_data(*term), *len) },
+            Storage::Refc(buffer) => buffer.as_mut_slice(),
+        }
+    }
+
+    /// Finish the binary and return its term
+    pub fn seal(mut self) -> NifResult<Term<'a>> {
+        match self.storage {
+            Storage::Heap { term, .. } => Ok(term),
+            Storage::Refc(buffer) => buffer.release(&mut self.heap),
+        }
+    }
+}
+
+/// First data byte of a heap binary built by `Term::write_heap_binary`
+fn heap_data(term: Term) -> *mut u8 {
+    unsafe { (term.boxed_ptr() as *mut usize).add(2) as *mut u8 }
+}
+
+impl Deref for BinaryBuilder<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl DerefMut for BinaryBuilder<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
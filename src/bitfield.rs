@@ -0,0 +1,290 @@
+//! Bit-packed register layouts
+//!
+//! Describes register maps and command words declaratively as named
+//! fields (bit offset, width, endianness, signedness), then decodes binaries
+//! into maps and encodes maps back into binaries. Offsets and widths follow
+//! Erlang's bit syntax: bit 0 is the most significant bit of the first byte,
+//! so `BitField::new("temp", 4, 12)` matches `<<_:4, Temp:12, _/bits>>`.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use avmnif_rs::bitfield::{BitField, BitLayout};
+//! # use avmnif_rs::parse::parse_term;
+//! # use avmnif_rs::term::TermValue;
+//! # let table = avmnif_rs::testing::mocks::MockAtomTable::new();
+//! # let args = [TermValue::binary(vec![0x8F, 0xF6]), parse_term("#{mode => 5}", &table).unwrap()];
+//!
+//! const STATUS: BitLayout = BitLayout::new(2, &[
+//!     BitField::new("ready", 0, 1),
+//!     BitField::new("mode", 1, 3),
+//!     BitField::new("temp", 4, 12).signed(),
+//! ]);
+//!
+//! // <<16#8F, 16#F6>> -> #{ready => 1, mode => 0, temp => -10}
+//! let reading = STATUS.decode_map(&args[0], &table)?;
+//!
+//! // #{mode => 5} -> <<16#50, 0>>
+//! let command = STATUS.encode_map(&args[1], &table)?;
+//! # assert_eq!(Ok(reading), parse_term("#{ready => 1, mode => 0, temp => -10}", &table));
+//! # assert_eq!(command, TermValue::binary(vec![0x50, 0]));
+//! # }
+//! # Ok::<(), avmnif_rs::bitfield::BitfieldError>(())
+//! ```
+
+extern crate alloc;
+
+use crate::atom::{AtomError, AtomTableOps};
+use crate::term::{NifError, TermValue};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Byte order of a field wider than one byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Big,
+    /// Only for widths that are a multiple of 8
+    Little,
+}
+
+/// Errors from reading or writing bit fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BitfieldError {
+    /// Field extends past the end of the data
+    OutOfBounds(&'static str),
+    /// Width is 0, over 64, or not whole bytes for a little-endian field
+    InvalidWidth(&'static str),
+    /// Value does not fit in the field
+    ValueOutOfRange(&'static str),
+    /// No field with this name in the layout
+    UnknownField,
+    /// Input is not a binary (decode) or not a map of integers (encode)
+    WrongType,
+    /// Atom table failure while building or reading map keys
+    Atom(AtomError),
+}
+
+impl fmt::Display for BitfieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BitfieldError::OutOfBounds(name) => write!(f, "field {} out of bounds", name),
+            BitfieldError::InvalidWidth(name) => write!(f, "field {} has an invalid width", name),
+            BitfieldError::ValueOutOfRange(name) => write!(f, "value does not fit field {}", name),
+            BitfieldError::UnknownField => write!(f, "unknown field"),
+            BitfieldError::WrongType => write!(f, "wrong input type"),
+            BitfieldError::Atom(e) => write!(f, "atom error: {}", e),
+        }
+    }
+}
+
+impl From<AtomError> for BitfieldError {
+    fn from(error: AtomError) -> Self {
+        BitfieldError::Atom(error)
+    }
+}
+
+impl From<BitfieldError> for NifError {
+    fn from(_error: BitfieldError) -> Self {
+        NifError::BadArg
+    }
+}
+
+/// One named field of a layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitField {
+    pub name: &'static str,
+    /// Offset in bits from the start of the data
+    pub offset: usize,
+    /// Width in bits (1..=64)
+    pub width: u32,
+    pub endian: Endian,
+    pub signed: bool,
+}
+
+impl BitField {
+    /// Unsigned big-endian field
+    pub const fn new(name: &'static str, offset: usize, width: u32) -> Self {
+        Self {
+            name,
+            offset,
+            width,
+            endian: Endian::Big,
+            signed: false,
+        }
+    }
+
+    /// Same field stored little-endian
+    pub const fn little(mut self) -> Self {
+        self.endian = Endian::Little;
+        self
+    }
+
+    /// Same field as two's complement
+    pub const fn signed(mut self) -> Self {
+        self.signed = true;
+        self
+    }
+
+    /// Read the field from `data`, sign-extending signed fields
+    pub fn read(&self, data: &[u8]) -> Result<i64, BitfieldError> {
+        let raw = self.read_raw(data)?;
+        if self.signed {
+            let shift = 64 - self.width;
+            Ok(((raw << shift) as i64) >> shift)
+        } else {
+            i64::try_from(raw).map_err(|_| BitfieldError::ValueOutOfRange(self.name))
+        }
+    }
+
+    /// Read the field's bits as an unsigned value
+    pub fn read_raw(&self, data: &[u8]) -> Result<u64, BitfieldError> {
+        self.check(data.len())?;
+        let mut raw = 0u64;
+        for bit in self.offset..self.offset + self.width as usize {
+            raw = (raw << 1) | ((data[bit / 8] >> (7 - bit % 8)) & 1) as u64;
+        }
+        Ok(match self.endian {
+            Endian::Big => raw,
+            Endian::Little => swap_bytes(raw, self.width),
+        })
+    }
+
+    /// Write `value` into the field, leaving other bits untouched
+    pub fn write(&self, data: &mut [u8], value: i64) -> Result<(), BitfieldError> {
+        self.check(data.len())?;
+        if !self.fits(value) {
+            return Err(BitfieldError::ValueOutOfRange(self.name));
+        }
+        let mut raw = (value as u64) & mask(self.width);
+        if self.endian == Endian::Little {
+            raw = swap_bytes(raw, self.width);
+        }
+        for (i, bit) in (self.offset..self.offset + self.width as usize).enumerate() {
+            let value_bit = ((raw >> (self.width as usize - 1 - i)) & 1) as u8;
+            let shift = 7 - bit % 8;
+            data[bit / 8] = (data[bit / 8] & !(1 << shift)) | (value_bit << shift);
+        }
+        Ok(())
+    }
+
+    /// One past the last bit used by the field
+    pub const fn end(&self) -> usize {
+        self.offset + self.width as usize
+    }
+
+    fn fits(&self, value: i64) -> bool {
+        if self.signed {
+            let min = if self.width == 64 { i64::MIN } else { -(1i64 << (self.width - 1)) };
+            let max = if self.width == 64 { i64::MAX } else { (1i64 << (self.width - 1)) - 1 };
+            (min..=max).contains(&value)
+        } else {
+            value >= 0 && (self.width == 64 || (value as u64) >> self.width == 0)
+        }
+    }
+
+    fn check(&self, data_len: usize) -> Result<(), BitfieldError> {
+        if self.width == 0 || self.width > 64 || (self.endian == Endian::Little && self.width % 8 != 0) {
+            return Err(BitfieldError::InvalidWidth(self.name));
+        }
+        if self.end() > data_len * 8 {
+            return Err(BitfieldError::OutOfBounds(self.name));
+        }
+        Ok(())
+    }
+}
+
+fn mask(width: u32) -> u64 {
+    if width == 64 {
+        u64::MAX
+    } else {
+        (1u64 << width) - 1
+    }
+}
+
+/// Reverse the low `width / 8` bytes of `value`
+fn swap_bytes(value: u64, width: u32) -> u64 {
+    value.swap_bytes() >> (64 - width)
+}
+
+/// A fixed-size register or command word made of named fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitLayout {
+    /// Size of the encoded data in bytes
+    pub size: usize,
+    pub fields: &'static [BitField],
+}
+
+impl BitLayout {
+    pub const fn new(size: usize, fields: &'static [BitField]) -> Self {
+        Self { size, fields }
+    }
+
+    /// Look up a field by name
+    pub fn field(&self, name: &str) -> Option<&BitField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// Read one named field
+    pub fn read(&self, data: &[u8], name: &str) -> Result<i64, BitfieldError> {
+        self.field(name).ok_or(BitfieldError::UnknownField)?.read(data)
+    }
+
+    /// Write one named field in place
+    pub fn write(&self, data: &mut [u8], name: &str, value: i64) -> Result<(), BitfieldError> {
+        self.field(name).ok_or(BitfieldError::UnknownField)?.write(data, value)
+    }
+
+    /// Read every field in layout order
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<(&'static str, i64)>, BitfieldError> {
+        self.fields
+            .iter()
+            .map(|field| Ok((field.name, field.read(data)?)))
+            .collect()
+    }
+
+    /// Build `size` bytes from named values; unspecified fields are zero
+    pub fn encode(&self, values: &[(&str, i64)]) -> Result<Vec<u8>, BitfieldError> {
+        let mut data = alloc::vec![0u8; self.size];
+        for (name, value) in values {
+            self.write(&mut data, name, *value)?;
+        }
+        Ok(data)
+    }
+
+    /// Decode a binary term into a map of field atoms to integers
+    pub fn decode_map<T: AtomTableOps>(&self, binary: &TermValue, table: &T) -> Result<TermValue, BitfieldError> {
+        let data = match binary {
+            TermValue::Binary(data) => data,
+            _ => return Err(BitfieldError::WrongType),
+        };
+        let mut pairs = Vec::with_capacity(self.fields.len());
+        for field in self.fields {
+            let value = i32::try_from(field.read(data)?)
+                .map_err(|_| BitfieldError::ValueOutOfRange(field.name))?;
+            pairs.push((TermValue::Atom(table.ensure_atom_str(field.name)?), TermValue::int(value)));
+        }
+        Ok(TermValue::Map(pairs))
+    }
+
+    /// Encode a map of field atoms to integers into a binary term
+    ///
+    /// Keys that are not fields of the layout are rejected.
+    pub fn encode_map<T: AtomTableOps>(&self, map: &TermValue, table: &T) -> Result<TermValue, BitfieldError> {
+        let pairs = match map {
+            TermValue::Map(pairs) => pairs,
+            _ => return Err(BitfieldError::WrongType),
+        };
+        let mut data = alloc::vec![0u8; self.size];
+        for (key, value) in pairs {
+            let field = self
+                .fields
+                .iter()
+                .find(|field| key.is_atom_str(field.name, table))
+                .ok_or(BitfieldError::UnknownField)?;
+            let value = value.as_int().ok_or(BitfieldError::WrongType)?;
+            field.write(&mut data, value as i64)?;
+        }
+        Ok(TermValue::Binary(data))
+    }
+}
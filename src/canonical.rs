@@ -0,0 +1,274 @@
+//! Canonical term form
+//!
+//! Two devices that build the same map can list its keys in different
+//! orders, and a float can be `0.0` or `-0.0`, so equal terms do not always
+//! encode to the same bytes. `canonicalize` rewrites a term into one form:
+//!
+//! - map keys sorted in Erlang term order, recursively
+//! - `-0.0` written as `0.0`, and every NaN as the same NaN
+//!
+//! Integers need no rewriting: `TermValue` keeps them as plain values, and
+//! the encoders always choose the smallest representation.
+//!
+//! `etf::encode_canonical` and `HeapGuard::encode_canonical` encode the
+//! canonical form, so the output can be hashed, signed or compared byte
+//! for byte.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use avmnif_rs::etf;
+//! use avmnif_rs::parse::parse_term;
+//! # let table = avmnif_rs::testing::mocks::MockAtomTable::new();
+//!
+//! // The same config, built in two key orders
+//! let a = parse_term("#{rate => 9600, pins => {4, 5}}", &table).unwrap();
+//! let b = parse_term("#{pins => {4, 5}, rate => 9600}", &table).unwrap();
+//! assert_eq!(etf::encode_canonical(&a, &table)?, etf::encode_canonical(&b, &table)?);
+//! # }
+//! # Ok::<(), avmnif_rs::etf::EtfError>(())
+//! ```
+
+extern crate alloc;
+
+use crate::atom::AtomTableOps;
+use crate::term::TermValue;
+use alloc::{boxed::Box, vec::Vec};
+use core::cmp::Ordering;
+
+/// Position of a term's type in Erlang term order
+///
+/// number < atom < reference < fun < port < pid < tuple < map < nil < list
+/// < bitstring. Resources are references in AtomVM; invalid terms go last.
+fn type_rank(value: &TermValue) -> u8 {
+    match value {
+        TermValue::SmallInt(_) | TermValue::Integer(_) | TermValue::Float(_) => 0,
+        TermValue::Atom(_) => 1,
+        TermValue::Reference(_) | TermValue::Resource(_) => 2,
+        TermValue::Function(_) => 3,
+        TermValue::Port(_) => 4,
+        TermValue::Pid(_) => 5,
+        TermValue::Tuple(_) => 6,
+        TermValue::Map(_) => 7,
+        TermValue::Nil => 8,
+        TermValue::List(_, _) => 9,
+        TermValue::Binary(_) => 10,
+        TermValue::Invalid(_) => 11,
+    }
+}
+
+fn compare_atoms<T: AtomTableOps>(a: crate::atom::AtomIndex, b: crate::atom::AtomIndex, table: &T) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    match (table.get_atom_string(a), table.get_atom_string(b)) {
+        (Ok(a), Ok(b)) => a.as_bytes().cmp(b.as_bytes()),
+        // Unknown atoms still get a stable order
+        _ => a.0.cmp(&b.0),
+    }
+}
+
+fn compare_numbers(a: &TermValue, b: &TermValue) -> Ordering {
+    match (a, b) {
+        (TermValue::Float(a), TermValue::Float(b)) => a.total_cmp(b),
+        // On a tie the integer sorts first
+        (TermValue::Float(a), b) => compare_integer_float(b.as_integer().unwrap_or_default(), *a)
+            .reverse()
+            .then(Ordering::Greater),
+        (a, TermValue::Float(b)) => compare_integer_float(a.as_integer().unwrap_or_default(), *b).then(Ordering::Less),
+        (a, b) => a.as_integer().cmp(&b.as_integer()),
+    }
+}
+
+/// Exact order of an integer and a float
+///
+/// Rounding to f64 keeps the order of unequal values; when the rounded
+/// integer equals the float, the float is integral and compared as one.
+fn compare_integer_float(integer: i64, float: f64) -> Ordering {
+    match (integer as f64).total_cmp(&float) {
+        Ordering::Equal => (integer as i128).cmp(&(float as i128)),
+        order => order,
+    }
+}
+
+/// Map pairs sorted by key, for comparing maps regardless of pair order
+fn sorted_pairs<'v, T: AtomTableOps>(pairs: &'v [(TermValue, TermValue)], table: &T) -> Vec<&'v (TermValue, TermValue)> {
+    let mut sorted: Vec<_> = pairs.iter().collect();
+    sorted.sort_by(|(a, _), (b, _)| compare(a, b, table));
+    sorted
+}
+
+/// Compare two terms in Erlang term order
+///
+/// Atoms compare by name, so the order does not depend on atom table
+/// indexes. Maps compare by size, then keys, then values.
+pub fn compare<T: AtomTableOps>(a: &TermValue, b: &TermValue, table: &T) -> Ordering {
+    let rank = type_rank(a).cmp(&type_rank(b));
+    if rank != Ordering::Equal {
+        return rank;
+    }
+    match (a, b) {
+        (TermValue::Atom(a), TermValue::Atom(b)) => compare_atoms(*a, *b, table),
+        (TermValue::Reference(a), TermValue::Reference(b)) => a.0.cmp(&b.0),
+        (TermValue::Resource(a), TermValue::Resource(b)) => {
+            a.type_name.cmp(&b.type_name).then((a.ptr as usize).cmp(&(b.ptr as usize)))
+        }
+        // References before resources
+        (TermValue::Reference(_), _) => Ordering::Less,
+        (_, TermValue::Reference(_)) => Ordering::Greater,
+        (TermValue::Function(a), TermValue::Function(b)) => compare_atoms(a.module, b.module, table)
+            .then_with(|| compare_atoms(a.function, b.function, table))
+            .then(a.arity.cmp(&b.arity)),
+        (TermValue::Port(a), TermValue::Port(b)) => a.0.cmp(&b.0),
+        (TermValue::Pid(a), TermValue::Pid(b)) => a.0.cmp(&b.0),
+        (TermValue::Tuple(a), TermValue::Tuple(b)) => a.len().cmp(&b.len()).then_with(|| {
+            a.iter()
+                .zip(b)
+                .map(|(x, y)| compare(x, y, table))
+                .find(|order| *order != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        }),
+        (TermValue::Map(a), TermValue::Map(b)) => a.len().cmp(&b.len()).then_with(|| {
+            let (a, b) = (sorted_pairs(a, table), sorted_pairs(b, table));
+            let keys = a.iter().zip(&b).map(|((x, _), (y, _))| compare(x, y, table));
+            let values = a.iter().zip(&b).map(|((_, x), (_, y))| compare(x, y, table));
+            keys.chain(values)
+                .find(|order| *order != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        }),
+        (TermValue::List(_, _), TermValue::List(_, _)) => {
+            // Walk both spines iteratively; long lists must not recurse per cell
+            let (mut a, mut b) = (a, b);
+            while let (TermValue::List(ha, ta), TermValue::List(hb, tb)) = (a, b) {
+                let order = compare(ha, hb, table);
+                if order != Ordering::Equal {
+                    return order;
+                }
+                a = ta;
+                b = tb;
+            }
+            compare(a, b, table)
+        }
+        (TermValue::Binary(a), TermValue::Binary(b)) => a.cmp(b),
+        (TermValue::Nil, TermValue::Nil) | (TermValue::Invalid(_), TermValue::Invalid(_)) => Ordering::Equal,
+        _ => compare_numbers(a, b),
+    }
+}
+
+/// A term ordered by `compare`, for `Ord`-based containers and sorts
+///
+/// `TermValue` itself cannot be `Ord`: comparing atoms needs the atom
+/// table, and its derived `PartialEq` is structural (map pair order
+/// matters, NaN is unequal to itself). `Ordered` pairs a term with a table
+/// so it can be a `BTreeSet` element or a `BTreeMap` key. Equality is
+/// exact equality (`=:=`), so `1` and `1.0` are different.
+#[derive(Debug, Clone)]
+pub struct Ordered<'t, T: AtomTableOps> {
+    value: TermValue,
+    table: &'t T,
+}
+
+impl<'t, T: AtomTableOps> Ordered<'t, T> {
+    pub fn new(value: TermValue, table: &'t T) -> Self {
+        Self { value, table }
+    }
+
+    pub fn value(&self) -> &TermValue {
+        &self.value
+    }
+
+    pub fn into_inner(self) -> TermValue {
+        self.value
+    }
+}
+
+impl<T: AtomTableOps> PartialEq for Ordered<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: AtomTableOps> Eq for Ordered<'_, T> {}
+
+impl<T: AtomTableOps> PartialOrd for Ordered<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: AtomTableOps> Ord for Ordered<'_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare(&self.value, &other.value, self.table)
+    }
+}
+
+/// Sort terms in Erlang term order, as `lists:sort/1`
+pub fn sort<T: AtomTableOps>(values: &mut [TermValue], table: &T) {
+    values.sort_by(|a, b| compare(a, b, table));
+}
+
+/// Sort terms and drop exact duplicates, keeping the first of each
+pub fn sort_dedup<T: AtomTableOps>(values: &mut Vec<TermValue>, table: &T) {
+    sort(values, table);
+    values.dedup_by(|a, b| compare(a, b, table) == Ordering::Equal);
+}
+
+fn canonical_float(value: f64) -> f64 {
+    if value.is_nan() {
+        f64::NAN
+    } else if value == 0.0 {
+        0.0
+    } else {
+        value
+    }
+}
+
+/// Rewrite a term into its canonical form
+pub fn canonicalize<T: AtomTableOps>(value: TermValue, table: &T) -> TermValue {
+    match value {
+        TermValue::Float(value) => TermValue::Float(canonical_float(value)),
+        TermValue::Tuple(elements) => {
+            TermValue::Tuple(elements.into_iter().map(|element| canonicalize(element, table)).collect())
+        }
+        TermValue::List(head, tail) => {
+            let mut heads = Vec::new();
+            let mut rest = TermValue::List(head, tail);
+            while let TermValue::List(head, tail) = rest {
+                heads.push(canonicalize(*head, table));
+                rest = *tail;
+            }
+            let mut list = canonicalize(rest, table);
+            for head in heads.into_iter().rev() {
+                list = TermValue::List(Box::new(head), Box::new(list));
+            }
+            list
+        }
+        TermValue::Map(pairs) => {
+            let mut pairs: Vec<_> = pairs
+                .into_iter()
+                .map(|(key, value)| (canonicalize(key, table), canonicalize(value, table)))
+                .collect();
+            pairs.sort_by(|(a, _), (b, _)| compare(a, b, table));
+            TermValue::Map(pairs)
+        }
+        other => other,
+    }
+}
+
+/// Whether a term is already in canonical form
+pub fn is_canonical<T: AtomTableOps>(value: &TermValue, table: &T) -> bool {
+    match value {
+        TermValue::Float(f) => f.to_bits() == canonical_float(*f).to_bits(),
+        TermValue::Tuple(elements) => elements.iter().all(|element| is_canonical(element, table)),
+        TermValue::List(_, _) => {
+            let mut elements = value.iter_list();
+            elements.by_ref().all(|element| is_canonical(element, table)) && is_canonical(elements.tail(), table)
+        }
+        TermValue::Map(pairs) => {
+            pairs.windows(2).all(|w| compare(&w[0].0, &w[1].0, table) != Ordering::Greater)
+                && pairs.iter().all(|(k, v)| is_canonical(k, table) && is_canonical(v, table))
+        }
+        _ => true,
+    }
+}
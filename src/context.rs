@@ -4,43 +4,16 @@
 
 use alloc::boxed::Box;
 use crate::term::Term;
+use core::any::Any;
 use core::ffi::c_void;
 
-/// Opaque context structure that matches AtomVM's internal representation
-#[repr(C)]
-pub struct Context {
-    _private: [u8; 0],
-}
-
-/// Global AtomVM context
-pub type GlobalContext = c_void;
+pub use avmnif_sys::{Context, GlobalContext};
 
 // AtomVM Context API FFI declarations
-extern "C" {
-    /// Create a new port context
-    pub fn create_port_context(global: *const GlobalContext) -> *mut Context;
-    
-    /// Destroy a port context and clean up resources
-    pub fn destroy_port_context(ctx: *mut Context);
-    
-    /// Check if a port is still alive
-    pub fn port_is_alive(ctx: *const Context) -> i32;
-    
-    /// Get platform data from context
-    pub fn context_get_platform_data(ctx: *const Context) -> *mut c_void;
-    
-    /// Set platform data in context
-    pub fn context_set_platform_data(ctx: *mut Context, data: *mut c_void);
-    
-    /// Get user data from context (for storing Erlang terms)
-    pub fn context_get_user_data(ctx: *const Context) -> u64;
-    
-    /// Set user data in context
-    pub fn context_set_user_data(ctx: *mut Context, data: u64);
-    
-    /// Get the global context pointer (for ISR use)
-    pub fn global_context_ptr() -> *mut GlobalContext;
-}
+pub use avmnif_sys::{
+    context_get_global, context_get_platform_data, context_get_user_data, context_set_platform_data,
+    context_set_user_data, create_port_context, destroy_port_context, global_context_ptr, port_is_alive,
+};
 
 /// Context extension trait for safe platform data management
 pub trait ContextExt {
@@ -58,7 +31,9 @@ pub trait ContextExt {
     
     /// Safely cast platform data to a specific type
     unsafe fn get_platform_data_as<T>(&self) -> *mut T {
-        self.get_platform_data() as *mut T
+        let ptr = self.get_platform_data() as *mut T;
+        crate::contracts::aligned(ptr as *const T, "platform data is misaligned for requested type");
+        ptr
     }
     
     /// Safely set platform data from a boxed value
@@ -83,7 +58,7 @@ pub trait ContextExt {
     }
     
     /// Get user data as a Term
-    unsafe fn get_user_term(&self) -> Term {
+    unsafe fn get_user_term(&self) -> Term<'_> {
         Term::from_raw(self.get_user_data().try_into().unwrap())
     }
     
@@ -138,6 +113,11 @@ pub fn get_global_context() -> *mut GlobalContext {
     unsafe { global_context_ptr() }
 }
 
+/// The global context `ctx` belongs to
+pub fn context_global(ctx: &Context) -> *mut GlobalContext {
+    unsafe { context_get_global(ctx as *const Context) }
+}
+
 /// Port builder for ergonomic port creation
 pub struct PortBuilder<T> {
     data: T,
@@ -376,3 +356,176 @@ pub fn cleanup_platform_data<T: PlatformData>(ctx: &mut Context) -> Option<T> {
         T::take_from_context(ctx)
     }
 }
+
+// ── Scratch Storage ─────────────────────────────────────────────────────────
+
+/// Small typed key-value store kept in a context's platform data
+///
+/// Lets multi-call NIF workflows (iterative parsers, chunked transfers)
+/// stash Rust state on the calling process between calls without defining
+/// a resource type. Values are looked up by key and type; asking for the
+/// wrong type behaves like a missing key.
+///
+/// The VM knows nothing of the store and does not free it when the
+/// process exits: the NIFs that fill it own it, and must call
+/// `release_scratch` once the workflow ends, or it leaks.
+#[derive(Default)]
+pub struct ScratchStore {
+    entries: alloc::vec::Vec<(&'static str, Box<dyn Any>)>,
+}
+
+impl ScratchStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store `value` under `key`, returning the previous value if it had the same type
+    pub fn put<T: 'static>(&mut self, key: &'static str, value: T) -> Option<T> {
+        let boxed: Box<dyn Any> = Box::new(value);
+        match self.position(key) {
+            Some(i) => {
+                let old = core::mem::replace(&mut self.entries[i].1, boxed);
+                old.downcast::<T>().ok().map(|old| *old)
+            }
+            None => {
+                self.entries.push((key, boxed));
+                None
+            }
+        }
+    }
+
+    /// Borrow the value under `key`
+    pub fn get<T: 'static>(&self, key: &'static str) -> Option<&T> {
+        self.position(key).and_then(|i| self.entries[i].1.downcast_ref::<T>())
+    }
+
+    /// Mutably borrow the value under `key`
+    pub fn get_mut<T: 'static>(&mut self, key: &'static str) -> Option<&mut T> {
+        match self.position(key) {
+            Some(i) => self.entries[i].1.downcast_mut::<T>(),
+            None => None,
+        }
+    }
+
+    /// Remove and return the value under `key`; a value of another type is left in place
+    pub fn take<T: 'static>(&mut self, key: &'static str) -> Option<T> {
+        let i = self.position(key)?;
+        if !self.entries[i].1.is::<T>() {
+            return None;
+        }
+        let (_, value) = self.entries.swap_remove(i);
+        value.downcast::<T>().ok().map(|value| *value)
+    }
+
+    /// Drop the value under `key` whatever its type
+    pub fn remove(&mut self, key: &'static str) -> bool {
+        match self.position(key) {
+            Some(i) => {
+                self.entries.swap_remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Check whether `key` holds a value
+    pub fn contains(&self, key: &'static str) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Number of stored values
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the store is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drop every value
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn position(&self, key: &'static str) -> Option<usize> {
+        self.entries.iter().position(|(k, _)| *k == key)
+    }
+}
+
+impl PlatformData for ScratchStore {
+    fn cleanup(&mut self) {
+        self.clear();
+    }
+}
+
+/// Run `f` with the context's scratch store, creating it on first use
+///
+/// The store takes over the context's platform data, so use it only on
+/// contexts that don't already hold other platform data (e.g. the calling
+/// process of a NIF, not a port).
+///
+/// # Safety
+/// The context's platform data must be unset or a `ScratchStore` put
+/// there by these functions. Anything else is read as a `ScratchStore`.
+/// The caller owns the store once it exists: nothing releases it when the
+/// context is destroyed, so it must be freed with `release_scratch`
+/// before the process exits.
+pub unsafe fn with_scratch<R, F>(ctx: &mut Context, f: F) -> R
+where
+    F: FnOnce(&mut ScratchStore) -> R,
+{
+    if !ctx.has_platform_data() {
+        init_platform_data(ctx, ScratchStore::new());
+    }
+    let store = &mut *ctx.get_platform_data_as::<ScratchStore>();
+    f(store)
+}
+
+/// Store a value in the context's scratch store
+///
+/// # Safety
+/// As for `with_scratch`.
+pub unsafe fn scratch_put<T: 'static>(ctx: &mut Context, key: &'static str, value: T) -> Option<T> {
+    with_scratch(ctx, |store| store.put(key, value))
+}
+
+/// Copy a value out of the context's scratch store
+///
+/// `None` when there is no store; reading never creates one.
+///
+/// # Safety
+/// As for `with_scratch`.
+pub unsafe fn scratch_get<T: Clone + 'static>(ctx: &mut Context, key: &'static str) -> Option<T> {
+    existing_scratch(ctx)?.get::<T>(key).cloned()
+}
+
+/// Remove a value from the context's scratch store
+///
+/// `None` when there is no store; taking never creates one.
+///
+/// # Safety
+/// As for `with_scratch`.
+pub unsafe fn scratch_take<T: 'static>(ctx: &mut Context, key: &'static str) -> Option<T> {
+    existing_scratch(ctx)?.take(key)
+}
+
+/// The context's scratch store, if one was created
+unsafe fn existing_scratch(ctx: &mut Context) -> Option<&mut ScratchStore> {
+    if ctx.has_platform_data() {
+        Some(&mut *ctx.get_platform_data_as::<ScratchStore>())
+    } else {
+        None
+    }
+}
+
+/// Free the context's scratch store and everything in it
+///
+/// # Safety
+/// As for `with_scratch`.
+pub unsafe fn release_scratch(ctx: &mut Context) {
+    if let Some(mut store) = cleanup_platform_data::<ScratchStore>(ctx) {
+        store.cleanup();
+    }
+}
@@ -0,0 +1,48 @@
+//! Runtime contract checks for unsafe paths
+//!
+//! With the `debug-contracts` feature in a debug build, these checks panic
+//! on misuse that would otherwise be undefined behavior: null contexts,
+//! misaligned boxed pointers, oversized tuples, releasing resources without
+//! a reference left. Without the feature, or in release builds, they
+//! compile to nothing.
+//!
+//! The checks are plain functions rather than macros so that code expanded
+//! in downstream crates (`nif_collection!`, `port_collection!`) follows
+//! this crate's feature, not the caller's.
+
+/// True when contract checks are compiled in
+pub const ENABLED: bool = cfg!(all(feature = "debug-contracts", debug_assertions));
+
+/// Check an arbitrary condition
+#[inline(always)]
+#[track_caller]
+pub fn require(condition: bool, what: &'static str) {
+    if ENABLED && !condition {
+        violated(what);
+    }
+}
+
+/// Check that a pointer received from the VM is non-null
+#[inline(always)]
+#[track_caller]
+pub fn non_null<T>(ptr: *const T, what: &'static str) {
+    if ENABLED && ptr.is_null() {
+        violated(what);
+    }
+}
+
+/// Check that a non-null pointer is suitably aligned for `T`
+#[inline(always)]
+#[track_caller]
+pub fn aligned<T>(ptr: *const T, what: &'static str) {
+    if ENABLED && (ptr as usize) % core::mem::align_of::<T>() != 0 {
+        violated(what);
+    }
+}
+
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn violated(what: &'static str) -> ! {
+    panic!("avmnif contract violated: {}", what)
+}
@@ -0,0 +1,247 @@
+//! Term diffing
+//!
+//! `term_diff` describes how one term became another, so a port can push
+//! only what changed in its state instead of a full snapshot each time.
+//! `apply_diff` replays a description on the old term. Both sides speak the
+//! same format, so Erlang code can apply diffs produced here and vice versa:
+//!
+//! - `same`: nothing changed
+//! - `{replace, New}`: take `New` as is
+//! - `{map, Ops}`: `Ops` is a list of `{put, Key, Value}`, `{remove, Key}`
+//!   and `{update, Key, Diff}`
+//! - `{tuple, Ops}`: same arity; `Ops` is a list of `{update, Index, Diff}`
+//! - `{list, Ops}`: `Ops` is a list of `{update, Index, Diff}` followed by
+//!   at most one `{splice, Index, DeleteCount, Inserted}`
+//!
+//! Indexes are zero-based. Lists are compared by trimming their common
+//! prefix and suffix, not by a full edit-distance search: one insertion or
+//! removal gives a single splice, while scattered edits within a run of the
+//! same length become updates.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use avmnif_rs::diff::{apply_diff, term_diff};
+//! # use avmnif_rs::parse::parse_term;
+//! # let table = avmnif_rs::testing::mocks::MockAtomTable::new();
+//! # let mut last_sent = parse_term("#{temp => 21, mode => auto}", &table).unwrap();
+//! # let state = parse_term("#{temp => 22, mode => auto}", &table).unwrap();
+//! # let mut sent = Vec::new();
+//! # let mut send = |delta: &avmnif_rs::term::TermValue| sent.push(delta.clone());
+//!
+//! let delta = term_diff(&last_sent, &state, &table);
+//! if !delta.is_atom_str("same", &table) {
+//!     send(&delta);
+//!     last_sent = state.clone();
+//! }
+//!
+//! // Elsewhere: rebuild the new state
+//! # let old = parse_term("#{temp => 21, mode => auto}", &table).unwrap();
+//! let state = apply_diff(&old, &delta, &table)?;
+//! # assert_eq!(state, last_sent);
+//! # assert_eq!(sent.len(), 1);
+//! # }
+//! # Ok::<(), avmnif_rs::term::NifError>(())
+//! ```
+
+extern crate alloc;
+
+use crate::atom::AtomTableOps;
+use crate::term::{NifError, NifResult, TermValue};
+use alloc::vec::Vec;
+
+/// Describe the changes that turn `old` into `new`
+pub fn term_diff<T: AtomTableOps>(old: &TermValue, new: &TermValue, table: &T) -> TermValue {
+    if old == new {
+        return TermValue::atom("same", table);
+    }
+    match (old, new) {
+        (TermValue::Map(old_pairs), TermValue::Map(new_pairs)) => {
+            tagged("map", diff_map(old_pairs, new_pairs, table), table)
+        }
+        (TermValue::Tuple(old_elems), TermValue::Tuple(new_elems)) if old_elems.len() == new_elems.len() => {
+            let ops = old_elems
+                .iter()
+                .zip(new_elems)
+                .enumerate()
+                .filter(|(_, (a, b))| a != b)
+                .map(|(i, (a, b))| update(TermValue::int(i as i32), term_diff(a, b, table), table))
+                .collect();
+            tagged("tuple", ops, table)
+        }
+        (TermValue::List(_, _) | TermValue::Nil, TermValue::List(_, _) | TermValue::Nil) => {
+            match (proper_list(old), proper_list(new)) {
+                (Some(old_elems), Some(new_elems)) => tagged("list", diff_list(&old_elems, &new_elems, table), table),
+                _ => replace(new, table),
+            }
+        }
+        _ => replace(new, table),
+    }
+}
+
+/// Apply a description produced by `term_diff` to `old`
+///
+/// Fails with `BadArg` if the diff is malformed or does not fit `old`.
+pub fn apply_diff<T: AtomTableOps>(old: &TermValue, diff: &TermValue, table: &T) -> NifResult<TermValue> {
+    if diff.is_atom_str("same", table) {
+        return Ok(old.clone());
+    }
+    let (tag, arg) = match diff.as_tuple() {
+        Some([tag, arg]) => (tag, arg),
+        _ => return Err(NifError::BadArg),
+    };
+    let ops = || proper_list(arg).ok_or(NifError::BadArg);
+
+    if tag.is_atom_str("replace", table) {
+        Ok(arg.clone())
+    } else if tag.is_atom_str("map", table) {
+        let mut pairs = match old {
+            TermValue::Map(pairs) => pairs.clone(),
+            _ => return Err(NifError::BadArg),
+        };
+        for op in ops()? {
+            match op.as_tuple() {
+                Some([op, key, value]) if op.is_atom_str("put", table) => {
+                    match pairs.iter_mut().find(|(k, _)| k == key) {
+                        Some(pair) => pair.1 = value.clone(),
+                        None => pairs.push((key.clone(), value.clone())),
+                    }
+                }
+                Some([op, key]) if op.is_atom_str("remove", table) => {
+                    pairs.retain(|(k, _)| k != key);
+                }
+                Some([op, key, sub]) if op.is_atom_str("update", table) => {
+                    let pair = pairs.iter_mut().find(|(k, _)| k == key).ok_or(NifError::BadArg)?;
+                    pair.1 = apply_diff(&pair.1, sub, table)?;
+                }
+                _ => return Err(NifError::BadArg),
+            }
+        }
+        Ok(TermValue::Map(pairs))
+    } else if tag.is_atom_str("tuple", table) {
+        let mut elems = old.as_tuple().ok_or(NifError::BadArg)?.to_vec();
+        apply_updates(&mut elems, &ops()?, table)?;
+        Ok(TermValue::Tuple(elems))
+    } else if tag.is_atom_str("list", table) {
+        let mut elems: Vec<TermValue> = proper_list(old).ok_or(NifError::BadArg)?.into_iter().cloned().collect();
+        let ops = ops()?;
+        let splice_at = ops.iter().position(|op| tuple_tag_is(op, "splice", table));
+        let (updates, splice) = ops.split_at(splice_at.unwrap_or(ops.len()));
+        apply_updates(&mut elems, updates, table)?;
+        match splice {
+            [] => {}
+            [op] => {
+                let (index, delete, inserted) = match op.as_tuple() {
+                    Some([_, index, delete, inserted]) => (index_of(index)?, index_of(delete)?, inserted),
+                    _ => return Err(NifError::BadArg),
+                };
+                if index + delete > elems.len() {
+                    return Err(NifError::BadArg);
+                }
+                let inserted = proper_list(inserted).ok_or(NifError::BadArg)?;
+                elems.splice(index..index + delete, inserted.into_iter().cloned());
+            }
+            _ => return Err(NifError::BadArg),
+        }
+        Ok(TermValue::list(elems))
+    } else {
+        Err(NifError::BadArg)
+    }
+}
+
+fn diff_map<T: AtomTableOps>(
+    old: &[(TermValue, TermValue)],
+    new: &[(TermValue, TermValue)],
+    table: &T,
+) -> Vec<TermValue> {
+    let mut ops = Vec::new();
+    for (key, new_value) in new {
+        match old.iter().find(|(k, _)| k == key) {
+            None => ops.push(TermValue::tuple(alloc::vec![
+                TermValue::atom("put", table),
+                key.clone(),
+                new_value.clone(),
+            ])),
+            Some((_, old_value)) if old_value != new_value => {
+                ops.push(update(key.clone(), term_diff(old_value, new_value, table), table));
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, _) in old {
+        if !new.iter().any(|(k, _)| k == key) {
+            ops.push(TermValue::tuple(alloc::vec![TermValue::atom("remove", table), key.clone()]));
+        }
+    }
+    ops
+}
+
+fn diff_list<T: AtomTableOps>(old: &[&TermValue], new: &[&TermValue], table: &T) -> Vec<TermValue> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    // Pair up the overlapping part as in-place updates, splice the rest
+    let paired = old_mid.len().min(new_mid.len());
+    let mut ops: Vec<TermValue> = (0..paired)
+        .filter(|&i| old_mid[i] != new_mid[i])
+        .map(|i| update(TermValue::int((prefix + i) as i32), term_diff(old_mid[i], new_mid[i], table), table))
+        .collect();
+    if old_mid.len() != new_mid.len() {
+        ops.push(TermValue::tuple(alloc::vec![
+            TermValue::atom("splice", table),
+            TermValue::int((prefix + paired) as i32),
+            TermValue::int((old_mid.len() - paired) as i32),
+            TermValue::list(new_mid[paired..].iter().map(|&v| v.clone()).collect()),
+        ]));
+    }
+    ops
+}
+
+fn apply_updates<T: AtomTableOps>(elems: &mut [TermValue], ops: &[&TermValue], table: &T) -> NifResult<()> {
+    for op in ops {
+        match op.as_tuple() {
+            Some([op, index, sub]) if op.is_atom_str("update", table) => {
+                let elem = elems.get_mut(index_of(index)?).ok_or(NifError::BadArg)?;
+                *elem = apply_diff(elem, sub, table)?;
+            }
+            _ => return Err(NifError::BadArg),
+        }
+    }
+    Ok(())
+}
+
+fn proper_list(value: &TermValue) -> Option<Vec<&TermValue>> {
+    let mut iter = value.iter_list();
+    let elems: Vec<&TermValue> = iter.by_ref().collect();
+    iter.tail().is_nil().then_some(elems)
+}
+
+fn index_of(value: &TermValue) -> NifResult<usize> {
+    value.as_int().and_then(|i| usize::try_from(i).ok()).ok_or(NifError::BadArg)
+}
+
+fn tuple_tag_is<T: AtomTableOps>(value: &TermValue, tag: &str, table: &T) -> bool {
+    value.tuple_get(0).is_some_and(|t| t.is_atom_str(tag, table))
+}
+
+fn tagged<T: AtomTableOps>(tag: &str, ops: Vec<TermValue>, table: &T) -> TermValue {
+    TermValue::tuple(alloc::vec![TermValue::atom(tag, table), TermValue::list(ops)])
+}
+
+fn replace<T: AtomTableOps>(new: &TermValue, table: &T) -> TermValue {
+    TermValue::tuple(alloc::vec![TermValue::atom("replace", table), new.clone()])
+}
+
+fn update<T: AtomTableOps>(key: TermValue, diff: TermValue, table: &T) -> TermValue {
+    TermValue::tuple(alloc::vec![TermValue::atom("update", table), key, diff])
+}
@@ -0,0 +1,612 @@
+//! Erlang External Term Format (ETF) encoding and decoding
+//!
+//! Converts `TermValue` trees to and from the byte format produced by
+//! `term_to_binary/1`, so terms can be stored, logged or exchanged with
+//! other nodes independently of any VM heap.
+//!
+//! # Design Philosophy
+//!
+//! Atoms are written by name, never by index. Both directions take an
+//! `impl AtomTableOps`, so the same bytes decode correctly against any
+//! atom table.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use avmnif_rs::etf;
+//! # use avmnif_rs::term::TermValue;
+//! # use avmnif_rs::testing::mocks::MockAtomTable;
+//! # let table = MockAtomTable::new();
+//! # let term = TermValue::tuple(vec![TermValue::atom("ok", &table), TermValue::int(42)]);
+//!
+//! let bytes = etf::encode(&term, &table)?;
+//! let back = etf::decode(&bytes, &table)?;
+//! assert_eq!(term, back);
+//! # }
+//! # Ok::<(), avmnif_rs::etf::EtfError>(())
+//! ```
+
+extern crate alloc;
+
+use crate::atom::{AtomError, AtomTableOps};
+use crate::canonical;
+use crate::term::{FunctionRef, NifError, PortId, ProcessId, RefId, TermValue};
+use alloc::{boxed::Box, vec::Vec};
+use core::fmt;
+
+// ── Tags ────────────────────────────────────────────────────────────────────
+
+/// Version byte prefixed to every encoded term
+pub const VERSION: u8 = 131;
+
+const NEW_FLOAT_EXT: u8 = 70;
+const NEW_PID_EXT: u8 = 88;
+const NEW_PORT_EXT: u8 = 89;
+const NEWER_REFERENCE_EXT: u8 = 90;
+const SMALL_INTEGER_EXT: u8 = 97;
+const INTEGER_EXT: u8 = 98;
+const ATOM_EXT: u8 = 100;
+const PID_EXT: u8 = 103;
+const SMALL_TUPLE_EXT: u8 = 104;
+const LARGE_TUPLE_EXT: u8 = 105;
+const NIL_EXT: u8 = 106;
+const STRING_EXT: u8 = 107;
+const LIST_EXT: u8 = 108;
+const BINARY_EXT: u8 = 109;
+const SMALL_BIG_EXT: u8 = 110;
+const EXPORT_EXT: u8 = 113;
+const SMALL_ATOM_EXT: u8 = 115;
+const MAP_EXT: u8 = 116;
+const ATOM_UTF8_EXT: u8 = 118;
+const SMALL_ATOM_UTF8_EXT: u8 = 119;
+const V4_PORT_EXT: u8 = 120;
+
+/// Node name used for pids, ports and references encoded by this module
+const LOCAL_NODE: &str = "nonode@nohost";
+
+/// Maximum nesting depth accepted by the decoder
+///
+/// Decoding recurses per nesting level; input comes from captures and
+/// other untrusted sources, so the depth is bounded.
+pub const MAX_DEPTH: usize = 256;
+
+// ── Errors ──────────────────────────────────────────────────────────────────
+
+/// Errors that can occur while encoding or decoding ETF
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EtfError {
+    /// Input ended in the middle of a term
+    UnexpectedEnd,
+    /// Missing or wrong version byte
+    BadVersion(u8),
+    /// Tag byte not understood by this decoder
+    UnknownTag(u8),
+    /// Term cannot be represented (resources, invalid terms, ...)
+    Unsupported(&'static str),
+    /// Integer does not fit in a small int
+    IntegerOverflow,
+    /// Nesting exceeds `MAX_DEPTH`
+    TooDeep,
+    /// Bytes left over after the top-level term
+    TrailingBytes(usize),
+    /// Atom name could not be resolved or created
+    Atom(AtomError),
+}
+
+impl fmt::Display for EtfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EtfError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            EtfError::BadVersion(v) => write!(f, "bad ETF version byte {}", v),
+            EtfError::UnknownTag(t) => write!(f, "unknown ETF tag {}", t),
+            EtfError::Unsupported(what) => write!(f, "cannot encode {}", what),
+            EtfError::IntegerOverflow => write!(f, "integer out of range"),
+            EtfError::TooDeep => write!(f, "term nesting too deep"),
+            EtfError::TrailingBytes(n) => write!(f, "{} trailing bytes after term", n),
+            EtfError::Atom(e) => write!(f, "atom error: {}", e),
+        }
+    }
+}
+
+impl From<AtomError> for EtfError {
+    fn from(error: AtomError) -> Self {
+        EtfError::Atom(error)
+    }
+}
+
+impl From<EtfError> for NifError {
+    fn from(error: EtfError) -> Self {
+        match error {
+            EtfError::Atom(AtomError::AllocationFailed) => NifError::OutOfMemory,
+            EtfError::Unsupported(_) | EtfError::Atom(_) | EtfError::IntegerOverflow => NifError::BadArg,
+            _ => NifError::InvalidTerm,
+        }
+    }
+}
+
+/// Result type for ETF operations
+pub type EtfResult<T> = core::result::Result<T, EtfError>;
+
+// ── Encoding ────────────────────────────────────────────────────────────────
+
+/// Encode a term, including the leading version byte
+pub fn encode<T: AtomTableOps>(term: &TermValue, table: &T) -> EtfResult<Vec<u8>> {
+    let mut out = Vec::new();
+    encode_into(term, table, &mut out)?;
+    Ok(out)
+}
+
+/// Encode a term, appending to an existing buffer
+pub fn encode_into<T: AtomTableOps>(
+    term: &TermValue,
+    table: &T,
+    out: &mut Vec<u8>,
+) -> EtfResult<()> {
+    out.push(VERSION);
+    Encoder { table, out }.term(term)
+}
+
+/// Encode the canonical form of a term (see `crate::canonical`)
+///
+/// Equal terms give the same bytes on every device, whatever order their
+/// maps were built in, so the output can be hashed or signed.
+pub fn encode_canonical<T: AtomTableOps>(term: &TermValue, table: &T) -> EtfResult<Vec<u8>> {
+    let mut out = Vec::new();
+    encode_canonical_into(term, table, &mut out)?;
+    Ok(out)
+}
+
+/// Encode the canonical form of a term, appending to an existing buffer
+pub fn encode_canonical_into<T: AtomTableOps>(
+    term: &TermValue,
+    table: &T,
+    out: &mut Vec<u8>,
+) -> EtfResult<()> {
+    if canonical::is_canonical(term, table) {
+        encode_into(term, table, out)
+    } else {
+        encode_into(&canonical::canonicalize(term.clone(), table), table, out)
+    }
+}
+
+/// A list encoded one element at a time
+///
+/// Events produced at a high rate are appended as they arrive and sent as
+/// one `[E1, E2, ...]` message. The buffer keeps its capacity across
+/// `clear`, so a batch loop stops allocating once it reached its size.
+#[derive(Debug, Clone)]
+pub struct ListEncoder {
+    out: Vec<u8>,
+    count: u32,
+}
+
+impl Default for ListEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ListEncoder {
+    /// Bytes of the version byte, list tag and length
+    const HEADER_LEN: usize = 6;
+
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Reserve room for `bytes` bytes of encoded elements up front
+    pub fn with_capacity(bytes: usize) -> Self {
+        let mut out = Vec::with_capacity(Self::HEADER_LEN + bytes + 1);
+        out.extend_from_slice(&[VERSION, LIST_EXT, 0, 0, 0, 0]);
+        Self { out, count: 0 }
+    }
+
+    /// Append an element; on error the list is left as it was
+    pub fn push<T: AtomTableOps>(&mut self, element: &TermValue, table: &T) -> EtfResult<()> {
+        let mark = self.out.len();
+        if let Err(error) = (Encoder { table, out: &mut self.out }).term(element) {
+            self.out.truncate(mark);
+            return Err(error);
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Number of elements
+    pub fn len(&self) -> usize {
+        self.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Bytes of the encoded elements so far
+    pub fn encoded_len(&self) -> usize {
+        self.out.len() - Self::HEADER_LEN
+    }
+
+    /// The complete encoded list, including the version byte
+    ///
+    /// Call `clear` before pushing more elements.
+    pub fn finish(&mut self) -> &[u8] {
+        if self.count == 0 {
+            self.out.truncate(1);
+            self.out.push(NIL_EXT);
+        } else {
+            self.out[2..Self::HEADER_LEN].copy_from_slice(&self.count.to_be_bytes());
+            self.out.push(NIL_EXT);
+        }
+        &self.out
+    }
+
+    /// Drop all elements, keeping the buffer
+    pub fn clear(&mut self) {
+        self.out.truncate(1);
+        self.out.extend_from_slice(&[LIST_EXT, 0, 0, 0, 0]);
+        self.count = 0;
+    }
+}
+
+struct Encoder<'a, T: AtomTableOps> {
+    table: &'a T,
+    out: &'a mut Vec<u8>,
+}
+
+impl<T: AtomTableOps> Encoder<'_, T> {
+    fn term(&mut self, term: &TermValue) -> EtfResult<()> {
+        match term {
+            TermValue::SmallInt(i) => {
+                if (0..=255).contains(i) {
+                    self.out.push(SMALL_INTEGER_EXT);
+                    self.out.push(*i as u8);
+                } else {
+                    self.out.push(INTEGER_EXT);
+                    self.out.extend_from_slice(&i.to_be_bytes());
+                }
+            }
+            TermValue::Integer(i) => {
+                if let Ok(small) = i32::try_from(*i) {
+                    return self.term(&TermValue::SmallInt(small));
+                }
+                let digits = i.unsigned_abs().to_le_bytes();
+                let len = digits.iter().rposition(|&digit| digit != 0).map_or(0, |last| last + 1);
+                self.out.push(SMALL_BIG_EXT);
+                self.out.push(len as u8);
+                self.out.push(u8::from(*i < 0));
+                self.out.extend_from_slice(&digits[..len]);
+            }
+            TermValue::Atom(index) => {
+                let name = self.table.get_atom_string(*index)?;
+                self.atom_bytes(name.as_bytes())?;
+            }
+            TermValue::Nil => self.out.push(NIL_EXT),
+            TermValue::Pid(ProcessId(id)) => {
+                self.out.push(NEW_PID_EXT);
+                self.atom_bytes(LOCAL_NODE.as_bytes())?;
+                self.u32(*id);
+                self.u32(0); // serial
+                self.u32(0); // creation
+            }
+            TermValue::Port(PortId(id)) => {
+                self.out.push(NEW_PORT_EXT);
+                self.atom_bytes(LOCAL_NODE.as_bytes())?;
+                self.u32(*id);
+                self.u32(0); // creation
+            }
+            TermValue::Reference(RefId(id)) => {
+                self.out.push(NEWER_REFERENCE_EXT);
+                self.out.extend_from_slice(&2u16.to_be_bytes());
+                self.atom_bytes(LOCAL_NODE.as_bytes())?;
+                self.u32(0); // creation
+                self.u32(*id as u32);
+                self.u32((*id >> 32) as u32);
+            }
+            TermValue::Tuple(elements) => {
+                if elements.len() <= u8::MAX as usize {
+                    self.out.push(SMALL_TUPLE_EXT);
+                    self.out.push(elements.len() as u8);
+                } else {
+                    self.out.push(LARGE_TUPLE_EXT);
+                    self.u32(elements.len() as u32);
+                }
+                for element in elements {
+                    self.term(element)?;
+                }
+            }
+            TermValue::List(_, _) => self.list(term)?,
+            TermValue::Map(pairs) => {
+                self.out.push(MAP_EXT);
+                self.u32(pairs.len() as u32);
+                for (key, value) in pairs {
+                    self.term(key)?;
+                    self.term(value)?;
+                }
+            }
+            TermValue::Binary(data) => {
+                self.out.push(BINARY_EXT);
+                self.u32(data.len() as u32);
+                self.out.extend_from_slice(data);
+            }
+            TermValue::Function(fun) => {
+                self.out.push(EXPORT_EXT);
+                let module = self.table.get_atom_string(fun.module)?;
+                self.atom_bytes(module.as_bytes())?;
+                let function = self.table.get_atom_string(fun.function)?;
+                self.atom_bytes(function.as_bytes())?;
+                self.out.push(SMALL_INTEGER_EXT);
+                self.out.push(fun.arity);
+            }
+            TermValue::Float(value) => {
+                self.out.push(NEW_FLOAT_EXT);
+                self.out.extend_from_slice(&value.to_bits().to_be_bytes());
+            }
+            TermValue::Resource(_) => return Err(EtfError::Unsupported("resource")),
+            TermValue::Invalid(_) => return Err(EtfError::Unsupported("invalid term")),
+        }
+        Ok(())
+    }
+
+    /// Encode a cons chain as a single LIST_EXT, walking the spine iteratively
+    fn list(&mut self, list: &TermValue) -> EtfResult<()> {
+        let mut heads = Vec::new();
+        let mut current = list;
+        while let TermValue::List(head, tail) = current {
+            heads.push(head.as_ref());
+            current = tail;
+        }
+
+        self.out.push(LIST_EXT);
+        self.u32(heads.len() as u32);
+        for head in heads {
+            self.term(head)?;
+        }
+        self.term(current)
+    }
+
+    fn atom_bytes(&mut self, name: &[u8]) -> EtfResult<()> {
+        if name.len() <= u8::MAX as usize {
+            self.out.push(SMALL_ATOM_UTF8_EXT);
+            self.out.push(name.len() as u8);
+        } else if name.len() <= u16::MAX as usize {
+            self.out.push(ATOM_UTF8_EXT);
+            self.out.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        } else {
+            return Err(EtfError::Atom(AtomError::InvalidLength));
+        }
+        self.out.extend_from_slice(name);
+        Ok(())
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.out.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+// ── Decoding ────────────────────────────────────────────────────────────────
+
+/// Decode a complete ETF blob, including the leading version byte
+///
+/// Fails if any bytes remain after the term.
+pub fn decode<T: AtomTableOps>(bytes: &[u8], table: &T) -> EtfResult<TermValue> {
+    let (term, used) = decode_prefix(bytes, table)?;
+    if used != bytes.len() {
+        return Err(EtfError::TrailingBytes(bytes.len() - used));
+    }
+    Ok(term)
+}
+
+/// Decode one term from the start of `bytes`, returning it and the bytes consumed
+pub fn decode_prefix<T: AtomTableOps>(bytes: &[u8], table: &T) -> EtfResult<(TermValue, usize)> {
+    let mut decoder = Decoder { table, bytes, pos: 0 };
+    let version = decoder.u8()?;
+    if version != VERSION {
+        return Err(EtfError::BadVersion(version));
+    }
+    let term = decoder.term(0)?;
+    Ok((term, decoder.pos))
+}
+
+struct Decoder<'a, T: AtomTableOps> {
+    table: &'a T,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a, T: AtomTableOps> Decoder<'a, T> {
+    fn term(&mut self, depth: usize) -> EtfResult<TermValue> {
+        if depth > MAX_DEPTH {
+            return Err(EtfError::TooDeep);
+        }
+
+        let tag = self.u8()?;
+        match tag {
+            SMALL_INTEGER_EXT => Ok(TermValue::SmallInt(self.u8()? as i32)),
+            INTEGER_EXT => Ok(TermValue::SmallInt(self.u32()? as i32)),
+            SMALL_BIG_EXT => self.small_big(),
+            NEW_FLOAT_EXT => {
+                let bits = u64::from_be_bytes(self.array::<8>()?);
+                Ok(TermValue::Float(f64::from_bits(bits)))
+            }
+            ATOM_EXT | ATOM_UTF8_EXT | SMALL_ATOM_EXT | SMALL_ATOM_UTF8_EXT => {
+                let name = self.atom_name(tag)?;
+                Ok(TermValue::Atom(self.table.ensure_atom(name)?))
+            }
+            NIL_EXT => Ok(TermValue::Nil),
+            STRING_EXT => {
+                let len = self.u16()? as usize;
+                let chars = self.take(len)?;
+                Ok(chars.iter().rev().fold(TermValue::Nil, |tail, &c| {
+                    TermValue::List(Box::new(TermValue::SmallInt(c as i32)), Box::new(tail))
+                }))
+            }
+            LIST_EXT => {
+                let len = self.count()?;
+                let mut heads = Vec::with_capacity(len);
+                for _ in 0..len {
+                    heads.push(self.term(depth + 1)?);
+                }
+                let tail = self.term(depth + 1)?;
+                Ok(heads.into_iter().rev().fold(tail, |acc, head| {
+                    TermValue::List(Box::new(head), Box::new(acc))
+                }))
+            }
+            SMALL_TUPLE_EXT | LARGE_TUPLE_EXT => {
+                let len = if tag == SMALL_TUPLE_EXT {
+                    self.u8()? as usize
+                } else {
+                    self.count()?
+                };
+                let mut elements = Vec::with_capacity(len);
+                for _ in 0..len {
+                    elements.push(self.term(depth + 1)?);
+                }
+                Ok(TermValue::Tuple(elements))
+            }
+            MAP_EXT => {
+                let len = self.count()?;
+                let mut pairs = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let key = self.term(depth + 1)?;
+                    let value = self.term(depth + 1)?;
+                    pairs.push((key, value));
+                }
+                Ok(TermValue::Map(pairs))
+            }
+            BINARY_EXT => {
+                let len = self.u32()? as usize;
+                Ok(TermValue::Binary(self.take(len)?.to_vec()))
+            }
+            NEW_PID_EXT | PID_EXT => {
+                self.node()?;
+                let id = self.u32()?;
+                self.u32()?; // serial
+                if tag == NEW_PID_EXT {
+                    self.u32()?;
+                } else {
+                    self.u8()?;
+                }
+                Ok(TermValue::Pid(ProcessId(id)))
+            }
+            NEW_PORT_EXT => {
+                self.node()?;
+                let id = self.u32()?;
+                self.u32()?; // creation
+                Ok(TermValue::Port(PortId(id)))
+            }
+            V4_PORT_EXT => {
+                self.node()?;
+                let id = u64::from_be_bytes(self.array::<8>()?);
+                self.u32()?; // creation
+                let id = u32::try_from(id).map_err(|_| EtfError::IntegerOverflow)?;
+                Ok(TermValue::Port(PortId(id)))
+            }
+            NEWER_REFERENCE_EXT => {
+                let words = self.u16()? as usize;
+                self.node()?;
+                self.u32()?; // creation
+                let mut id = 0u64;
+                for i in 0..words {
+                    let word = self.u32()? as u64;
+                    if i < 2 {
+                        id |= word << (32 * i);
+                    }
+                }
+                Ok(TermValue::Reference(RefId(id)))
+            }
+            EXPORT_EXT => {
+                let module = self.atom_index()?;
+                let function = self.atom_index()?;
+                let arity = match self.term(depth + 1)? {
+                    TermValue::SmallInt(a) => u8::try_from(a).map_err(|_| EtfError::IntegerOverflow)?,
+                    _ => return Err(EtfError::UnknownTag(EXPORT_EXT)),
+                };
+                Ok(TermValue::Function(FunctionRef { module, function, arity }))
+            }
+            other => Err(EtfError::UnknownTag(other)),
+        }
+    }
+
+    /// Bignums are accepted only when the value fits in an i64
+    fn small_big(&mut self) -> EtfResult<TermValue> {
+        let len = self.u8()? as usize;
+        let sign = self.u8()?;
+        let digits = self.take(len)?;
+
+        let mut magnitude: u64 = 0;
+        for (i, &digit) in digits.iter().enumerate() {
+            if digit == 0 {
+                continue;
+            }
+            if i >= 8 {
+                return Err(EtfError::IntegerOverflow);
+            }
+            magnitude |= (digit as u64) << (8 * i);
+        }
+
+        let value = if sign == 0 {
+            i64::try_from(magnitude).map_err(|_| EtfError::IntegerOverflow)?
+        } else if magnitude <= i64::MIN.unsigned_abs() {
+            0i64.wrapping_sub_unsigned(magnitude)
+        } else {
+            return Err(EtfError::IntegerOverflow);
+        };
+        Ok(TermValue::integer(value))
+    }
+
+    fn atom_name(&mut self, tag: u8) -> EtfResult<&'a [u8]> {
+        let len = match tag {
+            SMALL_ATOM_EXT | SMALL_ATOM_UTF8_EXT => self.u8()? as usize,
+            ATOM_EXT | ATOM_UTF8_EXT => self.u16()? as usize,
+            other => return Err(EtfError::UnknownTag(other)),
+        };
+        self.take(len)
+    }
+
+    fn atom_index(&mut self) -> EtfResult<crate::atom::AtomIndex> {
+        let tag = self.u8()?;
+        let name = self.atom_name(tag)?;
+        Ok(self.table.ensure_atom(name)?)
+    }
+
+    /// Skip the node atom of a pid/port/reference
+    fn node(&mut self) -> EtfResult<()> {
+        let tag = self.u8()?;
+        self.atom_name(tag).map(|_| ())
+    }
+
+    /// Read an element count, rejecting counts the remaining input cannot hold
+    fn count(&mut self) -> EtfResult<usize> {
+        let len = self.u32()? as usize;
+        if len > self.bytes.len() - self.pos {
+            return Err(EtfError::UnexpectedEnd);
+        }
+        Ok(len)
+    }
+
+    fn take(&mut self, len: usize) -> EtfResult<&'a [u8]> {
+        let end = self.pos.checked_add(len).ok_or(EtfError::UnexpectedEnd)?;
+        let slice = self.bytes.get(self.pos..end).ok_or(EtfError::UnexpectedEnd)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> EtfResult<[u8; N]> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> EtfResult<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> EtfResult<u16> {
+        Ok(u16::from_be_bytes(self.array::<2>()?))
+    }
+
+    fn u32(&mut self) -> EtfResult<u32> {
+        Ok(u32::from_be_bytes(self.array::<4>()?))
+    }
+}
@@ -0,0 +1,379 @@
+//! Framebuffers for display drivers
+//!
+//! A 320x240 RGB565 frame is 150 KiB, far too much to build as a binary
+//! on a process heap every frame. `Framebuffer<D>` keeps the pixels in a
+//! resource instead: Erlang holds it as an opaque resource term, NIFs draw
+//! into it (`blit` copies straight from a binary argument, see
+//! `binary::BinarySlice`) and `flush` sends the changed area to the panel
+//! through the driver's `Display` implementation.
+//!
+//! The framebuffer remembers the smallest rectangle covering every change
+//! since the last flush, so `flush` only transfers that.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::framebuffer::{Display, Framebuffer, FrameRegion, PixelFormat, Rect};
+//!
+//! struct Ili9341 { spi: Spi }
+//!
+//! impl Display for Ili9341 {
+//!     fn flush(&mut self, region: FrameRegion<'_>) -> NifResult<()> {
+//!         self.set_window(region.area())?;
+//!         for row in region.rows() {
+//!             self.spi.write(row)?;
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! impl_framebuffer!(LCD_FRAMEBUFFER, Ili9341);
+//!
+//! // display:open/0
+//! let fb = ResourceArc::new(Framebuffer::new(Ili9341::new()?, 320, 240, PixelFormat::Rgb565)?)?;
+//! fb.make_term(env)
+//!
+//! // display:blit(Fb, X, Y, W, H, Pixels)
+//! let pixels = BinarySlice::from_term(args[5], ctx)?;
+//! fb.blit(Rect::new(x, y, w, h), pixels.as_bytes())?;
+//!
+//! // display:flush(Fb)
+//! fb.flush()?;
+//! ```
+
+extern crate alloc;
+
+use crate::resource::{ErlNifResourceType, Resource};
+use crate::sync::SpinLock;
+use crate::term::{NifError, NifResult};
+use alloc::vec::Vec;
+
+/// Layout of one pixel in the buffer
+///
+/// Multi-byte pixels are stored big-endian, the order SPI panels expect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    Gray8,
+    Rgb565,
+    Rgb888,
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Gray8 => 1,
+            PixelFormat::Rgb565 => 2,
+            PixelFormat::Rgb888 => 3,
+        }
+    }
+
+    /// Write `color`, a pixel value in this format, to `out`
+    pub fn encode(self, color: u32, out: &mut [u8]) {
+        let bytes = color.to_be_bytes();
+        out.copy_from_slice(&bytes[4 - self.bytes_per_pixel()..]);
+    }
+}
+
+/// A rectangle of pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    pub const fn new(x: u16, y: u16, width: u16, height: u16) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// The smallest rectangle covering both
+    pub fn union(&self, other: &Rect) -> Rect {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+        Rect::new(x, y, right - x, bottom - y)
+    }
+
+    /// Whether the rectangle lies within a `width` x `height` frame
+    pub fn fits(&self, width: u16, height: u16) -> bool {
+        self.x as u32 + self.width as u32 <= width as u32 && self.y as u32 + self.height as u32 <= height as u32
+    }
+}
+
+/// The pixels of one area of a framebuffer, handed to `Display::flush`
+#[derive(Debug, Clone, Copy)]
+pub struct FrameRegion<'a> {
+    area: Rect,
+    format: PixelFormat,
+    stride: usize,
+    pixels: &'a [u8],
+}
+
+impl<'a> FrameRegion<'a> {
+    pub fn area(&self) -> Rect {
+        self.area
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// The pixels of each row of the area, top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &'a [u8]> + 'a {
+        let pixels = self.pixels;
+        let stride = self.stride;
+        let row_len = self.area.width as usize * self.format.bytes_per_pixel();
+        (0..self.area.height as usize).map(move |row| &pixels[row * stride..row * stride + row_len])
+    }
+
+    /// All pixels in one slice, when the area spans whole rows
+    pub fn as_contiguous(&self) -> Option<&'a [u8]> {
+        let len = self.area.height as usize * self.stride;
+        let row_len = self.area.width as usize * self.format.bytes_per_pixel();
+        (row_len == self.stride).then(|| &self.pixels[..len])
+    }
+}
+
+/// A panel a `Framebuffer` can be flushed to
+pub trait Display: Send + 'static {
+    /// Transfer `region` to the same area of the panel
+    fn flush(&mut self, region: FrameRegion<'_>) -> NifResult<()>;
+}
+
+/// Resource type of `Framebuffer<Self>`, implemented by `impl_framebuffer!`
+pub trait FramebufferType {
+    fn resource_type() -> *mut ErlNifResourceType;
+}
+
+struct Frame<D> {
+    display: D,
+    pixels: Vec<u8>,
+    dirty: Option<Rect>,
+}
+
+/// A pixel buffer resource bound to a display
+pub struct Framebuffer<D: Display> {
+    width: u16,
+    height: u16,
+    format: PixelFormat,
+    frame: SpinLock<Frame<D>>,
+}
+
+impl<D: Display> Framebuffer<D> {
+    /// Allocate a blank (all zero) `width` x `height` buffer for `display`
+    ///
+    /// Fails with `OutOfMemory` instead of aborting when the pixels do not fit.
+    pub fn new(display: D, width: u16, height: u16, format: PixelFormat) -> NifResult<Self> {
+        let len = width as usize * height as usize * format.bytes_per_pixel();
+        let mut pixels = Vec::new();
+        pixels.try_reserve_exact(len).map_err(|_| NifError::OutOfMemory)?;
+        pixels.resize(len, 0);
+        Ok(Self {
+            width,
+            height,
+            format,
+            frame: SpinLock::new(Frame {
+                display,
+                pixels,
+                dirty: None,
+            }),
+        })
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// Bytes per row of the buffer
+    pub fn stride(&self) -> usize {
+        self.width as usize * self.format.bytes_per_pixel()
+    }
+
+    /// The area changed since the last flush
+    pub fn dirty(&self) -> Option<Rect> {
+        self.frame.with(|frame| frame.dirty)
+    }
+
+    /// Mark `area` as changed, so the next `flush` sends it
+    pub fn invalidate(&self, area: Rect) -> NifResult<()> {
+        self.check(area)?;
+        self.frame.with(|frame| Self::mark(frame, area));
+        Ok(())
+    }
+
+    /// Copy packed rows of pixels into `area`
+    ///
+    /// `pixels` must hold exactly `area.width * area.height` pixels.
+    pub fn blit(&self, area: Rect, pixels: &[u8]) -> NifResult<()> {
+        self.check(area)?;
+        let row_len = area.width as usize * self.format.bytes_per_pixel();
+        if pixels.len() != row_len * area.height as usize {
+            return Err(NifError::BadArg);
+        }
+        if area.is_empty() {
+            return Ok(());
+        }
+        self.frame.with(|frame| {
+            for (row, source) in pixels.chunks_exact(row_len).enumerate() {
+                let start = self.offset(area.x, area.y + row as u16);
+                frame.pixels[start..start + row_len].copy_from_slice(source);
+            }
+            Self::mark(frame, area);
+        });
+        Ok(())
+    }
+
+    /// Set every pixel of `area` to `color`
+    pub fn fill_rect(&self, area: Rect, color: u32) -> NifResult<()> {
+        self.check(area)?;
+        if area.is_empty() {
+            return Ok(());
+        }
+        let bytes_per_pixel = self.format.bytes_per_pixel();
+        self.frame.with(|frame| {
+            for row in area.y..area.y + area.height {
+                let start = self.offset(area.x, row);
+                let end = start + area.width as usize * bytes_per_pixel;
+                for pixel in frame.pixels[start..end].chunks_exact_mut(bytes_per_pixel) {
+                    self.format.encode(color, pixel);
+                }
+            }
+            Self::mark(frame, area);
+        });
+        Ok(())
+    }
+
+    /// Set the whole buffer to `color`
+    pub fn fill(&self, color: u32) {
+        let _ = self.fill_rect(self.bounds(), color);
+    }
+
+    pub fn set_pixel(&self, x: u16, y: u16, color: u32) -> NifResult<()> {
+        self.fill_rect(Rect::new(x, y, 1, 1), color)
+    }
+
+    /// Draw directly into the pixels; the whole buffer is marked changed
+    pub fn with_pixels<R>(&self, f: impl FnOnce(&mut [u8]) -> R) -> R {
+        self.frame.with(|frame| {
+            let result = f(&mut frame.pixels);
+            Self::mark(frame, self.bounds());
+            result
+        })
+    }
+
+    /// Run `f` on the display, for commands other than pixel transfers
+    pub fn with_display<R>(&self, f: impl FnOnce(&mut D) -> R) -> R {
+        self.frame.with(|frame| f(&mut frame.display))
+    }
+
+    /// Send the changed area to the display
+    ///
+    /// Returns false when nothing changed. On a display error the area
+    /// stays marked, so the next flush tries again.
+    pub fn flush(&self) -> NifResult<bool> {
+        self.frame.with(|frame| {
+            let Some(area) = frame.dirty else {
+                return Ok(false);
+            };
+            self.flush_area(frame, area)?;
+            frame.dirty = None;
+            Ok(true)
+        })
+    }
+
+    /// Send the whole buffer to the display, changed or not
+    pub fn flush_all(&self) -> NifResult<()> {
+        self.frame.with(|frame| {
+            self.flush_area(frame, self.bounds())?;
+            frame.dirty = None;
+            Ok(())
+        })
+    }
+
+    fn flush_area(&self, frame: &mut Frame<D>, area: Rect) -> NifResult<()> {
+        let start = self.offset(area.x, area.y);
+        let region = FrameRegion {
+            area,
+            format: self.format,
+            stride: self.stride(),
+            pixels: &frame.pixels[start..],
+        };
+        frame.display.flush(region)
+    }
+
+    fn bounds(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
+    fn check(&self, area: Rect) -> NifResult<()> {
+        if area.fits(self.width, self.height) {
+            Ok(())
+        } else {
+            Err(NifError::BadArg)
+        }
+    }
+
+    fn offset(&self, x: u16, y: u16) -> usize {
+        y as usize * self.stride() + x as usize * self.format.bytes_per_pixel()
+    }
+
+    fn mark(frame: &mut Frame<D>, area: Rect) {
+        if !area.is_empty() {
+            frame.dirty = Some(frame.dirty.map_or(area, |dirty| dirty.union(&area)));
+        }
+    }
+}
+
+impl<D: Display + FramebufferType> Resource for Framebuffer<D> {
+    fn resource_type() -> *mut ErlNifResourceType {
+        D::resource_type()
+    }
+}
+
+/// Register `Framebuffer<$display>` as a resource type
+///
+/// Generates `init_<name>` like `impl_resource!`; the pixel
+/// buffer and the display are dropped with the last reference.
+///
+/// ```rust,ignore
+/// impl_framebuffer!(LCD_FRAMEBUFFER, Ili9341);
+/// ```
+#[macro_export]
+macro_rules! impl_framebuffer {
+    ($resource_name:ident, $display:ty) => {
+        impl $crate::framebuffer::FramebufferType for $display {
+            fn resource_type() -> *mut $crate::resource::ErlNifResourceType {
+                $crate::resource::ResourceType::ptr_of::<$crate::framebuffer::Framebuffer<$display>>()
+            }
+        }
+
+        $crate::resource_type!(
+            @init $resource_name,
+            $crate::framebuffer::Framebuffer<$display>,
+            $crate::resource::resource_type_init_with_dtor(
+                $crate::resource::resource_dtor::<$crate::framebuffer::Framebuffer<$display>>
+            )
+        );
+    };
+}
@@ -0,0 +1,270 @@
+//! Erlang-compatible term hashing
+//!
+//! `phash2` computes the same value as `erlang:phash2/1,2`, so a NIF can
+//! pick the bucket or shard for a key exactly as Erlang code does. The
+//! algorithm is OTP's `make_hash2`: Bob Jenkins' lookup2 mix over the
+//! term's parts, with atoms hashed by name (hashpjw) rather than by atom
+//! table index, and map pairs combined with xor so pair order does not
+//! matter.
+//!
+//! Pids, ports and references are only meaningful on the node that made
+//! them; they hash by their local number, like OTP does for local ones.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use avmnif_rs::hash::phash2_range;
+//! # use avmnif_rs::term::TermValue;
+//! # let table = avmnif_rs::testing::mocks::MockAtomTable::new();
+//! # let key = TermValue::atom("a", &table);
+//!
+//! // Same shard as erlang:phash2(Key, 16)
+//! let shard = phash2_range(&key, 16, &table)?;
+//! # assert_eq!(shard, 97 % 16);
+//! # }
+//! # Ok::<(), avmnif_rs::term::NifError>(())
+//! ```
+
+use crate::atom::{AtomIndex, AtomTableOps};
+use crate::term::{NifError, NifResult, Term, TermValue};
+
+/// The golden ratio; an arbitrary value
+const HCONST: u32 = 0x9e37_79b9;
+
+/// `HCONST * n`, the per-type constants of `make_hash2`
+const fn hconst(n: u32) -> u32 {
+    HCONST.wrapping_mul(n)
+}
+
+/// `make_hash2` of `[]` when it is the first thing hashed
+const NIL_HASH: u32 = 3_468_870_702;
+
+/// The raw `[]` immediate OTP mixes in for a non-leading `[]`
+const NIL_DEF: u32 = 0x3B;
+
+/// Upper bound (exclusive) of `erlang:phash2/1`
+pub const PHASH2_RANGE: u32 = 1 << 27;
+
+/// Bob Jenkins' lookup2 mix
+fn mix(mut a: u32, mut b: u32, mut c: u32) -> (u32, u32, u32) {
+    a = a.wrapping_sub(b).wrapping_sub(c) ^ (c >> 13);
+    b = b.wrapping_sub(c).wrapping_sub(a) ^ (a << 8);
+    c = c.wrapping_sub(a).wrapping_sub(b) ^ (b >> 13);
+    a = a.wrapping_sub(b).wrapping_sub(c) ^ (c >> 12);
+    b = b.wrapping_sub(c).wrapping_sub(a) ^ (a << 16);
+    c = c.wrapping_sub(a).wrapping_sub(b) ^ (b >> 5);
+    a = a.wrapping_sub(b).wrapping_sub(c) ^ (c >> 3);
+    b = b.wrapping_sub(c).wrapping_sub(a) ^ (a << 10);
+    c = c.wrapping_sub(a).wrapping_sub(b) ^ (b >> 15);
+    (a, b, c)
+}
+
+/// The atom table's hash of an atom name (hashpjw)
+///
+/// Two-byte UTF-8 sequences for Latin-1 characters are hashed as the
+/// Latin-1 byte, as OTP does to keep hashes from before UTF-8 atoms.
+pub fn atom_hash(name: &[u8]) -> u32 {
+    let mut hash: u32 = 0;
+    let mut i = 0;
+    while i < name.len() {
+        let mut byte = name[i];
+        i += 1;
+        if i < name.len() && (byte & 0xFE) == 0xC2 && (name[i] & 0xC0) == 0x80 {
+            byte = (byte << 6) | (name[i] & 0x3F);
+            i += 1;
+        }
+        hash = (hash << 4).wrapping_add(byte as u32);
+        let high = hash & 0xF000_0000;
+        if high != 0 {
+            hash ^= high >> 24;
+            hash ^= high;
+        }
+    }
+    hash
+}
+
+/// lookup2 over a byte block, seeded with `initval`
+fn block_hash(bytes: &[u8], initval: u32) -> u32 {
+    let word = |k: &[u8]| u32::from_le_bytes([k[0], k[1], k[2], k[3]]);
+    let (mut a, mut b, mut c) = (HCONST, HCONST, initval);
+    let mut blocks = bytes.chunks_exact(12);
+    for k in blocks.by_ref() {
+        a = a.wrapping_add(word(&k[0..4]));
+        b = b.wrapping_add(word(&k[4..8]));
+        c = c.wrapping_add(word(&k[8..12]));
+        (a, b, c) = mix(a, b, c);
+    }
+    // The first byte of c is reserved for the length
+    c = c.wrapping_add(bytes.len() as u32);
+    for (i, &byte) in blocks.remainder().iter().enumerate() {
+        let byte = byte as u32;
+        match i {
+            0..=3 => a = a.wrapping_add(byte << (8 * i)),
+            4..=7 => b = b.wrapping_add(byte << (8 * (i - 4))),
+            _ => c = c.wrapping_add(byte << (8 * (i - 7))),
+        }
+    }
+    mix(a, b, c).2
+}
+
+/// Running state of `make_hash2`: every part of the term mixes into `hash`
+struct Hasher<'t, T: AtomTableOps> {
+    hash: u32,
+    table: &'t T,
+}
+
+impl<T: AtomTableOps> Hasher<'_, T> {
+    fn uint32_2(&mut self, first: u32, second: u32, constant: u32) {
+        self.hash = mix(constant.wrapping_add(first), constant.wrapping_add(second), self.hash).2;
+    }
+
+    fn uint32(&mut self, value: u32, constant: u32) {
+        self.uint32_2(value, 0, constant);
+    }
+
+    fn atom_value(&self, index: AtomIndex) -> NifResult<u32> {
+        let name = self.table.get_atom_string(index).map_err(|_| NifError::BadArg)?;
+        Ok(atom_hash(name.as_bytes()))
+    }
+
+    fn integer(&mut self, value: i64) {
+        // Beyond 28 bits OTP hashes the integer as a bignum
+        if (-(1 << 27)..1 << 27).contains(&value) {
+            if value < 0 {
+                // Negative numbers are mixed twice, as in OTP
+                self.uint32(value.wrapping_neg() as u32, HCONST);
+            }
+            self.uint32(value as u32, HCONST);
+        } else {
+            // One 64-bit digit, mixed as its low and high halves
+            let constant = if value < 0 { hconst(10) } else { hconst(11) };
+            let magnitude = value.unsigned_abs();
+            self.uint32_2(magnitude as u32, (magnitude >> 32) as u32, constant);
+        }
+    }
+
+    fn term(&mut self, value: &TermValue) -> NifResult<()> {
+        match value {
+            TermValue::SmallInt(value) => self.integer(*value as i64),
+            TermValue::Integer(value) => self.integer(*value),
+            TermValue::Atom(index) => {
+                let atom = self.atom_value(*index)?;
+                if self.hash == 0 {
+                    self.hash = atom;
+                } else {
+                    self.uint32(atom, hconst(3));
+                }
+            }
+            TermValue::Nil => {
+                if self.hash == 0 {
+                    self.hash = NIL_HASH;
+                } else {
+                    self.uint32(NIL_DEF, hconst(2));
+                }
+            }
+            TermValue::Pid(pid) => self.uint32(pid.0, hconst(5)),
+            TermValue::Port(port) => self.uint32(port.0, hconst(6)),
+            TermValue::Reference(reference) => self.uint32(reference.0 as u32, hconst(7)),
+            // Resources are references in AtomVM
+            TermValue::Resource(resource) => self.uint32(resource.ptr as usize as u32, hconst(7)),
+            TermValue::Float(value) => {
+                let bits = if *value == 0.0 { 0 } else { value.to_bits() };
+                self.uint32_2((bits >> 32) as u32, bits as u32, hconst(12));
+            }
+            TermValue::Binary(bytes) => {
+                let constant = hconst(13).wrapping_add(self.hash);
+                self.hash = if bytes.is_empty() { constant } else { block_hash(bytes, constant) };
+            }
+            TermValue::Function(fun) => {
+                let module = self.atom_value(fun.module)?;
+                let function = self.atom_value(fun.function)?;
+                self.uint32_2(fun.arity as u32, module, HCONST);
+                self.uint32(function, hconst(14));
+            }
+            TermValue::Tuple(elements) => {
+                self.uint32(elements.len() as u32, hconst(9));
+                for element in elements {
+                    self.term(element)?;
+                }
+            }
+            TermValue::Map(pairs) => {
+                self.uint32(pairs.len() as u32, hconst(16));
+                if !pairs.is_empty() {
+                    // Each pair hashes on its own; xor makes the order irrelevant
+                    let outer = self.hash;
+                    let mut pairs_xor = 0;
+                    for (key, value) in pairs {
+                        self.hash = 0;
+                        self.term(key)?;
+                        self.term(value)?;
+                        pairs_xor ^= self.hash;
+                    }
+                    self.hash = outer;
+                    self.uint32(pairs_xor, hconst(19));
+                }
+            }
+            TermValue::List(_, _) => self.list(value)?,
+            TermValue::Invalid(_) => return Err(NifError::BadArg),
+        }
+        Ok(())
+    }
+
+    /// Walk a list's spine iteratively; runs of bytes are packed four at a time
+    fn list(&mut self, mut value: &TermValue) -> NifResult<()> {
+        while let TermValue::List(_, _) = value {
+            let (mut packed, mut count) = (0u32, 0);
+            while let TermValue::List(head, tail) = value {
+                match **head {
+                    TermValue::SmallInt(byte @ 0..=255) => {
+                        packed = (packed << 8) + byte as u32;
+                        count += 1;
+                        if count == 4 {
+                            self.uint32(packed, hconst(4));
+                            (packed, count) = (0, 0);
+                        }
+                        value = tail;
+                    }
+                    _ => break,
+                }
+            }
+            if count > 0 {
+                self.uint32(packed, hconst(4));
+            }
+            if let TermValue::List(head, tail) = value {
+                self.term(head)?;
+                value = tail;
+            }
+        }
+        self.term(value)
+    }
+}
+
+/// The full 32-bit `make_hash2` value of a term
+///
+/// Fails with `BadArg` for atoms missing from `table` and invalid terms.
+pub fn hash2<T: AtomTableOps>(value: &TermValue, table: &T) -> NifResult<u32> {
+    let mut hasher = Hasher { hash: 0, table };
+    hasher.term(value)?;
+    Ok(hasher.hash)
+}
+
+/// `erlang:phash2/1`: a hash in `0..2^27`
+pub fn phash2<T: AtomTableOps>(value: &TermValue, table: &T) -> NifResult<u32> {
+    Ok(hash2(value, table)? & (PHASH2_RANGE - 1))
+}
+
+/// `erlang:phash2/2`: a hash in `0..range`
+///
+/// Fails with `BadArg` for a zero range.
+pub fn phash2_range<T: AtomTableOps>(value: &TermValue, range: u32, table: &T) -> NifResult<u32> {
+    if range == 0 {
+        return Err(NifError::BadArg);
+    }
+    Ok(hash2(value, table)? % range)
+}
+
+/// `erlang:phash2/1` of a raw term
+pub fn phash2_term<T: AtomTableOps>(term: Term<'_>, table: &T) -> NifResult<u32> {
+    phash2(&term.to_value()?, table)
+}
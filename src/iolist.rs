@@ -0,0 +1,217 @@
+//! iolist support
+//!
+//! Erlang I/O code passes data as iolists: arbitrarily nested lists of
+//! binaries and byte integers, possibly ending in a binary tail. `IoList`
+//! holds such data as a flat sequence of chunks. Chunks parsed from a term
+//! borrow the term's binaries, so nothing is copied until the data is
+//! flattened or written out.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use avmnif_rs::iolist::IoList;
+//! # use avmnif_rs::parse::parse_term;
+//! # let table = avmnif_rs::testing::mocks::MockAtomTable::new();
+//! # let args = [parse_term("[<<\"AT\">>, $+, [<<\"RST\">>], $\\r]", &table).unwrap()];
+//! # let mut uart = Vec::new();
+//! # let payload = [1, 2];
+//!
+//! // Accepting: write each chunk straight to the UART
+//! let data = IoList::from_term(&args[0])?;
+//! data.write_to(|chunk| uart.extend_from_slice(chunk));
+//!
+//! // Returning: build a reply without concatenating first
+//! let mut reply = IoList::new();
+//! reply.push_slice(b"HDR");
+//! reply.push_byte(payload.len() as u8);
+//! reply.push_slice(&payload);
+//! let reply = reply.to_term();
+//! # assert_eq!(uart, b"AT+RST\r");
+//! # assert_eq!(IoList::from_term(&reply)?.to_vec(), b"HDR\x02\x01\x02");
+//! # }
+//! # Ok::<(), avmnif_rs::term::NifError>(())
+//! ```
+
+extern crate alloc;
+
+use crate::term::{ListIter, NifError, NifResult, TermValue};
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
+/// Flatten iodata into one byte slice
+///
+/// A binary, or iodata holding a single binary, is borrowed as is; only
+/// data spread over several chunks is concatenated.
+pub fn flatten(term: &TermValue) -> NifResult<Cow<'_, [u8]>> {
+    match term {
+        TermValue::Binary(bytes) => Ok(Cow::Borrowed(bytes)),
+        _ => IoList::from_term(term).map(IoList::into_bytes),
+    }
+}
+
+/// One contiguous piece of an iolist
+#[derive(Debug, Clone, PartialEq)]
+pub enum IoChunk<'a> {
+    /// Bytes borrowed from a term or caller buffer
+    Borrowed(&'a [u8]),
+    /// Bytes owned by the iolist (runs of byte integers, pushed vectors)
+    Owned(Vec<u8>),
+}
+
+impl<'a> IoChunk<'a> {
+    /// The chunk's bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            IoChunk::Borrowed(bytes) => bytes,
+            IoChunk::Owned(bytes) => bytes,
+        }
+    }
+}
+
+/// Flattened view of an iolist as a sequence of byte chunks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IoList<'a> {
+    chunks: Vec<IoChunk<'a>>,
+    len: usize,
+}
+
+impl<'a> IoList<'a> {
+    /// Create an empty iolist
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse iodata: a binary, or a possibly nested list of binaries and bytes
+    ///
+    /// Walks the structure without recursion. Fails with `BadArg` on
+    /// anything else, as `iolist_to_binary/1` does.
+    pub fn from_term(term: &'a TermValue) -> NifResult<Self> {
+        let mut iolist = IoList::new();
+        let mut stack: Vec<ListIter<'a>> = Vec::new();
+
+        match term {
+            TermValue::Binary(bytes) => {
+                iolist.push_slice(bytes);
+                return Ok(iolist);
+            }
+            TermValue::List(_, _) | TermValue::Nil => stack.push(term.iter_list()),
+            _ => return Err(NifError::BadArg),
+        }
+
+        while let Some(iter) = stack.last_mut() {
+            match iter.next() {
+                Some(TermValue::SmallInt(byte)) => {
+                    iolist.push_byte(u8::try_from(*byte).map_err(|_| NifError::BadArg)?);
+                }
+                Some(TermValue::Binary(bytes)) => iolist.push_slice(bytes),
+                Some(nested @ (TermValue::List(_, _) | TermValue::Nil)) => stack.push(nested.iter_list()),
+                Some(_) => return Err(NifError::BadArg),
+                None => {
+                    match iter.tail() {
+                        TermValue::Nil => {}
+                        TermValue::Binary(bytes) => iolist.push_slice(bytes),
+                        _ => return Err(NifError::BadArg),
+                    }
+                    stack.pop();
+                }
+            }
+        }
+
+        Ok(iolist)
+    }
+
+    /// Append borrowed bytes
+    pub fn push_slice(&mut self, bytes: &'a [u8]) {
+        if !bytes.is_empty() {
+            self.len += bytes.len();
+            self.chunks.push(IoChunk::Borrowed(bytes));
+        }
+    }
+
+    /// Append owned bytes
+    pub fn push_vec(&mut self, bytes: Vec<u8>) {
+        if !bytes.is_empty() {
+            self.len += bytes.len();
+            self.chunks.push(IoChunk::Owned(bytes));
+        }
+    }
+
+    /// Append a single byte, coalescing runs of bytes into one chunk
+    pub fn push_byte(&mut self, byte: u8) {
+        self.len += 1;
+        match self.chunks.last_mut() {
+            Some(IoChunk::Owned(run)) => run.push(byte),
+            _ => self.chunks.push(IoChunk::Owned(alloc::vec![byte])),
+        }
+    }
+
+    /// Append every chunk of another iolist
+    pub fn append(&mut self, other: IoList<'a>) {
+        self.len += other.len;
+        self.chunks.extend(other.chunks);
+    }
+
+    /// Total number of bytes (`iolist_size/1`)
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if the iolist holds no bytes
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The chunks in order
+    pub fn chunks(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        self.chunks.iter().map(IoChunk::as_bytes)
+    }
+
+    /// Hand every chunk to `write` in order, without concatenating
+    pub fn write_to<F: FnMut(&[u8])>(&self, mut write: F) {
+        for chunk in self.chunks() {
+            write(chunk);
+        }
+    }
+
+    /// Copy the bytes into `out`, returning how many were written
+    ///
+    /// Stops early if `out` is shorter than the iolist.
+    pub fn copy_to_slice(&self, out: &mut [u8]) -> usize {
+        let mut written = 0;
+        for chunk in self.chunks() {
+            let n = chunk.len().min(out.len() - written);
+            out[written..written + n].copy_from_slice(&chunk[..n]);
+            written += n;
+            if written == out.len() {
+                break;
+            }
+        }
+        written
+    }
+
+    /// Concatenate into one buffer (`iolist_to_binary/1`)
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.len);
+        self.write_to(|chunk| out.extend_from_slice(chunk));
+        out
+    }
+
+    /// The bytes as one slice, borrowed when there is at most one borrowed chunk
+    pub fn into_bytes(mut self) -> Cow<'a, [u8]> {
+        match self.chunks.len() {
+            0 => Cow::Borrowed(&[]),
+            1 => match self.chunks.pop() {
+                Some(IoChunk::Borrowed(bytes)) => Cow::Borrowed(bytes),
+                Some(IoChunk::Owned(bytes)) => Cow::Owned(bytes),
+                None => Cow::Borrowed(&[]),
+            },
+            _ => Cow::Owned(self.to_vec()),
+        }
+    }
+
+    /// Build a term for returning from a NIF: a flat list of binaries
+    pub fn to_term(&self) -> TermValue {
+        TermValue::list(self.chunks().map(|chunk| TermValue::Binary(chunk.to_vec())).collect())
+    }
+}
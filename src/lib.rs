@@ -1,5 +1,6 @@
 #![no_std]
 extern crate alloc;
+extern crate self as avmnif_rs;
 
 // Core modules - keep your existing structure
 pub mod atom;
@@ -10,9 +11,39 @@ pub mod tagged;
 pub mod context;
 pub mod resource;
 pub mod registry;
+pub mod etf;
+pub mod monitor;
+pub mod contracts;
+pub mod iolist;
+pub mod bitfield;
+pub mod binary;
+pub mod diff;
+pub mod metrics;
+pub mod reply;
+pub mod names;
+pub mod progress;
+pub mod select;
+pub mod framebuffer;
+pub mod resource_monitor;
+pub mod nif;
+pub mod args;
+pub mod canonical;
+pub mod hash;
+pub mod pretty;
+pub mod parse;
+#[cfg(any(test, feature = "serde"))]
+pub mod serde;
+pub mod task;
+pub mod panic;
+pub mod allocator;
+#[cfg(feature = "rp2040")]
+pub mod rp2040;
+#[cfg(feature = "async")]
+pub mod asynch;
+mod sync;
 
-// Testing infrastructure (only compiled for tests)
-#[cfg(test)]
+// Testing infrastructure (compiled for tests, or for downstream crates via the `testing` feature)
+#[cfg(any(test, feature = "testing"))]
 pub mod testing;
 
 // Re-export commonly used types - match your existing exports
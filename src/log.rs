@@ -1,25 +1,396 @@
-use alloc::ffi::CString;
+//! Logging to the AtomVM console
+//!
+//! Four levels, `Error` to `Debug`. The `log_error!`, `log_warn!`,
+//! `log_info!` and `log_debug!` macros take `format!` arguments and tag
+//! each line with its level, module path and line number:
+//!
+//! ```text
+//! [warn] my_driver::uart:88: rx overrun, 12 bytes lost
+//! ```
+//!
+//! The `max-level-*` features set the most verbose level that is compiled
+//! in; calls below it are removed at compile time. Without any of them
+//! every level is kept.
+//!
+//! `log_info` and its siblings log a plain message with the level tag only.
+//!
+//! Logging never allocates: each line is built in a fixed buffer on the
+//! stack, so it works when the heap is exhausted. Lines longer than
+//! `LINE_CAPACITY` bytes are cut and end in `...`, and NUL bytes are
+//! written as `\0` instead of ending the line early.
+//!
+//! A `LogSink` installed with `set_sink` also receives every record; with
+//! `ErlangLoggerSink` they reach Erlang's `logger` as terms. See below.
+//!
+//! Lines go to the `avmnif_log` C function. With the `defmt` feature they go
+//! to defmt instead, at the matching defmt level and without the `[level]`
+//! tag, which defmt adds itself; the firmware then provides the
+//! `#[defmt::global_logger]`, such as `defmt-rtt`.
 
-extern "C" {
-    fn avmnif_log(msg: *const i8);
+extern crate alloc;
+
+use crate::atom::{AtomTable, AtomTableOps};
+use crate::names::{self, AtomVMNameRegistry};
+use crate::sync::SpinLock;
+use crate::term::TermValue;
+#[cfg(not(feature = "defmt"))]
+use core::ffi::c_char;
+use core::fmt::{self, Write};
+
+#[cfg(not(feature = "defmt"))]
+use avmnif_sys::avmnif_log;
+
+/// Severity of a log line, most severe first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
 }
 
-pub fn log_info(msg: &str) {
-    let cstr = CString::new(msg).expect("log message contained null byte");
+impl Level {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        }
+    }
+
+    /// The level's name in Erlang's `logger`
+    pub const fn logger_name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warning",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        }
+    }
+
+    /// Whether lines at this level are compiled in
+    pub const fn enabled(self) -> bool {
+        match MAX_LEVEL {
+            Some(max) => self as u8 <= max as u8,
+            None => false,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The most verbose level compiled in, `None` with `max-level-off`
+///
+/// Several `max-level-*` features together mean the strictest of them.
+pub const MAX_LEVEL: Option<Level> = if cfg!(feature = "max-level-off") {
+    None
+} else if cfg!(feature = "max-level-error") {
+    Some(Level::Error)
+} else if cfg!(feature = "max-level-warn") {
+    Some(Level::Warn)
+} else if cfg!(feature = "max-level-info") {
+    Some(Level::Info)
+} else {
+    Some(Level::Debug)
+};
+
+/// Write one line as `[level] module:line: message`
+pub fn format_record<W: Write>(
+    out: &mut W,
+    level: Level,
+    module: &str,
+    line: u32,
+    args: fmt::Arguments<'_>,
+) -> fmt::Result {
+    write!(out, "[{}] {}:{}: {}", level, module, line, args)
+}
+
+/// Longest log line in bytes, not counting the terminating NUL
+pub const LINE_CAPACITY: usize = 255;
+
+const ELLIPSIS: &str = "...";
+
+/// One log line, built on the stack
+///
+/// Writing never fails: what does not fit is dropped and the line ends
+/// in `...`.
+#[derive(Debug, Default)]
+pub struct LineBuffer {
+    // One byte more than the line, for the NUL
+    bytes: heapless::Vec<u8, { LINE_CAPACITY + 1 }>,
+    truncated: bool,
+}
+
+impl LineBuffer {
+    pub const fn new() -> Self {
+        Self {
+            bytes: heapless::Vec::new(),
+            truncated: false,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Only whole characters are ever written
+        core::str::from_utf8(&self.bytes).unwrap_or("")
+    }
+
+    /// Some of the text written did not fit
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    fn push_str(&mut self, text: &str) -> bool {
+        if self.bytes.len() + text.len() > LINE_CAPACITY {
+            return false;
+        }
+        self.bytes.extend_from_slice(text.as_bytes()).is_ok()
+    }
+
+    /// Cut the line back far enough to end it in `...`
+    fn truncate(&mut self) {
+        self.truncated = true;
+        while self.bytes.len() + ELLIPSIS.len() > LINE_CAPACITY {
+            // Drop a whole UTF-8 sequence: continuation bytes, then the lead byte
+            while self.bytes.pop().is_some_and(|byte| byte & 0xC0 == 0x80) {}
+        }
+        self.push_str(ELLIPSIS);
+    }
+
+    /// The line as a C string
+    #[cfg(not(feature = "defmt"))]
+    fn as_c_str(&mut self) -> *const c_char {
+        // `push_str` keeps the last byte free
+        let _ = self.bytes.push(0);
+        self.bytes.as_ptr() as *const c_char
+    }
+}
+
+impl Write for LineBuffer {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        if self.truncated {
+            return Ok(());
+        }
+        let mut buf = [0u8; 4];
+        for c in text.chars() {
+            let piece = if c == '\0' { "\\0" } else { c.encode_utf8(&mut buf) };
+            if !self.push_str(piece) {
+                self.truncate();
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "defmt"))]
+fn emit(_level: Level, mut line: LineBuffer) {
     unsafe {
-        avmnif_log(cstr.as_ptr());
+        avmnif_log(line.as_c_str());
     }
 }
 
+#[cfg(feature = "defmt")]
+fn emit(level: Level, line: LineBuffer) {
+    let text = line.as_str();
+    match level {
+        Level::Error => defmt::error!("{=str}", text),
+        Level::Warn => defmt::warn!("{=str}", text),
+        Level::Info => defmt::info!("{=str}", text),
+        Level::Debug => defmt::debug!("{=str}", text),
+    }
+}
+
+/// Log a line with its source location; used by the `log_*!` macros
+pub fn log_record(level: Level, module: &str, line: u32, args: fmt::Arguments<'_>) {
+    if !level.enabled() {
+        return;
+    }
+    if has_sink() {
+        let mut message = LineBuffer::new();
+        let _ = message.write_fmt(args);
+        forward(&Record { level, module: Some(module), line: Some(line), message: message.as_str() });
+    }
+    let mut out = LineBuffer::new();
+    let _ = if cfg!(feature = "defmt") {
+        write!(out, "{}:{}: {}", module, line, args)
+    } else {
+        format_record(&mut out, level, module, line, args)
+    };
+    emit(level, out);
+}
+
+/// Log a plain message at `level`
+pub fn log(level: Level, msg: &str) {
+    if level.enabled() {
+        forward(&Record { level, module: None, line: None, message: msg });
+        let mut out = LineBuffer::new();
+        let _ = if cfg!(feature = "defmt") {
+            out.write_str(msg)
+        } else {
+            write!(out, "[{}] {}", level, msg)
+        };
+        emit(level, out);
+    }
+}
+
+pub fn log_error(msg: &str) {
+    log(Level::Error, msg);
+}
+
+pub fn log_warn(msg: &str) {
+    log(Level::Warn, msg);
+}
+
+pub fn log_info(msg: &str) {
+    log(Level::Info, msg);
+}
+
+pub fn log_debug(msg: &str) {
+    log(Level::Debug, msg);
+}
+
+// ── Sinks ───────────────────────────────────────────────────────────────────
+
+/// One log record as handed to a sink
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record<'a> {
+    pub level: Level,
+    /// Module path, for records from the `log_*!` macros
+    pub module: Option<&'a str>,
+    pub line: Option<u32>,
+    pub message: &'a str,
+}
+
+/// Receives every record logged, besides the console
+///
+/// Called on the logging thread; keep it short and do not log from it.
+pub trait LogSink: Sync {
+    fn log(&self, record: &Record<'_>);
+}
+
+static SINK: SpinLock<Option<&'static dyn LogSink>> = SpinLock::new(None);
+
+/// Install the sink, or remove it with `None`
+pub fn set_sink(sink: Option<&'static dyn LogSink>) {
+    SINK.with(|current| *current = sink);
+}
+
+fn has_sink() -> bool {
+    SINK.with(|current| current.is_some())
+}
+
+/// Hand a record to the installed sink, if any
+pub(crate) fn forward(record: &Record<'_>) {
+    // Copied out so the sink runs without the lock held
+    if let Some(sink) = SINK.with(|current| *current) {
+        sink.log(record);
+    }
+}
+
+/// `{log, Level, Message, Meta}` for a record
+///
+/// `Level` is the `logger` level atom (`warning` for `Level::Warn`),
+/// `Message` a binary, and `Meta` a map with `module` (a binary) and
+/// `line` when the record has them.
+pub fn record_term<T: AtomTableOps>(record: &Record<'_>, table: &T) -> TermValue {
+    let mut meta = alloc::vec::Vec::new();
+    if let Some(module) = record.module {
+        meta.push((TermValue::atom("module", table), TermValue::binary(module.as_bytes().to_vec())));
+    }
+    if let Some(line) = record.line {
+        meta.push((TermValue::atom("line", table), TermValue::int(line as i32)));
+    }
+    TermValue::tuple(alloc::vec![
+        TermValue::atom("log", table),
+        TermValue::atom(record.level.logger_name(), table),
+        TermValue::binary(record.message.as_bytes().to_vec()),
+        TermValue::Map(meta),
+    ])
+}
+
+/// Sends records as `{log, Level, Message, Meta}` to a registered process
+///
+/// The process forwards them into `logger`, for example with
+/// `logger:log(Level, Message, Meta)`. Records logged while no process has
+/// the name are dropped. Unlike the console path this allocates, so
+/// records logged when the heap is exhausted only reach the console.
+///
+/// ```rust,ignore
+/// static LOGGER: ErlangLoggerSink = ErlangLoggerSink::new("rust_logger");
+/// log::set_sink(Some(&LOGGER));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ErlangLoggerSink {
+    process: &'static str,
+}
+
+impl ErlangLoggerSink {
+    /// Send to the process registered as `process`
+    pub const fn new(process: &'static str) -> Self {
+        Self { process }
+    }
+
+    pub fn process(&self) -> &'static str {
+        self.process
+    }
+}
+
+impl LogSink for ErlangLoggerSink {
+    fn log(&self, record: &Record<'_>) {
+        let table = AtomTable::from_global();
+        if let Some(pid) = names::whereis(&AtomVMNameRegistry::new(), self.process, &table) {
+            let _ = crate::port::send_from_task(pid, &record_term(record, &table), &table);
+        }
+    }
+}
+
+/// Log at `level` with the caller's module path and line
+#[doc(hidden)]
+#[macro_export]
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)+) => {
+        if $level.enabled() {
+            $crate::log::log_record($level, module_path!(), line!(), format_args!($($arg)+))
+        }
+    };
+}
+
+/// Log an error: `log_error!("write failed: {:?}", error)`
+#[macro_export]
+macro_rules! log_error {
+    ($($arg:tt)+) => { $crate::log_at!($crate::log::Level::Error, $($arg)+) };
+}
+
+/// Log a warning
+#[macro_export]
+macro_rules! log_warn {
+    ($($arg:tt)+) => { $crate::log_at!($crate::log::Level::Warn, $($arg)+) };
+}
+
+/// Log an informational line
+#[macro_export]
+macro_rules! log_info {
+    ($($arg:tt)+) => { $crate::log_at!($crate::log::Level::Info, $($arg)+) };
+}
+
+/// Log a debugging line
+#[macro_export]
+macro_rules! log_debug {
+    ($($arg:tt)+) => { $crate::log_at!($crate::log::Level::Debug, $($arg)+) };
+}
+
+/// Same as `log_info!`; kept for existing callers
 #[macro_export]
 macro_rules! nif_log {
     ($msg:expr) => {
-        $crate::log::log_info($msg)
+        $crate::log_info!("{}", $msg)
+    };
+    ($($arg:tt)*) => {
+        $crate::log_info!($($arg)*)
     };
-    ($($arg:tt)*) => {{
-        use alloc::fmt::Write;
-        let mut buf = heapless::String::<256>::new();
-        let _ = write!(buf, $($arg)*);
-        $crate::log::log_info(&buf);
-    }};
 }
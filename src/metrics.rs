@@ -0,0 +1,372 @@
+//! Driver metrics
+//!
+//! Counters, gauges and histograms that NIFs and ports update with plain
+//! atomic operations, collected in a fixed-capacity registry. The `metrics/0`
+//! NIF (`metrics_nif`) exports the global registry as a map of name to
+//! value, so every driver on a device can be scraped the same way.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use avmnif_rs::metrics::{self, Counter, Gauge, Histogram};
+//! use avmnif_rs::{nif_collection, Context};
+//!
+//! static SPI_ERRORS: Counter = Counter::new("spi_errors");
+//! static TEMPERATURE: Gauge = Gauge::new("temperature_c");
+//! static READ_US: Histogram<3> = Histogram::new("read_us", [100, 1000, 10000]);
+//!
+//! fn sensor_init(_ctx: &mut Context) {
+//!     let _ = metrics::register(&SPI_ERRORS);
+//!     let _ = metrics::register(&TEMPERATURE);
+//!     let _ = metrics::register(&READ_US);
+//! }
+//!
+//! nif_collection!(
+//!     sensor,
+//!     init = sensor_init,
+//!     nifs = [
+//!         ("metrics", 0, avmnif_rs::metrics::metrics_nif),
+//!         // ...
+//!     ]
+//! );
+//! # use avmnif_rs::term::TermValue;
+//! # let global = avmnif_rs::testing::mocks::MockGlobalContext::new();
+//! # let mut process = global.new_context();
+//! # let ctx = process.as_context() as *mut Context;
+//! # sensor_nif_init(ctx);
+//! # SPI_ERRORS.add(2);
+//! # TEMPERATURE.set(21);
+//! # let snapshot = unsafe { metrics::metrics_nif(ctx.cast(), 0, core::ptr::null()) }.to_value().unwrap();
+//! # assert_eq!(snapshot.map_get(&TermValue::atom("spi_errors", global.atoms())), Some(&TermValue::int(2)));
+//! # assert_eq!(snapshot.map_get(&TermValue::atom("temperature_c", global.atoms())), Some(&TermValue::int(21)));
+//! # }
+//! ```
+//!
+//! ```erlang
+//! sensor:metrics().
+//! %% #{spi_errors => 2, temperature_c => 21,
+//! %%   read_us => #{count => 9, sum => 2450,
+//! %%                buckets => [{100, 3}, {1000, 6}, {10000, 0}, {infinity, 0}]}}
+//! ```
+
+extern crate alloc;
+
+use crate::atom::{AtomTable, AtomTableOps};
+use crate::sync::SpinLock;
+use crate::term::{Context, NifReturn, Term, TermValue};
+use alloc::vec::Vec;
+use core::fmt;
+use crate::sync::atomic::{AtomicI32, AtomicU32, Ordering};
+
+/// Registry capacity of the global registry behind `metrics_nif`
+pub const GLOBAL_CAPACITY: usize = 32;
+
+/// Snapshot of one metric
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetricValue {
+    Counter(u32),
+    Gauge(i32),
+    Histogram {
+        count: u32,
+        sum: u32,
+        /// Upper bound and count of each bucket
+        buckets: Vec<(u32, u32)>,
+        /// Observations above the last bound
+        overflow: u32,
+    },
+}
+
+impl MetricValue {
+    /// Build the exported term; counts beyond `i32::MAX` saturate
+    pub fn to_term<T: AtomTableOps>(&self, table: &T) -> TermValue {
+        match self {
+            MetricValue::Counter(value) => int(*value),
+            MetricValue::Gauge(value) => TermValue::int(*value),
+            MetricValue::Histogram { count, sum, buckets, overflow } => {
+                let mut bucket_terms: Vec<TermValue> = buckets
+                    .iter()
+                    .map(|(bound, n)| TermValue::tuple(alloc::vec![int(*bound), int(*n)]))
+                    .collect();
+                bucket_terms.push(TermValue::tuple(alloc::vec![
+                    TermValue::atom("infinity", table),
+                    int(*overflow),
+                ]));
+                TermValue::map(alloc::vec![
+                    (TermValue::atom("count", table), int(*count)),
+                    (TermValue::atom("sum", table), int(*sum)),
+                    (TermValue::atom("buckets", table), TermValue::list(bucket_terms)),
+                ])
+            }
+        }
+    }
+}
+
+fn int(value: u32) -> TermValue {
+    TermValue::int(i32::try_from(value).unwrap_or(i32::MAX))
+}
+
+/// Anything the registry can export
+pub trait Metric: Sync {
+    /// Key in the exported map
+    fn name(&self) -> &'static str;
+
+    /// Current value
+    fn read(&self) -> MetricValue;
+
+    /// Return to the initial value
+    fn reset(&self);
+}
+
+/// Monotonic event count (wraps at `u32::MAX`)
+#[derive(Debug)]
+pub struct Counter {
+    name: &'static str,
+    value: AtomicU32,
+}
+
+impl Counter {
+    pub const fn new(name: &'static str) -> Self {
+        Self { name, value: AtomicU32::new(0) }
+    }
+
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u32) {
+        self.value.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u32 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+impl Metric for Counter {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn read(&self) -> MetricValue {
+        MetricValue::Counter(self.get())
+    }
+
+    fn reset(&self) {
+        self.value.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Last observed level, such as a temperature or queue depth
+#[derive(Debug)]
+pub struct Gauge {
+    name: &'static str,
+    value: AtomicI32,
+}
+
+impl Gauge {
+    pub const fn new(name: &'static str) -> Self {
+        Self { name, value: AtomicI32::new(0) }
+    }
+
+    pub fn set(&self, value: i32) {
+        self.value.store(value, Ordering::Relaxed);
+    }
+
+    pub fn add(&self, delta: i32) {
+        self.value.fetch_add(delta, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i32 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+impl Metric for Gauge {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn read(&self) -> MetricValue {
+        MetricValue::Gauge(self.get())
+    }
+
+    fn reset(&self) {
+        self.value.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Distribution over `B` fixed buckets plus an overflow bucket
+///
+/// A value lands in the first bucket whose bound it does not exceed.
+/// Bounds must be ascending.
+#[derive(Debug)]
+pub struct Histogram<const B: usize> {
+    name: &'static str,
+    bounds: [u32; B],
+    buckets: [AtomicU32; B],
+    overflow: AtomicU32,
+    count: AtomicU32,
+    sum: AtomicU32,
+}
+
+impl<const B: usize> Histogram<B> {
+    pub const fn new(name: &'static str, bounds: [u32; B]) -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const ZERO: AtomicU32 = AtomicU32::new(0);
+        Self {
+            name,
+            bounds,
+            buckets: [ZERO; B],
+            overflow: ZERO,
+            count: ZERO,
+            sum: ZERO,
+        }
+    }
+
+    pub fn observe(&self, value: u32) {
+        match self.bounds.iter().position(|bound| value <= *bound) {
+            Some(index) => self.buckets[index].fetch_add(1, Ordering::Relaxed),
+            None => self.overflow.fetch_add(1, Ordering::Relaxed),
+        };
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+impl<const B: usize> Metric for Histogram<B> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn read(&self) -> MetricValue {
+        MetricValue::Histogram {
+            count: self.count(),
+            sum: self.sum.load(Ordering::Relaxed),
+            buckets: self
+                .bounds
+                .iter()
+                .zip(&self.buckets)
+                .map(|(bound, n)| (*bound, n.load(Ordering::Relaxed)))
+                .collect(),
+            overflow: self.overflow.load(Ordering::Relaxed),
+        }
+    }
+
+    fn reset(&self) {
+        for bucket in self.buckets.iter().chain([&self.overflow, &self.count, &self.sum]) {
+            bucket.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Errors from registering a metric
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsError {
+    /// Every slot is taken
+    Full,
+    /// Another metric already uses this name
+    DuplicateName(&'static str),
+}
+
+impl fmt::Display for MetricsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetricsError::Full => write!(f, "metrics registry full"),
+            MetricsError::DuplicateName(name) => write!(f, "metric {} already registered", name),
+        }
+    }
+}
+
+/// Fixed-capacity set of metrics, safe to share between tasks
+pub struct MetricsRegistry<const N: usize> {
+    slots: SpinLock<[Option<&'static dyn Metric>; N]>,
+}
+
+impl<const N: usize> Default for MetricsRegistry<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> MetricsRegistry<N> {
+    pub const fn new() -> Self {
+        Self { slots: SpinLock::new([None; N]) }
+    }
+
+    /// Add a metric; registering the same metric again is a no-op
+    pub fn register(&self, metric: &'static dyn Metric) -> Result<(), MetricsError> {
+        self.slots.with(|slots| {
+            for existing in slots.iter().flatten() {
+                if existing.name() == metric.name() {
+                    let same = core::ptr::eq(*existing as *const dyn Metric as *const (), metric as *const dyn Metric as *const ());
+                    return if same {
+                        Ok(())
+                    } else {
+                        Err(MetricsError::DuplicateName(metric.name()))
+                    };
+                }
+            }
+            let free = slots.iter_mut().find(|slot| slot.is_none()).ok_or(MetricsError::Full)?;
+            *free = Some(metric);
+            Ok(())
+        })
+    }
+
+    /// Number of registered metrics
+    pub fn len(&self) -> usize {
+        self.slots.with(|slots| slots.iter().flatten().count())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Read every metric, in registration order
+    pub fn snapshot(&self) -> Vec<(&'static str, MetricValue)> {
+        self.slots
+            .with(|slots| slots.iter().flatten().map(|m| (m.name(), m.read())).collect())
+    }
+
+    /// Export as a map of metric name atoms to values
+    pub fn to_term<T: AtomTableOps>(&self, table: &T) -> TermValue {
+        TermValue::map(
+            self.snapshot()
+                .into_iter()
+                .map(|(name, value)| (TermValue::atom(name, table), value.to_term(table)))
+                .collect(),
+        )
+    }
+
+    /// Reset every registered metric
+    pub fn reset_all(&self) {
+        self.slots.with(|slots| slots.iter().flatten().for_each(|m| m.reset()));
+    }
+}
+
+static GLOBAL: MetricsRegistry<GLOBAL_CAPACITY> = MetricsRegistry::new();
+
+/// The registry exported by `metrics_nif`
+pub fn global() -> &'static MetricsRegistry<GLOBAL_CAPACITY> {
+    &GLOBAL
+}
+
+/// Add a metric to the global registry
+pub fn register(metric: &'static dyn Metric) -> Result<(), MetricsError> {
+    GLOBAL.register(metric)
+}
+
+/// `metrics/0`: the global registry as a map of name to value
+///
+/// # Safety
+///
+/// `ctx` must point to the live context of the calling process, as it
+/// does when the VM calls the NIF.
+pub unsafe extern "C" fn metrics_nif(ctx: *mut Context, _argc: i32, _argv: *const Term) -> Term {
+    let snapshot = GLOBAL.to_term(&AtomTable::from_global());
+    NifReturn::Value(snapshot).into_term(&mut *ctx)
+}
@@ -0,0 +1,183 @@
+//! Monitored pid sets
+//!
+//! Ports and resources often keep a list of interested processes
+//! (subscribers, waiters, owners). `PidSet` monitors every pid it holds and
+//! drops it again when the matching DOWN notification arrives, so dead
+//! processes never pile up.
+//!
+//! Monitoring itself goes through the `ProcessMonitor` trait: ports use
+//! `port::PortProcessMonitor`, resources use
+//! `resource::ResourceProcessMonitor`, and tests can use any mock.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::monitor::PidSet;
+//! use avmnif_rs::port::PortProcessMonitor;
+//!
+//! // {subscribe, Pid}
+//! data.subscribers.insert(&mut PortProcessMonitor::new(ctx), pid)?;
+//!
+//! // {'DOWN', Ref, process, Pid, Reason}
+//! data.subscribers.handle_down_message(&message, &table, |pid| {
+//!     data.stats.dropped += 1;
+//! });
+//! ```
+
+extern crate alloc;
+
+use crate::atom::AtomTableOps;
+use crate::term::{ProcessId, TermValue};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Errors from monitoring a process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonitorError {
+    /// Process does not exist (already dead)
+    NoProcess,
+    /// Platform refused the monitor
+    Failed,
+}
+
+impl fmt::Display for MonitorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MonitorError::NoProcess => write!(f, "no such process"),
+            MonitorError::Failed => write!(f, "monitor failed"),
+        }
+    }
+}
+
+/// Something that can monitor processes on behalf of a port or resource
+pub trait ProcessMonitor {
+    /// Per-monitor state needed to remove it again
+    type Handle;
+
+    /// Start monitoring `pid`
+    fn monitor(&mut self, pid: u32) -> Result<Self::Handle, MonitorError>;
+
+    /// Stop a monitor started by `monitor`
+    fn demonitor(&mut self, pid: u32, handle: &Self::Handle);
+}
+
+/// Set of monitored pids, pruned on DOWN
+#[derive(Debug)]
+pub struct PidSet<H> {
+    entries: Vec<(u32, H)>,
+}
+
+impl<H> Default for PidSet<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H> PidSet<H> {
+    /// Create an empty set
+    pub fn new() -> Self {
+        Self { entries: Vec::new() }
+    }
+
+    /// Monitor and add `pid`; returns false if it was already present
+    pub fn insert<M>(&mut self, monitor: &mut M, pid: u32) -> Result<bool, MonitorError>
+    where
+        M: ProcessMonitor<Handle = H>,
+    {
+        if self.contains(pid) {
+            return Ok(false);
+        }
+        let handle = monitor.monitor(pid)?;
+        self.entries.push((pid, handle));
+        Ok(true)
+    }
+
+    /// Demonitor and remove `pid`; returns false if it was not present
+    pub fn remove<M>(&mut self, monitor: &mut M, pid: u32) -> bool
+    where
+        M: ProcessMonitor<Handle = H>,
+    {
+        match self.position(pid) {
+            Some(i) => {
+                let (pid, handle) = self.entries.remove(i);
+                monitor.demonitor(pid, &handle);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Demonitor and remove every pid
+    pub fn clear<M>(&mut self, monitor: &mut M)
+    where
+        M: ProcessMonitor<Handle = H>,
+    {
+        for (pid, handle) in self.entries.drain(..) {
+            monitor.demonitor(pid, &handle);
+        }
+    }
+
+    /// Prune `pid` after its monitor fired, calling `on_down` if it was present
+    ///
+    /// The monitor is already gone, so nothing is demonitored.
+    pub fn handle_down<F: FnOnce(u32)>(&mut self, pid: u32, on_down: F) -> bool {
+        match self.position(pid) {
+            Some(i) => {
+                self.entries.remove(i);
+                on_down(pid);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Like `handle_down`, taking the `{'DOWN', Ref, process, Pid, Reason}` message
+    ///
+    /// Returns false for any other message, so it can be tried first in a handler.
+    pub fn handle_down_message<T, F>(&mut self, message: &TermValue, table: &T, on_down: F) -> bool
+    where
+        T: AtomTableOps,
+        F: FnOnce(u32),
+    {
+        match match_down(message, table) {
+            Some(pid) => self.handle_down(pid, on_down),
+            None => false,
+        }
+    }
+
+    /// Check whether `pid` is in the set
+    pub fn contains(&self, pid: u32) -> bool {
+        self.position(pid).is_some()
+    }
+
+    /// Pids in insertion order
+    pub fn pids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.entries.iter().map(|(pid, _)| *pid)
+    }
+
+    /// Number of pids
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn position(&self, pid: u32) -> Option<usize> {
+        self.entries.iter().position(|(p, _)| *p == pid)
+    }
+}
+
+/// Recognize a process DOWN message, returning the dead pid
+pub fn match_down<T: AtomTableOps>(message: &TermValue, table: &T) -> Option<u32> {
+    match message.as_tuple() {
+        Some([tag, _reference, kind, TermValue::Pid(ProcessId(pid)), _reason])
+            if tag.is_atom_str("DOWN", table) && kind.is_atom_str("process", table) =>
+        {
+            Some(*pid)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,160 @@
+//! Registered process names
+//!
+//! A driver port can register itself under an atom, as `erlang:register/2`
+//! does, and other code can look names up with `whereis`. Erlang code then
+//! sends to `adc` instead of passing the port pid around, and Rust code
+//! (tasks, other ports, NIFs) finds the port the same way.
+//!
+//! Lookups go through the `NameRegistry` trait: `AtomVMNameRegistry` uses
+//! the VM's table of registered processes, and tests can use any mock.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::names::{self, AtomVMNameRegistry};
+//!
+//! fn adc_create_port(global: &GlobalContext, _opts: Term) -> *mut Context {
+//!     let ctx = PortBuilder::new(AdcData::new()).build(global);
+//!     if !ctx.is_null() {
+//!         let _ = names::register_port(unsafe { &mut *ctx }, "adc", &AtomTable::from_global());
+//!     }
+//!     ctx
+//! }
+//!
+//! // From a task
+//! let registry = AtomVMNameRegistry::new();
+//! if let Some(adc) = names::whereis(&registry, "adc", &table) {
+//!     port::send_from_task(adc, &request, &table)?;
+//! }
+//! ```
+
+use crate::atom::{AtomIndex, AtomTableOps};
+use crate::context::{get_global_context, Context, GlobalContext};
+use core::fmt;
+
+/// Errors from registering a name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameError {
+    /// The name cannot be an atom, or is `undefined`
+    BadName,
+    /// Another process already has the name
+    AlreadyRegistered,
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameError::BadName => write!(f, "invalid process name"),
+            NameError::AlreadyRegistered => write!(f, "name already registered"),
+        }
+    }
+}
+
+/// Table of registered process names
+pub trait NameRegistry {
+    /// Register `pid` under `name`
+    fn register(&mut self, name: AtomIndex, pid: u32) -> Result<(), NameError>;
+
+    /// Remove `name`; returns false if it was not registered
+    fn unregister(&mut self, name: AtomIndex) -> bool;
+
+    /// The pid registered under `name`
+    fn whereis(&self, name: AtomIndex) -> Option<u32>;
+}
+
+/// Register `pid` under the atom `name`
+pub fn register<R, T>(registry: &mut R, name: &str, pid: u32, table: &T) -> Result<(), NameError>
+where
+    R: NameRegistry,
+    T: AtomTableOps,
+{
+    if name == "undefined" {
+        return Err(NameError::BadName);
+    }
+    let atom = table.ensure_atom_str(name).map_err(|_| NameError::BadName)?;
+    registry.register(atom, pid)
+}
+
+/// Remove the atom `name`; returns false if it was not registered
+pub fn unregister<R: NameRegistry, T: AtomTableOps>(registry: &mut R, name: &str, table: &T) -> bool {
+    match table.find_atom_str(name) {
+        Ok(atom) => registry.unregister(atom),
+        Err(_) => false,
+    }
+}
+
+/// The pid registered under the atom `name`
+///
+/// A name that is not an atom yet cannot be registered, so it is not
+/// added to the atom table just to look it up.
+pub fn whereis<R: NameRegistry, T: AtomTableOps>(registry: &R, name: &str, table: &T) -> Option<u32> {
+    let atom = table.find_atom_str(name).ok()?;
+    registry.whereis(atom)
+}
+
+// Name registry FFI declarations
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    /// Register a local process id under an atom; false if the name is taken
+    fn globalcontext_register_process(glb: *mut GlobalContext, atom_index: i32, local_process_id: i32) -> bool;
+
+    /// Remove a registered name; false if it was not registered
+    fn globalcontext_unregister_process(glb: *mut GlobalContext, atom_index: i32) -> bool;
+
+    /// The process registered under an atom, or 0
+    fn globalcontext_get_registered_process(glb: *mut GlobalContext, atom_index: i32) -> i32;
+
+    /// The process id of a context (a port's own pid)
+    fn context_process_id(ctx: *const Context) -> i32;
+}
+
+/// Name registry backed by the VM's registered processes
+pub struct AtomVMNameRegistry {
+    global: *mut GlobalContext,
+}
+
+impl Default for AtomVMNameRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AtomVMNameRegistry {
+    /// Use the global context; safe from tasks and other ports
+    pub fn new() -> Self {
+        Self { global: get_global_context() }
+    }
+}
+
+impl NameRegistry for AtomVMNameRegistry {
+    fn register(&mut self, name: AtomIndex, pid: u32) -> Result<(), NameError> {
+        if unsafe { globalcontext_register_process(self.global, name.0 as i32, pid as i32) } {
+            Ok(())
+        } else {
+            Err(NameError::AlreadyRegistered)
+        }
+    }
+
+    fn unregister(&mut self, name: AtomIndex) -> bool {
+        unsafe { globalcontext_unregister_process(self.global, name.0 as i32) }
+    }
+
+    fn whereis(&self, name: AtomIndex) -> Option<u32> {
+        match unsafe { globalcontext_get_registered_process(self.global, name.0 as i32) } {
+            0 => None,
+            pid => Some(pid as u32),
+        }
+    }
+}
+
+/// The pid of a port, as seen by Erlang code
+pub fn port_pid(ctx: &Context) -> u32 {
+    unsafe { context_process_id(ctx as *const Context) as u32 }
+}
+
+/// Register the port `ctx` under the atom `name`
+///
+/// The VM drops the name when the port terminates.
+pub fn register_port<T: AtomTableOps>(ctx: &mut Context, name: &str, table: &T) -> Result<(), NameError> {
+    register(&mut AtomVMNameRegistry::new(), name, port_pid(ctx), table)
+}
@@ -0,0 +1,228 @@
+//! NIFs as ordinary Rust functions
+//!
+//! A `Nif` is any function or closure taking `&mut Env` followed by up to
+//! eight arguments that implement `FromTerm`, and returning anything that
+//! converts into a `NifReturn` (`TermValue`, `NifResult<TermValue>`,
+//! `NifReturn` itself, ...). The arity comes from the signature, arguments
+//! are decoded before the function runs, and an argument of the wrong type
+//! raises `badarg` without reaching it.
+//!
+//! A `NifError` the function returns is raised by default. With
+//! `ErrorEncoding::Tuple` it becomes `{error, Reason}` instead, so NIF
+//! bodies can use `?` and never build error tuples by hand.
+//!
+//! `nif_collection!` takes such functions under `typed = [...]` and
+//! generates the `extern "C"` wrapper and the table entry; there is no
+//! arity to keep in sync by hand. An entry ending in `error_tuple` uses
+//! `ErrorEncoding::Tuple`.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use avmnif_rs::nif_collection;
+//! use avmnif_rs::term::{Env, NifError, NifResult, TermValue};
+//! # fn adc_init(_ctx: &mut avmnif_rs::Context) {}
+//! # fn open(_env: &mut Env, channel: u32) -> NifResult<TermValue> {
+//! #     if channel < 8 { Ok(TermValue::int(channel as i32)) } else { Err(NifError::BadArg) }
+//! # }
+//!
+//! fn scale(_env: &mut Env, reading: i32, factor: i32) -> NifResult<TermValue> {
+//!     reading.checked_mul(factor).map(TermValue::int).ok_or(NifError::SystemLimit)
+//! }
+//!
+//! nif_collection!(
+//!     adc,
+//!     init = adc_init,
+//!     typed = [("scale", scale), ("open", open, error_tuple)],
+//!     nifs = []
+//! );
+//! # use avmnif_rs::registry::{find_nif, NifFunction};
+//! # use avmnif_rs::testing::mocks::{MockGlobalContext, MockHeap};
+//! # let global = MockGlobalContext::new();
+//! # let mut process = global.new_context();
+//! # let ctx = process.as_context() as *mut _ as *mut avmnif_rs::term::Context;
+//! # let nif = |name| unsafe { core::mem::transmute::<_, NifFunction>(find_nif(ADC_NIFS, name).unwrap().function) };
+//! # let mut heap = MockHeap::new(8);
+//! # let args = [heap.encode(TermValue::int(6)).unwrap(), heap.encode(TermValue::int(7)).unwrap()];
+//! # assert_eq!(unsafe { nif("scale")(ctx, 2, args.as_ptr()) }.to_value(), Ok(TermValue::int(42)));
+//! # let error = TermValue::tuple(vec![TermValue::atom("error", global.atoms()), TermValue::atom("badarg", global.atoms())]);
+//! # let channel = [heap.encode(TermValue::int(9)).unwrap()];
+//! # assert_eq!(unsafe { nif("open")(ctx, 1, channel.as_ptr()) }.to_value(), Ok(error));
+//! # }
+//! ```
+
+extern crate alloc;
+
+use crate::atom::{AtomTable, AtomTableOps};
+use crate::term::{Context, Env, NifError, NifResult, NifReturn, Term, TermValue};
+use alloc::vec::Vec;
+
+/// A NIF argument decoded from a term
+pub trait FromTerm: Sized {
+    fn from_term(term: Term<'_>) -> NifResult<Self>;
+}
+
+impl FromTerm for TermValue {
+    fn from_term(term: Term<'_>) -> NifResult<Self> {
+        term.to_value()
+    }
+}
+
+impl FromTerm for i64 {
+    fn from_term(term: Term<'_>) -> NifResult<Self> {
+        term.to_i64().map_err(|_| NifError::BadArg)
+    }
+}
+
+impl FromTerm for i32 {
+    fn from_term(term: Term<'_>) -> NifResult<Self> {
+        i32::try_from(i64::from_term(term)?).map_err(|_| NifError::BadArg)
+    }
+}
+
+impl FromTerm for u32 {
+    fn from_term(term: Term<'_>) -> NifResult<Self> {
+        u32::try_from(i64::from_term(term)?).map_err(|_| NifError::BadArg)
+    }
+}
+
+impl FromTerm for f64 {
+    fn from_term(term: Term<'_>) -> NifResult<Self> {
+        match term.to_value()? {
+            TermValue::Float(value) => Ok(value),
+            _ => Err(NifError::BadArg),
+        }
+    }
+}
+
+impl FromTerm for Vec<u8> {
+    fn from_term(term: Term<'_>) -> NifResult<Self> {
+        match term.to_value()? {
+            TermValue::Binary(bytes) => Ok(bytes),
+            _ => Err(NifError::BadArg),
+        }
+    }
+}
+
+/// A NIF result, converted into what the NIF returns
+pub trait IntoReturn {
+    fn into_return(self) -> NifReturn<'static>;
+}
+
+impl<T: Into<NifReturn<'static>>> IntoReturn for T {
+    fn into_return(self) -> NifReturn<'static> {
+        self.into()
+    }
+}
+
+/// How a NIF reports the `NifError`s it returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorEncoding {
+    /// Raise `error:badarg`, `error:out_of_memory`, ...
+    #[default]
+    Raise,
+    /// Return `{error, Reason}`, with `Reason` from `NifError::reason`
+    Tuple,
+}
+
+impl ErrorEncoding {
+    /// Apply the encoding to a NIF's result
+    ///
+    /// Explicit `NifReturn::Raise` results are left alone.
+    pub fn apply<T: AtomTableOps>(self, result: NifReturn<'static>, table: &T) -> NifReturn<'static> {
+        match (self, result) {
+            (ErrorEncoding::Tuple, NifReturn::Badarg) => NifError::BadArg.to_error_tuple(table).into(),
+            (ErrorEncoding::Tuple, NifReturn::Error(error)) => error.to_error_tuple(table).into(),
+            (_, result) => result,
+        }
+    }
+}
+
+/// A function usable as a NIF
+///
+/// `Signature` is `fn(A1, A2, ...) -> R` for the arguments after the env;
+/// it only tells the implementations apart and is always inferred.
+pub trait Nif<Signature> {
+    /// Number of Erlang arguments
+    const ARITY: u32;
+
+    /// Decode `args` and run the function, reporting its errors as `errors`
+    ///
+    /// A wrong argument count or type always raises `badarg`: that is the
+    /// caller's mistake, not an outcome of the NIF.
+    fn invoke<T: AtomTableOps>(
+        &self,
+        env: &mut Env<'_>,
+        args: &[Term<'_>],
+        errors: ErrorEncoding,
+        table: &T,
+    ) -> NifReturn<'static>;
+}
+
+macro_rules! impl_nif {
+    ($arity:literal $(, $arg:ident)*) => {
+        impl<F, R $(, $arg)*> Nif<fn($($arg),*) -> R> for F
+        where
+            F: Fn(&mut Env<'_> $(, $arg)*) -> R,
+            R: IntoReturn,
+            $( $arg: FromTerm, )*
+        {
+            const ARITY: u32 = $arity;
+
+            #[allow(non_snake_case)]
+            fn invoke<T: AtomTableOps>(
+                &self,
+                env: &mut Env<'_>,
+                args: &[Term<'_>],
+                errors: ErrorEncoding,
+                table: &T,
+            ) -> NifReturn<'static> {
+                let [$($arg),*] = args else {
+                    return NifReturn::Badarg;
+                };
+                $(
+                    let $arg = match $arg::from_term(*$arg) {
+                        Ok(value) => value,
+                        Err(error) => return error.into(),
+                    };
+                )*
+                errors.apply(self(env $(, $arg)*).into_return(), table)
+            }
+        }
+    };
+}
+
+impl_nif!(0);
+impl_nif!(1, A1);
+impl_nif!(2, A1, A2);
+impl_nif!(3, A1, A2, A3);
+impl_nif!(4, A1, A2, A3, A4);
+impl_nif!(5, A1, A2, A3, A4, A5);
+impl_nif!(6, A1, A2, A3, A4, A5, A6);
+impl_nif!(7, A1, A2, A3, A4, A5, A6, A7);
+impl_nif!(8, A1, A2, A3, A4, A5, A6, A7, A8);
+
+/// The arity of a NIF function, usable in constants
+pub const fn arity_of<N: Nif<S>, S>(_nif: &N) -> u32 {
+    N::ARITY
+}
+
+/// Run a `typed` NIF for its `extern "C"` wrapper; used by `nif_collection!`
+///
+/// # Safety
+/// `ctx`, `argc` and `argv` must be the arguments of the current NIF call.
+pub unsafe fn call_nif<N: Nif<S>, S>(
+    nif: N,
+    errors: ErrorEncoding,
+    ctx: *mut Context,
+    argc: i32,
+    argv: *const Term,
+) -> Term<'static> {
+    let _scope = crate::panic::enter_nif(ctx);
+    let mut env = Env::from_raw(ctx);
+    let args = env.args(argc, argv);
+    let result = nif.invoke(&mut env, args, errors, &AtomTable::from_global());
+    let term = result.into_term(env.context());
+    Term::from_raw(term.raw())
+}
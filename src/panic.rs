@@ -0,0 +1,185 @@
+//! Turning Rust panics into Erlang errors
+//!
+//! A panic in a NIF or port handler would otherwise abort the whole VM.
+//! With the `panic-handler` feature this crate provides the
+//! `#[panic_handler]`: it logs the panic message with `log_error`, then
+//! blames the call that was running when it happened.
+//!
+//! - In a NIF, the calling process gets `error:{nif_panic, Reason}`, with
+//!   `Reason` the panic message as a binary.
+//! - In a port handler, only that port stops, with the same reason.
+//! - Anywhere else (a task, init code) the VM aborts as before.
+//!
+//! There is no unwinding in `no_std`, so the handler cannot return to the
+//! code that called the NIF by itself. It ends in `avmnif_panic_resume`,
+//! which the platform glue implements by jumping back to where it
+//! dispatched the NIF or port message (`setjmp`/`longjmp` around the
+//! dispatch) and returning the raised exception or terminating the port.
+//! Panics must not happen while Rust code holds locks the VM needs.
+//!
+//! The wrappers generated for `typed` and `replies` NIFs and for port
+//! handlers mark the running call with `enter_nif`/`enter_port`; raw
+//! `extern "C"` NIFs can do the same. Only the innermost call is tracked,
+//! which assumes one scheduler thread runs Rust code at a time.
+
+extern crate alloc;
+
+use crate::atom::AtomTableOps;
+use crate::log::LineBuffer;
+use crate::term::TermValue;
+use core::ffi::c_void;
+use core::fmt::Write;
+#[cfg(not(test))]
+use crate::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+/// Where a panic happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PanicSite {
+    /// Outside any tracked call
+    Other = 0,
+    /// In a NIF, on the calling process's context
+    Nif = 1,
+    /// In a port's message handler
+    Port = 2,
+}
+
+impl PanicSite {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => PanicSite::Nif,
+            2 => PanicSite::Port,
+            _ => PanicSite::Other,
+        }
+    }
+}
+
+#[cfg(not(test))]
+static SITE: AtomicU8 = AtomicU8::new(PanicSite::Other as u8);
+#[cfg(not(test))]
+static SITE_CTX: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(not(test))]
+fn load_site() -> (u8, usize) {
+    (SITE.load(Ordering::Acquire), SITE_CTX.load(Ordering::Acquire))
+}
+
+#[cfg(not(test))]
+fn store_site(site: u8, ctx: usize) {
+    SITE_CTX.store(ctx, Ordering::Release);
+    SITE.store(site, Ordering::Release);
+}
+
+// Tests run on parallel threads, so each one tracks its own calls
+#[cfg(test)]
+extern crate std;
+
+#[cfg(test)]
+std::thread_local! {
+    static SITE: core::cell::Cell<(u8, usize)> = const { core::cell::Cell::new((PanicSite::Other as u8, 0)) };
+}
+
+#[cfg(test)]
+fn load_site() -> (u8, usize) {
+    SITE.with(|site| site.get())
+}
+
+#[cfg(test)]
+fn store_site(site: u8, ctx: usize) {
+    SITE.with(|slot| slot.set((site, ctx)));
+}
+
+/// The call a panic would be blamed on, with its context pointer
+pub fn current_site() -> (PanicSite, *mut c_void) {
+    let (site, ctx) = load_site();
+    (PanicSite::from_u8(site), ctx as *mut c_void)
+}
+
+/// Marks a running NIF or port handler; restores the outer call when dropped
+#[must_use = "the call is only tracked while the scope is alive"]
+#[derive(Debug)]
+pub struct Scope {
+    site: u8,
+    ctx: usize,
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        store_site(self.site, self.ctx);
+    }
+}
+
+fn enter(site: PanicSite, ctx: *mut c_void) -> Scope {
+    let (outer_site, outer_ctx) = load_site();
+    store_site(site as u8, ctx as usize);
+    Scope {
+        site: outer_site,
+        ctx: outer_ctx,
+    }
+}
+
+/// Blame panics on the NIF running on `ctx` until the scope is dropped
+pub fn enter_nif(ctx: *mut crate::term::Context) -> Scope {
+    enter(PanicSite::Nif, ctx.cast())
+}
+
+/// Blame panics on the port `ctx` until the scope is dropped
+pub fn enter_port(ctx: *mut crate::context::Context) -> Scope {
+    enter(PanicSite::Port, ctx.cast())
+}
+
+/// `{nif_panic, Reason}`, with the panic message as a binary
+pub fn panic_reason<T: AtomTableOps>(message: &str, table: &T) -> TermValue {
+    TermValue::tuple(alloc::vec![
+        TermValue::atom("nif_panic", table),
+        TermValue::binary(message.as_bytes().to_vec()),
+    ])
+}
+
+/// The panic message and location, cut to one log line
+pub fn describe(info: &core::panic::PanicInfo<'_>) -> LineBuffer {
+    let mut line = LineBuffer::new();
+    let _ = write!(line, "{}", info);
+    line
+}
+
+#[cfg(all(feature = "panic-handler", not(test)))]
+mod handler {
+    use super::*;
+    use crate::atom::AtomTable;
+    use crate::term::raise_error;
+    use core::ffi::c_int;
+
+    // Panic FFI declarations
+    #[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+    extern "C" {
+        /// Leave the panicking call: return the raised exception from the
+        /// NIF, stop the port, or abort the VM for `PanicSite::Other`
+        ///
+        /// # Safety
+        ///
+        /// For `PanicSite::Nif` and `PanicSite::Port`, the glue must have
+        /// set a jump target (`setjmp`) around the dispatch of the call
+        /// `ctx` belongs to, and that dispatch must still be running; the
+        /// implementation `longjmp`s to it. Nothing between the target and
+        /// the panic may need its destructors run: the frames are skipped.
+        fn avmnif_panic_resume(site: c_int, ctx: *mut c_void) -> !;
+    }
+
+    #[panic_handler]
+    fn on_panic(info: &core::panic::PanicInfo<'_>) -> ! {
+        let message = describe(info);
+        crate::log::log_error(message.as_str());
+
+        let (site, ctx) = current_site();
+        // A panic while building the error must not be blamed on the call again
+        let _outside = enter(PanicSite::Other, core::ptr::null_mut());
+        if site == PanicSite::Nif {
+            let reason = panic_reason(message.as_str(), &AtomTable::from_global());
+            raise_error(unsafe { &mut *ctx.cast::<crate::term::Context>() }, &reason);
+        }
+        // SAFETY: the tracked call is still being dispatched by the glue,
+        // which set the jump target before calling into Rust
+        unsafe { avmnif_panic_resume(site as c_int, ctx) }
+    }
+}
@@ -0,0 +1,534 @@
+//! Terms read from Erlang syntax
+//!
+//! `parse_term` turns text such as `#{name => <<"x">>, id => 3}` into a
+//! `TermValue`, interning atoms through the table. Fixtures, config files
+//! and golden tests can be written the way the shell prints terms:
+//!
+//! - integers (`42`, `-7`, `16#ff`, `1_000`, `$a`) and floats (`1.5e3`)
+//! - atoms, bare or quoted (`ok`, `'Hello world'`)
+//! - strings (`"abc"`, a list of character codes) and binaries
+//!   (`<<"abc">>`, `<<1,2,3>>`, `<<"ab",0>>`)
+//! - tuples, lists (including `[H|T]`) and maps
+//! - `fun m:f/A`, and the printed forms of pids, ports and references
+//!   (`<0.7.0>`, `#Port<0.3>`, `#Ref<0.0.0.99>`) that `TermValue::display`
+//!   writes, so printed terms read back
+//!
+//! Whitespace and `%` comments are skipped, and a final `.` is allowed.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use avmnif_rs::parse::parse_term;
+//! # use avmnif_rs::term::TermValue;
+//! # let table = avmnif_rs::testing::mocks::MockAtomTable::new();
+//!
+//! let config = parse_term("#{rate => 115200, pins => {4, 5}}.", &table)?;
+//! # let rate = TermValue::atom("rate", &table);
+//! # assert_eq!(config.map_get(&rate), Some(&TermValue::int(115200)));
+//! # }
+//! # Ok::<(), avmnif_rs::parse::ParseError>(())
+//! ```
+
+extern crate alloc;
+
+use crate::atom::{AtomError, AtomIndex, AtomTableOps};
+use crate::term::{FunctionRef, NifError, PortId, ProcessId, RefId, TermValue};
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt;
+
+/// Maximum nesting depth accepted by the parser
+pub const MAX_DEPTH: usize = 256;
+
+/// Why text could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseReason {
+    /// Text ended in the middle of a term
+    UnexpectedEnd,
+    /// A character that cannot start or continue the term here
+    Unexpected(char),
+    /// Integer does not fit in a small int, or a byte in a binary is over 255
+    IntegerOverflow,
+    /// Unknown or malformed escape sequence
+    BadEscape,
+    /// Nesting exceeds `MAX_DEPTH`
+    TooDeep,
+    /// Atom could not be created
+    Atom(AtomError),
+}
+
+/// A parse failure and the byte offset where it happened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub offset: usize,
+    pub reason: ParseReason,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            ParseReason::UnexpectedEnd => write!(f, "unexpected end of input")?,
+            ParseReason::Unexpected(c) => write!(f, "unexpected {:?}", c)?,
+            ParseReason::IntegerOverflow => write!(f, "integer out of range")?,
+            ParseReason::BadEscape => write!(f, "bad escape sequence")?,
+            ParseReason::TooDeep => write!(f, "term nesting too deep")?,
+            ParseReason::Atom(e) => write!(f, "atom error: {}", e)?,
+        }
+        write!(f, " at offset {}", self.offset)
+    }
+}
+
+impl From<ParseError> for NifError {
+    fn from(error: ParseError) -> Self {
+        match error.reason {
+            ParseReason::Atom(AtomError::AllocationFailed) => NifError::OutOfMemory,
+            _ => NifError::BadArg,
+        }
+    }
+}
+
+/// Parse one term; only whitespace, comments and a final `.` may follow it
+pub fn parse_term<T: AtomTableOps>(text: &str, table: &T) -> Result<TermValue, ParseError> {
+    let mut parser = Parser { text, offset: 0, table };
+    let value = parser.term(0)?;
+    parser.skip_blank();
+    if parser.peek() == Some('.') {
+        parser.offset += 1;
+        parser.skip_blank();
+    }
+    match parser.peek() {
+        None => Ok(value),
+        Some(c) => Err(parser.error(ParseReason::Unexpected(c))),
+    }
+}
+
+struct Parser<'a, T> {
+    text: &'a str,
+    offset: usize,
+    table: &'a T,
+}
+
+impl<'a, T: AtomTableOps> Parser<'a, T> {
+    fn error(&self, reason: ParseReason) -> ParseError {
+        ParseError {
+            offset: self.offset,
+            reason,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.offset..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn next(&mut self) -> Result<char, ParseError> {
+        let c = self.peek().ok_or_else(|| self.error(ParseReason::UnexpectedEnd))?;
+        self.offset += c.len_utf8();
+        Ok(c)
+    }
+
+    fn unexpected(&self) -> ParseError {
+        match self.peek() {
+            Some(c) => self.error(ParseReason::Unexpected(c)),
+            None => self.error(ParseReason::UnexpectedEnd),
+        }
+    }
+
+    fn skip_blank(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.offset += rest.len() - trimmed.len();
+            if !trimmed.starts_with('%') {
+                return;
+            }
+            self.offset += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    /// Skip blanks, then consume `token` if it comes next
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_blank();
+        if self.rest().starts_with(token) {
+            self.offset += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn term(&mut self, depth: usize) -> Result<TermValue, ParseError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error(ParseReason::TooDeep));
+        }
+        self.skip_blank();
+        let rest = self.rest();
+        match self.peek() {
+            None => Err(self.error(ParseReason::UnexpectedEnd)),
+            Some('{') => {
+                self.offset += 1;
+                let elements = self.sequence("}", depth)?;
+                Ok(TermValue::Tuple(elements))
+            }
+            Some('[') => {
+                self.offset += 1;
+                self.list(depth)
+            }
+            Some('"') => self.strings(),
+            Some('\'') => {
+                let name = self.quoted('\'')?;
+                self.atom(&name)
+            }
+            Some('$') => {
+                self.offset += 1;
+                let c = self.character()?;
+                Ok(TermValue::SmallInt(c as i32))
+            }
+            Some('-') | Some('0'..='9') => self.number(),
+            Some(_) if rest.starts_with("<<") => {
+                self.offset += 2;
+                self.binary()
+            }
+            Some(_) if rest.starts_with("#{") => {
+                self.offset += 2;
+                self.map(depth)
+            }
+            Some(_) if rest.starts_with("#Port<") => {
+                self.offset += "#Port<".len();
+                let [_, id] = self.dotted::<2>()?;
+                Ok(TermValue::Port(PortId(self.narrow(id)?)))
+            }
+            Some(_) if rest.starts_with("#Ref<") => {
+                self.offset += "#Ref<".len();
+                let [_, _, _, ticks] = self.dotted::<4>()?;
+                Ok(TermValue::Reference(RefId(ticks)))
+            }
+            Some('<') => {
+                self.offset += 1;
+                let [_, id, _] = self.dotted::<3>()?;
+                Ok(TermValue::Pid(ProcessId(self.narrow(id)?)))
+            }
+            Some(c) if c.is_ascii_lowercase() => {
+                let name = self.bare_name();
+                if name == "fun" {
+                    self.fun()
+                } else {
+                    self.atom(name)
+                }
+            }
+            Some(c) => Err(self.error(ParseReason::Unexpected(c))),
+        }
+    }
+
+    /// Comma-separated terms up to `close`
+    fn sequence(&mut self, close: &str, depth: usize) -> Result<Vec<TermValue>, ParseError> {
+        let mut elements = Vec::new();
+        if self.eat(close) {
+            return Ok(elements);
+        }
+        loop {
+            elements.push(self.term(depth + 1)?);
+            if self.eat(close) {
+                return Ok(elements);
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn list(&mut self, depth: usize) -> Result<TermValue, ParseError> {
+        let mut elements = Vec::new();
+        let mut tail = TermValue::Nil;
+        if !self.eat("]") {
+            loop {
+                elements.push(self.term(depth + 1)?);
+                if self.eat("|") {
+                    tail = self.term(depth + 1)?;
+                    self.expect("]")?;
+                    break;
+                }
+                if self.eat("]") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        Ok(elements
+            .into_iter()
+            .rev()
+            .fold(tail, |tail, head| TermValue::List(Box::new(head), Box::new(tail))))
+    }
+
+    fn map(&mut self, depth: usize) -> Result<TermValue, ParseError> {
+        let mut pairs = Vec::new();
+        if !self.eat("}") {
+            loop {
+                let key = self.term(depth + 1)?;
+                self.expect("=>")?;
+                pairs.push((key, self.term(depth + 1)?));
+                if self.eat("}") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        Ok(TermValue::Map(pairs))
+    }
+
+    fn binary(&mut self) -> Result<TermValue, ParseError> {
+        let mut bytes = Vec::new();
+        if self.eat(">>") {
+            return Ok(TermValue::Binary(bytes));
+        }
+        loop {
+            self.skip_blank();
+            if self.peek() == Some('"') {
+                for c in self.quoted('"')?.chars() {
+                    bytes.push(u8::try_from(c as u32).map_err(|_| self.error(ParseReason::IntegerOverflow))?);
+                }
+            } else {
+                let start = self.offset;
+                let value = match self.number()? {
+                    TermValue::SmallInt(value) => u8::try_from(value).ok(),
+                    _ => None,
+                };
+                let byte = value.ok_or(ParseError {
+                    offset: start,
+                    reason: ParseReason::IntegerOverflow,
+                })?;
+                bytes.push(byte);
+            }
+            if self.eat(">>") {
+                return Ok(TermValue::Binary(bytes));
+            }
+            self.expect(",")?;
+        }
+    }
+
+    /// One or more adjacent string literals, as a list of character codes
+    fn strings(&mut self) -> Result<TermValue, ParseError> {
+        let mut text = String::new();
+        loop {
+            text.push_str(&self.quoted('"')?);
+            self.skip_blank();
+            if self.peek() != Some('"') {
+                break;
+            }
+        }
+        let chars: Vec<_> = text.chars().collect();
+        Ok(chars.into_iter().rev().fold(TermValue::Nil, |tail, c| {
+            TermValue::List(Box::new(TermValue::SmallInt(c as i32)), Box::new(tail))
+        }))
+    }
+
+    fn quoted(&mut self, quote: char) -> Result<String, ParseError> {
+        self.offset += quote.len_utf8();
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                Some(c) if c == quote => {
+                    self.offset += c.len_utf8();
+                    return Ok(text);
+                }
+                Some(_) => text.push(self.character()?),
+                None => return Err(self.error(ParseReason::UnexpectedEnd)),
+            }
+        }
+    }
+
+    /// One character of a literal, with escapes resolved
+    fn character(&mut self) -> Result<char, ParseError> {
+        let c = self.next()?;
+        if c != '\\' {
+            return Ok(c);
+        }
+        let bad = self.error(ParseReason::BadEscape);
+        let escaped = match self.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            's' => ' ',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'v' => '\u{b}',
+            'e' => '\u{1b}',
+            'd' => '\u{7f}',
+            '^' => {
+                let c = self.next()?;
+                char::from_u32(c as u32 % 32).ok_or(bad)?
+            }
+            'x' => {
+                let digits = if self.peek() == Some('{') {
+                    self.offset += 1;
+                    let end = self.rest().find('}').ok_or(bad.clone())?;
+                    let digits = &self.text[self.offset..self.offset + end];
+                    self.offset += end + 1;
+                    digits
+                } else {
+                    let digits = self.rest().get(..2).ok_or(bad.clone())?;
+                    self.offset += 2;
+                    digits
+                };
+                let code = u32::from_str_radix(digits, 16).map_err(|_| bad.clone())?;
+                char::from_u32(code).ok_or(bad)?
+            }
+            c @ '0'..='7' => {
+                let mut code = c as u32 - '0' as u32;
+                for _ in 0..2 {
+                    match self.peek() {
+                        Some(d @ '0'..='7') => {
+                            code = code * 8 + (d as u32 - '0' as u32);
+                            self.offset += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                char::from_u32(code).ok_or(bad)?
+            }
+            c => c,
+        };
+        Ok(escaped)
+    }
+
+    fn bare_name(&mut self) -> &'a str {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '@'))
+            .unwrap_or(rest.len());
+        let start = self.offset;
+        self.offset += len;
+        &self.text[start..start + len]
+    }
+
+    fn atom(&self, name: &str) -> Result<TermValue, ParseError> {
+        let index = self.table.ensure_atom_str(name).map_err(|e| self.error(ParseReason::Atom(e)))?;
+        Ok(TermValue::Atom(index))
+    }
+
+    /// A bare or quoted atom's index, for the parts of a fun
+    fn atom_index(&mut self) -> Result<AtomIndex, ParseError> {
+        self.skip_blank();
+        let name = match self.peek() {
+            Some('\'') => self.quoted('\'')?,
+            Some(c) if c.is_ascii_lowercase() => String::from(self.bare_name()),
+            _ => return Err(self.unexpected()),
+        };
+        self.table
+            .ensure_atom_str(&name)
+            .map_err(|e| self.error(ParseReason::Atom(e)))
+    }
+
+    /// `fun M:F/A`, after `fun`
+    fn fun(&mut self) -> Result<TermValue, ParseError> {
+        let module = self.atom_index()?;
+        self.expect(":")?;
+        let function = self.atom_index()?;
+        self.expect("/")?;
+        self.skip_blank();
+        let start = self.offset;
+        let arity = self.digits(10)?;
+        let arity = u8::try_from(arity).map_err(|_| ParseError {
+            offset: start,
+            reason: ParseReason::IntegerOverflow,
+        })?;
+        Ok(TermValue::Function(FunctionRef {
+            module,
+            function,
+            arity,
+        }))
+    }
+
+    /// `N.N...>` with `COUNT` numbers, after the opening `<`
+    fn dotted<const COUNT: usize>(&mut self) -> Result<[u64; COUNT], ParseError> {
+        let mut parts = [0; COUNT];
+        for (i, part) in parts.iter_mut().enumerate() {
+            if i > 0 {
+                self.expect(".")?;
+            }
+            *part = self.digits(10)?;
+        }
+        self.expect(">")?;
+        Ok(parts)
+    }
+
+    fn narrow(&self, value: u64) -> Result<u32, ParseError> {
+        u32::try_from(value).map_err(|_| self.error(ParseReason::IntegerOverflow))
+    }
+
+    /// Digits in `radix`, with `_` separators between them
+    fn digits(&mut self, radix: u32) -> Result<u64, ParseError> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_digit(radix) || c == '_'))
+            .unwrap_or(rest.len());
+        let text = &rest[..len];
+        if text.is_empty() || text.starts_with('_') || text.ends_with('_') || text.contains("__") {
+            return Err(self.unexpected());
+        }
+        let mut value: u64 = 0;
+        for digit in text.chars().filter_map(|c| c.to_digit(radix)) {
+            value = value
+                .checked_mul(radix as u64)
+                .and_then(|value| value.checked_add(digit as u64))
+                .ok_or_else(|| self.error(ParseReason::IntegerOverflow))?;
+        }
+        self.offset += len;
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<TermValue, ParseError> {
+        let start = self.offset;
+        let negative = self.rest().starts_with('-');
+        if negative {
+            self.offset += 1;
+        }
+        let mut magnitude = self.digits(10)?;
+
+        if self.rest().starts_with('#') {
+            let radix = u32::try_from(magnitude)
+                .ok()
+                .filter(|radix| (2..=36).contains(radix))
+                .ok_or_else(|| self.unexpected())?;
+            self.offset += 1;
+            magnitude = self.digits(radix)?;
+        } else if self.rest().starts_with('.') && self.rest()[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            self.offset += 1;
+            self.digits(10)?;
+            if self.rest().starts_with(['e', 'E']) {
+                self.offset += 1;
+                if self.rest().starts_with(['-', '+']) {
+                    self.offset += 1;
+                }
+                self.digits(10)?;
+            }
+            let text: String = self.text[start..self.offset].chars().filter(|&c| c != '_').collect();
+            return text.parse().map(TermValue::Float).map_err(|_| ParseError {
+                offset: start,
+                reason: ParseReason::Unexpected('.'),
+            });
+        }
+
+        let value = if negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        };
+        value
+            .and_then(|value| i32::try_from(value).ok())
+            .map(TermValue::SmallInt)
+            .ok_or(ParseError {
+                offset: start,
+                reason: ParseReason::IntegerOverflow,
+            })
+    }
+}
@@ -7,29 +7,86 @@
 //! All operations work with any AtomTableOps implementation through dependency injection.
 //! No global state, no hardcoded dependencies.
 
-use crate::term::{Term, NifError, TermValue};
+use crate::term::{Term, NifError, TermValue, ProcessId, PortId, HeapGuard};
 use crate::context::{Context, GlobalContext, ContextExt, PlatformData, PortBuilder};
-use crate::atom::{AtomTableOps, AtomTable};
-use core::ffi::{c_void, c_char, c_int};
+use crate::atom::{AtomTableOps, AtomTable, AtomError, AtomIndex};
+use core::ffi::c_char;
+
+pub mod capture;
+pub mod timer;
+pub mod call;
+pub mod behavior;
+pub mod trace;
+pub mod policy;
+pub mod timeout;
+pub mod isr_queue;
+pub mod irq;
+pub mod polling;
+pub mod gpio;
+pub mod bus;
+pub mod stream;
 
 // Suppress warnings for unused items since this is a library
 #[allow(unused_imports)]
 use alloc::boxed::Box;
 
-// AtomVM port types (reuse from context module)
-pub type ErlNifEnv = c_void;
-pub type ERL_NIF_TERM = u64;
+// AtomVM port types
+pub use avmnif_sys::{ErlNifEnv, Message, ERL_NIF_TERM};
 
-/// Port message type
-pub type Message = c_void;
+/// Result of handling a port message
+///
+/// Handlers return replies as values instead of sending them; the wrapper
+/// generated by `port_collection!` delivers them to the caller.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PortResult {
+    /// Keep running, nothing to send
+    Continue,
+    /// Stop the port
+    Terminate,
+    /// Reply to the caller with this term and keep running
+    Reply(TermValue),
+    /// Reply `{error, Reason}` to the caller and keep running
+    ReplyError(TermValue),
+    /// Reply `{error, Reason}` to the caller, then stop the port
+    TerminateWithReason(TermValue),
+}
 
-/// Port result enum
+/// Port result as returned to AtomVM across the FFI boundary
 #[repr(C)]
-pub enum PortResult {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NativePortResult {
     Continue = 0,
     Terminate = 1,
 }
 
+impl PortResult {
+    /// Check whether the port stops after this result
+    pub fn is_terminate(&self) -> bool {
+        matches!(self, PortResult::Terminate | PortResult::TerminateWithReason(_))
+    }
+
+    /// The term to send back to the caller, if any
+    pub fn reply_term<T: AtomTableOps>(&self, table: &T) -> Result<Option<TermValue>, AtomError> {
+        match self {
+            PortResult::Continue | PortResult::Terminate => Ok(None),
+            PortResult::Reply(reply) => Ok(Some(reply.clone())),
+            PortResult::ReplyError(reason) | PortResult::TerminateWithReason(reason) => {
+                let error = table.ensure_atom_str("error")?;
+                Ok(Some(TermValue::Tuple(alloc::vec![TermValue::Atom(error), reason.clone()])))
+            }
+        }
+    }
+
+    /// The continue/terminate decision reported to AtomVM
+    pub fn to_native(&self) -> NativePortResult {
+        if self.is_terminate() {
+            NativePortResult::Terminate
+        } else {
+            NativePortResult::Continue
+        }
+    }
+}
+
 /// Port driver function type signatures
 pub type PortInitFn = fn(&mut GlobalContext);
 pub type PortDestroyFn = fn(&mut GlobalContext);  
@@ -38,7 +95,7 @@ pub type PortHandlerFn = fn(&mut Context, &Message) -> PortResult;
 
 /// C-compatible function types for FFI boundary
 type CPortCreateFn = extern "C" fn(*const GlobalContext, ERL_NIF_TERM) -> *mut Context;
-type CPortHandlerFn = extern "C" fn(*mut Context, *const Message) -> PortResult;
+type CPortHandlerFn = extern "C" fn(*mut Context, *const Message) -> NativePortResult;
 
 /// Port driver registration structure
 #[repr(C)]
@@ -53,30 +110,10 @@ pub struct AtomVMPortDriver {
 unsafe impl Sync for AtomVMPortDriver {}
 
 // AtomVM Port API FFI declarations
-extern "C" {
-    /// Send a reply to an Erlang process from port context
-    pub fn port_send_reply(
-        ctx: *mut Context,
-        pid: ERL_NIF_TERM,
-        reference: ERL_NIF_TERM,
-        reply: ERL_NIF_TERM,
-    );
-    
-    /// Send an async message to an Erlang process from any context (ISR-safe)
-    pub fn port_send_message_from_task(
-        global: *mut GlobalContext,
-        pid: u32,
-        message: ERL_NIF_TERM,
-    );
-    
-    /// Parse a generic port message into components
-    pub fn parse_port_message(
-        message: *const Message,
-        pid: *mut ERL_NIF_TERM,
-        reference: *mut ERL_NIF_TERM,
-        command: *mut ERL_NIF_TERM,
-    ) -> c_int;
-}
+pub use avmnif_sys::{
+    parse_port_message, port_demonitor_process, port_get_id, port_message_term, port_monitor_process,
+    port_send_external_reply, port_send_external_term, port_send_message_from_task, port_send_reply,
+};
 
 /// Register a port collection with AtomVM
 /// 
@@ -107,25 +144,48 @@ macro_rules! port_collection {
                 global: *const $crate::context::GlobalContext,
                 opts: $crate::port::ERL_NIF_TERM
             ) -> *mut $crate::context::Context {
+                $crate::contracts::non_null(global, "port create called with null global context");
                 let global_ref = unsafe { &*global };
-                let opts_term = $crate::term::Term::from_raw(opts.try_into().unwrap());
+                let opts_term = $crate::term::Term::from_raw(opts);
                 $create_port_fn(global_ref, opts_term)
             }
             
             extern "C" fn [<$handler_fn _wrapper>](
                 ctx: *mut $crate::context::Context,
                 message: *const $crate::port::Message
-            ) -> $crate::port::PortResult {
+            ) -> $crate::port::NativePortResult {
+                $crate::contracts::non_null(ctx, "port handler called with null context");
+                $crate::contracts::non_null(message, "port handler called with null message");
+                let _scope = $crate::panic::enter_port(ctx);
                 let ctx_ref = unsafe { &mut *ctx };
                 let message_ref = unsafe { &*message };
-                $handler_fn(ctx_ref, message_ref)
+                let result = $handler_fn(ctx_ref, message_ref);
+                $crate::port::capture::capture_port_call(message_ref, &result);
+                $crate::port::complete_port_result(ctx_ref, message_ref, result)
             }
             
+            // Init runs once until destroy, however often the host calls it
+            static [<$port_name:upper _INIT>]: $crate::registry::InitGuard = $crate::registry::InitGuard::new();
+
+            fn [<$init_fn _once>](global: &mut $crate::context::GlobalContext) {
+                if ![<$port_name:upper _INIT>].begin() {
+                    $crate::registry::repeated_init(concat!(stringify!($port_name), "_init"));
+                    return;
+                }
+                $init_fn(global);
+                [<$port_name:upper _INIT>].finish(true);
+            }
+
+            fn [<$destroy_fn _once>](global: &mut $crate::context::GlobalContext) {
+                $destroy_fn(global);
+                [<$port_name:upper _INIT>].reset();
+            }
+
             // Create the port driver structure using wrapper functions
             static [<$port_name:upper _PORT_DRIVER>]: $crate::port::AtomVMPortDriver = $crate::port::AtomVMPortDriver {
                 name: concat!(stringify!($port_name), "\0").as_ptr() as *const core::ffi::c_char,
-                init: Some($init_fn),
-                destroy: Some($destroy_fn),
+                init: Some([<$init_fn _once>]),
+                destroy: Some([<$destroy_fn _once>]),
                 create_port: [<$create_port_fn _wrapper>],
                 message_handler: [<$handler_fn _wrapper>],
             };
@@ -135,18 +195,24 @@ macro_rules! port_collection {
             pub extern "C" fn [<$port_name _port_driver_init>]() -> *const $crate::port::AtomVMPortDriver {
                 &[<$port_name:upper _PORT_DRIVER>]
             }
+
+            $crate::port_driver_registration!(
+                $port_name,
+                init = [<$port_name _init>] as *const core::ffi::c_void,
+                destroy = [<$port_name _destroy>] as *const core::ffi::c_void
+            );
             
             // Export individual functions for debugging/testing
             #[no_mangle]
             pub extern "C" fn [<$port_name _init>](global: *mut $crate::context::GlobalContext) {
                 let global_ref = unsafe { &mut *global };
-                $init_fn(global_ref);
+                [<$init_fn _once>](global_ref);
             }
             
             #[no_mangle]
             pub extern "C" fn [<$port_name _destroy>](global: *mut $crate::context::GlobalContext) {
                 let global_ref = unsafe { &mut *global };
-                $destroy_fn(global_ref);
+                [<$destroy_fn _once>](global_ref);
             }
             
             #[no_mangle]
@@ -161,7 +227,7 @@ macro_rules! port_collection {
             pub extern "C" fn [<$port_name _message_handler>](
                 ctx: *mut $crate::context::Context,
                 message: *const $crate::port::Message
-            ) -> $crate::port::PortResult {
+            ) -> $crate::port::NativePortResult {
                 [<$handler_fn _wrapper>](ctx, message)
             }
         }
@@ -179,18 +245,24 @@ macro_rules! port_collection {
                 global: *const $crate::context::GlobalContext,
                 opts: $crate::port::ERL_NIF_TERM
             ) -> *mut $crate::context::Context {
+                $crate::contracts::non_null(global, "port create called with null global context");
                 let global_ref = unsafe { &*global };
-                let opts_term = $crate::term::Term::from_raw(opts.try_into().unwrap());
+                let opts_term = $crate::term::Term::from_raw(opts);
                 $create_port_fn(global_ref, opts_term)
             }
             
             extern "C" fn [<$handler_fn _wrapper>](
                 ctx: *mut $crate::context::Context,
                 message: *const $crate::port::Message
-            ) -> $crate::port::PortResult {
+            ) -> $crate::port::NativePortResult {
+                $crate::contracts::non_null(ctx, "port handler called with null context");
+                $crate::contracts::non_null(message, "port handler called with null message");
+                let _scope = $crate::panic::enter_port(ctx);
                 let ctx_ref = unsafe { &mut *ctx };
                 let message_ref = unsafe { &*message };
-                $handler_fn(ctx_ref, message_ref)
+                let result = $handler_fn(ctx_ref, message_ref);
+                $crate::port::capture::capture_port_call(message_ref, &result);
+                $crate::port::complete_port_result(ctx_ref, message_ref, result)
             }
             
             static [<$port_name:upper _PORT_DRIVER>]: $crate::port::AtomVMPortDriver = $crate::port::AtomVMPortDriver {
@@ -205,6 +277,12 @@ macro_rules! port_collection {
             pub extern "C" fn [<$port_name _port_driver_init>]() -> *const $crate::port::AtomVMPortDriver {
                 &[<$port_name:upper _PORT_DRIVER>]
             }
+
+            $crate::port_driver_registration!(
+                $port_name,
+                init = core::ptr::null(),
+                destroy = core::ptr::null()
+            );
             
             #[no_mangle]
             pub extern "C" fn [<$port_name _create_port>](
@@ -218,24 +296,82 @@ macro_rules! port_collection {
             pub extern "C" fn [<$port_name _message_handler>](
                 ctx: *mut $crate::context::Context,
                 message: *const $crate::port::Message
-            ) -> $crate::port::PortResult {
+            ) -> $crate::port::NativePortResult {
                 [<$handler_fn _wrapper>](ctx, message)
             }
         }
     };
 }
 
+/// Register the driver generated by `port_collection!` with AtomVM
+///
+/// Emits `<port>_port_register()`, which calls
+/// `REGISTER_PORT_DRIVER(name, init, destroy, create_port)` with the
+/// driver's exports, the arguments of AtomVM's C macro of that name, and a
+/// `.port_collection` link-section record pointing at it so the VM
+/// registers the driver at startup, the same way `nif_collection!` does
+/// for NIFs. `init` and `destroy` are null for drivers without them. On
+/// wasm32 the record is left out; see `register_port_collections!`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! port_driver_registration {
+    ($port_name:ident, init = $init:expr, destroy = $destroy:expr) => {
+        paste::paste! {
+            #[cfg_attr(target_arch = "wasm32", no_mangle)]
+            pub extern "C" fn [<$port_name _port_register>]() {
+                $crate::registration_call!(REGISTER_PORT_DRIVER(
+                    concat!(stringify!($port_name), "\0").as_ptr(),
+                    $init,
+                    $destroy,
+                    [<$port_name _create_port>] as *const core::ffi::c_void
+                ));
+            }
+
+            $crate::registration_entry!(
+                [<_ $port_name:upper _PORT_REGISTER>] = [<$port_name _port_register>],
+                section = ".port_collection",
+                macos_section = "__DATA,.port_collection"
+            );
+        }
+    };
+}
+
+/// Export a single entry point that registers several port drivers
+///
+/// The port counterpart of `register_nif_collections!`: on wasm32 it
+/// generates `avmnif_register_port_collections()` for the host to call at
+/// startup, and elsewhere it expands to nothing.
+///
+/// ```rust,ignore
+/// port_collection!(gpio, create_port = gpio_create, handler = gpio_handler);
+/// port_collection!(uart, create_port = uart_create, handler = uart_handler);
+///
+/// register_port_collections!(gpio, uart);
+/// ```
+#[macro_export]
+macro_rules! register_port_collections {
+    ( $( $port_name:ident ),* $(,)? ) => {
+        paste::paste! {
+            #[cfg(target_arch = "wasm32")]
+            #[no_mangle]
+            pub extern "C" fn avmnif_register_port_collections() {
+                $( [<$port_name _port_register>](); )*
+            }
+        }
+    };
+}
+
 /// Helper functions for port message handling
 
 /// Parse a generic port message into its components
-pub fn parse_gen_message(message: &Message) -> Result<(Term, Term, Term), NifError> {
-    let mut pid: u64 = 0;
-    let mut reference: u64 = 0;
-    let mut command: u64 = 0;
+pub fn parse_gen_message(message: &Message) -> Result<(Term<'_>, Term<'_>, Term<'_>), NifError> {
+    let mut pid: ERL_NIF_TERM = 0;
+    let mut reference: ERL_NIF_TERM = 0;
+    let mut command: ERL_NIF_TERM = 0;
     
     let result = unsafe {
         parse_port_message(
-            message as *const _ as *const c_void,
+            message,
             &mut pid,
             &mut reference,
             &mut command,
@@ -244,38 +380,229 @@ pub fn parse_gen_message(message: &Message) -> Result<(Term, Term, Term), NifErr
     
     if result != 0 {
         Ok((
-            Term::from_raw(pid.try_into().unwrap()),
-            Term::from_raw(reference.try_into().unwrap()),
-            Term::from_raw(command.try_into().unwrap()),
+            Term::from_raw(pid),
+            Term::from_raw(reference),
+            Term::from_raw(command),
         ))
     } else {
         Err(NifError::BadArg)
     }
 }
 
+/// The term carried by a port message, whatever its shape
+pub fn message_term(message: &Message) -> Term<'_> {
+    let term = unsafe { port_message_term(message as *const Message) };
+    Term::from_raw(term as usize)
+}
+
 /// Send a reply to an Erlang process
 pub fn send_reply(ctx: &Context, pid: Term, reference: Term, reply: Term) {
     unsafe {
         port_send_reply(
             ctx as *const _ as *mut Context,
-            pid.raw().try_into().unwrap(),
-            reference.raw().try_into().unwrap(),
-            reply.raw().try_into().unwrap(),
+            pid.raw(),
+            reference.raw(),
+            reply.raw(),
         );
     }
 }
 
+/// Reply to the caller of a port message with a structured term
+pub fn send_reply_value<T: AtomTableOps>(
+    ctx: &Context,
+    pid: Term,
+    reference: Term,
+    reply: &TermValue,
+    table: &T,
+) -> Result<(), NifError> {
+    let data = crate::etf::encode(reply, table)?;
+    let delivered = unsafe {
+        port_send_external_reply(
+            ctx as *const _ as *mut Context,
+            pid.raw() as ERL_NIF_TERM,
+            reference.raw() as ERL_NIF_TERM,
+            data.as_ptr(),
+            data.len(),
+        )
+    };
+    if delivered != 0 {
+        Ok(())
+    } else {
+        Err(NifError::Other("reply not delivered"))
+    }
+}
+
+/// Deliver the reply carried by a handler result and report continue/terminate
+///
+/// Called by the `port_collection!` wrapper after every handler invocation.
+/// A reply that cannot be built or delivered is dropped; the port keeps the
+/// continue/terminate decision of the handler.
+pub fn complete_port_result(ctx: &mut Context, message: &Message, result: PortResult) -> NativePortResult {
+    let table = AtomTable::from_global();
+    complete_port_result_with(&mut AtomVMMessageBackend::new(ctx), message, result, &table)
+}
+
+/// As `complete_port_result`, delivering the reply through `backend`
+pub fn complete_port_result_with<B: MessageBackend, T: AtomTableOps>(
+    backend: &mut B,
+    message: &Message,
+    result: PortResult,
+    table: &T,
+) -> NativePortResult {
+    if let Ok(Some(reply)) = result.reply_term(table) {
+        if let Ok((pid, reference, _)) = parse_gen_message(message) {
+            let _ = backend.reply(pid, reference, &reply);
+        }
+    }
+    result.to_native()
+}
+
 /// Send an async message to an Erlang process (ISR-safe)
+///
+/// `message` must live on a heap that stays valid until the call returns;
+/// build it in an `OwnedEnv` and use `OwnedEnv::send_and_clear`.
 pub fn send_async_message(pid: u32, message: Term) {
     unsafe {
         port_send_message_from_task(
             crate::context::get_global_context(),
             pid,
-            message.raw().try_into().unwrap(),
+            message.raw(),
         );
     }
 }
 
+/// Send a structured message to an Erlang process
+///
+/// The term is serialized to ETF and rebuilt on the receiver's heap, so it
+/// may contain tuples, lists, maps and binaries rather than only a pre-built
+/// raw term. Atoms are resolved by name through `table`.
+pub fn send<T: AtomTableOps>(
+    ctx: &Context,
+    pid: u32,
+    message: &TermValue,
+    table: &T,
+) -> Result<(), NifError> {
+    let global = unsafe { crate::context::context_get_global(ctx as *const Context) };
+    send_external(global, pid, message, table)
+}
+
+/// Send a structured message from a background task (no context needed)
+pub fn send_from_task<T: AtomTableOps>(
+    pid: u32,
+    message: &TermValue,
+    table: &T,
+) -> Result<(), NifError> {
+    send_external(crate::context::get_global_context(), pid, message, table)
+}
+
+/// Send many events to `pid` as one list message, `[E1, E2, ...]`
+///
+/// One message per batch instead of one per event: the whole batch is
+/// encoded into a single buffer and copied to the receiver's heap in one
+/// allocation. Nothing is sent for an empty batch.
+pub fn send_event_batch<T: AtomTableOps>(
+    ctx: &Context,
+    pid: u32,
+    events: &[TermValue],
+    table: &T,
+) -> Result<(), NifError> {
+    let mut batch = crate::etf::ListEncoder::with_capacity(events.len() * 8);
+    for event in events {
+        batch.push(event, table)?;
+    }
+    send_batch(ctx, pid, &mut batch)
+}
+
+/// Send the events collected in `batch` as one list message and clear it
+///
+/// Nothing is sent if the batch is empty. The batch is cleared even if
+/// the message could not be delivered.
+pub fn send_batch(ctx: &Context, pid: u32, batch: &mut crate::etf::ListEncoder) -> Result<(), NifError> {
+    let global = unsafe { crate::context::context_get_global(ctx as *const Context) };
+    send_batch_external(global, pid, batch)
+}
+
+/// As `send_batch`, from a background task
+pub fn send_batch_from_task(pid: u32, batch: &mut crate::etf::ListEncoder) -> Result<(), NifError> {
+    send_batch_external(crate::context::get_global_context(), pid, batch)
+}
+
+/// Where a port's replies and messages go
+///
+/// Code that sends through a `MessageBackend` rather than the functions
+/// above can be run against a mock process in tests, which collects what
+/// was sent in its mailbox. `AtomVMMessageBackend` hands everything to the VM.
+pub trait MessageBackend {
+    /// Reply to the call from `pid` tagged with `reference`, as `send_reply_value`
+    fn reply(&mut self, pid: Term, reference: Term, reply: &TermValue) -> Result<(), NifError>;
+
+    /// Send a structured message to `pid`, as `send`
+    fn send(&mut self, pid: u32, message: &TermValue) -> Result<(), NifError>;
+
+    /// Send an already built term to `pid`, as `send_async_message`
+    fn send_term(&mut self, pid: u32, message: Term);
+}
+
+/// Message backend of a port context, resolving atoms in the VM's table
+pub struct AtomVMMessageBackend<'a> {
+    ctx: &'a Context,
+}
+
+impl<'a> AtomVMMessageBackend<'a> {
+    pub fn new(ctx: &'a Context) -> Self {
+        Self { ctx }
+    }
+}
+
+impl MessageBackend for AtomVMMessageBackend<'_> {
+    fn reply(&mut self, pid: Term, reference: Term, reply: &TermValue) -> Result<(), NifError> {
+        send_reply_value(self.ctx, pid, reference, reply, &AtomTable::from_global())
+    }
+
+    fn send(&mut self, pid: u32, message: &TermValue) -> Result<(), NifError> {
+        send(self.ctx, pid, message, &AtomTable::from_global())
+    }
+
+    fn send_term(&mut self, pid: u32, message: Term) {
+        send_async_message(pid, message)
+    }
+}
+
+fn send_batch_external(
+    global: *mut GlobalContext,
+    pid: u32,
+    batch: &mut crate::etf::ListEncoder,
+) -> Result<(), NifError> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+    let delivered = {
+        let data = batch.finish();
+        unsafe { port_send_external_term(global, pid, data.as_ptr(), data.len()) }
+    };
+    batch.clear();
+    if delivered != 0 {
+        Ok(())
+    } else {
+        Err(NifError::Other("message not delivered"))
+    }
+}
+
+fn send_external<T: AtomTableOps>(
+    global: *mut GlobalContext,
+    pid: u32,
+    message: &TermValue,
+    table: &T,
+) -> Result<(), NifError> {
+    let data = crate::etf::encode(message, table)?;
+    let delivered = unsafe { port_send_external_term(global, pid, data.as_ptr(), data.len()) };
+    if delivered != 0 {
+        Ok(())
+    } else {
+        Err(NifError::Other("message not delivered"))
+    }
+}
+
 /// Trait for port data types to implement cleanup and message handling
 pub trait PortData: PlatformData {
     /// Called when the port receives a message
@@ -299,6 +626,26 @@ pub trait PortData: PlatformData {
     
     /// Activate/deactivate the port
     fn set_active(&mut self, _active: bool) {}
+    
+    /// Called after the port was handed to a new owner (`port_connect/2`)
+    fn owner_changed(&mut self, _old_owner: Option<u32>, _new_owner: u32) {}
+
+    /// Whether `caller` may run the command named `command`
+    ///
+    /// Asked before any command is dispatched, the standard ones included;
+    /// a rejected command is answered with `{error, not_allowed}`. See
+    /// `port::policy`.
+    fn allow_command(&self, _caller: u32, _command: AtomIndex) -> bool {
+        true
+    }
+
+    /// Time limits for the commands passed to `handle_message`
+    ///
+    /// A command that runs longer is answered with `{error, timeout}`. See
+    /// `port::timeout`.
+    fn command_timeouts(&self) -> Option<&timeout::CommandTimeouts> {
+        None
+    }
 }
 
 /// Generic port data wrapper with standard functionality
@@ -324,12 +671,42 @@ impl<T: PortData> GenericPortData<T> {
         self.inner.set_owner_pid(pid);
     }
     
+    /// Transfer ownership to `new_owner`, returning the previous owner
+    ///
+    /// Only updates the data; `connect_port` also moves the monitor and
+    /// notifies the previous owner.
+    pub fn connect(&mut self, new_owner: u32) -> PortOpResult<Option<u32>> {
+        if !self.active {
+            return Err(PortError::PortInactive);
+        }
+        if new_owner == 0 {
+            return Err(PortError::InvalidMessage);
+        }
+        let old_owner = self.get_owner_pid();
+        self.owner_pid = new_owner;
+        self.inner.set_owner_pid(new_owner);
+        self.inner.owner_changed(old_owner, new_owner);
+        Ok(old_owner)
+    }
+    
     pub fn deactivate(&mut self) {
         self.active = false;
         self.inner.set_active(false);
         self.inner.cleanup();
     }
     
+    /// React to `pid` going down; deactivates the port if it was the owner
+    ///
+    /// Returns true if the port lost its owner and should stop.
+    pub fn owner_down(&mut self, pid: u32) -> bool {
+        if self.active && self.get_owner_pid() == Some(pid) {
+            self.deactivate();
+            true
+        } else {
+            false
+        }
+    }
+    
     pub fn get_inner(&self) -> &T {
         &self.inner
     }
@@ -373,6 +750,14 @@ impl<T: PortData> PortData for GenericPortData<T> {
     fn set_active(&mut self, active: bool) {
         self.active = active;
     }
+
+    fn allow_command(&self, caller: u32, command: AtomIndex) -> bool {
+        self.inner.allow_command(caller, command)
+    }
+
+    fn command_timeouts(&self) -> Option<&timeout::CommandTimeouts> {
+        self.inner.command_timeouts()
+    }
 }
 
 /// Macro for creating simple port data structures
@@ -424,10 +809,32 @@ pub enum PortError {
     HardwareError,
     /// Out of memory
     OutOfMemory,
+    /// Caller is not the port owner
+    NotOwner,
+    /// Target process does not exist
+    NoProcess,
+    /// The port's policy rejected the caller
+    NotAllowed,
     /// Generic error
     Generic,
 }
 
+impl PortError {
+    /// Reason atom used in `{error, Reason}` replies
+    pub fn reason(&self) -> &'static str {
+        match self {
+            PortError::InvalidMessage => "badarg",
+            PortError::PortInactive => "closed",
+            PortError::HardwareError => "hardware_error",
+            PortError::OutOfMemory => "enomem",
+            PortError::NotOwner => "not_owner",
+            PortError::NoProcess => "noproc",
+            PortError::NotAllowed => "not_allowed",
+            PortError::Generic => "error",
+        }
+    }
+}
+
 impl From<PortError> for PortResult {
     fn from(_error: PortError) -> Self {
         PortResult::Terminate
@@ -441,34 +848,214 @@ pub type PortOpResult<T> = Result<T, PortError>;
 
 /// Extract PID as u32 from Term (for use in async messaging)
 pub fn term_to_pid(term: Term) -> PortOpResult<u32> {
-    // This would need to be implemented based on actual Term structure
-    // For now, return a placeholder
-    Ok(term.raw() as u32) // This is obviously wrong, but demonstrates the interface
+    match term.to_value() {
+        Ok(TermValue::Pid(ProcessId(pid))) => Ok(pid),
+        _ => Err(PortError::InvalidMessage),
+    }
 }
 
-/// Create a standard error reply using any atom table
-pub fn create_error_reply<T: AtomTableOps>(reason: &str, table: &T) -> Result<Term, NifError> {
-    // Create an error tuple: {error, Reason}
-    let error_atom = table.ensure_atom_str("error").map_err(|_| NifError::BadArg)?;
+/// Heap words needed by `create_ok_reply` and `create_error_reply`
+pub const REPLY_TUPLE_WORDS: usize = 3;
+
+/// Create an `{error, Reason}` reply on the heap using any atom table
+pub fn create_error_reply<'a, T: AtomTableOps>(
+    heap: &mut HeapGuard<'a>,
+    reason: &str,
+    table: &T,
+) -> Result<Term<'a>, NifError> {
     let reason_atom = table.ensure_atom_str(reason).map_err(|_| NifError::BadArg)?;
-    
-    // This would use the actual term construction API
-    // For now, return a placeholder that shows the pattern
-    let _ = (error_atom, reason_atom);
-    Ok(Term::from_raw(0)) // Obviously wrong, but demonstrates interface
+    let reason = heap.encode(TermValue::Atom(reason_atom))?;
+    tagged_reply(heap, "error", reason, table)
 }
 
-/// Create a standard success reply using any atom table
-pub fn create_ok_reply<T: AtomTableOps>(data: Term, table: &T) -> Result<Term, NifError> {
-    // Create an ok tuple: {ok, Data}
-    let ok_atom = table.ensure_atom_str("ok").map_err(|_| NifError::BadArg)?;
+/// Create an `{ok, Data}` reply on the heap using any atom table
+pub fn create_ok_reply<'a, T: AtomTableOps>(
+    heap: &mut HeapGuard<'a>,
+    data: Term<'a>,
+    table: &T,
+) -> Result<Term<'a>, NifError> {
+    tagged_reply(heap, "ok", data, table)
+}
+
+fn tagged_reply<'a, T: AtomTableOps>(
+    heap: &mut HeapGuard<'a>,
+    tag: &str,
+    data: Term<'a>,
+    table: &T,
+) -> Result<Term<'a>, NifError> {
+    let tag_atom = table.ensure_atom_str(tag).map_err(|_| NifError::BadArg)?;
+    let tag = heap.encode(TermValue::Atom(tag_atom))?;
+    heap.tuple(&[tag, data])
+}
+
+/// Build a reply on the port's heap with `build` and send it to the caller
+///
+/// Nothing is sent if the heap cannot be reserved or `build` fails.
+fn reply_on_heap<F>(ctx: &mut Context, pid: Term, reference: Term, build: F)
+where
+    F: for<'h> FnOnce(&mut HeapGuard<'h>) -> Result<Term<'h>, NifError>,
+{
+    // The caller's pid and reference live in the message, not on the port
+    // heap, so reserving space cannot move them
+    let heap_ctx = unsafe { &mut *(ctx as *mut Context).cast::<crate::term::Context>() };
+    let reply = HeapGuard::ensure_free(heap_ctx, REPLY_TUPLE_WORDS)
+        .and_then(|mut heap| build(&mut heap))
+        .map(Term::raw);
+    if let Ok(reply) = reply {
+        send_reply(ctx, pid, reference, Term::from_raw(reply));
+    }
+}
+
+/// Match a `{connect, Pid}` command, returning the new owner
+pub fn match_connect<T: AtomTableOps>(command: &TermValue, table: &T) -> Option<u32> {
+    match command.as_tuple() {
+        Some([tag, TermValue::Pid(ProcessId(pid))]) if tag.is_atom_str("connect", table) => Some(*pid),
+        _ => None,
+    }
+}
+
+/// Hand a port to a new controlling process, as `port_connect/2` does
+///
+/// `caller` must be the current owner (any process may claim a port that
+/// has none). The new owner is monitored before anything changes, so a dead
+/// pid leaves the port untouched; the previous owner's monitor is then
+/// dropped and it receives `{Port, connected}`.
+pub fn connect_port<T: PortData, A: AtomTableOps>(
+    ctx: &mut Context,
+    caller: u32,
+    new_owner: u32,
+    table: &A,
+) -> PortOpResult<Option<u32>> {
+    let ctx_ptr = ctx as *mut Context;
+    let port_data = unsafe {
+        let data_ptr = ctx.get_platform_data_as::<GenericPortData<T>>();
+        if data_ptr.is_null() {
+            return Err(PortError::PortInactive);
+        }
+        &mut *data_ptr
+    };
+    
+    if let Some(owner) = port_data.get_owner_pid() {
+        if owner != caller {
+            return Err(PortError::NotOwner);
+        }
+    }
+    if new_owner == 0 {
+        return Err(PortError::InvalidMessage);
+    }
+    if port_data.get_owner_pid() == Some(new_owner) {
+        return Ok(Some(new_owner));
+    }
     
-    // This would use the actual term construction API
-    let _ = (ok_atom, data);
-    Ok(Term::from_raw(0)) // Obviously wrong, but demonstrates interface
+    if unsafe { port_monitor_process(ctx_ptr, new_owner) } == 0 {
+        return Err(PortError::NoProcess);
+    }
+    let old_owner = match port_data.connect(new_owner) {
+        Ok(old_owner) => old_owner,
+        Err(e) => {
+            unsafe { port_demonitor_process(ctx_ptr, new_owner) };
+            return Err(e);
+        }
+    };
+    
+    if let Some(old) = old_owner {
+        unsafe { port_demonitor_process(ctx_ptr, old) };
+        let port = TermValue::Port(PortId(unsafe { port_get_id(ctx_ptr) }));
+        let connected = TermValue::tuple(alloc::vec![port, TermValue::atom("connected", table)]);
+        let _ = send(ctx, old, &connected, table);
+    }
+    Ok(old_owner)
+}
+
+/// Make `pid` the port's owner and monitor it
+///
+/// Pair with `handle_owner_down` so the port stops, and its data is
+/// released, when the owner dies. A previous owner's monitor is dropped.
+pub fn monitor_owner<T: PortData>(ctx: &mut Context, pid: u32) -> PortOpResult<()> {
+    let ctx_ptr = ctx as *mut Context;
+    let port_data = unsafe {
+        let data_ptr = ctx.get_platform_data_as::<GenericPortData<T>>();
+        if data_ptr.is_null() {
+            return Err(PortError::PortInactive);
+        }
+        &mut *data_ptr
+    };
+    if pid == 0 {
+        return Err(PortError::InvalidMessage);
+    }
+    let old_owner = port_data.get_owner_pid();
+    if old_owner == Some(pid) {
+        return Ok(());
+    }
+    if unsafe { port_monitor_process(ctx_ptr, pid) } == 0 {
+        return Err(PortError::NoProcess);
+    }
+    if let Some(old) = old_owner {
+        unsafe { port_demonitor_process(ctx_ptr, old) };
+    }
+    port_data.set_owner(pid);
+    Ok(())
+}
+
+/// Stop the port if `message` is the owner's `{'DOWN', Ref, process, Pid, Reason}`
+///
+/// Deactivates the port data (running its cleanup), frees it, and returns
+/// true; the handler should then return `PortResult::Terminate`. Other
+/// messages are left alone.
+pub fn handle_owner_down<T: PortData, A: AtomTableOps>(ctx: &mut Context, message: &TermValue, table: &A) -> bool {
+    let pid = match crate::monitor::match_down(message, table) {
+        Some(pid) => pid,
+        None => return false,
+    };
+    let owner_gone = with_port_data_mut::<T, _, _>(ctx, |data| data.owner_down(pid)).unwrap_or(false);
+    if owner_gone {
+        drop(unsafe { ctx.take_platform_data_box::<GenericPortData<T>>() });
+    }
+    owner_gone
+}
+
+/// Process monitor backed by the port's own monitors, for use with `PidSet`
+///
+/// DOWN notifications arrive in the port mailbox as
+/// `{'DOWN', Ref, process, Pid, Reason}`.
+pub struct PortProcessMonitor {
+    ctx: *mut Context,
+}
+
+impl PortProcessMonitor {
+    /// Create a monitor backend for the given port
+    pub fn new(ctx: &mut Context) -> Self {
+        Self { ctx: ctx as *mut Context }
+    }
+}
+
+impl crate::monitor::ProcessMonitor for PortProcessMonitor {
+    type Handle = ();
+
+    fn monitor(&mut self, pid: u32) -> Result<(), crate::monitor::MonitorError> {
+        if unsafe { port_monitor_process(self.ctx, pid) } != 0 {
+            Ok(())
+        } else {
+            Err(crate::monitor::MonitorError::NoProcess)
+        }
+    }
+
+    fn demonitor(&mut self, pid: u32, _handle: &()) {
+        unsafe { port_demonitor_process(self.ctx, pid) }
+    }
+}
+
+/// `{ok, Atom}`, the answer to the standard commands
+fn ok_atom_reply<'h, T: AtomTableOps>(heap: &mut HeapGuard<'h>, name: &str, table: &T) -> Result<Term<'h>, NifError> {
+    let atom = table.ensure_atom_str(name).map_err(|_| NifError::BadArg)?;
+    let data = heap.encode(TermValue::Atom(atom))?;
+    create_ok_reply(heap, data, table)
 }
 
 /// Generic standard message handler template
+///
+/// `start`, `stop` and `status` are answered with `{ok, active}` or
+/// `{ok, inactive}`, the port's state after the command.
 pub fn handle_standard_message<T: PortData>(
     ctx: &mut Context,
     message: &Message,
@@ -489,48 +1076,66 @@ pub fn handle_standard_message<T: PortData>(
         let command_value = match command.to_value() {
             Ok(val) => val,
             Err(_) => {
-                if let Ok(reply) = create_error_reply("invalid_command", &table) {
-                    send_reply(ctx, pid, reference, reply);
-                }
+                reply_on_heap(ctx, pid, reference, |heap| create_error_reply(heap, "invalid_command", &table));
                 return PortResult::Continue;
             }
         };
         
+        let allowed = term_to_pid(pid).and_then(|caller| {
+            policy::authorize(&|caller, name| port_data.allow_command(caller, name), caller, &command_value)
+        });
+        if let Err(e) = allowed {
+            reply_on_heap(ctx, pid, reference, |heap| create_error_reply(heap, e.reason(), &table));
+            return PortResult::Continue;
+        }
+
         // Handle standard commands using TermValue pattern matching with the table
         if command_value.is_atom_str("start", &table) {
-            if let Ok(pid_u32) = term_to_pid(pid) {
-                port_data.set_owner(pid_u32);
-                if let Ok(reply) = create_ok_reply(Term::from_raw(0), &table) {
-                    send_reply(ctx, pid, reference, reply);
-                }
-                PortResult::Continue
-            } else {
-                if let Ok(reply) = create_error_reply("invalid_pid", &table) {
-                    send_reply(ctx, pid, reference, reply);
-                }
-                PortResult::Continue
+            match term_to_pid(pid).and_then(|pid_u32| monitor_owner::<T>(ctx, pid_u32)) {
+                Ok(()) => reply_on_heap(ctx, pid, reference, |heap| ok_atom_reply(heap, "active", &table)),
+                Err(e) => reply_on_heap(ctx, pid, reference, |heap| create_error_reply(heap, e.reason(), &table)),
             }
+            PortResult::Continue
         } else if command_value.is_atom_str("stop", &table) {
             port_data.deactivate();
-            if let Ok(reply) = create_ok_reply(Term::from_raw(0), &table) {
-                send_reply(ctx, pid, reference, reply);
-            }
+            reply_on_heap(ctx, pid, reference, |heap| ok_atom_reply(heap, "inactive", &table));
             PortResult::Terminate
+        } else if let Some(new_owner) = match_connect(&command_value, &table) {
+            let reply = term_to_pid(pid)
+                .and_then(|caller| connect_port::<T, _>(ctx, caller, new_owner, &table))
+                .map_err(|e| e.reason());
+            let reply = match reply {
+                Ok(_) => TermValue::atom("ok", &table),
+                Err(reason) => TermValue::tuple(alloc::vec![
+                    TermValue::atom("error", &table),
+                    TermValue::atom(reason, &table),
+                ]),
+            };
+            let _ = send_reply_value(ctx, pid, reference, &reply, &table);
+            PortResult::Continue
         } else if command_value.is_atom_str("status", &table) {
-            let _status = if port_data.is_active() {
+            let status = if port_data.is_active() {
                 "active"
             } else {
                 "inactive"
             };
-            if let Ok(reply) = create_ok_reply(Term::from_raw(0), &table) {
-                send_reply(ctx, pid, reference, reply);
-            }
+            reply_on_heap(ctx, pid, reference, |heap| ok_atom_reply(heap, status, &table));
             PortResult::Continue
         } else {
-            // Delegate to the port data's message handler
-            port_data.handle_message(message)
+            // Delegate to the port data's message handler, within its time limit
+            let clock = timer::AtomVMClock;
+            let deadline = port_data.command_timeouts().map(|timeouts| timeouts.start(&clock, &command_value));
+            let result = port_data.handle_message(message);
+            match deadline {
+                Some(deadline) => deadline.finish(&clock, result, &table),
+                None => result,
+            }
         }
     } else {
+        // Not a call; if it is the owner's DOWN, release the data on the way out
+        if let Ok(value) = message_term(message).to_value() {
+            handle_owner_down::<T, _>(ctx, &value, &table);
+        }
         PortResult::Terminate
     }
 }
@@ -0,0 +1,283 @@
+//! GenServer-style port behavior
+//!
+//! `PortBehavior` is the high-level alternative to writing a `PortHandlerFn`
+//! by hand. The port state implements `init`, `handle_call`, `handle_cast`,
+//! `handle_info` and `terminate` and gets decoded `TermValue`s. The
+//! `port_behavior!` macro generates the create and handler functions: it
+//! stores the state in the port's platform data, sorts each message into a
+//! call, cast or info, and sends call replies back.
+//!
+//! Messages are sorted the way `gen_server` does it:
+//!
+//! - `{'$call', {Pid, Ref}, Request}` (`port:call/2`) goes to `handle_call`
+//! - `{'$cast', Request}` goes to `handle_cast`
+//! - anything else, such as `{'DOWN', ...}` or timer messages, goes to
+//!   `handle_info`
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::port::behavior::{CallFrom, PortBehavior};
+//!
+//! struct Led { on: bool }
+//!
+//! impl PortBehavior for Led {
+//!     fn init<T: AtomTableOps>(_opts: &TermValue, _table: &T) -> Result<Self, PortError> {
+//!         Ok(Led { on: false })
+//!     }
+//!
+//!     fn handle_call<T: AtomTableOps>(&mut self, request: &TermValue, _from: &CallFrom, table: &T) -> PortResult {
+//!         if request.is_atom_str("toggle", table) {
+//!             self.on = !self.on;
+//!             PortResult::Reply(TermValue::atom("ok", table))
+//!         } else {
+//!             PortResult::ReplyError(TermValue::atom("badarg", table))
+//!         }
+//!     }
+//! }
+//!
+//! port_behavior!(led, Led);
+//! ```
+
+use crate::atom::{AtomIndex, AtomTable, AtomTableOps};
+use crate::context::{Context, ContextExt, GlobalContext, PlatformData, PortBuilder};
+use crate::port::timeout::CommandTimeouts;
+use crate::port::timer::{AtomVMClock, Clock};
+use crate::port::{policy, send, Message, PortError, PortResult};
+use crate::term::{NifError, ProcessId, Term, TermValue};
+
+/// Caller of a `handle_call`, for replying later
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallFrom {
+    pub pid: u32,
+    pub reference: TermValue,
+}
+
+/// Callbacks of a port written as a behavior
+///
+/// The implementing type is the port state. Only `init` and `handle_call`
+/// are required; the others ignore their message by default.
+pub trait PortBehavior: Sized {
+    /// Build the state from the `open_port` options
+    ///
+    /// An error makes port creation fail.
+    fn init<T: AtomTableOps>(opts: &TermValue, table: &T) -> Result<Self, PortError>;
+
+    /// Answer a request from `port:call/2`
+    ///
+    /// `Reply` and `ReplyError` are sent back to `from`. Return `Continue`
+    /// to answer later with `reply`.
+    fn handle_call<T: AtomTableOps>(&mut self, request: &TermValue, from: &CallFrom, table: &T) -> PortResult;
+
+    /// Handle a `{'$cast', Request}` message; replies are dropped
+    fn handle_cast<T: AtomTableOps>(&mut self, request: &TermValue, table: &T) -> PortResult {
+        let _ = (request, table);
+        PortResult::Continue
+    }
+
+    /// Handle any other message; replies are dropped
+    fn handle_info<T: AtomTableOps>(&mut self, info: &TermValue, table: &T) -> PortResult {
+        let _ = (info, table);
+        PortResult::Continue
+    }
+
+    /// Whether `caller` may make the request named `command`
+    ///
+    /// Asked before `handle_call`; a rejected call is answered with
+    /// `{error, not_allowed}`. See `port::policy`.
+    fn allow_call(&self, _caller: u32, _command: AtomIndex) -> bool {
+        true
+    }
+
+    /// Time limits for `handle_call`
+    ///
+    /// A call that runs longer is answered with `{error, timeout}`. See
+    /// `port::timeout`.
+    fn call_timeouts(&self) -> Option<&CommandTimeouts> {
+        None
+    }
+
+    /// Called once when the port stops
+    fn terminate(&mut self) {}
+}
+
+/// A port message, sorted for dispatch
+#[derive(Debug, Clone, PartialEq)]
+pub enum Incoming {
+    Call { request: TermValue, from: CallFrom },
+    Cast(TermValue),
+    Info(TermValue),
+}
+
+impl Incoming {
+    /// Sort a decoded message into call, cast or info
+    pub fn classify<T: AtomTableOps>(message: TermValue, table: &T) -> Self {
+        match message.as_tuple() {
+            Some([tag, from, request]) if tag.is_atom_str("$call", table) => {
+                if let Some([TermValue::Pid(ProcessId(pid)), reference]) = from.as_tuple() {
+                    return Incoming::Call {
+                        request: request.clone(),
+                        from: CallFrom {
+                            pid: *pid,
+                            reference: reference.clone(),
+                        },
+                    };
+                }
+            }
+            Some([tag, request]) if tag.is_atom_str("$cast", table) => {
+                return Incoming::Cast(request.clone());
+            }
+            _ => {}
+        }
+        Incoming::Info(message)
+    }
+}
+
+/// Behavior state as stored in the port's platform data
+pub struct BehaviorPort<B: PortBehavior> {
+    state: B,
+    terminated: bool,
+}
+
+impl<B: PortBehavior> BehaviorPort<B> {
+    pub fn new(state: B) -> Self {
+        Self {
+            state,
+            terminated: false,
+        }
+    }
+
+    pub fn state(&self) -> &B {
+        &self.state
+    }
+
+    pub fn state_mut(&mut self) -> &mut B {
+        &mut self.state
+    }
+
+    /// Run the matching callback, and `terminate` if the port stops
+    pub fn dispatch<T: AtomTableOps>(&mut self, message: Incoming, table: &T) -> PortResult {
+        if self.terminated {
+            return PortResult::Terminate;
+        }
+        let result = match message {
+            Incoming::Call { request, from } => {
+                let state = &self.state;
+                match policy::authorize(&|caller, name| state.allow_call(caller, name), from.pid, &request) {
+                    Ok(()) => self.state.handle_call(&request, &from, table),
+                    Err(e) => PortResult::ReplyError(TermValue::atom(e.reason(), table)),
+                }
+            }
+            Incoming::Cast(request) => self.state.handle_cast(&request, table),
+            Incoming::Info(info) => self.state.handle_info(&info, table),
+        };
+        if result.is_terminate() {
+            self.terminate();
+        }
+        result
+    }
+
+    /// As `dispatch`, holding calls to the state's `call_timeouts`
+    pub fn dispatch_with_clock<C: Clock + ?Sized, T: AtomTableOps>(
+        &mut self,
+        message: Incoming,
+        clock: &C,
+        table: &T,
+    ) -> PortResult {
+        let deadline = match &message {
+            Incoming::Call { request, .. } if !self.terminated => {
+                self.state.call_timeouts().map(|timeouts| timeouts.start(clock, request))
+            }
+            _ => None,
+        };
+        let result = self.dispatch(message, table);
+        let Some(deadline) = deadline else {
+            return result;
+        };
+        let result = deadline.finish(clock, result, table);
+        if result.is_terminate() {
+            self.terminate();
+        }
+        result
+    }
+
+    /// Run `terminate` unless it already ran
+    pub fn terminate(&mut self) {
+        if !self.terminated {
+            self.terminated = true;
+            self.state.terminate();
+        }
+    }
+}
+
+impl<B: PortBehavior> PlatformData for BehaviorPort<B> {
+    fn cleanup(&mut self) {
+        self.terminate();
+    }
+}
+
+/// Reply to a call that `handle_call` left pending
+pub fn reply<T: AtomTableOps>(ctx: &Context, from: &CallFrom, reply: &TermValue, table: &T) -> Result<(), NifError> {
+    let message = TermValue::tuple(alloc::vec![from.reference.clone(), reply.clone()]);
+    send(ctx, from.pid, &message, table)
+}
+
+/// Port create function generated by `port_behavior!`
+pub fn create_port<B: PortBehavior>(global: &GlobalContext, opts: Term) -> *mut Context {
+    let table = AtomTable::from_global();
+    let opts = opts.to_value().unwrap_or(TermValue::Nil);
+    match B::init(&opts, &table) {
+        Ok(state) => PortBuilder::new(BehaviorPort::new(state)).build(global),
+        Err(_) => core::ptr::null_mut(),
+    }
+}
+
+/// Port handler generated by `port_behavior!`
+///
+/// Messages that cannot be decoded are dropped.
+pub fn handle_port_message<B: PortBehavior>(ctx: &mut Context, message: &Message) -> PortResult {
+    let port = unsafe {
+        let data_ptr = ctx.get_platform_data_as::<BehaviorPort<B>>();
+        if data_ptr.is_null() {
+            return PortResult::Terminate;
+        }
+        &mut *data_ptr
+    };
+    let table = AtomTable::from_global();
+    match crate::port::message_term(message).to_value() {
+        Ok(value) => port.dispatch_with_clock(Incoming::classify(value, &table), &AtomVMClock, &table),
+        Err(_) => PortResult::Continue,
+    }
+}
+
+/// Declare a port driver whose state implements `PortBehavior`
+///
+/// ```rust,ignore
+/// port_behavior!(led, Led);
+/// ```
+#[macro_export]
+macro_rules! port_behavior {
+    ($port_name:ident, $state:ty) => {
+        ::paste::paste! {
+            fn [<$port_name _behavior_create>](
+                global: &$crate::context::GlobalContext,
+                opts: $crate::term::Term,
+            ) -> *mut $crate::context::Context {
+                $crate::port::behavior::create_port::<$state>(global, opts)
+            }
+
+            fn [<$port_name _behavior_handler>](
+                ctx: &mut $crate::context::Context,
+                message: &$crate::port::Message,
+            ) -> $crate::port::PortResult {
+                $crate::port::behavior::handle_port_message::<$state>(ctx, message)
+            }
+
+            $crate::port_collection!(
+                $port_name,
+                create_port = [<$port_name _behavior_create>],
+                handler = [<$port_name _behavior_handler>]
+            );
+        }
+    };
+}
@@ -0,0 +1,162 @@
+//! I2C and SPI transaction ports
+//!
+//! Bus device drivers all decode the same few commands, run a transfer and
+//! answer with binaries. `BusPort` does the decoding and answering; a
+//! driver implements `BusBackend` for its bus and declares the port with
+//! `bus_port!`. The Erlang side (`port:call/2`):
+//!
+//! - `{write, Addr, Data}` writes `Data` (a binary or iolist), answering `ok`
+//! - `{read, Addr, Len}` answers `{ok, Binary}` with `Len` bytes
+//! - `{write_read, Addr, Data, Len}` writes, then reads `Len` bytes in
+//!   the same transaction (a repeated start on I2C, one chip select on
+//!   SPI), answering `{ok, Binary}`
+//!
+//! `Addr` is the I2C device address, or whatever selects the device on an
+//! SPI bus (a chip-select index). Failed transfers are answered with
+//! `{error, Reason}`, using the `PortError` reasons. Reads are limited to
+//! `MAX_READ` bytes.
+//!
+//! `BusPort` is a `PortBehavior`, so a port can also be written by hand
+//! around it.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::bus_port;
+//! use avmnif_rs::port::bus::BusBackend;
+//!
+//! struct I2c0;
+//!
+//! impl BusBackend for I2c0 {
+//!     fn init(_opts: &TermValue) -> Result<Self, PortError> { ... }
+//!     fn write(&mut self, addr: u16, data: &[u8]) -> Result<(), PortError> { ... }
+//!     fn read(&mut self, addr: u16, buffer: &mut [u8]) -> Result<(), PortError> { ... }
+//!     fn write_read(&mut self, addr: u16, data: &[u8], buffer: &mut [u8]) -> Result<(), PortError> { ... }
+//! }
+//!
+//! bus_port!(i2c0, I2c0);
+//! ```
+//!
+//! ```erlang
+//! Port = erlang:open_port({spawn, "i2c0"}, []),
+//! {ok, <<Id>>} = port:call(Port, {write_read, 16#68, <<16#75>>, 1}).
+//! ```
+
+extern crate alloc;
+
+use super::behavior::{CallFrom, PortBehavior};
+use super::{PortError, PortResult};
+use crate::atom::AtomTableOps;
+use crate::term::TermValue;
+use alloc::vec;
+
+/// Largest read a single command may ask for
+pub const MAX_READ: usize = 4096;
+
+/// Transfers on one I2C or SPI bus
+pub trait BusBackend: Sized {
+    /// Set up the bus from the `open_port` options; an error fails the open
+    fn init(opts: &TermValue) -> Result<Self, PortError>;
+
+    /// Write `data` to the device at `addr`
+    fn write(&mut self, addr: u16, data: &[u8]) -> Result<(), PortError>;
+
+    /// Fill `buffer` from the device at `addr`
+    fn read(&mut self, addr: u16, buffer: &mut [u8]) -> Result<(), PortError>;
+
+    /// Write `data`, then fill `buffer`, without releasing the bus in between
+    fn write_read(&mut self, addr: u16, data: &[u8], buffer: &mut [u8]) -> Result<(), PortError>;
+
+    /// Called once when the port stops
+    fn close(&mut self) {}
+}
+
+/// Port state of a bus device driver
+pub struct BusPort<B: BusBackend> {
+    backend: B,
+}
+
+impl<B: BusBackend> BusPort<B> {
+    pub fn new(backend: B) -> Self {
+        Self { backend }
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// Decode and run one transaction, returning the reply
+    pub fn transact<T: AtomTableOps>(&mut self, request: &TermValue, table: &T) -> Result<TermValue, PortError> {
+        let Some([command, addr, args @ ..]) = request.as_tuple() else {
+            return Err(PortError::InvalidMessage);
+        };
+        let addr = addr
+            .as_int()
+            .and_then(|addr| u16::try_from(addr).ok())
+            .ok_or(PortError::InvalidMessage)?;
+
+        match args {
+            [data] if command.is_atom_str("write", table) => {
+                let data = crate::iolist::flatten(data).map_err(|_| PortError::InvalidMessage)?;
+                self.backend.write(addr, &data)?;
+                Ok(TermValue::atom("ok", table))
+            }
+            [len] if command.is_atom_str("read", table) => {
+                let mut buffer = vec![0; read_len(len)?];
+                self.backend.read(addr, &mut buffer)?;
+                Ok(ok_binary(buffer, table))
+            }
+            [data, len] if command.is_atom_str("write_read", table) => {
+                let data = crate::iolist::flatten(data).map_err(|_| PortError::InvalidMessage)?;
+                let mut buffer = vec![0; read_len(len)?];
+                self.backend.write_read(addr, &data, &mut buffer)?;
+                Ok(ok_binary(buffer, table))
+            }
+            _ => Err(PortError::InvalidMessage),
+        }
+    }
+}
+
+impl<B: BusBackend> PortBehavior for BusPort<B> {
+    fn init<T: AtomTableOps>(opts: &TermValue, _table: &T) -> Result<Self, PortError> {
+        B::init(opts).map(Self::new)
+    }
+
+    fn handle_call<T: AtomTableOps>(&mut self, request: &TermValue, _from: &CallFrom, table: &T) -> PortResult {
+        match self.transact(request, table) {
+            Ok(reply) => PortResult::Reply(reply),
+            Err(e) => PortResult::ReplyError(TermValue::atom(e.reason(), table)),
+        }
+    }
+
+    fn terminate(&mut self) {
+        self.backend.close();
+    }
+}
+
+fn read_len(len: &TermValue) -> Result<usize, PortError> {
+    len.as_int()
+        .and_then(|len| usize::try_from(len).ok())
+        .filter(|len| *len <= MAX_READ)
+        .ok_or(PortError::InvalidMessage)
+}
+
+fn ok_binary<T: AtomTableOps>(data: alloc::vec::Vec<u8>, table: &T) -> TermValue {
+    TermValue::tuple(vec![TermValue::atom("ok", table), TermValue::binary(data)])
+}
+
+/// Declare a bus device port driver on top of a `BusBackend`
+///
+/// ```rust,ignore
+/// bus_port!(i2c0, I2c0);
+/// ```
+#[macro_export]
+macro_rules! bus_port {
+    ($port_name:ident, $backend:ty) => {
+        $crate::port_behavior!($port_name, $crate::port::bus::BusPort<$backend>);
+    };
+}
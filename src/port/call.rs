@@ -0,0 +1,286 @@
+//! Synchronous calls from Rust tasks into Erlang
+//!
+//! The reverse of a port command: a driver's background task sends
+//! `{call, Ref, Request}` to an Erlang process and blocks until the port
+//! receives `{Ref, Reply}`, the timeout passes, or the call is cancelled.
+//! The port's message handler hands incoming messages to
+//! `CallQueue::deliver`, which wakes the waiting task.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::port::call::{AtomVMCallBackend, CallQueue};
+//!
+//! static CALLS: CallQueue = CallQueue::new();
+//!
+//! // Background task, mid-operation:
+//! let table = AtomTable::from_global();
+//! let gain = CALLS.call(&mut AtomVMCallBackend::new(), owner, &request, 500, &table)?;
+//!
+//! // Port message handler:
+//! if CALLS.deliver(&message) {
+//!     return PortResult::Continue;
+//! }
+//! ```
+//!
+//! On the Erlang side:
+//!
+//! ```erlang
+//! receive {call, Ref, {get_gain, Channel}} -> Port ! {Ref, gain_for(Channel)} end
+//! ```
+
+extern crate alloc;
+
+use crate::atom::{AtomTable, AtomTableOps};
+use crate::sync::SpinLock;
+use crate::term::{RefId, TermValue};
+use alloc::vec::Vec;
+use core::fmt;
+use crate::sync::atomic::{AtomicBool, Ordering};
+
+/// Why a call produced no reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallError {
+    /// No reply before the deadline
+    Timeout,
+    /// The call's `CancelToken` was triggered
+    Cancelled,
+    /// The request could not be sent
+    SendFailed,
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::Timeout => write!(f, "call timed out"),
+            CallError::Cancelled => write!(f, "call cancelled"),
+            CallError::SendFailed => write!(f, "call request not delivered"),
+        }
+    }
+}
+
+/// Platform services for a blocking call: sending, a clock, and a way to wait
+pub trait CallBackend {
+    /// Send a message to `pid` from outside the scheduler
+    fn send(&mut self, pid: u32, message: &TermValue) -> Result<(), CallError>;
+
+    /// Monotonic time in milliseconds
+    fn now_ms(&self) -> u64;
+
+    /// Block for a while, at most until `deadline_ms`
+    ///
+    /// May return early; the caller re-checks for a reply each time.
+    fn wait_until(&mut self, deadline_ms: u64);
+}
+
+/// Flag for abandoning a call from another task
+#[derive(Debug, Default)]
+pub struct CancelToken(AtomicBool);
+
+impl CancelToken {
+    pub const fn new() -> Self {
+        Self(AtomicBool::new(false))
+    }
+
+    /// Make any call waiting on this token return `Cancelled`
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Re-arm the token for another call
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+struct PendingCall {
+    reference: u64,
+    reply: Option<TermValue>,
+}
+
+struct Pending {
+    next_reference: u64,
+    calls: Vec<PendingCall>,
+}
+
+/// Calls waiting for a reply, shared by the calling tasks and the port
+///
+/// Usually a `static`, since it is reached both from tasks and from the
+/// port handler.
+pub struct CallQueue {
+    pending: SpinLock<Pending>,
+}
+
+// Replies may hold resource pointers (`ResourceRef`), which are only handed
+// from the port to the waiting task, never dereferenced here
+unsafe impl Sync for CallQueue {}
+
+impl Default for CallQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CallQueue {
+    pub const fn new() -> Self {
+        Self {
+            pending: SpinLock::new(Pending {
+                next_reference: 1,
+                calls: Vec::new(),
+            }),
+        }
+    }
+
+    /// Send `{call, Ref, Request}` to `pid` and wait for `{Ref, Reply}`
+    pub fn call<B: CallBackend, T: AtomTableOps>(
+        &self,
+        backend: &mut B,
+        pid: u32,
+        request: &TermValue,
+        timeout_ms: u64,
+        table: &T,
+    ) -> Result<TermValue, CallError> {
+        self.call_cancellable(backend, pid, request, timeout_ms, &CancelToken::new(), table)
+    }
+
+    /// As `call`, also returning `Cancelled` once `cancel` is triggered
+    pub fn call_cancellable<B: CallBackend, T: AtomTableOps>(
+        &self,
+        backend: &mut B,
+        pid: u32,
+        request: &TermValue,
+        timeout_ms: u64,
+        cancel: &CancelToken,
+        table: &T,
+    ) -> Result<TermValue, CallError> {
+        let reference = self.with(|pending| {
+            let reference = pending.next_reference;
+            pending.next_reference += 1;
+            pending.calls.push(PendingCall { reference, reply: None });
+            reference
+        });
+
+        let message = TermValue::tuple(alloc::vec![
+            TermValue::atom("call", table),
+            TermValue::Reference(RefId(reference)),
+            request.clone(),
+        ]);
+        if let Err(error) = backend.send(pid, &message) {
+            self.forget(reference);
+            return Err(error);
+        }
+
+        let deadline = backend.now_ms().saturating_add(timeout_ms);
+        loop {
+            if let Some(reply) = self.take_reply(reference) {
+                return Ok(reply);
+            }
+            if cancel.is_cancelled() {
+                self.forget(reference);
+                return Err(CallError::Cancelled);
+            }
+            if backend.now_ms() >= deadline {
+                self.forget(reference);
+                return Err(CallError::Timeout);
+            }
+            backend.wait_until(deadline);
+        }
+    }
+
+    /// Route a port message to its waiting call
+    ///
+    /// Returns true if `message` was the `{Ref, Reply}` of a pending call.
+    /// Replies arriving after their call timed out or was cancelled are not
+    /// consumed.
+    pub fn deliver(&self, message: &TermValue) -> bool {
+        let (reference, reply) = match message.as_tuple() {
+            Some([TermValue::Reference(RefId(reference)), reply]) => (*reference, reply),
+            _ => return false,
+        };
+        self.with(|pending| {
+            match pending.calls.iter_mut().find(|call| call.reference == reference && call.reply.is_none()) {
+                Some(call) => {
+                    call.reply = Some(reply.clone());
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    /// Number of calls still waiting
+    pub fn pending(&self) -> usize {
+        self.with(|pending| pending.calls.len())
+    }
+
+    fn take_reply(&self, reference: u64) -> Option<TermValue> {
+        self.with(|pending| {
+            let index = pending
+                .calls
+                .iter()
+                .position(|call| call.reference == reference && call.reply.is_some())?;
+            pending.calls.swap_remove(index).reply
+        })
+    }
+
+    fn forget(&self, reference: u64) {
+        self.with(|pending| pending.calls.retain(|call| call.reference != reference));
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut Pending) -> R) -> R {
+        self.pending.with(f)
+    }
+}
+
+// Task services FFI declarations
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    /// Monotonic platform time in milliseconds
+    fn port_timer_now_ms() -> u64;
+
+    /// Suspend the calling task (not the scheduler) for `ms` milliseconds
+    fn port_task_sleep_ms(ms: u32);
+}
+
+/// Call backend for a platform task: sends through ETF and sleeps in slices
+pub struct AtomVMCallBackend {
+    poll_ms: u32,
+}
+
+impl AtomVMCallBackend {
+    /// Backend that checks for a reply every 10 ms
+    pub fn new() -> Self {
+        Self { poll_ms: 10 }
+    }
+
+    /// Check for a reply every `poll_ms` milliseconds
+    pub fn with_poll_interval(poll_ms: u32) -> Self {
+        Self { poll_ms: poll_ms.max(1) }
+    }
+}
+
+impl Default for AtomVMCallBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CallBackend for AtomVMCallBackend {
+    fn send(&mut self, pid: u32, message: &TermValue) -> Result<(), CallError> {
+        crate::port::send_from_task(pid, message, &AtomTable::from_global()).map_err(|_| CallError::SendFailed)
+    }
+
+    fn now_ms(&self) -> u64 {
+        unsafe { port_timer_now_ms() }
+    }
+
+    fn wait_until(&mut self, deadline_ms: u64) {
+        let remaining = deadline_ms.saturating_sub(self.now_ms());
+        let slice = remaining.min(self.poll_ms as u64) as u32;
+        unsafe { port_task_sleep_ms(slice) }
+    }
+}
@@ -0,0 +1,342 @@
+//! Session capture for port drivers
+//!
+//! A `PortRecorder` logs every inbound command and outbound reply of a port
+//! as ETF into a pluggable `CaptureSink` (RAM buffer, UART, flash, ...).
+//! The resulting capture can be replayed on the host against the same
+//! handler to reproduce bugs seen in the field.
+//!
+//! Ports defined with `port_collection!` are captured by installing a
+//! hook with `set_hook`, such as a `SharedRecorder`: the generated handler
+//! passes it every call and the reply it sent.
+//!
+//! # Capture format
+//!
+//! A capture is a sequence of frames:
+//!
+//! ```text
+//! +--------+-----------------+------------------+
+//! | kind   | length (u32 BE) | ETF term (bytes) |
+//! +--------+-----------------+------------------+
+//! ```
+//!
+//! where `kind` is `1` for an inbound command and `2` for an outbound reply.
+//! A command is stored as `{Ref, Command}` and its reply as `{Ref, Reply}`,
+//! with `Ref` the reference of the call, so replies sent out of order
+//! still find their command.
+
+extern crate alloc;
+
+use super::{parse_gen_message, Message, PortResult};
+use crate::atom::{AtomTable, AtomTableOps};
+use crate::etf::{self, EtfError};
+use crate::sync::SpinLock;
+use crate::term::TermValue;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Direction of a captured message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Direction {
+    /// Command received by the port
+    Inbound = 1,
+    /// Reply sent by the port
+    Outbound = 2,
+}
+
+impl Direction {
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            1 => Some(Direction::Inbound),
+            2 => Some(Direction::Outbound),
+            _ => None,
+        }
+    }
+}
+
+/// Errors that can occur while recording or reading a capture
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptureError {
+    /// Capture ended in the middle of a frame
+    Truncated,
+    /// Frame kind byte is not a known direction
+    UnknownKind(u8),
+    /// Frame term is not a `{Ref, Term}` pair
+    Malformed,
+    /// Term could not be encoded or decoded
+    Etf(EtfError),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::Truncated => write!(f, "capture truncated"),
+            CaptureError::UnknownKind(k) => write!(f, "unknown capture frame kind {}", k),
+            CaptureError::Malformed => write!(f, "capture frame is not a {{Ref, Term}} pair"),
+            CaptureError::Etf(e) => write!(f, "ETF error: {}", e),
+        }
+    }
+}
+
+impl From<EtfError> for CaptureError {
+    fn from(error: EtfError) -> Self {
+        CaptureError::Etf(error)
+    }
+}
+
+/// Destination for captured frames
+///
+/// Implementations decide where frames end up; the recorder only formats them.
+pub trait CaptureSink {
+    /// Write one complete frame
+    fn write_frame(&mut self, frame: &[u8]);
+}
+
+/// In-memory sink, useful for short sessions and for tests
+impl CaptureSink for Vec<u8> {
+    fn write_frame(&mut self, frame: &[u8]) {
+        self.extend_from_slice(frame);
+    }
+}
+
+/// Records the command/reply traffic of one port
+pub struct PortRecorder<S: CaptureSink> {
+    sink: S,
+    enabled: bool,
+    frame: Vec<u8>,
+}
+
+impl<S: CaptureSink> PortRecorder<S> {
+    /// Create an enabled recorder writing to `sink`
+    pub const fn new(sink: S) -> Self {
+        Self {
+            sink,
+            enabled: true,
+            frame: Vec::new(),
+        }
+    }
+
+    /// Turn recording on or off without tearing down the sink
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Check whether frames are currently being recorded
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Record a command received by the port in the call tagged `reference`
+    pub fn record_command<T: AtomTableOps>(
+        &mut self,
+        reference: &TermValue,
+        command: &TermValue,
+        table: &T,
+    ) -> Result<(), CaptureError> {
+        self.record(Direction::Inbound, reference, command, table)
+    }
+
+    /// Record the reply sent to the call tagged `reference`
+    pub fn record_reply<T: AtomTableOps>(
+        &mut self,
+        reference: &TermValue,
+        reply: &TermValue,
+        table: &T,
+    ) -> Result<(), CaptureError> {
+        self.record(Direction::Outbound, reference, reply, table)
+    }
+
+    /// Run a command through `handler`, recording the command and its reply
+    ///
+    /// Capture failures never affect the handler's result.
+    pub fn handle<T, F>(&mut self, reference: &TermValue, command: &TermValue, table: &T, handler: F) -> Option<TermValue>
+    where
+        T: AtomTableOps,
+        F: FnOnce(&TermValue) -> Option<TermValue>,
+    {
+        let _ = self.record_command(reference, command, table);
+        let reply = handler(command);
+        if let Some(reply) = &reply {
+            let _ = self.record_reply(reference, reply, table);
+        }
+        reply
+    }
+
+    /// Access the underlying sink
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Stop recording and return the sink
+    pub fn into_sink(self) -> S {
+        self.sink
+    }
+
+    fn record<T: AtomTableOps>(
+        &mut self,
+        direction: Direction,
+        reference: &TermValue,
+        term: &TermValue,
+        table: &T,
+    ) -> Result<(), CaptureError> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        // Reserve the header, encode in place, then patch the length
+        self.frame.clear();
+        self.frame.push(direction as u8);
+        self.frame.extend_from_slice(&[0; 4]);
+        let tagged = TermValue::tuple(alloc::vec![reference.clone(), term.clone()]);
+        etf::encode_into(&tagged, table, &mut self.frame)?;
+        let len = (self.frame.len() - 5) as u32;
+        self.frame[1..5].copy_from_slice(&len.to_be_bytes());
+
+        self.sink.write_frame(&self.frame);
+        Ok(())
+    }
+}
+
+/// Receives the calls handled by every port defined with `port_collection!`
+///
+/// Called on the port's thread after the handler returns, with the call's
+/// reference, its command and the reply sent, if any; keep it short.
+pub trait CaptureHook: Sync {
+    fn capture(&self, reference: &TermValue, command: &TermValue, reply: Option<&TermValue>);
+}
+
+#[cfg(not(test))]
+static HOOK: SpinLock<Option<&'static dyn CaptureHook>> = SpinLock::new(None);
+
+#[cfg(not(test))]
+fn hook() -> Option<&'static dyn CaptureHook> {
+    HOOK.with(|current| *current)
+}
+
+#[cfg(not(test))]
+fn store_hook(hook: Option<&'static dyn CaptureHook>) {
+    HOOK.with(|current| *current = hook);
+}
+
+// Tests run on parallel threads, so each one installs its own hook
+#[cfg(test)]
+extern crate std;
+
+#[cfg(test)]
+std::thread_local! {
+    static HOOK: core::cell::Cell<Option<&'static dyn CaptureHook>> = const { core::cell::Cell::new(None) };
+}
+
+#[cfg(test)]
+fn hook() -> Option<&'static dyn CaptureHook> {
+    HOOK.with(|current| current.get())
+}
+
+#[cfg(test)]
+fn store_hook(hook: Option<&'static dyn CaptureHook>) {
+    HOOK.with(|current| current.set(hook));
+}
+
+/// Install the hook, or remove it with `None`
+pub fn set_hook(hook: Option<&'static dyn CaptureHook>) {
+    store_hook(hook);
+}
+
+/// Hand a call and the reply `result` carries to the installed hook
+///
+/// Called by the handler `port_collection!` generates, before the reply
+/// is sent. Messages other than calls are not captured, and nothing is
+/// decoded while no hook is installed.
+pub fn capture_port_call(message: &Message, result: &PortResult) {
+    let Some(hook) = hook() else {
+        return;
+    };
+    let Ok((_, reference, command)) = parse_gen_message(message) else {
+        return;
+    };
+    let (Ok(reference), Ok(command)) = (reference.to_value(), command.to_value()) else {
+        return;
+    };
+    let reply = result.reply_term(&AtomTable::from_global()).ok().flatten();
+    hook.capture(&reference, &command, reply.as_ref());
+}
+
+/// A `PortRecorder` that can be installed as the capture hook
+///
+/// ```rust,ignore
+/// static RECORDER: SharedRecorder<UartSink> = SharedRecorder::new(UartSink::new());
+/// capture::set_hook(Some(&RECORDER));
+/// ```
+pub struct SharedRecorder<S: CaptureSink> {
+    recorder: SpinLock<PortRecorder<S>>,
+}
+
+impl<S: CaptureSink> SharedRecorder<S> {
+    /// Create an enabled recorder writing to `sink`
+    pub const fn new(sink: S) -> Self {
+        Self {
+            recorder: SpinLock::new(PortRecorder::new(sink)),
+        }
+    }
+
+    /// Run `f` on the recorder, to toggle it or read its sink
+    pub fn with<R>(&self, f: impl FnOnce(&mut PortRecorder<S>) -> R) -> R {
+        self.recorder.with(f)
+    }
+}
+
+impl<S: CaptureSink + Send> CaptureHook for SharedRecorder<S> {
+    fn capture(&self, reference: &TermValue, command: &TermValue, reply: Option<&TermValue>) {
+        let table = AtomTable::from_global();
+        self.recorder.with(|recorder| {
+            let _ = recorder.record_command(reference, command, &table);
+            if let Some(reply) = reply {
+                let _ = recorder.record_reply(reference, reply, &table);
+            }
+        });
+    }
+}
+
+/// One decoded frame of a capture
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureFrame {
+    pub direction: Direction,
+    /// Reference of the call the command or reply belongs to
+    pub reference: TermValue,
+    pub term: TermValue,
+}
+
+/// Decode a complete capture into its frames
+pub fn parse_capture<T: AtomTableOps>(
+    capture: &[u8],
+    table: &T,
+) -> Result<Vec<CaptureFrame>, CaptureError> {
+    let mut frames = Vec::new();
+    let mut rest = capture;
+
+    while !rest.is_empty() {
+        if rest.len() < 5 {
+            return Err(CaptureError::Truncated);
+        }
+        let direction = Direction::from_byte(rest[0]).ok_or(CaptureError::UnknownKind(rest[0]))?;
+        let len = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]) as usize;
+        let end = 5usize.checked_add(len).ok_or(CaptureError::Truncated)?;
+        let body = rest.get(5..end).ok_or(CaptureError::Truncated)?;
+
+        let (reference, term) = match etf::decode(body, table)? {
+            TermValue::Tuple(mut pair) if pair.len() == 2 => {
+                let term = pair.pop().unwrap_or(TermValue::Nil);
+                (pair.pop().unwrap_or(TermValue::Nil), term)
+            }
+            _ => return Err(CaptureError::Malformed),
+        };
+        frames.push(CaptureFrame {
+            direction,
+            reference,
+            term,
+        });
+        rest = &rest[end..];
+    }
+
+    Ok(frames)
+}
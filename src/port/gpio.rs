@@ -0,0 +1,463 @@
+//! GPIO ports on any HAL
+//!
+//! `gpio_port!` generates a GPIO port driver on top of a `GpioHal`, the few
+//! pin operations a platform provides. The Erlang side is the same on
+//! every platform (`port:call/2`):
+//!
+//! - `{set, Pin, Level}` drives a pin, with `Level` `high`, `low`, `1` or
+//!   `0`, and answers `ok`
+//! - `{read, Pin}` answers `{ok, high}` or `{ok, low}`
+//! - `{subscribe, Pin, Edge}` with `Edge` `rising`, `falling` or `both`
+//!   sends the caller `{gpio_interrupt, Pin, rising | falling}` on every
+//!   matching edge, and answers `ok`
+//! - `{unsubscribe, Pin}` stops that, answering `ok`
+//!
+//! Errors are answered with `{error, Reason}`, using the `PortError`
+//! reasons. Subscribers are monitored and dropped when they go down; a
+//! pin's interrupt is disabled when its last subscriber leaves.
+//!
+//! Edges travel from the interrupt handler through an `IrqQueue`, so
+//! nothing is built on a heap in interrupt context. The HAL gets the
+//! function to call with each edge when an interrupt is enabled.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::gpio_port;
+//! use avmnif_rs::port::gpio::{Edge, EdgeFn, GpioHal};
+//!
+//! struct BoardGpio;
+//!
+//! impl GpioHal for BoardGpio {
+//!     fn init(_opts: &TermValue) -> Result<Self, PortError> { Ok(BoardGpio) }
+//!     fn set(&mut self, pin: u32, high: bool) -> Result<(), PortError> { ... }
+//!     fn read(&mut self, pin: u32) -> Result<bool, PortError> { ... }
+//!     fn enable_interrupt(&mut self, pin: u32, edge: Edge, notify: EdgeFn) -> Result<(), PortError> { ... }
+//!     fn disable_interrupt(&mut self, pin: u32) { ... }
+//! }
+//!
+//! gpio_port!(gpio, BoardGpio);
+//! ```
+
+extern crate alloc;
+
+use super::behavior::Incoming;
+use super::irq::{IrqEvent, IrqQueue};
+use super::{send, Message, PortError, PortProcessMonitor, PortResult};
+use crate::atom::{AtomTable, AtomTableOps};
+use crate::context::{Context, ContextExt, GlobalContext, PlatformData, PortBuilder};
+use crate::monitor::{PidSet, ProcessMonitor};
+use crate::term::{Term, TermValue};
+use alloc::vec::Vec;
+
+/// Called by the HAL's interrupt handler with the pin and its new level (0 or 1)
+pub type EdgeFn = extern "C" fn(pin: u32, level: u32);
+
+/// Edges queued between interrupts and the port
+pub const EDGE_QUEUE_CAPACITY: usize = 32;
+
+/// Highest pin number plus one whose edges are coalesced, not dropped, on a full queue
+pub const MAX_PINS: usize = 64;
+
+/// The edge queue of a `gpio_port!` driver
+pub type EdgeQueue = IrqQueue<EDGE_QUEUE_CAPACITY, MAX_PINS>;
+
+/// Which transitions of a pin to report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Rising,
+    Falling,
+    Both,
+}
+
+impl Edge {
+    /// `rising`, `falling` or `both`
+    pub fn from_term<T: AtomTableOps>(term: &TermValue, table: &T) -> Option<Self> {
+        [Edge::Rising, Edge::Falling, Edge::Both]
+            .into_iter()
+            .find(|edge| term.is_atom_str(edge.name(), table))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Edge::Rising => "rising",
+            Edge::Falling => "falling",
+            Edge::Both => "both",
+        }
+    }
+
+    /// Whether a transition to `high` is reported
+    pub fn matches(self, high: bool) -> bool {
+        match self {
+            Edge::Rising => high,
+            Edge::Falling => !high,
+            Edge::Both => true,
+        }
+    }
+
+    /// The edge that covers both `self` and `other`
+    pub fn union(self, other: Edge) -> Edge {
+        if self == other {
+            self
+        } else {
+            Edge::Both
+        }
+    }
+}
+
+/// Pin operations a platform provides to `gpio_port!`
+pub trait GpioHal: Sized {
+    /// Set up the HAL from the `open_port` options; an error fails the open
+    fn init(opts: &TermValue) -> Result<Self, PortError>;
+
+    /// Configure `pin` as an output if needed and drive it
+    fn set(&mut self, pin: u32, high: bool) -> Result<(), PortError>;
+
+    /// Read the level of `pin`
+    fn read(&mut self, pin: u32) -> Result<bool, PortError>;
+
+    /// Call `notify` from the interrupt handler on `edge` transitions of `pin`
+    ///
+    /// Called again with a wider edge when subscribers need more.
+    fn enable_interrupt(&mut self, pin: u32, edge: Edge, notify: EdgeFn) -> Result<(), PortError>;
+
+    /// Stop reporting transitions of `pin`
+    fn disable_interrupt(&mut self, pin: u32);
+}
+
+/// A pin with subscribers, and the edge each one wants
+struct PinSubscribers {
+    pin: u32,
+    edges: Vec<(u32, Edge)>,
+}
+
+impl PinSubscribers {
+    fn edge(&self) -> Option<Edge> {
+        self.edges.iter().map(|(_, edge)| *edge).reduce(Edge::union)
+    }
+}
+
+/// State of a `gpio_port!` port: the HAL and who listens to which pin
+pub struct GpioPort<G: GpioHal, H = ()> {
+    hal: G,
+    notify: EdgeFn,
+    pins: Vec<PinSubscribers>,
+    // Every subscriber is monitored once, however many pins it listens to
+    subscribers: PidSet<H>,
+    queue: Option<&'static EdgeQueue>,
+}
+
+impl<G: GpioHal, H> GpioPort<G, H> {
+    pub fn new(hal: G, notify: EdgeFn) -> Self {
+        Self {
+            hal,
+            notify,
+            pins: Vec::new(),
+            subscribers: PidSet::new(),
+            queue: None,
+        }
+    }
+
+    /// Detach `queue` from the port when it closes
+    pub fn with_queue(mut self, queue: &'static EdgeQueue) -> Self {
+        self.queue = Some(queue);
+        self
+    }
+
+    pub fn hal(&self) -> &G {
+        &self.hal
+    }
+
+    pub fn hal_mut(&mut self) -> &mut G {
+        &mut self.hal
+    }
+
+    /// The edge enabled on `pin`, if it has subscribers
+    pub fn subscribed_edge(&self, pin: u32) -> Option<Edge> {
+        self.pins.iter().find(|entry| entry.pin == pin).and_then(PinSubscribers::edge)
+    }
+
+    /// Report `edge` transitions of `pin` to `pid`, replacing the edge it had
+    pub fn subscribe<M>(&mut self, monitor: &mut M, pid: u32, pin: u32, edge: Edge) -> Result<(), PortError>
+    where
+        M: ProcessMonitor<Handle = H>,
+    {
+        if self.subscribers.insert(monitor, pid).is_err() {
+            return Err(PortError::NoProcess);
+        }
+        let before = self.subscribed_edge(pin);
+        let index = match self.pins.iter().position(|entry| entry.pin == pin) {
+            Some(index) => index,
+            None => {
+                self.pins.push(PinSubscribers { pin, edges: Vec::new() });
+                self.pins.len() - 1
+            }
+        };
+        let edges = &mut self.pins[index].edges;
+        let previous = edges.iter().position(|(subscriber, _)| *subscriber == pid).map(|i| edges.remove(i));
+        edges.push((pid, edge));
+
+        let now = self.pins[index].edge().unwrap_or(edge);
+        if before == Some(now) {
+            return Ok(());
+        }
+        if let Err(e) = self.hal.enable_interrupt(pin, now, self.notify) {
+            // Put the subscription back as it was
+            let edges = &mut self.pins[index].edges;
+            edges.retain(|(subscriber, _)| *subscriber != pid);
+            edges.extend(previous);
+            self.pins.retain(|entry| !entry.edges.is_empty());
+            if !self.listens(pid) {
+                self.subscribers.remove(monitor, pid);
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Stop reporting `pin` to `pid`
+    pub fn unsubscribe<M>(&mut self, monitor: &mut M, pid: u32, pin: u32)
+    where
+        M: ProcessMonitor<Handle = H>,
+    {
+        let before = self.subscribed_edge(pin);
+        if let Some(entry) = self.pins.iter_mut().find(|entry| entry.pin == pin) {
+            entry.edges.retain(|(subscriber, _)| *subscriber != pid);
+        }
+        self.prune(before.map(|edge| (pin, edge)));
+        if !self.listens(pid) {
+            self.subscribers.remove(monitor, pid);
+        }
+    }
+
+    /// Drop a subscriber that went down, from every pin
+    pub fn subscriber_down(&mut self, pid: u32) {
+        let before: Vec<(u32, Edge)> = self
+            .pins
+            .iter()
+            .filter_map(|entry| entry.edge().map(|edge| (entry.pin, edge)))
+            .collect();
+        for entry in &mut self.pins {
+            entry.edges.retain(|(subscriber, _)| *subscriber != pid);
+        }
+        for pin in before {
+            self.prune(Some(pin));
+        }
+    }
+
+    /// The `{gpio_interrupt, Pin, Edge}` messages for one queued edge
+    pub fn edge_messages<T: AtomTableOps>(&self, event: IrqEvent, table: &T) -> Vec<(u32, TermValue)> {
+        let high = event.payload != 0;
+        let Some(entry) = self.pins.iter().find(|entry| entry.pin == event.id) else {
+            return Vec::new();
+        };
+        let edge = if high { Edge::Rising } else { Edge::Falling };
+        let message = TermValue::tuple(alloc::vec![
+            TermValue::atom("gpio_interrupt", table),
+            TermValue::int(event.id as i32),
+            TermValue::atom(edge.name(), table),
+        ]);
+        entry
+            .edges
+            .iter()
+            .filter(|(_, wanted)| wanted.matches(high))
+            .map(|(pid, _)| (*pid, message.clone()))
+            .collect()
+    }
+
+    /// Answer a call, or handle a DOWN for a subscriber
+    pub fn dispatch<M, T>(&mut self, message: Incoming, monitor: &mut M, table: &T) -> PortResult
+    where
+        M: ProcessMonitor<Handle = H>,
+        T: AtomTableOps,
+    {
+        let (request, from) = match message {
+            Incoming::Call { request, from } => (request, from),
+            Incoming::Info(info) => {
+                if let Some(pid) = crate::monitor::match_down(&info, table) {
+                    if self.subscribers.handle_down(pid, |_| {}) {
+                        self.subscriber_down(pid);
+                    }
+                }
+                return PortResult::Continue;
+            }
+            Incoming::Cast(_) => return PortResult::Continue,
+        };
+        match self.call(&request, from.pid, monitor, table) {
+            Ok(reply) => PortResult::Reply(reply),
+            Err(e) => PortResult::ReplyError(TermValue::atom(e.reason(), table)),
+        }
+    }
+
+    fn call<M, T>(&mut self, request: &TermValue, caller: u32, monitor: &mut M, table: &T) -> Result<TermValue, PortError>
+    where
+        M: ProcessMonitor<Handle = H>,
+        T: AtomTableOps,
+    {
+        let ok = || TermValue::atom("ok", table);
+        let (command, pin, rest) = match request.as_tuple() {
+            Some([command, pin, rest @ ..]) => (command, pin, rest),
+            _ => return Err(PortError::InvalidMessage),
+        };
+        let pin = pin.as_int().and_then(|pin| u32::try_from(pin).ok()).ok_or(PortError::InvalidMessage)?;
+
+        match rest {
+            [level] if command.is_atom_str("set", table) => {
+                let high = level_from_term(level, table).ok_or(PortError::InvalidMessage)?;
+                self.hal.set(pin, high)?;
+                Ok(ok())
+            }
+            [] if command.is_atom_str("read", table) => {
+                let level = if self.hal.read(pin)? { "high" } else { "low" };
+                Ok(TermValue::tuple(alloc::vec![ok(), TermValue::atom(level, table)]))
+            }
+            [edge] if command.is_atom_str("subscribe", table) => {
+                let edge = Edge::from_term(edge, table).ok_or(PortError::InvalidMessage)?;
+                self.subscribe(monitor, caller, pin, edge)?;
+                Ok(ok())
+            }
+            [] if command.is_atom_str("unsubscribe", table) => {
+                self.unsubscribe(monitor, caller, pin);
+                Ok(ok())
+            }
+            _ => Err(PortError::InvalidMessage),
+        }
+    }
+
+    fn listens(&self, pid: u32) -> bool {
+        self.pins.iter().any(|entry| entry.edges.iter().any(|(subscriber, _)| *subscriber == pid))
+    }
+
+    /// Put the hardware back in line with the subscribers of a pin that had `before` enabled
+    fn prune(&mut self, before: Option<(u32, Edge)>) {
+        let Some((pin, before)) = before else {
+            return;
+        };
+        let now = self.subscribed_edge(pin);
+        if now != Some(before) {
+            self.restore(pin, now);
+        }
+        self.pins.retain(|entry| !entry.edges.is_empty());
+    }
+
+    fn restore(&mut self, pin: u32, edge: Option<Edge>) {
+        match edge {
+            Some(edge) => {
+                let _ = self.hal.enable_interrupt(pin, edge, self.notify);
+            }
+            None => self.hal.disable_interrupt(pin),
+        }
+    }
+}
+
+impl<G: GpioHal, H> PlatformData for GpioPort<G, H> {
+    fn cleanup(&mut self) {
+        for entry in &self.pins {
+            self.hal.disable_interrupt(entry.pin);
+        }
+        self.pins.clear();
+        if let Some(queue) = self.queue {
+            queue.detach();
+        }
+    }
+}
+
+/// `high`, `low`, `1` or `0`
+pub fn level_from_term<T: AtomTableOps>(term: &TermValue, table: &T) -> Option<bool> {
+    match term.as_int() {
+        Some(0) => Some(false),
+        Some(1) => Some(true),
+        Some(_) => None,
+        None if term.is_atom_str("high", table) => Some(true),
+        None if term.is_atom_str("low", table) => Some(false),
+        None => None,
+    }
+}
+
+/// Port create function generated by `gpio_port!`
+pub fn create_port<G: GpioHal>(
+    global: &GlobalContext,
+    opts: Term,
+    queue: &'static EdgeQueue,
+    notify: EdgeFn,
+) -> *mut Context {
+    let opts = opts.to_value().unwrap_or(TermValue::Nil);
+    let Ok(hal) = G::init(&opts) else {
+        return core::ptr::null_mut();
+    };
+    let ctx = PortBuilder::new(GpioPort::<G>::new(hal, notify).with_queue(queue)).build(global);
+    if !ctx.is_null() {
+        queue.attach_port(unsafe { &*ctx });
+    }
+    ctx
+}
+
+/// Port handler generated by `gpio_port!`
+///
+/// Sends the queued edges on `irq_wakeup`; edges that cannot be delivered
+/// are dropped.
+pub fn handle_port_message<G: GpioHal>(ctx: &mut Context, message: &Message, queue: &'static EdgeQueue) -> PortResult {
+    let port = unsafe {
+        let data_ptr = ctx.get_platform_data_as::<GpioPort<G>>();
+        if data_ptr.is_null() {
+            return PortResult::Terminate;
+        }
+        &mut *data_ptr
+    };
+    let table = AtomTable::from_global();
+    let Ok(value) = super::message_term(message).to_value() else {
+        return PortResult::Continue;
+    };
+    if EdgeQueue::is_wakeup(&value, &table) {
+        queue.drain(|event| {
+            for (pid, message) in port.edge_messages(event, &table) {
+                let _ = send(ctx, pid, &message, &table);
+            }
+        });
+        return PortResult::Continue;
+    }
+    let mut monitor = PortProcessMonitor::new(ctx);
+    port.dispatch(Incoming::classify(value, &table), &mut monitor, &table)
+}
+
+/// Declare a GPIO port driver on top of a `GpioHal`
+///
+/// Exports `<name>_edge(pin, level)`, the `EdgeFn` handed to the HAL.
+///
+/// ```rust,ignore
+/// gpio_port!(gpio, BoardGpio);
+/// ```
+#[macro_export]
+macro_rules! gpio_port {
+    ($port_name:ident, $hal:ty) => {
+        ::paste::paste! {
+            static [<$port_name:upper _EDGES>]: $crate::port::gpio::EdgeQueue = $crate::port::gpio::EdgeQueue::new();
+
+            /// Interrupt entry: queue an edge and wake the port
+            #[no_mangle]
+            pub extern "C" fn [<$port_name _edge>](pin: u32, level: u32) {
+                [<$port_name:upper _EDGES>].raise_and_wake(pin, level);
+            }
+
+            fn [<$port_name _gpio_create>](
+                global: &$crate::context::GlobalContext,
+                opts: $crate::term::Term,
+            ) -> *mut $crate::context::Context {
+                $crate::port::gpio::create_port::<$hal>(global, opts, &[<$port_name:upper _EDGES>], [<$port_name _edge>])
+            }
+
+            fn [<$port_name _gpio_handler>](
+                ctx: &mut $crate::context::Context,
+                message: &$crate::port::Message,
+            ) -> $crate::port::PortResult {
+                $crate::port::gpio::handle_port_message::<$hal>(ctx, message, &[<$port_name:upper _EDGES>])
+            }
+
+            $crate::port_collection!(
+                $port_name,
+                create_port = [<$port_name _gpio_create>],
+                handler = [<$port_name _gpio_handler>]
+            );
+        }
+    };
+}
@@ -0,0 +1,357 @@
+//! Ports that forward interrupts to their owner
+//!
+//! `irq_port!` builds a whole port driver around an `IrqQueue`. It exports
+//! `<name>_irq(id, payload)`, the function to install as (or call from) the
+//! interrupt handler. Each call queues the event and wakes the port, which
+//! sends the owner `{irq, Id, Payload}`, one message per event, once the
+//! owner has started it. Nothing is built on a heap in interrupt context.
+//!
+//! Delivery does not depend on the queue having room. When it is full, an
+//! event is coalesced with the others of the same id: the owner gets one
+//! `{irq, Id, Payload}` for all of them, with the latest payload, after
+//! the queued events. Only ids at or above the `ids` bound are dropped when
+//! the queue is full; `IrqQueue::take_dropped` counts them.
+//!
+//! The wakeup is the `irq_wakeup` atom, sent to the port with
+//! `port_send_message_from_task`. Only the first event after a drain sends
+//! one, so a burst of interrupts costs one wakeup.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::irq_port;
+//!
+//! // Install `buttons_irq` for the button pins, passing the pin as id
+//! fn buttons_attach(_opts: Term) -> bool {
+//!     install_gpio_isrs(buttons_irq)
+//! }
+//!
+//! fn buttons_detach() {
+//!     remove_gpio_isrs();
+//! }
+//!
+//! irq_port!(buttons, capacity = 16, ids = 32, attach = buttons_attach, detach = buttons_detach);
+//! ```
+//!
+//! ```erlang
+//! Port = erlang:open_port({spawn, "buttons"}, []),
+//! {ok, active} = port:call(Port, start),
+//! receive {irq, Pin, Level} -> ... end.
+//! ```
+
+extern crate alloc;
+
+use super::isr_queue::IsrQueue;
+use super::{handle_standard_message, message_term, with_port_data, Message, PortData, PortResult};
+use crate::atom::{AtomIndex, AtomTable, AtomTableOps};
+use crate::context::{Context, PlatformData};
+use crate::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use crate::term::{NifError, Term, TermValue};
+
+/// One interrupt: which source fired, and a word of data captured with it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IrqEvent {
+    pub id: u32,
+    pub payload: u32,
+}
+
+impl IrqEvent {
+    /// `{irq, Id, Payload}`
+    pub fn to_message<T: AtomTableOps>(&self, table: &T) -> TermValue {
+        TermValue::tuple(alloc::vec![
+            TermValue::atom("irq", table),
+            TermValue::int(self.id as i32),
+            TermValue::int(self.payload as i32),
+        ])
+    }
+}
+
+/// Latest event of one id that found the queue full
+struct Coalesced {
+    pending: AtomicBool,
+    payload: AtomicU32,
+}
+
+impl Coalesced {
+    // Only used to initialize the array, never shared
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: Coalesced = Coalesced {
+        pending: AtomicBool::new(false),
+        payload: AtomicU32::new(0),
+    };
+}
+
+/// Interrupt events waiting for a port, with coalescing per id
+///
+/// Holds `N` events; ids below `IDS` are coalesced once it is full. `raise`
+/// is called from one interrupt handler, the rest from the port.
+pub struct IrqQueue<const N: usize, const IDS: usize> {
+    events: IsrQueue<IrqEvent, N>,
+    coalesced: [Coalesced; IDS],
+    wake_sent: AtomicBool,
+    dropped: AtomicUsize,
+    // The port to wake and the raw `irq_wakeup` atom, 0 until attached
+    port: AtomicU32,
+    wakeup: AtomicUsize,
+}
+
+impl<const N: usize, const IDS: usize> IrqQueue<N, IDS> {
+    pub const fn new() -> Self {
+        Self {
+            events: IsrQueue::new(),
+            coalesced: [Coalesced::EMPTY; IDS],
+            wake_sent: AtomicBool::new(false),
+            dropped: AtomicUsize::new(0),
+            port: AtomicU32::new(0),
+            wakeup: AtomicUsize::new(0),
+        }
+    }
+
+    /// Queue an event; returns whether the port needs waking
+    ///
+    /// The producer side, safe to call from an interrupt handler. Only the
+    /// first event after a drain asks for a wakeup.
+    pub fn raise(&self, id: u32, payload: u32) -> bool {
+        if self.events.push(IrqEvent { id, payload }).is_err() {
+            match self.coalesced.get(id as usize) {
+                Some(slot) => {
+                    slot.payload.store(payload, Ordering::Relaxed);
+                    slot.pending.store(true, Ordering::Release);
+                }
+                None => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    return false;
+                }
+            }
+        }
+        !self.wake_sent.swap(true, Ordering::AcqRel)
+    }
+
+    /// `raise`, then wake the attached port if needed
+    pub fn raise_and_wake(&self, id: u32, payload: u32) {
+        if self.raise(id, payload) {
+            self.wake();
+        }
+    }
+
+    /// Send `irq_wakeup` to the attached port
+    pub fn wake(&self) {
+        let port = self.port.load(Ordering::Acquire);
+        let wakeup = self.wakeup.load(Ordering::Acquire);
+        if port == 0 || wakeup == 0 {
+            // Not attached: allow the next event to try again
+            self.wake_sent.store(false, Ordering::Release);
+        } else {
+            #[cfg(not(test))]
+            unsafe {
+                super::port_send_message_from_task(crate::context::get_global_context(), port, wakeup);
+            }
+        }
+    }
+
+    /// Wake the port `port_pid` from now on
+    pub fn attach(&self, port_pid: u32, wakeup: AtomIndex) {
+        if let Ok(term) = Term::encode_atom(wakeup) {
+            self.wakeup.store(term.raw(), Ordering::Release);
+            self.port.store(port_pid, Ordering::Release);
+        }
+    }
+
+    /// Wake the port running on `ctx` from now on
+    pub fn attach_port(&self, ctx: &Context) {
+        if let Ok(wakeup) = AtomTable::from_global().ensure_atom_str("irq_wakeup") {
+            self.attach(unsafe { super::port_get_id(ctx) }, wakeup);
+        }
+    }
+
+    /// Stop waking the port; queued events stay until drained
+    pub fn detach(&self) {
+        self.port.store(0, Ordering::Release);
+        self.wake_sent.store(false, Ordering::Release);
+    }
+
+    /// Whether events or coalesced ids are waiting
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty() && !self.coalesced.iter().any(|slot| slot.pending.load(Ordering::Acquire))
+    }
+
+    /// Events of ids at or above `IDS` dropped on a full queue, since the last call
+    pub fn take_dropped(&self) -> usize {
+        self.dropped.swap(0, Ordering::Relaxed)
+    }
+
+    /// Pass every waiting event to `f`: the queued ones in order, then one
+    /// per coalesced id; returns how many
+    pub fn drain(&self, mut f: impl FnMut(IrqEvent)) -> usize {
+        // Cleared first, so an event raised during the drain wakes the port again
+        self.wake_sent.store(false, Ordering::Release);
+        let mut count = self.events.drain(&mut f);
+        for (id, slot) in self.coalesced.iter().enumerate() {
+            if slot.pending.swap(false, Ordering::AcqRel) {
+                f(IrqEvent { id: id as u32, payload: slot.payload.load(Ordering::Relaxed) });
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Send every waiting event to `pid` as `{irq, Id, Payload}`
+    ///
+    /// Events are sent one message each, in order. On a failed send the
+    /// rest of the drained events are lost.
+    pub fn forward<T: AtomTableOps>(&self, ctx: &Context, pid: u32, table: &T) -> Result<usize, NifError> {
+        let mut result = Ok(());
+        let count = self.drain(|event| {
+            if result.is_ok() {
+                result = super::send(ctx, pid, &event.to_message(table), table);
+            }
+        });
+        result.map(|()| count)
+    }
+
+    /// The port's wakeup message
+    pub fn is_wakeup<T: AtomTableOps>(message: &TermValue, table: &T) -> bool {
+        message.is_atom_str("irq_wakeup", table)
+    }
+}
+
+impl<const N: usize, const IDS: usize> Default for IrqQueue<N, IDS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Port data of an `irq_port!` driver
+pub struct IrqPort<const N: usize, const IDS: usize> {
+    queue: &'static IrqQueue<N, IDS>,
+    detach: fn(),
+}
+
+impl<const N: usize, const IDS: usize> IrqPort<N, IDS> {
+    pub fn new(queue: &'static IrqQueue<N, IDS>, detach: fn()) -> Self {
+        Self { queue, detach }
+    }
+}
+
+impl<const N: usize, const IDS: usize> PlatformData for IrqPort<N, IDS> {
+    fn cleanup(&mut self) {
+        (self.detach)();
+        self.queue.detach();
+    }
+}
+
+impl<const N: usize, const IDS: usize> PortData for IrqPort<N, IDS> {
+    fn handle_message(&mut self, _message: &Message) -> PortResult {
+        PortResult::ReplyError(TermValue::atom("badarg", &AtomTable::from_global()))
+    }
+}
+
+/// Message handler of an `irq_port!` driver
+///
+/// Forwards the waiting events on `irq_wakeup`, and after any other
+/// message once the port has an owner, so events raised before `start`
+/// are delivered too. Everything else goes to `handle_standard_message`.
+pub fn handle_irq_message<const N: usize, const IDS: usize>(
+    ctx: &mut Context,
+    message: &Message,
+    queue: &'static IrqQueue<N, IDS>,
+) -> PortResult {
+    let table = AtomTable::from_global();
+    let is_wakeup = message_term(message)
+        .to_value()
+        .is_ok_and(|value| IrqQueue::<N, IDS>::is_wakeup(&value, &table));
+    let result = if is_wakeup {
+        PortResult::Continue
+    } else {
+        handle_standard_message::<IrqPort<N, IDS>>(ctx, message)
+    };
+
+    if !result.is_terminate() && !queue.is_empty() {
+        let owner = with_port_data::<IrqPort<N, IDS>, _, _>(ctx, |data| data.get_owner_pid()).flatten();
+        if let Some(owner) = owner {
+            let _ = queue.forward(ctx, owner, &table);
+        }
+    }
+    result
+}
+
+/// Create an `irq_port!` port and attach its queue to it
+///
+/// `attached` is what the driver's `attach` returned; the port is not
+/// created if it failed.
+pub fn create_irq_port<const N: usize, const IDS: usize>(
+    global: &crate::context::GlobalContext,
+    queue: &'static IrqQueue<N, IDS>,
+    attached: bool,
+    detach: fn(),
+) -> *mut Context {
+    if !attached {
+        return core::ptr::null_mut();
+    }
+    let ctx = super::create_port_with_data(global, IrqPort::new(queue, detach));
+    if ctx.is_null() {
+        detach();
+        return ctx;
+    }
+    queue.attach_port(unsafe { &*ctx });
+    ctx
+}
+
+/// Define a port driver that forwards interrupts to its owner
+///
+/// Exports `<name>_irq(id: u32, payload: u32)` for the interrupt handler
+/// and registers the port `<name>` like `port_collection!`. `attach(opts)`
+/// installs the interrupt handler when a port opens; returning `false`
+/// fails the open. `detach()` removes it when the port closes. One port of
+/// the driver can be open at a time.
+///
+/// ```rust,ignore
+/// irq_port!(buttons, capacity = 16, ids = 32, attach = buttons_attach, detach = buttons_detach);
+/// ```
+#[macro_export]
+macro_rules! irq_port {
+    (
+        $name:ident,
+        capacity = $capacity:expr,
+        ids = $ids:expr,
+        attach = $attach_fn:ident,
+        detach = $detach_fn:ident
+    ) => {
+        paste::paste! {
+            static [<$name:upper _IRQ>]: $crate::port::irq::IrqQueue<{ $capacity }, { $ids }> =
+                $crate::port::irq::IrqQueue::new();
+
+            /// Interrupt entry: queue an event and wake the port
+            #[no_mangle]
+            pub extern "C" fn [<$name _irq>](id: u32, payload: u32) {
+                [<$name:upper _IRQ>].raise_and_wake(id, payload);
+            }
+
+            fn [<$name _irq_setup>](_global: &mut $crate::context::GlobalContext) {}
+
+            fn [<$name _irq_teardown>](_global: &mut $crate::context::GlobalContext) {}
+
+            fn [<$name _irq_create>](
+                global: &$crate::context::GlobalContext,
+                opts: $crate::term::Term,
+            ) -> *mut $crate::context::Context {
+                $crate::port::irq::create_irq_port(global, &[<$name:upper _IRQ>], $attach_fn(opts), $detach_fn)
+            }
+
+            fn [<$name _irq_handler>](
+                ctx: &mut $crate::context::Context,
+                message: &$crate::port::Message,
+            ) -> $crate::port::PortResult {
+                $crate::port::irq::handle_irq_message(ctx, message, &[<$name:upper _IRQ>])
+            }
+
+            $crate::port_collection!(
+                $name,
+                init = [<$name _irq_setup>],
+                destroy = [<$name _irq_teardown>],
+                create_port = [<$name _irq_create>],
+                handler = [<$name _irq_handler>]
+            );
+        }
+    };
+}
@@ -0,0 +1,194 @@
+//! Handing events from interrupt handlers to a port
+//!
+//! An interrupt handler must not build terms: that allocates on a heap the
+//! scheduler may be using at the same moment. `IsrQueue` lets the handler
+//! push plain `Copy` events (a pin level, an ADC sample, a timestamp) into
+//! a fixed ring instead. The port, running on the scheduler, drains the
+//! ring and turns the events into terms there.
+//!
+//! The queue is lock-free: `push` and `pop` only load and store atomics, so
+//! they work on targets without compare-and-swap and never block an
+//! interrupt. It has one producer and one consumer. Interrupts that can
+//! preempt each other need a queue each; one port draining them all is
+//! fine. When the ring is full, new events are dropped and counted.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::port::isr_queue::IsrQueue;
+//!
+//! static EDGES: IsrQueue<(u8, u32), 32> = IsrQueue::new();
+//!
+//! // Interrupt handler
+//! fn on_gpio_irq(pin: u8, now: u32) {
+//!     let _ = EDGES.push((pin, now));
+//! }
+//!
+//! // Port handler, on a timer wakeup
+//! EDGES.forward(ctx, owner, &table, |(pin, at)| {
+//!     TermValue::tuple(vec![TermValue::int(pin as i32), TermValue::int(at as i32)])
+//! })?;
+//! ```
+
+use crate::atom::AtomTableOps;
+use crate::context::Context;
+use crate::etf::{EtfResult, ListEncoder};
+use crate::sync::atomic::{AtomicUsize, Ordering};
+use crate::term::{NifError, TermValue};
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+
+/// Bounded single-producer, single-consumer queue of raw events
+///
+/// `push` is called from one interrupt handler, everything else from the
+/// scheduler side. Holds up to `N` events.
+pub struct IsrQueue<E: Copy, const N: usize> {
+    slots: UnsafeCell<[MaybeUninit<E>; N]>,
+    // Events pushed and popped so far, modulo 2 * N so that a full ring and
+    // an empty one differ for any N; only the producer writes `pushed` and
+    // only the consumer writes `popped`
+    pushed: AtomicUsize,
+    popped: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+// A slot is written by the producer before `pushed` publishes it and read
+// by the consumer before `popped` hands it back
+unsafe impl<E: Copy + Send, const N: usize> Sync for IsrQueue<E, N> {}
+
+impl<E: Copy, const N: usize> IsrQueue<E, N> {
+    pub const fn new() -> Self {
+        assert!(N > 0, "an IsrQueue needs room for at least one event");
+        assert!(N <= usize::MAX / 2, "an IsrQueue counts up to twice its capacity");
+        Self {
+            // An array of `MaybeUninit` needs no initialization
+            slots: UnsafeCell::new(unsafe { MaybeUninit::<[MaybeUninit<E>; N]>::uninit().assume_init() }),
+            pushed: AtomicUsize::new(0),
+            popped: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Events waiting to be drained
+    pub fn len(&self) -> usize {
+        let popped = self.popped.load(Ordering::Acquire);
+        Self::distance(self.pushed.load(Ordering::Acquire), popped)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Queue `event`; gives it back, and counts it as dropped, when full
+    ///
+    /// The producer side, safe to call from an interrupt handler.
+    pub fn push(&self, event: E) -> Result<(), E> {
+        let pushed = self.pushed.load(Ordering::Relaxed);
+        let popped = self.popped.load(Ordering::Acquire);
+        if Self::distance(pushed, popped) >= N {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return Err(event);
+        }
+        unsafe {
+            (*self.slots.get())[pushed % N].write(event);
+        }
+        self.pushed.store(Self::advance(pushed), Ordering::Release);
+        Ok(())
+    }
+
+    /// The oldest queued event
+    pub fn pop(&self) -> Option<E> {
+        let popped = self.popped.load(Ordering::Relaxed);
+        if self.pushed.load(Ordering::Acquire) == popped {
+            return None;
+        }
+        let event = unsafe { (*self.slots.get())[popped % N].assume_init_read() };
+        self.popped.store(Self::advance(popped), Ordering::Release);
+        Some(event)
+    }
+
+    /// The count after `count`, modulo 2 * N
+    fn advance(count: usize) -> usize {
+        if count + 1 == 2 * N {
+            0
+        } else {
+            count + 1
+        }
+    }
+
+    /// Events between two counts taken modulo 2 * N
+    fn distance(pushed: usize, popped: usize) -> usize {
+        if pushed >= popped {
+            pushed - popped
+        } else {
+            pushed + 2 * N - popped
+        }
+    }
+
+    /// Events dropped because the queue was full, since the last `take_dropped`
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Read and reset the dropped count
+    pub fn take_dropped(&self) -> usize {
+        self.dropped.swap(0, Ordering::Relaxed)
+    }
+
+    /// Pass every queued event to `f`, oldest first; returns how many
+    ///
+    /// Events pushed while draining are taken too.
+    pub fn drain(&self, mut f: impl FnMut(E)) -> usize {
+        let mut count = 0;
+        while let Some(event) = self.pop() {
+            f(event);
+            count += 1;
+        }
+        count
+    }
+
+    /// Convert every queued event to a term and add it to `batch`
+    ///
+    /// Send the batch with `port::send_batch` or `send_batch_from_task`. An
+    /// event that fails to encode is lost; the ones after it stay queued.
+    pub fn collect_into<T: AtomTableOps>(
+        &self,
+        batch: &mut ListEncoder,
+        table: &T,
+        mut convert: impl FnMut(E) -> TermValue,
+    ) -> EtfResult<usize> {
+        let mut count = 0;
+        while let Some(event) = self.pop() {
+            batch.push(&convert(event), table)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Send every queued event to `pid` as one list message, `[E1, E2, ...]`
+    ///
+    /// Returns how many events were sent; nothing is sent when the queue is
+    /// empty.
+    pub fn forward<T: AtomTableOps>(
+        &self,
+        ctx: &Context,
+        pid: u32,
+        table: &T,
+        convert: impl FnMut(E) -> TermValue,
+    ) -> Result<usize, NifError> {
+        let mut batch = ListEncoder::with_capacity(self.len() * 8);
+        let count = self.collect_into(&mut batch, table, convert)?;
+        super::send_batch(ctx, pid, &mut batch)?;
+        Ok(count)
+    }
+}
+
+impl<E: Copy, const N: usize> Default for IsrQueue<E, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,129 @@
+//! Access control for port commands
+//!
+//! Some commands drive hardware that must not be touched by just any
+//! process: erasing flash, reprogramming a radio, cutting power to a rail.
+//! A `CommandPolicy` is asked before each command is dispatched, with the
+//! caller's pid and the command's name, and a rejected command is answered
+//! with `{error, not_allowed}` without reaching the handler.
+//!
+//! The command name is the atom itself for `erase`, or the first element
+//! for `{erase, Sector}`. Commands without a name are not checked.
+//!
+//! `PortData::allow_command` and `PortBehavior::allow_call` are the hooks
+//! used by `handle_standard_message` and `port_behavior!`; both allow
+//! everything by default. `RestrictedCommands` covers the common case of a
+//! few commands reserved for known processes.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::port::policy::{CommandPolicy, RestrictedCommands};
+//!
+//! struct Flash { policy: RestrictedCommands }
+//!
+//! impl PortData for Flash {
+//!     fn allow_command(&self, caller: u32, command: AtomIndex) -> bool {
+//!         self.policy.allow(caller, command)
+//!     }
+//! }
+//!
+//! // Only the updater may erase
+//! let erase = table.ensure_atom_str("erase")?;
+//! let policy = RestrictedCommands::new().restrict(erase, &[updater_pid]);
+//! ```
+
+extern crate alloc;
+
+use crate::atom::AtomIndex;
+use crate::port::{PortError, PortOpResult};
+use crate::term::TermValue;
+use alloc::vec::Vec;
+
+/// Decides which callers may run which commands
+pub trait CommandPolicy {
+    /// Whether `caller` may run the command named `command`
+    fn allow(&self, caller: u32, command: AtomIndex) -> bool;
+}
+
+impl<F: Fn(u32, AtomIndex) -> bool> CommandPolicy for F {
+    fn allow(&self, caller: u32, command: AtomIndex) -> bool {
+        self(caller, command)
+    }
+}
+
+/// Policy that allows every command
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllowAll;
+
+impl CommandPolicy for AllowAll {
+    fn allow(&self, _caller: u32, _command: AtomIndex) -> bool {
+        true
+    }
+}
+
+/// Commands reserved for listed callers; all other commands are allowed
+#[derive(Debug, Clone, Default)]
+pub struct RestrictedCommands {
+    entries: Vec<(AtomIndex, Vec<u32>)>,
+}
+
+impl RestrictedCommands {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserve `command` for `callers`; an empty list locks it for everyone
+    pub fn restrict(mut self, command: AtomIndex, callers: &[u32]) -> Self {
+        match self.entries.iter_mut().find(|(name, _)| *name == command) {
+            Some((_, allowed)) => allowed.extend_from_slice(callers),
+            None => self.entries.push((command, callers.to_vec())),
+        }
+        self
+    }
+
+    /// Let `caller` run the restricted `command`
+    pub fn grant(&mut self, command: AtomIndex, caller: u32) {
+        if let Some((_, allowed)) = self.entries.iter_mut().find(|(name, _)| *name == command) {
+            if !allowed.contains(&caller) {
+                allowed.push(caller);
+            }
+        }
+    }
+
+    /// Take every grant away from `caller`, e.g. when it goes down
+    pub fn revoke(&mut self, caller: u32) {
+        for (_, allowed) in &mut self.entries {
+            allowed.retain(|pid| *pid != caller);
+        }
+    }
+
+    pub fn is_restricted(&self, command: AtomIndex) -> bool {
+        self.entries.iter().any(|(name, _)| *name == command)
+    }
+}
+
+impl CommandPolicy for RestrictedCommands {
+    fn allow(&self, caller: u32, command: AtomIndex) -> bool {
+        match self.entries.iter().find(|(name, _)| *name == command) {
+            Some((_, allowed)) => allowed.contains(&caller),
+            None => true,
+        }
+    }
+}
+
+/// The name of a command: `erase` for both `erase` and `{erase, Sector}`
+pub fn command_name(command: &TermValue) -> Option<AtomIndex> {
+    match command {
+        TermValue::Atom(name) => Some(*name),
+        TermValue::Tuple(elements) => elements.first().and_then(TermValue::as_atom),
+        _ => None,
+    }
+}
+
+/// Check a command against a policy; `PortError::NotAllowed` if rejected
+pub fn authorize<P: CommandPolicy + ?Sized>(policy: &P, caller: u32, command: &TermValue) -> PortOpResult<()> {
+    match command_name(command) {
+        Some(name) if !policy.allow(caller, name) => Err(PortError::NotAllowed),
+        _ => Ok(()),
+    }
+}
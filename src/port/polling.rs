@@ -0,0 +1,295 @@
+//! Ports that poll a sensor on a timer
+//!
+//! Many drivers only read a value every so often and pass it on.
+//! `PollingPort` does everything but the reading: the driver supplies
+//! `poll(&mut T) -> Option<TermValue>` and an interval, and the port keeps
+//! the periodic timer, the monitored subscribers, and sends each reading
+//! to every subscriber as `{reading, Value}`. A `None` reading sends
+//! nothing. The timer only runs while someone is subscribed.
+//!
+//! Calls the port answers (`port:call/2`):
+//!
+//! - `subscribe` / `unsubscribe` add or remove the caller, answering `ok`
+//! - `read` polls right away, answering `{ok, Value}` or `{error, no_reading}`
+//! - `{interval, Ms}` changes the interval, answering `ok`
+//!
+//! Subscribers that go down are dropped.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::polling_port;
+//!
+//! struct Thermometer { channel: u8 }
+//!
+//! fn thermometer_init(_opts: &TermValue) -> Result<Thermometer, PortError> {
+//!     Ok(Thermometer { channel: 3 })
+//! }
+//!
+//! fn read_temperature(sensor: &mut Thermometer) -> Option<TermValue> {
+//!     adc_read(sensor.channel).map(TermValue::int)
+//! }
+//!
+//! polling_port!(thermometer, Thermometer, init = thermometer_init, poll = read_temperature, interval_ms = 1000);
+//! ```
+//!
+//! ```erlang
+//! Port = erlang:open_port({spawn, "thermometer"}, []),
+//! ok = port:call(Port, subscribe),
+//! receive {reading, Celsius} -> ... end.
+//! ```
+
+extern crate alloc;
+
+use super::behavior::Incoming;
+use super::timer::{AtomVMTimerBackend, PortTimers, TimerBackend, TimerRef};
+use super::{send, Message, PortError, PortProcessMonitor, PortResult};
+use crate::atom::{AtomTable, AtomTableOps};
+use crate::context::{Context, ContextExt, GlobalContext, PlatformData, PortBuilder};
+use crate::monitor::{MonitorError, PidSet, ProcessMonitor};
+use crate::term::{Term, TermValue};
+
+/// Reads one value from the driver state, or nothing this time
+pub type PollFn<T> = fn(&mut T) -> Option<TermValue>;
+
+/// Driver state polled on a timer, with the processes subscribed to it
+pub struct PollingPort<T, H = ()> {
+    state: T,
+    poll: PollFn<T>,
+    interval_ms: u64,
+    timers: PortTimers,
+    timer: Option<TimerRef>,
+    subscribers: PidSet<H>,
+}
+
+impl<T, H> PollingPort<T, H> {
+    pub fn new(state: T, interval_ms: u64, poll: PollFn<T>) -> Self {
+        Self {
+            state,
+            poll,
+            interval_ms: interval_ms.max(1),
+            timers: PortTimers::new(),
+            timer: None,
+            subscribers: PidSet::new(),
+        }
+    }
+
+    pub fn state(&self) -> &T {
+        &self.state
+    }
+
+    pub fn state_mut(&mut self) -> &mut T {
+        &mut self.state
+    }
+
+    pub fn interval_ms(&self) -> u64 {
+        self.interval_ms
+    }
+
+    pub fn subscribers(&self) -> &PidSet<H> {
+        &self.subscribers
+    }
+
+    /// Whether the poll timer is running
+    pub fn is_polling(&self) -> bool {
+        self.timer.is_some()
+    }
+
+    /// Read a value now, outside the timer
+    pub fn poll_now(&mut self) -> Option<TermValue> {
+        (self.poll)(&mut self.state)
+    }
+
+    /// Add `pid`, starting the timer for the first subscriber
+    pub fn subscribe<B, M>(&mut self, backend: &mut B, monitor: &mut M, pid: u32) -> Result<bool, MonitorError>
+    where
+        B: TimerBackend,
+        M: ProcessMonitor<Handle = H>,
+    {
+        let added = self.subscribers.insert(monitor, pid)?;
+        self.update_timer(backend);
+        Ok(added)
+    }
+
+    /// Remove `pid`, stopping the timer after the last subscriber
+    pub fn unsubscribe<B, M>(&mut self, backend: &mut B, monitor: &mut M, pid: u32) -> bool
+    where
+        B: TimerBackend,
+        M: ProcessMonitor<Handle = H>,
+    {
+        let removed = self.subscribers.remove(monitor, pid);
+        self.update_timer(backend);
+        removed
+    }
+
+    /// Poll every `interval_ms` from now on, restarting a running timer
+    pub fn set_interval<B: TimerBackend>(&mut self, backend: &mut B, interval_ms: u64) {
+        self.interval_ms = interval_ms.max(1);
+        if let Some(timer) = self.timer.take() {
+            self.timers.cancel(backend, timer);
+        }
+        self.update_timer(backend);
+    }
+
+    /// Handle the platform's timer wakeup; the `{reading, Value}` to send, if due
+    pub fn on_wakeup<B: TimerBackend, A: AtomTableOps>(&mut self, backend: &mut B, table: &A) -> Option<TermValue> {
+        let fired = self.timers.dispatch(backend, table).ok()?;
+        let timer = self.timer?;
+        let due = fired.iter().any(|message| PortTimers::match_timeout(message, table) == Some(timer));
+        if !due || self.subscribers.is_empty() {
+            return None;
+        }
+        self.poll_now().map(|value| Self::reading_message(value, table))
+    }
+
+    /// Answer a call, or handle a DOWN for a subscriber
+    ///
+    /// The timer wakeup is handled by `on_wakeup`; other messages are ignored.
+    pub fn dispatch<B, M, A>(&mut self, message: Incoming, backend: &mut B, monitor: &mut M, table: &A) -> PortResult
+    where
+        B: TimerBackend,
+        M: ProcessMonitor<Handle = H>,
+        A: AtomTableOps,
+    {
+        let (request, from) = match message {
+            Incoming::Call { request, from } => (request, from),
+            Incoming::Info(info) => {
+                if self.subscribers.handle_down_message(&info, table, |_| {}) {
+                    self.update_timer(backend);
+                }
+                return PortResult::Continue;
+            }
+            Incoming::Cast(_) => return PortResult::Continue,
+        };
+
+        let ok = || PortResult::Reply(TermValue::atom("ok", table));
+        let error = |reason: &str| PortResult::ReplyError(TermValue::atom(reason, table));
+        if request.is_atom_str("subscribe", table) {
+            match self.subscribe(backend, monitor, from.pid) {
+                Ok(_) => ok(),
+                Err(_) => error("noproc"),
+            }
+        } else if request.is_atom_str("unsubscribe", table) {
+            self.unsubscribe(backend, monitor, from.pid);
+            ok()
+        } else if request.is_atom_str("read", table) {
+            match self.poll_now() {
+                Some(value) => PortResult::Reply(TermValue::tuple(alloc::vec![TermValue::atom("ok", table), value])),
+                None => error("no_reading"),
+            }
+        } else if let Some([tag, interval]) = request.as_tuple() {
+            match interval.as_int() {
+                Some(ms) if tag.is_atom_str("interval", table) && ms > 0 => {
+                    self.set_interval(backend, ms as u64);
+                    ok()
+                }
+                _ => error("badarg"),
+            }
+        } else {
+            error("badarg")
+        }
+    }
+
+    /// `{reading, Value}`
+    pub fn reading_message<A: AtomTableOps>(value: TermValue, table: &A) -> TermValue {
+        TermValue::tuple(alloc::vec![TermValue::atom("reading", table), value])
+    }
+
+    /// Run the timer exactly while there are subscribers
+    fn update_timer<B: TimerBackend>(&mut self, backend: &mut B) {
+        match (self.timer, self.subscribers.is_empty()) {
+            (None, false) => self.timer = Some(self.timers.start_periodic(backend, self.interval_ms)),
+            (Some(timer), true) => {
+                self.timers.cancel(backend, timer);
+                self.timer = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<T, H> PlatformData for PollingPort<T, H> {}
+
+/// Port create function generated by `polling_port!`
+pub fn create_port<T>(
+    global: &GlobalContext,
+    opts: Term,
+    init: fn(&TermValue) -> Result<T, PortError>,
+    poll: PollFn<T>,
+    interval_ms: u64,
+) -> *mut Context {
+    let opts = opts.to_value().unwrap_or(TermValue::Nil);
+    match init(&opts) {
+        Ok(state) => PortBuilder::new(PollingPort::<T>::new(state, interval_ms, poll)).build(global),
+        Err(_) => core::ptr::null_mut(),
+    }
+}
+
+/// Port handler generated by `polling_port!`
+///
+/// Readings that cannot be delivered to a subscriber are dropped.
+pub fn handle_port_message<T>(ctx: &mut Context, message: &Message) -> PortResult {
+    let port = unsafe {
+        let data_ptr = ctx.get_platform_data_as::<PollingPort<T>>();
+        if data_ptr.is_null() {
+            return PortResult::Terminate;
+        }
+        &mut *data_ptr
+    };
+    let table = AtomTable::from_global();
+    let Ok(value) = super::message_term(message).to_value() else {
+        return PortResult::Continue;
+    };
+    let mut backend = AtomVMTimerBackend::new(ctx);
+    if PortTimers::is_wakeup(&value, &table) {
+        if let Some(reading) = port.on_wakeup(&mut backend, &table) {
+            for pid in port.subscribers().pids() {
+                let _ = send(ctx, pid, &reading, &table);
+            }
+        }
+        return PortResult::Continue;
+    }
+    let mut monitor = PortProcessMonitor::new(ctx);
+    port.dispatch(Incoming::classify(value, &table), &mut backend, &mut monitor, &table)
+}
+
+/// Declare a port driver that polls `poll` every `interval_ms`
+///
+/// `init` builds the state from the `open_port` options; an error fails
+/// the port creation.
+///
+/// ```rust,ignore
+/// polling_port!(thermometer, Thermometer, init = thermometer_init, poll = read_temperature, interval_ms = 1000);
+/// ```
+#[macro_export]
+macro_rules! polling_port {
+    (
+        $port_name:ident,
+        $state:ty,
+        init = $init_fn:path,
+        poll = $poll_fn:path,
+        interval_ms = $interval:expr
+    ) => {
+        ::paste::paste! {
+            fn [<$port_name _polling_create>](
+                global: &$crate::context::GlobalContext,
+                opts: $crate::term::Term,
+            ) -> *mut $crate::context::Context {
+                $crate::port::polling::create_port::<$state>(global, opts, $init_fn, $poll_fn, $interval)
+            }
+
+            fn [<$port_name _polling_handler>](
+                ctx: &mut $crate::context::Context,
+                message: &$crate::port::Message,
+            ) -> $crate::port::PortResult {
+                $crate::port::polling::handle_port_message::<$state>(ctx, message)
+            }
+
+            $crate::port_collection!(
+                $port_name,
+                create_port = [<$port_name _polling_create>],
+                handler = [<$port_name _polling_handler>]
+            );
+        }
+    };
+}
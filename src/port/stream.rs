@@ -0,0 +1,429 @@
+//! Byte stream ports with framing
+//!
+//! A UART, USB CDC or TCP-like link delivers bytes in arbitrary chunks,
+//! while the Erlang side wants whole messages. `StreamPort` buffers what
+//! the `StreamBackend` has received, cuts it into frames with a `Framer`
+//! and sends each frame to the port's owner as `{frame, Binary}`. Going
+//! the other way, `port:call(Port, {send, Data})` frames `Data` and writes
+//! it out, answering `ok`.
+//!
+//! Three framers are provided:
+//!
+//! - `Lines`: frames end with a delimiter byte, `\n` by default; a `\r`
+//!   before a `\n` is dropped
+//! - `LengthPrefixed<N>`: each frame starts with its length as an `N`-byte
+//!   big-endian integer (1, 2 or 4)
+//! - `Slip`: RFC 1055 framing, as used by many serial protocols
+//!
+//! The port reads the backend on a periodic timer. The owner is the
+//! process that sends `start`, as with `handle_standard_message`; frames
+//! received before that are dropped. A partial frame that grows past
+//! `MAX_BUFFER` bytes is thrown away.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::stream_port;
+//! use avmnif_rs::port::stream::{Lines, StreamBackend};
+//!
+//! impl StreamBackend for Uart1 { ... }
+//!
+//! stream_port!(uart1, Uart1, Lines, poll_ms = 20);
+//! ```
+//!
+//! ```erlang
+//! Port = erlang:open_port({spawn, "uart1"}, []),
+//! {ok, active} = port:call(Port, start),
+//! ok = port:call(Port, {send, <<"AT">>}),
+//! receive {frame, <<"OK">>} -> ok end.
+//! ```
+
+extern crate alloc;
+
+use super::timer::{AtomVMTimerBackend, PortTimers, TimerBackend, TimerRef};
+use super::{
+    create_port_with_data, handle_standard_message, message_term, parse_gen_message, send, with_port_data_mut, Message,
+    PortData, PortError, PortResult,
+};
+use crate::atom::{AtomTable, AtomTableOps};
+use crate::context::{Context, GlobalContext, PlatformData};
+use crate::term::{Term, TermValue};
+use alloc::vec::Vec;
+
+/// Largest partial frame kept while waiting for the rest
+pub const MAX_BUFFER: usize = 4096;
+
+/// Splits a byte stream into frames and frames outgoing data
+pub trait Framer {
+    /// Take the next complete frame off the front of `buffer`
+    fn decode(&mut self, buffer: &mut Vec<u8>) -> Option<Vec<u8>>;
+
+    /// Append `payload`, framed, to `out`
+    fn encode(&self, payload: &[u8], out: &mut Vec<u8>) -> Result<(), PortError>;
+}
+
+/// Frames ended by a delimiter byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lines {
+    delimiter: u8,
+}
+
+impl Lines {
+    pub fn new(delimiter: u8) -> Self {
+        Self { delimiter }
+    }
+}
+
+impl Default for Lines {
+    fn default() -> Self {
+        Self::new(b'\n')
+    }
+}
+
+impl Framer for Lines {
+    fn decode(&mut self, buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
+        let end = buffer.iter().position(|byte| *byte == self.delimiter)?;
+        let mut frame: Vec<u8> = buffer.drain(..=end).collect();
+        frame.pop();
+        if self.delimiter == b'\n' && frame.last() == Some(&b'\r') {
+            frame.pop();
+        }
+        Some(frame)
+    }
+
+    fn encode(&self, payload: &[u8], out: &mut Vec<u8>) -> Result<(), PortError> {
+        if payload.contains(&self.delimiter) {
+            return Err(PortError::InvalidMessage);
+        }
+        out.extend_from_slice(payload);
+        out.push(self.delimiter);
+        Ok(())
+    }
+}
+
+/// Frames preceded by their length, `N` bytes big-endian
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LengthPrefixed<const N: usize = 2>;
+
+impl<const N: usize> LengthPrefixed<N> {
+    const VALID: () = assert!(N == 1 || N == 2 || N == 4, "length prefix must be 1, 2 or 4 bytes");
+}
+
+impl<const N: usize> Framer for LengthPrefixed<N> {
+    fn decode(&mut self, buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
+        let () = Self::VALID;
+        let header = buffer.get(..N)?;
+        let len = header.iter().fold(0usize, |len, byte| (len << 8) | *byte as usize);
+        if buffer.len() < N + len {
+            return None;
+        }
+        let frame = buffer[N..N + len].to_vec();
+        buffer.drain(..N + len);
+        Some(frame)
+    }
+
+    fn encode(&self, payload: &[u8], out: &mut Vec<u8>) -> Result<(), PortError> {
+        if N < core::mem::size_of::<usize>() && payload.len() >> (8 * N) != 0 {
+            return Err(PortError::InvalidMessage);
+        }
+        let len = payload.len().to_be_bytes();
+        out.extend_from_slice(&len[len.len() - N..]);
+        out.extend_from_slice(payload);
+        Ok(())
+    }
+}
+
+/// RFC 1055 SLIP framing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Slip;
+
+impl Slip {
+    pub const END: u8 = 0xC0;
+    pub const ESC: u8 = 0xDB;
+    pub const ESC_END: u8 = 0xDC;
+    pub const ESC_ESC: u8 = 0xDD;
+}
+
+impl Framer for Slip {
+    fn decode(&mut self, buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
+        loop {
+            let end = buffer.iter().position(|byte| *byte == Self::END)?;
+            let raw: Vec<u8> = buffer.drain(..=end).collect();
+            // Back-to-back ENDs only flush line noise
+            if end == 0 {
+                continue;
+            }
+            let mut frame = Vec::with_capacity(end);
+            let mut escaped = false;
+            for byte in &raw[..end] {
+                let byte = match (escaped, *byte) {
+                    (false, Self::ESC) => {
+                        escaped = true;
+                        continue;
+                    }
+                    (true, Self::ESC_END) => Self::END,
+                    (true, Self::ESC_ESC) => Self::ESC,
+                    (_, byte) => byte,
+                };
+                escaped = false;
+                frame.push(byte);
+            }
+            return Some(frame);
+        }
+    }
+
+    fn encode(&self, payload: &[u8], out: &mut Vec<u8>) -> Result<(), PortError> {
+        out.push(Self::END);
+        for byte in payload {
+            match *byte {
+                Self::END => out.extend_from_slice(&[Self::ESC, Self::ESC_END]),
+                Self::ESC => out.extend_from_slice(&[Self::ESC, Self::ESC_ESC]),
+                byte => out.push(byte),
+            }
+        }
+        out.push(Self::END);
+        Ok(())
+    }
+}
+
+/// The link a `StreamPort` reads from and writes to
+pub trait StreamBackend: Sized {
+    /// Open the link from the `open_port` options; an error fails the open
+    fn init(opts: &TermValue) -> Result<Self, PortError>;
+
+    /// Copy bytes received so far into `buffer`, without blocking
+    ///
+    /// Returns how many were copied; 0 when there is nothing new.
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, PortError>;
+
+    /// Write all of `data`
+    fn write(&mut self, data: &[u8]) -> Result<(), PortError>;
+
+    /// Called once when the port stops
+    fn close(&mut self) {}
+}
+
+/// Port state of a framed byte stream
+pub struct StreamPort<S: StreamBackend, F: Framer> {
+    backend: S,
+    framer: F,
+    buffer: Vec<u8>,
+    overflows: usize,
+    timers: PortTimers,
+    poll: Option<TimerRef>,
+    closed: bool,
+}
+
+impl<S: StreamBackend, F: Framer> StreamPort<S, F> {
+    pub fn new(backend: S, framer: F) -> Self {
+        Self {
+            backend,
+            framer,
+            buffer: Vec::new(),
+            overflows: 0,
+            timers: PortTimers::new(),
+            poll: None,
+            closed: false,
+        }
+    }
+
+    pub fn backend(&self) -> &S {
+        &self.backend
+    }
+
+    pub fn backend_mut(&mut self) -> &mut S {
+        &mut self.backend
+    }
+
+    /// Bytes of the partial frame waiting for the rest
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Partial frames thrown away for growing past `MAX_BUFFER`
+    pub fn overflows(&self) -> usize {
+        self.overflows
+    }
+
+    /// Add received bytes and return the frames they complete
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<Vec<u8>> {
+        self.buffer.extend_from_slice(bytes);
+        let mut frames = Vec::new();
+        while let Some(frame) = self.framer.decode(&mut self.buffer) {
+            frames.push(frame);
+        }
+        if self.buffer.len() > MAX_BUFFER {
+            self.buffer.clear();
+            self.overflows += 1;
+        }
+        frames
+    }
+
+    /// Read everything the backend has and return the complete frames
+    pub fn receive(&mut self) -> Result<Vec<Vec<u8>>, PortError> {
+        let mut frames = Vec::new();
+        let mut chunk = [0u8; 64];
+        loop {
+            let count = self.backend.read(&mut chunk)?.min(chunk.len());
+            if count == 0 {
+                return Ok(frames);
+            }
+            frames.extend(self.feed(&chunk[..count]));
+        }
+    }
+
+    /// Frame `payload` and write it out
+    pub fn send(&mut self, payload: &[u8]) -> Result<(), PortError> {
+        let mut out = Vec::with_capacity(payload.len() + 4);
+        self.framer.encode(payload, &mut out)?;
+        self.backend.write(&out)
+    }
+
+    /// Read the backend every `interval_ms`
+    pub fn start_polling<B: TimerBackend>(&mut self, backend: &mut B, interval_ms: u64) {
+        if self.poll.is_none() {
+            self.poll = Some(self.timers.start_periodic(backend, interval_ms.max(1)));
+        }
+    }
+
+    /// Handle the platform's timer wakeup; the frames received, if it was time to read
+    pub fn on_wakeup<B: TimerBackend, T: AtomTableOps>(&mut self, backend: &mut B, table: &T) -> Result<Vec<Vec<u8>>, PortError> {
+        let fired = self.timers.dispatch(backend, table).map_err(|_| PortError::OutOfMemory)?;
+        let due = self
+            .poll
+            .is_some_and(|poll| fired.iter().any(|message| PortTimers::match_timeout(message, table) == Some(poll)));
+        if due {
+            self.receive()
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Answer `{send, Data}`
+    pub fn handle_request<T: AtomTableOps>(&mut self, request: &TermValue, table: &T) -> PortResult {
+        let result = match request.as_tuple() {
+            Some([tag, data]) if tag.is_atom_str("send", table) => crate::iolist::flatten(data)
+                .map_err(|_| PortError::InvalidMessage)
+                .and_then(|data| self.send(&data)),
+            _ => Err(PortError::InvalidMessage),
+        };
+        match result {
+            Ok(()) => PortResult::Reply(TermValue::atom("ok", table)),
+            Err(e) => PortResult::ReplyError(TermValue::atom(e.reason(), table)),
+        }
+    }
+
+    /// `{frame, Binary}`
+    pub fn frame_message<T: AtomTableOps>(frame: Vec<u8>, table: &T) -> TermValue {
+        TermValue::tuple(alloc::vec![TermValue::atom("frame", table), TermValue::binary(frame)])
+    }
+}
+
+impl<S: StreamBackend, F: Framer> PlatformData for StreamPort<S, F> {
+    fn cleanup(&mut self) {
+        if !self.closed {
+            self.closed = true;
+            self.backend.close();
+        }
+    }
+}
+
+impl<S: StreamBackend, F: Framer> PortData for StreamPort<S, F> {
+    fn handle_message(&mut self, message: &Message) -> PortResult {
+        let table = AtomTable::from_global();
+        match parse_gen_message(message).ok().and_then(|(_, _, request)| request.to_value().ok()) {
+            Some(request) => self.handle_request(&request, &table),
+            None => PortResult::ReplyError(TermValue::atom("badarg", &table)),
+        }
+    }
+}
+
+/// Port create function generated by `stream_port!`
+pub fn create_port<S: StreamBackend, F: Framer>(
+    global: &GlobalContext,
+    opts: Term,
+    framer: F,
+    poll_ms: u64,
+) -> *mut Context {
+    let opts = opts.to_value().unwrap_or(TermValue::Nil);
+    let Ok(backend) = S::init(&opts) else {
+        return core::ptr::null_mut();
+    };
+    let ctx = create_port_with_data(global, StreamPort::new(backend, framer));
+    if let Some(ctx) = unsafe { ctx.as_mut() } {
+        let mut timers = AtomVMTimerBackend::new(ctx);
+        with_port_data_mut::<StreamPort<S, F>, _, _>(ctx, |data| data.get_inner_mut().start_polling(&mut timers, poll_ms));
+    }
+    ctx
+}
+
+/// Port handler generated by `stream_port!`
+///
+/// Reads the backend on the poll timer and sends each frame to the owner;
+/// everything else goes to `handle_standard_message`.
+pub fn handle_port_message<S: StreamBackend, F: Framer>(ctx: &mut Context, message: &Message) -> PortResult {
+    let table = AtomTable::from_global();
+    let is_wakeup = message_term(message)
+        .to_value()
+        .is_ok_and(|value| PortTimers::is_wakeup(&value, &table));
+    if !is_wakeup {
+        return handle_standard_message::<StreamPort<S, F>>(ctx, message);
+    }
+
+    let mut timers = AtomVMTimerBackend::new(ctx);
+    let received = with_port_data_mut::<StreamPort<S, F>, _, _>(ctx, |data| {
+        let frames = data.get_inner_mut().on_wakeup(&mut timers, &table);
+        (data.get_owner_pid(), frames)
+    });
+    match received {
+        Some((Some(owner), Ok(frames))) => {
+            for frame in frames {
+                let _ = send(ctx, owner, &StreamPort::<S, F>::frame_message(frame, &table), &table);
+            }
+            PortResult::Continue
+        }
+        Some((_, Ok(_))) => PortResult::Continue,
+        Some((_, Err(_))) | None => PortResult::Terminate,
+    }
+}
+
+/// Declare a framed byte stream port driver
+///
+/// The framer type must implement `Default`; the backend is read every
+/// `poll_ms` milliseconds.
+///
+/// ```rust,ignore
+/// stream_port!(uart1, Uart1, Lines, poll_ms = 20);
+/// stream_port!(modem, Uart2, LengthPrefixed<4>, poll_ms = 10);
+/// ```
+#[macro_export]
+macro_rules! stream_port {
+    ($port_name:ident, $backend:ty, $framer:ty, poll_ms = $poll_ms:expr) => {
+        ::paste::paste! {
+            fn [<$port_name _stream_create>](
+                global: &$crate::context::GlobalContext,
+                opts: $crate::term::Term,
+            ) -> *mut $crate::context::Context {
+                $crate::port::stream::create_port::<$backend, $framer>(
+                    global,
+                    opts,
+                    <$framer as ::core::default::Default>::default(),
+                    $poll_ms,
+                )
+            }
+
+            fn [<$port_name _stream_handler>](
+                ctx: &mut $crate::context::Context,
+                message: &$crate::port::Message,
+            ) -> $crate::port::PortResult {
+                $crate::port::stream::handle_port_message::<$backend, $framer>(ctx, message)
+            }
+
+            $crate::port_collection!(
+                $port_name,
+                create_port = [<$port_name _stream_create>],
+                handler = [<$port_name _stream_handler>]
+            );
+        }
+    };
+}
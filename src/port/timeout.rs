@@ -0,0 +1,236 @@
+//! Time limits for port commands
+//!
+//! A handler stuck on a bus transaction that never completes freezes its
+//! port: no other command gets through. `CommandTimeouts` declares how long
+//! each command may take. When a command runs past its limit the caller
+//! gets `{error, timeout}` instead of the handler's reply, a warning naming
+//! the command is logged, and with `terminate_on_timeout` the port stops.
+//!
+//! The limit is checked when the handler returns, and handlers that wait in
+//! a loop can check it themselves with `CommandDeadline::check` to give up
+//! early. Commands are named as in `port::policy`: `read` for both `read`
+//! and `{read, Register}`.
+//!
+//! `PortData::command_timeouts` and `PortBehavior::call_timeouts` are the
+//! hooks used by `handle_standard_message` and `port_behavior!`; neither
+//! sets a limit by default.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::port::timeout::CommandTimeouts;
+//!
+//! struct I2cBus { timeouts: CommandTimeouts }
+//!
+//! impl PortData for I2cBus {
+//!     fn command_timeouts(&self) -> Option<&CommandTimeouts> {
+//!         Some(&self.timeouts)
+//!     }
+//! }
+//!
+//! let read = table.ensure_atom_str("read")?;
+//! let timeouts = CommandTimeouts::new().limit(read, 50).with_default(500);
+//! ```
+
+extern crate alloc;
+
+use crate::atom::{AtomIndex, AtomTableOps};
+use crate::port::policy::command_name;
+use crate::port::timer::Clock;
+use crate::port::PortResult;
+use crate::term::TermValue;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// What the port does after a command timed out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnTimeout {
+    /// Reply `{error, timeout}` and keep running
+    #[default]
+    Reply,
+    /// Reply `{error, timeout}`, then stop the port
+    Terminate,
+}
+
+/// Maximum duration per command, in milliseconds
+#[derive(Debug, Clone, Default)]
+pub struct CommandTimeouts {
+    limits: Vec<(AtomIndex, u64)>,
+    default_ms: Option<u64>,
+    on_timeout: OnTimeout,
+}
+
+impl CommandTimeouts {
+    /// No limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit `command` to `max_ms`
+    pub fn limit(mut self, command: AtomIndex, max_ms: u64) -> Self {
+        match self.limits.iter_mut().find(|(name, _)| *name == command) {
+            Some((_, limit)) => *limit = max_ms,
+            None => self.limits.push((command, max_ms)),
+        }
+        self
+    }
+
+    /// Limit every command without its own limit, unnamed ones included
+    pub fn with_default(mut self, max_ms: u64) -> Self {
+        self.default_ms = Some(max_ms);
+        self
+    }
+
+    /// Stop the port after a timeout instead of carrying on
+    pub fn terminate_on_timeout(mut self) -> Self {
+        self.on_timeout = OnTimeout::Terminate;
+        self
+    }
+
+    pub fn on_timeout(&self) -> OnTimeout {
+        self.on_timeout
+    }
+
+    /// The limit that applies to `command`, if any
+    pub fn limit_for(&self, command: &TermValue) -> Option<u64> {
+        let name = command_name(command);
+        name.and_then(|name| self.limits.iter().find(|(limit_name, _)| *limit_name == name))
+            .map(|(_, limit)| *limit)
+            .or(self.default_ms)
+    }
+
+    /// Start timing `command` now
+    pub fn start<C: Clock + ?Sized>(&self, clock: &C, command: &TermValue) -> CommandDeadline {
+        CommandDeadline {
+            command: command_name(command),
+            started_ms: clock.now_ms(),
+            limit_ms: self.limit_for(command),
+            on_timeout: self.on_timeout,
+        }
+    }
+
+    /// Run `handler` for `command` and enforce its limit on the result
+    pub fn run<C, T, F>(&self, clock: &C, command: &TermValue, table: &T, handler: F) -> PortResult
+    where
+        C: Clock + ?Sized,
+        T: AtomTableOps,
+        F: FnOnce(&CommandDeadline) -> PortResult,
+    {
+        let deadline = self.start(clock, command);
+        let result = handler(&deadline);
+        deadline.finish(clock, result, table)
+    }
+}
+
+/// The time limit of one running command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandDeadline {
+    command: Option<AtomIndex>,
+    started_ms: u64,
+    limit_ms: Option<u64>,
+    on_timeout: OnTimeout,
+}
+
+impl CommandDeadline {
+    /// The command's name, if it has one
+    pub fn command(&self) -> Option<AtomIndex> {
+        self.command
+    }
+
+    pub fn limit_ms(&self) -> Option<u64> {
+        self.limit_ms
+    }
+
+    pub fn elapsed_ms<C: Clock + ?Sized>(&self, clock: &C) -> u64 {
+        clock.now_ms().saturating_sub(self.started_ms)
+    }
+
+    /// Time left, `None` without a limit
+    pub fn remaining_ms<C: Clock + ?Sized>(&self, clock: &C) -> Option<u64> {
+        self.limit_ms.map(|limit| limit.saturating_sub(self.elapsed_ms(clock)))
+    }
+
+    pub fn is_expired<C: Clock + ?Sized>(&self, clock: &C) -> bool {
+        self.limit_ms.is_some_and(|limit| self.elapsed_ms(clock) > limit)
+    }
+
+    /// `Err` once the limit has passed; for handlers polling hardware
+    ///
+    /// ```rust,ignore
+    /// while !bus.ready() {
+    ///     if let Err(timeout) = deadline.check(&clock) {
+    ///         return timeout.into_result(&table);
+    ///     }
+    /// }
+    /// ```
+    pub fn check<C: Clock + ?Sized>(&self, clock: &C) -> Result<(), CommandTimeout> {
+        let elapsed_ms = self.elapsed_ms(clock);
+        match self.limit_ms {
+            Some(limit_ms) if elapsed_ms > limit_ms => Err(CommandTimeout {
+                command: self.command,
+                elapsed_ms,
+                limit_ms,
+                on_timeout: self.on_timeout,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// The handler's result, or the timeout result if the limit has passed
+    pub fn finish<C: Clock + ?Sized, T: AtomTableOps>(&self, clock: &C, result: PortResult, table: &T) -> PortResult {
+        match self.check(clock) {
+            Ok(()) => result,
+            // A handler that decided to stop keeps its own reason
+            Err(_) if result.is_terminate() => result,
+            Err(timeout) => timeout.into_result(table),
+        }
+    }
+}
+
+/// A command that ran past its limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandTimeout {
+    pub command: Option<AtomIndex>,
+    pub elapsed_ms: u64,
+    pub limit_ms: u64,
+    pub on_timeout: OnTimeout,
+}
+
+impl CommandTimeout {
+    /// Log the timeout and build the `{error, timeout}` result
+    pub fn into_result<T: AtomTableOps>(self, table: &T) -> PortResult {
+        #[cfg(not(test))]
+        crate::log_warn!("{}", self.describe(table));
+        let reason = TermValue::atom("timeout", table);
+        match self.on_timeout {
+            OnTimeout::Reply => PortResult::ReplyError(reason),
+            OnTimeout::Terminate => PortResult::TerminateWithReason(reason),
+        }
+    }
+
+    /// `port command read timed out after 120 ms (limit 50 ms)`
+    pub fn describe<'a, T: AtomTableOps>(&'a self, table: &'a T) -> impl fmt::Display + 'a {
+        Described { timeout: self, table }
+    }
+}
+
+struct Described<'a, T> {
+    timeout: &'a CommandTimeout,
+    table: &'a T,
+}
+
+impl<T: AtomTableOps> fmt::Display for Described<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let timeout = self.timeout;
+        let name = timeout.command.and_then(|name| self.table.get_atom_string(name).ok());
+        match name.as_ref().and_then(|name| name.as_str().ok()) {
+            Some(name) => write!(f, "port command {}", name)?,
+            None => f.write_str("port command")?,
+        }
+        write!(f, " timed out after {} ms (limit {} ms)", timeout.elapsed_ms, timeout.limit_ms)?;
+        if timeout.on_timeout == OnTimeout::Terminate {
+            f.write_str(", stopping the port")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,276 @@
+//! Timers for port drivers
+//!
+//! `PortTimers` keeps a port's one-shot and periodic timers. A single
+//! platform wakeup is armed for the earliest deadline; when it fires the
+//! port receives the `timer_wakeup` atom, and `PortTimers::dispatch` turns
+//! every expired timer into a `{timeout, Ref}` message for the handler.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::port::timer::{PortTimers, AtomVMTimerBackend};
+//!
+//! // In the port data:
+//! let poll = data.timers.start_periodic(&mut AtomVMTimerBackend::new(ctx), 500);
+//!
+//! // In the handler:
+//! if PortTimers::is_wakeup(&command, &table) {
+//!     for msg in data.timers.dispatch(&mut AtomVMTimerBackend::new(ctx), &table)? {
+//!         // msg is {timeout, Ref}
+//!     }
+//! }
+//! ```
+
+extern crate alloc;
+
+use crate::atom::{AtomError, AtomTableOps};
+use crate::context::Context;
+use crate::term::{RefId, TermValue};
+use alloc::{vec, vec::Vec};
+use core::ffi::c_int;
+
+/// Handle identifying one timer of a port
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimerRef(pub u32);
+
+impl TimerRef {
+    /// Reference term carried in the `{timeout, Ref}` message
+    pub fn to_term(self) -> TermValue {
+        TermValue::Reference(RefId(self.0 as u64))
+    }
+}
+
+/// A monotonic millisecond clock
+pub trait Clock {
+    fn now_ms(&self) -> u64;
+}
+
+/// Platform services needed by the timers: a clock and one wakeup per port
+///
+/// Implementations only need to remember the latest wakeup; arming again
+/// replaces the previous one.
+pub trait TimerBackend {
+    /// Monotonic time in milliseconds
+    fn now_ms(&self) -> u64;
+
+    /// Deliver the `timer_wakeup` atom to the port after `delay_ms`
+    fn arm(&mut self, delay_ms: u64);
+
+    /// Cancel any pending wakeup
+    fn disarm(&mut self);
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TimerEntry {
+    timer: TimerRef,
+    deadline: u64,
+    interval: Option<u64>,
+}
+
+/// One-shot and periodic timers owned by a port
+#[derive(Debug)]
+pub struct PortTimers {
+    entries: Vec<TimerEntry>,
+    next_id: u32,
+}
+
+impl Default for PortTimers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PortTimers {
+    /// Create an empty timer set
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Start a timer that fires once after `delay_ms`
+    pub fn start_once<B: TimerBackend>(&mut self, backend: &mut B, delay_ms: u64) -> TimerRef {
+        self.start(backend, delay_ms, None)
+    }
+
+    /// Start a timer that fires every `interval_ms`
+    ///
+    /// An interval of zero is treated as one millisecond.
+    pub fn start_periodic<B: TimerBackend>(&mut self, backend: &mut B, interval_ms: u64) -> TimerRef {
+        let interval = interval_ms.max(1);
+        self.start(backend, interval, Some(interval))
+    }
+
+    /// Cancel a timer; returns false if it already fired or never existed
+    pub fn cancel<B: TimerBackend>(&mut self, backend: &mut B, timer: TimerRef) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.timer != timer);
+        let removed = self.entries.len() != before;
+        if removed {
+            self.rearm(backend);
+        }
+        removed
+    }
+
+    /// Cancel every timer (e.g. when the port stops)
+    pub fn cancel_all<B: TimerBackend>(&mut self, backend: &mut B) {
+        self.entries.clear();
+        backend.disarm();
+    }
+
+    /// Check whether a timer is still pending
+    pub fn is_active(&self, timer: TimerRef) -> bool {
+        self.entries.iter().any(|entry| entry.timer == timer)
+    }
+
+    /// Number of pending timers
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if no timers are pending
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Earliest pending deadline
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.entries.iter().map(|entry| entry.deadline).min()
+    }
+
+    /// Remove or reschedule every timer due at `now`, returning them in deadline order
+    ///
+    /// A periodic timer that missed several periods fires once and is
+    /// rescheduled to its next future period.
+    pub fn expire(&mut self, now: u64) -> Vec<TimerRef> {
+        let mut due: Vec<(u64, TimerRef)> = Vec::new();
+
+        self.entries.retain_mut(|entry| {
+            if entry.deadline > now {
+                return true;
+            }
+            due.push((entry.deadline, entry.timer));
+            match entry.interval {
+                Some(interval) => {
+                    let missed = (now - entry.deadline) / interval;
+                    entry.deadline += (missed + 1) * interval;
+                    true
+                }
+                None => false,
+            }
+        });
+
+        due.sort();
+        due.into_iter().map(|(_, timer)| timer).collect()
+    }
+
+    /// Handle a wakeup: expire due timers, re-arm, and build their messages
+    pub fn dispatch<B: TimerBackend, T: AtomTableOps>(
+        &mut self,
+        backend: &mut B,
+        table: &T,
+    ) -> Result<Vec<TermValue>, AtomError> {
+        let expired = self.expire(backend.now_ms());
+        self.rearm(backend);
+        expired
+            .into_iter()
+            .map(|timer| Self::timeout_message(timer, table))
+            .collect()
+    }
+
+    /// Build the `{timeout, Ref}` message for a timer
+    pub fn timeout_message<T: AtomTableOps>(timer: TimerRef, table: &T) -> Result<TermValue, AtomError> {
+        let timeout = table.ensure_atom_str("timeout")?;
+        Ok(TermValue::Tuple(vec![TermValue::Atom(timeout), timer.to_term()]))
+    }
+
+    /// Recognize a `{timeout, Ref}` message, returning its timer
+    pub fn match_timeout<T: AtomTableOps>(message: &TermValue, table: &T) -> Option<TimerRef> {
+        match message.as_tuple() {
+            Some([tag, TermValue::Reference(RefId(id))]) if tag.is_atom_str("timeout", table) => {
+                u32::try_from(*id).ok().map(TimerRef)
+            }
+            _ => None,
+        }
+    }
+
+    /// Recognize the platform wakeup message
+    pub fn is_wakeup<T: AtomTableOps>(message: &TermValue, table: &T) -> bool {
+        message.is_atom_str("timer_wakeup", table)
+    }
+
+    fn start<B: TimerBackend>(&mut self, backend: &mut B, delay_ms: u64, interval: Option<u64>) -> TimerRef {
+        let timer = TimerRef(self.next_id);
+        self.next_id = self.next_id.wrapping_add(1).max(1);
+        self.entries.push(TimerEntry {
+            timer,
+            deadline: backend.now_ms().saturating_add(delay_ms),
+            interval,
+        });
+        self.rearm(backend);
+        timer
+    }
+
+    fn rearm<B: TimerBackend>(&self, backend: &mut B) {
+        match self.next_deadline() {
+            Some(deadline) => backend.arm(deadline.saturating_sub(backend.now_ms())),
+            None => backend.disarm(),
+        }
+    }
+}
+
+// ── AtomVM Backend ──────────────────────────────────────────────────────────
+
+// AtomVM port timer FFI declarations
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    /// Monotonic milliseconds since boot
+    fn port_timer_now_ms() -> u64;
+
+    /// Send the `timer_wakeup` atom to the port after `delay_ms`, replacing any pending wakeup
+    fn port_timer_arm(ctx: *mut Context, delay_ms: u32) -> c_int;
+
+    /// Cancel the pending wakeup of a port
+    fn port_timer_disarm(ctx: *mut Context);
+}
+
+/// The platform's monotonic clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AtomVMClock;
+
+impl Clock for AtomVMClock {
+    fn now_ms(&self) -> u64 {
+        unsafe { port_timer_now_ms() }
+    }
+}
+
+/// Timer backend using the platform timer attached to a port context
+pub struct AtomVMTimerBackend {
+    ctx: *mut Context,
+}
+
+impl AtomVMTimerBackend {
+    /// Create a backend for the given port
+    pub fn new(ctx: &mut Context) -> Self {
+        Self { ctx: ctx as *mut Context }
+    }
+}
+
+impl TimerBackend for AtomVMTimerBackend {
+    fn now_ms(&self) -> u64 {
+        unsafe { port_timer_now_ms() }
+    }
+
+    fn arm(&mut self, delay_ms: u64) {
+        // Longer delays simply wake early and re-arm
+        let delay = u32::try_from(delay_ms).unwrap_or(u32::MAX);
+        unsafe {
+            port_timer_arm(self.ctx, delay);
+        }
+    }
+
+    fn disarm(&mut self) {
+        unsafe { port_timer_disarm(self.ctx) }
+    }
+}
@@ -0,0 +1,180 @@
+//! Request tracing for port drivers
+//!
+//! When a tracer pid is set, `PortTrace` follows each request by its
+//! reference and sends `{trace, Ref, Stage, Time}` to the tracer at every
+//! stage the driver marks: when the request arrives, when a timer started
+//! for it fires, and when the reply goes out. Log lines written through the
+//! trace carry the same reference, so latency in a multi-stage pipeline can
+//! be read off one process's mailbox. With no tracer set, nothing is sent
+//! or recorded.
+//!
+//! `Time` is the backend clock in milliseconds, wrapped to 31 bits; compare
+//! the times of one request rather than reading them as absolute.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::port::trace::{AtomVMTraceBackend, PortTrace};
+//!
+//! // {trace_to, Pid}
+//! data.trace.set_tracer(Some(pid));
+//!
+//! // {'$call', {Pid, Ref}, {read, Channel}}
+//! let mut tb = AtomVMTraceBackend::new();
+//! data.trace.stage(&mut tb, &from.reference, "received", &table);
+//! let timer = data.timers.start_once(&mut timers, 5);
+//! data.trace.link_timer(timer, &from.reference);
+//!
+//! // {timeout, Timer}
+//! if let Some(reference) = data.trace.timer_fired(&mut tb, timer, &table) {
+//!     data.trace.log(&mut tb, &reference, "conversion done");
+//!     data.trace.reply(&mut tb, &reference, &table);
+//! }
+//! ```
+
+extern crate alloc;
+
+use crate::atom::{AtomTable, AtomTableOps};
+use crate::port::timer::TimerRef;
+use crate::term::TermValue;
+use alloc::vec::Vec;
+
+/// Platform services for tracing: a clock, the tracer mailbox and the log
+pub trait TraceBackend {
+    /// Monotonic time in milliseconds
+    fn now_ms(&self) -> u64;
+
+    /// Send a trace event to the tracer
+    fn emit(&mut self, tracer: u32, event: &TermValue);
+
+    /// Write a log line
+    fn log(&mut self, line: &str);
+}
+
+/// Trace state of one port
+#[derive(Debug, Default)]
+pub struct PortTrace {
+    tracer: Option<u32>,
+    timers: Vec<(TimerRef, TermValue)>,
+}
+
+impl PortTrace {
+    /// Tracing starts switched off
+    pub const fn new() -> Self {
+        Self {
+            tracer: None,
+            timers: Vec::new(),
+        }
+    }
+
+    /// Send events to `tracer`, or stop tracing with `None`
+    pub fn set_tracer(&mut self, tracer: Option<u32>) {
+        self.tracer = tracer;
+        if tracer.is_none() {
+            self.timers.clear();
+        }
+    }
+
+    pub fn tracer(&self) -> Option<u32> {
+        self.tracer
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.tracer.is_some()
+    }
+
+    /// Report that the request `reference` reached `stage`
+    pub fn stage<B: TraceBackend, T: AtomTableOps>(
+        &self,
+        backend: &mut B,
+        reference: &TermValue,
+        stage: &str,
+        table: &T,
+    ) {
+        if let Some(tracer) = self.tracer {
+            let time = (backend.now_ms() & 0x7FFF_FFFF) as i32;
+            let event = TermValue::tuple(alloc::vec![
+                TermValue::atom("trace", table),
+                reference.clone(),
+                TermValue::atom(stage, table),
+                TermValue::int(time),
+            ]);
+            backend.emit(tracer, &event);
+        }
+    }
+
+    /// Remember that `timer` was started on behalf of `reference`
+    pub fn link_timer(&mut self, timer: TimerRef, reference: &TermValue) {
+        if self.is_enabled() {
+            self.timers.retain(|(t, _)| *t != timer);
+            self.timers.push((timer, reference.clone()));
+        }
+    }
+
+    /// Report a `timer` stage for the request the timer was started for
+    ///
+    /// Returns that request's reference. The link stays until the reply,
+    /// so a periodic timer is reported each time it fires.
+    pub fn timer_fired<B: TraceBackend, T: AtomTableOps>(
+        &self,
+        backend: &mut B,
+        timer: TimerRef,
+        table: &T,
+    ) -> Option<TermValue> {
+        let (_, reference) = self.timers.iter().find(|(t, _)| *t == timer)?;
+        self.stage(backend, reference, "timer", table);
+        Some(reference.clone())
+    }
+
+    /// Write a log line, tagged with `reference` while tracing
+    pub fn log<B: TraceBackend>(&self, backend: &mut B, reference: &TermValue, message: &str) {
+        if self.is_enabled() {
+            backend.log(&alloc::format!("[{:?}] {}", reference, message));
+        } else {
+            backend.log(message);
+        }
+    }
+
+    /// Report the `reply` stage and forget the request's timers
+    pub fn reply<B: TraceBackend, T: AtomTableOps>(
+        &mut self,
+        backend: &mut B,
+        reference: &TermValue,
+        table: &T,
+    ) {
+        self.stage(backend, reference, "reply", table);
+        self.timers.retain(|(_, r)| r != reference);
+    }
+}
+
+// Trace FFI declarations
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    /// Monotonic milliseconds since boot
+    fn port_timer_now_ms() -> u64;
+}
+
+/// Trace backend sending events as ordinary messages and logging to the console
+#[derive(Debug, Default)]
+pub struct AtomVMTraceBackend;
+
+impl AtomVMTraceBackend {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl TraceBackend for AtomVMTraceBackend {
+    fn now_ms(&self) -> u64 {
+        unsafe { port_timer_now_ms() }
+    }
+
+    fn emit(&mut self, tracer: u32, event: &TermValue) {
+        // A dead tracer only loses the event
+        let _ = crate::port::send_from_task(tracer, event, &AtomTable::from_global());
+    }
+
+    fn log(&mut self, line: &str) {
+        crate::log::log_info(line);
+    }
+}
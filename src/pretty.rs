@@ -0,0 +1,219 @@
+//! Terms printed in Erlang syntax
+//!
+//! `TermValue::display(&table)` renders a term the way `io:format("~p")`
+//! writes it on one line, so log lines and test failures read like the
+//! shell: `{ok,[1,2,3],#{a => 1}}`. Atom names come from the table and are
+//! quoted when Erlang would need quotes; lists and binaries of printable
+//! characters are shown as strings.
+//!
+//! Pids, ports and references are node-local, so they print in the
+//! shell's `<0.N.0>`, `#Port<0.N>` and `#Ref<0.0.0.N>` forms, which Erlang
+//! cannot read back.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! # use avmnif_rs::{log_info, parse::parse_term};
+//! # let global = avmnif_rs::testing::mocks::MockGlobalContext::new();
+//! # let table = avmnif_rs::testing::mocks::MockAtomTable::new();
+//! # let state = parse_term("{ok, [1, 2, 3], #{a => 1}}", &table).unwrap();
+//! log_info!("state: {}", state.display(&table));
+//! # assert!(global.log_lines()[0].ends_with("state: {ok,[1,2,3],#{a => 1}}"));
+//! # }
+//! ```
+
+extern crate alloc;
+
+use crate::atom::{AtomIndex, AtomTableOps};
+use crate::term::TermValue;
+use alloc::format;
+use core::fmt::{self, Write};
+
+/// Words that must be quoted to be read as atoms
+const RESERVED: &[&str] = &[
+    "after", "and", "andalso", "band", "begin", "bnot", "bor", "bsl", "bsr", "bxor", "case", "catch", "cond", "div",
+    "else", "end", "fun", "if", "let", "maybe", "not", "of", "or", "orelse", "receive", "rem", "try", "when", "xor",
+];
+
+/// A term paired with the atom table to print it with
+pub struct Pretty<'a, T> {
+    value: &'a TermValue,
+    table: &'a T,
+}
+
+impl TermValue {
+    /// Display the term in Erlang syntax, resolving atoms through `table`
+    pub fn display<'a, T: AtomTableOps>(&'a self, table: &'a T) -> Pretty<'a, T> {
+        Pretty { value: self, table }
+    }
+}
+
+impl<T: AtomTableOps> fmt::Display for Pretty<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_term(f, self.value, self.table)
+    }
+}
+
+impl<T: AtomTableOps> fmt::Debug for Pretty<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+fn write_term<T: AtomTableOps>(f: &mut fmt::Formatter<'_>, value: &TermValue, table: &T) -> fmt::Result {
+    match value {
+        TermValue::SmallInt(value) => write!(f, "{}", value),
+        TermValue::Integer(value) => write!(f, "{}", value),
+        TermValue::Float(value) => write_float(f, *value),
+        TermValue::Atom(index) => write_atom(f, *index, table),
+        TermValue::Nil => f.write_str("[]"),
+        TermValue::Pid(pid) => write!(f, "<0.{}.0>", pid.0),
+        TermValue::Port(port) => write!(f, "#Port<0.{}>", port.0),
+        TermValue::Reference(reference) => write!(f, "#Ref<0.0.0.{}>", reference.0),
+        TermValue::Resource(resource) => write!(f, "#Resource<{}>", resource.type_name),
+        TermValue::Function(fun) => {
+            f.write_str("fun ")?;
+            write_atom(f, fun.module, table)?;
+            f.write_char(':')?;
+            write_atom(f, fun.function, table)?;
+            write!(f, "/{}", fun.arity)
+        }
+        TermValue::Tuple(elements) => {
+            f.write_char('{')?;
+            write_sequence(f, elements, table)?;
+            f.write_char('}')
+        }
+        TermValue::Map(pairs) => {
+            f.write_str("#{")?;
+            for (i, (key, value)) in pairs.iter().enumerate() {
+                if i > 0 {
+                    f.write_char(',')?;
+                }
+                write_term(f, key, table)?;
+                f.write_str(" => ")?;
+                write_term(f, value, table)?;
+            }
+            f.write_char('}')
+        }
+        TermValue::List(_, _) => write_list(f, value, table),
+        TermValue::Binary(bytes) => {
+            if !bytes.is_empty() && bytes.iter().all(|&byte| printable(byte as u32)) {
+                f.write_str("<<")?;
+                write_string(f, bytes.iter().map(|&byte| byte as u32))?;
+                f.write_str(">>")
+            } else {
+                f.write_str("<<")?;
+                for (i, byte) in bytes.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", byte)?;
+                }
+                f.write_str(">>")
+            }
+        }
+        TermValue::Invalid(error) => write!(f, "#Invalid<{:#x}>", error.raw),
+    }
+}
+
+fn write_sequence<T: AtomTableOps>(f: &mut fmt::Formatter<'_>, elements: &[TermValue], table: &T) -> fmt::Result {
+    for (i, element) in elements.iter().enumerate() {
+        if i > 0 {
+            f.write_char(',')?;
+        }
+        write_term(f, element, table)?;
+    }
+    Ok(())
+}
+
+/// Proper lists of printable characters as strings, others element by element
+fn write_list<T: AtomTableOps>(f: &mut fmt::Formatter<'_>, list: &TermValue, table: &T) -> fmt::Result {
+    let mut elements = list.iter_list();
+    let is_string = elements
+        .by_ref()
+        .all(|element| matches!(element, TermValue::SmallInt(c) if *c >= 0 && printable(*c as u32)));
+    if is_string && matches!(elements.tail(), TermValue::Nil) {
+        let chars = list.iter_list().map(|element| match element {
+            TermValue::SmallInt(c) => *c as u32,
+            _ => 0,
+        });
+        return write_string(f, chars);
+    }
+
+    f.write_char('[')?;
+    let mut elements = list.iter_list();
+    for (i, element) in elements.by_ref().enumerate() {
+        if i > 0 {
+            f.write_char(',')?;
+        }
+        write_term(f, element, table)?;
+    }
+    if !matches!(elements.tail(), TermValue::Nil) {
+        f.write_char('|')?;
+        write_term(f, elements.tail(), table)?;
+    }
+    f.write_char(']')
+}
+
+/// Latin-1 text and the usual control characters, as `io_lib:printable_list/1`
+fn printable(c: u32) -> bool {
+    matches!(c, 32..=126 | 160..=255 | 8..=13 | 27)
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, chars: impl Iterator<Item = u32>) -> fmt::Result {
+    f.write_char('"')?;
+    for c in chars {
+        write_char_escaped(f, c, '"')?;
+    }
+    f.write_char('"')
+}
+
+fn write_char_escaped(f: &mut fmt::Formatter<'_>, c: u32, quote: char) -> fmt::Result {
+    match c {
+        8 => f.write_str("\\b"),
+        9 => f.write_str("\\t"),
+        10 => f.write_str("\\n"),
+        11 => f.write_str("\\v"),
+        12 => f.write_str("\\f"),
+        13 => f.write_str("\\r"),
+        27 => f.write_str("\\e"),
+        0x5C => f.write_str("\\\\"),
+        c if c == quote as u32 => write!(f, "\\{}", quote),
+        c if c < 32 || c == 127 => write!(f, "\\{:o}", c),
+        c => f.write_char(char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER)),
+    }
+}
+
+fn write_atom<T: AtomTableOps>(f: &mut fmt::Formatter<'_>, index: AtomIndex, table: &T) -> fmt::Result {
+    let Ok(atom) = table.get_atom_string(index) else {
+        return write!(f, "#Atom<{}>", index.0);
+    };
+    let Ok(name) = atom.as_str() else {
+        return write!(f, "#Atom<{}>", index.0);
+    };
+    if is_bare_atom(name) {
+        return f.write_str(name);
+    }
+    f.write_char('\'')?;
+    for c in name.chars() {
+        write_char_escaped(f, c as u32, '\'')?;
+    }
+    f.write_char('\'')
+}
+
+fn is_bare_atom(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@')
+        && !RESERVED.contains(&name)
+}
+
+/// Shortest round-trip form, with the `.0` Erlang needs before an exponent
+fn write_float(f: &mut fmt::Formatter<'_>, value: f64) -> fmt::Result {
+    let text = format!("{:?}", value);
+    match text.find('e') {
+        Some(e) if !text[..e].contains('.') => write!(f, "{}.0{}", &text[..e], &text[e..]),
+        _ => f.write_str(&text),
+    }
+}
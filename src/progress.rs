@@ -0,0 +1,174 @@
+//! Progress reports for long-running operations
+//!
+//! OTA flashing, file transfers and sensor calibration take seconds to
+//! minutes. A `ProgressReporter` lets the task doing the work tell the
+//! caller how far it got by sending `{progress, Ref, Value}`, where `Ref`
+//! is the reference the caller passed in and `Value` is a percentage or any
+//! term. Updates are throttled so a tight loop does not flood the mailbox:
+//! one goes out only after `interval_ms` has passed and the percentage
+//! moved by at least `min_step`. The first update and 100% always go out,
+//! and the same percentage is never sent twice.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::progress::{AtomVMProgressSink, ProgressReporter};
+//!
+//! // {flash, Ref, Image} from the caller
+//! let mut sink = AtomVMProgressSink::new();
+//! let mut progress = ProgressReporter::new(caller, reference).with_interval(500);
+//! for (written, chunk) in image.chunks(4096).enumerate() {
+//!     flash.write(chunk)?;
+//!     progress.update_percent(&mut sink, written * 100 / total, &table)?;
+//! }
+//! progress.finish(&mut sink, &table)?;
+//! ```
+//!
+//! ```erlang
+//! receive {progress, Ref, Percent} -> show(Percent) end.
+//! ```
+
+extern crate alloc;
+
+use crate::atom::{AtomTable, AtomTableOps};
+use crate::term::{NifError, TermValue};
+
+/// Default minimum time between two updates
+pub const DEFAULT_INTERVAL_MS: u32 = 250;
+
+/// Platform services for progress reports: a clock and the caller's mailbox
+pub trait ProgressSink {
+    /// Monotonic time in milliseconds
+    fn now_ms(&self) -> u64;
+
+    /// Send a progress message
+    fn send(&mut self, pid: u32, message: &TermValue) -> Result<(), NifError>;
+}
+
+/// Throttled `{progress, Ref, Value}` sender for one operation
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    pid: u32,
+    reference: TermValue,
+    interval_ms: u32,
+    min_step: u8,
+    last_sent_at: Option<u64>,
+    last_percent: Option<u8>,
+}
+
+impl ProgressReporter {
+    /// Report to `pid`, tagging every message with `reference`
+    pub fn new(pid: u32, reference: TermValue) -> Self {
+        Self {
+            pid,
+            reference,
+            interval_ms: DEFAULT_INTERVAL_MS,
+            min_step: 1,
+            last_sent_at: None,
+            last_percent: None,
+        }
+    }
+
+    /// Send at most one update per `interval_ms`
+    pub fn with_interval(mut self, interval_ms: u32) -> Self {
+        self.interval_ms = interval_ms;
+        self
+    }
+
+    /// Skip percentage updates smaller than `min_step` points
+    pub fn with_min_step(mut self, min_step: u8) -> Self {
+        self.min_step = min_step.max(1);
+        self
+    }
+
+    /// The last percentage sent
+    pub fn last_percent(&self) -> Option<u8> {
+        self.last_percent
+    }
+
+    /// Report a percentage, clamped to 100; returns whether it was sent
+    pub fn update_percent<S: ProgressSink, T: AtomTableOps>(
+        &mut self,
+        sink: &mut S,
+        percent: usize,
+        table: &T,
+    ) -> Result<bool, NifError> {
+        let percent = percent.min(100) as u8;
+        let due = match self.last_percent {
+            None => true,
+            Some(last) if percent == last => false,
+            Some(_) if percent == 100 => true,
+            Some(last) => percent.abs_diff(last) >= self.min_step && self.interval_elapsed(sink),
+        };
+        if !due {
+            return Ok(false);
+        }
+        self.send(sink, TermValue::int(percent as i32), table)?;
+        self.last_percent = Some(percent);
+        Ok(true)
+    }
+
+    /// Report any term, such as `{Written, Total}`; throttled by time only
+    pub fn update<S: ProgressSink, T: AtomTableOps>(
+        &mut self,
+        sink: &mut S,
+        value: TermValue,
+        table: &T,
+    ) -> Result<bool, NifError> {
+        if !self.interval_elapsed(sink) {
+            return Ok(false);
+        }
+        self.send(sink, value, table)?;
+        Ok(true)
+    }
+
+    /// Report 100% unless it was already sent
+    pub fn finish<S: ProgressSink, T: AtomTableOps>(&mut self, sink: &mut S, table: &T) -> Result<(), NifError> {
+        self.update_percent(sink, 100, table).map(|_| ())
+    }
+
+    fn interval_elapsed<S: ProgressSink>(&self, sink: &S) -> bool {
+        match self.last_sent_at {
+            None => true,
+            Some(at) => sink.now_ms().saturating_sub(at) >= u64::from(self.interval_ms),
+        }
+    }
+
+    fn send<S: ProgressSink, T: AtomTableOps>(&mut self, sink: &mut S, value: TermValue, table: &T) -> Result<(), NifError> {
+        let message = TermValue::tuple(alloc::vec![
+            TermValue::atom("progress", table),
+            self.reference.clone(),
+            value,
+        ]);
+        sink.send(self.pid, &message)?;
+        self.last_sent_at = Some(sink.now_ms());
+        Ok(())
+    }
+}
+
+// Progress FFI declarations
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    /// Monotonic milliseconds since boot
+    fn port_timer_now_ms() -> u64;
+}
+
+/// Progress sink sending from a task with `port::send_from_task`
+#[derive(Debug, Default)]
+pub struct AtomVMProgressSink;
+
+impl AtomVMProgressSink {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ProgressSink for AtomVMProgressSink {
+    fn now_ms(&self) -> u64 {
+        unsafe { port_timer_now_ms() }
+    }
+
+    fn send(&mut self, pid: u32, message: &TermValue) -> Result<(), NifError> {
+        crate::port::send_from_task(pid, message, &AtomTable::from_global())
+    }
+}
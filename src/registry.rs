@@ -1,15 +1,552 @@
+//! NIF collection registration
+//!
+//! `nif_collection!` exports a collection's init and resolver functions and
+//! registers them with AtomVM. Its name/arity/function table is also a
+//! plain `const` (`<MONIKER>_NIFS`), so `static` items and other macros can
+//! read the exported NIFs without going through the resolver. The
+//! resolver binary-searches `<MONIKER>_NIF_INDEX`, the table's positions
+//! sorted by name at compile time. A name may be listed with several
+//! arities; a request for `module:function/arity` (what AtomVM asks for)
+//! or `function/arity` then reaches the entry with that arity. With
+//! `module = "name"` the resolver only answers for that Erlang module, so
+//! a collection does not pick up NIFs AtomVM looks up for other modules.
+//!
+//! A collection also has a `ReplyStyle` (`<MONIKER>_REPLY_STYLE`, tagged
+//! tuples unless `reply_style = ...` says otherwise). NIFs listed under
+//! `replies = [...]` return a `ReplyResult` and get a generated wrapper
+//! that applies it; see `crate::reply`. NIFs listed under `typed = [...]`
+//! are plain Rust functions whose arity and argument decoding come from
+//! their signature; see `crate::nif`. Signatures are checked when the
+//! collection is compiled: `nifs` entries must be `NifFunction`s, and a
+//! `typed` entry that states an arity must agree with its function.
+//! A `typed` entry ending in `error_tuple` returns `{error, Reason}` for
+//! the `NifError`s of its function instead of raising them.
+//!
+//! An entry may end with a scheduling flag, `dirty_cpu` or `dirty_io`, for
+//! NIFs that run long enough to hold up the scheduler. The registration
+//! record, `<MONIKER>_NIF_COLLECTION`, holds AtomVM's four arguments (name,
+//! init, destroy, resolver) and the flags resolver `<moniker>_get_nif_flags`.
+//! AtomVM's `REGISTER_NIF_COLLECTION` has no slot for the flags, so they
+//! reach the host through the component stub of `avmnif-build`, which keeps
+//! each NIF's flags next to its `struct Nif` for the platform's scheduler.
+//!
+//! Besides `init`, a collection may name an `upgrade` callback (run when
+//! the registered init is called again after it completed, which is how a
+//! host signals a reload) and an `unload` callback (run by the collection's
+//! destroy function). `priv_data = T`
+//! adds `<MONIKER>_PRIV`, a `PrivData<T>` handle to state kept on the
+//! global context, which outlives module reloads; it is cleared after
+//! `unload`.
+//!
+//! Every generated init function (`<moniker>_nif_init`, `init_<resource>`,
+//! a port's init) runs once. A repeated `<moniker>_nif_init` runs the
+//! collection's `upgrade` callback instead; any other repeated call, or one
+//! without an `upgrade`, is logged and otherwise ignored. The destroy
+//! function re-arms the collection's init.
+//!
+//! `info = VERSION` adds an `__info__/0` NIF that reports the collection's
+//! name, its NIFs and `VERSION` (usually `env!("CARGO_PKG_VERSION")`), so
+//! Erlang code can discover what a loaded native collection provides.
+
+extern crate alloc;
+
+use crate::atom::AtomTableOps;
+use crate::sync::SpinLock;
+use crate::term::{Context, Term, TermValue};
+use crate::context::GlobalContext;
+use alloc::boxed::Box;
+use avmnif_sys::{global_context_get_nif_priv, global_context_set_nif_priv};
+use core::ffi::{c_char, c_void};
+use core::marker::PhantomData;
+use crate::sync::atomic::{AtomicU8, Ordering};
+
+/// Makes a generated init function run once
+///
+/// `begin` claims the initialization; a second caller, or one arriving
+/// while the first is still running, is turned away. `finish(false)`
+/// releases the claim so a failed init can be retried.
+#[derive(Debug, Default)]
+pub struct InitGuard {
+    state: AtomicU8,
+}
+
+impl InitGuard {
+    const UNINIT: u8 = 0;
+    const RUNNING: u8 = 1;
+    const DONE: u8 = 2;
+
+    pub const fn new() -> Self {
+        Self { state: AtomicU8::new(Self::UNINIT) }
+    }
+
+    /// Claim the initialization; `false` if it ran already or is running
+    pub fn begin(&self) -> bool {
+        self.state
+            .compare_exchange(Self::UNINIT, Self::RUNNING, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// End a claimed initialization; a failed one may be claimed again
+    pub fn finish(&self, succeeded: bool) {
+        let state = if succeeded { Self::DONE } else { Self::UNINIT };
+        self.state.store(state, Ordering::Release);
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.state.load(Ordering::Acquire) == Self::DONE
+    }
+
+    /// Allow initializing again, once the matching destroy has run
+    pub fn reset(&self) {
+        self.state.store(Self::UNINIT, Ordering::Release);
+    }
+}
+
+/// Report an init call turned away by its `InitGuard`
+#[doc(hidden)]
+pub fn repeated_init(entry_point: &str) {
+    // Tests never link AtomVM's log function
+    #[cfg(not(test))]
+    crate::log_warn!("{}: already initialized, call ignored", entry_point);
+    let _ = entry_point;
+}
+
+/// State of a NIF collection, kept across reloads of its Erlang module
+///
+/// `nif_collection!` with `priv_data = T` declares one as `<MONIKER>_PRIV`.
+/// The value is boxed and kept on the global context under the
+/// collection's name, so it belongs to the VM rather than to the Erlang
+/// module. It is empty until set, usually by `init`. `context_global(ctx)`
+/// gives the global context from a NIF's or callback's context.
+pub struct PrivData<T> {
+    /// Collection name, NUL-terminated, the value is kept under
+    key: &'static str,
+    lock: SpinLock<()>,
+    value: PhantomData<T>,
+}
+
+// The value is only reached through the global context, under the lock
+unsafe impl<T: Send> Sync for PrivData<T> {}
+
+impl<T: Send> PrivData<T> {
+    /// Private data kept under `key`, which must end with a NUL byte
+    pub const fn new(key: &'static str) -> Self {
+        let bytes = key.as_bytes();
+        assert!(!bytes.is_empty() && bytes[bytes.len() - 1] == 0, "PrivData key must be NUL-terminated");
+        Self { key, lock: SpinLock::new(()), value: PhantomData }
+    }
+
+    fn get(&self, global: *mut GlobalContext) -> *mut T {
+        unsafe { global_context_get_nif_priv(global, self.key.as_ptr() as *const c_char) as *mut T }
+    }
+
+    fn replace(&self, global: *mut GlobalContext, value: *mut T) -> Option<T> {
+        let previous = self.get(global);
+        unsafe {
+            global_context_set_nif_priv(global, self.key.as_ptr() as *const c_char, value as *mut c_void);
+            (!previous.is_null()).then(|| *Box::from_raw(previous))
+        }
+    }
+
+    /// Store `value` on `global`, returning the previous one
+    pub fn set(&self, global: *mut GlobalContext, value: T) -> Option<T> {
+        crate::contracts::non_null(global, "PrivData::set called with null global context");
+        self.lock.with(|_| self.replace(global, Box::into_raw(Box::new(value))))
+    }
+
+    /// Remove and return the value
+    pub fn take(&self, global: *mut GlobalContext) -> Option<T> {
+        crate::contracts::non_null(global, "PrivData::take called with null global context");
+        self.lock.with(|_| self.replace(global, core::ptr::null_mut()))
+    }
+
+    /// Run `f` on the value; `None` if it is not set
+    ///
+    /// `f` runs under a spin lock, so keep it short and do not touch the
+    /// same private data from inside it.
+    pub fn with<R>(&self, global: *mut GlobalContext, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        crate::contracts::non_null(global, "PrivData::with called with null global context");
+        self.lock.with(|_| unsafe { self.get(global).as_mut().map(f) })
+    }
+
+    pub fn is_set(&self, global: *mut GlobalContext) -> bool {
+        crate::contracts::non_null(global, "PrivData::is_set called with null global context");
+        !self.get(global).is_null()
+    }
+}
+
+/// Scheduling hint for a NIF, with the values of `ERL_NIF_DIRTY_JOB_*`
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NifFlags {
+    /// Runs on the normal scheduler; keep it short
+    #[default]
+    Normal = 0,
+    /// Long computation, such as compression or crypto
+    DirtyCpu = 1,
+    /// Long blocking I/O, such as flash writes
+    DirtyIo = 2,
+}
+
+/// One exported NIF of a collection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NifEntry {
+    pub name: &'static str,
+    pub arity: u32,
+    /// The `extern "C"` NIF function, as handed to AtomVM
+    pub function: *const c_void,
+    pub flags: NifFlags,
+}
+
+// Entries only hold `'static` strings and function addresses
+unsafe impl Sync for NifEntry {}
+
+impl NifEntry {
+    pub const fn new(name: &'static str, arity: u32, function: *const c_void) -> Self {
+        Self { name, arity, function, flags: NifFlags::Normal }
+    }
+
+    pub const fn with_flags(mut self, flags: NifFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+}
+
+/// Signature of the functions under `nifs = [...]`
+///
+/// `nif_collection!` checks every entry against it, so a function that
+/// does not take the context, `argc` and `argv` fails to compile instead
+/// of being called with the wrong arguments:
+///
+/// ```compile_fail,E0308
+/// use avmnif_rs::term::{Context, Term};
+///
+/// extern "C" fn add_nif(_ctx: *mut Context, _argv: *const Term) -> Term<'static> {
+///     Term::from_raw(0x3B)
+/// }
+///
+/// fn math_init(_ctx: &mut avmnif_rs::Context) {}
+///
+/// avmnif_rs::nif_collection!(math, init = math_init, nifs = [("add", 2, add_nif)]);
+/// ```
+///
+/// `typed` entries may state their arity as well; it must match the
+/// function's signature:
+///
+/// ```compile_fail,E0080
+/// use avmnif_rs::term::{Env, TermValue};
+///
+/// fn add(_env: &mut Env, a: i32, b: i32) -> TermValue {
+///     TermValue::int(a + b)
+/// }
+///
+/// fn math_init(_ctx: &mut avmnif_rs::Context) {}
+///
+/// avmnif_rs::nif_collection!(math, init = math_init, typed = [("add", 3, add)], nifs = []);
+/// ```
+pub type NifFunction = unsafe extern "C" fn(*mut Context, i32, *const Term<'static>) -> Term<'static>;
+
+/// Registration record of a NIF collection
+///
+/// `nif_collection!` declares one as `<MONIKER>_NIF_COLLECTION`, and its
+/// registration function reads it. The first four fields are the arguments
+/// of `REGISTER_NIF_COLLECTION`, in the order of AtomVM's own collection
+/// record; `get_nif_flags` is handed to hosts that schedule by flag.
+#[repr(C)]
+pub struct NifCollectionDef {
+    pub name: *const c_char,
+    pub init: extern "C" fn(*mut crate::Context),
+    pub destroy: extern "C" fn(*mut GlobalContext),
+    /// The NIF function for a name, null if unknown
+    pub get_nif: extern "C" fn(*const u8) -> *const c_void,
+    /// The `NifFlags` of a NIF as `u32`, 0 if unknown
+    pub get_nif_flags: extern "C" fn(*const u8) -> u32,
+}
+
+// The record only holds a `'static` name and function addresses
+unsafe impl Sync for NifCollectionDef {}
+
+/// Find a NIF by name, scanning the table
+pub fn find_nif<'t>(table: &'t [NifEntry], name: &str) -> Option<&'t NifEntry> {
+    table.iter().find(|entry| entry.name == name)
+}
+
+/// `a < b`, byte by byte, in a const context
+const fn name_less(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut i = 0;
+    while i < a.len() && i < b.len() {
+        if a[i] != b[i] {
+            return a[i] < b[i];
+        }
+        i += 1;
+    }
+    a.len() < b.len()
+}
+
+/// Positions of `table`'s entries ordered by name, for `find_nif_sorted`
+///
+/// Evaluated at compile time by `nif_collection!`. Entries with the same
+/// name keep their table order. `N` must be `table.len()`, and no two
+/// entries may share both name and arity:
+///
+/// ```compile_fail,E0080
+/// use avmnif_rs::term::{Context, Term};
+///
+/// extern "C" fn add_nif(_ctx: *mut Context, _argc: i32, _argv: *const Term) -> Term<'static> {
+///     Term::from_raw(0x3B)
+/// }
+///
+/// fn math_init(_ctx: &mut avmnif_rs::Context) {}
+///
+/// avmnif_rs::nif_collection!(math, init = math_init, nifs = [("add", 2, add_nif), ("add", 2, add_nif)]);
+/// ```
+pub const fn sorted_index<const N: usize>(table: &[NifEntry]) -> [usize; N] {
+    assert!(N == table.len(), "sorted_index: N must be the table length");
+    let mut i = 0;
+    while i < N {
+        let mut j = i + 1;
+        while j < N {
+            let (a, b) = (&table[i], &table[j]);
+            let same_name = !name_less(a.name, b.name) && !name_less(b.name, a.name);
+            assert!(!(same_name && a.arity == b.arity), "a NIF collection lists the same name and arity twice");
+            j += 1;
+        }
+        i += 1;
+    }
+    let mut index = [0; N];
+    let mut i = 0;
+    while i < N {
+        index[i] = i;
+        i += 1;
+    }
+    // Insertion sort: stable, and tables are small
+    let mut i = 1;
+    while i < N {
+        let mut j = i;
+        while j > 0 && name_less(table[index[j]].name, table[index[j - 1]].name) {
+            let swapped = index[j];
+            index[j] = index[j - 1];
+            index[j - 1] = swapped;
+            j -= 1;
+        }
+        i += 1;
+    }
+    index
+}
+
+/// Find a NIF by name with a binary search over `sorted_index(table)`
+///
+/// This is the generated resolver's lookup. With several entries of the
+/// same name it returns the first listed, as `find_nif` does.
+pub fn find_nif_sorted<'t>(table: &'t [NifEntry], index: &[usize], name: &str) -> Option<&'t NifEntry> {
+    let first = index.partition_point(|&i| table[i].name < name);
+    index.get(first).map(|&i| &table[i]).filter(|entry| entry.name == name)
+}
+
+/// Find the NIF `name/arity` over `sorted_index(table)`
+pub fn find_nif_arity<'t>(table: &'t [NifEntry], index: &[usize], name: &str, arity: u32) -> Option<&'t NifEntry> {
+    let first = index.partition_point(|&i| table[i].name < name);
+    index[first..]
+        .iter()
+        .map(|&i| &table[i])
+        .take_while(|entry| entry.name == name)
+        .find(|entry| entry.arity == arity)
+}
+
+/// Resolve the name AtomVM asks a collection for, as the generated resolver does
+///
+/// An entry named exactly `requested` wins. Otherwise `function/arity` or
+/// `module:function/arity` is looked up by function name and arity, so
+/// `foo/1` and `foo/2` reach different entries. AtomVM asks every
+/// registered collection in turn, so with `module` set a request for
+/// another module's `module:function/arity` resolves to nothing; without
+/// it the module prefix is not checked.
+pub fn resolve_nif<'t>(table: &'t [NifEntry], index: &[usize], module: Option<&str>, requested: &str) -> Option<&'t NifEntry> {
+    find_nif_sorted(table, index, requested).or_else(|| {
+        let (function, arity) = requested.rsplit_once('/')?;
+        let arity = arity.parse().ok()?;
+        let function = match function.split_once(':') {
+            Some((prefix, function)) if module.map_or(true, |module| module == prefix) => function,
+            Some(_) => return None,
+            None => function,
+        };
+        find_nif_arity(table, index, function, arity)
+    })
+}
+
+/// The term returned by a collection's `__info__/0` NIF
+///
+/// `#{name => Name, nifs => [{Function, Arity}, ...], version => <<"Version">>}`,
+/// with the NIFs in table order like `Module:module_info(exports)`.
+pub fn collection_info<T: AtomTableOps>(name: &str, nifs: &[NifEntry], version: &str, table: &T) -> TermValue {
+    let exports = nifs
+        .iter()
+        .map(|entry| TermValue::tuple(alloc::vec![TermValue::atom(entry.name, table), TermValue::int(entry.arity as i32)]))
+        .collect();
+    TermValue::map(alloc::vec![
+        (TermValue::atom("name", table), TermValue::atom(name, table)),
+        (TermValue::atom("nifs", table), TermValue::list(exports)),
+        (TermValue::atom("version", table), TermValue::binary(version.as_bytes().to_vec())),
+    ])
+}
+
 #[macro_export]
 macro_rules! nif_collection {
     (
         $moniker:ident,
+        $( module = $module:literal, )?
         init = $init_fn:ident,
-        nifs = [ $( ($name:literal, $arity:literal, $func:path) ),* $(,)? ]
+        $( upgrade = $upgrade_fn:ident, )?
+        $( unload = $unload_fn:ident, )?
+        $( priv_data = $priv_ty:ty, )?
+        $( atoms = $intern_atoms:path, )?
+        $( reply_style = $style:expr, )?
+        $( replies = [ $( ($rname:literal, $rarity:literal, $rfunc:path $(, $rflag:ident)?) ),* $(,)? ], )?
+        $( typed = [ $( ($tname:literal, $( $tarity:literal, )? $tfunc:path $(, $tflag:ident)*) ),* $(,)? ], )?
+        $( info = $version:expr, )?
+        nifs = [ $( ($name:literal, $arity:literal, $func:path $(, $flag:ident)?) ),* $(,)? ]
     ) => {
         ::paste::paste! {
+            // ── reply style ──────────────────────────────────────────────────
+            /// Envelope used by `reply_ok!`, `reply_error!` and the `replies` wrappers
+            #[allow(dead_code)]
+            pub const [<$moniker:upper _REPLY_STYLE>]: $crate::reply::ReplyStyle = {
+                // The configured style, if any, comes last
+                let styles = [$crate::reply::ReplyStyle::Tagged $(, $style)?];
+                styles[styles.len() - 1]
+            };
+
+            // ── module ───────────────────────────────────────────────────────
+            /// Erlang module whose NIFs the resolver answers for; `None` answers any
+            #[allow(dead_code)]
+            pub const [<$moniker:upper _MODULE>]: Option<&str> = {
+                let modules = [None $(, Some($module))?];
+                modules[modules.len() - 1]
+            };
+
+            // ── private data ─────────────────────────────────────────────────
+            $(
+                /// State kept on the global context across reloads of the collection's module
+                pub static [<$moniker:upper _PRIV>]: $crate::registry::PrivData<$priv_ty> =
+                    $crate::registry::PrivData::new(concat!(stringify!($moniker), "\0"));
+            )?
+
+            // ── NIF table ────────────────────────────────────────────────────
+            /// Name, arity and function of every NIF in the collection
+            pub const [<$moniker:upper _NIFS>]: &[$crate::registry::NifEntry] = &[
+                $(
+                    $crate::registry::NifEntry::new($name, $arity, {
+                        // Does not compile unless `$func` has the NIF signature
+                        let function: $crate::registry::NifFunction = $func;
+                        function as *const () as *const core::ffi::c_void
+                    })
+                    .with_flags($crate::nif_flags!($($flag)?)),
+                )*
+                $( $(
+                    $crate::registry::NifEntry::new($rname, $rarity, {
+                        extern "C" fn wrapper(
+                            ctx: *mut $crate::term::Context,
+                            argc: i32,
+                            argv: *const $crate::term::Term,
+                        ) -> $crate::term::Term<'static> {
+                            let nif = $rfunc;
+                            unsafe { $crate::reply::call_nif([<$moniker:upper _REPLY_STYLE>], ctx, argc, argv, nif) }
+                        }
+                        wrapper as *const () as *const core::ffi::c_void
+                    })
+                    .with_flags($crate::nif_flags!($($rflag)?)),
+                )* )?
+                $( $(
+                    $crate::registry::NifEntry::new($tname, {
+                        const ARITY: u32 = $crate::nif::arity_of(&$tfunc);
+                        $(
+                            const _: () = assert!(
+                                ARITY == $tarity,
+                                concat!("NIF `", $tname, "` is declared with arity ", stringify!($tarity),
+                                        " but its function takes a different number of arguments")
+                            );
+                        )?
+                        ARITY
+                    }, {
+                        extern "C" fn wrapper(
+                            ctx: *mut $crate::term::Context,
+                            argc: i32,
+                            argv: *const $crate::term::Term,
+                        ) -> $crate::term::Term<'static> {
+                            let nif = $tfunc;
+                            let errors = $crate::nif_error_encoding!($($tflag)*);
+                            unsafe { $crate::nif::call_nif(nif, errors, ctx, argc, argv) }
+                        }
+                        wrapper as *const () as *const core::ffi::c_void
+                    })
+                    .with_flags($crate::nif_flags!($($tflag)*)),
+                )* )?
+                $(
+                    $crate::registry::NifEntry::new("__info__", 0, {
+                        extern "C" fn wrapper(
+                            ctx: *mut $crate::term::Context,
+                            argc: i32,
+                            argv: *const $crate::term::Term,
+                        ) -> $crate::term::Term<'static> {
+                            let nif = |_env: &mut $crate::term::Env<'_>| {
+                                $crate::registry::collection_info(
+                                    stringify!($moniker),
+                                    [<$moniker:upper _NIFS>],
+                                    $version,
+                                    &$crate::atom::AtomTable::from_global(),
+                                )
+                            };
+                            unsafe { $crate::nif::call_nif(nif, $crate::nif::ErrorEncoding::Raise, ctx, argc, argv) }
+                        }
+                        wrapper as *const () as *const core::ffi::c_void
+                    }),
+                )?
+            ];
+
+            /// Positions in the NIF table sorted by name, for the resolver
+            pub const [<$moniker:upper _NIF_INDEX>]: [usize; [<$moniker:upper _NIFS>].len()] =
+                $crate::registry::sorted_index([<$moniker:upper _NIFS>]);
+
             // ── init & resolver ───────────────────────────────────────────────
+            static [<_ $moniker:upper _INIT>]: $crate::registry::InitGuard = $crate::registry::InitGuard::new();
+
             #[no_mangle]
             pub extern "C" fn [<$moniker _nif_init>](ctx: *mut $crate::Context) {
-                unsafe { $init_fn(&mut *ctx) }
+                if ![<_ $moniker:upper _INIT>].begin() {
+                    // A completed init called again is a reload
+                    let upgrades: &[fn(&mut $crate::Context)] = &[$($upgrade_fn)?];
+                    match upgrades.first() {
+                        Some(upgrade) if [<_ $moniker:upper _INIT>].is_initialized() => {
+                            $crate::contracts::non_null(ctx, "NIF collection upgrade called with null context");
+                            unsafe { upgrade(&mut *ctx) }
+                        }
+                        _ => $crate::registry::repeated_init(concat!(stringify!($moniker), "_nif_init")),
+                    }
+                    return;
+                }
+                // Atoms declared with `atoms!` are interned before user init runs;
+                // failure means the atom table is exhausted and the accessors stay unset
+                $( let _ = $intern_atoms(&$crate::atom::AtomTable::from_global()); )?
+                $crate::contracts::non_null(ctx, "NIF collection init called with null context");
+                unsafe { $init_fn(&mut *ctx) };
+                [<_ $moniker:upper _INIT>].finish(true);
+            }
+
+            /// Destroy function of the collection: runs `unload`, drops the private data and re-arms init
+            #[no_mangle]
+            pub extern "C" fn [<$moniker _nif_destroy>](global: *mut $crate::context::GlobalContext) {
+                $(
+                    $crate::contracts::non_null(global, "NIF collection unload called with null global context");
+                    $unload_fn(unsafe { &mut *global });
+                )?
+                $(
+                    if !global.is_null() {
+                        let _: Option<$priv_ty> = [<$moniker:upper _PRIV>].take(global);
+                    }
+                )?
+                let _ = global;
+                [<_ $moniker:upper _INIT>].reset();
             }
 
             #[no_mangle]
@@ -17,48 +554,208 @@ macro_rules! nif_collection {
                 -> *const core::ffi::c_void
             {
                 let cstr = unsafe { core::ffi::CStr::from_ptr(name as *const _) };
-                match cstr.to_str().unwrap_or("") {
-                    $(
-                        $name => $func as *const () as *const core::ffi::c_void,
-                    )*
-                    _ => core::ptr::null(),
-                }
+                $crate::registry::resolve_nif(
+                    [<$moniker:upper _NIFS>],
+                    &[<$moniker:upper _NIF_INDEX>],
+                    [<$moniker:upper _MODULE>],
+                    cstr.to_str().unwrap_or(""),
+                )
+                    .map_or(core::ptr::null(), |entry| entry.function)
+            }
+
+            /// Scheduling hint of a NIF (`NifFlags` as `u32`), 0 if unknown
+            #[no_mangle]
+            pub extern "C" fn [<$moniker _get_nif_flags>](name: *const u8) -> u32 {
+                let cstr = unsafe { core::ffi::CStr::from_ptr(name as *const _) };
+                $crate::registry::resolve_nif(
+                    [<$moniker:upper _NIFS>],
+                    &[<$moniker:upper _NIF_INDEX>],
+                    [<$moniker:upper _MODULE>],
+                    cstr.to_str().unwrap_or(""),
+                )
+                    .map_or(0, |entry| entry.flags as u32)
+            }
+
+            // ── registration ─────────────────────────────────────────────────
+            /// What the collection registers with the host
+            pub static [<$moniker:upper _NIF_COLLECTION>]: $crate::registry::NifCollectionDef =
+                $crate::registry::NifCollectionDef {
+                    name: concat!(stringify!($moniker), "\0").as_ptr() as *const core::ffi::c_char,
+                    init: [<$moniker _nif_init>],
+                    destroy: [<$moniker _nif_destroy>],
+                    get_nif: [<$moniker _get_nif>],
+                    get_nif_flags: [<$moniker _get_nif_flags>],
+                };
+
+            // On wasm32 there are no ELF constructor sections, so the host
+            // calls this export explicitly (see `register_nif_collections!`).
+            #[cfg_attr(target_arch = "wasm32", no_mangle)]
+            pub extern "C" fn [<$moniker _nif_register>]() {
+                // unread where `registration_call!` expands to nothing
+                #[allow(unused_variables)]
+                let def = &[<$moniker:upper _NIF_COLLECTION>];
+                $crate::registration_call!(REGISTER_NIF_COLLECTION(
+                    def.name as *const u8,
+                    def.init as *const core::ffi::c_void,
+                    def.destroy as *const core::ffi::c_void,
+                    def.get_nif as *const core::ffi::c_void
+                ));
             }
 
             // ── registration blob ────────────────────────────────────────────
-            #[used]
-            #[cfg_attr(
-                any(target_os = "macos", target_os = "ios"),
-                link_section = "__DATA,.nif_collection"
-            )]
-            #[cfg_attr(
-                not(any(target_os = "macos", target_os = "ios")),
-                link_section = ".nif_collection"
-            )]
-            static _REGISTER: extern "C" fn() = {
-                extern "C" fn register() {
-                    // skip during `cargo test` so the host linker
-                    // doesn’t look for AtomVM’s C symbol
-                    #[cfg(not(test))]
-                    unsafe {
-                        extern "C" {
-                            fn REGISTER_NIF_COLLECTION(
-                                name: *const u8,
-                                init: *const core::ffi::c_void,
-                                destroy: *const core::ffi::c_void,
-                                resolver: *const core::ffi::c_void,
-                            );
-                        }
-                        REGISTER_NIF_COLLECTION(
-                            concat!(stringify!($moniker), "\0").as_ptr(),
-                            [<$moniker _nif_init>] as *const _,
-                            core::ptr::null(),
-                            [<$moniker _get_nif>] as *const _,
-                        );
-                    }
-                }
-                register
-            };
+            $crate::registration_entry!(
+                [<_ $moniker:upper _NIF_REGISTER>] = [<$moniker _nif_register>],
+                section = ".nif_collection",
+                macos_section = "__DATA,.nif_collection"
+            );
+        }
+    };
+}
+
+/// Place a registration function where the platform finds it at startup
+///
+/// Used by `nif_collection!` and `port_collection!`. Off wasm32 the record
+/// goes in the collection's link section. With the `rp2040` feature it goes
+/// in `.init_array` instead: pico-sdk's runtime runs those before `main`,
+/// the way it runs the constructors of AtomVM's C `REGISTER_*` macros,
+/// while its linker script has no place for the custom sections. With the
+/// `esp-idf` feature there is no record: the component stub from
+/// `avmnif-build` registers through AtomVM's C macros instead.
+#[doc(hidden)]
+#[cfg(not(any(feature = "rp2040", feature = "esp-idf")))]
+#[macro_export]
+macro_rules! registration_entry {
+    ($name:ident = $register:expr, section = $section:literal, macos_section = $macos_section:literal) => {
+        #[cfg(not(target_arch = "wasm32"))]
+        #[used]
+        #[cfg_attr(any(target_os = "macos", target_os = "ios"), link_section = $macos_section)]
+        #[cfg_attr(not(any(target_os = "macos", target_os = "ios")), link_section = $section)]
+        static $name: extern "C" fn() = $register;
+    };
+}
+
+#[doc(hidden)]
+#[cfg(all(feature = "rp2040", not(feature = "esp-idf")))]
+#[macro_export]
+macro_rules! registration_entry {
+    ($name:ident = $register:expr, section = $section:literal, macos_section = $macos_section:literal) => {
+        #[used]
+        #[link_section = ".init_array"]
+        static $name: extern "C" fn() = $register;
+    };
+}
+
+#[doc(hidden)]
+#[cfg(feature = "esp-idf")]
+#[macro_export]
+macro_rules! registration_entry {
+    ($name:ident = $register:expr, section = $section:literal, macos_section = $macos_section:literal) => {};
+}
+
+/// Call one of the host's `REGISTER_*` functions
+///
+/// `REGISTER_NIF_COLLECTION` and `REGISTER_PORT_DRIVER` take the four
+/// arguments of AtomVM's C macros of those names: the name, the init and
+/// destroy callbacks (null when absent), and the resolver or the port
+/// constructor. With the `esp-idf` feature the call is left out, as the
+/// component stub registers the same exports through the C macros.
+#[doc(hidden)]
+#[cfg(not(feature = "esp-idf"))]
+#[macro_export]
+macro_rules! registration_call {
+    ($register:ident($name:expr, $init:expr, $destroy:expr, $callback:expr)) => {
+        // skip during `cargo test` so the host linker
+        // doesn’t look for AtomVM’s C symbol
+        #[cfg(not(test))]
+        unsafe {
+            #[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+            extern "C" {
+                fn $register(
+                    name: *const u8,
+                    init: *const core::ffi::c_void,
+                    destroy: *const core::ffi::c_void,
+                    callback: *const core::ffi::c_void,
+                );
+            }
+            $register($name, $init, $destroy, $callback);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[cfg(feature = "esp-idf")]
+#[macro_export]
+macro_rules! registration_call {
+    ($register:ident($name:expr, $init:expr, $destroy:expr, $callback:expr)) => {};
+}
+
+/// Map `nif_collection!` entry flags to `NifFlags`
+#[doc(hidden)]
+#[macro_export]
+macro_rules! nif_flags {
+    () => {
+        $crate::registry::NifFlags::Normal
+    };
+    (dirty_cpu $(error_tuple)?) => {
+        $crate::registry::NifFlags::DirtyCpu
+    };
+    (dirty_io $(error_tuple)?) => {
+        $crate::registry::NifFlags::DirtyIo
+    };
+    (error_tuple $($rest:ident)*) => {
+        $crate::nif_flags!($($rest)*)
+    };
+}
+
+/// Map `nif_collection!` entry flags to an `ErrorEncoding`
+#[doc(hidden)]
+#[macro_export]
+macro_rules! nif_error_encoding {
+    () => {
+        $crate::nif::ErrorEncoding::Raise
+    };
+    (error_tuple $($rest:ident)*) => {
+        $crate::nif::ErrorEncoding::Tuple
+    };
+    ($flag:ident $($rest:ident)*) => {
+        $crate::nif_error_encoding!($($rest)*)
+    };
+}
+
+/// Export a single entry point that registers several NIF collections
+///
+/// wasm32 has no link-section based constructors, so popcorn/AtomVM cannot
+/// discover collections on its own. This generates
+/// `avmnif_register_nif_collections()`, which the host calls once after the
+/// module is instantiated and before any Erlang code loads the NIFs.
+///
+/// On every other target registration already happens through the
+/// `.nif_collection` section and this macro expands to nothing, so the same
+/// source builds for both.
+///
+/// ```
+/// # #[cfg(feature = "sim")] {
+/// # use avmnif_rs::{nif_collection, register_nif_collections};
+/// # use avmnif_rs::term::{Context, Term};
+/// # fn math_init(_ctx: &mut avmnif_rs::Context) {}
+/// # fn text_init(_ctx: &mut avmnif_rs::Context) {}
+/// # extern "C" fn add_nif(_ctx: *mut Context, _argc: i32, argv: *const Term) -> Term<'static> { unsafe { Term::from_raw((*argv).raw()) } }
+/// # extern "C" fn upcase_nif(_ctx: *mut Context, _argc: i32, argv: *const Term) -> Term<'static> { unsafe { Term::from_raw((*argv).raw()) } }
+/// nif_collection!(math_nifs, init = math_init, nifs = [("add", 2, add_nif)]);
+/// nif_collection!(text_nifs, init = text_init, nifs = [("upcase", 1, upcase_nif)]);
+///
+/// register_nif_collections!(math_nifs, text_nifs);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! register_nif_collections {
+    ( $( $moniker:ident ),* $(,)? ) => {
+        ::paste::paste! {
+            #[cfg(target_arch = "wasm32")]
+            #[no_mangle]
+            pub extern "C" fn avmnif_register_nif_collections() {
+                $( [<$moniker _nif_register>](); )*
+            }
         }
     };
 }
@@ -0,0 +1,143 @@
+//! Reply envelope conventions
+//!
+//! Drivers disagree on how a NIF answers: `{ok, Value}` and
+//! `{error, Reason}`, the bare value with a raised error, or a status map.
+//! A collection picks one `ReplyStyle` with `reply_style = ...` in
+//! `nif_collection!`. NIFs listed under `replies = [...]` return a plain
+//! `Result<TermValue, TermValue>` and the generated wrapper puts it in the
+//! collection's envelope; hand-written NIFs use `reply_ok!` and `reply_error!`. Either
+//! way, switching the convention is a one-line change.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use avmnif_rs::atom::AtomTable;
+//! use avmnif_rs::reply::{ReplyResult, ReplyStyle};
+//! use avmnif_rs::term::{Context, Env, Term, TermValue};
+//! use avmnif_rs::{nif_collection, reply_ok};
+//! # fn adc_init(_ctx: &mut avmnif_rs::Context) {}
+//! # fn adc_read(channel: i32) -> i32 { 100 + channel }
+//!
+//! fn read(_env: &mut Env, args: &[Term]) -> ReplyResult {
+//!     let table = AtomTable::from_global();
+//!     match args[0].to_value() {
+//!         Ok(TermValue::SmallInt(channel)) => Ok(TermValue::int(adc_read(channel))),
+//!         _ => Err(TermValue::atom("badarg", &table)),
+//!     }
+//! }
+//!
+//! extern "C" fn version_nif(ctx: *mut Context, _argc: i32, _argv: *const Term) -> Term<'static> {
+//!     let table = AtomTable::from_global();
+//!     let reply = reply_ok!(adc, TermValue::int(2), &table).into_term(unsafe { &mut *ctx });
+//!     Term::from_raw(reply.raw())
+//! }
+//!
+//! nif_collection!(
+//!     adc,
+//!     init = adc_init,
+//!     reply_style = ReplyStyle::StatusMap,
+//!     replies = [("read", 1, read)],
+//!     nifs = [("version", 0, version_nif)]
+//! );
+//! # use avmnif_rs::parse::parse_term;
+//! # let global = avmnif_rs::testing::mocks::MockGlobalContext::new();
+//! # let mut process = global.new_context();
+//! # let ctx = process.as_context() as *mut _ as *mut Context;
+//! # let expected = parse_term("#{status => ok, value => 2}", global.atoms()).unwrap();
+//! # assert_eq!(version_nif(ctx, 0, core::ptr::null()).to_value(), Ok(expected));
+//! # }
+//! ```
+
+extern crate alloc;
+
+use crate::atom::{AtomTable, AtomTableOps};
+use crate::term::{Context, Env, ExceptionClass, NifReturn, Term, TermValue};
+
+/// Outcome of a NIF before it is put in an envelope
+pub type ReplyResult = Result<TermValue, TermValue>;
+
+/// How a collection wraps successes and errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplyStyle {
+    /// `{ok, Value}` and `{error, Reason}`
+    #[default]
+    Tagged,
+    /// `Value`, and errors raise `error:Reason`
+    Bare,
+    /// `#{status => ok, value => Value}` and
+    /// `#{status => error, reason => Reason}`
+    StatusMap,
+}
+
+impl ReplyStyle {
+    /// Envelope for a successful result
+    pub fn ok<T: AtomTableOps>(self, value: TermValue, table: &T) -> NifReturn<'static> {
+        match self {
+            ReplyStyle::Tagged => NifReturn::Value(TermValue::tuple(alloc::vec![
+                TermValue::atom("ok", table),
+                value,
+            ])),
+            ReplyStyle::Bare => NifReturn::Value(value),
+            ReplyStyle::StatusMap => NifReturn::Value(TermValue::map(alloc::vec![
+                (TermValue::atom("status", table), TermValue::atom("ok", table)),
+                (TermValue::atom("value", table), value),
+            ])),
+        }
+    }
+
+    /// Envelope for an error, or the exception for `Bare`
+    pub fn error<T: AtomTableOps>(self, reason: TermValue, table: &T) -> NifReturn<'static> {
+        match self {
+            ReplyStyle::Tagged => NifReturn::Value(TermValue::tuple(alloc::vec![
+                TermValue::atom("error", table),
+                reason,
+            ])),
+            ReplyStyle::Bare => NifReturn::Raise(ExceptionClass::Error, reason),
+            ReplyStyle::StatusMap => NifReturn::Value(TermValue::map(alloc::vec![
+                (TermValue::atom("status", table), TermValue::atom("error", table)),
+                (TermValue::atom("reason", table), reason),
+            ])),
+        }
+    }
+
+    /// Envelope for either outcome
+    pub fn reply<T: AtomTableOps>(self, result: ReplyResult, table: &T) -> NifReturn<'static> {
+        match result {
+            Ok(value) => self.ok(value, table),
+            Err(reason) => self.error(reason, table),
+        }
+    }
+}
+
+/// Run a `replies` NIF and wrap its result; used by `nif_collection!`
+///
+/// # Safety
+/// `ctx`, `argc` and `argv` must be the arguments of the current NIF call.
+pub unsafe fn call_nif<F>(style: ReplyStyle, ctx: *mut Context, argc: i32, argv: *const Term, nif: F) -> Term<'static>
+where
+    F: for<'a> FnOnce(&mut Env<'a>, &'a [Term<'a>]) -> ReplyResult,
+{
+    let _scope = crate::panic::enter_nif(ctx);
+    let mut env = Env::from_raw(ctx);
+    let args = env.args(argc, argv);
+    let result = nif(&mut env, args);
+    let term = style.reply(result, &AtomTable::from_global()).into_term(env.context());
+    Term::from_raw(term.raw())
+}
+
+/// Success in a collection's reply style: `reply_ok!(moniker, value, table)`
+#[macro_export]
+macro_rules! reply_ok {
+    ($moniker:ident, $value:expr, $table:expr) => {
+        ::paste::paste! { [<$moniker:upper _REPLY_STYLE>].ok($value, $table) }
+    };
+}
+
+/// Error in a collection's reply style: `reply_error!(moniker, reason, table)`
+#[macro_export]
+macro_rules! reply_error {
+    ($moniker:ident, $reason:expr, $table:expr) => {
+        ::paste::paste! { [<$moniker:upper _REPLY_STYLE>].error($reason, $table) }
+    };
+}
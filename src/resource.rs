@@ -2,136 +2,28 @@
 //! 
 //! Provides safe Rust wrappers around AtomVM's resource NIF API with trait abstraction
 
-use crate::term::{NifError, NifResult};
-use core::ffi::{c_void, c_char, c_int, c_uint};
+use crate::sync::SpinLock;
+use crate::term::{NifError, NifResult, Term};
+use core::any::TypeId;
+use core::ffi::{c_void, c_char, c_uint};
+use core::fmt;
+use core::ops::Deref;
+use core::ptr::NonNull;
 use alloc::format;
 use alloc::boxed::Box;
+use alloc::vec::Vec;
 
-// Suppress naming warnings for FFI compatibility
-#[allow(non_camel_case_types)]
-pub type ERL_NIF_TERM = u64; // typedef term ERL_NIF_TERM (assuming 64-bit term)
-
-pub type ErlNifEnv = c_void; // Opaque struct
-pub type ErlNifResourceType = c_void; // Opaque struct  
-pub type ErlNifPid = i32;
-pub type ErlNifEvent = c_int;
-
-/// Resource destructor callback type
-pub type ErlNifResourceDtor = unsafe extern "C" fn(caller_env: *mut ErlNifEnv, obj: *mut c_void);
-
-/// Select stop callback type  
-pub type ErlNifResourceStop = unsafe extern "C" fn(
-    caller_env: *mut ErlNifEnv, 
-    obj: *mut c_void, 
-    event: ErlNifEvent, 
-    is_direct_call: c_int
-);
-
-/// Resource monitor callback type
-pub type ErlNifResourceDown = unsafe extern "C" fn(
-    caller_env: *mut ErlNifEnv, 
-    obj: *mut c_void, 
-    pid: *mut ErlNifPid, 
-    mon: *mut ErlNifMonitor
-);
-
-/// Monitor type
-#[repr(C)]
-pub struct ErlNifMonitor {
-    pub resource_type: *mut ErlNifResourceType,
-    pub ref_ticks: u64,
-}
-
-/// Resource type initialization callbacks
-#[repr(C)]
-pub struct ErlNifResourceTypeInit {
-    pub members: c_int,
-    pub dtor: Option<ErlNifResourceDtor>,
-    pub stop: Option<ErlNifResourceStop>, 
-    pub down: Option<ErlNifResourceDown>,
-}
-
-/// Resource creation flags
-#[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq)]
-#[allow(non_camel_case_types)]
-pub enum ErlNifResourceFlags {
-    ERL_NIF_RT_CREATE = 1,
-    // ERL_NIF_RT_TAKEOVER not supported yet
-}
-
-/// Select mode flags
-#[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq)]
-#[allow(non_camel_case_types)]
-pub enum ErlNifSelectFlags {
-    ERL_NIF_SELECT_READ = 1,
-    ERL_NIF_SELECT_WRITE = 2,
-    ERL_NIF_SELECT_STOP = 4,
-}
-
-// AtomVM Resource NIF FFI declarations (exact signatures from erl_nif.h)
-extern "C" {
-    /// Create or take over a resource type
-    pub fn enif_init_resource_type(
-        env: *mut ErlNifEnv,
-        name: *const c_char,
-        init: *const ErlNifResourceTypeInit,
-        flags: ErlNifResourceFlags,
-        tried: *mut ErlNifResourceFlags,
-    ) -> *mut ErlNifResourceType;
-
-    /// Allocate a new resource of the specified type and size
-    pub fn enif_alloc_resource(
-        resource_type: *mut ErlNifResourceType,
-        size: c_uint,
-    ) -> *mut c_void;
-
-    /// Create an Erlang term from a resource pointer
-    pub fn enif_make_resource(
-        env: *mut ErlNifEnv,
-        obj: *mut c_void,
-    ) -> ERL_NIF_TERM;
-
-    /// Extract a resource from an Erlang term
-    pub fn enif_get_resource(
-        env: *mut ErlNifEnv,
-        t: ERL_NIF_TERM,
-        resource_type: *mut ErlNifResourceType,
-        objp: *mut *mut c_void,
-    ) -> c_int;
-
-    /// Increment resource reference count
-    pub fn enif_keep_resource(obj: *mut c_void) -> c_int;
-
-    /// Decrement resource reference count
-    pub fn enif_release_resource(obj: *mut c_void) -> c_int;
-
-    /// Select on file descriptors  
-    pub fn enif_select(
-        env: *mut ErlNifEnv,
-        event: ErlNifEvent,
-        mode: ErlNifSelectFlags,
-        obj: *mut c_void,
-        pid: *const ErlNifPid,
-        reference: ERL_NIF_TERM,
-    ) -> c_int;
-
-    /// Monitor a process using a resource
-    pub fn enif_monitor_process(
-        env: *mut ErlNifEnv,
-        obj: *mut c_void,
-        target_pid: *const ErlNifPid,
-        mon: *mut ErlNifMonitor,
-    ) -> c_int;
+pub use avmnif_sys::{
+    ErlNifEnv, ErlNifEvent, ErlNifMonitor, ErlNifPid, ErlNifResourceDown, ErlNifResourceDtor,
+    ErlNifResourceFlags, ErlNifResourceStop, ErlNifResourceType, ErlNifResourceTypeInit,
+    ErlNifSelectFlags, ERL_NIF_TERM,
+};
 
-    /// Remove a process monitor
-    pub fn enif_demonitor_process(
-        caller_env: *mut ErlNifEnv,
-        obj: *mut c_void,
-        mon: *const ErlNifMonitor,
-    ) -> c_int;
-}
+// AtomVM Resource NIF FFI declarations
+pub use avmnif_sys::{
+    enif_alloc_resource, enif_demonitor_process, enif_get_resource, enif_init_resource_type,
+    enif_keep_resource, enif_make_resource, enif_monitor_process, enif_release_resource, enif_select,
+};
 
 /// Errors that can occur during resource operations
 #[derive(Debug, PartialEq, Clone)]
@@ -386,7 +278,8 @@ impl ResourceManager for AtomVMResourceManager {
         let result = unsafe {
             enif_select(env, event, mode, obj, pid, reference)
         };
-        if result == 0 {
+        // Stopping reports how the stop callback was run as positive flags
+        if result >= 0 {
             Ok(())
         } else {
             Err(ResourceError::BadArg)
@@ -435,6 +328,43 @@ impl ResourceManager for AtomVMResourceManager {
     }
 }
 
+/// Process monitor owned by a resource, for use with `monitor::PidSet`
+///
+/// DOWN notifications arrive through the resource type's `down` callback;
+/// pass the pid it receives to `PidSet::handle_down`.
+pub struct ResourceProcessMonitor<'a, R: ResourceManager + ?Sized> {
+    manager: &'a R,
+    env: *mut ErlNifEnv,
+    obj: *mut c_void,
+}
+
+impl<'a, R: ResourceManager + ?Sized> ResourceProcessMonitor<'a, R> {
+    /// Monitor processes on behalf of the resource `obj`
+    pub fn new(manager: &'a R, env: *mut ErlNifEnv, obj: *mut c_void) -> Self {
+        Self { manager, env, obj }
+    }
+}
+
+impl<'a, R: ResourceManager + ?Sized> crate::monitor::ProcessMonitor for ResourceProcessMonitor<'a, R> {
+    type Handle = ErlNifMonitor;
+
+    fn monitor(&mut self, pid: u32) -> Result<ErlNifMonitor, crate::monitor::MonitorError> {
+        let target = pid as ErlNifPid;
+        let mut mon = ErlNifMonitor {
+            resource_type: core::ptr::null_mut(),
+            ref_ticks: 0,
+        };
+        self.manager
+            .monitor_process(self.env, self.obj, &target, &mut mon)
+            .map(|_| mon)
+            .map_err(|_| crate::monitor::MonitorError::NoProcess)
+    }
+
+    fn demonitor(&mut self, _pid: u32, handle: &ErlNifMonitor) {
+        let _ = self.manager.demonitor_process(self.env, self.obj, handle);
+    }
+}
+
 /// Global resource manager instance
 /// 
 /// This can be swapped out for testing or different implementations
@@ -524,9 +454,142 @@ pub const fn resource_type_init_full(
     }
 }
 
+/// A registered resource type, found by the Rust type it holds
+///
+/// The generated `init_<name>` functions record each type they create, so
+/// code that knows the Rust type can find its `ErlNifResourceType*`
+/// without a symbol per type. Each Rust type maps to one resource type;
+/// registering it again replaces the entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceType(NonNull<ErlNifResourceType>);
+
+/// `TypeId` to resource type, filled in at init time
+static RESOURCE_TYPES: SpinLock<Vec<(TypeId, usize)>> = SpinLock::new(Vec::new());
+
+// Safety: the handle is only ever passed back to the VM, which shares
+// resource types between schedulers
+unsafe impl Send for ResourceType {}
+unsafe impl Sync for ResourceType {}
+
+impl ResourceType {
+    /// The resource type registered for `T`, if its init function ran
+    pub fn of<T: 'static>() -> Option<Self> {
+        let id = TypeId::of::<T>();
+        RESOURCE_TYPES.with(|types| {
+            types
+                .iter()
+                .find(|(registered, _)| *registered == id)
+                .and_then(|(_, ptr)| NonNull::new(*ptr as *mut ErlNifResourceType))
+                .map(Self)
+        })
+    }
+
+    /// Record `resource_type` as the type holding `T`s
+    pub fn register<T: 'static>(resource_type: *mut ErlNifResourceType) -> Option<Self> {
+        let registered = NonNull::new(resource_type).map(Self)?;
+        let id = TypeId::of::<T>();
+        RESOURCE_TYPES.with(|types| match types.iter_mut().find(|(existing, _)| *existing == id) {
+            Some(entry) => entry.1 = resource_type as usize,
+            None => types.push((id, resource_type as usize)),
+        });
+        Some(registered)
+    }
+
+    /// `of::<T>()` as a raw pointer, null if `T` is not registered
+    pub fn ptr_of<T: 'static>() -> *mut ErlNifResourceType {
+        Self::of::<T>().map_or(core::ptr::null_mut(), Self::as_ptr)
+    }
+
+    pub fn as_ptr(self) -> *mut ErlNifResourceType {
+        self.0.as_ptr()
+    }
+}
+
+/// Create the resource type for `T` once, for the generated `init_<name>` functions
+///
+/// A repeated call does not register the name again: it keeps the type
+/// from the first call and reports whether that one succeeded.
+///
+/// # Safety
+/// `name` must end with a nul byte, and `guard` must belong to this
+/// resource type alone.
+#[doc(hidden)]
+pub unsafe fn init_resource_type_once<T: 'static>(
+    guard: &crate::registry::InitGuard,
+    env: *mut ErlNifEnv,
+    name: &'static str,
+    callbacks: &ErlNifResourceTypeInit,
+) -> bool {
+    if !guard.begin() {
+        crate::registry::repeated_init(name.trim_end_matches('\0'));
+        return ResourceType::of::<T>().is_some();
+    }
+    let mut tried_flags = ErlNifResourceFlags::ERL_NIF_RT_CREATE;
+    let created = enif_init_resource_type(
+        env,
+        name.as_ptr() as *const c_char,
+        callbacks,
+        ErlNifResourceFlags::ERL_NIF_RT_CREATE,
+        &mut tried_flags,
+    );
+    let registered = ResourceType::register::<T>(created).is_some();
+    guard.finish(registered);
+    registered
+}
+
+/// Allocate a resource of `T`'s registered type and move `data` into it
+///
+/// Used by `create_resource!`. Fails with `BadArg` if `T` has no
+/// registered type.
+pub fn create_registered<T: 'static>(data: T) -> NifResult<*mut c_void> {
+    create_registered_in(default_resource_manager(), data)
+}
+
+/// `create_registered` using a specific manager
+pub fn create_registered_in<T: 'static>(manager: &dyn ResourceManager, data: T) -> NifResult<*mut c_void> {
+    let resource_type = ResourceType::of::<T>().ok_or(NifError::BadArg)?;
+    let ptr = alloc_for::<T>(manager, resource_type.as_ptr())?;
+    unsafe { ptr.as_ptr().write(data) };
+    Ok(ptr.as_ptr() as *mut c_void)
+}
+
+/// Allocate a resource block of `resource_type` sized and aligned for `T`
+///
+/// A misaligned block is released before anything is written to it, which
+/// `resource_dtor` relies on.
+fn alloc_for<T>(manager: &dyn ResourceManager, resource_type: *mut ErlNifResourceType) -> NifResult<NonNull<T>> {
+    let size = core::mem::size_of::<T>().max(1) as c_uint;
+    let obj = manager.alloc_resource(resource_type, size)?;
+    if (obj as usize) & (core::mem::align_of::<T>() - 1) != 0 {
+        let _ = manager.release_resource(obj);
+        return Err(NifError::Other("resource allocation is misaligned for type"));
+    }
+    NonNull::new(obj as *mut T).ok_or(NifError::OutOfMemory)
+}
+
+/// The `T` held by the resource `term` refers to
+///
+/// Used by `get_resource!`. Fails with `BadArg` if the term is not a
+/// resource of `T`'s registered type.
+pub fn get_registered<T: 'static>(env: *mut ErlNifEnv, term: Term) -> NifResult<*mut T> {
+    get_registered_in(default_resource_manager(), env, term)
+}
+
+/// `get_registered` using a specific manager
+pub fn get_registered_in<T: 'static>(manager: &dyn ResourceManager, env: *mut ErlNifEnv, term: Term) -> NifResult<*mut T> {
+    let resource_type = ResourceType::of::<T>().ok_or(NifError::BadArg)?;
+    let ptr = manager.get_resource(env, term.raw() as ERL_NIF_TERM, resource_type.as_ptr())?;
+    if ptr.is_null() {
+        Err(NifError::BadArg)
+    } else {
+        Ok(ptr as *mut T)
+    }
+}
+
 /// Convenience functions that use the global resource manager or fallback to direct FFI
 /// Manually increment resource reference count
 pub fn keep_resource(resource: *mut c_void) -> NifResult<()> {
+    crate::contracts::non_null(resource, "keep_resource on null resource");
     if RESOURCE_MANAGER_INIT.load(core::sync::atomic::Ordering::SeqCst) {
         let manager = get_resource_manager();
         manager.keep_resource(resource).map_err(|e| e.into())
@@ -543,6 +606,7 @@ pub fn keep_resource(resource: *mut c_void) -> NifResult<()> {
 
 /// Manually decrement resource reference count
 pub fn release_resource(resource: *mut c_void) -> NifResult<()> {
+    crate::contracts::non_null(resource, "release_resource on null resource");
     if RESOURCE_MANAGER_INIT.load(core::sync::atomic::Ordering::SeqCst) {
         let manager = get_resource_manager();
         manager.release_resource(resource).map_err(|e| e.into())
@@ -557,89 +621,315 @@ pub fn release_resource(resource: *mut c_void) -> NifResult<()> {
     }
 }
 
+/// Rust types that can live inside an AtomVM resource
+///
+/// Implemented by `impl_resource!`, which registers the type with
+/// `resource_dtor::<T>` so the type's `Drop` impl runs when AtomVM
+/// destroys the last reference.
+pub trait Resource: Sized + Send + Sync + 'static {
+    /// Registered resource type handle (null until the init function ran)
+    fn resource_type() -> *mut ErlNifResourceType {
+        ResourceType::ptr_of::<Self>()
+    }
+}
+
+/// Destructor running `T`'s `Drop` impl
+///
+/// Registered for every `Resource` type, and by `resource_type!` when no
+/// destructor is given. A block not aligned for `T` is skipped:
+/// `ResourceArc::new_in` and `create_registered_in` give such a block back
+/// without writing a value into it, so there is nothing to drop.
+///
+/// # Safety
+/// Must only be called by the VM (or a mock manager) with `obj` pointing to
+/// an initialized `T` that is never accessed again afterwards, or to a
+/// block misaligned for `T`.
+pub unsafe extern "C" fn resource_dtor<T>(_env: *mut ErlNifEnv, obj: *mut c_void) {
+    if !obj.is_null() && (obj as usize) % core::mem::align_of::<T>() == 0 {
+        core::ptr::drop_in_place(obj as *mut T);
+    }
+}
+
+/// Direct-FFI manager used when no global manager has been installed
+static ATOMVM_RESOURCE_MANAGER: AtomVMResourceManager = AtomVMResourceManager;
+
+/// Get the global resource manager, falling back to direct FFI calls
+pub fn default_resource_manager() -> &'static dyn ResourceManager {
+    if RESOURCE_MANAGER_INIT.load(core::sync::atomic::Ordering::SeqCst) {
+        get_resource_manager()
+    } else {
+        &ATOMVM_RESOURCE_MANAGER
+    }
+}
+
+/// Reference-counted handle to a typed AtomVM resource
+///
+/// Each handle owns one resource reference: `Clone` keeps it, `Drop`
+/// releases it, and the VM runs `T`'s destructor once the last handle
+/// and the last term referencing the resource are gone.
+pub struct ResourceArc<T: Resource> {
+    ptr: NonNull<T>,
+    manager: &'static dyn ResourceManager,
+}
+
+impl<T: Resource> ResourceArc<T> {
+    /// Allocate a new resource holding `value`
+    pub fn new(value: T) -> NifResult<Self> {
+        Self::new_in(default_resource_manager(), value)
+    }
+
+    /// Allocate a new resource holding `value` using a specific manager
+    ///
+    /// Fails with `BadArg` if `T`'s resource type was never initialized.
+    pub fn new_in(manager: &'static dyn ResourceManager, value: T) -> NifResult<Self> {
+        let resource_type = T::resource_type();
+        if resource_type.is_null() {
+            return Err(NifError::BadArg);
+        }
+        let ptr = alloc_for::<T>(manager, resource_type)?;
+        unsafe {
+            ptr.as_ptr().write(value);
+        }
+
+        Ok(Self { ptr, manager })
+    }
+
+    /// Get a new handle to the resource referenced by `term`
+    ///
+    /// Fails with `BadArg` if the term is not a resource of type `T`.
+    pub fn from_term(env: *mut ErlNifEnv, term: Term) -> NifResult<Self> {
+        Self::from_term_in(default_resource_manager(), env, term)
+    }
+
+    /// Get a new handle to the resource referenced by `term` using a specific manager
+    pub fn from_term_in(
+        manager: &'static dyn ResourceManager,
+        env: *mut ErlNifEnv,
+        term: Term,
+    ) -> NifResult<Self> {
+        let obj = manager.get_resource(env, term.raw() as ERL_NIF_TERM, T::resource_type())?;
+        let ptr = NonNull::new(obj as *mut T).ok_or(NifError::BadArg)?;
+        manager.keep_resource(obj)?;
+
+        Ok(Self { ptr, manager })
+    }
+
+    /// Create an Erlang term referencing this resource
+    pub fn make_term(&self, env: *mut ErlNifEnv) -> NifResult<Term<'_>> {
+        let raw = self.manager.make_resource(env, self.as_ptr())?;
+        Ok(Term::from_raw(raw))
+    }
+
+    /// The manager this handle was created with
+    pub(crate) fn manager(&self) -> &'static dyn ResourceManager {
+        self.manager
+    }
+
+    /// Get the raw resource pointer (as seen by the enif_* API)
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.ptr.as_ptr() as *mut c_void
+    }
+
+    /// Check whether two handles refer to the same resource
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        a.ptr == b.ptr
+    }
+}
+
+impl<T: Resource> Clone for ResourceArc<T> {
+    fn clone(&self) -> Self {
+        let kept = self.manager.keep_resource(self.as_ptr());
+        debug_assert!(kept.is_ok(), "keep_resource failed on a live resource");
+
+        Self {
+            ptr: self.ptr,
+            manager: self.manager,
+        }
+    }
+}
+
+impl<T: Resource> Drop for ResourceArc<T> {
+    fn drop(&mut self) {
+        let released = self.manager.release_resource(self.as_ptr());
+        crate::contracts::require(released.is_ok(), "ResourceArc released a resource with no references left");
+    }
+}
+
+impl<T: Resource> Deref for ResourceArc<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T: Resource + fmt::Debug> fmt::Debug for ResourceArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ResourceArc").field(&**self).finish()
+    }
+}
+
+// Safety: `Resource` requires `Send + Sync`, and the VM's reference
+// counting is thread-safe
+unsafe impl<T: Resource> Send for ResourceArc<T> {}
+unsafe impl<T: Resource> Sync for ResourceArc<T> {}
+
+/// One counted reference to an untyped resource
+///
+/// For raw pointers from `create_resource!`/`get_resource!`: `new` calls
+/// keep, `Drop` calls release, so a pointer moved into a background task
+/// keeps the resource alive exactly as long as the guard.
+pub struct ResourceGuard {
+    ptr: NonNull<c_void>,
+    manager: &'static dyn ResourceManager,
+}
+
+impl ResourceGuard {
+    /// Take a new reference to `resource`
+    pub fn new(resource: *mut c_void) -> NifResult<Self> {
+        Self::new_in(default_resource_manager(), resource)
+    }
+
+    /// Take a new reference to `resource` using a specific manager
+    pub fn new_in(manager: &'static dyn ResourceManager, resource: *mut c_void) -> NifResult<Self> {
+        let ptr = NonNull::new(resource).ok_or(NifError::BadArg)?;
+        manager.keep_resource(resource)?;
+        Ok(Self { ptr, manager })
+    }
+
+    /// Take over a reference the caller already holds, such as the one
+    /// `create_resource!` returns
+    ///
+    /// # Safety
+    /// `resource` must be a live resource, and the caller must not release
+    /// the reference it hands over.
+    pub unsafe fn from_raw(manager: &'static dyn ResourceManager, resource: *mut c_void) -> NifResult<Self> {
+        let ptr = NonNull::new(resource).ok_or(NifError::BadArg)?;
+        Ok(Self { ptr, manager })
+    }
+
+    /// Give up the guard without releasing; the caller now owns the reference
+    pub fn into_raw(self) -> *mut c_void {
+        let ptr = self.as_ptr();
+        core::mem::forget(self);
+        ptr
+    }
+
+    /// Get the raw resource pointer (as seen by the enif_* API)
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.ptr.as_ptr()
+    }
+
+    /// Create an Erlang term referencing the resource
+    pub fn make_term(&self, env: *mut ErlNifEnv) -> NifResult<Term<'_>> {
+        let raw = self.manager.make_resource(env, self.as_ptr())?;
+        Ok(Term::from_raw(raw))
+    }
+
+    /// View the resource as a `T`
+    ///
+    /// # Safety
+    /// The resource must hold an initialized `T`, and nothing may mutate it
+    /// while the reference is in use.
+    pub unsafe fn as_ref<T>(&self) -> &T {
+        &*(self.ptr.as_ptr() as *const T)
+    }
+}
+
+impl Clone for ResourceGuard {
+    fn clone(&self) -> Self {
+        let kept = self.manager.keep_resource(self.as_ptr());
+        debug_assert!(kept.is_ok(), "keep_resource failed on a live resource");
+
+        Self {
+            ptr: self.ptr,
+            manager: self.manager,
+        }
+    }
+}
+
+impl Drop for ResourceGuard {
+    fn drop(&mut self) {
+        let released = self.manager.release_resource(self.as_ptr());
+        crate::contracts::require(released.is_ok(), "ResourceGuard released a resource with no references left");
+    }
+}
+
+impl fmt::Debug for ResourceGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ResourceGuard").field(&self.ptr).finish()
+    }
+}
+
+// Safety: the guard only carries the pointer; the VM's reference counting
+// is thread-safe, and reading through it is gated by `as_ref`'s contract
+unsafe impl Send for ResourceGuard {}
+unsafe impl Sync for ResourceGuard {}
+
 /// Register a new resource type with AtomVM
 /// 
+/// Generates `init_<name>(env)`, which creates the type and records it for
+/// `ResourceType::of::<$rust_type>()`. Without a destructor function the
+/// VM drops the value in place, running its `Drop` impl; pass one only
+/// when the cleanup needs to differ from `Drop`.
+///
 /// # Usage
 /// ```rust,ignore
 /// use avmnif_rs::resource_type;
 /// 
-/// resource_type!(DISPLAY_TYPE, DisplayContext, display_destructor);
+/// resource_type!(DISPLAY_TYPE, DisplayContext);
+/// resource_type!(RAW_BUFFER_TYPE, RawBuffer, raw_buffer_destructor);
 /// ```
 #[macro_export]
 macro_rules! resource_type {
     ($resource_name:ident, $rust_type:ty, $destructor_fn:ident) => {
-        // Create global static to hold the resource type pointer
-        static mut $resource_name: *mut $crate::resource::ErlNifResourceType = core::ptr::null_mut();
-        
-        // Create a module init function that registers this resource type
-        paste::paste! {
-            #[no_mangle]
-            pub extern "C" fn [<init_ $resource_name:lower>](env: *mut $crate::resource::ErlNifEnv) -> bool {
-                let resource_name_cstr = concat!(stringify!($resource_name), "\0");
-                let init_callbacks = $crate::resource::resource_type_init_with_dtor($destructor_fn);
-                let mut tried_flags = $crate::resource::ErlNifResourceFlags::ERL_NIF_RT_CREATE;
-                
-                unsafe {
-                    $resource_name = $crate::resource::enif_init_resource_type(
-                        env,
-                        resource_name_cstr.as_ptr() as *const core::ffi::c_char,
-                        &init_callbacks,
-                        $crate::resource::ErlNifResourceFlags::ERL_NIF_RT_CREATE,
-                        &mut tried_flags,
-                    );
-                    
-                    !$resource_name.is_null()
-                }
-            }
-            
-            // Provide a getter function for the resource type
-            #[no_mangle]
-            pub extern "C" fn [<get_ $resource_name:lower>]() -> *mut $crate::resource::ErlNifResourceType {
-                unsafe { $resource_name }
-            }
-        }
+        $crate::resource_type!(
+            @init $resource_name,
+            $rust_type,
+            $crate::resource::resource_type_init_with_dtor($destructor_fn)
+        );
     };
     
-    // Version without destructor
+    // Without a destructor function, the type's `Drop` impl runs
     ($resource_name:ident, $rust_type:ty) => {
-        // Create global static to hold the resource type pointer
-        static mut $resource_name: *mut $crate::resource::ErlNifResourceType = core::ptr::null_mut();
-        
+        $crate::resource_type!(
+            @init $resource_name,
+            $rust_type,
+            $crate::resource::resource_type_init_with_dtor($crate::resource::resource_dtor::<$rust_type>)
+        );
+    };
+
+    (@init $resource_name:ident, $rust_type:ty, $callbacks:expr) => {
         paste::paste! {
+            static [<$resource_name _INIT>]: $crate::registry::InitGuard = $crate::registry::InitGuard::new();
+
             #[no_mangle]
             pub extern "C" fn [<init_ $resource_name:lower>](env: *mut $crate::resource::ErlNifEnv) -> bool {
-                let resource_name_cstr = concat!(stringify!($resource_name), "\0");
-                let init_callbacks = $crate::resource::resource_type_init();
-                let mut tried_flags = $crate::resource::ErlNifResourceFlags::ERL_NIF_RT_CREATE;
-                
+                let init_callbacks = $callbacks;
                 unsafe {
-                    $resource_name = $crate::resource::enif_init_resource_type(
+                    $crate::resource::init_resource_type_once::<$rust_type>(
+                        &[<$resource_name _INIT>],
                         env,
-                        resource_name_cstr.as_ptr() as *const core::ffi::c_char,
+                        concat!(stringify!($resource_name), "\0"),
                         &init_callbacks,
-                        $crate::resource::ErlNifResourceFlags::ERL_NIF_RT_CREATE,
-                        &mut tried_flags,
-                    );
-                    
-                    !$resource_name.is_null()
+                    )
                 }
             }
-            
-            #[no_mangle]
-            pub extern "C" fn [<get_ $resource_name:lower>]() -> *mut $crate::resource::ErlNifResourceType {
-                unsafe { $resource_name }
-            }
         }
     };
 }
 
 /// Create a new resource instance
 /// 
+/// Allocates a resource of the type registered for `$rust_type` and moves
+/// the data into it, returning the raw pointer. Fails with `BadArg` if the
+/// type's init function has not run.
+///
 /// # Usage
 /// ```rust,ignore
 /// use avmnif_rs::create_resource;
 /// 
-/// let display_ptr = create_resource!(display_type, DisplayContext {
+/// let display_ptr = create_resource!(DisplayContext, DisplayContext {
 ///     width: 240,
 ///     height: 320,
 ///     initialized: true,
@@ -647,28 +937,9 @@ macro_rules! resource_type {
 /// ```
 #[macro_export]
 macro_rules! create_resource {
-    ($type_var:ident, $data:expr) => {{
-        let data = $data;
-        let size = core::mem::size_of_val(&data) as core::ffi::c_uint;
-        let ptr = unsafe {
-            paste::paste! {
-                extern "C" {
-                    fn [<get_ $type_var:lower>]() -> *mut $crate::resource::ErlNifResourceType;
-                }
-                let resource_type = [<get_ $type_var:lower>]();
-                $crate::resource::enif_alloc_resource(resource_type, size)
-            }
-        };
-        if ptr.is_null() {
-            Err($crate::term::NifError::OutOfMemory)
-        } else {
-            // Write the data to the allocated resource
-            unsafe {
-                core::ptr::write(ptr as *mut _, data);
-            }
-            Ok(ptr)
-        }
-    }};
+    ($rust_type:ty, $data:expr) => {
+        $crate::resource::create_registered::<$rust_type>($data)
+    };
 }
 
 /// Extract a resource from an Erlang term
@@ -677,34 +948,16 @@ macro_rules! create_resource {
 /// ```rust,ignore
 /// use avmnif_rs::get_resource;
 /// 
-/// let display = get_resource!(env, args[0], display_type)?;
+/// let display = get_resource!(env, args[0], DisplayContext)?;
 /// display.width = 320;
 /// ```
 #[macro_export]
 macro_rules! get_resource {
-    ($env:expr, $term:expr, $type_var:ident) => {{
-        let mut ptr: *mut core::ffi::c_void = core::ptr::null_mut();
-        let success = unsafe {
-            paste::paste! {
-                extern "C" {
-                    fn [<get_ $type_var:lower>]() -> *mut $crate::resource::ErlNifResourceType;
-                }
-                let resource_type = [<get_ $type_var:lower>]();
-                $crate::resource::enif_get_resource(
-                    $env.as_c_ptr(),
-                    $term.as_raw(),
-                    resource_type,
-                    &mut ptr as *mut *mut core::ffi::c_void,
-                )
-            }
-        };
-        if success != 0 && !ptr.is_null() {
-            // SAFETY: Resource type system ensures this cast is valid
-            Ok(unsafe { &mut *(ptr as *mut _) })
-        } else {
-            Err($crate::term::NifError::BadArg)
-        }
-    }};
+    ($env:expr, $term:expr, $rust_type:ty) => {
+        $crate::resource::get_registered::<$rust_type>($env, $term)
+            // SAFETY: the registry only returns resources of `$rust_type`'s type
+            .map(|ptr| unsafe { &mut *ptr })
+    };
 }
 
 /// Convert a resource pointer to an Erlang term
@@ -726,4 +979,33 @@ macro_rules! make_resource_term {
         };
         $crate::term::Term::from_raw(raw_term)
     }};
-}
\ No newline at end of file
+}
+
+/// Register a Rust type as a typed resource usable with `ResourceArc`
+///
+/// Generates the same `init_<name>` function as `resource_type!`,
+/// registering `resource_dtor::<T>` as the destructor so the type's `Drop`
+/// impl runs when the VM frees the resource.
+///
+/// # Usage
+/// ```rust,ignore
+/// use avmnif_rs::impl_resource;
+/// use avmnif_rs::resource::ResourceArc;
+///
+/// impl_resource!(DISPLAY_TYPE, DisplayContext);
+///
+/// let display = ResourceArc::new(DisplayContext::new())?;
+/// let term = display.make_term(env)?;
+/// ```
+#[macro_export]
+macro_rules! impl_resource {
+    ($resource_name:ident, $rust_type:ty) => {
+        impl $crate::resource::Resource for $rust_type {}
+
+        $crate::resource_type!(
+            @init $resource_name,
+            $rust_type,
+            $crate::resource::resource_type_init_with_dtor($crate::resource::resource_dtor::<$rust_type>)
+        );
+    };
+}
@@ -0,0 +1,194 @@
+//! Resources that clean up after their owning process
+//!
+//! A resource holding a file handle or a bus lock should let go of it when
+//! the process using it dies, not whenever the garbage collector gets to
+//! the last term. `ResourceMonitor<T>` wraps the value with a monitor on
+//! its owner: `monitor_owner` starts it, and when the owner goes down the
+//! VM's `down` callback runs the driver's `on_owner_down(&mut T)` once.
+//! The value itself lives on until the resource is destroyed, so NIFs
+//! still holding the term see the released state rather than a dangling
+//! pointer.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::resource_monitor::ResourceMonitor;
+//!
+//! struct BusLock { bus: u8, held: bool }
+//!
+//! impl_resource_monitor!(BUS_LOCK_TYPE, BusLock);
+//!
+//! // bus:lock/1
+//! let lock = ResourceArc::new(ResourceMonitor::new(BusLock::acquire(bus)?, |lock| lock.release()))?;
+//! lock.monitor_owner(env, caller)?;
+//! lock.make_term(env)
+//!
+//! // bus:transfer/2
+//! lock.with(|lock| if lock.held { ... } else { Err(NifError::BadArg) })
+//! ```
+
+use crate::resource::{ErlNifEnv, ErlNifMonitor, ErlNifPid, ErlNifResourceType, Resource, ResourceArc};
+use crate::sync::atomic::{AtomicBool, Ordering};
+use crate::sync::SpinLock;
+use crate::term::{NifError, NifResult};
+use core::ffi::c_void;
+
+/// Resource type of `ResourceMonitor<Self>`, implemented by `impl_resource_monitor!`
+pub trait MonitoredType {
+    fn resource_type() -> *mut ErlNifResourceType;
+}
+
+/// The VM's monitor, kept in a `Send` form
+#[derive(Debug, Clone, Copy)]
+struct Owner {
+    pid: u32,
+    resource_type: usize,
+    ref_ticks: u64,
+}
+
+impl Owner {
+    fn monitor(&self) -> ErlNifMonitor {
+        ErlNifMonitor {
+            resource_type: self.resource_type as *mut ErlNifResourceType,
+            ref_ticks: self.ref_ticks,
+        }
+    }
+}
+
+/// A resource value that is released when its owner goes down
+pub struct ResourceMonitor<T: Send + 'static> {
+    value: SpinLock<T>,
+    on_owner_down: fn(&mut T),
+    owner: SpinLock<Option<Owner>>,
+    released: AtomicBool,
+}
+
+impl<T: Send + 'static> ResourceMonitor<T> {
+    pub fn new(value: T, on_owner_down: fn(&mut T)) -> Self {
+        Self {
+            value: SpinLock::new(value),
+            on_owner_down,
+            owner: SpinLock::new(None),
+            released: AtomicBool::new(false),
+        }
+    }
+
+    /// Run `f` with the value
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        self.value.with(f)
+    }
+
+    /// The monitored owner, if any
+    pub fn owner(&self) -> Option<u32> {
+        self.owner.with(|owner| owner.map(|owner| owner.pid))
+    }
+
+    /// `on_owner_down` has run
+    pub fn is_released(&self) -> bool {
+        self.released.load(Ordering::Acquire)
+    }
+
+    /// React to `pid` going down; runs `on_owner_down` if it was the owner
+    ///
+    /// Called by the `down` callback. Returns true if the value was released.
+    pub fn owner_down(&self, pid: u32) -> bool {
+        let was_owner = self.owner.with(|owner| match owner {
+            Some(current) if current.pid == pid => owner.take().is_some(),
+            _ => false,
+        });
+        if !was_owner || self.released.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+        self.value.with(self.on_owner_down);
+        true
+    }
+}
+
+impl<T: Send + MonitoredType> Resource for ResourceMonitor<T> {
+    fn resource_type() -> *mut ErlNifResourceType {
+        T::resource_type()
+    }
+}
+
+impl<T: Send + MonitoredType> ResourceArc<ResourceMonitor<T>> {
+    /// Monitor `pid` as the owner, replacing the previous owner's monitor
+    ///
+    /// Fails with `BadArg` once the value was released, and if `pid` is
+    /// not alive.
+    pub fn monitor_owner(&self, env: *mut ErlNifEnv, pid: u32) -> NifResult<()> {
+        if self.is_released() {
+            return Err(NifError::BadArg);
+        }
+        let target = pid as ErlNifPid;
+        let mut mon = ErlNifMonitor {
+            resource_type: core::ptr::null_mut(),
+            ref_ticks: 0,
+        };
+        self.manager().monitor_process(env, self.as_ptr(), &target, &mut mon)?;
+        let owner = Owner {
+            pid,
+            resource_type: mon.resource_type as usize,
+            ref_ticks: mon.ref_ticks,
+        };
+        if let Some(previous) = self.owner.with(|current| current.replace(owner)) {
+            let _ = self.manager().demonitor_process(env, self.as_ptr(), &previous.monitor());
+        }
+        Ok(())
+    }
+
+    /// Stop monitoring the owner, without releasing the value
+    pub fn demonitor_owner(&self, env: *mut ErlNifEnv) -> NifResult<()> {
+        match self.owner.with(Option::take) {
+            Some(owner) => {
+                self.manager().demonitor_process(env, self.as_ptr(), &owner.monitor())?;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Down callback registered by `impl_resource_monitor!`
+///
+/// # Safety
+/// Must only be called by the VM (or a mock manager) with `obj` pointing to
+/// a live `ResourceMonitor<T>`.
+pub unsafe extern "C" fn resource_monitor_down<T: Send + 'static>(
+    _env: *mut ErlNifEnv,
+    obj: *mut c_void,
+    pid: *mut ErlNifPid,
+    _mon: *mut ErlNifMonitor,
+) {
+    if let (Some(resource), Some(pid)) = ((obj as *const ResourceMonitor<T>).as_ref(), pid.as_ref()) {
+        resource.owner_down(*pid as u32);
+    }
+}
+
+/// Register `ResourceMonitor<$rust_type>` as a resource type
+///
+/// Generates `init_<name>` like `impl_resource!`, with the
+/// `down` callback that runs `on_owner_down`.
+///
+/// ```rust,ignore
+/// impl_resource_monitor!(BUS_LOCK_TYPE, BusLock);
+/// ```
+#[macro_export]
+macro_rules! impl_resource_monitor {
+    ($resource_name:ident, $rust_type:ty) => {
+        impl $crate::resource_monitor::MonitoredType for $rust_type {
+            fn resource_type() -> *mut $crate::resource::ErlNifResourceType {
+                $crate::resource::ResourceType::ptr_of::<$crate::resource_monitor::ResourceMonitor<$rust_type>>()
+            }
+        }
+
+        $crate::resource_type!(
+            @init $resource_name,
+            $crate::resource_monitor::ResourceMonitor<$rust_type>,
+            $crate::resource::resource_type_init_full(
+                Some($crate::resource::resource_dtor::<$crate::resource_monitor::ResourceMonitor<$rust_type>>),
+                None,
+                Some($crate::resource_monitor::resource_monitor_down::<$rust_type>),
+            )
+        );
+    };
+}
@@ -0,0 +1,105 @@
+//! Raspberry Pi Pico (RP2040) support
+//!
+//! AtomVM's Pico port is built with pico-sdk for `thumbv6m-none-eabi`. Two
+//! things differ from the other embedded targets, and the `rp2040` feature
+//! takes care of both:
+//!
+//! - Cortex-M0+ has no compare-and-swap. The crate's atomics (init guards,
+//!   spin locks, counters) go through `portable-atomic`, which runs them in
+//!   a critical section. This module provides that critical section: it
+//!   masks interrupts on the calling core and takes SIO hardware spinlock
+//!   `SPINLOCK_ID` against the other core.
+//! - pico-sdk's linker script has no `.nif_collection` or
+//!   `.port_collection` output section. Registration records go in
+//!   `.init_array`, which the SDK runtime runs before `main`, as it does for
+//!   the constructors of AtomVM's C `REGISTER_*` macros.
+//!
+//! Build the static library with
+//!
+//! ```text
+//! cargo build --release --target thumbv6m-none-eabi --features rp2040
+//! ```
+//!
+//! and link it into the AtomVM Pico build (`src/platforms/rp2`), for
+//! example with `target_link_libraries(AtomVM PRIVATE ${RUST_LIB})`.
+//!
+//! Firmware that already has a critical-section implementation, such as
+//! rp2040-hal's `critical-section-impl`, or that claims spinlock 31 for
+//! something else, cannot use this feature as is.
+
+/// SIO spinlock taken by the critical section
+///
+/// The same one rp2040-hal uses; pico-sdk only hands out spinlocks from 24
+/// up through `spin_lock_claim_unused`, so claim it at startup if the
+/// firmware does that.
+pub const SPINLOCK_ID: usize = 31;
+
+/// Address of SIO spinlock `id`: reading claims it (zero if taken), writing releases it
+pub const fn spinlock_address(id: usize) -> usize {
+    0xD000_0100 + 4 * id
+}
+
+/// SIO register holding the number of the core running the code
+pub const CPUID_ADDRESS: usize = 0xD000_0000;
+
+#[cfg(target_arch = "arm")]
+mod critical {
+    use super::{spinlock_address, CPUID_ADDRESS, SPINLOCK_ID};
+    use core::sync::atomic::{compiler_fence, AtomicU8, Ordering};
+
+    // Core holding the lock plus one, 0 when free; only read and written under the lock
+    static OWNER: AtomicU8 = AtomicU8::new(0);
+
+    // Restore states
+    const NESTED: u8 = 0;
+    const TAKEN_INTERRUPTS_ON: u8 = 1;
+    const TAKEN_INTERRUPTS_OFF: u8 = 2;
+
+    struct SpinlockCriticalSection;
+    critical_section::set_impl!(SpinlockCriticalSection);
+
+    fn interrupts_enabled() -> bool {
+        let primask: u32;
+        unsafe { core::arch::asm!("mrs {}, PRIMASK", out(reg) primask, options(nomem, nostack, preserves_flags)) };
+        primask & 1 == 0
+    }
+
+    unsafe impl critical_section::Impl for SpinlockCriticalSection {
+        unsafe fn acquire() -> u8 {
+            let core = (CPUID_ADDRESS as *const u32).read_volatile() as u8 + 1;
+            if OWNER.load(Ordering::Relaxed) == core {
+                return NESTED;
+            }
+            let enabled = interrupts_enabled();
+            loop {
+                core::arch::asm!("cpsid i", options(nomem, nostack, preserves_flags));
+                compiler_fence(Ordering::SeqCst);
+                if (spinlock_address(SPINLOCK_ID) as *const u32).read_volatile() != 0 {
+                    break;
+                }
+                // Let interrupts in while the other core holds the lock
+                if enabled {
+                    core::arch::asm!("cpsie i", options(nomem, nostack, preserves_flags));
+                }
+            }
+            OWNER.store(core, Ordering::Relaxed);
+            if enabled {
+                TAKEN_INTERRUPTS_ON
+            } else {
+                TAKEN_INTERRUPTS_OFF
+            }
+        }
+
+        unsafe fn release(state: u8) {
+            if state == NESTED {
+                return;
+            }
+            OWNER.store(0, Ordering::Relaxed);
+            compiler_fence(Ordering::SeqCst);
+            (spinlock_address(SPINLOCK_ID) as *mut u32).write_volatile(1);
+            if state == TAKEN_INTERRUPTS_ON {
+                core::arch::asm!("cpsie i", options(nomem, nostack, preserves_flags));
+            }
+        }
+    }
+}
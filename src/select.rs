@@ -0,0 +1,251 @@
+//! Asynchronous I/O on file descriptors
+//!
+//! `enif_select` asks the VM to watch a file descriptor and send
+//! `{select, Resource, Ref, ready_input | ready_output}` to a process when
+//! it becomes readable or writable. The descriptor must stay open until the
+//! VM has stopped watching it, which it reports through the resource
+//! type's stop callback.
+//!
+//! `Selectable<F>` is a resource that owns a descriptor and handles that
+//! lifecycle: `select` arms one notification with a fresh `SelectRef`,
+//! `stop` asks the VM to let go, and `SelectableFd::close` runs exactly
+//! once, from the stop callback or when the resource is destroyed without
+//! ever being stopped. Drivers never call `enif_select` themselves.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::select::{self, Interest, Selectable, SelectableFd};
+//!
+//! struct Uart { fd: i32 }
+//!
+//! impl SelectableFd for Uart {
+//!     fn event(&self) -> ErlNifEvent { self.fd }
+//!     fn close(self) { unsafe { close(self.fd) }; }
+//! }
+//!
+//! impl_selectable!(UART_TYPE, Uart);
+//!
+//! // uart:open/1
+//! let uart = ResourceArc::new(Selectable::new(Uart { fd }))?;
+//! let pending = uart.select(env, Interest::Read, caller)?;
+//!
+//! // {select, _, Ref, ready_input} in the caller
+//! if select::match_select(&message, &table) == Some((pending, Interest::Read)) { ... }
+//!
+//! // uart:close/1
+//! uart.stop(env)?;
+//! ```
+
+use crate::atom::AtomTableOps;
+use crate::resource::{
+    ErlNifEnv, ErlNifEvent, ErlNifResourceType, ErlNifSelectFlags, Resource, ResourceArc, ERL_NIF_TERM,
+};
+use crate::sync::SpinLock;
+use crate::term::{NifError, NifResult, Term, TermValue};
+use core::ffi::{c_int, c_void};
+use crate::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// A file descriptor (or other pollable event) owned by a `Selectable`
+pub trait SelectableFd: Send + 'static {
+    /// The event handed to `enif_select`
+    fn event(&self) -> ErlNifEvent;
+
+    /// Release the descriptor; called once the VM no longer watches it
+    fn close(self);
+}
+
+/// Resource type of `Selectable<Self>`, implemented by `impl_selectable!`
+pub trait SelectableType {
+    fn resource_type() -> *mut ErlNifResourceType;
+}
+
+/// Readiness to wait for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interest {
+    Read,
+    Write,
+}
+
+impl Interest {
+    fn mode(self) -> ErlNifSelectFlags {
+        match self {
+            Interest::Read => ErlNifSelectFlags::ERL_NIF_SELECT_READ,
+            Interest::Write => ErlNifSelectFlags::ERL_NIF_SELECT_WRITE,
+        }
+    }
+}
+
+/// Identifies one `select` call in the notification it triggers
+///
+/// Sent as a small integer, so stale notifications from an earlier select
+/// can be told apart from the pending one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectRef(pub u32);
+
+static NEXT_SELECT_REF: AtomicU32 = AtomicU32::new(1);
+
+impl SelectRef {
+    /// A reference not handed out before (until the 27-bit counter wraps)
+    pub fn next() -> Self {
+        SelectRef(NEXT_SELECT_REF.fetch_add(1, Ordering::Relaxed) & 0x07FF_FFFF)
+    }
+
+    fn raw(self) -> NifResult<ERL_NIF_TERM> {
+        Ok(Term::encode_small_int(self.0 as i32)?.raw() as ERL_NIF_TERM)
+    }
+}
+
+/// A resource owning a descriptor that can be selected on
+pub struct Selectable<F: SelectableFd> {
+    fd: SpinLock<Option<F>>,
+    event: ErlNifEvent,
+    stopping: AtomicBool,
+}
+
+impl<F: SelectableFd> Selectable<F> {
+    pub fn new(fd: F) -> Self {
+        Self {
+            event: fd.event(),
+            fd: SpinLock::new(Some(fd)),
+            stopping: AtomicBool::new(false),
+        }
+    }
+
+    /// The descriptor's event, even after it was closed
+    pub fn event(&self) -> ErlNifEvent {
+        self.event
+    }
+
+    /// Run `f` on the descriptor; `None` once it was closed
+    pub fn with_fd<R>(&self, f: impl FnOnce(&mut F) -> R) -> Option<R> {
+        self.fd.with(|fd| fd.as_mut().map(f))
+    }
+
+    /// `stop` was requested; no more selects are accepted
+    pub fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::Acquire)
+    }
+
+    /// The descriptor has been closed
+    pub fn is_closed(&self) -> bool {
+        self.fd.with(|fd| fd.is_none())
+    }
+
+    fn close(&self) {
+        if let Some(fd) = self.fd.with(Option::take) {
+            fd.close();
+        }
+    }
+}
+
+impl<F: SelectableFd> Drop for Selectable<F> {
+    fn drop(&mut self) {
+        // Never stopped, so never selected or already let go by the VM
+        self.close();
+    }
+}
+
+impl<F: SelectableFd + SelectableType> Resource for Selectable<F> {
+    fn resource_type() -> *mut ErlNifResourceType {
+        F::resource_type()
+    }
+}
+
+impl<F: SelectableFd + SelectableType> ResourceArc<Selectable<F>> {
+    /// Ask for one `{select, Resource, Ref, ready_input | ready_output}` to `pid`
+    ///
+    /// The notification fires once; select again to keep watching.
+    pub fn select(&self, env: *mut ErlNifEnv, interest: Interest, pid: u32) -> NifResult<SelectRef> {
+        if self.is_stopping() {
+            return Err(NifError::BadArg);
+        }
+        let reference = SelectRef::next();
+        let pid = pid as i32;
+        self.manager()
+            .select(env, self.event(), interest.mode(), self.as_ptr(), &pid, reference.raw()?)?;
+        Ok(reference)
+    }
+
+    /// Stop watching the descriptor and close it
+    ///
+    /// The VM calls the stop callback, which closes the descriptor, either
+    /// right away or once it has finished with it. Stopping twice is a no-op.
+    pub fn stop(&self, env: *mut ErlNifEnv) -> NifResult<()> {
+        if self.stopping.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+        let no_pid = 0;
+        self.manager().select(
+            env,
+            self.event(),
+            ErlNifSelectFlags::ERL_NIF_SELECT_STOP,
+            self.as_ptr(),
+            &no_pid,
+            0,
+        )?;
+        Ok(())
+    }
+}
+
+/// Stop callback registered by `impl_selectable!`
+///
+/// # Safety
+/// Must only be called by the VM (or a mock manager) with `obj` pointing to
+/// a live `Selectable<F>`.
+pub unsafe extern "C" fn selectable_stop<F: SelectableFd>(
+    _env: *mut ErlNifEnv,
+    obj: *mut c_void,
+    _event: ErlNifEvent,
+    _is_direct_call: c_int,
+) {
+    if let Some(selectable) = (obj as *const Selectable<F>).as_ref() {
+        selectable.close();
+    }
+}
+
+/// Recognize `{select, Resource, Ref, ready_input | ready_output}`
+pub fn match_select<T: AtomTableOps>(message: &TermValue, table: &T) -> Option<(SelectRef, Interest)> {
+    match message.as_tuple() {
+        Some([tag, _resource, TermValue::SmallInt(reference), ready]) if tag.is_atom_str("select", table) => {
+            let interest = if ready.is_atom_str("ready_input", table) {
+                Interest::Read
+            } else if ready.is_atom_str("ready_output", table) {
+                Interest::Write
+            } else {
+                return None;
+            };
+            Some((SelectRef(*reference as u32), interest))
+        }
+        _ => None,
+    }
+}
+
+/// Register `Selectable<$fd_type>` as a resource type
+///
+/// Generates `init_<name>` like `impl_resource!`, with the
+/// stop callback that closes the descriptor.
+///
+/// ```rust,ignore
+/// impl_selectable!(UART_TYPE, Uart);
+/// ```
+#[macro_export]
+macro_rules! impl_selectable {
+    ($resource_name:ident, $fd_type:ty) => {
+        impl $crate::select::SelectableType for $fd_type {
+            fn resource_type() -> *mut $crate::resource::ErlNifResourceType {
+                $crate::resource::ResourceType::ptr_of::<$crate::select::Selectable<$fd_type>>()
+            }
+        }
+
+        $crate::resource_type!(
+            @init $resource_name,
+            $crate::select::Selectable<$fd_type>,
+            $crate::resource::resource_type_init_full(
+                Some($crate::resource::resource_dtor::<$crate::select::Selectable<$fd_type>>),
+                Some($crate::select::selectable_stop::<$fd_type>),
+                None,
+            )
+        );
+    };
+}
@@ -0,0 +1,770 @@
+//! serde support: any `Serialize`/`Deserialize` type to and from terms
+//!
+//! `to_term` and `from_term` convert through `TermValue`, so types that
+//! already derive serde's traits cross the NIF boundary without a
+//! `TaggedMap` impl. Errors are `TaggedError`s, the same as tagged maps.
+//!
+//! The data model maps onto Erlang terms as:
+//!
+//! | serde | Erlang |
+//! |-------|--------|
+//! | `bool` | `true` / `false` |
+//! | integers, `char` | integer (must fit a small int) |
+//! | floats | float |
+//! | `str`, bytes | binary |
+//! | `None` / `Some(v)` | `nil` / `v` |
+//! | unit | `{}` |
+//! | unit struct | atom of the struct name |
+//! | newtype struct | the inner value |
+//! | sequence | list |
+//! | tuple, tuple struct | tuple |
+//! | map | map |
+//! | struct | map with atom keys |
+//! | unit variant | atom |
+//! | newtype variant | `{variant, v}` |
+//! | tuple variant | `{variant, v1, v2, ...}` |
+//! | struct variant | `{variant, #{field => v, ...}}` |
+//!
+//! Names are used as serde gives them; `#[serde(rename_all = "snake_case")]`
+//! makes Rust names into conventional atoms. Strings decode from binaries,
+//! atoms, charlists and iodata.
+//!
+//! # Examples
+//!
+//! ```
+//! # use avmnif_rs::{tagged::TaggedError, term::NifError};
+//! # #[derive(Debug)]
+//! # enum Error { Nif(NifError), Tagged(TaggedError) }
+//! # impl From<NifError> for Error { fn from(e: NifError) -> Self { Error::Nif(e) } }
+//! # impl From<TaggedError> for Error { fn from(e: TaggedError) -> Self { Error::Tagged(e) } }
+//! # #[cfg(feature = "sim")] {
+//! use serde::{Deserialize, Serialize};
+//! # use avmnif_rs::parse::parse_term;
+//! # use avmnif_rs::term::TermValue;
+//! # use avmnif_rs::testing::mocks::MockHeap;
+//! # let table = avmnif_rs::testing::mocks::MockAtomTable::new();
+//! # let mut heap = MockHeap::new(32);
+//! # let args = [heap.encode(parse_term("#{rate => 9600, channels => [1, 2]}", &table).unwrap()).unwrap()];
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config { rate: u32, channels: Vec<u8> }
+//!
+//! let config: Config = avmnif_rs::serde::from_term(&args[0].to_value()?, &table)?;
+//! let reply = avmnif_rs::serde::to_term(&config, &table)?;
+//! # assert_eq!(reply, args[0].to_value()?);
+//! # }
+//! # Ok::<(), Error>(())
+//! ```
+
+extern crate alloc;
+
+use crate::atom::AtomTableOps;
+use crate::tagged::{atom_name, get_type_atom, TaggedError, TaggedField, TaggedResult};
+use crate::term::TermValue;
+use alloc::{string::String, string::ToString, vec::Vec};
+use core::fmt;
+use serde::de::{self, DeserializeSeed, Visitor};
+use serde::ser::{self, Serialize};
+
+impl ser::Error for TaggedError {
+    fn custom<M: fmt::Display>(msg: M) -> Self {
+        TaggedError::Other(msg.to_string())
+    }
+}
+
+impl de::Error for TaggedError {
+    fn custom<M: fmt::Display>(msg: M) -> Self {
+        TaggedError::Other(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        TaggedError::missing_field(field)
+    }
+}
+
+impl de::StdError for TaggedError {}
+
+/// Serialize `value` into a term, creating atoms in `table`
+pub fn to_term<S: Serialize + ?Sized, T: AtomTableOps>(value: &S, table: &T) -> TaggedResult<TermValue> {
+    value.serialize(Serializer { table })
+}
+
+/// Deserialize a `D` from `value`, resolving atoms through `table`
+///
+/// Strings and bytes can borrow from `value`'s binaries.
+pub fn from_term<'de, D: de::Deserialize<'de>, T: AtomTableOps>(value: &'de TermValue, table: &T) -> TaggedResult<D> {
+    D::deserialize(Deserializer { value, table })
+}
+
+// ── Serializer ─────────────────────────────────────────────────────────────
+
+/// serde `Serializer` producing a `TermValue`
+pub struct Serializer<'t, T> {
+    table: &'t T,
+}
+
+impl<'t, T: AtomTableOps> Serializer<'t, T> {
+    pub fn new(table: &'t T) -> Self {
+        Serializer { table }
+    }
+
+    fn atom(&self, name: &str) -> TaggedResult<TermValue> {
+        Ok(TermValue::Atom(get_type_atom(name, self.table)?))
+    }
+
+    fn int<I: TryInto<i64>>(&self, value: I) -> TaggedResult<TermValue> {
+        value.try_into().map(TermValue::integer).map_err(|_| TaggedError::OutOfRange("i64"))
+    }
+}
+
+impl<'t, T: AtomTableOps> ser::Serializer for Serializer<'t, T> {
+    type Ok = TermValue;
+    type Error = TaggedError;
+    type SerializeSeq = Sequence<'t, T>;
+    type SerializeTuple = Sequence<'t, T>;
+    type SerializeTupleStruct = Sequence<'t, T>;
+    type SerializeTupleVariant = Sequence<'t, T>;
+    type SerializeMap = Pairs<'t, T>;
+    type SerializeStruct = Pairs<'t, T>;
+    type SerializeStructVariant = Pairs<'t, T>;
+
+    fn serialize_bool(self, value: bool) -> TaggedResult<TermValue> {
+        self.atom(if value { "true" } else { "false" })
+    }
+
+    fn serialize_i8(self, value: i8) -> TaggedResult<TermValue> {
+        self.int(value)
+    }
+
+    fn serialize_i16(self, value: i16) -> TaggedResult<TermValue> {
+        self.int(value)
+    }
+
+    fn serialize_i32(self, value: i32) -> TaggedResult<TermValue> {
+        self.int(value)
+    }
+
+    fn serialize_i64(self, value: i64) -> TaggedResult<TermValue> {
+        self.int(value)
+    }
+
+    fn serialize_u8(self, value: u8) -> TaggedResult<TermValue> {
+        self.int(value)
+    }
+
+    fn serialize_u16(self, value: u16) -> TaggedResult<TermValue> {
+        self.int(value)
+    }
+
+    fn serialize_u32(self, value: u32) -> TaggedResult<TermValue> {
+        self.int(value)
+    }
+
+    fn serialize_u64(self, value: u64) -> TaggedResult<TermValue> {
+        self.int(value)
+    }
+
+    fn serialize_f32(self, value: f32) -> TaggedResult<TermValue> {
+        Ok(TermValue::Float(value as f64))
+    }
+
+    fn serialize_f64(self, value: f64) -> TaggedResult<TermValue> {
+        Ok(TermValue::Float(value))
+    }
+
+    fn serialize_char(self, value: char) -> TaggedResult<TermValue> {
+        self.int(value as u32)
+    }
+
+    fn serialize_str(self, value: &str) -> TaggedResult<TermValue> {
+        Ok(TermValue::Binary(value.as_bytes().to_vec()))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> TaggedResult<TermValue> {
+        Ok(TermValue::Binary(value.to_vec()))
+    }
+
+    fn serialize_none(self) -> TaggedResult<TermValue> {
+        self.atom("nil")
+    }
+
+    fn serialize_some<V: Serialize + ?Sized>(self, value: &V) -> TaggedResult<TermValue> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> TaggedResult<TermValue> {
+        Ok(TermValue::Tuple(Vec::new()))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> TaggedResult<TermValue> {
+        self.atom(name)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> TaggedResult<TermValue> {
+        self.atom(variant)
+    }
+
+    fn serialize_newtype_struct<V: Serialize + ?Sized>(self, _name: &'static str, value: &V) -> TaggedResult<TermValue> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<V: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &V,
+    ) -> TaggedResult<TermValue> {
+        Ok(TermValue::Tuple(alloc::vec![self.atom(variant)?, value.serialize(self)?]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> TaggedResult<Sequence<'t, T>> {
+        Ok(Sequence::new(self, len.unwrap_or(0), SequenceKind::List))
+    }
+
+    fn serialize_tuple(self, len: usize) -> TaggedResult<Sequence<'t, T>> {
+        Ok(Sequence::new(self, len, SequenceKind::Tuple))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> TaggedResult<Sequence<'t, T>> {
+        Ok(Sequence::new(self, len, SequenceKind::Tuple))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> TaggedResult<Sequence<'t, T>> {
+        let mut sequence = Sequence::new(self, len + 1, SequenceKind::Tuple);
+        sequence.elements.push(self.atom(variant)?);
+        Ok(sequence)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> TaggedResult<Pairs<'t, T>> {
+        Ok(Pairs::new(self, len.unwrap_or(0), None))
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> TaggedResult<Pairs<'t, T>> {
+        Ok(Pairs::new(self, len, None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> TaggedResult<Pairs<'t, T>> {
+        let variant = self.atom(variant)?;
+        Ok(Pairs::new(self, len, Some(variant)))
+    }
+
+    fn collect_str<V: fmt::Display + ?Sized>(self, value: &V) -> TaggedResult<TermValue> {
+        self.serialize_str(&value.to_string())
+    }
+}
+
+// Not derived: the table type need not be Clone
+impl<T> Clone for Serializer<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Serializer<'_, T> {}
+
+enum SequenceKind {
+    List,
+    Tuple,
+}
+
+/// Elements of a list, tuple or tuple variant being serialized
+pub struct Sequence<'t, T> {
+    serializer: Serializer<'t, T>,
+    elements: Vec<TermValue>,
+    kind: SequenceKind,
+}
+
+impl<'t, T: AtomTableOps> Sequence<'t, T> {
+    fn new(serializer: Serializer<'t, T>, len: usize, kind: SequenceKind) -> Self {
+        Sequence { serializer, elements: Vec::with_capacity(len), kind }
+    }
+
+    fn push<V: Serialize + ?Sized>(&mut self, value: &V) -> TaggedResult<()> {
+        self.elements.push(value.serialize(self.serializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> TaggedResult<TermValue> {
+        Ok(match self.kind {
+            SequenceKind::List => TermValue::list(self.elements),
+            SequenceKind::Tuple => TermValue::Tuple(self.elements),
+        })
+    }
+}
+
+impl<T: AtomTableOps> ser::SerializeSeq for Sequence<'_, T> {
+    type Ok = TermValue;
+    type Error = TaggedError;
+
+    fn serialize_element<V: Serialize + ?Sized>(&mut self, value: &V) -> TaggedResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> TaggedResult<TermValue> {
+        self.finish()
+    }
+}
+
+impl<T: AtomTableOps> ser::SerializeTuple for Sequence<'_, T> {
+    type Ok = TermValue;
+    type Error = TaggedError;
+
+    fn serialize_element<V: Serialize + ?Sized>(&mut self, value: &V) -> TaggedResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> TaggedResult<TermValue> {
+        self.finish()
+    }
+}
+
+impl<T: AtomTableOps> ser::SerializeTupleStruct for Sequence<'_, T> {
+    type Ok = TermValue;
+    type Error = TaggedError;
+
+    fn serialize_field<V: Serialize + ?Sized>(&mut self, value: &V) -> TaggedResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> TaggedResult<TermValue> {
+        self.finish()
+    }
+}
+
+impl<T: AtomTableOps> ser::SerializeTupleVariant for Sequence<'_, T> {
+    type Ok = TermValue;
+    type Error = TaggedError;
+
+    fn serialize_field<V: Serialize + ?Sized>(&mut self, value: &V) -> TaggedResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> TaggedResult<TermValue> {
+        self.finish()
+    }
+}
+
+/// Pairs of a map, struct or struct variant being serialized
+pub struct Pairs<'t, T> {
+    serializer: Serializer<'t, T>,
+    pairs: Vec<(TermValue, TermValue)>,
+    key: Option<TermValue>,
+    variant: Option<TermValue>,
+}
+
+impl<'t, T: AtomTableOps> Pairs<'t, T> {
+    fn new(serializer: Serializer<'t, T>, len: usize, variant: Option<TermValue>) -> Self {
+        Pairs { serializer, pairs: Vec::with_capacity(len), key: None, variant }
+    }
+
+    fn field<V: Serialize + ?Sized>(&mut self, name: &'static str, value: &V) -> TaggedResult<()> {
+        let value = value.serialize(self.serializer).map_err(|error| TaggedError::nested(name, error))?;
+        self.pairs.push((self.serializer.atom(name)?, value));
+        Ok(())
+    }
+
+    fn finish(self) -> TaggedResult<TermValue> {
+        let map = TermValue::Map(self.pairs);
+        Ok(match self.variant {
+            Some(variant) => TermValue::Tuple(alloc::vec![variant, map]),
+            None => map,
+        })
+    }
+}
+
+impl<T: AtomTableOps> ser::SerializeMap for Pairs<'_, T> {
+    type Ok = TermValue;
+    type Error = TaggedError;
+
+    fn serialize_key<K: Serialize + ?Sized>(&mut self, key: &K) -> TaggedResult<()> {
+        self.key = Some(key.serialize(self.serializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<V: Serialize + ?Sized>(&mut self, value: &V) -> TaggedResult<()> {
+        let key = self.key.take().ok_or_else(|| TaggedError::Other("map value without a key".to_string()))?;
+        self.pairs.push((key, value.serialize(self.serializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> TaggedResult<TermValue> {
+        self.finish()
+    }
+}
+
+impl<T: AtomTableOps> ser::SerializeStruct for Pairs<'_, T> {
+    type Ok = TermValue;
+    type Error = TaggedError;
+
+    fn serialize_field<V: Serialize + ?Sized>(&mut self, name: &'static str, value: &V) -> TaggedResult<()> {
+        self.field(name, value)
+    }
+
+    fn end(self) -> TaggedResult<TermValue> {
+        self.finish()
+    }
+}
+
+impl<T: AtomTableOps> ser::SerializeStructVariant for Pairs<'_, T> {
+    type Ok = TermValue;
+    type Error = TaggedError;
+
+    fn serialize_field<V: Serialize + ?Sized>(&mut self, name: &'static str, value: &V) -> TaggedResult<()> {
+        self.field(name, value)
+    }
+
+    fn end(self) -> TaggedResult<TermValue> {
+        self.finish()
+    }
+}
+
+// ── Deserializer ───────────────────────────────────────────────────────────
+
+/// serde `Deserializer` reading a `TermValue`
+pub struct Deserializer<'de, 't, T> {
+    value: &'de TermValue,
+    table: &'t T,
+}
+
+impl<'de, 't, T: AtomTableOps> Deserializer<'de, 't, T> {
+    pub fn new(value: &'de TermValue, table: &'t T) -> Self {
+        Deserializer { value, table }
+    }
+
+    fn is_atom(&self, name: &str) -> bool {
+        matches!(self.value, TermValue::Atom(_)) && atom_name(self.value, self.table).is_ok_and(|atom| atom == name)
+    }
+
+    fn wrong_type(&self, expected: &'static str) -> TaggedError {
+        TaggedError::WrongType { expected, found: kind(self.value) }
+    }
+
+    /// A proper list's elements, or a tuple's
+    fn elements(&self) -> TaggedResult<Vec<&'de TermValue>> {
+        match self.value {
+            TermValue::Tuple(elements) => Ok(elements.iter().collect()),
+            TermValue::Nil | TermValue::List(_, _) => {
+                let mut iter = self.value.iter_list();
+                let elements: Vec<_> = iter.by_ref().collect();
+                match iter.tail() {
+                    TermValue::Nil => Ok(elements),
+                    _ => Err(TaggedError::WrongType { expected: "proper list", found: "improper list" }),
+                }
+            }
+            _ => Err(self.wrong_type("list")),
+        }
+    }
+}
+
+/// The kind of term, for `WrongType` errors
+fn kind(value: &TermValue) -> &'static str {
+    match value {
+        TermValue::SmallInt(_) | TermValue::Integer(_) => "integer",
+        TermValue::Float(_) => "float",
+        TermValue::Atom(_) => "atom",
+        TermValue::Nil | TermValue::List(_, _) => "list",
+        TermValue::Tuple(_) => "tuple",
+        TermValue::Map(_) => "map",
+        TermValue::Binary(_) => "binary",
+        TermValue::Pid(_) => "pid",
+        TermValue::Port(_) => "port",
+        TermValue::Reference(_) => "reference",
+        TermValue::Resource(_) => "resource",
+        TermValue::Function(_) => "function",
+        TermValue::Invalid(_) => "invalid",
+    }
+}
+
+impl<'de, T: AtomTableOps> de::Deserializer<'de> for Deserializer<'de, '_, T> {
+    type Error = TaggedError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> TaggedResult<V::Value> {
+        match self.value {
+            TermValue::SmallInt(value) => visitor.visit_i32(*value),
+            TermValue::Integer(value) => visitor.visit_i64(*value),
+            TermValue::Float(value) => visitor.visit_f64(*value),
+            TermValue::Atom(_) => match atom_name(self.value, self.table)?.as_str() {
+                "true" => visitor.visit_bool(true),
+                "false" => visitor.visit_bool(false),
+                "nil" => visitor.visit_none(),
+                name => visitor.visit_str(name),
+            },
+            TermValue::Binary(bytes) => match core::str::from_utf8(bytes) {
+                Ok(text) => visitor.visit_borrowed_str(text),
+                Err(_) => visitor.visit_borrowed_bytes(bytes),
+            },
+            TermValue::Nil | TermValue::List(_, _) | TermValue::Tuple(_) => self.deserialize_seq(visitor),
+            TermValue::Map(_) => self.deserialize_map(visitor),
+            _ => Err(self.wrong_type("serializable term")),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> TaggedResult<V::Value> {
+        match atom_name(self.value, self.table).as_deref() {
+            Ok("true") => visitor.visit_bool(true),
+            Ok("false") => visitor.visit_bool(false),
+            _ => Err(self.wrong_type("boolean")),
+        }
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> TaggedResult<V::Value> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> TaggedResult<V::Value> {
+        match self.value {
+            TermValue::Float(value) => visitor.visit_f64(*value),
+            TermValue::SmallInt(value) => visitor.visit_f64(*value as f64),
+            TermValue::Integer(value) => visitor.visit_f64(*value as f64),
+            _ => Err(self.wrong_type("float")),
+        }
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> TaggedResult<V::Value> {
+        match self.value {
+            TermValue::SmallInt(value) => {
+                let c = u32::try_from(*value).ok().and_then(char::from_u32);
+                visitor.visit_char(c.ok_or(TaggedError::OutOfRange("char"))?)
+            }
+            _ => self.deserialize_str(visitor),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> TaggedResult<V::Value> {
+        match self.value {
+            TermValue::Binary(bytes) => {
+                visitor.visit_borrowed_str(core::str::from_utf8(bytes).map_err(|_| TaggedError::InvalidUtf8)?)
+            }
+            TermValue::Atom(_) => visitor.visit_string(atom_name(self.value, self.table)?),
+            _ => visitor.visit_string(String::from_field(self.value, self.table)?),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> TaggedResult<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> TaggedResult<V::Value> {
+        match self.value {
+            TermValue::Binary(bytes) => visitor.visit_borrowed_bytes(bytes),
+            _ => Err(self.wrong_type("binary")),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> TaggedResult<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> TaggedResult<V::Value> {
+        if self.is_atom("nil") {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> TaggedResult<V::Value> {
+        match self.value {
+            TermValue::Tuple(elements) if elements.is_empty() => visitor.visit_unit(),
+            _ => Err(self.wrong_type("{}")),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, name: &'static str, visitor: V) -> TaggedResult<V::Value> {
+        if self.is_atom(name) {
+            visitor.visit_unit()
+        } else {
+            Err(TaggedError::type_mismatch(name, kind(self.value)))
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> TaggedResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> TaggedResult<V::Value> {
+        let elements = self.elements()?;
+        let len = elements.len();
+        let mut access = Elements { elements: elements.into_iter(), table: self.table };
+        let value = visitor.visit_seq(&mut access)?;
+        match access.elements.len() {
+            0 => Ok(value),
+            _ => Err(de::Error::invalid_length(len, &"fewer elements")),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> TaggedResult<V::Value> {
+        let elements = self.elements()?;
+        if elements.len() != len {
+            return Err(TaggedError::OutOfBounds { index: elements.len(), max: len });
+        }
+        visitor.visit_seq(Elements { elements: elements.into_iter(), table: self.table })
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> TaggedResult<V::Value> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> TaggedResult<V::Value> {
+        match self.value {
+            TermValue::Map(pairs) => visitor.visit_map(Entries { pairs: pairs.iter(), pair: None, table: self.table }),
+            _ => Err(self.wrong_type("map")),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> TaggedResult<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> TaggedResult<V::Value> {
+        match self.value {
+            TermValue::Atom(_) => visitor.visit_enum(Variant { name: self.value, fields: &[], table: self.table }),
+            TermValue::Tuple(elements) if matches!(elements.first(), Some(TermValue::Atom(_))) => {
+                visitor.visit_enum(Variant { name: &elements[0], fields: &elements[1..], table: self.table })
+            }
+            _ => Err(self.wrong_type("atom or tagged tuple")),
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> TaggedResult<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> TaggedResult<V::Value> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128
+    }
+}
+
+/// Remaining elements of a list or tuple
+struct Elements<'de, 't, T> {
+    elements: alloc::vec::IntoIter<&'de TermValue>,
+    table: &'t T,
+}
+
+impl<'de, T: AtomTableOps> de::SeqAccess<'de> for Elements<'de, '_, T> {
+    type Error = TaggedError;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> TaggedResult<Option<S::Value>> {
+        match self.elements.next() {
+            Some(value) => seed.deserialize(Deserializer { value, table: self.table }).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.elements.len())
+    }
+}
+
+/// Remaining pairs of a map, with the pair whose key was last read
+struct Entries<'de, 't, T> {
+    pairs: core::slice::Iter<'de, (TermValue, TermValue)>,
+    pair: Option<&'de (TermValue, TermValue)>,
+    table: &'t T,
+}
+
+impl<'de, T: AtomTableOps> de::MapAccess<'de> for Entries<'de, '_, T> {
+    type Error = TaggedError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> TaggedResult<Option<K::Value>> {
+        self.pair = self.pairs.next();
+        match self.pair {
+            Some((key, _)) => seed.deserialize(Deserializer { value: key, table: self.table }).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> TaggedResult<S::Value> {
+        let (key, value) = self.pair.take().ok_or_else(|| TaggedError::Other("map value without a key".to_string()))?;
+        // Atom keys are struct fields: say which one failed
+        seed.deserialize(Deserializer { value, table: self.table }).map_err(|error| match atom_name(key, self.table) {
+            Ok(name) => TaggedError::nested(name, error),
+            Err(_) => error,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.pairs.len())
+    }
+}
+
+/// An enum variant: its name atom and the fields after it in the tuple
+struct Variant<'de, 't, T> {
+    name: &'de TermValue,
+    fields: &'de [TermValue],
+    table: &'t T,
+}
+
+impl<'de, 't, T: AtomTableOps> de::EnumAccess<'de> for Variant<'de, 't, T> {
+    type Error = TaggedError;
+    type Variant = Self;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> TaggedResult<(S::Value, Self)> {
+        let variant = seed.deserialize(Deserializer { value: self.name, table: self.table })?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, T: AtomTableOps> de::VariantAccess<'de> for Variant<'de, '_, T> {
+    type Error = TaggedError;
+
+    fn unit_variant(self) -> TaggedResult<()> {
+        match self.fields {
+            [] => Ok(()),
+            _ => Err(TaggedError::WrongType { expected: "atom", found: "tuple" }),
+        }
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> TaggedResult<S::Value> {
+        match self.fields {
+            [value] => seed.deserialize(Deserializer { value, table: self.table }),
+            _ => Err(TaggedError::OutOfBounds { index: self.fields.len(), max: 1 }),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> TaggedResult<V::Value> {
+        if self.fields.len() != len {
+            return Err(TaggedError::OutOfBounds { index: self.fields.len(), max: len });
+        }
+        visitor.visit_seq(Elements { elements: self.fields.iter().collect::<Vec<_>>().into_iter(), table: self.table })
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> TaggedResult<V::Value> {
+        match self.fields {
+            [value] => de::Deserializer::deserialize_map(Deserializer { value, table: self.table }, visitor),
+            _ => Err(TaggedError::OutOfBounds { index: self.fields.len(), max: 1 }),
+        }
+    }
+}
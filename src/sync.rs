@@ -0,0 +1,43 @@
+//! Minimal synchronization for state shared between tasks and the scheduler
+
+use core::cell::UnsafeCell;
+use self::atomic::{AtomicBool, Ordering};
+
+/// The crate's atomics: `core`'s, or `portable-atomic`'s where the target
+/// has no compare-and-swap (thumbv6m) and critical sections stand in for it
+#[cfg(not(feature = "rp2040"))]
+pub(crate) use core::sync::atomic;
+#[cfg(feature = "rp2040")]
+pub(crate) use portable_atomic as atomic;
+
+/// Spin lock for short critical sections on targets without an OS mutex
+pub(crate) struct SpinLock<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+// All access to `value` goes through the lock
+unsafe impl<T: Send> Sync for SpinLock<T> {}
+
+impl<T> SpinLock<T> {
+    pub(crate) const fn new(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Run `f` with exclusive access to the value
+    pub(crate) fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        let result = f(unsafe { &mut *self.value.get() });
+        self.locked.store(false, Ordering::Release);
+        result
+    }
+}
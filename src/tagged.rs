@@ -11,11 +11,13 @@
 //!
 //! # Examples
 //!
-//! ```rust,ignore
-//! use avmnif_rs::tagged::{TaggedMap, TaggedError};
-//! use avmnif_rs::testing::mocks::MockAtomTable;
+//! ```
+//! # #[cfg(all(feature = "sim", feature = "derive"))] {
+//! use avmnif_rs::atom::AtomTable;
+//! use avmnif_rs::tagged::TaggedMap;
+//! use avmnif_rs::testing::mocks::{MockAtomTable, MockGlobalContext};
 //!
-//! #[derive(TaggedMap)]
+//! #[derive(TaggedMap, Debug, PartialEq)]
 //! struct SensorReading {
 //!     temperature: f32,
 //!     humidity: f32,
@@ -27,17 +29,21 @@
 //! let reading = SensorReading { temperature: 23.5, humidity: 45.2, timestamp: 1634567890 };
 //! let term = reading.to_tagged_map(&table)?;
 //! let parsed = SensorReading::from_tagged_map(term, &table)?;
+//! assert_eq!(parsed, reading);
 //!
-//! // In production:
+//! // In production, or on the simulator's VM:
+//! # let _vm = MockGlobalContext::new();
 //! let table = AtomTable::from_global();
 //! let term = reading.to_tagged_map(&table)?;
+//! # }
+//! # Ok::<(), avmnif_rs::tagged::TaggedError>(())
 //! ```
 
 extern crate alloc;
 
 use crate::atom::{AtomTableOps, AtomError, atoms};
-use crate::term::{AtomIndex, TermValue};
-use alloc::{string::String, string::ToString, vec, vec::Vec, format};
+use crate::term::{AtomIndex, Charset, TermValue};
+use alloc::{collections::BTreeMap, string::String, string::ToString, vec, vec::Vec, format};
 use core::fmt;
 
 // ── Error Handling ──────────────────────────────────────────────────────────
@@ -61,6 +67,8 @@ pub enum TaggedError {
     OutOfMemory,
     /// Invalid UTF-8 in binary
     InvalidUtf8,
+    /// Number does not fit the Rust type, or the small int a term can hold
+    OutOfRange(&'static str),
     /// Nested error with path context
     NestedError { path: String, source: alloc::boxed::Box<TaggedError> },
     /// Generic error with message
@@ -114,6 +122,7 @@ impl fmt::Display for TaggedError {
                 write!(f, "invalid variant '{}' for enum {}", variant, enum_name),
             TaggedError::OutOfMemory => write!(f, "out of memory"),
             TaggedError::InvalidUtf8 => write!(f, "invalid UTF-8"),
+            TaggedError::OutOfRange(ty) => write!(f, "value out of range for {}", ty),
             TaggedError::NestedError { path, source } => 
                 write!(f, "error at {}: {}", path, source),
             TaggedError::Other(msg) => write!(f, "{}", msg),
@@ -152,6 +161,22 @@ pub trait TaggedMap: Sized {
     fn type_name() -> &'static str;
 }
 
+/// A value stored in one field of a tagged map
+///
+/// Primitives are stored as plain terms: `42`, `<<"name">>`, `true`.
+/// `Option` is the value or `nil`, `Vec` a list. Types with a `TaggedMap`
+/// derive are stored as their own tagged map, so structs nest.
+pub trait TaggedField: Sized {
+    fn to_field<T: AtomTableOps>(&self, table: &T) -> TaggedResult<TermValue>;
+
+    fn from_field<T: AtomTableOps>(value: &TermValue, table: &T) -> TaggedResult<Self>;
+
+    /// The value of a field missing from the map; `None` makes it required
+    fn absent() -> Option<Self> {
+        None
+    }
+}
+
 // ── Helper Functions ────────────────────────────────────────────────────────
 
 /// Convert Rust identifier to snake_case atom name
@@ -222,60 +247,36 @@ pub fn get_map_value(map: &TermValue, key_atom: AtomIndex) -> TaggedResult<&Term
 
 /// Extract required string field from map
 pub fn extract_string_field<T: AtomTableOps>(map: &TermValue, field_name: &str, table: &T) -> TaggedResult<String> {
+    let field_atom = get_type_atom(field_name, table)?;
+    String::from_field(get_map_value(map, field_atom)?, table)
+}
+
+/// Extract required binary field from map, accepting any iodata
+pub fn extract_binary_field<T: AtomTableOps>(map: &TermValue, field_name: &str, table: &T) -> TaggedResult<Vec<u8>> {
     let field_atom = get_type_atom(field_name, table)?;
     let value = get_map_value(map, field_atom)?;
-    
-    match value {
-        TermValue::Binary(bytes) => {
-            String::from_utf8(bytes.clone()).map_err(|_| TaggedError::InvalidUtf8)
-        }
-        _ => Err(TaggedError::WrongType { expected: "binary/string", found: "other" }),
-    }
+    value
+        .iodata_bytes()
+        .map(|bytes| bytes.into_owned())
+        .ok_or(TaggedError::WrongType { expected: "iodata", found: "other" })
 }
 
 /// Extract required integer field from map
 pub fn extract_int_field<T: AtomTableOps>(map: &TermValue, field_name: &str, table: &T) -> TaggedResult<i32> {
     let field_atom = get_type_atom(field_name, table)?;
-    let value = get_map_value(map, field_atom)?;
-    
-    match value {
-        TermValue::SmallInt(i) => Ok(*i),
-        _ => Err(TaggedError::WrongType { expected: "integer", found: "other" }),
-    }
+    i32::from_field(get_map_value(map, field_atom)?, table)
 }
 
 /// Extract required float field from map  
 pub fn extract_float_field<T: AtomTableOps>(map: &TermValue, field_name: &str, table: &T) -> TaggedResult<f64> {
     let field_atom = get_type_atom(field_name, table)?;
-    let value = get_map_value(map, field_atom)?;
-    
-    match value {
-        TermValue::Float(f) => Ok(*f),
-        TermValue::SmallInt(i) => Ok(*i as f64), // Allow integer to float conversion
-        _ => Err(TaggedError::WrongType { expected: "float", found: "other" }),
-    }
+    f64::from_field(get_map_value(map, field_atom)?, table)
 }
 
 /// Extract required boolean field from map
 pub fn extract_bool_field<T: AtomTableOps>(map: &TermValue, field_name: &str, table: &T) -> TaggedResult<bool> {
     let field_atom = get_type_atom(field_name, table)?;
-    let value = get_map_value(map, field_atom)?;
-    
-    let true_atom = atoms::true_atom(table).map_err(TaggedError::from)?;
-    let false_atom = atoms::false_atom(table).map_err(TaggedError::from)?;
-    
-    match value {
-        TermValue::Atom(atom_idx) => {
-            if *atom_idx == true_atom {
-                Ok(true)
-            } else if *atom_idx == false_atom {
-                Ok(false)
-            } else {
-                Err(TaggedError::WrongType { expected: "boolean", found: "other atom" })
-            }
-        }
-        _ => Err(TaggedError::WrongType { expected: "boolean", found: "other" }),
-    }
+    bool::from_field(get_map_value(map, field_atom)?, table)
 }
 
 /// Extract optional field from map
@@ -327,6 +328,512 @@ pub fn validate_type_discriminator<T: AtomTableOps>(map: &TermValue, expected_ty
     }
 }
 
+// ── Derive Support ──────────────────────────────────────────────────────────
+
+// Used by the code `#[derive(TaggedMap)]` and the rustler-compat derives generate
+
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::vec;
+    pub use alloc::vec::Vec;
+}
+
+/// A new map's pairs, starting with `type => type_name`
+pub fn tagged_pairs<T: AtomTableOps>(type_name: &str, table: &T) -> TaggedResult<Vec<(TermValue, TermValue)>> {
+    Ok(vec![(
+        TermValue::Atom(type_field_atom(table)?),
+        TermValue::Atom(get_type_atom(type_name, table)?),
+    )])
+}
+
+/// Add `variant => name`
+pub fn push_variant<T: AtomTableOps>(
+    pairs: &mut Vec<(TermValue, TermValue)>,
+    variant: &str,
+    table: &T,
+) -> TaggedResult<()> {
+    pairs.push((TermValue::Atom(variant_field_atom(table)?), TermValue::Atom(get_type_atom(variant, table)?)));
+    Ok(())
+}
+
+/// Add `name => value`
+pub fn push_field<F: TaggedField, T: AtomTableOps>(
+    pairs: &mut Vec<(TermValue, TermValue)>,
+    name: &str,
+    value: &F,
+    table: &T,
+) -> TaggedResult<()> {
+    let value = value.to_field(table).map_err(|error| TaggedError::nested(name, error))?;
+    pairs.push((TermValue::Atom(get_type_atom(name, table)?), value));
+    Ok(())
+}
+
+/// Decode the field `name`, or its `absent` value if the map lacks it
+pub fn field<F: TaggedField, T: AtomTableOps>(map: &TermValue, name: &str, table: &T) -> TaggedResult<F> {
+    if !matches!(map, TermValue::Map(_)) {
+        return Err(TaggedError::WrongType { expected: "map", found: "other" });
+    }
+    match get_map_value(map, get_type_atom(name, table)?) {
+        Ok(value) => F::from_field(value, table).map_err(|error| TaggedError::nested(name, error)),
+        Err(_) => F::absent().ok_or_else(|| TaggedError::missing_field(name)),
+    }
+}
+
+/// Decode the field `name`, or `F::default()` if the map lacks it
+pub fn field_or_default<F: TaggedField + Default, T: AtomTableOps>(
+    map: &TermValue,
+    name: &str,
+    table: &T,
+) -> TaggedResult<F> {
+    match field(map, name, table) {
+        Err(TaggedError::MissingField(_)) => Ok(F::default()),
+        result => result,
+    }
+}
+
+/// The name of an atom term
+pub fn atom_name<T: AtomTableOps>(value: &TermValue, table: &T) -> TaggedResult<String> {
+    match value {
+        TermValue::Atom(index) => {
+            let name = table.get_atom_string(*index).map_err(TaggedError::from)?;
+            name.as_str().map(ToString::to_string).map_err(|_| TaggedError::InvalidUtf8)
+        }
+        _ => Err(TaggedError::WrongType { expected: "atom", found: "other" }),
+    }
+}
+
+/// The `variant` of an enum's map
+pub fn variant_name<T: AtomTableOps>(map: &TermValue, table: &T) -> TaggedResult<String> {
+    let variant = get_map_value(map, variant_field_atom(table)?).map_err(|_| TaggedError::missing_field("variant"))?;
+    atom_name(variant, table)
+}
+
+/// The elements of a tuple of `arity` elements
+pub fn tuple_elements(value: &TermValue, arity: usize) -> TaggedResult<&[TermValue]> {
+    match value.as_tuple() {
+        Some(elements) if elements.len() == arity => Ok(elements),
+        Some(elements) => Err(TaggedError::OutOfBounds { index: elements.len(), max: arity }),
+        None => Err(TaggedError::WrongType { expected: "tuple", found: "other" }),
+    }
+}
+
+/// A new map's pairs, starting with `__struct__ => module`
+pub fn struct_pairs<T: AtomTableOps>(module: &str, table: &T) -> TaggedResult<Vec<(TermValue, TermValue)>> {
+    Ok(vec![(
+        TermValue::Atom(get_type_atom("__struct__", table)?),
+        TermValue::Atom(get_type_atom(module, table)?),
+    )])
+}
+
+/// Check that a map is an Elixir struct of `module`
+pub fn validate_struct_module<T: AtomTableOps>(map: &TermValue, module: &str, table: &T) -> TaggedResult<()> {
+    let found = get_map_value(map, get_type_atom("__struct__", table)?)
+        .map_err(|_| TaggedError::missing_field("__struct__"))?;
+    if *found == TermValue::Atom(get_type_atom(module, table)?) {
+        Ok(())
+    } else {
+        Err(TaggedError::type_mismatch(module, atom_name(found, table).unwrap_or_else(|_| "unknown".to_string())))
+    }
+}
+
+/// A new record's elements, starting with the `type_name` tag
+pub fn record_elements<T: AtomTableOps>(type_name: &str, table: &T) -> TaggedResult<Vec<TermValue>> {
+    Ok(vec![TermValue::Atom(get_type_atom(type_name, table)?)])
+}
+
+/// Add a record element
+pub fn push_element<F: TaggedField, T: AtomTableOps>(
+    elements: &mut Vec<TermValue>,
+    name: &str,
+    value: &F,
+    table: &T,
+) -> TaggedResult<()> {
+    elements.push(value.to_field(table).map_err(|error| TaggedError::nested(name, error))?);
+    Ok(())
+}
+
+/// The fields of a `{type_name, ...}` record with at most `arity` fields
+///
+/// Fewer fields are allowed so trailing optional fields can be left off.
+pub fn record_fields<'a, T: AtomTableOps>(
+    value: &'a TermValue,
+    type_name: &str,
+    arity: usize,
+    table: &T,
+) -> TaggedResult<&'a [TermValue]> {
+    let elements = value.as_tuple().ok_or(TaggedError::WrongType { expected: "tuple", found: "other" })?;
+    let (tag, fields) = elements.split_first().ok_or(TaggedError::OutOfBounds { index: 0, max: arity + 1 })?;
+    if *tag != TermValue::Atom(get_type_atom(type_name, table)?) {
+        return Err(TaggedError::type_mismatch(type_name, atom_name(tag, table).unwrap_or_else(|_| "unknown".to_string())));
+    }
+    if fields.len() > arity {
+        return Err(TaggedError::OutOfBounds { index: elements.len(), max: arity + 1 });
+    }
+    Ok(fields)
+}
+
+/// Decode the record field at `index`, or its `absent` value if the record is shorter
+pub fn record_field<F: TaggedField, T: AtomTableOps>(
+    fields: &[TermValue],
+    index: usize,
+    name: &str,
+    table: &T,
+) -> TaggedResult<F> {
+    match fields.get(index) {
+        Some(value) => F::from_field(value, table).map_err(|error| TaggedError::nested(name, error)),
+        None => F::absent().ok_or_else(|| TaggedError::missing_field(name)),
+    }
+}
+
+/// Decode the record field at `index`, or `F::default()` if the record is shorter
+pub fn record_field_or_default<F: TaggedField + Default, T: AtomTableOps>(
+    fields: &[TermValue],
+    index: usize,
+    name: &str,
+    table: &T,
+) -> TaggedResult<F> {
+    match record_field(fields, index, name, table) {
+        Err(TaggedError::MissingField(_)) => Ok(F::default()),
+        result => result,
+    }
+}
+
+// ── Field Implementations ───────────────────────────────────────────────────
+
+impl TaggedField for i32 {
+    fn to_field<T: AtomTableOps>(&self, _table: &T) -> TaggedResult<TermValue> {
+        Ok(TermValue::SmallInt(*self))
+    }
+
+    fn from_field<T: AtomTableOps>(value: &TermValue, _table: &T) -> TaggedResult<Self> {
+        match value {
+            TermValue::SmallInt(i) => Ok(*i),
+            _ => Err(TaggedError::WrongType { expected: "integer", found: "other" }),
+        }
+    }
+}
+
+/// Integers are stored as `TermValue::integer`; values outside `i64` fail to encode
+macro_rules! integer_field {
+    ($($ty:ty),*) => {$(
+        impl TaggedField for $ty {
+            fn to_field<T: AtomTableOps>(&self, _table: &T) -> TaggedResult<TermValue> {
+                i64::try_from(*self)
+                    .map(TermValue::integer)
+                    .map_err(|_| TaggedError::OutOfRange("i64"))
+            }
+
+            fn from_field<T: AtomTableOps>(value: &TermValue, _table: &T) -> TaggedResult<Self> {
+                match value.as_integer() {
+                    Some(i) => <$ty>::try_from(i).map_err(|_| TaggedError::OutOfRange(stringify!($ty))),
+                    None => Err(TaggedError::WrongType { expected: "integer", found: "other" }),
+                }
+            }
+        }
+    )*};
+}
+
+integer_field!(i8, i16, i64, u8, u16, u32, u64);
+
+impl TaggedField for f32 {
+    fn to_field<T: AtomTableOps>(&self, _table: &T) -> TaggedResult<TermValue> {
+        Ok(TermValue::Float(*self as f64))
+    }
+
+    fn from_field<T: AtomTableOps>(value: &TermValue, table: &T) -> TaggedResult<Self> {
+        f64::from_field(value, table).map(|f| f as f32)
+    }
+}
+
+/// A character is its code point, as `$a` is in Erlang
+impl TaggedField for char {
+    fn to_field<T: AtomTableOps>(&self, _table: &T) -> TaggedResult<TermValue> {
+        Ok(TermValue::SmallInt(*self as i32))
+    }
+
+    fn from_field<T: AtomTableOps>(value: &TermValue, _table: &T) -> TaggedResult<Self> {
+        match value {
+            TermValue::SmallInt(i) => u32::try_from(*i)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or(TaggedError::OutOfRange("char")),
+            _ => Err(TaggedError::WrongType { expected: "integer", found: "other" }),
+        }
+    }
+}
+
+/// The unit value is the empty tuple
+impl TaggedField for () {
+    fn to_field<T: AtomTableOps>(&self, _table: &T) -> TaggedResult<TermValue> {
+        Ok(TermValue::Tuple(Vec::new()))
+    }
+
+    fn from_field<T: AtomTableOps>(value: &TermValue, _table: &T) -> TaggedResult<Self> {
+        match value {
+            TermValue::Tuple(elements) if elements.is_empty() => Ok(()),
+            _ => Err(TaggedError::WrongType { expected: "empty tuple", found: "other" }),
+        }
+    }
+}
+
+impl TaggedField for f64 {
+    fn to_field<T: AtomTableOps>(&self, _table: &T) -> TaggedResult<TermValue> {
+        Ok(TermValue::Float(*self))
+    }
+
+    fn from_field<T: AtomTableOps>(value: &TermValue, _table: &T) -> TaggedResult<Self> {
+        match value {
+            TermValue::Float(f) => Ok(*f),
+            TermValue::SmallInt(i) => Ok(*i as f64), // Allow integer to float conversion
+            TermValue::Integer(i) => Ok(*i as f64),
+            _ => Err(TaggedError::WrongType { expected: "float", found: "other" }),
+        }
+    }
+}
+
+impl TaggedField for bool {
+    fn to_field<T: AtomTableOps>(&self, table: &T) -> TaggedResult<TermValue> {
+        let atom = if *self { atoms::true_atom(table) } else { atoms::false_atom(table) };
+        Ok(TermValue::Atom(atom.map_err(TaggedError::from)?))
+    }
+
+    fn from_field<T: AtomTableOps>(value: &TermValue, table: &T) -> TaggedResult<Self> {
+        let true_atom = atoms::true_atom(table).map_err(TaggedError::from)?;
+        let false_atom = atoms::false_atom(table).map_err(TaggedError::from)?;
+        
+        match value {
+            TermValue::Atom(atom_idx) => {
+                if *atom_idx == true_atom {
+                    Ok(true)
+                } else if *atom_idx == false_atom {
+                    Ok(false)
+                } else {
+                    Err(TaggedError::WrongType { expected: "boolean", found: "other atom" })
+                }
+            }
+            _ => Err(TaggedError::WrongType { expected: "boolean", found: "other" }),
+        }
+    }
+}
+
+impl TaggedField for String {
+    fn to_field<T: AtomTableOps>(&self, _table: &T) -> TaggedResult<TermValue> {
+        Ok(TermValue::Binary(self.as_bytes().to_vec()))
+    }
+
+    fn from_field<T: AtomTableOps>(value: &TermValue, _table: &T) -> TaggedResult<Self> {
+        match value {
+            TermValue::Binary(bytes) => {
+                String::from_utf8(bytes.clone()).map_err(|_| TaggedError::InvalidUtf8)
+            }
+            // Erlang callers often pass strings as charlists or iodata
+            TermValue::List(_, _) | TermValue::Nil => {
+                if let Some(text) = value.charlist_to_string(Charset::Unicode) {
+                    return Ok(text);
+                }
+                let bytes = value.iodata_bytes().ok_or(TaggedError::WrongType {
+                    expected: "binary/string",
+                    found: "list",
+                })?;
+                String::from_utf8(bytes.into_owned()).map_err(|_| TaggedError::InvalidUtf8)
+            }
+            _ => Err(TaggedError::WrongType { expected: "binary/string", found: "other" }),
+        }
+    }
+}
+
+/// `None` is stored as the atom `nil`, so the round trip is lossy for a
+/// value stored as `nil` itself: `Some(nil)` of a `TermValue` field reads
+/// back as `None`.
+impl<F: TaggedField> TaggedField for Option<F> {
+    fn to_field<T: AtomTableOps>(&self, table: &T) -> TaggedResult<TermValue> {
+        match self {
+            Some(value) => value.to_field(table),
+            None => Ok(TermValue::Atom(atoms::nil(table).map_err(TaggedError::from)?)),
+        }
+    }
+
+    fn from_field<T: AtomTableOps>(value: &TermValue, table: &T) -> TaggedResult<Self> {
+        let nil_atom = atoms::nil(table).map_err(TaggedError::from)?;
+        match value {
+            TermValue::Atom(atom_idx) if *atom_idx == nil_atom => Ok(None),
+            _ => F::from_field(value, table).map(Some),
+        }
+    }
+
+    fn absent() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl<F: TaggedField> TaggedField for Vec<F> {
+    fn to_field<T: AtomTableOps>(&self, table: &T) -> TaggedResult<TermValue> {
+        let elements = self.iter().map(|item| item.to_field(table)).collect::<TaggedResult<Vec<_>>>()?;
+        Ok(TermValue::from_vec(elements))
+    }
+
+    fn from_field<T: AtomTableOps>(value: &TermValue, table: &T) -> TaggedResult<Self> {
+        if !matches!(value, TermValue::List(_, _) | TermValue::Nil) {
+            return Err(TaggedError::WrongType { expected: "list", found: "other" });
+        }
+        let mut items = value.iter_list();
+        let decoded = items.by_ref().map(|item| F::from_field(item, table)).collect::<TaggedResult<Vec<_>>>()?;
+        if !items.tail().is_nil() {
+            return Err(TaggedError::WrongType { expected: "proper list", found: "improper list" });
+        }
+        Ok(decoded)
+    }
+}
+
+/// Tuples are Erlang tuples of the same arity
+macro_rules! tuple_field {
+    ($($arity:literal => ($($name:ident $index:tt),+)),*) => {$(
+        impl<$($name: TaggedField),+> TaggedField for ($($name,)+) {
+            fn to_field<T: AtomTableOps>(&self, table: &T) -> TaggedResult<TermValue> {
+                Ok(TermValue::Tuple(vec![$(self.$index.to_field(table)?),+]))
+            }
+
+            fn from_field<T: AtomTableOps>(value: &TermValue, table: &T) -> TaggedResult<Self> {
+                let elements = tuple_elements(value, $arity)?;
+                Ok(($($name::from_field(&elements[$index], table)?,)+))
+            }
+        }
+
+        impl<$($name: TaggedField),+> TaggedMap for ($($name,)+) {
+            fn to_tagged_map<T: AtomTableOps>(&self, table: &T) -> TaggedResult<TermValue> {
+                value_map("tuple", self, table)
+            }
+
+            fn from_tagged_map<T: AtomTableOps>(map: TermValue, table: &T) -> TaggedResult<Self> {
+                map_value(&map, "tuple", table)
+            }
+
+            fn type_name() -> &'static str {
+                "tuple"
+            }
+        }
+    )*};
+}
+
+tuple_field!(
+    1 => (A 0),
+    2 => (A 0, B 1),
+    3 => (A 0, B 1, C 2),
+    4 => (A 0, B 1, C 2, D 3),
+    5 => (A 0, B 1, C 2, D 3, E 4),
+    6 => (A 0, B 1, C 2, D 3, E 4, F 5),
+    7 => (A 0, B 1, C 2, D 3, E 4, F 5, G 6),
+    8 => (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7)
+);
+
+/// Arrays are lists of exactly `N` elements
+impl<F: TaggedField, const N: usize> TaggedField for [F; N] {
+    fn to_field<T: AtomTableOps>(&self, table: &T) -> TaggedResult<TermValue> {
+        let elements = self.iter().map(|item| item.to_field(table)).collect::<TaggedResult<Vec<_>>>()?;
+        Ok(TermValue::from_vec(elements))
+    }
+
+    fn from_field<T: AtomTableOps>(value: &TermValue, table: &T) -> TaggedResult<Self> {
+        let items = Vec::<F>::from_field(value, table)?;
+        let len = items.len();
+        items.try_into().map_err(|_| TaggedError::OutOfBounds { index: len, max: N })
+    }
+}
+
+/// Maps are Erlang maps, keys and values stored as fields
+impl<K: TaggedField + Ord, V: TaggedField> TaggedField for BTreeMap<K, V> {
+    fn to_field<T: AtomTableOps>(&self, table: &T) -> TaggedResult<TermValue> {
+        let pairs = self
+            .iter()
+            .map(|(key, value)| Ok((key.to_field(table)?, value.to_field(table)?)))
+            .collect::<TaggedResult<Vec<_>>>()?;
+        Ok(TermValue::Map(pairs))
+    }
+
+    fn from_field<T: AtomTableOps>(value: &TermValue, table: &T) -> TaggedResult<Self> {
+        match value {
+            TermValue::Map(pairs) => pairs
+                .iter()
+                .map(|(key, value)| Ok((K::from_field(key, table)?, V::from_field(value, table)?)))
+                .collect(),
+            _ => Err(TaggedError::WrongType { expected: "map", found: "other" }),
+        }
+    }
+}
+
+/// `{ok, Value}` or `{error, Reason}`
+impl<R: TaggedField, E: TaggedField> TaggedField for Result<R, E> {
+    fn to_field<T: AtomTableOps>(&self, table: &T) -> TaggedResult<TermValue> {
+        let (tag, value) = match self {
+            Ok(value) => (atoms::ok(table), value.to_field(table)?),
+            Err(reason) => (atoms::error(table), reason.to_field(table)?),
+        };
+        Ok(TermValue::Tuple(vec![TermValue::Atom(tag.map_err(TaggedError::from)?), value]))
+    }
+
+    fn from_field<T: AtomTableOps>(value: &TermValue, table: &T) -> TaggedResult<Self> {
+        let elements = tuple_elements(value, 2)?;
+        match atom_name(&elements[0], table)?.as_str() {
+            "ok" => R::from_field(&elements[1], table).map(Ok),
+            "error" => E::from_field(&elements[1], table).map(Err),
+            other => Err(TaggedError::invalid_variant("Result", other)),
+        }
+    }
+}
+
+impl<F: TaggedField, const N: usize> TaggedMap for [F; N] {
+    fn to_tagged_map<T: AtomTableOps>(&self, table: &T) -> TaggedResult<TermValue> {
+        value_map("array", self, table)
+    }
+
+    fn from_tagged_map<T: AtomTableOps>(map: TermValue, table: &T) -> TaggedResult<Self> {
+        map_value(&map, "array", table)
+    }
+
+    fn type_name() -> &'static str {
+        "array"
+    }
+}
+
+impl<K: TaggedField + Ord, V: TaggedField> TaggedMap for BTreeMap<K, V> {
+    fn to_tagged_map<T: AtomTableOps>(&self, table: &T) -> TaggedResult<TermValue> {
+        value_map("map", self, table)
+    }
+
+    fn from_tagged_map<T: AtomTableOps>(map: TermValue, table: &T) -> TaggedResult<Self> {
+        map_value(&map, "map", table)
+    }
+
+    fn type_name() -> &'static str {
+        "map"
+    }
+}
+
+impl<R: TaggedField, E: TaggedField> TaggedMap for Result<R, E> {
+    fn to_tagged_map<T: AtomTableOps>(&self, table: &T) -> TaggedResult<TermValue> {
+        value_map("result", self, table)
+    }
+
+    fn from_tagged_map<T: AtomTableOps>(map: TermValue, table: &T) -> TaggedResult<Self> {
+        map_value(&map, "result", table)
+    }
+
+    fn type_name() -> &'static str {
+        "result"
+    }
+}
+
+/// Any term, stored as it is
+impl TaggedField for TermValue {
+    fn to_field<T: AtomTableOps>(&self, _table: &T) -> TaggedResult<TermValue> {
+        Ok(self.clone())
+    }
+
+    fn from_field<T: AtomTableOps>(value: &TermValue, _table: &T) -> TaggedResult<Self> {
+        Ok(value.clone())
+    }
+}
+
 // ── Generic Primitive Type Implementations ─────────────────────────────────
 
 // These allow primitive types to be used directly in tagged structs
@@ -405,6 +912,43 @@ impl TaggedMap for bool {
     }
 }
 
+/// `#{type => type_name, value => Field}`
+fn value_map<F: TaggedField, T: AtomTableOps>(type_name: &str, value: &F, table: &T) -> TaggedResult<TermValue> {
+    let mut pairs = tagged_pairs(type_name, table)?;
+    push_field(&mut pairs, "value", value, table)?;
+    Ok(TermValue::Map(pairs))
+}
+
+/// The `value` of a map built by `value_map`
+fn map_value<F: TaggedField, T: AtomTableOps>(map: &TermValue, type_name: &str, table: &T) -> TaggedResult<F> {
+    validate_type_discriminator(map, type_name, table)?;
+    field(map, "value", table)
+}
+
+/// `#{type => Name, value => Field}` for primitives with a `TaggedField` form
+macro_rules! primitive_map {
+    ($($ty:ty => $name:literal),*) => {$(
+        impl TaggedMap for $ty {
+            fn to_tagged_map<T: AtomTableOps>(&self, table: &T) -> TaggedResult<TermValue> {
+                value_map($name, self, table)
+            }
+
+            fn from_tagged_map<T: AtomTableOps>(map: TermValue, table: &T) -> TaggedResult<Self> {
+                map_value(&map, $name, table)
+            }
+
+            fn type_name() -> &'static str {
+                $name
+            }
+        }
+    )*};
+}
+
+primitive_map!(
+    i8 => "i8", i16 => "i16", i64 => "i64", u8 => "u8", u16 => "u16", u32 => "u32", u64 => "u64",
+    f32 => "f32", f64 => "f64", char => "char", () => "unit"
+);
+
 impl<U: TaggedMap> TaggedMap for Option<U> {
     fn to_tagged_map<T: AtomTableOps>(&self, table: &T) -> TaggedResult<TermValue> {
         match self {
@@ -514,3 +1058,7 @@ impl<U: TaggedMap> TaggedMap for Vec<U> {
 // Re-export the derive macro when available
 #[cfg(feature = "derive")]
 pub use avmnif_derive::TaggedMap;
+
+// Rustler's derives, generating `TaggedMap` impls with rustler's encodings
+#[cfg(feature = "rustler-compat")]
+pub use avmnif_derive::{NifMap, NifStruct, NifTuple, NifUnitEnum};
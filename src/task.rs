@@ -0,0 +1,171 @@
+//! Running NIF work on a task and replying later
+//!
+//! A NIF must return quickly, but some work (a flash erase, a TLS
+//! handshake, a sensor sweep) takes long. `spawn_and_reply` moves the work
+//! to a task: it takes the calling process's pid and a fresh reference,
+//! starts the closure, and returns the reference right away. When the
+//! closure finishes, the task sends `{Ref, Result}` to the caller, which
+//! waits for it with a selective receive.
+//!
+//! Where the closure runs is up to an `Executor`. `FreeRtosExecutor` starts
+//! a FreeRTOS task per job; other platforms or a work queue plug in their
+//! own with `spawn_and_reply_on`. The reply goes through a `ReplySender`,
+//! `AtomVMReplySender` in the VM.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use avmnif_rs::task;
+//!
+//! // flash:erase_async(Sector) -> Ref
+//! fn erase_async(env: &mut Env, sector: u32) -> NifResult<TermValue> {
+//!     task::spawn_and_reply(env, move || match flash_erase(sector) {
+//!         Ok(()) => TermValue::atom("ok", &AtomTable::from_global()),
+//!         Err(code) => TermValue::int(code),
+//!     })
+//! }
+//! ```
+//!
+//! ```erlang
+//! Ref = flash:erase_async(3),
+//! receive {Ref, Result} -> Result end.
+//! ```
+
+extern crate alloc;
+
+use crate::atom::AtomTable;
+use crate::context::{get_global_context, GlobalContext};
+use crate::term::{Env, NifError, NifResult, RefId, TermValue};
+use alloc::boxed::Box;
+use core::ffi::{c_char, c_void};
+
+/// Work handed to an executor
+pub type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Runs jobs away from the scheduler
+pub trait Executor {
+    /// Start `job`; it may run on another thread at any later time
+    fn spawn(&self, job: Job) -> NifResult<()>;
+}
+
+/// Delivers the `{Ref, Result}` message from the job's task
+pub trait ReplySender: Send + 'static {
+    fn send(&self, pid: u32, message: &TermValue) -> NifResult<()>;
+}
+
+/// Run `work` on `executor` and send `{Ref, Result}` to `caller`
+///
+/// Fails only if the executor cannot start the job; the reply is then
+/// never sent.
+pub fn spawn_with<E, S, F>(executor: &E, sender: S, caller: u32, reference: RefId, work: F) -> NifResult<()>
+where
+    E: Executor + ?Sized,
+    S: ReplySender,
+    F: FnOnce() -> TermValue + Send + 'static,
+{
+    executor.spawn(Box::new(move || {
+        let result = work();
+        // The caller may be gone; there is no one left to tell
+        let message = TermValue::tuple(alloc::vec![TermValue::Reference(reference), result]);
+        let _ = sender.send(caller, &message);
+    }))
+}
+
+// Task FFI declarations
+#[cfg_attr(target_arch = "wasm32", link(wasm_import_module = "env"))]
+extern "C" {
+    /// A reference number not handed out before
+    fn globalcontext_get_ref_ticks(glb: *mut GlobalContext) -> u64;
+
+    fn xTaskCreate(
+        task: extern "C" fn(*mut c_void),
+        name: *const c_char,
+        stack_depth: u32,
+        parameters: *mut c_void,
+        priority: u32,
+        created_task: *mut *mut c_void,
+    ) -> i32;
+
+    fn vTaskDelete(task: *mut c_void);
+}
+
+/// A fresh reference number from the VM, as `make_ref/0` would use
+pub fn fresh_reference() -> RefId {
+    RefId(unsafe { globalcontext_get_ref_ticks(get_global_context()) })
+}
+
+/// Run `work` on a FreeRTOS task and send `{Ref, Result}` to the caller
+///
+/// Returns `Ref`, for the NIF to hand back.
+pub fn spawn_and_reply<F>(env: &mut Env<'_>, work: F) -> NifResult<TermValue>
+where
+    F: FnOnce() -> TermValue + Send + 'static,
+{
+    spawn_and_reply_on(&FreeRtosExecutor::default(), env, work)
+}
+
+/// As `spawn_and_reply`, on any executor
+pub fn spawn_and_reply_on<E, F>(executor: &E, env: &mut Env<'_>, work: F) -> NifResult<TermValue>
+where
+    E: Executor + ?Sized,
+    F: FnOnce() -> TermValue + Send + 'static,
+{
+    let caller = crate::names::port_pid(unsafe { &*(env.as_ptr() as *const crate::context::Context) });
+    let reference = fresh_reference();
+    spawn_with(executor, AtomVMReplySender, caller, reference, work)?;
+    Ok(TermValue::Reference(reference))
+}
+
+/// Reply sender using `port::send_from_task`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AtomVMReplySender;
+
+impl ReplySender for AtomVMReplySender {
+    fn send(&self, pid: u32, message: &TermValue) -> NifResult<()> {
+        crate::port::send_from_task(pid, message, &AtomTable::from_global())
+    }
+}
+
+/// One FreeRTOS task per job, deleted when the job returns
+#[derive(Debug, Clone, Copy)]
+pub struct FreeRtosExecutor {
+    /// Stack depth in words, as `xTaskCreate` takes it
+    pub stack_depth: u32,
+    pub priority: u32,
+}
+
+impl Default for FreeRtosExecutor {
+    fn default() -> Self {
+        Self { stack_depth: 4096, priority: 5 }
+    }
+}
+
+extern "C" fn run_job(parameters: *mut c_void) {
+    let job = unsafe { Box::from_raw(parameters as *mut Job) };
+    job();
+    // A FreeRTOS task must not return
+    unsafe { vTaskDelete(core::ptr::null_mut()) }
+}
+
+impl Executor for FreeRtosExecutor {
+    fn spawn(&self, job: Job) -> NifResult<()> {
+        const PD_PASS: i32 = 1;
+        let parameters = Box::into_raw(Box::new(job)) as *mut c_void;
+        let created = unsafe {
+            xTaskCreate(
+                run_job,
+                "avmnif_job\0".as_ptr() as *const c_char,
+                self.stack_depth,
+                parameters,
+                self.priority,
+                core::ptr::null_mut(),
+            )
+        };
+        if created == PD_PASS {
+            Ok(())
+        } else {
+            drop(unsafe { Box::from_raw(parameters as *mut Job) });
+            Err(NifError::OutOfMemory)
+        }
+    }
+}
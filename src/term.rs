@@ -1,6 +1,9 @@
 extern crate alloc;
 
 use core::ffi::c_void;
+use core::fmt;
+use core::marker::PhantomData;
+use crate::contracts;
 use alloc::{string::{String, ToString}, vec::Vec, boxed::Box};
 
 // Import types from atom module - centralized in atom.rs
@@ -40,6 +43,10 @@ pub struct ResourceRef {
 pub enum TermValue {
     // Immediate values
     SmallInt(i32),
+    /// An integer outside `i32`, boxed when it does not fit an immediate
+    ///
+    /// Decoding and `TermValue::integer` only produce it for such values.
+    Integer(i64),
     Atom(AtomIndex),
     Nil,
     
@@ -59,17 +66,80 @@ pub enum TermValue {
     Resource(ResourceRef),
     Float(f64),
     
-    // Error case
-    Invalid,
+    /// A term that could not be decoded (lenient decoding only)
+    Invalid(DecodeError),
+}
+
+/// Why a raw term could not be decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeReason {
+    /// Immediate tag or boxed header not recognized
+    UnknownTag,
+    /// Boxed or list term with a null pointer
+    NullPointer,
+    /// Boxed or list pointer not aligned to a word
+    Misaligned,
+    /// Valid term with no `TermValue` form, such as a closure or an
+    /// integer wider than 64 bits
+    Unsupported,
+    /// Header and contents disagree (sizes, offsets)
+    Corrupt,
+}
+
+/// A raw term that `Term::decode` rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeError {
+    /// The offending word; for nested terms, the innermost bad one
+    pub raw: usize,
+    pub reason: DecodeReason,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.reason {
+            DecodeReason::UnknownTag => "unknown tag",
+            DecodeReason::NullPointer => "null pointer",
+            DecodeReason::Misaligned => "misaligned pointer",
+            DecodeReason::Unsupported => "unsupported term",
+            DecodeReason::Corrupt => "corrupt term",
+        };
+        write!(f, "cannot decode term {:#x}: {}", self.raw, reason)
+    }
+}
+
+impl From<DecodeError> for NifError {
+    fn from(_error: DecodeError) -> Self {
+        NifError::InvalidTerm
+    }
+}
+
+/// What `Term::decode` does with a term it cannot read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecodeMode {
+    /// Fail the whole decode
+    #[default]
+    Strict,
+    /// Put `TermValue::Invalid` in its place and carry on
+    Lenient,
 }
 
 // ── Low-level Term (FFI boundary) ────────────────────────────────────────────
 
 /// Low-level term representation for FFI with AtomVM
 /// This handles the bit-level encoding/decoding
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///
+/// The lifetime is that of the `Env` (NIF call) the term belongs to, so a
+/// term cannot be stored past the call. At the C boundary, where no env
+/// exists yet, terms are `Term<'static>`.
+#[derive(Copy, Clone, PartialEq, Eq)]
 #[repr(transparent)]
-pub struct Term(pub usize);
+pub struct Term<'a>(pub usize, PhantomData<&'a ()>);
+
+impl fmt::Debug for Term<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Term").field(&self.0).finish()
+    }
+}
 
 /// AtomVM Context - opaque pointer to runtime context
 #[repr(C)]
@@ -77,16 +147,233 @@ pub struct Context {
     pub _private: [u8; 0],
 }
 
+/// The environment of one NIF call
+///
+/// Terms read from or created in an env are `Term<'a>` and cannot outlive
+/// it. To keep a value across calls, convert it with `to_value` or hold it
+/// in a resource.
+///
+/// ```
+/// # #[cfg(feature = "sim")] {
+/// use avmnif_rs::term::{Context, Env, NifReturn, Term, TermValue};
+///
+/// pub extern "C" fn double_nif(ctx: *mut Context, argc: i32, argv: *const Term) -> Term<'static> {
+///     let mut env = unsafe { Env::from_raw(ctx) };
+///     let args = unsafe { env.args(argc, argv) };
+///     let result = match args[0].to_value() {
+///         Ok(TermValue::SmallInt(n)) => NifReturn::Value(TermValue::int(n * 2)),
+///         _ => NifReturn::Badarg,
+///     };
+///     Term::from_raw(result.into_term(env.context()).raw())
+/// }
+///
+/// // On the host, through the simulator
+/// # use avmnif_rs::testing::mocks::{MockGlobalContext, MockHeap};
+/// # let global = MockGlobalContext::new();
+/// # let mut process = global.new_context();
+/// # let ctx = process.as_context() as *mut _ as *mut Context;
+/// let mut heap = MockHeap::new(4);
+/// let argv = [heap.encode(TermValue::int(21)).unwrap()];
+/// assert_eq!(double_nif(ctx, 1, argv.as_ptr()).to_value(), Ok(TermValue::int(42)));
+/// # }
+/// ```
+///
+/// Keeping a term beyond its env does not compile:
+///
+/// ```compile_fail
+/// use avmnif_rs::term::{Env, Term};
+///
+/// fn escape<'a>(env: &Env<'a>, raw: usize) -> Term<'static> {
+///     env.term(raw)
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Env<'a> {
+    ctx: *mut Context,
+    _call: PhantomData<&'a mut Context>,
+}
+
+impl<'a> Env<'a> {
+    /// Wrap the context of the current NIF call
+    ///
+    /// # Safety
+    /// `ctx` must be the live context of the call, and the env must not be
+    /// kept after the call returns.
+    pub unsafe fn from_raw(ctx: *mut Context) -> Self {
+        contracts::non_null(ctx, "NIF env context is null");
+        Self {
+            ctx,
+            _call: PhantomData,
+        }
+    }
+
+    /// The raw context pointer
+    pub fn as_ptr(&self) -> *mut Context {
+        self.ctx
+    }
+
+    /// The call's context, for APIs that take `&mut Context`
+    pub fn context(&mut self) -> &mut Context {
+        unsafe { &mut *self.ctx }
+    }
+
+    /// Brand the NIF arguments with this env's lifetime
+    ///
+    /// # Safety
+    /// `argv` must point to `argc` valid terms.
+    pub unsafe fn args(&self, argc: i32, argv: *const Term<'_>) -> &'a [Term<'a>] {
+        if argv.is_null() || argc <= 0 {
+            return &[];
+        }
+        core::slice::from_raw_parts(argv.cast(), argc as usize)
+    }
+
+    /// Brand a raw term word with this env's lifetime
+    pub fn term(&self, raw: usize) -> Term<'a> {
+        Term::from_raw(raw)
+    }
+
+    /// Reserve heap space for building terms, as `HeapGuard::ensure_free`
+    pub fn heap(&mut self, words: usize) -> NifResult<HeapGuard<'_>> {
+        HeapGuard::ensure_free(self.context(), words)
+    }
+
+    /// Allocate a writable binary of `len` bytes to fill in place
+    ///
+    /// Runs `ensure_free` for the binary, so earlier terms built on the
+    /// heap must not be used afterwards.
+    pub fn alloc_binary(&mut self, len: usize) -> NifResult<crate::binary::BinaryBuilder<'_>> {
+        let heap = self.heap(Term::binary_heap_words(len))?;
+        crate::binary::BinaryBuilder::new(heap, len)
+    }
+}
+
+/// An environment that is not tied to a process or a NIF call
+///
+/// Background tasks and ISRs build terms on the env's own heap and send
+/// them with `send_and_clear`. The message is copied into the receiver's
+/// mailbox in one step, and then the heap is emptied for reuse.
+///
+/// ```
+/// # use avmnif_rs::term::NifError;
+/// # #[cfg(feature = "sim")] {
+/// # use avmnif_rs::term::{OwnedEnv, ProcessId, TermValue};
+/// # use avmnif_rs::testing::mocks::MockGlobalContext;
+/// # let global = MockGlobalContext::new();
+/// # let owner = 7;
+/// # let mut samples = [512, 514].into_iter();
+/// # let mut adc = || samples.next();
+/// let mut env = OwnedEnv::new(64).ok_or(NifError::OutOfMemory)?;
+/// while let Some(sample) = adc() {
+///     env.send_and_clear(owner, |heap| heap.encode(TermValue::int(sample)))?;
+/// }
+/// # assert_eq!(global.sent(), [(ProcessId(7), TermValue::int(512)), (ProcessId(7), TermValue::int(514))]);
+/// # }
+/// # Ok::<(), NifError>(())
+/// ```
+pub struct OwnedEnv {
+    heap: *mut Heap,
+    words: usize,
+}
+
+// The heap is only reached through `&mut self`
+unsafe impl Send for OwnedEnv {}
+
+impl OwnedEnv {
+    /// Create an env with a heap of `words` words
+    pub fn new(words: usize) -> Option<Self> {
+        let heap = unsafe { owned_heap_create(words) };
+        if heap.is_null() {
+            None
+        } else {
+            Some(Self { heap, words })
+        }
+    }
+
+    /// Heap size in words
+    pub fn capacity(&self) -> usize {
+        self.words
+    }
+
+    /// Build a message with `build` and send it to `pid`
+    ///
+    /// Nothing is sent if building fails. The heap is cleared either way,
+    /// so terms from `build` cannot be used afterwards.
+    pub fn send_and_clear<F>(&mut self, pid: u32, build: F) -> NifResult<()>
+    where
+        F: for<'e> FnOnce(&mut HeapGuard<'e>) -> NifResult<Term<'e>>,
+    {
+        let result = {
+            let mut guard = HeapGuard::from_heap(unsafe { &mut *self.heap }, self.words);
+            build(&mut guard).map(|term| unsafe {
+                crate::port::port_send_message_from_task(
+                    crate::context::get_global_context(),
+                    pid,
+                    term.raw(),
+                )
+            })
+        };
+        self.clear();
+        result
+    }
+
+    /// Encode `message` and send it to `pid`
+    pub fn send_value_and_clear(&mut self, pid: u32, message: TermValue) -> NifResult<()> {
+        self.send_and_clear(pid, |heap| heap.encode(message))
+    }
+
+    /// Free all terms built so far
+    pub fn clear(&mut self) {
+        unsafe { owned_heap_clear(self.heap) }
+    }
+}
+
+impl Drop for OwnedEnv {
+    fn drop(&mut self) {
+        unsafe { owned_heap_destroy(self.heap) }
+    }
+}
+
 /// AtomVM GlobalContext - runtime global state
 #[repr(C)]
 pub struct GlobalContext {
     pub _private: [u8; 0],
 }
 
+/// Off-heap storage behind a refc binary (AtomVM's `struct RefcBinary`)
+#[repr(C)]
+pub(crate) struct RefcBinary {
+    pub(crate) head: [usize; 2],
+    pub(crate) ref_count: usize,
+    pub(crate) size: usize,
+    pub(crate) resource_type: *const c_void,
+    pub(crate) data: [u8; 0],
+}
+
 /// AtomVM Heap for memory allocation
-#[repr(C)] 
-pub struct Heap {
-    pub _private: [u8; 0],
+pub use avmnif_sys::Heap;
+
+use avmnif_sys::{
+    avmnif_memory_ensure_free, avmnif_memory_heap_alloc, context_heap, context_raise_exception, owned_heap_clear,
+    owned_heap_create, owned_heap_destroy,
+};
+
+// ── Encoding Options ────────────────────────────────────────────────────────
+
+/// What to do with integers that don't fit in an immediate small int
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntOverflow {
+    /// Transparently store the value as a boxed integer
+    #[default]
+    Promote,
+    /// Fail the encode (for heap-free encoding paths)
+    Error,
+}
+
+/// Options controlling `Term::from_value_with`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EncodeOptions {
+    pub int_overflow: IntOverflow,
 }
 
 // ── AtomVM Constants ─────────────────────────────────────────────────────────
@@ -109,12 +396,169 @@ enum TermType {
     Invalid,
 }
 
-impl Term {
+// ── Word Layout ─────────────────────────────────────────────────────────────
+
+/// The parts of AtomVM's term encoding that depend on the word size
+///
+/// Tags and the boxed header format are the same on 32-bit Xtensa/RISC-V
+/// and on 64-bit hosts; what changes is how many payload bits a word has
+/// left. `NATIVE` is the layout of the target being built, picked with
+/// `cfg(target_pointer_width)`. The other layout stays available so host
+/// tests can check 32-bit encodings too, with `u64` holding either word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WordLayout {
+    /// Bits in a machine word
+    pub word_bits: u32,
+}
+
+impl WordLayout {
+    /// 32-bit targets: ESP32 (Xtensa, RISC-V), RP2040, STM32, wasm32
+    pub const W32: Self = Self { word_bits: 32 };
+    /// 64-bit targets: the generic Unix port and host tests
+    pub const W64: Self = Self { word_bits: 64 };
+
+    #[cfg(target_pointer_width = "64")]
+    pub const NATIVE: Self = Self::W64;
+    // Other widths fail the layout checks below unless `unchecked-layout` is on
+    #[cfg(not(target_pointer_width = "64"))]
+    pub const NATIVE: Self = Self::W32;
+
+    /// Tag bits below the value of an integer, atom, pid or port immediate
+    pub const IMMED_SHIFT: u32 = 4;
+    /// Tag bits below the size in a boxed header
+    pub const BOXED_SIZE_SHIFT: u32 = 6;
+
+    pub const fn word_bytes(self) -> usize {
+        (self.word_bits / 8) as usize
+    }
+
+    const fn word_mask(self) -> u64 {
+        u64::MAX >> (64 - self.word_bits)
+    }
+
+    /// Largest integer stored as an immediate
+    pub const fn max_small_int(self) -> i64 {
+        (1i64 << (self.word_bits - Self::IMMED_SHIFT - 1)) - 1
+    }
+
+    /// Smallest integer stored as an immediate
+    pub const fn min_small_int(self) -> i64 {
+        -(1i64 << (self.word_bits - Self::IMMED_SHIFT - 1))
+    }
+
+    /// Words holding a 64-bit payload (refs, floats, int64): high word first on 32-bit
+    pub const fn u64_words(self) -> usize {
+        (64 / self.word_bits) as usize
+    }
+
+    /// Binaries this long or longer are refc binaries
+    pub const fn refc_binary_min(self) -> usize {
+        8 * self.word_bytes()
+    }
+
+    /// Words holding `len` bytes of binary data
+    pub const fn data_words(self, len: usize) -> usize {
+        (len + self.word_bytes() - 1) / self.word_bytes()
+    }
+
+    /// Words a boxed integer needs, header included
+    pub const fn boxed_integer_words(self, value: i64) -> usize {
+        let unused = 64 - self.word_bits;
+        if value >= i64::MIN >> unused && value <= i64::MAX >> unused {
+            2
+        } else {
+            1 + self.u64_words()
+        }
+    }
+
+    /// The immediate for `value`, if it is a small int
+    pub const fn encode_small_int(self, value: i64) -> Option<u64> {
+        if value < self.min_small_int() || value > self.max_small_int() {
+            return None;
+        }
+        Some((((value as u64) << Self::IMMED_SHIFT) | Term::TERM_INTEGER_TAG as u64) & self.word_mask())
+    }
+
+    /// The value of a small int immediate, sign-extended from the word
+    pub const fn decode_small_int(self, raw: u64) -> i64 {
+        let unused = 64 - self.word_bits;
+        ((raw << unused) as i64) >> (unused + Self::IMMED_SHIFT)
+    }
+
+    /// The value of an atom, pid or port immediate
+    pub const fn immediate_value(self, raw: u64) -> u64 {
+        (raw & self.word_mask()) >> Self::IMMED_SHIFT
+    }
+
+    /// A boxed header for `words` words after it
+    pub const fn boxed_header(self, words: usize, tag: usize) -> u64 {
+        (((words as u64) << Self::BOXED_SIZE_SHIFT) | tag as u64) & self.word_mask()
+    }
+
+    /// The words after a boxed header
+    pub const fn boxed_size(self, header: u64) -> usize {
+        ((header & self.word_mask()) >> Self::BOXED_SIZE_SHIFT) as usize
+    }
+
+    /// A 64-bit payload as AtomVM stores it: one word, or high word then low word
+    pub const fn split_u64(self, value: u64) -> [u64; 2] {
+        if self.word_bits == 64 {
+            [value, 0]
+        } else {
+            [value >> 32, value & 0xFFFF_FFFF]
+        }
+    }
+
+    /// The 64-bit payload stored by `split_u64`
+    pub const fn join_u64(self, words: [u64; 2]) -> u64 {
+        if self.word_bits == 64 {
+            words[0]
+        } else {
+            (words[0] << 32) | (words[1] & 0xFFFF_FFFF)
+        }
+    }
+}
+
+// ── Target Layout Checks ────────────────────────────────────────────────────
+
+// The tag constants and boxed layouts below assume AtomVM's term model on a
+// 32- or 64-bit target. Checked while compiling, so building for any other
+// target fails here instead of corrupting heaps at runtime. The
+// `unchecked-layout` feature turns the checks off for bring-up work.
+#[cfg(not(feature = "unchecked-layout"))]
+const _: () = {
+    use core::mem::{align_of, size_of};
+
+    let word = size_of::<usize>();
+    assert!(word == 4 || word == 8, "AtomVM terms need a 32- or 64-bit target");
+    assert!(WordLayout::NATIVE.word_bytes() == word, "the native word layout must match usize");
+    assert!(size_of::<isize>() == word, "isize and usize must have the same width");
+    assert!(size_of::<Term<'static>>() == word, "a term must be exactly one word");
+
+    // Boxed pointers carry the primary tag in their two low bits
+    assert!(align_of::<usize>() >= 4, "heap words must be at least 4-byte aligned");
+    assert!(
+        Term::TERM_PRIMARY_MASK < align_of::<usize>(),
+        "the primary tag must fit in a word pointer's alignment bits"
+    );
+
+    // Floats and references are a header followed by a 64-bit payload
+    assert!(size_of::<f64>() == 8 && size_of::<u64>() == 8, "boxed payloads are 64 bits");
+    assert!(
+        Term::U64_WORDS * word == size_of::<f64>(),
+        "a boxed float payload must fill whole words"
+    );
+
+    // A small int keeps 4 tag bits and must hold at least an i32 on 64-bit
+    assert!(word == 4 || Term::MAX_SMALL_INT >= i32::MAX as i64, "i32 must be immediate on 64-bit targets");
+};
+
+impl<'a> Term<'a> {
     // AtomVM tag constants (from AtomVM source)
-    const TERM_PRIMARY_MASK: usize = 0x3;
+    pub(crate) const TERM_PRIMARY_MASK: usize = 0x3;
     const TERM_PRIMARY_IMMED: usize = 0x3;
-    const TERM_PRIMARY_LIST: usize = 0x1;
-    const TERM_PRIMARY_BOXED: usize = 0x2;
+    pub(crate) const TERM_PRIMARY_LIST: usize = 0x1;
+    pub(crate) const TERM_PRIMARY_BOXED: usize = 0x2;
     
     const TERM_IMMED_TAG_MASK: usize = 0xF;
     const TERM_INTEGER_TAG: usize = 0xF;
@@ -122,19 +566,52 @@ impl Term {
     const TERM_PID_TAG: usize = 0x3;
     const TERM_PORT_TAG: usize = 0x7;
     
-    const TERM_NIL: usize = 0x3B;
+    pub(crate) const TERM_NIL: usize = 0x3B;
     
-    const TERM_BOXED_TAG_MASK: usize = 0x3F;
+    // Boxed header tags, as in AtomVM's term.h. A resource is a refc
+    // binary there, with no tag of its own.
+    pub(crate) const TERM_BOXED_TAG_MASK: usize = 0x3F;
     const TERM_BOXED_TUPLE: usize = 0x00;
-    const TERM_BOXED_POSITIVE_INTEGER: usize = 0x08;
-    const TERM_BOXED_REF: usize = 0x10;
-    const TERM_BOXED_FUN: usize = 0x18;
-    const TERM_BOXED_FLOAT: usize = 0x20;
-    const TERM_BOXED_REFC_BINARY: usize = 0x28;
-    const TERM_BOXED_HEAP_BINARY: usize = 0x30;
-    const TERM_BOXED_SUB_BINARY: usize = 0x38;
-    const TERM_BOXED_MAP: usize = 0x40;
-    const TERM_BOXED_RESOURCE: usize = 0x48;
+    pub(crate) const TERM_BOXED_POSITIVE_INTEGER: usize = 0x08;
+    pub(crate) const TERM_BOXED_NEGATIVE_INTEGER: usize = 0x0C;
+    pub(crate) const TERM_BOXED_REF: usize = 0x10;
+    pub(crate) const TERM_BOXED_FUN: usize = 0x14;
+    pub(crate) const TERM_BOXED_FLOAT: usize = 0x18;
+    pub(crate) const TERM_BOXED_REFC_BINARY: usize = 0x20;
+    pub(crate) const TERM_BOXED_HEAP_BINARY: usize = 0x24;
+    pub(crate) const TERM_BOXED_SUB_BINARY: usize = 0x28;
+    pub(crate) const TERM_BOXED_MAP: usize = 0x2C;
+
+    /// Shift of the value in an integer, atom, pid or port immediate
+    const IMMED_SHIFT: u32 = WordLayout::IMMED_SHIFT;
+    /// Shift of the size in a boxed header
+    pub(crate) const BOXED_SIZE_SHIFT: u32 = WordLayout::BOXED_SIZE_SHIFT;
+
+    /// Words needed to store a 64-bit payload (refs, floats) after a header
+    const U64_WORDS: usize = WordLayout::NATIVE.u64_words();
+
+    /// Payload words of a short reference: just the ticks (`REF_SIZE - 1` in AtomVM)
+    pub(crate) const REF_WORDS: usize = Self::U64_WORDS;
+
+    /// Largest integer stored as an immediate on this target (4 tag bits)
+    pub const MAX_SMALL_INT: i64 = WordLayout::NATIVE.max_small_int();
+    /// Smallest integer stored as an immediate on this target
+    pub const MIN_SMALL_INT: i64 = WordLayout::NATIVE.min_small_int();
+
+    /// Refc binary flag: word 3 points at constant data, not a `RefcBinary`
+    const REFC_BINARY_CONST: usize = 0x1;
+
+    /// Words of a refc binary term plus its entry in the heap's off-heap list
+    pub const REFC_BINARY_WORDS: usize = 6;
+
+    /// Words of a sub-binary: header, length, offset, original binary
+    pub const SUB_BINARY_WORDS: usize = 4;
+
+    /// Binaries this long or longer are refc binaries, shorter ones live on the heap
+    pub const REFC_BINARY_MIN: usize = WordLayout::NATIVE.refc_binary_min();
+
+    /// The non-value a NIF returns after raising an exception
+    pub const INVALID: Self = Term(0, PhantomData);
 
     /// Get raw term value
     pub fn raw(self) -> usize {
@@ -143,58 +620,99 @@ impl Term {
     
     /// Create term from raw value
     pub fn from_raw(raw: usize) -> Self {
-        Term(raw)
+        Term(raw, PhantomData)
     }
 
     /// Decode the low-level type of this term
     fn decode_type(self) -> TermType {
+        self.check_type().unwrap_or(TermType::Invalid)
+    }
+
+    /// Decode the low-level type, saying why when the term is not valid
+    ///
+    /// Pointers are checked for null and word alignment before the boxed
+    /// header is read.
+    fn check_type(self) -> Result<TermType, DecodeReason> {
         if self.0 == Self::TERM_NIL {
-            return TermType::Nil;
+            return Ok(TermType::Nil);
         }
         
         match self.0 & Self::TERM_PRIMARY_MASK {
             Self::TERM_PRIMARY_IMMED => {
                 match self.0 & Self::TERM_IMMED_TAG_MASK {
-                    Self::TERM_INTEGER_TAG => TermType::SmallInt,
-                    Self::TERM_ATOM_TAG => TermType::Atom,
-                    Self::TERM_PID_TAG => TermType::Pid,
-                    Self::TERM_PORT_TAG => TermType::Port,
-                    _ => TermType::Invalid,
+                    Self::TERM_INTEGER_TAG => Ok(TermType::SmallInt),
+                    Self::TERM_ATOM_TAG => Ok(TermType::Atom),
+                    Self::TERM_PID_TAG => Ok(TermType::Pid),
+                    Self::TERM_PORT_TAG => Ok(TermType::Port),
+                    _ => Err(DecodeReason::UnknownTag),
                 }
             }
-            Self::TERM_PRIMARY_LIST => TermType::List,
+            Self::TERM_PRIMARY_LIST => {
+                Self::check_pointer(self.0 & !Self::TERM_PRIMARY_MASK)?;
+                Ok(TermType::List)
+            }
             Self::TERM_PRIMARY_BOXED => {
                 let boxed_ptr = (self.0 & !Self::TERM_PRIMARY_MASK) as *const usize;
-                if boxed_ptr.is_null() {
-                    return TermType::Invalid;
-                }
+                Self::check_pointer(boxed_ptr as usize)?;
                 
                 let header = unsafe { *boxed_ptr };
                 match header & Self::TERM_BOXED_TAG_MASK {
-                    Self::TERM_BOXED_TUPLE => TermType::Tuple,
-                    Self::TERM_BOXED_POSITIVE_INTEGER => TermType::SmallInt,
-                    Self::TERM_BOXED_REF => TermType::Reference,
-                    Self::TERM_BOXED_FUN => TermType::Function,
-                    Self::TERM_BOXED_FLOAT => TermType::Float,
+                    Self::TERM_BOXED_TUPLE => Ok(TermType::Tuple),
+                    Self::TERM_BOXED_POSITIVE_INTEGER |
+                    Self::TERM_BOXED_NEGATIVE_INTEGER => Ok(TermType::SmallInt),
+                    Self::TERM_BOXED_REF => Ok(TermType::Reference),
+                    Self::TERM_BOXED_FUN => Ok(TermType::Function),
+                    Self::TERM_BOXED_FLOAT => Ok(TermType::Float),
+                    Self::TERM_BOXED_REFC_BINARY if unsafe { Self::refc_resource_type(boxed_ptr) }.is_some() => {
+                        Ok(TermType::Resource)
+                    }
                     Self::TERM_BOXED_REFC_BINARY |
                     Self::TERM_BOXED_HEAP_BINARY |
-                    Self::TERM_BOXED_SUB_BINARY => TermType::Binary,
-                    Self::TERM_BOXED_MAP => TermType::Map,
-                    Self::TERM_BOXED_RESOURCE => TermType::Resource,
-                    _ => TermType::Invalid,
+                    Self::TERM_BOXED_SUB_BINARY => Ok(TermType::Binary),
+                    Self::TERM_BOXED_MAP => Ok(TermType::Map),
+                    _ => Err(DecodeReason::UnknownTag),
                 }
             }
-            _ => TermType::Invalid,
+            _ => Err(DecodeReason::UnknownTag),
+        }
+    }
+
+    fn check_pointer(address: usize) -> Result<(), DecodeReason> {
+        if address == 0 {
+            Err(DecodeReason::NullPointer)
+        } else if address % core::mem::align_of::<usize>() != 0 {
+            Err(DecodeReason::Misaligned)
+        } else {
+            Ok(())
         }
     }
 
     // ── Low-level extraction methods ─────────────────────────────────────────
 
     fn extract_small_int(self) -> NifResult<i32> {
+        let value = self.extract_integer()?;
+        i32::try_from(value).map_err(|_| NifError::Other("integer out of range for i32"))
+    }
+
+    /// Extract an immediate or boxed integer
+    fn extract_integer(self) -> NifResult<i64> {
         match self.decode_type() {
+            TermType::SmallInt if self.0 & Self::TERM_PRIMARY_MASK == Self::TERM_PRIMARY_IMMED => {
+                Ok(((self.0 as isize) >> Self::IMMED_SHIFT) as i64)
+            }
             TermType::SmallInt => {
-                let raw_value = (self.0 & !0xF) as i32 >> 4;
-                Ok(raw_value)
+                let boxed_ptr = self.boxed_ptr();
+                let size = unsafe { *boxed_ptr } >> Self::BOXED_SIZE_SHIFT;
+                // One word holds a native int; 32-bit targets use two for int64
+                if size == 1 {
+                    Ok(unsafe { *(boxed_ptr.add(1) as *const isize) } as i64)
+                } else if size == Self::U64_WORDS {
+                    Ok(unsafe { (boxed_ptr.add(1) as *const i64).read_unaligned() })
+                } else if size > Self::U64_WORDS {
+                    Err(NifError::Other("integer wider than 64 bits"))
+                } else {
+                    Err(NifError::BadArg)
+                }
             }
             _ => Err(NifError::BadArg),
         }
@@ -202,7 +720,7 @@ impl Term {
 
     fn extract_atom_index(self) -> NifResult<AtomIndex> {
         match self.decode_type() {
-            TermType::Atom => Ok(AtomIndex((self.0 >> 4) as u32)),
+            TermType::Atom => Ok(AtomIndex((self.0 >> Self::IMMED_SHIFT) as u32)),
             _ => Err(NifError::BadArg),
         }
     }
@@ -212,83 +730,222 @@ impl Term {
             TermType::Tuple => {
                 let boxed_ptr = (self.0 & !Self::TERM_PRIMARY_MASK) as *const usize;
                 let header = unsafe { *boxed_ptr };
-                Ok((header >> 6) as usize)
+                Ok(header >> Self::BOXED_SIZE_SHIFT)
             }
             _ => Err(NifError::BadArg),
         }
     }
 
-    fn extract_tuple_element(self, index: usize) -> NifResult<Term> {
+    fn extract_tuple_element(self, index: usize) -> NifResult<Term<'a>> {
         let arity = self.extract_tuple_arity()?;
+        contracts::require(index < arity, "tuple element index out of arity bounds");
         if index >= arity {
             return Err(NifError::BadArg);
         }
         
-        let boxed_ptr = (self.0 & !Self::TERM_PRIMARY_MASK) as *const usize;
-        let element = unsafe { *boxed_ptr.add(1 + index) };
-        Ok(Term(element))
+        let element = unsafe { *self.boxed_ptr().add(1 + index) };
+        Ok(Term::from_raw(element))
     }
 
-    fn extract_list_head(self) -> NifResult<Term> {
+    fn extract_list_head(self) -> NifResult<Term<'a>> {
         match self.decode_type() {
             TermType::List => {
                 let list_ptr = (self.0 & !Self::TERM_PRIMARY_MASK) as *const usize;
                 let head = unsafe { *list_ptr };
-                Ok(Term(head))
+                Ok(Term::from_raw(head))
             }
             _ => Err(NifError::BadArg),
         }
     }
 
-    fn extract_list_tail(self) -> NifResult<Term> {
+    fn extract_list_tail(self) -> NifResult<Term<'a>> {
         match self.decode_type() {
             TermType::List => {
                 let list_ptr = (self.0 & !Self::TERM_PRIMARY_MASK) as *const usize;
                 let tail = unsafe { *list_ptr.add(1) };
-                Ok(Term(tail))
+                Ok(Term::from_raw(tail))
             }
             _ => Err(NifError::BadArg),
         }
     }
 
-    fn extract_binary_data(self) -> NifResult<&'static [u8]> {
-        match self.decode_type() {
-            TermType::Binary => {
-                let boxed_ptr = (self.0 & !Self::TERM_PRIMARY_MASK) as *const usize;
-                let size = unsafe { *boxed_ptr.add(1) };
-                let data_ptr = unsafe { boxed_ptr.add(2) as *const u8 };
-                Ok(unsafe { core::slice::from_raw_parts(data_ptr, size) })
+    /// Head and tail of a cons cell, `None` for `[]`
+    pub(crate) fn list_cell(self) -> NifResult<Option<(Term<'a>, Term<'a>)>> {
+        if self.0 == Self::TERM_NIL {
+            return Ok(None);
+        }
+        Ok(Some((self.extract_list_head()?, self.extract_list_tail()?)))
+    }
+
+    /// Borrow a binary's bytes, following a sub-binary to its original
+    ///
+    /// # Safety
+    /// The caller chooses `'b` and must not use the slice after the term
+    /// can move or die (a garbage collection, the end of the NIF call).
+    pub(crate) unsafe fn binary_bytes<'b>(self) -> NifResult<&'b [u8]> {
+        if self.decode_type() != TermType::Binary {
+            return Err(NifError::BadArg);
+        }
+        let boxed_ptr = self.boxed_ptr();
+        let size = *boxed_ptr.add(1);
+        match *boxed_ptr & Self::TERM_BOXED_TAG_MASK {
+            Self::TERM_BOXED_SUB_BINARY => {
+                let offset = *boxed_ptr.add(2);
+                let original = Term::from_raw(*boxed_ptr.add(3)).binary_bytes::<'b>()?;
+                let end = offset.checked_add(size).ok_or(NifError::InvalidTerm)?;
+                original.get(offset..end).ok_or(NifError::InvalidTerm)
             }
-            _ => Err(NifError::BadArg),
+            Self::TERM_BOXED_REFC_BINARY => {
+                // Word 2 holds flags, word 3 either the constant data or the RefcBinary
+                let data = *boxed_ptr.add(3);
+                let data = if *boxed_ptr.add(2) & Self::REFC_BINARY_CONST != 0 {
+                    data as *const u8
+                } else {
+                    (*(data as *const RefcBinary)).data.as_ptr()
+                };
+                Ok(core::slice::from_raw_parts(data, size))
+            }
+            _ => Ok(core::slice::from_raw_parts(boxed_ptr.add(2) as *const u8, size)),
+        }
+    }
+
+    /// Resolve a binary to the term a sub-binary would reference
+    ///
+    /// Sub-binaries point at their original; heap binaries cannot be
+    /// shared and yield `None`.
+    fn sub_binary_parent(self, offset: usize) -> NifResult<Option<(Term<'a>, usize)>> {
+        if self.decode_type() != TermType::Binary {
+            return Err(NifError::BadArg);
+        }
+        let boxed_ptr = self.boxed_ptr();
+        match unsafe { *boxed_ptr } & Self::TERM_BOXED_TAG_MASK {
+            Self::TERM_BOXED_SUB_BINARY => {
+                let (base, original) = unsafe { (*boxed_ptr.add(2), Term::from_raw(*boxed_ptr.add(3))) };
+                original.sub_binary_parent(base + offset)
+            }
+            Self::TERM_BOXED_REFC_BINARY => Ok(Some((self, offset))),
+            _ => Ok(None),
         }
     }
 
+    /// Number of pairs in a map: its header counts the keys word and the values
     fn extract_map_size(self) -> NifResult<usize> {
         match self.decode_type() {
             TermType::Map => {
-                let boxed_ptr = (self.0 & !Self::TERM_PRIMARY_MASK) as *const usize;
-                let size = unsafe { *boxed_ptr.add(1) };
-                Ok(size)
+                let header = unsafe { *self.boxed_ptr() };
+                (header >> Self::BOXED_SIZE_SHIFT).checked_sub(1).ok_or(NifError::InvalidTerm)
             }
             _ => Err(NifError::BadArg),
         }
     }
 
-    fn extract_map_key(self, _index: usize) -> NifResult<Term> {
-        // Placeholder - real implementation would traverse map structure
-        Err(NifError::Other("map traversal not implemented"))
+    /// The tuple of a map's keys, at least as long as the map
+    fn extract_map_keys(self) -> NifResult<Term<'a>> {
+        let size = self.extract_map_size()?;
+        let keys = Term::from_raw(unsafe { *self.boxed_ptr().add(1) });
+        if keys.extract_tuple_arity().map_err(|_| NifError::InvalidTerm)? < size {
+            return Err(NifError::InvalidTerm);
+        }
+        Ok(keys)
+    }
+
+    fn extract_map_key(self, index: usize) -> NifResult<Term<'a>> {
+        if index >= self.extract_map_size()? {
+            return Err(NifError::BadArg);
+        }
+        self.extract_map_keys()?.extract_tuple_element(index)
+    }
+
+    fn extract_map_value(self, index: usize) -> NifResult<Term<'a>> {
+        if index >= self.extract_map_size()? {
+            return Err(NifError::BadArg);
+        }
+        Ok(Term::from_raw(unsafe { *self.boxed_ptr().add(2 + index) }))
     }
 
-    fn extract_map_value(self, _index: usize) -> NifResult<Term> {
-        // Placeholder - real implementation would traverse map structure  
-        Err(NifError::Other("map traversal not implemented"))
+    pub(crate) fn boxed_ptr(self) -> *const usize {
+        let ptr = (self.0 & !Self::TERM_PRIMARY_MASK) as *const usize;
+        contracts::non_null(ptr, "boxed term points to null");
+        contracts::aligned(ptr, "boxed term pointer is not word aligned");
+        ptr
+    }
+
+    /// Extract the ticks of a reference
+    ///
+    /// Short references hold just the ticks; process references (from
+    /// monitors and aliases) add the pid after them, which is dropped here.
+    fn extract_reference_id(self) -> NifResult<u64> {
+        match self.decode_type() {
+            TermType::Reference => {
+                let boxed_ptr = self.boxed_ptr();
+                let size = unsafe { *boxed_ptr } >> Self::BOXED_SIZE_SHIFT;
+                if size != Self::REF_WORDS && size != Self::REF_WORDS + 1 {
+                    return Err(NifError::BadArg);
+                }
+                Ok(unsafe { Self::read_u64_words(boxed_ptr.add(1)) })
+            }
+            _ => Err(NifError::BadArg),
+        }
     }
 
+    fn extract_float(self) -> NifResult<f64> {
+        match self.decode_type() {
+            TermType::Float => {
+                let data = unsafe { self.boxed_ptr().add(1) as *const f64 };
+                Ok(unsafe { data.read_unaligned() })
+            }
+            _ => Err(NifError::BadArg),
+        }
+    }
+
+    /// Extract an external fun (`fun M:F/A`); closures have no ADT form
+    fn extract_function_ref(self) -> NifResult<FunctionRef> {
+        match self.decode_type() {
+            TermType::Function => {
+                let boxed_ptr = self.boxed_ptr();
+                if unsafe { *boxed_ptr } >> Self::BOXED_SIZE_SHIFT != 3 {
+                    return Err(NifError::BadArg);
+                }
+                let (module, function, arity) = unsafe {
+                    (Term::from_raw(*boxed_ptr.add(1)), Term::from_raw(*boxed_ptr.add(2)), Term::from_raw(*boxed_ptr.add(3)))
+                };
+                let arity = arity.extract_small_int()?;
+                Ok(FunctionRef {
+                    module: module.extract_atom_index()?,
+                    function: function.extract_atom_index()?,
+                    arity: u8::try_from(arity).map_err(|_| NifError::BadArg)?,
+                })
+            }
+            _ => Err(NifError::BadArg),
+        }
+    }
+
+    /// The `RefcBinary` of a refc binary that holds a resource
+    ///
+    /// # Safety
+    ///
+    /// `boxed_ptr` must point to the header of a refc binary.
+    unsafe fn refc_resource_type(boxed_ptr: *const usize) -> Option<*mut RefcBinary> {
+        if *boxed_ptr.add(2) & Self::REFC_BINARY_CONST != 0 {
+            return None;
+        }
+        let refc = *boxed_ptr.add(3) as *mut RefcBinary;
+        if refc.is_null() || (*refc).resource_type.is_null() {
+            None
+        } else {
+            Some(refc)
+        }
+    }
+
+    /// The object of a resource term
+    ///
+    /// AtomVM keeps resources in refc binaries: the object is the binary's
+    /// data, and the `RefcBinary` records its resource type.
     fn extract_resource_ptr(self) -> NifResult<*mut c_void> {
         match self.decode_type() {
             TermType::Resource => {
-                let ptr = (self.0 & !Self::TERM_PRIMARY_MASK) as *mut c_void;
-                Ok(ptr)
+                let refc = unsafe { Self::refc_resource_type(self.boxed_ptr()) }.ok_or(NifError::BadArg)?;
+                Ok(unsafe { (*refc).data.as_mut_ptr() }.cast())
             }
             _ => Err(NifError::BadArg),
         }
@@ -296,142 +953,390 @@ impl Term {
 
     // ── Low-level encoding methods ───────────────────────────────────────────
 
-    fn encode_small_int(value: i32) -> NifResult<Self> {
-        if value >= -(1 << 27) && value < (1 << 27) {
-            Ok(Term(((value as usize) << 4) | Self::TERM_INTEGER_TAG))
+    pub(crate) fn encode_small_int(value: i32) -> NifResult<Self> {
+        Self::encode_small_int_i64(value as i64)
+    }
+
+    fn encode_small_int_i64(value: i64) -> NifResult<Self> {
+        if (Self::MIN_SMALL_INT..=Self::MAX_SMALL_INT).contains(&value) {
+            Ok(Term::from_raw(((value as isize as usize) << Self::IMMED_SHIFT) | Self::TERM_INTEGER_TAG))
         } else {
             Err(NifError::Other("integer too large for small int"))
         }
     }
 
-    fn encode_atom(AtomIndex(index): AtomIndex) -> NifResult<Self> {
-        Ok(Term(((index as usize) << 4) | Self::TERM_ATOM_TAG))
+    /// Encode an integer, promoting to a boxed integer when it doesn't fit an immediate
+    fn encode_integer(value: i64, overflow: IntOverflow, heap: &mut Heap) -> NifResult<Self> {
+        if let Ok(term) = Self::encode_small_int_i64(value) {
+            return Ok(term);
+        }
+        if overflow == IntOverflow::Error {
+            return Err(NifError::Other("integer too large for small int"));
+        }
+
+        let tag = if value < 0 {
+            Self::TERM_BOXED_NEGATIVE_INTEGER
+        } else {
+            Self::TERM_BOXED_POSITIVE_INTEGER
+        };
+        let words = WordLayout::NATIVE.boxed_integer_words(value);
+        let fits_word = words == 2;
+        let ptr = Self::heap_alloc(heap, words)?;
+        unsafe {
+            *ptr = ((words - 1) << Self::BOXED_SIZE_SHIFT) | tag;
+            if fits_word {
+                *(ptr.add(1) as *mut isize) = value as isize;
+            } else {
+                (ptr.add(1) as *mut i64).write_unaligned(value);
+            }
+        }
+        Ok(Self::from_boxed(ptr))
+    }
+
+    pub(crate) fn encode_atom(AtomIndex(index): AtomIndex) -> NifResult<Self> {
+        Ok(Term::from_raw(((index as usize) << Self::IMMED_SHIFT) | Self::TERM_ATOM_TAG))
     }
 
     fn encode_nil() -> Self {
-        Term(Self::TERM_NIL)
+        Term::from_raw(Self::TERM_NIL)
     }
 
-    #[allow(dead_code)]
-    fn encode_tuple(_elements: Vec<Term>, _heap: &mut Heap) -> NifResult<Self> {
-        // Placeholder - would need actual heap allocation
-        Err(NifError::Other("tuple encoding not implemented"))
+    fn encode_pid(ProcessId(id): ProcessId) -> Self {
+        Term::from_raw(((id as usize) << Self::IMMED_SHIFT) | Self::TERM_PID_TAG)
     }
 
-    #[allow(dead_code)]
-    fn encode_list(_head: Term, _tail: Term, _heap: &mut Heap) -> NifResult<Self> {
-        // Placeholder - would need actual heap allocation
-        Err(NifError::Other("list encoding not implemented"))
+    fn encode_port(PortId(id): PortId) -> Self {
+        Term::from_raw(((id as usize) << Self::IMMED_SHIFT) | Self::TERM_PORT_TAG)
     }
 
-    #[allow(dead_code)]
-    fn encode_binary(_data: &[u8], _heap: &mut Heap) -> NifResult<Self> {
-        // Placeholder - would need actual heap allocation
-        Err(NifError::Other("binary encoding not implemented"))
+    fn encode_reference(RefId(id): RefId, heap: &mut Heap) -> NifResult<Self> {
+        let ptr = Self::heap_alloc(heap, 1 + Self::REF_WORDS)?;
+        unsafe {
+            *ptr = (Self::REF_WORDS << Self::BOXED_SIZE_SHIFT) | Self::TERM_BOXED_REF;
+            Self::write_u64_words(ptr.add(1), id);
+        }
+        Ok(Self::from_boxed(ptr))
     }
 
-    #[allow(dead_code)]
-    fn encode_map(_pairs: Vec<(Term, Term)>, _heap: &mut Heap) -> NifResult<Self> {
-        // Placeholder - would need actual heap allocation
-        Err(NifError::Other("map encoding not implemented"))
+    fn encode_float(value: f64, heap: &mut Heap) -> NifResult<Self> {
+        let words = 1 + Self::U64_WORDS;
+        let ptr = Self::heap_alloc(heap, words)?;
+        unsafe {
+            *ptr = ((words - 1) << Self::BOXED_SIZE_SHIFT) | Self::TERM_BOXED_FLOAT;
+            (ptr.add(1) as *mut f64).write_unaligned(value);
+        }
+        Ok(Self::from_boxed(ptr))
+    }
+
+    /// Encode an external fun: header, module atom, function atom, arity
+    fn encode_function(fun: &FunctionRef, heap: &mut Heap) -> NifResult<Self> {
+        let ptr = Self::heap_alloc(heap, 4)?;
+        unsafe {
+            *ptr = (3 << Self::BOXED_SIZE_SHIFT) | Self::TERM_BOXED_FUN;
+            *ptr.add(1) = Self::encode_atom(fun.module)?.0;
+            *ptr.add(2) = Self::encode_atom(fun.function)?.0;
+            *ptr.add(3) = Self::encode_small_int(fun.arity as i32)?.0;
+        }
+        Ok(Self::from_boxed(ptr))
+    }
+
+    pub(crate) fn from_boxed(ptr: *mut usize) -> Self {
+        Term::from_raw(ptr as usize | Self::TERM_PRIMARY_BOXED)
+    }
+
+    fn heap_alloc(heap: &mut Heap, words: usize) -> NifResult<*mut usize> {
+        let ptr = unsafe { avmnif_memory_heap_alloc(heap, words) };
+        if ptr.is_null() {
+            Err(NifError::OutOfMemory)
+        } else {
+            Ok(ptr)
+        }
+    }
+
+    /// Store a u64 the way AtomVM does: one word on 64-bit, high word first on 32-bit
+    unsafe fn write_u64_words(ptr: *mut usize, value: u64) {
+        if Self::U64_WORDS == 1 {
+            *ptr = value as usize;
+        } else {
+            *ptr = (value >> 32) as usize;
+            *ptr.add(1) = value as u32 as usize;
+        }
+    }
+
+    unsafe fn read_u64_words(ptr: *const usize) -> u64 {
+        if Self::U64_WORDS == 1 {
+            *ptr as u64
+        } else {
+            ((*ptr as u64) << 32) | (*ptr.add(1) as u64 & 0xFFFF_FFFF)
+        }
+    }
+
+    fn encode_tuple(elements: &[Term], heap: &mut Heap) -> NifResult<Self> {
+        let ptr = Self::heap_alloc(heap, 1 + elements.len())?;
+        Ok(unsafe { Self::write_tuple(ptr, elements) })
+    }
+
+    /// Lay out a tuple at `ptr`: header with the arity, then the elements
+    ///
+    /// `ptr` must have room for `1 + elements.len()` words.
+    pub(crate) unsafe fn write_tuple(ptr: *mut usize, elements: &[Term]) -> Self {
+        *ptr = (elements.len() << Self::BOXED_SIZE_SHIFT) | Self::TERM_BOXED_TUPLE;
+        for (i, element) in elements.iter().enumerate() {
+            *ptr.add(1 + i) = element.0;
+        }
+        Self::from_boxed(ptr)
+    }
+
+    /// Heap words of a binary of `len` bytes built with `BinaryBuilder`
+    pub fn binary_heap_words(len: usize) -> usize {
+        if len < Self::REFC_BINARY_MIN {
+            2 + Self::binary_data_words(len)
+        } else {
+            Self::REFC_BINARY_WORDS
+        }
+    }
+
+    fn binary_data_words(len: usize) -> usize {
+        WordLayout::NATIVE.data_words(len)
+    }
+
+    /// Lay out a zeroed heap binary of `len` bytes at `ptr`
+    ///
+    /// `ptr` must have room for `2` words plus `len` bytes rounded up to
+    /// whole words. The bytes start at word 2.
+    pub(crate) unsafe fn write_heap_binary(ptr: *mut usize, len: usize) -> Self {
+        let data_words = Self::binary_data_words(len);
+        *ptr = ((data_words + 1) << Self::BOXED_SIZE_SHIFT) | Self::TERM_BOXED_HEAP_BINARY;
+        *ptr.add(1) = len;
+        core::ptr::write_bytes(ptr.add(2), 0, data_words);
+        Self::from_boxed(ptr)
+    }
+
+    /// Encode a cons cell: the head, then the tail
+    fn encode_list(head: Term, tail: Term, heap: &mut Heap) -> NifResult<Self> {
+        let ptr = Self::heap_alloc(heap, 2)?;
+        unsafe {
+            *ptr = head.0;
+            *ptr.add(1) = tail.0;
+        }
+        Ok(Term::from_raw(ptr as usize | Self::TERM_PRIMARY_LIST))
+    }
+
+    /// Reference `len` bytes of `parent` at `offset` without copying
+    ///
+    /// Refc binaries and sub-binaries get a sub-binary term; a heap binary
+    /// is small enough that its bytes are copied instead.
+    fn encode_sub_binary(parent: Term, offset: usize, len: usize, heap: &mut Heap) -> NifResult<Self> {
+        let bytes = unsafe { parent.binary_bytes()? };
+        if offset.checked_add(len).map_or(true, |end| end > bytes.len()) {
+            return Err(NifError::BadArg);
+        }
+        match parent.sub_binary_parent(offset)? {
+            Some((original, offset)) => {
+                let ptr = Self::heap_alloc(heap, Self::SUB_BINARY_WORDS)?;
+                unsafe {
+                    *ptr = ((Self::SUB_BINARY_WORDS - 1) << Self::BOXED_SIZE_SHIFT) | Self::TERM_BOXED_SUB_BINARY;
+                    *ptr.add(1) = len;
+                    *ptr.add(2) = offset;
+                    *ptr.add(3) = original.0;
+                }
+                Ok(Self::from_boxed(ptr))
+            }
+            None => Self::encode_binary(&bytes[offset..offset + len], heap),
+        }
+    }
+
+    /// Heap words `HeapGuard::sub_binary` needs for a view of `len` bytes of `parent`
+    pub fn sub_binary_heap_words(parent: Term, len: usize) -> NifResult<usize> {
+        match parent.sub_binary_parent(0)? {
+            Some(_) => Ok(Self::SUB_BINARY_WORDS),
+            None => Ok(2 + Self::binary_data_words(len)),
+        }
+    }
+
+    /// Copy `data` into a heap binary
+    ///
+    /// Long binaries are kept on the heap as well, so `heap_words` stays
+    /// exact; `BinaryBuilder` makes refc binaries for large payloads.
+    fn encode_binary(data: &[u8], heap: &mut Heap) -> NifResult<Self> {
+        let ptr = Self::heap_alloc(heap, 2 + Self::binary_data_words(data.len()))?;
+        unsafe {
+            let term = Self::write_heap_binary(ptr, data.len());
+            core::ptr::copy_nonoverlapping(data.as_ptr(), ptr.add(2) as *mut u8, data.len());
+            Ok(term)
+        }
+    }
+
+    /// Encode a map as AtomVM lays it out: a tuple of the keys, then a
+    /// header, a pointer to the keys and the values in the same order
+    fn encode_map(pairs: Vec<(Term, Term)>, heap: &mut Heap) -> NifResult<Self> {
+        let keys: Vec<Term> = pairs.iter().map(|&(key, _)| key).collect();
+        let keys = Self::encode_tuple(&keys, heap)?;
+        let ptr = Self::heap_alloc(heap, 2 + pairs.len())?;
+        unsafe {
+            *ptr = ((1 + pairs.len()) << Self::BOXED_SIZE_SHIFT) | Self::TERM_BOXED_MAP;
+            *ptr.add(1) = keys.0;
+            for (i, (_, value)) in pairs.iter().enumerate() {
+                *ptr.add(2 + i) = value.0;
+            }
+        }
+        Ok(Self::from_boxed(ptr))
     }
 }
 
 // ── Conversion Between ADT and Low-level ─────────────────────────────────────
 
-impl Term {
+impl<'a> Term<'a> {
+    /// Read any integer term (immediate or boxed) as an i64
+    pub fn to_i64(self) -> NifResult<i64> {
+        self.extract_integer()
+    }
+
+    /// Encode an i64, promoting to a boxed integer if needed
+    pub fn from_i64(value: i64, heap: &mut Heap) -> NifResult<Self> {
+        Self::encode_integer(value, IntOverflow::Promote, heap)
+    }
+
     /// Convert low-level term to high-level ADT
+    ///
+    /// Strict: any part that cannot be decoded fails the whole term with
+    /// `InvalidTerm`. Use `decode` for the reason or for lenient decoding.
     pub fn to_value(self) -> NifResult<TermValue> {
-        match self.decode_type() {
-            TermType::SmallInt => {
-                let val = self.extract_small_int()?;
-                Ok(TermValue::SmallInt(val))
-            }
+        self.decode(DecodeMode::Strict).map_err(NifError::from)
+    }
+
+    /// Convert to the ADT, handling undecodable parts according to `mode`
+    pub fn decode(self, mode: DecodeMode) -> Result<TermValue, DecodeError> {
+        match self.decode_checked(mode) {
+            Err(error) if mode == DecodeMode::Lenient => Ok(TermValue::Invalid(error)),
+            result => result,
+        }
+    }
+
+    fn decode_checked(self, mode: DecodeMode) -> Result<TermValue, DecodeError> {
+        let error = |reason| DecodeError { raw: self.0, reason };
+        let corrupt = |_: NifError| error(DecodeReason::Corrupt);
+        match self.check_type().map_err(error)? {
+            TermType::SmallInt => match self.extract_integer() {
+                Ok(value) => Ok(TermValue::integer(value)),
+                Err(NifError::Other(_)) => Err(error(DecodeReason::Unsupported)),
+                Err(other) => Err(corrupt(other)),
+            },
             TermType::Atom => {
-                let index = self.extract_atom_index()?;
+                let index = self.extract_atom_index().map_err(corrupt)?;
                 Ok(TermValue::Atom(index))
             }
             TermType::Nil => Ok(TermValue::Nil),
             TermType::Tuple => {
-                let arity = self.extract_tuple_arity()?;
+                let arity = self.extract_tuple_arity().map_err(corrupt)?;
                 let mut elements = Vec::with_capacity(arity);
                 for i in 0..arity {
-                    let elem_term = self.extract_tuple_element(i)?;
-                    elements.push(elem_term.to_value()?);
+                    let elem_term = self.extract_tuple_element(i).map_err(corrupt)?;
+                    elements.push(elem_term.decode(mode)?);
                 }
                 Ok(TermValue::Tuple(elements))
             }
             TermType::List => {
-                let head_term = self.extract_list_head()?;
-                let tail_term = self.extract_list_tail()?;
-                Ok(TermValue::List(
-                    Box::new(head_term.to_value()?),
-                    Box::new(tail_term.to_value()?)
-                ))
+                // Walk the spine iteratively; only element nesting recurses
+                let mut heads = Vec::new();
+                let mut current = self;
+                while current.decode_type() == TermType::List {
+                    heads.push(current.extract_list_head().map_err(corrupt)?.decode(mode)?);
+                    current = current.extract_list_tail().map_err(corrupt)?;
+                }
+                Ok(TermValue::improper_list(heads, current.decode(mode)?))
             }
             TermType::Binary => {
-                let data = self.extract_binary_data()?;
+                let data = unsafe { self.binary_bytes().map_err(corrupt)? };
                 Ok(TermValue::Binary(data.to_vec()))
             }
             TermType::Map => {
-                let size = self.extract_map_size()?;
+                let size = self.extract_map_size().map_err(corrupt)?;
                 let mut pairs = Vec::with_capacity(size);
                 for i in 0..size {
-                    let key_term = self.extract_map_key(i)?;
-                    let val_term = self.extract_map_value(i)?;
-                    pairs.push((key_term.to_value()?, val_term.to_value()?));
+                    let key_term = self.extract_map_key(i).map_err(corrupt)?;
+                    let val_term = self.extract_map_value(i).map_err(corrupt)?;
+                    pairs.push((key_term.decode(mode)?, val_term.decode(mode)?));
                 }
                 Ok(TermValue::Map(pairs))
             }
             TermType::Resource => {
-                let ptr = self.extract_resource_ptr()?;
+                let ptr = self.extract_resource_ptr().map_err(corrupt)?;
                 Ok(TermValue::Resource(ResourceRef {
                     type_name: "unknown".into(),
                     ptr,
                 }))
             }
             TermType::Pid => {
-                let id = (self.0 >> 4) as u32; // Simplified
+                let id = (self.0 >> Self::IMMED_SHIFT) as u32; // Simplified
                 Ok(TermValue::Pid(ProcessId(id)))
             }
             TermType::Port => {
-                let id = (self.0 >> 4) as u32; // Simplified
+                let id = (self.0 >> Self::IMMED_SHIFT) as u32; // Simplified
                 Ok(TermValue::Port(PortId(id)))
             }
-            _ => Ok(TermValue::Invalid),
+            TermType::Reference => Ok(TermValue::Reference(RefId(self.extract_reference_id().map_err(corrupt)?))),
+            TermType::Float => Ok(TermValue::Float(self.extract_float().map_err(corrupt)?)),
+            // Closures point at a module structure and have no ADT form
+            TermType::Function => self
+                .extract_function_ref()
+                .map(TermValue::Function)
+                .map_err(|_| error(DecodeReason::Unsupported)),
+            TermType::Invalid => Err(error(DecodeReason::UnknownTag)),
         }
     }
     
     /// Convert high-level ADT to low-level term
     #[allow(dead_code)]
     pub fn from_value(value: TermValue, heap: &mut Heap) -> NifResult<Self> {
+        Self::from_value_with(value, heap, EncodeOptions::default())
+    }
+
+    /// Convert high-level ADT to low-level term with explicit encoding options
+    pub fn from_value_with(value: TermValue, heap: &mut Heap, options: EncodeOptions) -> NifResult<Self> {
         match value {
-            TermValue::SmallInt(i) => Self::encode_small_int(i),
+            TermValue::SmallInt(i) => Self::encode_integer(i as i64, options.int_overflow, heap),
+            TermValue::Integer(i) => Self::encode_integer(i, options.int_overflow, heap),
             TermValue::Atom(idx) => Self::encode_atom(idx),
             TermValue::Nil => Ok(Self::encode_nil()),
             
             TermValue::Tuple(elements) => {
                 let term_elements: Result<Vec<Term>, NifError> = elements
                     .into_iter()
-                    .map(|elem| Self::from_value(elem, heap))
+                    .map(|elem| Self::from_value_with(elem, heap, options))
                     .collect();
-                Self::encode_tuple(term_elements?, heap)
+                Self::encode_tuple(&term_elements?, heap)
             }
             
             TermValue::List(head, tail) => {
-                let head_term = Self::from_value(*head, heap)?;
-                let tail_term = Self::from_value(*tail, heap)?;
-                Self::encode_list(head_term, tail_term, heap)
+                // Encode the spine iteratively, building cons cells from the end
+                let mut heads = Vec::new();
+                let mut rest = TermValue::List(head, tail);
+                while let TermValue::List(head, tail) = rest {
+                    heads.push(Self::from_value_with(*head, heap, options)?);
+                    rest = *tail;
+                }
+                let mut list = Self::from_value_with(rest, heap, options)?;
+                for head in heads.into_iter().rev() {
+                    list = Self::encode_list(head, list, heap)?;
+                }
+                Ok(list)
             }
             
             TermValue::Binary(data) => {
                 Self::encode_binary(&data, heap)
             }
             
+            TermValue::Pid(pid) => Ok(Self::encode_pid(pid)),
+            TermValue::Port(port) => Ok(Self::encode_port(port)),
+            TermValue::Reference(reference) => Self::encode_reference(reference, heap),
+            TermValue::Float(value) => Self::encode_float(value, heap),
+            TermValue::Function(fun) => Self::encode_function(&fun, heap),
+            
             TermValue::Map(pairs) => {
                 let term_pairs: Result<Vec<(Term, Term)>, NifError> = pairs
                     .into_iter()
-                    .map(|(k, v)| Ok((Self::from_value(k, heap)?, Self::from_value(v, heap)?)))
+                    .map(|(k, v)| Ok((Self::from_value_with(k, heap, options)?, Self::from_value_with(v, heap, options)?)))
                     .collect();
                 Self::encode_map(term_pairs?, heap)
             }
@@ -441,6 +1346,191 @@ impl Term {
     }
 }
 
+// ── Heap Reservation ────────────────────────────────────────────────────────
+
+impl<'a> Term<'a> {
+    /// Number of heap words `from_value` needs to encode `value`
+    ///
+    /// Immediates need none; every boxed or cons cell is counted, including
+    /// integers that will be promoted to boxed form.
+    pub fn heap_words(value: &TermValue) -> usize {
+        match value {
+            TermValue::SmallInt(_) | TermValue::Integer(_) => {
+                let i = value.as_integer().unwrap_or_default();
+                if (Self::MIN_SMALL_INT..=Self::MAX_SMALL_INT).contains(&i) {
+                    0
+                } else {
+                    WordLayout::NATIVE.boxed_integer_words(i)
+                }
+            }
+            TermValue::Atom(_) | TermValue::Nil | TermValue::Pid(_) | TermValue::Port(_) => 0,
+            TermValue::Reference(_) => 1 + Self::REF_WORDS,
+            TermValue::Float(_) => 1 + Self::U64_WORDS,
+            TermValue::Function(_) => 4,
+            TermValue::Tuple(elements) => {
+                1 + elements.len() + elements.iter().map(Self::heap_words).sum::<usize>()
+            }
+            TermValue::List(_, _) => {
+                let mut words = 0;
+                let mut current = value;
+                while let TermValue::List(head, tail) = current {
+                    words += 2 + Self::heap_words(head);
+                    current = tail;
+                }
+                words + Self::heap_words(current)
+            }
+            TermValue::Map(pairs) => {
+                // Keys tuple plus map header, keys pointer and values
+                let children: usize = pairs
+                    .iter()
+                    .map(|(k, v)| Self::heap_words(k) + Self::heap_words(v))
+                    .sum();
+                3 + 2 * pairs.len() + children
+            }
+            TermValue::Binary(data) => 2 + Self::binary_data_words(data.len()),
+            TermValue::Resource(_) | TermValue::Invalid(_) => 0,
+        }
+    }
+}
+
+/// Reserved heap space for building terms
+///
+/// `ensure_free` runs AtomVM's `memory_ensure_free`, through the platform
+/// glue, up front, so a garbage collection can't move the heap halfway
+/// through building a term. The guard
+/// borrows the context for its whole lifetime and refuses to encode more
+/// than was reserved.
+///
+/// ```
+/// # use avmnif_rs::term::NifError;
+/// # #[cfg(feature = "sim")] {
+/// # use avmnif_rs::term::{Context, HeapGuard, Term, TermValue};
+/// # use avmnif_rs::testing::mocks::MockGlobalContext;
+/// # let global = MockGlobalContext::new();
+/// # let mut process = global.new_context();
+/// # let ctx = unsafe { &mut *(process.as_context() as *mut _ as *mut Context) };
+/// # let (ok_atom, data) = (TermValue::atom("ok", global.atoms()), vec![1, 2, 3]);
+/// let reply = TermValue::tuple(vec![ok_atom, TermValue::binary(data)]);
+/// let mut heap = HeapGuard::ensure_free(ctx, Term::heap_words(&reply))?;
+/// let term = heap.encode(reply.clone())?;
+/// # assert_eq!(term.to_value(), Ok(reply));
+/// # }
+/// # Ok::<(), NifError>(())
+/// ```
+pub struct HeapGuard<'a> {
+    heap: &'a mut Heap,
+    reserved: usize,
+    used: usize,
+}
+
+impl<'a> HeapGuard<'a> {
+    /// Reserve `words` free heap words on the context, collecting garbage if needed
+    ///
+    /// Terms created before this call may be moved by the collection and
+    /// must not be used afterwards.
+    pub fn ensure_free(ctx: &'a mut Context, words: usize) -> NifResult<Self> {
+        let ctx = ctx as *mut Context;
+        if unsafe { avmnif_memory_ensure_free(ctx.cast(), words) } != 0 {
+            return Err(NifError::OutOfMemory);
+        }
+        let heap = unsafe { context_heap(ctx.cast()) };
+        contracts::aligned(heap as *const usize, "context heap is not word aligned");
+        if heap.is_null() {
+            return Err(NifError::InvalidTerm);
+        }
+        Ok(Self {
+            heap: unsafe { &mut *heap },
+            reserved: words,
+            used: 0,
+        })
+    }
+
+    /// Wrap a heap that already has `words` free words (standalone heaps)
+    pub(crate) fn from_heap(heap: &'a mut Heap, words: usize) -> Self {
+        Self {
+            heap,
+            reserved: words,
+            used: 0,
+        }
+    }
+
+    /// Encode a term into the reserved space
+    pub fn encode(&mut self, value: TermValue) -> NifResult<Term<'a>> {
+        self.encode_with(value, EncodeOptions::default())
+    }
+
+    /// Encode a term into the reserved space with explicit options
+    pub fn encode_with(&mut self, value: TermValue, options: EncodeOptions) -> NifResult<Term<'a>> {
+        let needed = Term::heap_words(&value);
+        if needed > self.remaining() {
+            return Err(NifError::OutOfMemory);
+        }
+        let term = Term::from_value_with(value, self.heap, options)?;
+        self.used += needed;
+        Ok(term)
+    }
+
+    /// Encode the canonical form of a term (see `crate::canonical`)
+    ///
+    /// Map keys are written in Erlang term order, so equal maps have the
+    /// same layout whatever order they were built in.
+    pub fn encode_canonical<T: AtomTableOps>(&mut self, value: TermValue, table: &T) -> NifResult<Term<'a>> {
+        self.encode(crate::canonical::canonicalize(value, table))
+    }
+
+    /// Create a sub-binary of `parent` covering `range`, without copying
+    ///
+    /// The parent must be re-read after `ensure_free` (e.g. from the NIF's
+    /// argv), since a collection may have moved it. Reserve
+    /// `Term::sub_binary_heap_words` words.
+    pub fn sub_binary(&mut self, parent: Term, range: core::ops::Range<usize>) -> NifResult<Term<'a>> {
+        let len = range.end.saturating_sub(range.start);
+        let needed = Term::sub_binary_heap_words(parent, len)?;
+        if needed > self.remaining() {
+            return Err(NifError::OutOfMemory);
+        }
+        let term = Term::encode_sub_binary(parent, range.start, len, self.heap)?;
+        self.used += needed;
+        Ok(term)
+    }
+
+    /// Build a tuple of already encoded terms; needs `1 + elements.len()` words
+    pub fn tuple(&mut self, elements: &[Term<'a>]) -> NifResult<Term<'a>> {
+        let heap = self.claim(1 + elements.len())?;
+        Term::encode_tuple(elements, heap)
+    }
+
+    /// Claim `words` and allocate them on the heap, for in-place layouts
+    pub(crate) fn alloc(&mut self, words: usize) -> NifResult<*mut usize> {
+        let heap = self.claim(words)?;
+        Term::heap_alloc(heap, words)
+    }
+
+    /// Words still available in the reservation
+    pub fn remaining(&self) -> usize {
+        self.reserved - self.used
+    }
+
+    /// Words reserved by `ensure_free`
+    pub fn reserved(&self) -> usize {
+        self.reserved
+    }
+
+    /// Raw heap access for custom encoders; the caller accounts for space
+    pub fn heap(&mut self) -> &mut Heap {
+        self.heap
+    }
+
+    /// Count `words` of the reservation as used and return the heap to write them
+    pub fn claim(&mut self, words: usize) -> NifResult<&mut Heap> {
+        if words > self.remaining() {
+            return Err(NifError::OutOfMemory);
+        }
+        self.used += words;
+        Ok(self.heap)
+    }
+}
+
 // ── Functional Operations on TermValue (ADT Methods) ─────────────────────────
 
 impl TermValue {
@@ -451,6 +1541,15 @@ impl TermValue {
             _ => None,
         }
     }
+
+    /// Pattern match on integers of either width
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            TermValue::SmallInt(i) => Some(*i as i64),
+            TermValue::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
     
     /// Pattern match on atoms
     pub fn as_atom(&self) -> Option<AtomIndex> {
@@ -486,79 +1585,54 @@ impl TermValue {
         self.is_nil()
     }
     
+    /// Iterate over list elements without recursion
+    ///
+    /// Stops at the first non-cons cell; `ListIter::tail` then gives the
+    /// list's tail (`Nil` for proper lists). Non-lists yield nothing.
+    pub fn iter_list(&self) -> ListIter<'_> {
+        ListIter { current: self }
+    }
+
     /// Fold over list elements (functional programming!)
     pub fn fold_list<T, F>(&self, init: T, f: F) -> T 
     where 
         F: Fn(T, &TermValue) -> T,
     {
-        match self {
-            TermValue::Nil => init,
-            TermValue::List(head, tail) => {
-                let acc = f(init, head);
-                tail.fold_list(acc, f)
-            }
-            _ => init, // Not a list
-        }
+        self.iter_list().fold(init, f)
     }
     
     /// Map over list elements  
+    ///
+    /// An improper tail is kept as-is; non-lists are returned unchanged.
     pub fn map_list<F>(&self, f: F) -> TermValue
     where
         F: Fn(&TermValue) -> TermValue + Clone,
     {
-        match self {
-            TermValue::Nil => TermValue::Nil,
-            TermValue::List(head, tail) => {
-                TermValue::List(
-                    Box::new(f(head)),
-                    Box::new(tail.map_list(f))
-                )
-            }
-            _ => self.clone(), // Not a list
-        }
+        let mut iter = self.iter_list();
+        let mapped: Vec<TermValue> = iter.by_ref().map(&f).collect();
+        Self::improper_list(mapped, iter.tail().clone())
     }
 
     /// Filter list elements
+    ///
+    /// An improper tail is kept as-is; non-lists are returned unchanged.
     pub fn filter_list<F>(&self, predicate: F) -> TermValue
     where
         F: Fn(&TermValue) -> bool + Clone,
     {
-        match self {
-            TermValue::Nil => TermValue::Nil,
-            TermValue::List(head, tail) => {
-                let filtered_tail = tail.filter_list(predicate.clone());
-                if predicate(head) {
-                    TermValue::List(head.clone(), Box::new(filtered_tail))
-                } else {
-                    filtered_tail
-                }
-            }
-            _ => self.clone(),
-        }
+        let mut iter = self.iter_list();
+        let kept: Vec<TermValue> = iter.by_ref().filter(|elem| predicate(elem)).cloned().collect();
+        Self::improper_list(kept, iter.tail().clone())
     }
 
     /// Get list length
     pub fn list_length(&self) -> usize {
-        self.fold_list(0, |acc, _| acc + 1)
+        self.iter_list().count()
     }
 
     /// Convert list to Vec
     pub fn list_to_vec(&self) -> Vec<TermValue> {
-        let mut result = Vec::new();
-        let mut current = self;
-        
-        loop {
-            match current {
-                TermValue::Nil => break,
-                TermValue::List(head, tail) => {
-                    result.push((**head).clone());
-                    current = tail;
-                }
-                _ => break,
-            }
-        }
-        
-        result
+        self.iter_list().cloned().collect()
     }
     
     /// Get map value by key (functional lookup)
@@ -609,6 +1683,44 @@ impl TermValue {
     pub fn from_vec(elements: Vec<TermValue>) -> TermValue {
         Self::from_iter(elements)
     }
+
+    /// Construct a list ending in `tail` instead of `Nil`
+    pub fn improper_list(elements: Vec<TermValue>, tail: TermValue) -> TermValue {
+        elements
+            .into_iter()
+            .rev()
+            .fold(tail, |acc, elem| TermValue::List(Box::new(elem), Box::new(acc)))
+    }
+}
+
+/// Iterator over the elements of a `TermValue` list, see `TermValue::iter_list`
+#[derive(Debug, Clone)]
+pub struct ListIter<'a> {
+    current: &'a TermValue,
+}
+
+impl<'a> ListIter<'a> {
+    /// The part of the list not yet iterated
+    ///
+    /// After the iterator is exhausted this is `Nil` for a proper list, or
+    /// the improper tail otherwise.
+    pub fn tail(&self) -> &'a TermValue {
+        self.current
+    }
+}
+
+impl<'a> Iterator for ListIter<'a> {
+    type Item = &'a TermValue;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.current {
+            TermValue::List(head, tail) => {
+                self.current = tail;
+                Some(head)
+            }
+            _ => None,
+        }
+    }
 }
 
 // ── Generic Smart Constructors ──────────────────────────────────────────────
@@ -617,6 +1729,11 @@ impl TermValue {
     pub fn int(value: i32) -> Self {
         TermValue::SmallInt(value)
     }
+
+    /// An integer, as `SmallInt` when it fits an `i32`
+    pub fn integer(value: i64) -> Self {
+        i32::try_from(value).map_or(TermValue::Integer(value), TermValue::SmallInt)
+    }
     
     /// Create atom using any atom table (GENERIC!)
     pub fn atom<T: AtomTableOps>(name: &str, table: &T) -> Self {
@@ -655,10 +1772,25 @@ impl TermValue {
     pub fn float(value: f64) -> Self {
         TermValue::Float(value)
     }
+
+    /// Build a charlist (list of code points), as Erlang's `"..."` literals
+    pub fn charlist(text: &str) -> Self {
+        Self::from_vec(text.chars().map(|c| TermValue::SmallInt(c as i32)).collect())
+    }
 }
 
 // ── Generic Convenience Methods ─────────────────────────────────────────────
 
+/// How the integers of a charlist map to characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Charset {
+    /// Unicode code points (`io_lib:format`, `unicode:characters_to_list`)
+    #[default]
+    Unicode,
+    /// Bytes 0..=255 (`binary_to_list`, latin1 I/O)
+    Latin1,
+}
+
 impl TermValue {
     /// Extract integer with default
     pub fn to_int_or(&self, default: i32) -> i32 {
@@ -700,6 +1832,57 @@ impl TermValue {
         }
     }
 
+    /// Convert a charlist to a `String`
+    ///
+    /// Returns `None` for improper lists, non-integer elements and
+    /// integers outside the charset. `Nil` is the empty string.
+    pub fn charlist_to_string(&self, charset: Charset) -> Option<String> {
+        let mut out = String::new();
+        let mut iter = self.iter_list();
+        for elem in iter.by_ref() {
+            let code = u32::try_from(elem.as_int()?).ok()?;
+            let c = match charset {
+                Charset::Unicode => char::from_u32(code)?,
+                Charset::Latin1 => char::from(u8::try_from(code).ok()?),
+            };
+            out.push(c);
+        }
+        if iter.tail().is_nil() {
+            Some(out)
+        } else {
+            None
+        }
+    }
+
+    /// Read text passed as a UTF-8 binary, a Unicode charlist or iodata
+    ///
+    /// A flat list of integers is read as code points. Any other list of
+    /// binaries and bytes is flattened as iodata and must be UTF-8.
+    pub fn as_string(&self) -> Option<String> {
+        match self {
+            TermValue::Binary(bytes) => String::from_utf8(bytes.clone()).ok(),
+            TermValue::List(_, _) | TermValue::Nil => self
+                .charlist_to_string(Charset::Unicode)
+                .or_else(|| String::from_utf8(self.iodata_bytes()?.into_owned()).ok()),
+            _ => None,
+        }
+    }
+
+    /// Bytes of a binary or iodata, borrowed when they are already contiguous
+    pub fn iodata_bytes(&self) -> Option<alloc::borrow::Cow<'_, [u8]>> {
+        crate::iolist::flatten(self).ok()
+    }
+
+    /// Flatten iodata into one buffer, as `iolist_to_binary/1`
+    pub fn iolist_to_binary(&self) -> NifResult<Vec<u8>> {
+        crate::iolist::IoList::from_term(self).map(|iolist| iolist.to_vec())
+    }
+
+    /// Number of bytes in iodata, as `iolist_size/1`
+    pub fn iolist_size(&self) -> NifResult<usize> {
+        crate::iolist::IoList::from_term(self).map(|iolist| iolist.len())
+    }
+
     /// Get atom as string using any atom table (GENERIC!)
     pub fn as_atom_str<T: AtomTableOps>(&self, table: &T) -> Option<String> {
         match self.as_atom() {
@@ -757,8 +1940,222 @@ impl From<&'static str> for NifError {
     }
 }
 
+impl NifError {
+    /// The atom describing the error, as used in `{error, Reason}`
+    pub fn reason(&self) -> &'static str {
+        match self {
+            NifError::BadArg => "badarg",
+            NifError::BadArity => "badarity",
+            NifError::OutOfMemory => "out_of_memory",
+            NifError::SystemLimit => "system_limit",
+            NifError::InvalidTerm => "invalid_term",
+            NifError::Other(reason) => reason,
+        }
+    }
+
+    /// `{error, Reason}` for the error
+    pub fn to_error_tuple<T: AtomTableOps>(&self, table: &T) -> TermValue {
+        TermValue::tuple(alloc::vec![
+            TermValue::atom("error", table),
+            TermValue::atom(self.reason(), table),
+        ])
+    }
+}
+
 pub type NifResult<T> = core::result::Result<T, NifError>;
 
+// ── Exceptions ───────────────────────────────────────────────────────────────
+
+/// Erlang exception class
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub enum ExceptionClass {
+    Error = 0,
+    Throw = 1,
+    Exit = 2,
+}
+
+impl ExceptionClass {
+    /// Name of the class atom
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExceptionClass::Error => "error",
+            ExceptionClass::Throw => "throw",
+            ExceptionClass::Exit => "exit",
+        }
+    }
+}
+
+/// What a NIF hands back to the VM: a value or an exception
+///
+/// Mirrors C NIFs, which either return a term or set the exception
+/// registers and return the invalid term. `into_term` does the latter for
+/// the exception variants.
+///
+/// ```
+/// # #[cfg(feature = "sim")] {
+/// use avmnif_rs::atom::AtomTable;
+/// use avmnif_rs::term::{Context, ExceptionClass, NifReturn, Term, TermValue};
+///
+/// fn div_nif<'a>(_ctx: &mut Context, args: &[Term<'a>]) -> NifReturn<'a> {
+///     let table = AtomTable::from_global();
+///     match (args[0].to_value(), args[1].to_value()) {
+///         (Ok(TermValue::SmallInt(_)), Ok(TermValue::SmallInt(0))) => {
+///             NifReturn::Raise(ExceptionClass::Error, TermValue::atom("badarith", &table))
+///         }
+///         (Ok(TermValue::SmallInt(a)), Ok(TermValue::SmallInt(b))) => NifReturn::Value(TermValue::int(a / b)),
+///         _ => NifReturn::Badarg,
+///     }
+/// }
+///
+/// // On the host, through the simulator
+/// # use avmnif_rs::testing::mocks::{MockGlobalContext, MockHeap};
+/// # let global = MockGlobalContext::new();
+/// # let mut process = global.new_context();
+/// # let ctx = unsafe { &mut *(process.as_context() as *mut _ as *mut Context) };
+/// let mut heap = MockHeap::new(4);
+/// let args = [heap.encode(TermValue::int(7)).unwrap(), heap.encode(TermValue::int(0)).unwrap()];
+/// let result = div_nif(ctx, &args).into_term(ctx);
+/// assert_eq!(result, Term::INVALID);
+/// assert_eq!(process.exception(), Some(&(ExceptionClass::Error as i32, TermValue::atom("badarith", global.atoms()))));
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum NifReturn<'a> {
+    /// Already encoded result
+    Term(Term<'a>),
+    /// Result still to be encoded on the caller's heap
+    Value(TermValue),
+    /// Raise `error:badarg`
+    Badarg,
+    /// Raise an exception with an arbitrary reason
+    Raise(ExceptionClass, TermValue),
+    /// Raise the exception matching a `NifError`
+    Error(NifError),
+}
+
+impl<'a> NifReturn<'a> {
+    /// Produce the term to return from the NIF, raising if needed
+    pub fn into_term<'c>(self, ctx: &'c mut Context) -> Term<'c>
+    where
+        'a: 'c,
+    {
+        match self {
+            NifReturn::Term(term) => term,
+            NifReturn::Value(value) => {
+                let result = HeapGuard::ensure_free(ctx, Term::heap_words(&value))
+                    .and_then(|mut heap| heap.encode(value))
+                    .map(Term::raw);
+                match result {
+                    Ok(raw) => Term::from_raw(raw),
+                    Err(error) => raise_nif_error(ctx, &error),
+                }
+            }
+            NifReturn::Badarg => raise_badarg(ctx),
+            NifReturn::Raise(class, reason) => raise(ctx, class, &reason),
+            NifReturn::Error(error) => raise_nif_error(ctx, &error),
+        }
+    }
+}
+
+impl<'a> From<Term<'a>> for NifReturn<'a> {
+    fn from(term: Term<'a>) -> Self {
+        NifReturn::Term(term)
+    }
+}
+
+impl From<TermValue> for NifReturn<'_> {
+    fn from(value: TermValue) -> Self {
+        NifReturn::Value(value)
+    }
+}
+
+impl From<NifError> for NifReturn<'_> {
+    fn from(error: NifError) -> Self {
+        match error {
+            NifError::BadArg => NifReturn::Badarg,
+            other => NifReturn::Error(other),
+        }
+    }
+}
+
+impl<'a, T: Into<NifReturn<'a>>> From<NifResult<T>> for NifReturn<'a> {
+    fn from(result: NifResult<T>) -> Self {
+        match result {
+            Ok(value) => value.into(),
+            Err(error) => error.into(),
+        }
+    }
+}
+
+/// A new reference on the calling process's heap, as `make_ref/0` returns
+///
+/// For async replies: hand the reference back from the NIF and tag the
+/// later message with it. The term lives as long as the env's call.
+pub fn make_ref<'a>(env: &mut Env<'a>) -> NifResult<Term<'a>> {
+    let reference = crate::task::fresh_reference();
+    let raw = env.heap(1 + Term::REF_WORDS)?.encode(TermValue::Reference(reference))?.raw();
+    Ok(env.term(raw))
+}
+
+/// Raise an exception in the calling process; return the result from the NIF
+///
+/// If the reason cannot be built on the heap, `error:out_of_memory` is
+/// raised instead.
+pub fn raise(ctx: &mut Context, class: ExceptionClass, reason: &TermValue) -> Term<'static> {
+    let result = HeapGuard::ensure_free(ctx, Term::heap_words(reason))
+        .and_then(|mut heap| heap.encode(reason.clone()))
+        .map(Term::raw);
+    match result {
+        Ok(raw) => raise_term(ctx, class, Term::from_raw(raw)),
+        Err(_) => raise_atom(ctx, ExceptionClass::Error, "out_of_memory"),
+    }
+}
+
+/// Raise `error:badarg`
+pub fn raise_badarg(ctx: &mut Context) -> Term<'static> {
+    raise_atom(ctx, ExceptionClass::Error, "badarg")
+}
+
+/// Raise `error:Reason`
+pub fn raise_error(ctx: &mut Context, reason: &TermValue) -> Term<'static> {
+    raise(ctx, ExceptionClass::Error, reason)
+}
+
+/// Throw `Value`, as `throw/1` does
+pub fn raise_throw(ctx: &mut Context, value: &TermValue) -> Term<'static> {
+    raise(ctx, ExceptionClass::Throw, value)
+}
+
+/// Raise `exit:Reason`
+pub fn raise_exit(ctx: &mut Context, reason: &TermValue) -> Term<'static> {
+    raise(ctx, ExceptionClass::Exit, reason)
+}
+
+/// Raise an already encoded reason term
+pub fn raise_term(ctx: &mut Context, class: ExceptionClass, reason: Term<'_>) -> Term<'static> {
+    unsafe { context_raise_exception((ctx as *mut Context).cast(), class as core::ffi::c_int, reason.raw()) };
+    Term::INVALID
+}
+
+fn raise_atom(ctx: &mut Context, class: ExceptionClass, name: &str) -> Term<'static> {
+    let table = crate::atom::AtomTable::from_global();
+    let reason = table.ensure_atom_str(name).ok().and_then(|index| Term::encode_atom(index).ok());
+    match reason {
+        Some(reason) => raise_term(ctx, class, reason),
+        None => Term::INVALID,
+    }
+}
+
+fn raise_nif_error(ctx: &mut Context, error: &NifError) -> Term<'static> {
+    let reason = match error {
+        NifError::OutOfMemory => "out_of_memory",
+        NifError::SystemLimit => "system_limit",
+        _ => "badarg",
+    };
+    raise_atom(ctx, ExceptionClass::Error, reason)
+}
+
 // ── Generic Constructor Macros ──────────────────────────────────────────────
 
 /// These macros now require an atom table parameter for full genericity
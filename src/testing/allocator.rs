@@ -0,0 +1,145 @@
+//! Global allocator testing suite
+
+use crate::allocator::{AllocStats, AtomVMAllocator, RawHeap};
+use alloc::alloc::{alloc, dealloc, Layout};
+use core::alloc::GlobalAlloc;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+const HEADER: usize = 16;
+
+/// `malloc` on the host heap, with the size kept in a header like a libc
+#[derive(Default)]
+struct HostHeap {
+    live: AtomicUsize,
+}
+
+unsafe impl RawHeap for HostHeap {
+    const ALIGN: usize = HEADER;
+
+    unsafe fn malloc(&self, size: usize) -> *mut u8 {
+        let block = alloc(Layout::from_size_align(size + HEADER, HEADER).unwrap());
+        if block.is_null() {
+            return block;
+        }
+        block.cast::<usize>().write(size);
+        self.live.fetch_add(1, Ordering::Relaxed);
+        block.add(HEADER)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, size: usize) -> *mut u8 {
+        let old_size = ptr.sub(HEADER).cast::<usize>().read();
+        let new_ptr = self.malloc(size);
+        if !new_ptr.is_null() {
+            core::ptr::copy_nonoverlapping(ptr, new_ptr, old_size.min(size));
+            self.free(ptr);
+        }
+        new_ptr
+    }
+
+    unsafe fn free(&self, ptr: *mut u8) {
+        let block = ptr.sub(HEADER);
+        let size = block.cast::<usize>().read();
+        dealloc(block, Layout::from_size_align(size + HEADER, HEADER).unwrap());
+        self.live.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A heap that is always full
+struct FullHeap;
+
+unsafe impl RawHeap for FullHeap {
+    const ALIGN: usize = 8;
+
+    unsafe fn malloc(&self, _size: usize) -> *mut u8 {
+        core::ptr::null_mut()
+    }
+
+    unsafe fn realloc(&self, _ptr: *mut u8, _size: usize) -> *mut u8 {
+        core::ptr::null_mut()
+    }
+
+    unsafe fn free(&self, _ptr: *mut u8) {}
+}
+
+fn allocator() -> AtomVMAllocator<HostHeap> {
+    AtomVMAllocator::with_heap(HostHeap::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_allocations() {
+        let allocator = allocator();
+        let small = Layout::from_size_align(24, 8).unwrap();
+        let large = Layout::from_size_align(100, 8).unwrap();
+        unsafe {
+            let a = allocator.alloc(small);
+            let b = allocator.alloc(large);
+            assert_eq!(allocator.stats(), AllocStats { in_use: 124, peak: 124, allocations: 2, failures: 0 });
+            allocator.dealloc(a, small);
+            allocator.dealloc(b, large);
+        }
+        assert_eq!(allocator.stats(), AllocStats { in_use: 0, peak: 124, allocations: 2, failures: 0 });
+    }
+
+    #[test]
+    fn test_over_aligned_blocks() {
+        let allocator = allocator();
+        for align in [32, 64, 4096] {
+            let layout = Layout::from_size_align(40, align).unwrap();
+            unsafe {
+                let ptr = allocator.alloc(layout);
+                assert!(!ptr.is_null());
+                assert_eq!(ptr as usize % align, 0);
+                ptr.write_bytes(0xAB, 40);
+                allocator.dealloc(ptr, layout);
+            }
+        }
+        assert_eq!(allocator.stats().in_use, 0);
+    }
+
+    #[test]
+    fn test_realloc_keeps_contents_and_alignment() {
+        let allocator = allocator();
+        for align in [8, 64] {
+            let layout = Layout::from_size_align(16, align).unwrap();
+            unsafe {
+                let ptr = allocator.alloc(layout);
+                for i in 0..16 {
+                    ptr.add(i).write(i as u8);
+                }
+                let grown = allocator.realloc(ptr, layout, 256);
+                assert_eq!(grown as usize % align, 0);
+                assert_eq!(core::slice::from_raw_parts(grown, 16), &(0..16).collect::<alloc::vec::Vec<u8>>()[..]);
+                assert_eq!(allocator.stats().in_use, 256);
+                allocator.dealloc(grown, Layout::from_size_align(256, align).unwrap());
+            }
+        }
+        assert_eq!(allocator.stats().in_use, 0);
+    }
+
+    #[test]
+    fn test_every_block_freed() {
+        let allocator = allocator();
+        let layout = Layout::from_size_align(8, 128).unwrap();
+        unsafe {
+            let ptr = allocator.alloc(layout);
+            let ptr = allocator.realloc(ptr, layout, 64);
+            allocator.dealloc(ptr, Layout::from_size_align(64, 128).unwrap());
+        }
+        assert_eq!(allocator.heap().live.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_out_of_memory_is_null() {
+        let allocator = AtomVMAllocator::with_heap(FullHeap);
+        let layout = Layout::from_size_align(32, 8).unwrap();
+        unsafe {
+            assert!(allocator.alloc(layout).is_null());
+            assert!(allocator.alloc(Layout::from_size_align(32, 64).unwrap()).is_null());
+        }
+        assert_eq!(allocator.stats(), AllocStats { in_use: 0, peak: 0, allocations: 0, failures: 2 });
+    }
+}
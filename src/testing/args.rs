@@ -0,0 +1,132 @@
+//! Positional NIF argument testing suite
+//!
+//! Lays out argument terms by hand in host memory and decodes them
+//! through `Args`.
+
+use crate::args::{ArgError, Args};
+use crate::term::{NifError, Term, TermValue};
+use alloc::{string::ToString, vec, vec::Vec};
+
+const WORD: usize = core::mem::size_of::<usize>();
+const NIL: usize = 0x3B;
+
+fn small(value: i32) -> Term<'static> {
+    Term::from_raw(((value as usize) << 4) | 0xF)
+}
+
+/// Heap binary: header, byte length, then the bytes packed into words
+fn heap_binary(bytes: &[u8]) -> Vec<usize> {
+    let data_words = (bytes.len() + WORD - 1) / WORD;
+    let mut words = vec![0usize; 2 + data_words];
+    words[0] = ((1 + data_words) << 6) | 0x24;
+    words[1] = bytes.len();
+    unsafe {
+        core::ptr::copy_nonoverlapping(bytes.as_ptr(), words.as_mut_ptr().add(2) as *mut u8, bytes.len());
+    }
+    words
+}
+
+/// Cons cells for `elements`, each cell `[Head, Tail]`, ending in `tail`
+fn list_cells(elements: &[Term], tail: usize) -> Vec<[usize; 2]> {
+    let mut cells: Vec<[usize; 2]> = elements.iter().map(|element| [element.raw(), tail]).collect();
+    for i in (0..cells.len().saturating_sub(1)).rev() {
+        cells[i][1] = &cells[i + 1] as *const [usize; 2] as usize | 0x1;
+    }
+    cells
+}
+
+fn list(cells: &[[usize; 2]]) -> Term<'_> {
+    match cells.first() {
+        Some(first) => Term::from_raw(first as *const [usize; 2] as usize | 0x1),
+        None => Term::from_raw(NIL),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_in_order() {
+        let name = heap_binary(b"uart0");
+        let argv = [small(-3), Term::from_raw(name.as_ptr() as usize | 0x2), small(9)];
+        let mut args = Args::new(&argv);
+
+        let (offset, name) = args.decode2::<i64, &str>().unwrap();
+        assert_eq!((offset, name), (-3, "uart0"));
+        assert_eq!(args.position(), 2);
+        assert_eq!(args.decode::<u32>(), Ok(9));
+        assert_eq!(args.remaining(), 0);
+        assert_eq!(args.finish(), Ok(()));
+
+        // Positional access leaves the cursor alone
+        assert_eq!(args.arg::<&[u8]>(1), Ok(&b"uart0"[..]));
+    }
+
+    #[test]
+    fn test_errors_name_the_position() {
+        let argv = [small(1), Term::from_raw(NIL)];
+        let mut args = Args::new(&argv);
+
+        let error = args.decode2::<i64, i64>().unwrap_err();
+        assert_eq!(error, ArgError::Invalid { position: 2, expected: "integer" });
+        assert_eq!(error.to_string(), "argument 2: expected integer");
+        assert_eq!(error.position(), Some(2));
+        assert_eq!(NifError::from(error), NifError::BadArg);
+
+        let mut args = Args::new(&argv);
+        assert_eq!(
+            args.decode3::<i64, TermValue, i64>().unwrap_err().to_string(),
+            "argument 3: missing"
+        );
+        assert_eq!(args.arg::<u32>(0), Ok(1));
+        assert_eq!(
+            Args::new(&[small(-1)]).decode::<u32>(),
+            Err(ArgError::Invalid { position: 1, expected: "non-negative 32-bit integer" })
+        );
+    }
+
+    #[test]
+    fn test_arity_checks() {
+        let argv = [small(1), small(2), small(3)];
+        let mut args = Args::new(&argv);
+        assert_eq!(args.expect_arity(3), Ok(()));
+        assert_eq!(args.expect_arity(2).unwrap_err().to_string(), "expected 2 arguments, got 3");
+
+        args.decode::<i32>().unwrap();
+        assert_eq!(args.finish(), Err(ArgError::Arity { expected: 1, found: 3 }));
+    }
+
+    #[test]
+    fn test_rest_as_list() {
+        let argv = [small(0), small(10), small(20)];
+        let mut args = Args::new(&argv);
+        assert_eq!(args.decode::<i32>(), Ok(0));
+        assert_eq!(args.rest_as_list::<i32>(), Ok(vec![10, 20]));
+        assert_eq!(args.rest_as_list::<i32>(), Ok(vec![]));
+
+        let argv = [small(0), small(1), Term::from_raw(NIL)];
+        let mut args = Args::new(&argv);
+        assert_eq!(
+            args.rest_as_list::<i64>(),
+            Err(ArgError::Invalid { position: 3, expected: "integer" })
+        );
+    }
+
+    #[test]
+    fn test_list_argument() {
+        let cells = list_cells(&[small(4), small(5), small(6)], NIL);
+        let argv = [list(&cells), list(&[])];
+        let mut args = Args::new(&argv);
+        assert_eq!(args.decode2::<Vec<u32>, Vec<u32>>(), Ok((vec![4, 5, 6], vec![])));
+
+        // An improper list or a wrong element fails the whole argument
+        let improper = list_cells(&[small(4)], small(5).raw());
+        let wrong = list_cells(&[small(4), Term::from_raw(NIL)], NIL);
+        let argv = [list(&improper), list(&wrong)];
+        let args = Args::new(&argv);
+        assert_eq!(args.arg::<Vec<i64>>(0), Err(ArgError::Invalid { position: 1, expected: "proper list" }));
+        assert!(args.arg::<Vec<i64>>(1).is_err());
+        assert_eq!(args.arg::<TermValue>(1).unwrap().iter_list().count(), 2);
+    }
+}
@@ -0,0 +1,124 @@
+//! Async mailbox and job testing suite
+
+use crate::asynch::{spawn_async_with, AsyncJobs, Mailbox};
+use crate::sync::SpinLock;
+use crate::task::ReplySender;
+use crate::term::{NifError, NifResult, RefId, TermValue};
+use alloc::{sync::Arc, task::Wake, vec::Vec};
+use core::future::Future;
+use core::pin::pin;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::task::{Context, Poll, Waker};
+
+/// Counts how often it was woken
+struct CountingWaker(AtomicUsize);
+
+impl Wake for CountingWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn counting_waker() -> (Arc<CountingWaker>, Waker) {
+    let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+    (counter.clone(), Waker::from(counter))
+}
+
+/// Collects `{Ref, Int}` replies as `(pid, ref, int)`
+#[derive(Clone)]
+struct Outbox(Arc<SpinLock<Vec<(u32, u64, i32)>>>);
+
+impl Outbox {
+    fn new() -> Self {
+        Self(Arc::new(SpinLock::new(Vec::new())))
+    }
+
+    fn sent(&self) -> Vec<(u32, u64, i32)> {
+        self.0.with(|sent| sent.clone())
+    }
+}
+
+impl ReplySender for Outbox {
+    fn send(&self, pid: u32, message: &TermValue) -> NifResult<()> {
+        match message.as_tuple() {
+            Some([TermValue::Reference(RefId(reference)), TermValue::SmallInt(result)]) => {
+                self.0.with(|sent| sent.push((pid, *reference, *result)));
+                Ok(())
+            }
+            _ => Err(NifError::BadArg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mailbox_wakes_receiver() {
+        let mailbox = Mailbox::new();
+        let (wakes, waker) = counting_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let mut recv = pin!(mailbox.recv());
+        assert!(recv.as_mut().poll(&mut cx).is_pending());
+
+        mailbox.deliver(TermValue::int(5));
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+        assert_eq!(recv.as_mut().poll(&mut cx), Poll::Ready(TermValue::int(5)));
+        assert!(mailbox.is_empty());
+    }
+
+    #[test]
+    fn test_mailbox_keeps_order() {
+        let mailbox = Mailbox::new();
+        mailbox.deliver(TermValue::int(1));
+        mailbox.deliver(TermValue::int(2));
+        assert_eq!(mailbox.len(), 2);
+        assert_eq!(mailbox.try_recv(), Some(TermValue::int(1)));
+        assert_eq!(mailbox.try_recv(), Some(TermValue::int(2)));
+        assert_eq!(mailbox.try_recv(), None);
+    }
+
+    #[test]
+    fn test_job_replies_when_future_completes() {
+        static INBOX: Mailbox = Mailbox::new();
+        let jobs = AsyncJobs::new();
+        let outbox = Outbox::new();
+        let (wakes, waker) = counting_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let mut run = pin!(jobs.run());
+        assert!(run.as_mut().poll(&mut cx).is_pending());
+
+        // The job waits for a message from the port before answering
+        spawn_async_with(&jobs, outbox.clone(), 12, RefId(40), async {
+            match INBOX.recv().await {
+                TermValue::SmallInt(sample) => TermValue::int(sample * 2),
+                _ => TermValue::int(-1),
+            }
+        });
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+        assert!(run.as_mut().poll(&mut cx).is_pending());
+        assert!(outbox.sent().is_empty());
+
+        INBOX.deliver(TermValue::int(21));
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 2);
+        assert!(run.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(outbox.sent(), [(12, 40, 42)]);
+    }
+
+    #[test]
+    fn test_jobs_spawned_before_run_start_on_first_poll() {
+        let jobs = AsyncJobs::new();
+        let outbox = Outbox::new();
+        spawn_async_with(&jobs, outbox.clone(), 1, RefId(1), async { TermValue::int(10) });
+        spawn_async_with(&jobs, outbox.clone(), 2, RefId(2), async { TermValue::int(20) });
+
+        let (_, waker) = counting_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut run = pin!(jobs.run());
+        assert!(run.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(outbox.sent(), [(1, 1, 10), (2, 2, 20)]);
+    }
+}
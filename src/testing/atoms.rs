@@ -0,0 +1,159 @@
+//! Atom table testing suite
+//!
+//! Tests for atom utilities that work across `AtomTableOps` implementations,
+//! such as translating terms between two tables with different layouts.
+
+use crate::atom::translate::{translate_term, AtomTranslator, TranslateMode};
+use crate::atom::{AtomError, AtomTableOps};
+use crate::term::{FunctionRef, TermValue};
+use crate::testing::mocks::MockAtomTable;
+use alloc::vec;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tables() -> (MockAtomTable, MockAtomTable) {
+        // Same names, different index layout
+        let source = MockAtomTable::new_with_atoms(&["ok", "error", "temp", "reading"]);
+        let target = MockAtomTable::new_with_atoms(&["reading", "temp", "error", "ok"]);
+        (source, target)
+    }
+
+    #[test]
+    fn test_translate_single_atom() {
+        let (source, target) = tables();
+        let translator = AtomTranslator::new(&source, &target);
+
+        let ok_src = source.find_atom_str("ok").unwrap();
+        let ok_dst = translator.translate_atom(ok_src).unwrap();
+
+        assert_ne!(ok_src, ok_dst);
+        assert!(target.atom_equals_str(ok_dst, "ok"));
+    }
+
+    #[test]
+    fn test_translate_nested_term() {
+        let (source, target) = tables();
+        let ok = source.find_atom_str("ok").unwrap();
+        let temp = source.find_atom_str("temp").unwrap();
+        let reading = source.find_atom_str("reading").unwrap();
+
+        let term = TermValue::tuple(vec![
+            TermValue::Atom(ok),
+            TermValue::Map(vec![(TermValue::Atom(temp), TermValue::int(21))]),
+            TermValue::list(vec![TermValue::Atom(reading), TermValue::int(1)]),
+        ]);
+
+        let translated = translate_term(&term, &source, &target).unwrap();
+
+        let expected = TermValue::tuple(vec![
+            TermValue::Atom(target.find_atom_str("ok").unwrap()),
+            TermValue::Map(vec![(
+                TermValue::Atom(target.find_atom_str("temp").unwrap()),
+                TermValue::int(21),
+            )]),
+            TermValue::list(vec![
+                TermValue::Atom(target.find_atom_str("reading").unwrap()),
+                TermValue::int(1),
+            ]),
+        ]);
+        assert_eq!(translated, expected);
+    }
+
+    #[test]
+    fn test_translate_function_ref() {
+        let (source, target) = tables();
+        let fun = FunctionRef {
+            module: source.find_atom_str("temp").unwrap(),
+            function: source.find_atom_str("reading").unwrap(),
+            arity: 2,
+        };
+
+        let translator = AtomTranslator::new(&source, &target);
+        match translator.translate_term(&TermValue::Function(fun)).unwrap() {
+            TermValue::Function(f) => {
+                assert!(target.atom_equals_str(f.module, "temp"));
+                assert!(target.atom_equals_str(f.function, "reading"));
+                assert_eq!(f.arity, 2);
+            }
+            other => panic!("expected function, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_translation_cache() {
+        let (source, target) = tables();
+        let ok = source.find_atom_str("ok").unwrap();
+        let translator = AtomTranslator::new(&source, &target);
+
+        let term = TermValue::tuple(vec![TermValue::Atom(ok), TermValue::Atom(ok)]);
+        translator.translate_term(&term).unwrap();
+        assert_eq!(translator.cache_len(), 1);
+
+        translator.clear_cache();
+        assert_eq!(translator.cache_len(), 0);
+    }
+
+    #[test]
+    fn test_ensure_mode_creates_missing_atoms() {
+        let source = MockAtomTable::new_with_atoms(&["only_in_source"]);
+        let target = MockAtomTable::new_empty();
+        let index = source.find_atom_str("only_in_source").unwrap();
+
+        let translator = AtomTranslator::new(&source, &target);
+        assert_eq!(translator.mode(), TranslateMode::Ensure);
+
+        let translated = translator.translate_atom(index).unwrap();
+        assert!(target.atom_equals_str(translated, "only_in_source"));
+    }
+
+    #[test]
+    fn test_existing_only_mode_rejects_missing_atoms() {
+        let source = MockAtomTable::new_with_atoms(&["only_in_source"]);
+        let target = MockAtomTable::new_empty();
+        let index = source.find_atom_str("only_in_source").unwrap();
+
+        let translator = AtomTranslator::existing_only(&source, &target);
+        assert_eq!(translator.translate_atom(index), Err(AtomError::NotFound));
+        assert_eq!(target.count(), 0);
+    }
+
+    #[test]
+    fn test_unknown_source_index_fails() {
+        let (source, target) = tables();
+        let translator = AtomTranslator::new(&source, &target);
+        let bogus = crate::atom::AtomIndex(9999);
+        assert!(translator.translate_atom(bogus).is_err());
+    }
+
+    #[test]
+    fn test_non_atom_terms_pass_through() {
+        let (source, target) = tables();
+        let term = TermValue::tuple(vec![
+            TermValue::int(7),
+            TermValue::Binary(vec![1, 2, 3]),
+            TermValue::Nil,
+        ]);
+        assert_eq!(translate_term(&term, &source, &target).unwrap(), term);
+    }
+
+    mod sensor_atoms {
+        crate::atoms! { ok, sensor_reading, kind = "type" }
+    }
+
+    #[test]
+    fn test_static_atoms_interned_once() {
+        let table = MockAtomTable::new();
+        sensor_atoms::intern_atoms(&table).unwrap();
+        let count = table.count();
+
+        assert_eq!(sensor_atoms::ok(), table.ensure_atom_str("ok").unwrap());
+        assert_eq!(sensor_atoms::sensor_reading(), table.ensure_atom_str("sensor_reading").unwrap());
+        assert_eq!(sensor_atoms::kind(), table.ensure_atom_str("type").unwrap());
+
+        // Accessors do not touch the table
+        assert_eq!(table.count(), count);
+        assert!(table.get_atom_string(sensor_atoms::kind()).unwrap() == *"type");
+    }
+}
@@ -0,0 +1,131 @@
+//! Port behavior testing suite
+
+use crate::atom::AtomTableOps;
+use crate::port::behavior::{BehaviorPort, CallFrom, Incoming, PortBehavior};
+use crate::port::{PortError, PortResult};
+use crate::term::{ProcessId, RefId, TermValue};
+use crate::testing::helpers::*;
+use crate::testing::mocks::MockAtomTable;
+use alloc::{vec, vec::Vec};
+
+/// Counter port: `get` and `{add, N}` calls, `reset` casts, and it logs
+/// every info message
+struct CounterPort {
+    count: i32,
+    infos: Vec<TermValue>,
+    terminations: u32,
+}
+
+impl PortBehavior for CounterPort {
+    fn init<T: AtomTableOps>(opts: &TermValue, _table: &T) -> Result<Self, PortError> {
+        let count = opts.as_int().ok_or(PortError::InvalidMessage)?;
+        Ok(Self {
+            count,
+            infos: Vec::new(),
+            terminations: 0,
+        })
+    }
+
+    fn handle_call<T: AtomTableOps>(&mut self, request: &TermValue, _from: &CallFrom, table: &T) -> PortResult {
+        if request.is_atom_str("get", table) {
+            return PortResult::Reply(TermValue::int(self.count));
+        }
+        if request.is_atom_str("stop", table) {
+            return PortResult::TerminateWithReason(TermValue::atom("normal", table));
+        }
+        match request.as_tuple() {
+            Some([tag, TermValue::SmallInt(n)]) if tag.is_atom_str("add", table) => {
+                self.count += n;
+                PortResult::Reply(TermValue::atom("ok", table))
+            }
+            _ => PortResult::ReplyError(TermValue::atom("badarg", table)),
+        }
+    }
+
+    fn handle_cast<T: AtomTableOps>(&mut self, request: &TermValue, table: &T) -> PortResult {
+        if request.is_atom_str("reset", table) {
+            self.count = 0;
+        }
+        PortResult::Continue
+    }
+
+    fn handle_info<T: AtomTableOps>(&mut self, info: &TermValue, _table: &T) -> PortResult {
+        self.infos.push(info.clone());
+        PortResult::Continue
+    }
+
+    fn terminate(&mut self) {
+        self.terminations += 1;
+    }
+}
+
+fn call(request: TermValue, table: &MockAtomTable) -> TermValue {
+    let from = TermValue::tuple(vec![TermValue::Pid(ProcessId(9)), TermValue::Reference(RefId(4))]);
+    TermValue::tuple(vec![atom("$call", table), from, request])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_messages() {
+        let table = MockAtomTable::new();
+
+        match Incoming::classify(call(atom("get", &table), &table), &table) {
+            Incoming::Call { request, from } => {
+                assert_atom_str(&request, "get", &table);
+                assert_eq!(from, CallFrom { pid: 9, reference: TermValue::Reference(RefId(4)) });
+            }
+            other => panic!("expected call, got {:?}", other),
+        }
+
+        let cast = TermValue::tuple(vec![atom("$cast", &table), atom("reset", &table)]);
+        assert_eq!(Incoming::classify(cast, &table), Incoming::Cast(atom("reset", &table)));
+
+        // A malformed call is just another message
+        let odd = TermValue::tuple(vec![atom("$call", &table), TermValue::Nil, TermValue::Nil]);
+        assert_eq!(Incoming::classify(odd.clone(), &table), Incoming::Info(odd));
+    }
+
+    #[test]
+    fn test_dispatch_runs_callbacks() {
+        let table = MockAtomTable::new();
+        let mut port = BehaviorPort::new(CounterPort::init(&TermValue::int(5), &table).unwrap());
+        let dispatch = |port: &mut BehaviorPort<CounterPort>, message| port.dispatch(Incoming::classify(message, &table), &table);
+
+        let add = TermValue::tuple(vec![atom("add", &table), TermValue::int(3)]);
+        assert_eq!(dispatch(&mut port, call(add, &table)), PortResult::Reply(atom("ok", &table)));
+        assert_eq!(dispatch(&mut port, call(atom("get", &table), &table)), PortResult::Reply(TermValue::int(8)));
+        assert_eq!(
+            dispatch(&mut port, call(TermValue::Nil, &table)),
+            PortResult::ReplyError(atom("badarg", &table))
+        );
+
+        let reset = TermValue::tuple(vec![atom("$cast", &table), atom("reset", &table)]);
+        assert_eq!(dispatch(&mut port, reset), PortResult::Continue);
+        assert_eq!(port.state().count, 0);
+
+        dispatch(&mut port, atom("timer_wakeup", &table));
+        assert_eq!(port.state().infos, vec![atom("timer_wakeup", &table)]);
+
+        assert!(CounterPort::init(&TermValue::Nil, &table).is_err());
+    }
+
+    #[test]
+    fn test_terminate_runs_once() {
+        let table = MockAtomTable::new();
+        let mut port = BehaviorPort::new(CounterPort::init(&TermValue::int(0), &table).unwrap());
+
+        let result = port.dispatch(Incoming::classify(call(atom("stop", &table), &table), &table), &table);
+        assert!(result.is_terminate());
+        assert_eq!(port.state().terminations, 1);
+
+        // Cleanup after a stop, or messages still queued, do not call it again
+        port.terminate();
+        let result = port.dispatch(Incoming::Info(TermValue::Nil), &table);
+        assert_eq!(result, PortResult::Terminate);
+        assert_eq!(port.state().terminations, 1);
+        assert!(port.state().infos.is_empty());
+    }
+}
@@ -0,0 +1,175 @@
+//! Binary view testing suite
+//!
+//! Lays out heap binaries and sub-binaries by hand in host memory and
+//! reads them through `BinarySlice`.
+
+use crate::binary::BinarySlice;
+use crate::term::{Context, NifError, Term, TermValue};
+use alloc::{vec, vec::Vec};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORD: usize = core::mem::size_of::<usize>();
+
+    fn boxed(words: &[usize]) -> Term<'_> {
+        Term::from_raw(words.as_ptr() as usize | 0x2)
+    }
+
+    /// Heap binary: header, byte length, then the bytes packed into words
+    fn heap_binary(bytes: &[u8]) -> Vec<usize> {
+        let data_words = (bytes.len() + WORD - 1) / WORD;
+        let mut words = vec![0usize; 2 + data_words];
+        words[0] = ((1 + data_words) << 6) | 0x24;
+        words[1] = bytes.len();
+        unsafe {
+            core::ptr::copy_nonoverlapping(bytes.as_ptr(), words.as_mut_ptr().add(2) as *mut u8, bytes.len());
+        }
+        words
+    }
+
+    fn sub_binary(parent: Term, offset: usize, len: usize) -> Vec<usize> {
+        vec![(3 << 6) | 0x28, len, offset, parent.raw()]
+    }
+
+    #[test]
+    fn test_view_borrows_binary() {
+        let env = Context { _private: [] };
+        let words = heap_binary(b"hello world");
+        let term = boxed(&words);
+
+        let view = BinarySlice::from_term(term, &env).unwrap();
+        assert_eq!(view.as_bytes(), b"hello world");
+        assert_eq!(view.as_bytes().as_ptr(), unsafe { (words.as_ptr().add(2)) as *const u8 });
+        assert_eq!(view.as_str(), Some("hello world"));
+        assert_eq!(term.to_value().unwrap(), TermValue::binary(b"hello world".to_vec()));
+
+        let not_binary = Term::from_raw((42 << 4) | 0xF);
+        assert_eq!(BinarySlice::from_term(not_binary, &env).unwrap_err(), NifError::BadArg);
+    }
+
+    #[test]
+    fn test_slices_track_parent_offset() {
+        let env = Context { _private: [] };
+        let words = heap_binary(b"\x01\x02header:payload");
+        let view = BinarySlice::from_term(boxed(&words), &env).unwrap();
+
+        let body = view.slice(2..view.len()).unwrap();
+        let (header, payload) = body.split_at(7).unwrap();
+        assert_eq!(&*header, b"header:");
+        assert_eq!(&*payload, b"payload");
+        assert_eq!(payload.range(), 9..16);
+        assert_eq!(payload.parent(), boxed(&words));
+
+        assert!(view.slice(10..30).is_none());
+    }
+
+    #[test]
+    fn test_sub_binary_reads_through_original() {
+        let env = Context { _private: [] };
+        let original = heap_binary(b"0123456789");
+        let sub = sub_binary(boxed(&original), 3, 4);
+
+        let view = BinarySlice::from_term(boxed(&sub), &env).unwrap();
+        assert_eq!(&*view, b"3456");
+        assert_eq!(boxed(&sub).to_value().unwrap(), TermValue::binary(b"3456".to_vec()));
+
+        // Heap binaries cannot be shared, so a sub-binary of one costs a copy
+        assert_eq!(Term::sub_binary_heap_words(boxed(&original), 4).unwrap(), 2 + (4 + WORD - 1) / WORD);
+
+        let bad = sub_binary(boxed(&original), 8, 4);
+        assert_eq!(BinarySlice::from_term(boxed(&bad), &env).unwrap_err(), NifError::InvalidTerm);
+    }
+
+    #[test]
+    fn test_heap_guard_sub_binary() {
+        use crate::testing::mocks::MockHeap;
+
+        // A heap binary parent is copied into a new heap binary
+        let original = heap_binary(b"0123456789");
+        let parent = boxed(&original);
+        let words = Term::sub_binary_heap_words(parent, 4).unwrap();
+        let mut heap = MockHeap::new(words);
+        let copy = heap.guard().sub_binary(parent, 3..7).unwrap().raw();
+        assert!(heap.contains(Term::from_raw(copy)));
+        assert_eq!(Term::from_raw(copy).to_value().unwrap(), TermValue::binary(b"3456".to_vec()));
+        assert_eq!(heap.free(), 0);
+
+        // A refc parent is shared through a sub-binary
+        static DATA: &[u8] = b"firmware blob";
+        let refc = vec![(3 << 6) | 0x20, DATA.len(), 0x1, DATA.as_ptr() as usize];
+        let mut heap = MockHeap::new(Term::SUB_BINARY_WORDS);
+        let mut guard = heap.guard();
+        let sub = guard.sub_binary(boxed(&refc), 9..13).unwrap();
+        assert_eq!(sub.to_value().unwrap(), TermValue::binary(b"blob".to_vec()));
+        assert_eq!(guard.sub_binary(boxed(&refc), 9..13), Err(NifError::OutOfMemory));
+
+        let mut heap = MockHeap::new(64);
+        assert_eq!(heap.guard().sub_binary(parent, 8..12), Err(NifError::BadArg));
+    }
+
+    #[test]
+    fn test_constant_refc_binary_is_borrowed() {
+        let env = Context { _private: [] };
+        static DATA: &[u8] = b"firmware blob";
+        let refc = vec![(3 << 6) | 0x20, DATA.len(), 0x1, DATA.as_ptr() as usize];
+
+        let view = BinarySlice::from_term(boxed(&refc), &env).unwrap();
+        assert_eq!(view.as_bytes().as_ptr(), DATA.as_ptr());
+
+        // Refc binaries are shared through a fixed-size sub-binary
+        assert_eq!(Term::sub_binary_heap_words(boxed(&refc), 1000).unwrap(), Term::SUB_BINARY_WORDS);
+    }
+
+    #[test]
+    fn test_released_refc_binary_layout() {
+        // What `OwnedBinary::release` produces: a refc box pointing at a
+        // RefcBinary (list head, refcount, size, resource type, data)
+        let env = Context { _private: [] };
+        let mut refc = vec![0usize; 5 + 2];
+        refc[2] = 1;
+        refc[3] = 10;
+        unsafe {
+            core::ptr::copy_nonoverlapping(b"0123456789".as_ptr(), refc.as_mut_ptr().add(5) as *mut u8, 10);
+        }
+        let boxed_refc = vec![(3 << 6) | 0x20, 10, 0, refc.as_ptr() as usize];
+
+        let view = BinarySlice::from_term(boxed(&boxed_refc), &env).unwrap();
+        assert_eq!(&*view, b"0123456789");
+        assert_eq!(view.as_bytes().as_ptr(), unsafe { refc.as_ptr().add(5) as *const u8 });
+    }
+
+    #[test]
+    fn test_refc_binary_with_resource_type_is_a_resource() {
+        // AtomVM keeps resources in refc binaries whose RefcBinary has a type
+        static RESOURCE_TYPE: u8 = 0;
+        let mut refc = vec![0usize; 5 + 1];
+        refc[2] = 1;
+        refc[3] = WORD;
+        refc[4] = &RESOURCE_TYPE as *const u8 as usize;
+        let boxed_refc = vec![(3 << 6) | 0x20, WORD, 0, refc.as_ptr() as usize];
+
+        match boxed(&boxed_refc).to_value().unwrap() {
+            TermValue::Resource(resource) => assert_eq!(resource.ptr, unsafe { refc.as_ptr().add(5) } as *mut _),
+            other => panic!("expected a resource, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_builder_heap_binary_layout() {
+        assert_eq!(Term::binary_heap_words(0), 2);
+        assert_eq!(Term::binary_heap_words(WORD + 1), 4);
+        assert_eq!(Term::binary_heap_words(Term::REFC_BINARY_MIN - 1), 2 + Term::REFC_BINARY_MIN / WORD);
+        assert_eq!(Term::binary_heap_words(Term::REFC_BINARY_MIN), Term::REFC_BINARY_WORDS);
+
+        // Lay out over garbage, fill in place, and read back as a normal heap binary
+        let mut words = vec![usize::MAX; Term::binary_heap_words(11)];
+        let term = unsafe { Term::write_heap_binary(words.as_mut_ptr(), 11) };
+        let data = unsafe { (term.boxed_ptr() as *mut usize).add(2) as *mut u8 };
+        unsafe { core::ptr::copy_nonoverlapping(b"hello world".as_ptr(), data, 11) };
+
+        assert_eq!(words, heap_binary(b"hello world"));
+        assert_eq!(term.to_value().unwrap(), TermValue::Binary(b"hello world".to_vec()));
+    }
+}
@@ -0,0 +1,97 @@
+//! Bitfield testing suite
+
+use crate::bitfield::{BitField, BitLayout, BitfieldError};
+use crate::term::TermValue;
+use crate::testing::mocks::MockAtomTable;
+use alloc::vec;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: BitLayout = BitLayout::new(2, &[
+        BitField::new("ready", 0, 1),
+        BitField::new("mode", 1, 3),
+        BitField::new("temp", 4, 12).signed(),
+    ]);
+
+    #[test]
+    fn test_decode_register_dump() {
+        let data = [0x8F, 0xF6];
+
+        assert_eq!(STATUS.decode(&data).unwrap(), vec![("ready", 1), ("mode", 0), ("temp", -10)]);
+        assert_eq!(STATUS.read(&data, "temp").unwrap(), -10);
+        assert_eq!(STATUS.field("temp").unwrap().read_raw(&data).unwrap(), 0xFF6);
+        assert_eq!(STATUS.read(&data, "missing"), Err(BitfieldError::UnknownField));
+    }
+
+    #[test]
+    fn test_encode_command_word() {
+        assert_eq!(STATUS.encode(&[("mode", 5)]).unwrap(), vec![0x50, 0x00]);
+        assert_eq!(
+            STATUS.encode(&[("ready", 1), ("temp", -10)]).unwrap(),
+            vec![0x8F, 0xF6]
+        );
+
+        // Writing leaves neighbouring bits alone
+        let mut data = [0xFF, 0xFF];
+        STATUS.write(&mut data, "mode", 0).unwrap();
+        assert_eq!(data, [0x8F, 0xFF]);
+    }
+
+    #[test]
+    fn test_value_range_checked() {
+        assert_eq!(
+            STATUS.encode(&[("mode", 8)]),
+            Err(BitfieldError::ValueOutOfRange("mode"))
+        );
+        assert_eq!(
+            STATUS.encode(&[("mode", -1)]),
+            Err(BitfieldError::ValueOutOfRange("mode"))
+        );
+        assert!(STATUS.encode(&[("temp", -2048)]).is_ok());
+        assert_eq!(
+            STATUS.encode(&[("temp", 2048)]),
+            Err(BitfieldError::ValueOutOfRange("temp"))
+        );
+    }
+
+    #[test]
+    fn test_little_endian_and_bounds() {
+        let word = BitField::new("word", 8, 16).little();
+        let mut data = [0u8; 3];
+        word.write(&mut data, 0x1234).unwrap();
+        assert_eq!(data, [0x00, 0x34, 0x12]);
+        assert_eq!(word.read(&data).unwrap(), 0x1234);
+
+        assert_eq!(word.read(&data[..2]), Err(BitfieldError::OutOfBounds("word")));
+        assert_eq!(
+            BitField::new("odd", 0, 12).little().read(&data),
+            Err(BitfieldError::InvalidWidth("odd"))
+        );
+
+        let full = BitField::new("full", 0, 64).signed();
+        let mut wide = [0u8; 8];
+        full.write(&mut wide, i64::MIN).unwrap();
+        assert_eq!(full.read(&wide).unwrap(), i64::MIN);
+    }
+
+    #[test]
+    fn test_map_round_trip() {
+        let table = MockAtomTable::new();
+
+        let map = STATUS.decode_map(&TermValue::binary(vec![0x8F, 0xF6]), &table).unwrap();
+        assert_eq!(map.map_get(&TermValue::atom("ready", &table)), Some(&TermValue::int(1)));
+        assert_eq!(map.map_get(&TermValue::atom("temp", &table)), Some(&TermValue::int(-10)));
+
+        let encoded = STATUS.encode_map(&map, &table).unwrap();
+        assert_eq!(encoded, TermValue::binary(vec![0x8F, 0xF6]));
+
+        let bad_key = TermValue::Map(vec![(TermValue::atom("speed", &table), TermValue::int(1))]);
+        assert_eq!(STATUS.encode_map(&bad_key, &table), Err(BitfieldError::UnknownField));
+        assert_eq!(
+            STATUS.decode_map(&TermValue::int(1), &table),
+            Err(BitfieldError::WrongType)
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! Bus port testing suite
+
+use crate::port::behavior::{BehaviorPort, CallFrom, Incoming, PortBehavior};
+use crate::port::bus::{BusBackend, BusPort, MAX_READ};
+use crate::port::{PortError, PortResult};
+use crate::term::TermValue;
+use crate::testing::mocks::MockAtomTable;
+use alloc::{vec, vec::Vec};
+
+/// One device at address 0x40 with 16 registers; a write sets the register
+/// pointer, then stores the following bytes
+#[derive(Default)]
+struct MockBus {
+    registers: [u8; 16],
+    pointer: usize,
+    closed: bool,
+}
+
+impl MockBus {
+    fn device(addr: u16) -> Result<(), PortError> {
+        if addr == 0x40 {
+            Ok(())
+        } else {
+            Err(PortError::HardwareError)
+        }
+    }
+}
+
+impl BusBackend for MockBus {
+    fn init(_opts: &TermValue) -> Result<Self, PortError> {
+        Ok(Self::default())
+    }
+
+    fn write(&mut self, addr: u16, data: &[u8]) -> Result<(), PortError> {
+        Self::device(addr)?;
+        if let Some((register, values)) = data.split_first() {
+            self.pointer = *register as usize;
+            for (offset, value) in values.iter().enumerate() {
+                self.registers[(self.pointer + offset) % 16] = *value;
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, addr: u16, buffer: &mut [u8]) -> Result<(), PortError> {
+        Self::device(addr)?;
+        for (offset, byte) in buffer.iter_mut().enumerate() {
+            *byte = self.registers[(self.pointer + offset) % 16];
+        }
+        Ok(())
+    }
+
+    fn write_read(&mut self, addr: u16, data: &[u8], buffer: &mut [u8]) -> Result<(), PortError> {
+        self.write(addr, data)?;
+        self.read(addr, buffer)
+    }
+
+    fn close(&mut self) {
+        self.closed = true;
+    }
+}
+
+fn bus_port(table: &MockAtomTable) -> BehaviorPort<BusPort<MockBus>> {
+    BehaviorPort::new(BusPort::init(&TermValue::Nil, table).unwrap())
+}
+
+fn call(request: TermValue) -> Incoming {
+    Incoming::Call {
+        request,
+        from: CallFrom {
+            pid: 1,
+            reference: TermValue::reference(1),
+        },
+    }
+}
+
+fn command(name: &str, addr: i32, args: Vec<TermValue>, table: &MockAtomTable) -> TermValue {
+    let mut elements = vec![TermValue::atom(name, table), TermValue::int(addr)];
+    elements.extend(args);
+    TermValue::tuple(elements)
+}
+
+fn ok_binary(data: &[u8], table: &MockAtomTable) -> PortResult {
+    PortResult::Reply(TermValue::tuple(vec![TermValue::atom("ok", table), TermValue::binary(data.to_vec())]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_then_read() {
+        let table = MockAtomTable::new();
+        let mut port = bus_port(&table);
+
+        let write = command("write", 0x40, vec![TermValue::binary(vec![2, 0xAA, 0xBB])], &table);
+        assert_eq!(port.dispatch(call(write), &table), PortResult::Reply(TermValue::atom("ok", &table)));
+        assert_eq!(&port.state().backend().registers[2..4], &[0xAA, 0xBB]);
+
+        let read = command("read", 0x40, vec![TermValue::int(2)], &table);
+        assert_eq!(port.dispatch(call(read), &table), ok_binary(&[0xAA, 0xBB], &table));
+    }
+
+    #[test]
+    fn test_write_read_in_one_transaction() {
+        let table = MockAtomTable::new();
+        let mut port = bus_port(&table);
+        port.state_mut().backend_mut().registers[5] = 0x68;
+
+        // Data may be an iolist
+        let data = TermValue::list(vec![TermValue::int(5)]);
+        let request = command("write_read", 0x40, vec![data, TermValue::int(1)], &table);
+        assert_eq!(port.dispatch(call(request), &table), ok_binary(&[0x68], &table));
+    }
+
+    #[test]
+    fn test_errors_are_replied() {
+        let table = MockAtomTable::new();
+        let mut port = bus_port(&table);
+
+        let absent = command("read", 0x41, vec![TermValue::int(1)], &table);
+        assert_eq!(
+            port.dispatch(call(absent), &table),
+            PortResult::ReplyError(TermValue::atom("hardware_error", &table))
+        );
+
+        let too_long = command("read", 0x40, vec![TermValue::int(MAX_READ as i32 + 1)], &table);
+        let bad_addr = command("write", -1, vec![TermValue::binary(vec![0])], &table);
+        let unknown = command("erase", 0x40, vec![], &table);
+        for request in [too_long, bad_addr, unknown] {
+            assert_eq!(
+                port.dispatch(call(request), &table),
+                PortResult::ReplyError(TermValue::atom("badarg", &table))
+            );
+        }
+    }
+
+    #[test]
+    fn test_backend_closed_on_terminate() {
+        let table = MockAtomTable::new();
+        let mut port = bus_port(&table);
+        port.terminate();
+        assert!(port.state().backend().closed);
+    }
+}
@@ -0,0 +1,122 @@
+//! Rust-to-Erlang call testing suite
+
+use crate::port::call::{CallBackend, CallError, CallQueue, CancelToken};
+use crate::term::{RefId, TermValue};
+use crate::testing::helpers::*;
+use crate::testing::mocks::MockAtomTable;
+use alloc::{vec, vec::Vec};
+
+/// Simulated Erlang peer: each wait advances the clock and lets the peer
+/// answer queued requests after `reply_after_ms`
+struct SimulatedPeer<'q> {
+    queue: &'q CallQueue,
+    now: u64,
+    reply_after_ms: Option<u64>,
+    sent: Vec<(u32, TermValue)>,
+    fail_send: bool,
+    cancel_on_wait: Option<&'q CancelToken>,
+}
+
+impl<'q> SimulatedPeer<'q> {
+    fn new(queue: &'q CallQueue, reply_after_ms: Option<u64>) -> Self {
+        Self {
+            queue,
+            now: 0,
+            reply_after_ms,
+            sent: Vec::new(),
+            fail_send: false,
+            cancel_on_wait: None,
+        }
+    }
+}
+
+impl CallBackend for SimulatedPeer<'_> {
+    fn send(&mut self, pid: u32, message: &TermValue) -> Result<(), CallError> {
+        if self.fail_send {
+            return Err(CallError::SendFailed);
+        }
+        self.sent.push((pid, message.clone()));
+        Ok(())
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.now
+    }
+
+    fn wait_until(&mut self, deadline_ms: u64) {
+        self.now = (self.now + 10).min(deadline_ms);
+        if let Some(token) = self.cancel_on_wait {
+            token.cancel();
+        }
+        if self.reply_after_ms.is_some_and(|after| self.now >= after) {
+            let (_, request) = self.sent.last().unwrap();
+            let reference = request.tuple_get(1).unwrap().clone();
+            let reply = TermValue::tuple(vec![reference, TermValue::int(42)]);
+            self.queue.deliver(&reply);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call_waits_for_reply() {
+        let table = MockAtomTable::new();
+        let queue = CallQueue::new();
+        let mut peer = SimulatedPeer::new(&queue, Some(30));
+        let request = TermValue::tuple(vec![atom("get_gain", &table), TermValue::int(1)]);
+
+        let reply = queue.call(&mut peer, 77, &request, 1000, &table).unwrap();
+        assert_eq!(reply, TermValue::int(42));
+        assert_eq!(peer.now, 30);
+        assert_eq!(queue.pending(), 0);
+
+        let (pid, message) = &peer.sent[0];
+        assert_eq!(*pid, 77);
+        assert_atom_str(message.tuple_get(0).unwrap(), "call", &table);
+        assert!(matches!(message.tuple_get(1), Some(TermValue::Reference(_))));
+        assert_eq!(message.tuple_get(2), Some(&request));
+    }
+
+    #[test]
+    fn test_call_times_out_and_ignores_late_reply() {
+        let table = MockAtomTable::new();
+        let queue = CallQueue::new();
+        let mut peer = SimulatedPeer::new(&queue, None);
+
+        let result = queue.call(&mut peer, 1, &TermValue::Nil, 50, &table);
+        assert_eq!(result, Err(CallError::Timeout));
+        assert_eq!(peer.now, 50);
+        assert_eq!(queue.pending(), 0);
+
+        let reference = peer.sent[0].1.tuple_get(1).unwrap().clone();
+        assert!(!queue.deliver(&TermValue::tuple(vec![reference, TermValue::Nil])));
+    }
+
+    #[test]
+    fn test_call_cancelled_and_send_failure() {
+        let table = MockAtomTable::new();
+        let queue = CallQueue::new();
+        let cancel = CancelToken::new();
+        let mut peer = SimulatedPeer::new(&queue, None);
+        peer.cancel_on_wait = Some(&cancel);
+
+        let result = queue.call_cancellable(&mut peer, 1, &TermValue::Nil, 1000, &cancel, &table);
+        assert_eq!(result, Err(CallError::Cancelled));
+        assert_eq!(queue.pending(), 0);
+
+        let mut failing = SimulatedPeer::new(&queue, None);
+        failing.fail_send = true;
+        assert_eq!(queue.call(&mut failing, 1, &TermValue::Nil, 1000, &table), Err(CallError::SendFailed));
+        assert_eq!(queue.pending(), 0);
+    }
+
+    #[test]
+    fn test_deliver_ignores_other_messages() {
+        let queue = CallQueue::new();
+        assert!(!queue.deliver(&TermValue::int(1)));
+        assert!(!queue.deliver(&TermValue::tuple(vec![TermValue::Reference(RefId(99)), TermValue::Nil])));
+    }
+}
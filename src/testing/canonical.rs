@@ -0,0 +1,162 @@
+//! Canonical encoding testing suite
+
+use crate::canonical::{canonicalize, compare, is_canonical, sort, sort_dedup, Ordered};
+use crate::etf;
+use crate::term::{ProcessId, RefId, TermValue};
+use crate::testing::helpers::*;
+use crate::testing::mocks::MockAtomTable;
+use alloc::{vec, vec::Vec};
+use core::cmp::Ordering;
+
+/// `#{zeta => 1, alpha => #{2 => b, 1 => a}}`, keys in the given order
+fn config(table: &MockAtomTable, reversed: bool) -> TermValue {
+    let inner = |pairs: Vec<(TermValue, TermValue)>| {
+        if reversed {
+            TermValue::map(pairs.into_iter().rev().collect())
+        } else {
+            TermValue::map(pairs)
+        }
+    };
+    inner(vec![
+        (atom("zeta", table), TermValue::int(1)),
+        (
+            atom("alpha", table),
+            inner(vec![
+                (TermValue::int(2), atom("b", table)),
+                (TermValue::int(1), atom("a", table)),
+            ]),
+        ),
+    ])
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_term_order() {
+        let table = MockAtomTable::new();
+        // Interned first, so its index is lower, but it sorts by name
+        let zeta = atom("zeta", &table);
+        let alpha = atom("alpha", &table);
+
+        let ascending = [
+            TermValue::int(1),
+            TermValue::Float(1.5),
+            TermValue::int(2),
+            alpha.clone(),
+            zeta.clone(),
+            TermValue::Reference(RefId(1)),
+            TermValue::Pid(ProcessId(1)),
+            TermValue::tuple(vec![TermValue::int(9)]),
+            TermValue::tuple(vec![TermValue::int(1), TermValue::int(1)]),
+            TermValue::map(vec![]),
+            TermValue::Nil,
+            TermValue::list(vec![TermValue::int(1)]),
+            TermValue::list(vec![TermValue::int(1), TermValue::int(0)]),
+            TermValue::binary(vec![0]),
+        ];
+        for pair in ascending.windows(2) {
+            assert_eq!(compare(&pair[0], &pair[1], &table), Ordering::Less, "{:?} < {:?}", pair[0], pair[1]);
+            assert_eq!(compare(&pair[1], &pair[0], &table), Ordering::Greater);
+        }
+
+        // Equal numbers: the integer first
+        assert_eq!(compare(&TermValue::int(1), &TermValue::Float(1.0), &table), Ordering::Less);
+        // Integers beyond f64 precision compare exactly against floats
+        let above = TermValue::integer((1 << 53) + 1);
+        assert_eq!(compare(&above, &TermValue::Float((1u64 << 53) as f64), &table), Ordering::Greater);
+        assert_eq!(compare(&TermValue::Float((1u64 << 53) as f64), &above, &table), Ordering::Less);
+        assert_eq!(compare(&TermValue::int(i32::MAX), &TermValue::integer(i64::MAX), &table), Ordering::Less);
+        // Map pair order does not matter
+        assert_eq!(compare(&config(&table, false), &config(&table, true), &table), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_sort_and_ordered_containers() {
+        let table = MockAtomTable::new();
+        let b = atom("b", &table);
+        let a = atom("a", &table);
+
+        let mut values = vec![
+            TermValue::binary(b"x".to_vec()),
+            b.clone(),
+            TermValue::Float(1.0),
+            a.clone(),
+            TermValue::int(1),
+            b.clone(),
+            config(&table, true),
+            config(&table, false),
+        ];
+        sort(&mut values, &table);
+        assert_eq!(values[..4], [TermValue::int(1), TermValue::Float(1.0), a.clone(), b.clone()]);
+
+        sort_dedup(&mut values, &table);
+        assert_eq!(values.len(), 6);
+        assert_eq!(values[3], b);
+        assert_eq!(values[5], TermValue::binary(b"x".to_vec()));
+
+        let mut ordered: Vec<_> = [b.clone(), TermValue::Nil, a.clone(), b.clone()]
+            .into_iter()
+            .map(|value| Ordered::new(value, &table))
+            .collect();
+        ordered.sort();
+        ordered.dedup();
+        let sorted: Vec<_> = ordered.into_iter().map(Ordered::into_inner).collect();
+        assert_eq!(sorted, vec![a, b, TermValue::Nil]);
+
+        // Equal maps listed in another order are one key
+        assert_eq!(Ordered::new(config(&table, true), &table), Ordered::new(config(&table, false), &table));
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let table = MockAtomTable::new();
+        let value = config(&table, false);
+        assert!(!is_canonical(&value, &table));
+
+        let canonical = canonicalize(value, &table);
+        assert!(is_canonical(&canonical, &table));
+        let pairs = match &canonical {
+            TermValue::Map(pairs) => pairs,
+            other => panic!("expected map, got {:?}", other),
+        };
+        assert_atom_str(&pairs[0].0, "alpha", &table);
+        assert_eq!(pairs[0].1.map_get(&TermValue::int(1)), Some(&atom("a", &table)));
+        match &pairs[0].1 {
+            TermValue::Map(inner) => assert_eq!(inner[0].0, TermValue::int(1)),
+            other => panic!("expected map, got {:?}", other),
+        }
+
+        let floats = canonicalize(TermValue::list(vec![TermValue::Float(-0.0), TermValue::Float(-f64::NAN)]), &table);
+        let floats = floats.list_to_vec();
+        match floats[0] {
+            TermValue::Float(zero) => assert!(zero.is_sign_positive()),
+            _ => unreachable!(),
+        }
+        match floats[1] {
+            TermValue::Float(nan) => assert_eq!(nan.to_bits(), f64::NAN.to_bits()),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_canonical_etf_is_byte_identical() {
+        let table = MockAtomTable::new();
+        let built_one_way = config(&table, false);
+        let built_other_way = config(&table, true);
+
+        assert_ne!(
+            etf::encode(&built_one_way, &table).unwrap(),
+            etf::encode(&built_other_way, &table).unwrap()
+        );
+        let bytes = etf::encode_canonical(&built_one_way, &table).unwrap();
+        assert_eq!(bytes, etf::encode_canonical(&built_other_way, &table).unwrap());
+
+        // Still an ordinary ETF blob
+        let decoded = etf::decode(&bytes, &table).unwrap();
+        assert!(is_canonical(&decoded, &table));
+        assert_eq!(compare(&decoded, &built_other_way, &table), Ordering::Equal);
+    }
+}
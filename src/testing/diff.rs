@@ -0,0 +1,119 @@
+//! Term diff testing suite
+
+use crate::diff::{apply_diff, term_diff};
+use crate::term::{NifError, TermValue};
+use crate::testing::helpers::*;
+use crate::testing::mocks::MockAtomTable;
+use alloc::vec;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(old: &TermValue, new: &TermValue, table: &MockAtomTable) -> TermValue {
+        let delta = term_diff(old, new, table);
+        assert_eq!(&apply_diff(old, &delta, table).unwrap(), new);
+        delta
+    }
+
+    #[test]
+    fn test_unchanged_and_replaced() {
+        let table = MockAtomTable::new();
+        let value = int_list(&[1, 2, 3]);
+
+        assert_atom_str(&round_trip(&value, &value, &table), "same", &table);
+
+        let delta = round_trip(&TermValue::int(1), &TermValue::binary(vec![1]), &table);
+        assert_eq!(
+            delta,
+            TermValue::tuple(vec![atom("replace", &table), TermValue::binary(vec![1])])
+        );
+    }
+
+    #[test]
+    fn test_map_changes_are_minimal() {
+        let table = MockAtomTable::new();
+        let old = atom_map(&[
+            ("temp", TermValue::int(20)),
+            ("mode", atom("idle", &table)),
+            ("fan", TermValue::int(0)),
+        ], &table);
+        let new = atom_map(&[
+            ("temp", TermValue::int(21)),
+            ("mode", atom("idle", &table)),
+            ("alarm", atom("true", &table)),
+        ], &table);
+
+        let delta = round_trip(&old, &new, &table);
+        let ops = delta.tuple_get(1).unwrap().list_to_vec();
+        assert_eq!(ops, vec![
+            TermValue::tuple(vec![
+                atom("update", &table),
+                atom("temp", &table),
+                TermValue::tuple(vec![atom("replace", &table), TermValue::int(21)]),
+            ]),
+            TermValue::tuple(vec![atom("put", &table), atom("alarm", &table), atom("true", &table)]),
+            TermValue::tuple(vec![atom("remove", &table), atom("fan", &table)]),
+        ]);
+    }
+
+    #[test]
+    fn test_list_edits() {
+        let table = MockAtomTable::new();
+
+        // One insertion in the middle is a single splice
+        let delta = round_trip(&int_list(&[1, 2, 4, 5]), &int_list(&[1, 2, 3, 4, 5]), &table);
+        assert_eq!(
+            delta.tuple_get(1).unwrap().list_to_vec(),
+            vec![TermValue::tuple(vec![
+                atom("splice", &table),
+                TermValue::int(2),
+                TermValue::int(0),
+                int_list(&[3]),
+            ])]
+        );
+
+        // Same length: positional updates
+        let delta = round_trip(&int_list(&[1, 2, 3]), &int_list(&[1, 9, 3]), &table);
+        assert_eq!(delta.tuple_get(1).unwrap().list_length(), 1);
+
+        round_trip(&int_list(&[1, 2, 3]), &TermValue::Nil, &table);
+        round_trip(&TermValue::Nil, &int_list(&[7]), &table);
+        round_trip(&int_list(&[1, 2, 3, 4]), &int_list(&[5, 2]), &table);
+    }
+
+    #[test]
+    fn test_nested_structures() {
+        let table = MockAtomTable::new();
+        let old = TermValue::tuple(vec![
+            atom("state", &table),
+            atom_map(&[("readings", int_list(&[1, 2]))], &table),
+        ]);
+        let new = TermValue::tuple(vec![
+            atom("state", &table),
+            atom_map(&[("readings", int_list(&[1, 2, 3]))], &table),
+        ]);
+
+        let delta = round_trip(&old, &new, &table);
+        assert!(delta.tuple_get(0).unwrap().is_atom_str("tuple", &table));
+    }
+
+    #[test]
+    fn test_malformed_diff_rejected() {
+        let table = MockAtomTable::new();
+        let old = int_list(&[1]);
+
+        assert_eq!(apply_diff(&old, &TermValue::int(3), &table), Err(NifError::BadArg));
+
+        let out_of_range = TermValue::tuple(vec![
+            atom("list", &table),
+            TermValue::list(vec![TermValue::tuple(vec![
+                atom("splice", &table),
+                TermValue::int(5),
+                TermValue::int(1),
+                TermValue::Nil,
+            ])]),
+        ]);
+        assert_eq!(apply_diff(&old, &out_of_range, &table), Err(NifError::BadArg));
+    }
+}
@@ -0,0 +1,221 @@
+//! ESP-IDF component generation testing suite
+
+use avmnif_build::{EspIdfComponent, PortDriver};
+
+fn component() -> EspIdfComponent {
+    EspIdfComponent::new("sensor_nifs", "/work/target/xtensa-esp32-none-elf/release/libsensor_nifs.a")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{Context, GlobalContext};
+    use crate::port::{Message, PortResult};
+    use crate::term::Term;
+    use core::ffi::c_void;
+    use alloc::collections::BTreeSet;
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+
+    // The exports the stub declares, generated by the real macros
+
+    fn esp_sensors_init(_ctx: &mut Context) {}
+
+    extern "C" fn esp_read_nif(_ctx: *mut crate::term::Context, _argc: i32, argv: *const Term) -> Term<'static> {
+        unsafe { Term::from_raw((*argv).raw()) }
+    }
+
+    crate::nif_collection!(esp_sensors, init = esp_sensors_init, nifs = [("read", 1, esp_read_nif)]);
+
+    fn esp_uart_setup(_global: &mut GlobalContext) {}
+    fn esp_uart_teardown(_global: &mut GlobalContext) {}
+    fn esp_uart_create(_global: &GlobalContext, _opts: Term) -> *mut Context {
+        core::ptr::null_mut()
+    }
+    fn esp_uart_handler(_ctx: &mut Context, _message: &Message) -> PortResult {
+        PortResult::Continue
+    }
+
+    crate::port_collection!(
+        esp_uart,
+        init = esp_uart_setup,
+        destroy = esp_uart_teardown,
+        create_port = esp_uart_create,
+        handler = esp_uart_handler
+    );
+
+    fn esp_gpio_create(_global: &GlobalContext, _opts: Term) -> *mut Context {
+        core::ptr::null_mut()
+    }
+    fn esp_gpio_handler(_ctx: &mut Context, _message: &Message) -> PortResult {
+        PortResult::Continue
+    }
+
+    crate::port_collection!(esp_gpio, create_port = esp_gpio_create, handler = esp_gpio_handler);
+
+    /// A type crossing the FFI, named after what it is in Rust
+    ///
+    /// `GlobalContext` is `c_void` and `ERL_NIF_TERM` is `usize`, so those
+    /// are compared through what they alias.
+    trait FfiType {
+        const NAME: &'static str;
+    }
+
+    impl FfiType for () {
+        const NAME: &'static str = "()";
+    }
+    impl FfiType for *mut Context {
+        const NAME: &'static str = "*mut Context";
+    }
+    impl FfiType for *mut c_void {
+        const NAME: &'static str = "*mut c_void";
+    }
+    impl FfiType for *const c_void {
+        const NAME: &'static str = "*const c_void";
+    }
+    impl FfiType for *const u8 {
+        const NAME: &'static str = "*const u8";
+    }
+    impl FfiType for usize {
+        const NAME: &'static str = "usize";
+    }
+    impl FfiType for u32 {
+        const NAME: &'static str = "u32";
+    }
+
+    trait Signature {
+        fn signature() -> String;
+    }
+
+    impl<R: FfiType, A: FfiType> Signature for extern "C" fn(A) -> R {
+        fn signature() -> String {
+            format!("fn({}) -> {}", A::NAME, R::NAME)
+        }
+    }
+
+    impl<R: FfiType, A: FfiType, B: FfiType> Signature for extern "C" fn(A, B) -> R {
+        fn signature() -> String {
+            format!("fn({}, {}) -> {}", A::NAME, B::NAME, R::NAME)
+        }
+    }
+
+    fn rust_signature<F: Signature>(_export: F) -> String {
+        F::signature()
+    }
+
+    /// The Rust type a C type of the stub must be
+    fn rust_type(c_type: &str) -> &'static str {
+        match c_type {
+            "void" => "()",
+            "Context *" => "*mut Context",
+            "GlobalContext *" => "*mut c_void",
+            "const GlobalContext *" | "const void *" => "*const c_void",
+            "const char *" => "*const u8",
+            "term" => "usize",
+            "uint32_t" => "u32",
+            other => panic!("no Rust type for C type `{}`", other),
+        }
+    }
+
+    /// The prototypes the stub declares for Rust exports, by name, as Rust signatures
+    fn declared_exports(source: &str) -> Vec<(String, String)> {
+        source
+            .lines()
+            .filter(|line| line.ends_with(");") && !line.starts_with(char::is_whitespace) && !line.starts_with("static"))
+            .map(|line| {
+                let (head, params) = line.trim_end_matches(");").split_once('(').unwrap();
+                let name_at = head.rfind(|c: char| c == ' ' || c == '*').unwrap() + 1;
+                let (ret, name) = head.split_at(name_at);
+                let params: Vec<&str> = params
+                    .split(',')
+                    .map(|param| {
+                        // Drop the parameter's name
+                        let param = param.trim();
+                        let type_end = param.rfind(|c: char| c == ' ' || c == '*').unwrap() + 1;
+                        rust_type(param[..type_end].trim_end_matches(' ').trim())
+                    })
+                    .collect();
+                let ret = rust_type(ret.trim_end_matches(' '));
+                (name.to_string(), format!("fn({}) -> {}", params.join(", "), ret))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cmake_links_the_static_library() {
+        let cmake = component().requires("driver").cmake_lists();
+        assert!(cmake.contains("SRCS \"sensor_nifs_registration.c\""));
+        assert!(cmake.contains("PRIV_REQUIRES libatomvm avm_sys driver"));
+        assert!(cmake.contains("WHOLE_ARCHIVE"));
+        assert!(cmake.contains(
+            "add_prebuilt_library(sensor_nifs_rust \"/work/target/xtensa-esp32-none-elf/release/libsensor_nifs.a\""
+        ));
+        assert!(cmake.contains("target_link_libraries(${COMPONENT_LIB} PRIVATE sensor_nifs_rust)"));
+    }
+
+    #[test]
+    fn test_stub_prototypes_match_the_rust_exports() {
+        let source = component()
+            .nif_collection("esp_sensors")
+            .port_driver(PortDriver::new("esp_uart").with_init_and_destroy())
+            .port_driver(PortDriver::new("esp_gpio"))
+            .registration_source();
+
+        let exports = [
+            ("esp_sensors_nif_init", rust_signature(esp_sensors_nif_init as extern "C" fn(_) -> _)),
+            ("esp_sensors_nif_destroy", rust_signature(esp_sensors_nif_destroy as extern "C" fn(_) -> _)),
+            ("esp_sensors_get_nif", rust_signature(esp_sensors_get_nif as extern "C" fn(_) -> _)),
+            ("esp_sensors_get_nif_flags", rust_signature(esp_sensors_get_nif_flags as extern "C" fn(_) -> _)),
+            ("esp_uart_init", rust_signature(esp_uart_init as extern "C" fn(_) -> _)),
+            ("esp_uart_destroy", rust_signature(esp_uart_destroy as extern "C" fn(_) -> _)),
+            ("esp_uart_create_port", rust_signature(esp_uart_create_port as extern "C" fn(_, _) -> _)),
+            ("esp_gpio_create_port", rust_signature(esp_gpio_create_port as extern "C" fn(_, _) -> _)),
+        ];
+
+        let declared = declared_exports(&source);
+        for (name, signature) in &declared {
+            let export = exports.iter().find(|(export, _)| export == name);
+            let (_, rust) = export.unwrap_or_else(|| panic!("the stub declares `{}`, which is not a Rust export", name));
+            assert_eq!(signature, rust, "prototype of `{}`", name);
+        }
+        let declared: BTreeSet<&str> = declared.iter().map(|(name, _)| name.as_str()).collect();
+        let expected: BTreeSet<&str> = exports.iter().map(|(name, _)| *name).collect();
+        assert_eq!(declared, expected);
+    }
+
+    #[test]
+    fn test_collections_register_through_adapters() {
+        let source = component()
+            .nif_collection("esp_sensors")
+            .port_driver(PortDriver::new("esp_uart").with_init_and_destroy())
+            .port_driver(PortDriver::new("esp_gpio"))
+            .registration_source();
+        assert!(source.contains("#include <esp32_sys.h>"));
+        assert!(source.contains(
+            "REGISTER_NIF_COLLECTION(esp_sensors, esp_sensors_avmnif_init, esp_sensors_nif_destroy, esp_sensors_avmnif_resolve)"
+        ));
+        assert!(source.contains("REGISTER_PORT_DRIVER(esp_uart, esp_uart_init, esp_uart_destroy, esp_uart_avmnif_create_port)"));
+        assert!(source.contains("REGISTER_PORT_DRIVER(esp_gpio, NULL, NULL, esp_gpio_avmnif_create_port)"));
+        // The adapters are emitted once, whatever the number of collections
+        let twice = component().nif_collection("a").nif_collection("b").registration_source();
+        assert_eq!(twice.matches("static const struct Nif *avmnif_nif(").count(), 1);
+        assert_eq!(twice.matches("uint32_t sensor_nifs_nif_flags(const struct Nif *nif)").count(), 1);
+    }
+
+    #[test]
+    fn test_resolver_keeps_the_flags_beside_the_nif() {
+        let source = component().nif_collection("esp_sensors").registration_source();
+        assert!(source.contains("return avmnif_nif(esp_sensors_get_nif(name), esp_sensors_get_nif_flags(name));"));
+        assert!(source.contains("record->flags = flags;"));
+        assert!(source.contains("return avmnif_flags(nif);"));
+    }
+
+    #[test]
+    fn test_empty_component_registers_nothing() {
+        let source = component().registration_source();
+        assert!(!source.contains("REGISTER_"));
+        assert!(!source.contains("avmnif_nif("));
+        assert_eq!(component().name(), "sensor_nifs");
+    }
+}
@@ -0,0 +1,199 @@
+//! ETF encoder/decoder testing suite
+
+use crate::atom::AtomTableOps;
+use crate::etf::{self, EtfError};
+use crate::term::{DecodeError, DecodeReason, FunctionRef, NifError, PortId, ProcessId, RefId, TermValue};
+use crate::testing::mocks::MockAtomTable;
+use alloc::vec;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(term: TermValue, table: &MockAtomTable) {
+        let bytes = etf::encode(&term, table).unwrap();
+        assert_eq!(etf::decode(&bytes, table).unwrap(), term);
+    }
+
+    #[test]
+    fn test_matches_term_to_binary() {
+        let table = MockAtomTable::new();
+        // term_to_binary({ok, 1})
+        let term = TermValue::tuple(vec![TermValue::atom("ok", &table), TermValue::int(1)]);
+        assert_eq!(
+            etf::encode(&term, &table).unwrap(),
+            vec![131, 104, 2, 119, 2, b'o', b'k', 97, 1]
+        );
+
+        // term_to_binary([1, -1]) and term_to_binary(<<"hi">>)
+        let list = TermValue::list(vec![TermValue::int(1), TermValue::int(-1)]);
+        assert_eq!(
+            etf::encode(&list, &table).unwrap(),
+            vec![131, 108, 0, 0, 0, 2, 97, 1, 98, 255, 255, 255, 255, 106]
+        );
+        assert_eq!(
+            etf::encode(&TermValue::binary(b"hi".to_vec()), &table).unwrap(),
+            vec![131, 109, 0, 0, 0, 2, b'h', b'i']
+        );
+    }
+
+    #[test]
+    fn test_round_trip_all_supported_types() {
+        let table = MockAtomTable::new();
+        let module = table.ensure_atom_str("lists").unwrap();
+        let function = table.ensure_atom_str("map").unwrap();
+
+        round_trip(TermValue::int(0), &table);
+        round_trip(TermValue::int(i32::MIN), &table);
+        round_trip(TermValue::integer(i32::MAX as i64 + 1), &table);
+        round_trip(TermValue::integer(i64::MIN), &table);
+        round_trip(TermValue::Nil, &table);
+        round_trip(TermValue::Float(-2.5), &table);
+        round_trip(TermValue::Pid(ProcessId(42)), &table);
+        round_trip(TermValue::Port(PortId(7)), &table);
+        round_trip(TermValue::Reference(RefId(0x1234_5678_9abc)), &table);
+        round_trip(TermValue::Function(FunctionRef { module, function, arity: 2 }), &table);
+        round_trip(
+            TermValue::Map(vec![
+                (TermValue::atom("temp", &table), TermValue::Float(21.5)),
+                (TermValue::binary(vec![1, 2]), TermValue::tuple(vec![])),
+            ]),
+            &table,
+        );
+        // Improper list
+        round_trip(
+            TermValue::List(
+                alloc::boxed::Box::new(TermValue::int(1)),
+                alloc::boxed::Box::new(TermValue::int(2)),
+            ),
+            &table,
+        );
+    }
+
+    #[test]
+    fn test_decode_against_other_table() {
+        let source = MockAtomTable::new_with_atoms(&["a", "b"]);
+        let target = MockAtomTable::new_with_atoms(&["b", "a"]);
+        let term = TermValue::tuple(vec![TermValue::atom("a", &source), TermValue::atom("b", &source)]);
+
+        let bytes = etf::encode(&term, &source).unwrap();
+        let decoded = etf::decode(&bytes, &target).unwrap();
+        assert_eq!(
+            decoded,
+            TermValue::tuple(vec![TermValue::atom("a", &target), TermValue::atom("b", &target)])
+        );
+    }
+
+    #[test]
+    fn test_decode_legacy_forms() {
+        let table = MockAtomTable::new();
+        // ATOM_EXT 'ok', STRING_EXT "ab", SMALL_BIG_EXT -2^31, 2^63 and -2^63
+        assert!(etf::decode(&[131, 100, 0, 2, b'o', b'k'], &table).unwrap().is_atom_str("ok", &table));
+        assert_eq!(
+            etf::decode(&[131, 107, 0, 2, b'a', b'b'], &table).unwrap(),
+            TermValue::list(vec![TermValue::int(97), TermValue::int(98)])
+        );
+        assert_eq!(
+            etf::decode(&[131, 110, 4, 1, 0, 0, 0, 128], &table).unwrap(),
+            TermValue::int(i32::MIN)
+        );
+        assert_eq!(
+            etf::decode(&[131, 110, 8, 0, 0, 0, 0, 0, 0, 0, 0, 128], &table),
+            Err(EtfError::IntegerOverflow)
+        );
+        assert_eq!(
+            etf::decode(&[131, 110, 8, 1, 0, 0, 0, 0, 0, 0, 0, 128], &table).unwrap(),
+            TermValue::Integer(i64::MIN)
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed_input() {
+        let table = MockAtomTable::new();
+        assert_eq!(etf::decode(&[], &table), Err(EtfError::UnexpectedEnd));
+        assert_eq!(etf::decode(&[130, 106], &table), Err(EtfError::BadVersion(130)));
+        assert_eq!(etf::decode(&[131, 1], &table), Err(EtfError::UnknownTag(1)));
+        assert_eq!(etf::decode(&[131, 106, 0], &table), Err(EtfError::TrailingBytes(1)));
+        // Claims a huge list but has no elements
+        assert_eq!(
+            etf::decode(&[131, 108, 0xff, 0xff, 0xff, 0xff], &table),
+            Err(EtfError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_decode_depth_limit() {
+        let table = MockAtomTable::new();
+        let mut bytes = vec![131];
+        for _ in 0..(etf::MAX_DEPTH + 2) {
+            bytes.extend_from_slice(&[104, 1]);
+        }
+        bytes.push(106);
+        assert_eq!(etf::decode(&bytes, &table), Err(EtfError::TooDeep));
+    }
+
+    #[test]
+    fn test_encode_unsupported() {
+        let table = MockAtomTable::new();
+        assert_eq!(
+            etf::encode(
+                &TermValue::Invalid(DecodeError { raw: 0x3F, reason: DecodeReason::UnknownTag }),
+                &table
+            ),
+            Err(EtfError::Unsupported("invalid term"))
+        );
+    }
+
+    #[test]
+    fn test_list_encoder_matches_list() {
+        let table = MockAtomTable::new();
+        let events = [
+            TermValue::tuple(vec![TermValue::atom("sample", &table), TermValue::int(1)]),
+            TermValue::tuple(vec![TermValue::atom("sample", &table), TermValue::int(-2)]),
+            TermValue::binary(b"raw".to_vec()),
+        ];
+
+        let mut batch = etf::ListEncoder::with_capacity(64);
+        for event in &events {
+            batch.push(event, &table).unwrap();
+        }
+        assert_eq!(batch.len(), 3);
+        let expected = etf::encode(&TermValue::list(events.to_vec()), &table).unwrap();
+        assert_eq!(batch.finish(), &expected[..]);
+
+        // Reused after clear without the earlier elements
+        batch.clear();
+        assert!(batch.is_empty());
+        assert_eq!(batch.encoded_len(), 0);
+        batch.push(&TermValue::int(7), &table).unwrap();
+        let expected = etf::encode(&TermValue::list(vec![TermValue::int(7)]), &table).unwrap();
+        assert_eq!(batch.finish(), &expected[..]);
+    }
+
+    #[test]
+    fn test_list_encoder_empty_and_failed_push() {
+        let table = MockAtomTable::new();
+        let mut batch = etf::ListEncoder::new();
+        assert_eq!(batch.finish(), &[131, 106][..]);
+
+        batch.clear();
+        batch.push(&TermValue::int(1), &table).unwrap();
+        let invalid = TermValue::Invalid(DecodeError { raw: 0x3F, reason: DecodeReason::UnknownTag });
+        assert!(batch.push(&invalid, &table).is_err());
+        // The failed element left nothing behind
+        assert_eq!(batch.len(), 1);
+        let expected = etf::encode(&TermValue::list(vec![TermValue::int(1)]), &table).unwrap();
+        assert_eq!(batch.finish(), &expected[..]);
+    }
+
+    #[test]
+    fn test_etf_error_to_nif_error() {
+        // Used by port::send when a message cannot be serialized
+        assert_eq!(NifError::from(EtfError::Unsupported("resource")), NifError::BadArg);
+        assert_eq!(
+            NifError::from(EtfError::Atom(crate::atom::AtomError::AllocationFailed)),
+            NifError::OutOfMemory
+        );
+        assert_eq!(NifError::from(EtfError::UnexpectedEnd), NifError::InvalidTerm);
+    }
+}
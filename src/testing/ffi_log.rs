@@ -0,0 +1,455 @@
+//! Instrumented FFI call log
+//!
+//! Records the sequence of simulated AtomVM calls made during a test and
+//! checks ordering invariants on it. Decorators wrap the injectable backends
+//! (`ResourceManager`, `TimerBackend`, `ProcessMonitor`) and forward every
+//! call after logging it; calls without a backend trait (heap reservation,
+//! replies) are logged directly by the test through `FfiCallLog`.
+//!
+//! ```rust,ignore
+//! let log = FfiCallLog::new();
+//! let mut manager = RecordingResourceManager::new(MockResourceManager::new(), log.clone());
+//! // ... exercise code under test ...
+//! log.assert_order(&["alloc_resource", "make_resource", "release_resource"]);
+//! log.assert_reply_once_per_request();
+//! ```
+
+use crate::monitor::{MonitorError, ProcessMonitor};
+use crate::port::timer::TimerBackend;
+use crate::resource::*;
+use alloc::{format, rc::Rc, string::String, vec::Vec};
+use core::cell::RefCell;
+use core::ffi::{c_uint, c_void};
+
+/// One simulated FFI call
+#[derive(Debug, Clone, PartialEq)]
+pub enum FfiCall {
+    InitResourceType(String),
+    AllocResource(u32),
+    MakeResource,
+    GetResource,
+    KeepResource,
+    ReleaseResource,
+    Select,
+    MonitorProcess(u32),
+    DemonitorProcess(u32),
+    TimerArm(u64),
+    TimerDisarm,
+    /// `avmnif_memory_ensure_free` for this many words
+    EnsureFree(usize),
+    /// Words written to the process heap
+    HeapWrite(usize),
+    /// Port request received, identified by its reference
+    Request(u64),
+    /// Reply sent for the request with this reference
+    SendReply(u64),
+    /// Message sent to a pid outside any request
+    SendMessage(u32),
+}
+
+impl FfiCall {
+    /// Name of the call, as used by `assert_order`
+    pub fn name(&self) -> &'static str {
+        match self {
+            FfiCall::InitResourceType(_) => "init_resource_type",
+            FfiCall::AllocResource(_) => "alloc_resource",
+            FfiCall::MakeResource => "make_resource",
+            FfiCall::GetResource => "get_resource",
+            FfiCall::KeepResource => "keep_resource",
+            FfiCall::ReleaseResource => "release_resource",
+            FfiCall::Select => "select",
+            FfiCall::MonitorProcess(_) => "monitor_process",
+            FfiCall::DemonitorProcess(_) => "demonitor_process",
+            FfiCall::TimerArm(_) => "timer_arm",
+            FfiCall::TimerDisarm => "timer_disarm",
+            FfiCall::EnsureFree(_) => "ensure_free",
+            FfiCall::HeapWrite(_) => "heap_write",
+            FfiCall::Request(_) => "request",
+            FfiCall::SendReply(_) => "send_reply",
+            FfiCall::SendMessage(_) => "send_message",
+        }
+    }
+}
+
+/// Shared, cloneable log of simulated FFI calls
+#[derive(Debug, Clone, Default)]
+pub struct FfiCallLog {
+    calls: Rc<RefCell<Vec<FfiCall>>>,
+}
+
+impl FfiCallLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a call
+    pub fn record(&self, call: FfiCall) {
+        self.calls.borrow_mut().push(call);
+    }
+
+    /// Log a heap reservation
+    pub fn ensure_free(&self, words: usize) {
+        self.record(FfiCall::EnsureFree(words));
+    }
+
+    /// Log a heap write
+    pub fn heap_write(&self, words: usize) {
+        self.record(FfiCall::HeapWrite(words));
+    }
+
+    /// Log an incoming port request
+    pub fn request(&self, reference: u64) {
+        self.record(FfiCall::Request(reference));
+    }
+
+    /// Log a reply to a request
+    pub fn send_reply(&self, reference: u64) {
+        self.record(FfiCall::SendReply(reference));
+    }
+
+    /// Log a plain message send
+    pub fn send_message(&self, pid: u32) {
+        self.record(FfiCall::SendMessage(pid));
+    }
+
+    /// Snapshot of every call so far
+    pub fn calls(&self) -> Vec<FfiCall> {
+        self.calls.borrow().clone()
+    }
+
+    /// Call names in order
+    pub fn names(&self) -> Vec<&'static str> {
+        self.calls.borrow().iter().map(FfiCall::name).collect()
+    }
+
+    /// Number of calls with the given name
+    pub fn count(&self, name: &str) -> usize {
+        self.calls.borrow().iter().filter(|call| call.name() == name).count()
+    }
+
+    /// Forget every call
+    pub fn clear(&self) {
+        self.calls.borrow_mut().clear();
+    }
+
+    /// Check that `expected` occurs in this order (other calls may be interleaved)
+    pub fn check_order(&self, expected: &[&str]) -> Result<(), String> {
+        let names = self.names();
+        let mut rest = names.iter();
+        for want in expected {
+            if !rest.any(|name| name == want) {
+                return Err(format!("`{}` missing or out of order in {:?}", want, names));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that every request got exactly one reply, after it, and no reply is unsolicited
+    pub fn check_reply_once_per_request(&self) -> Result<(), String> {
+        let mut open: Vec<u64> = Vec::new();
+        let mut answered: Vec<u64> = Vec::new();
+        for (i, call) in self.calls.borrow().iter().enumerate() {
+            match call {
+                FfiCall::Request(reference) => open.push(*reference),
+                FfiCall::SendReply(reference) => match open.iter().position(|r| r == reference) {
+                    Some(pos) => answered.push(open.swap_remove(pos)),
+                    None if answered.contains(reference) => {
+                        return Err(format!("call {}: second reply to request {}", i, reference));
+                    }
+                    None => return Err(format!("call {}: reply to unknown request {}", i, reference)),
+                },
+                _ => {}
+            }
+        }
+        match open.first() {
+            Some(reference) => Err(format!("request {} never got a reply", reference)),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that every heap write fits in the space reserved by the latest `ensure_free`
+    pub fn check_ensure_free_before_heap_writes(&self) -> Result<(), String> {
+        let mut remaining: Option<usize> = None;
+        for (i, call) in self.calls.borrow().iter().enumerate() {
+            match call {
+                FfiCall::EnsureFree(words) => remaining = Some(*words),
+                FfiCall::HeapWrite(words) => match remaining {
+                    Some(free) if *words <= free => remaining = Some(free - words),
+                    Some(free) => {
+                        return Err(format!("call {}: wrote {} words with {} reserved", i, words, free));
+                    }
+                    None => return Err(format!("call {}: heap write before ensure_free", i)),
+                },
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Panicking form of `check_order`
+    #[track_caller]
+    pub fn assert_order(&self, expected: &[&str]) {
+        if let Err(e) = self.check_order(expected) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Panicking form of `check_reply_once_per_request`
+    #[track_caller]
+    pub fn assert_reply_once_per_request(&self) {
+        if let Err(e) = self.check_reply_once_per_request() {
+            panic!("{}", e);
+        }
+    }
+
+    /// Panicking form of `check_ensure_free_before_heap_writes`
+    #[track_caller]
+    pub fn assert_ensure_free_before_heap_writes(&self) {
+        if let Err(e) = self.check_ensure_free_before_heap_writes() {
+            panic!("{}", e);
+        }
+    }
+}
+
+// ── Decorators ──────────────────────────────────────────────────────────────
+
+/// `ResourceManager` decorator that logs every call before forwarding it
+pub struct RecordingResourceManager<R: ResourceManager> {
+    pub inner: R,
+    log: FfiCallLog,
+}
+
+// Tests are single-threaded; the shared log is never touched concurrently
+unsafe impl<R: ResourceManager> Send for RecordingResourceManager<R> {}
+unsafe impl<R: ResourceManager> Sync for RecordingResourceManager<R> {}
+
+impl<R: ResourceManager> RecordingResourceManager<R> {
+    pub fn new(inner: R, log: FfiCallLog) -> Self {
+        Self { inner, log }
+    }
+}
+
+impl<R: ResourceManager> ResourceManager for RecordingResourceManager<R> {
+    fn init_resource_type(
+        &mut self,
+        env: *mut ErlNifEnv,
+        name: &str,
+        init: &ErlNifResourceTypeInit,
+        flags: ErlNifResourceFlags,
+    ) -> Result<*mut ErlNifResourceType, ResourceError> {
+        self.log.record(FfiCall::InitResourceType(name.into()));
+        self.inner.init_resource_type(env, name, init, flags)
+    }
+
+    fn alloc_resource(
+        &self,
+        resource_type: *mut ErlNifResourceType,
+        size: c_uint,
+    ) -> Result<*mut c_void, ResourceError> {
+        self.log.record(FfiCall::AllocResource(size));
+        self.inner.alloc_resource(resource_type, size)
+    }
+
+    fn make_resource(&self, env: *mut ErlNifEnv, obj: *mut c_void) -> Result<ERL_NIF_TERM, ResourceError> {
+        self.log.record(FfiCall::MakeResource);
+        self.inner.make_resource(env, obj)
+    }
+
+    fn get_resource(
+        &self,
+        env: *mut ErlNifEnv,
+        term: ERL_NIF_TERM,
+        resource_type: *mut ErlNifResourceType,
+    ) -> Result<*mut c_void, ResourceError> {
+        self.log.record(FfiCall::GetResource);
+        self.inner.get_resource(env, term, resource_type)
+    }
+
+    fn keep_resource(&self, obj: *mut c_void) -> Result<(), ResourceError> {
+        self.log.record(FfiCall::KeepResource);
+        self.inner.keep_resource(obj)
+    }
+
+    fn release_resource(&self, obj: *mut c_void) -> Result<(), ResourceError> {
+        self.log.record(FfiCall::ReleaseResource);
+        self.inner.release_resource(obj)
+    }
+
+    fn select(
+        &self,
+        env: *mut ErlNifEnv,
+        event: ErlNifEvent,
+        mode: ErlNifSelectFlags,
+        obj: *mut c_void,
+        pid: *const ErlNifPid,
+        reference: ERL_NIF_TERM,
+    ) -> Result<(), ResourceError> {
+        self.log.record(FfiCall::Select);
+        self.inner.select(env, event, mode, obj, pid, reference)
+    }
+
+    fn monitor_process(
+        &self,
+        env: *mut ErlNifEnv,
+        obj: *mut c_void,
+        target_pid: *const ErlNifPid,
+        mon: *mut ErlNifMonitor,
+    ) -> Result<(), ResourceError> {
+        let pid = if target_pid.is_null() { 0 } else { unsafe { *target_pid as u32 } };
+        self.log.record(FfiCall::MonitorProcess(pid));
+        self.inner.monitor_process(env, obj, target_pid, mon)
+    }
+
+    fn demonitor_process(
+        &self,
+        env: *mut ErlNifEnv,
+        obj: *mut c_void,
+        mon: *const ErlNifMonitor,
+    ) -> Result<(), ResourceError> {
+        self.log.record(FfiCall::DemonitorProcess(0));
+        self.inner.demonitor_process(env, obj, mon)
+    }
+}
+
+/// `TimerBackend` decorator that logs arm/disarm calls
+pub struct RecordingTimerBackend<B: TimerBackend> {
+    pub inner: B,
+    log: FfiCallLog,
+}
+
+impl<B: TimerBackend> RecordingTimerBackend<B> {
+    pub fn new(inner: B, log: FfiCallLog) -> Self {
+        Self { inner, log }
+    }
+}
+
+impl<B: TimerBackend> TimerBackend for RecordingTimerBackend<B> {
+    fn now_ms(&self) -> u64 {
+        self.inner.now_ms()
+    }
+
+    fn arm(&mut self, delay_ms: u64) {
+        self.log.record(FfiCall::TimerArm(delay_ms));
+        self.inner.arm(delay_ms);
+    }
+
+    fn disarm(&mut self) {
+        self.log.record(FfiCall::TimerDisarm);
+        self.inner.disarm();
+    }
+}
+
+/// `ProcessMonitor` decorator that logs monitor/demonitor calls
+pub struct RecordingProcessMonitor<M: ProcessMonitor> {
+    pub inner: M,
+    log: FfiCallLog,
+}
+
+impl<M: ProcessMonitor> RecordingProcessMonitor<M> {
+    pub fn new(inner: M, log: FfiCallLog) -> Self {
+        Self { inner, log }
+    }
+}
+
+impl<M: ProcessMonitor> ProcessMonitor for RecordingProcessMonitor<M> {
+    type Handle = M::Handle;
+
+    fn monitor(&mut self, pid: u32) -> Result<M::Handle, MonitorError> {
+        self.log.record(FfiCall::MonitorProcess(pid));
+        self.inner.monitor(pid)
+    }
+
+    fn demonitor(&mut self, pid: u32, handle: &M::Handle) {
+        self.log.record(FfiCall::DemonitorProcess(pid));
+        self.inner.demonitor(pid, handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::PidSet;
+    use crate::port::timer::PortTimers;
+    use crate::testing::mocks::{MockProcessMonitor, MockResourceManager, MockTimerBackend};
+
+    #[test]
+    fn test_resource_calls_recorded_in_order() {
+        let log = FfiCallLog::new();
+        let mut manager = RecordingResourceManager::new(MockResourceManager::new(), log.clone());
+        let env = core::ptr::null_mut();
+
+        let resource_type = manager
+            .init_resource_type(env, "buffer", &resource_type_init(), ErlNifResourceFlags::ERL_NIF_RT_CREATE)
+            .unwrap();
+        let obj = manager.alloc_resource(resource_type, 32).unwrap();
+        manager.make_resource(env, obj).unwrap();
+        manager.release_resource(obj).unwrap();
+
+        log.assert_order(&["init_resource_type", "alloc_resource", "make_resource", "release_resource"]);
+        assert!(log.check_order(&["make_resource", "alloc_resource"]).is_err());
+        assert_eq!(log.calls()[1], FfiCall::AllocResource(32));
+    }
+
+    #[test]
+    fn test_backend_decorators() {
+        let log = FfiCallLog::new();
+
+        let mut timers = PortTimers::new();
+        let mut backend = RecordingTimerBackend::new(MockTimerBackend::new(), log.clone());
+        let timer = timers.start_once(&mut backend, 50);
+        timers.cancel(&mut backend, timer);
+
+        let mut monitor = RecordingProcessMonitor::new(MockProcessMonitor::with_alive(&[4]), log.clone());
+        let mut set = PidSet::new();
+        set.insert(&mut monitor, 4).unwrap();
+        set.clear(&mut monitor);
+
+        assert_eq!(log.calls(), alloc::vec![
+            FfiCall::TimerArm(50),
+            FfiCall::TimerDisarm,
+            FfiCall::MonitorProcess(4),
+            FfiCall::DemonitorProcess(4),
+        ]);
+    }
+
+    #[test]
+    fn test_reply_once_per_request() {
+        let log = FfiCallLog::new();
+        log.request(1);
+        log.request(2);
+        log.send_message(9);
+        log.send_reply(2);
+        log.send_reply(1);
+        log.assert_reply_once_per_request();
+
+        log.send_reply(1);
+        assert!(log.check_reply_once_per_request().unwrap_err().contains("second reply"));
+
+        log.clear();
+        log.send_reply(3);
+        assert!(log.check_reply_once_per_request().unwrap_err().contains("unknown request"));
+
+        log.clear();
+        log.request(4);
+        assert!(log.check_reply_once_per_request().unwrap_err().contains("never got a reply"));
+    }
+
+    #[test]
+    fn test_ensure_free_before_heap_writes() {
+        let log = FfiCallLog::new();
+        log.ensure_free(5);
+        log.heap_write(3);
+        log.heap_write(2);
+        log.assert_ensure_free_before_heap_writes();
+
+        log.heap_write(1);
+        assert!(log.check_ensure_free_before_heap_writes().is_err());
+
+        log.clear();
+        log.heap_write(1);
+        assert!(log
+            .check_ensure_free_before_heap_writes()
+            .unwrap_err()
+            .contains("before ensure_free"));
+    }
+}
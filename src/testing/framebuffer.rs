@@ -0,0 +1,159 @@
+//! Framebuffer testing suite
+
+use crate::framebuffer::{Display, FrameRegion, Framebuffer, FramebufferType, PixelFormat, Rect};
+use crate::resource::{
+    resource_dtor, resource_type_init_with_dtor, ErlNifResourceFlags, ErlNifResourceType, ResourceArc, ResourceManager,
+};
+use crate::term::{NifError, NifResult};
+use crate::testing::mocks::MockResourceManager;
+use alloc::boxed::Box;
+use alloc::{vec, vec::Vec};
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+static PANEL_TYPE: AtomicPtr<ErlNifResourceType> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Records every transfer as (area, rows)
+#[derive(Default)]
+struct MockPanel {
+    transfers: Vec<(Rect, Vec<Vec<u8>>)>,
+    fail: bool,
+}
+
+impl Display for MockPanel {
+    fn flush(&mut self, region: FrameRegion<'_>) -> NifResult<()> {
+        if self.fail {
+            return Err(NifError::Other("spi timeout"));
+        }
+        self.transfers.push((region.area(), region.rows().map(<[u8]>::to_vec).collect()));
+        Ok(())
+    }
+}
+
+impl FramebufferType for MockPanel {
+    fn resource_type() -> *mut ErlNifResourceType {
+        PANEL_TYPE.load(Ordering::SeqCst)
+    }
+}
+
+/// Create a leaked mock manager with the `Framebuffer<MockPanel>` type registered
+fn panel_manager() -> &'static MockResourceManager {
+    let mut manager = MockResourceManager::new();
+    let resource_type = manager
+        .init_resource_type(
+            core::ptr::null_mut(),
+            "panel",
+            &resource_type_init_with_dtor(resource_dtor::<Framebuffer<MockPanel>>),
+            ErlNifResourceFlags::ERL_NIF_RT_CREATE,
+        )
+        .unwrap();
+    PANEL_TYPE.store(resource_type, Ordering::SeqCst);
+    Box::leak(Box::new(manager))
+}
+
+fn framebuffer(width: u16, height: u16, format: PixelFormat) -> Framebuffer<MockPanel> {
+    Framebuffer::new(MockPanel::default(), width, height, format).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blit_and_flush_dirty_area() {
+        let fb = framebuffer(4, 3, PixelFormat::Rgb565);
+        assert_eq!(fb.stride(), 8);
+        assert_eq!(fb.flush(), Ok(false));
+
+        fb.blit(Rect::new(1, 1, 2, 2), &[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        assert_eq!(fb.dirty(), Some(Rect::new(1, 1, 2, 2)));
+        assert_eq!(fb.flush(), Ok(true));
+        assert_eq!(fb.dirty(), None);
+
+        fb.with_display(|panel| {
+            assert_eq!(
+                panel.transfers,
+                vec![(Rect::new(1, 1, 2, 2), vec![vec![1, 2, 3, 4], vec![5, 6, 7, 8]])]
+            );
+        });
+    }
+
+    #[test]
+    fn test_changes_merge_into_one_area() {
+        let fb = framebuffer(8, 8, PixelFormat::Gray8);
+        fb.set_pixel(1, 2, 0xFF).unwrap();
+        fb.fill_rect(Rect::new(5, 6, 2, 1), 0x80).unwrap();
+        assert_eq!(fb.dirty(), Some(Rect::new(1, 2, 6, 5)));
+
+        fb.with_pixels(|pixels| {
+            assert_eq!(pixels[2 * 8 + 1], 0xFF);
+            assert_eq!(&pixels[6 * 8 + 5..6 * 8 + 7], &[0x80, 0x80]);
+        });
+        assert_eq!(fb.dirty(), Some(Rect::new(0, 0, 8, 8)));
+    }
+
+    #[test]
+    fn test_fill_encodes_big_endian() {
+        let fb = framebuffer(2, 1, PixelFormat::Rgb565);
+        fb.fill(0xF800);
+        fb.flush_all().unwrap();
+        fb.with_display(|panel| assert_eq!(panel.transfers[0].1, vec![vec![0xF8, 0x00, 0xF8, 0x00]]));
+
+        let mut pixel = [0; 3];
+        PixelFormat::Rgb888.encode(0x112233, &mut pixel);
+        assert_eq!(pixel, [0x11, 0x22, 0x33]);
+    }
+
+    #[test]
+    fn test_out_of_bounds_and_short_blits_rejected() {
+        let fb = framebuffer(4, 4, PixelFormat::Gray8);
+        assert_eq!(fb.blit(Rect::new(3, 0, 2, 1), &[0, 0]), Err(NifError::BadArg));
+        assert_eq!(fb.blit(Rect::new(0, 0, 2, 2), &[0, 0, 0]), Err(NifError::BadArg));
+        assert_eq!(fb.set_pixel(0, 4, 1), Err(NifError::BadArg));
+        assert_eq!(fb.dirty(), None);
+    }
+
+    #[test]
+    fn test_failed_flush_keeps_area_dirty() {
+        let fb = framebuffer(2, 2, PixelFormat::Gray8);
+        fb.set_pixel(0, 0, 1).unwrap();
+        fb.with_display(|panel| panel.fail = true);
+        assert_eq!(fb.flush(), Err(NifError::Other("spi timeout")));
+        assert_eq!(fb.dirty(), Some(Rect::new(0, 0, 1, 1)));
+
+        fb.with_display(|panel| panel.fail = false);
+        assert_eq!(fb.flush(), Ok(true));
+    }
+
+    #[test]
+    fn test_full_width_region_is_contiguous() {
+        struct Contiguous(Option<Vec<u8>>);
+        impl Display for Contiguous {
+            fn flush(&mut self, region: FrameRegion<'_>) -> NifResult<()> {
+                self.0 = region.as_contiguous().map(<[u8]>::to_vec);
+                Ok(())
+            }
+        }
+        let pixels = [1, 2, 3, 4, 5, 6];
+        let whole = Framebuffer::new(Contiguous(None), 3, 2, PixelFormat::Gray8).unwrap();
+        whole.blit(Rect::new(0, 0, 3, 2), &pixels).unwrap();
+        whole.flush().unwrap();
+        assert_eq!(whole.with_display(|display| display.0.clone()), Some(pixels.to_vec()));
+
+        whole.set_pixel(1, 1, 9).unwrap();
+        whole.flush().unwrap();
+        assert_eq!(whole.with_display(|display| display.0.clone()), None);
+    }
+
+    #[test]
+    fn test_resource_drops_buffer_and_display() {
+        let manager = panel_manager();
+        let fb = ResourceArc::new_in(manager, framebuffer(16, 16, PixelFormat::Rgb888)).unwrap();
+        let handle = fb.clone();
+        handle.fill(0x00FF00);
+        assert_eq!(fb.dirty(), Some(Rect::new(0, 0, 16, 16)));
+
+        drop(fb);
+        drop(handle);
+        assert_eq!(manager.get_destructor_call_count(), 1);
+    }
+}
@@ -0,0 +1,257 @@
+//! Fuzzing entry points for term decoding
+//!
+//! Each function takes an arbitrary byte blob and must return without
+//! panicking, whatever the bytes are. The `cargo-fuzz` targets in `fuzz/`
+//! call them; they are plain functions so a crash found by the fuzzer can
+//! be kept as a regular test.
+//!
+//! - `term_to_value` lays the bytes out as a process heap and decodes the
+//!   first word with `Term::to_value`
+//! - `etf_decode` parses the bytes as external term format
+//! - `tagged_from_map` builds a term from the bytes and reads it back
+//!   through `TaggedMap::from_tagged_map`
+//!
+//! ```text
+//! cargo +nightly fuzz run term_to_value
+//! ```
+
+extern crate alloc;
+extern crate std;
+
+use crate::atom::AtomTableOps;
+use crate::etf;
+use crate::tagged::TaggedMap;
+use crate::term::{Term, TermValue};
+use crate::testing::generate::{arbitrary_term, TermShape};
+use crate::testing::mocks::MockAtomTable;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Words of heap a blob is cut down to
+pub const MAX_HEAP_WORDS: usize = 1024;
+
+const WORD: usize = core::mem::size_of::<usize>();
+
+std::thread_local! {
+    /// One table for the whole run: the mock never frees an atom's name
+    static TABLE: MockAtomTable = {
+        let table = MockAtomTable::new();
+        for atom in ["reading", "sensor", "value", "unit", "tags", "mode", "idle", "burst", "count"] {
+            let _ = table.ensure_atom_str(atom);
+        }
+        table
+    };
+}
+
+/// Decode a heap built from `data` with `Term::to_value` and `Term::decode`
+pub fn term_to_value(data: &[u8]) {
+    let heap = FuzzHeap::new(data);
+    if let Some(root) = heap.root() {
+        let _ = root.to_value();
+        let _ = root.decode(crate::term::DecodeMode::Lenient);
+    }
+}
+
+/// Decode `data` as ETF, re-encoding whatever decodes
+pub fn etf_decode(data: &[u8]) {
+    TABLE.with(|table| {
+        if let Ok(term) = etf::decode(data, table) {
+            let _ = etf::encode(&term, table);
+        }
+        let _ = etf::decode_prefix(data, table);
+    });
+}
+
+/// Read a term built from `data`, and `data` as ETF, as tagged maps
+pub fn tagged_from_map(data: &[u8]) {
+    TABLE.with(|table| {
+        let mut u = arbitrary::Unstructured::new(data);
+        if let Ok(term) = arbitrary_term(&mut u, &TermShape::new(table)) {
+            read_tagged(term, table);
+        }
+        if let Ok(term) = etf::decode(data, table) {
+            read_tagged(term, table);
+        }
+    });
+}
+
+fn read_tagged(term: TermValue, table: &MockAtomTable) {
+    let _ = Reading::from_tagged_map(term.clone(), table);
+    let _ = Mode::from_tagged_map(term, table);
+}
+
+#[derive(Debug, Clone, PartialEq, avmnif_derive::TaggedMap)]
+struct Reading {
+    sensor: String,
+    value: i32,
+    unit: Option<String>,
+    tags: Vec<u8>,
+    mode: Mode,
+}
+
+#[derive(Debug, Clone, PartialEq, avmnif_derive::TaggedMap)]
+enum Mode {
+    Idle,
+    Burst { count: u8 },
+}
+
+/// A blob laid out as a heap the decoder can walk without leaving it
+///
+/// The words are the blob's bytes. List and boxed pointers are turned into
+/// pointers to a later word of the heap, so every term is acyclic, and
+/// each boxed header is cut to the words left after it. Objects too short
+/// for what the decoder reads from them become `[]`, refc binaries become
+/// heap binaries, and heap binary sizes are cut to their words. Anything
+/// the decoder does past that is its own doing.
+pub struct FuzzHeap {
+    words: Vec<usize>,
+}
+
+impl FuzzHeap {
+    pub fn new(data: &[u8]) -> Self {
+        let words: Vec<usize> = data
+            .chunks(WORD)
+            .take(MAX_HEAP_WORDS)
+            .map(|chunk| {
+                let mut bytes = [0; WORD];
+                bytes[..chunk.len()].copy_from_slice(chunk);
+                usize::from_le_bytes(bytes)
+            })
+            .collect();
+        let mut heap = Self { words };
+        heap.fix_pointers();
+        heap
+    }
+
+    /// The term in the first word, `None` for an empty blob
+    pub fn root(&self) -> Option<Term<'_>> {
+        self.words.first().map(|&word| Term::from_raw(word))
+    }
+
+    fn fix_pointers(&mut self) {
+        let len = self.words.len();
+        let base = self.words.as_ptr() as usize;
+        let mut roles = alloc::vec![Role::Free; len];
+        // Last to first, so a pointer's target is settled before it
+        for i in (0..len).rev() {
+            let word = self.words[i];
+            let primary = word & Term::TERM_PRIMARY_MASK;
+            if primary != Term::TERM_PRIMARY_LIST && primary != Term::TERM_PRIMARY_BOXED {
+                continue;
+            }
+            let room = len - i - 1;
+            let target = if room == 0 { None } else { Some(i + 1 + (word >> 2) % room) };
+            let fixed = match target {
+                Some(target) if primary == Term::TERM_PRIMARY_LIST && target + 1 < len => Some(target),
+                Some(target) if primary == Term::TERM_PRIMARY_BOXED && self.fix_boxed(target, &mut roles) => Some(target),
+                _ => None,
+            };
+            self.words[i] = match fixed {
+                Some(target) => (base + target * WORD) | primary,
+                None => Term::TERM_NIL,
+            };
+        }
+    }
+
+    /// Fit the object at `at` into the heap; false if it cannot
+    ///
+    /// A header or binary size already written for another object is
+    /// never overwritten, so objects that overlap keep their own bounds.
+    fn fix_boxed(&mut self, at: usize, roles: &mut [Role]) -> bool {
+        match roles[at] {
+            Role::Header => return true,
+            Role::Size => return false,
+            Role::Free => {}
+        }
+        let header = self.words[at];
+        let mut tag = header & Term::TERM_BOXED_TAG_MASK;
+        if tag & Term::TERM_PRIMARY_MASK != 0 {
+            return false;
+        }
+        if tag == Term::TERM_BOXED_REFC_BINARY {
+            tag = Term::TERM_BOXED_HEAP_BINARY;
+        }
+        let room = self.words.len() - at - 1;
+        let size = (header >> Term::BOXED_SIZE_SHIFT).min(room);
+        let needed = match tag {
+            Term::TERM_BOXED_POSITIVE_INTEGER | Term::TERM_BOXED_NEGATIVE_INTEGER | Term::TERM_BOXED_FLOAT => 8 / WORD,
+            Term::TERM_BOXED_REF => Term::REF_WORDS,
+            Term::TERM_BOXED_FUN => 3,
+            Term::TERM_BOXED_SUB_BINARY => 3,
+            Term::TERM_BOXED_HEAP_BINARY | Term::TERM_BOXED_MAP => 1,
+            _ => 0,
+        };
+        if room < needed {
+            return false;
+        }
+        if tag == Term::TERM_BOXED_HEAP_BINARY {
+            if roles[at + 1] != Role::Free {
+                return false;
+            }
+            self.words[at + 1] = self.words[at + 1].min(size.saturating_sub(1) * WORD);
+            roles[at + 1] = Role::Size;
+        }
+        self.words[at] = (size << Term::BOXED_SIZE_SHIFT) | tag;
+        roles[at] = Role::Header;
+        true
+    }
+}
+
+/// What `FuzzHeap` has fixed a word as
+#[derive(Clone, Copy, PartialEq)]
+enum Role {
+    Free,
+    Header,
+    Size,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic bytes standing in for a fuzzer corpus
+    fn blobs() -> impl Iterator<Item = Vec<u8>> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        (0..2000).map(move |i| {
+            (0..(i % 97) * 8 + i % 5)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn test_entry_points_survive_noise() {
+        for blob in blobs() {
+            term_to_value(&blob);
+            etf_decode(&blob);
+            tagged_from_map(&blob);
+        }
+    }
+
+    #[test]
+    fn test_fuzz_heap_holds_real_terms() {
+        let table = MockAtomTable::new();
+        let point = TermValue::tuple(alloc::vec![TermValue::int(3), TermValue::Float(1.5)]);
+        // A boxed pointer to word 1, then the tuple, then the float it points to
+        let mut data = Vec::new();
+        for word in [0x2usize, (2 << 6), 3 << 4 | 0xF, 0x2 | (2 << 2), (1 << 6) | 0x18, 1.5f64.to_bits() as usize] {
+            data.extend_from_slice(&word.to_le_bytes());
+        }
+        let heap = FuzzHeap::new(&data);
+        assert_eq!(heap.root().unwrap().to_value(), Ok(point));
+
+        // A sub-binary whose offset and size overflow
+        let sub = [0x2, (3 << 6) | 0x28, 4, usize::MAX & !0x3, 0x2, (2 << 6) | 0x24, 8, 0];
+        let data: Vec<u8> = sub.iter().flat_map(|word| word.to_le_bytes()).collect();
+        assert_eq!(FuzzHeap::new(&data).root().unwrap().to_value(), Err(crate::term::NifError::InvalidTerm));
+
+        let bytes = etf::encode(&TermValue::atom("reading", &table), &table).unwrap();
+        etf_decode(&bytes);
+        assert!(FuzzHeap::new(&[]).root().is_none());
+    }
+}
@@ -0,0 +1,336 @@
+//! Random well-formed terms for property tests and fuzzing
+//!
+//! A `TermShape` bounds what a generated term may hold: how deep and wide
+//! it nests, which atoms it uses and which kinds of term appear. With the
+//! `proptest` feature, `term_strategy(shape)` is a proptest strategy; with
+//! `arbitrary`, `arbitrary_term(&mut u, &shape)` builds a term from fuzzer
+//! input. Generated terms never contain resources or invalid terms, map
+//! keys are distinct and floats are finite, so a term that comes back from
+//! an encoding compares equal to the one that went in.
+//!
+//! ```rust,ignore
+//! let table = MockAtomTable::new();
+//! proptest!(|(term in term_strategy(TermShape::new(&table)))| {
+//!     let bytes = etf::encode(&term, &table).unwrap();
+//!     prop_assert_eq!(etf::decode(&bytes, &table).unwrap(), term);
+//! });
+//! ```
+
+extern crate alloc;
+
+use crate::atom::{AtomIndex, AtomTableOps};
+use alloc::vec::Vec;
+#[cfg(any(test, feature = "proptest", feature = "arbitrary"))]
+use crate::term::{FunctionRef, PortId, ProcessId, RefId, Term, TermValue};
+#[cfg(any(test, feature = "proptest", feature = "arbitrary"))]
+use core::ops::RangeInclusive;
+
+/// Largest pid or port number generated; fits an immediate on 32-bit targets
+#[cfg(any(test, feature = "proptest", feature = "arbitrary"))]
+const MAX_ID: u32 = (1 << 27) - 1;
+
+/// Integers that are small on the host and fit a `SmallInt`
+#[cfg(any(test, feature = "proptest", feature = "arbitrary"))]
+fn small_ints() -> RangeInclusive<i32> {
+    Term::MIN_SMALL_INT.max(i32::MIN as i64) as i32..=Term::MAX_SMALL_INT.min(i32::MAX as i64) as i32
+}
+
+/// Bounds on the terms a generator produces
+#[derive(Debug, Clone, PartialEq)]
+pub struct TermShape {
+    /// Atoms to draw from; no atoms or funs are generated when empty
+    pub atoms: Vec<AtomIndex>,
+    /// Levels of tuples, lists and maps below the top
+    pub max_depth: u32,
+    /// Elements per tuple, list or map
+    pub max_len: usize,
+    /// Bytes per binary
+    pub max_binary: usize,
+    /// Lists, maps and binaries
+    pub collections: bool,
+    /// Pids, ports, references and funs
+    pub identifiers: bool,
+    pub floats: bool,
+}
+
+impl TermShape {
+    /// Every kind of term, with atoms drawn from `table`
+    pub fn new<T: AtomTableOps>(table: &T) -> Self {
+        Self {
+            atoms: atoms_of(table),
+            max_depth: 3,
+            max_len: 4,
+            max_binary: 32,
+            collections: true,
+            identifiers: true,
+            floats: true,
+        }
+    }
+
+    /// Limit nesting to `depth` levels below the top
+    pub fn max_depth(mut self, depth: u32) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Limit tuples, lists and maps to `len` elements
+    pub fn max_len(mut self, len: usize) -> Self {
+        self.max_len = len;
+        self
+    }
+
+    /// Draw atoms from `atoms` instead of the whole table
+    pub fn with_atoms(mut self, atoms: Vec<AtomIndex>) -> Self {
+        self.atoms = atoms;
+        self
+    }
+
+    /// Kinds allowed at `depth` levels from the bottom, leaves first
+    #[cfg(any(test, feature = "proptest", feature = "arbitrary"))]
+    fn kinds(&self, depth: u32) -> Vec<Kind> {
+        let mut kinds = alloc::vec![Kind::Int, Kind::Nil];
+        if !self.atoms.is_empty() {
+            kinds.push(Kind::Atom);
+        }
+        if self.floats {
+            kinds.push(Kind::Float);
+        }
+        if self.collections {
+            kinds.push(Kind::Binary);
+        }
+        if self.identifiers {
+            kinds.extend([Kind::Pid, Kind::Port, Kind::Reference]);
+            if !self.atoms.is_empty() {
+                kinds.push(Kind::Function);
+            }
+        }
+        if depth > 0 {
+            kinds.push(Kind::Tuple);
+            if self.collections {
+                kinds.extend([Kind::List, Kind::Map]);
+            }
+        }
+        kinds
+    }
+}
+
+/// Every atom in `table`
+pub fn atoms_of<T: AtomTableOps>(table: &T) -> Vec<AtomIndex> {
+    (0..=table.count() as u32)
+        .map(AtomIndex)
+        .filter(|&index| table.get_atom_string(index).is_ok())
+        .collect()
+}
+
+#[cfg(any(test, feature = "proptest", feature = "arbitrary"))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Int,
+    Nil,
+    Atom,
+    Float,
+    Binary,
+    Pid,
+    Port,
+    Reference,
+    Function,
+    Tuple,
+    List,
+    Map,
+}
+
+/// A map term keeping the first of any pairs with equal keys
+#[cfg(any(test, feature = "proptest", feature = "arbitrary"))]
+fn distinct_keys(pairs: Vec<(TermValue, TermValue)>) -> TermValue {
+    let mut distinct: Vec<(TermValue, TermValue)> = Vec::with_capacity(pairs.len());
+    for (key, value) in pairs {
+        if !distinct.iter().any(|(seen, _)| *seen == key) {
+            distinct.push((key, value));
+        }
+    }
+    TermValue::Map(distinct)
+}
+
+// ── proptest ───────────────────────────────────────────────────────────────
+
+#[cfg(any(test, feature = "proptest"))]
+pub use self::strategy::term_strategy;
+
+#[cfg(any(test, feature = "proptest"))]
+mod strategy {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::sample::select;
+    use proptest::strategy::{BoxedStrategy, Union};
+
+    /// Terms within `shape`, shrinking towards smaller and shallower ones
+    pub fn term_strategy(shape: TermShape) -> BoxedStrategy<TermValue> {
+        let leaves = Union::new(shape.kinds(0).into_iter().map(|kind| leaf(kind, &shape))).boxed();
+        let nested = shape.kinds(1);
+        let max_len = shape.max_len;
+        leaves
+            .prop_recursive(shape.max_depth, 64, max_len.max(1) as u32, move |inner| {
+                let collections = nested.iter().filter(|kind| matches!(kind, Kind::Tuple | Kind::List | Kind::Map));
+                Union::new(collections.map(|kind| match kind {
+                    Kind::List => vec(inner.clone(), 0..=max_len).prop_map(TermValue::list).boxed(),
+                    Kind::Map => vec((inner.clone(), inner.clone()), 0..=max_len).prop_map(distinct_keys).boxed(),
+                    _ => vec(inner.clone(), 0..=max_len).prop_map(TermValue::Tuple).boxed(),
+                }))
+            })
+            .boxed()
+    }
+
+    fn leaf(kind: Kind, shape: &TermShape) -> BoxedStrategy<TermValue> {
+        let atoms = shape.atoms.clone();
+        match kind {
+            Kind::Nil => Just(TermValue::Nil).boxed(),
+            Kind::Atom => select(atoms).prop_map(TermValue::Atom).boxed(),
+            Kind::Float => any::<f64>()
+                .prop_filter("finite", |value| value.is_finite())
+                .prop_map(TermValue::Float)
+                .boxed(),
+            Kind::Binary => vec(any::<u8>(), 0..=shape.max_binary).prop_map(TermValue::Binary).boxed(),
+            Kind::Pid => (0..=MAX_ID).prop_map(|id| TermValue::Pid(ProcessId(id))).boxed(),
+            Kind::Port => (0..=MAX_ID).prop_map(|id| TermValue::Port(PortId(id))).boxed(),
+            Kind::Reference => any::<u64>().prop_map(|id| TermValue::Reference(RefId(id))).boxed(),
+            Kind::Function => (select(atoms.clone()), select(atoms), any::<u8>())
+                .prop_map(|(module, function, arity)| TermValue::Function(FunctionRef { module, function, arity }))
+                .boxed(),
+            _ => small_ints().prop_map(TermValue::SmallInt).boxed(),
+        }
+    }
+}
+
+// ── arbitrary ──────────────────────────────────────────────────────────────
+
+/// A term within `shape` built from fuzzer input
+///
+/// Running out of input yields small leaves rather than an error, so every
+/// input maps to some term.
+#[cfg(any(test, feature = "arbitrary"))]
+pub fn arbitrary_term(u: &mut arbitrary::Unstructured<'_>, shape: &TermShape) -> arbitrary::Result<TermValue> {
+    arbitrary_at(u, shape, shape.max_depth)
+}
+
+#[cfg(any(test, feature = "arbitrary"))]
+fn arbitrary_at(u: &mut arbitrary::Unstructured<'_>, shape: &TermShape, depth: u32) -> arbitrary::Result<TermValue> {
+    let kinds = shape.kinds(depth);
+    let elements = |u: &mut arbitrary::Unstructured<'_>| -> arbitrary::Result<Vec<TermValue>> {
+        let len = u.int_in_range(0..=shape.max_len)?;
+        (0..len).map(|_| arbitrary_at(u, shape, depth - 1)).collect()
+    };
+    Ok(match *u.choose(&kinds)? {
+        Kind::Int => TermValue::SmallInt(u.int_in_range(small_ints())?),
+        Kind::Nil => TermValue::Nil,
+        Kind::Atom => TermValue::Atom(*u.choose(&shape.atoms)?),
+        Kind::Float => {
+            let value: f64 = u.arbitrary()?;
+            TermValue::Float(if value.is_finite() { value } else { 0.0 })
+        }
+        Kind::Binary => {
+            let len = u.int_in_range(0..=shape.max_binary)?;
+            TermValue::Binary(u.bytes(len.min(u.len()))?.to_vec())
+        }
+        Kind::Pid => TermValue::Pid(ProcessId(u.int_in_range(0..=MAX_ID)?)),
+        Kind::Port => TermValue::Port(PortId(u.int_in_range(0..=MAX_ID)?)),
+        Kind::Reference => TermValue::Reference(RefId(u.arbitrary()?)),
+        Kind::Function => TermValue::Function(FunctionRef {
+            module: *u.choose(&shape.atoms)?,
+            function: *u.choose(&shape.atoms)?,
+            arity: u.arbitrary()?,
+        }),
+        Kind::Tuple => TermValue::Tuple(elements(u)?),
+        Kind::List => TermValue::list(elements(u)?),
+        Kind::Map => {
+            let keys = elements(u)?;
+            let values = elements(u)?;
+            distinct_keys(keys.into_iter().zip(values).collect())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::etf;
+    use crate::tagged::TaggedMap;
+    use crate::testing::mocks::{MockAtomTable, MockHeap};
+    use proptest::prelude::*;
+
+    #[derive(Debug, Clone, PartialEq, avmnif_derive::TaggedMap)]
+    struct Sample {
+        label: TermValue,
+        extra: Option<TermValue>,
+    }
+
+    fn depth(term: &TermValue) -> u32 {
+        match term {
+            TermValue::Tuple(elements) => 1 + elements.iter().map(depth).max().unwrap_or(0),
+            TermValue::List(_, _) => 1 + term.iter_list().map(depth).max().unwrap_or(0),
+            TermValue::Map(pairs) => 1 + pairs.iter().map(|(k, v)| depth(k).max(depth(v))).max().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_terms_stay_within_shape(term in term_strategy(TermShape::new(&MockAtomTable::new()).max_depth(2))) {
+            prop_assert!(depth(&term) <= 2);
+        }
+
+        #[test]
+        fn test_etf_round_trip(term in term_strategy(TermShape::new(&MockAtomTable::new()))) {
+            let table = MockAtomTable::new();
+            let bytes = etf::encode(&term, &table).unwrap();
+            prop_assert_eq!(etf::decode(&bytes, &table).unwrap(), term);
+        }
+
+        #[test]
+        fn test_tagged_map_round_trip(
+            label in term_strategy(TermShape::new(&MockAtomTable::new())),
+            extra in proptest::option::of(term_strategy(TermShape::new(&MockAtomTable::new()).max_depth(1))),
+        ) {
+            let table = MockAtomTable::new();
+            let sample = Sample { label, extra };
+            let map = sample.to_tagged_map(&table).unwrap();
+            let read = Sample::from_tagged_map(map, &table).unwrap();
+            // Lossy by design: `None` is stored as `nil`, so `Some(nil)` reads back as `None`
+            let expected = Sample {
+                extra: sample.extra.clone().filter(|value| !value.is_atom_str("nil", &table)),
+                ..sample
+            };
+            prop_assert_eq!(read, expected);
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_terms_from_any_input() {
+        let table = MockAtomTable::new();
+        let shape = TermShape::new(&table).max_depth(2);
+        let empty = arbitrary_term(&mut arbitrary::Unstructured::new(&[]), &shape);
+        assert_eq!(empty, Ok(TermValue::SmallInt(*small_ints().start())));
+
+        let input: Vec<u8> = (0..4096u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+        let mut u = arbitrary::Unstructured::new(&input);
+        while !u.is_empty() {
+            let term = arbitrary_term(&mut u, &shape).unwrap();
+            assert!(depth(&term) <= 2);
+            let bytes = etf::encode(&term, &table).unwrap();
+            assert_eq!(etf::decode(&bytes, &table).unwrap(), term);
+        }
+    }
+
+    #[test]
+    fn test_heap_round_trip() {
+        // Atom 3 encodes to the same word as nil, so the heap cannot tell them apart
+        let table = MockAtomTable::new();
+        let atoms = atoms_of(&table).into_iter().filter(|&index| index != AtomIndex(3)).collect();
+        let shape = TermShape::new(&table).with_atoms(atoms);
+        proptest!(|(term in term_strategy(shape))| {
+            let mut heap = MockHeap::new(Term::heap_words(&term) + 1);
+            let encoded = heap.encode(term.clone()).unwrap();
+            prop_assert_eq!(encoded.to_value().unwrap(), term);
+        });
+    }
+}
@@ -0,0 +1,134 @@
+//! Golden ETF fixtures written by real Erlang
+//!
+//! A fixture is the output of `term_to_binary/1` saved to a file, checked
+//! against the term it holds, written in Erlang syntax. `check_golden`
+//! decodes the bytes, compares the result with the expected term and
+//! encodes it again, requiring the same bytes back, so any drift from
+//! what OTP writes shows up as a failing test. Terms OTP writes in a form
+//! avmnif-rs does not produce (strings as `STRING_EXT`, Latin-1 atoms)
+//! are checked with `check_golden_decode`, which skips the re-encoding.
+//!
+//! Fixtures are made in an Erlang shell:
+//!
+//! ```erlang
+//! file:write_file("ok_tuple.etf", term_to_binary({ok, 42})).
+//! ```
+//!
+//! The fixtures under `testing/golden/` hold the bytes OTP 26 writes, with
+//! atoms as `SMALL_ATOM_UTF8_EXT`, the default since that release.
+//!
+//! ```rust,ignore
+//! assert_golden(include_bytes!("golden/ok_tuple.etf"), "{ok, 42}", &table);
+//! ```
+
+extern crate alloc;
+
+use crate::atom::AtomTableOps;
+use crate::etf;
+use crate::parse::parse_term;
+use crate::term::TermValue;
+use alloc::format;
+use alloc::string::String;
+
+/// Check that `bytes` decode to `expected` and encode back to `bytes`
+pub fn check_golden<T: AtomTableOps>(bytes: &[u8], expected: &str, table: &T) -> Result<(), String> {
+    let decoded = check_golden_decode_value(bytes, expected, table)?;
+    let encoded = etf::encode(&decoded, table).map_err(|e| format!("re-encoding failed: {}", e))?;
+    if encoded == bytes {
+        return Ok(());
+    }
+    let offset = encoded.iter().zip(bytes).position(|(a, b)| a != b).unwrap_or(encoded.len().min(bytes.len()));
+    Err(format!(
+        "re-encoding differs at byte {} of {}:\nFixture: {:?}\nEncoded: {:?}",
+        offset,
+        bytes.len(),
+        window(bytes, offset),
+        window(&encoded, offset)
+    ))
+}
+
+/// Check that `bytes` decode to `expected`, without re-encoding
+pub fn check_golden_decode<T: AtomTableOps>(bytes: &[u8], expected: &str, table: &T) -> Result<(), String> {
+    check_golden_decode_value(bytes, expected, table).map(|_| ())
+}
+
+/// Panic unless `bytes` decode to `expected` and encode back to `bytes`
+pub fn assert_golden<T: AtomTableOps>(bytes: &[u8], expected: &str, table: &T) {
+    if let Err(message) = check_golden(bytes, expected, table) {
+        panic!("Golden fixture {}: {}", expected, message);
+    }
+}
+
+/// Panic unless `bytes` decode to `expected`
+pub fn assert_golden_decode<T: AtomTableOps>(bytes: &[u8], expected: &str, table: &T) {
+    if let Err(message) = check_golden_decode(bytes, expected, table) {
+        panic!("Golden fixture {}: {}", expected, message);
+    }
+}
+
+fn check_golden_decode_value<T: AtomTableOps>(bytes: &[u8], expected: &str, table: &T) -> Result<TermValue, String> {
+    let expected = parse_term(expected, table).map_err(|e| format!("expected term does not parse: {}", e))?;
+    let decoded = etf::decode(bytes, table).map_err(|e| format!("decoding failed: {}", e))?;
+    if decoded != expected {
+        return Err(format!(
+            "decoded term differs:\nExpected: {}\nDecoded:  {}",
+            expected.display(table),
+            decoded.display(table)
+        ));
+    }
+    Ok(decoded)
+}
+
+/// Up to eight bytes either side of `offset`
+fn window(bytes: &[u8], offset: usize) -> &[u8] {
+    let start = offset.saturating_sub(8).min(bytes.len());
+    let end = (offset + 8).min(bytes.len());
+    &bytes[start..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mocks::MockAtomTable;
+
+    #[test]
+    fn test_otp_fixtures_round_trip() {
+        let table = MockAtomTable::new();
+        for (bytes, term) in [
+            (&include_bytes!("golden/atom.etf")[..], "ok"),
+            (include_bytes!("golden/ok_tuple.etf"), "{ok, 42}"),
+            (include_bytes!("golden/negative.etf"), "-1"),
+            (include_bytes!("golden/integer.etf"), "1000"),
+            (include_bytes!("golden/binary.etf"), "<<\"abc\">>"),
+            (include_bytes!("golden/nil.etf"), "[]"),
+            (include_bytes!("golden/list.etf"), "[1, a]"),
+            (include_bytes!("golden/improper_list.etf"), "[a | b]"),
+            (include_bytes!("golden/map.etf"), "{ok, #{id => 7, name => <<\"probe\">>}}"),
+            (include_bytes!("golden/float.etf"), "1.5"),
+            (include_bytes!("golden/export_fun.etf"), "fun lists:map/2"),
+        ] {
+            assert_golden(bytes, term, &table);
+        }
+    }
+
+    #[test]
+    fn test_otp_fixtures_in_other_encodings() {
+        let table = MockAtomTable::new();
+        assert_golden_decode(include_bytes!("golden/string.etf"), "\"abc\"", &table);
+        assert_golden_decode(include_bytes!("golden/latin1_atom.etf"), "ok", &table);
+
+        // Re-encoding writes a list and a UTF-8 atom instead
+        let error = check_golden(include_bytes!("golden/string.etf"), "\"abc\"", &table).unwrap_err();
+        assert!(error.starts_with("re-encoding differs at byte 1"), "{}", error);
+        assert!(check_golden(include_bytes!("golden/latin1_atom.etf"), "ok", &table).is_err());
+    }
+
+    #[test]
+    fn test_mismatches_are_reported() {
+        let table = MockAtomTable::new();
+        let error = check_golden(include_bytes!("golden/ok_tuple.etf"), "{ok, 43}", &table).unwrap_err();
+        assert!(error.contains("Expected: {ok,43}") && error.contains("Decoded:  {ok,42}"), "{}", error);
+        assert!(check_golden(&[131, 104], "{}", &table).unwrap_err().starts_with("decoding failed"));
+        assert!(check_golden_decode(include_bytes!("golden/nil.etf"), "[", &table).is_err());
+    }
+}
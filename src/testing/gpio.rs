@@ -0,0 +1,198 @@
+//! GPIO port testing suite
+
+use crate::port::behavior::{CallFrom, Incoming};
+use crate::port::gpio::{level_from_term, Edge, EdgeFn, GpioHal, GpioPort};
+use crate::port::irq::IrqEvent;
+use crate::port::{PortError, PortResult};
+use crate::term::TermValue;
+use crate::testing::mocks::{MockAtomTable, MockProcessMonitor};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Pins 0..8; interrupts are recorded instead of installed
+#[derive(Default)]
+struct MockGpio {
+    levels: [bool; 8],
+    interrupts: Vec<(u32, Option<Edge>)>,
+}
+
+impl MockGpio {
+    fn check(pin: u32) -> Result<usize, PortError> {
+        if pin < 8 {
+            Ok(pin as usize)
+        } else {
+            Err(PortError::InvalidMessage)
+        }
+    }
+
+    fn interrupt(&self, pin: u32) -> Option<Edge> {
+        self.interrupts.iter().rev().find(|(p, _)| *p == pin).and_then(|(_, edge)| *edge)
+    }
+}
+
+impl GpioHal for MockGpio {
+    fn init(_opts: &TermValue) -> Result<Self, PortError> {
+        Ok(Self::default())
+    }
+
+    fn set(&mut self, pin: u32, high: bool) -> Result<(), PortError> {
+        self.levels[Self::check(pin)?] = high;
+        Ok(())
+    }
+
+    fn read(&mut self, pin: u32) -> Result<bool, PortError> {
+        Ok(self.levels[Self::check(pin)?])
+    }
+
+    fn enable_interrupt(&mut self, pin: u32, edge: Edge, _notify: EdgeFn) -> Result<(), PortError> {
+        Self::check(pin)?;
+        self.interrupts.push((pin, Some(edge)));
+        Ok(())
+    }
+
+    fn disable_interrupt(&mut self, pin: u32) {
+        self.interrupts.push((pin, None));
+    }
+}
+
+extern "C" fn ignore_edge(_pin: u32, _level: u32) {}
+
+fn gpio_port() -> GpioPort<MockGpio, usize> {
+    GpioPort::new(MockGpio::default(), ignore_edge)
+}
+
+fn call(request: TermValue, pid: u32) -> Incoming {
+    Incoming::Call {
+        request,
+        from: CallFrom {
+            pid,
+            reference: TermValue::reference(1),
+        },
+    }
+}
+
+fn command(name: &str, pin: i32, extra: &[&str], table: &MockAtomTable) -> TermValue {
+    let mut elements = vec![TermValue::atom(name, table), TermValue::int(pin)];
+    elements.extend(extra.iter().map(|atom| TermValue::atom(atom, table)));
+    TermValue::tuple(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_and_read() {
+        let table = MockAtomTable::new();
+        let mut monitor = MockProcessMonitor::default();
+        let mut port = gpio_port();
+
+        let ok = PortResult::Reply(TermValue::atom("ok", &table));
+        assert_eq!(port.dispatch(call(command("set", 3, &["high"], &table), 1), &mut monitor, &table), ok);
+        assert!(port.hal().levels[3]);
+        let set_zero = TermValue::tuple(vec![TermValue::atom("set", &table), TermValue::int(3), TermValue::int(0)]);
+        assert_eq!(port.dispatch(call(set_zero, 1), &mut monitor, &table), ok);
+
+        assert_eq!(
+            port.dispatch(call(command("read", 3, &[], &table), 1), &mut monitor, &table),
+            PortResult::Reply(TermValue::tuple(vec![TermValue::atom("ok", &table), TermValue::atom("low", &table)]))
+        );
+        assert_eq!(
+            port.dispatch(call(command("read", 9, &[], &table), 1), &mut monitor, &table),
+            PortResult::ReplyError(TermValue::atom("badarg", &table))
+        );
+        assert_eq!(
+            port.dispatch(call(command("set", 3, &["up"], &table), 1), &mut monitor, &table),
+            PortResult::ReplyError(TermValue::atom("badarg", &table))
+        );
+    }
+
+    #[test]
+    fn test_interrupt_edge_covers_every_subscriber() {
+        let table = MockAtomTable::new();
+        let mut monitor = MockProcessMonitor::with_alive(&[1, 2]);
+        let mut port = gpio_port();
+
+        port.dispatch(call(command("subscribe", 4, &["rising"], &table), 1), &mut monitor, &table);
+        assert_eq!(port.hal().interrupt(4), Some(Edge::Rising));
+        port.dispatch(call(command("subscribe", 4, &["falling"], &table), 2), &mut monitor, &table);
+        assert_eq!(port.hal().interrupt(4), Some(Edge::Both));
+
+        port.dispatch(call(command("unsubscribe", 4, &[], &table), 2), &mut monitor, &table);
+        assert_eq!(port.hal().interrupt(4), Some(Edge::Rising));
+        assert_eq!(monitor.demonitored.len(), 1);
+        port.dispatch(call(command("unsubscribe", 4, &[], &table), 1), &mut monitor, &table);
+        assert_eq!(port.hal().interrupt(4), None);
+        assert_eq!(port.subscribed_edge(4), None);
+    }
+
+    #[test]
+    fn test_failed_subscribe_leaves_nothing_behind() {
+        let table = MockAtomTable::new();
+        let mut monitor = MockProcessMonitor::with_alive(&[1]);
+        let mut port = gpio_port();
+
+        assert_eq!(
+            port.dispatch(call(command("subscribe", 12, &["both"], &table), 1), &mut monitor, &table),
+            PortResult::ReplyError(TermValue::atom("badarg", &table))
+        );
+        assert_eq!(port.subscribed_edge(12), None);
+        assert_eq!(monitor.demonitored.len(), 1);
+
+        assert_eq!(
+            port.dispatch(call(command("subscribe", 2, &["both"], &table), 5), &mut monitor, &table),
+            PortResult::ReplyError(TermValue::atom("noproc", &table))
+        );
+    }
+
+    #[test]
+    fn test_edges_go_to_matching_subscribers() {
+        let table = MockAtomTable::new();
+        let mut monitor = MockProcessMonitor::with_alive(&[1, 2]);
+        let mut port = gpio_port();
+        port.subscribe(&mut monitor, 1, 5, Edge::Rising).unwrap();
+        port.subscribe(&mut monitor, 2, 5, Edge::Both).unwrap();
+
+        let rising = port.edge_messages(IrqEvent { id: 5, payload: 1 }, &table);
+        let message = TermValue::tuple(vec![
+            TermValue::atom("gpio_interrupt", &table),
+            TermValue::int(5),
+            TermValue::atom("rising", &table),
+        ]);
+        assert_eq!(rising, vec![(1, message.clone()), (2, message)]);
+
+        let falling = port.edge_messages(IrqEvent { id: 5, payload: 0 }, &table);
+        assert_eq!(falling.iter().map(|(pid, _)| *pid).collect::<Vec<_>>(), vec![2]);
+        assert!(port.edge_messages(IrqEvent { id: 6, payload: 1 }, &table).is_empty());
+    }
+
+    #[test]
+    fn test_down_subscriber_leaves_every_pin() {
+        let table = MockAtomTable::new();
+        let mut monitor = MockProcessMonitor::with_alive(&[1]);
+        let mut port = gpio_port();
+        port.subscribe(&mut monitor, 1, 2, Edge::Rising).unwrap();
+        port.subscribe(&mut monitor, 1, 3, Edge::Falling).unwrap();
+        assert_eq!(monitor.monitored.len(), 1);
+
+        let down = TermValue::tuple(vec![
+            TermValue::atom("DOWN", &table),
+            TermValue::reference(1),
+            TermValue::atom("process", &table),
+            TermValue::pid(1),
+            TermValue::atom("normal", &table),
+        ]);
+        assert_eq!(port.dispatch(Incoming::Info(down), &mut monitor, &table), PortResult::Continue);
+        assert_eq!(port.hal().interrupt(2), None);
+        assert_eq!(port.hal().interrupt(3), None);
+    }
+
+    #[test]
+    fn test_level_terms() {
+        let table = MockAtomTable::new();
+        assert_eq!(level_from_term(&TermValue::atom("high", &table), &table), Some(true));
+        assert_eq!(level_from_term(&TermValue::int(0), &table), Some(false));
+        assert_eq!(level_from_term(&TermValue::int(2), &table), None);
+        assert_eq!(Edge::from_term(&TermValue::atom("both", &table), &table), Some(Edge::Both));
+    }
+}
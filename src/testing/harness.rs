@@ -0,0 +1,224 @@
+//! A port and its handler, driven by messages in a test
+//!
+//! `PortHarness` builds a port on its own `MockGlobalContext` with the
+//! given platform data and passes each message to the handler the way the
+//! `port_collection!` wrapper does: the handler runs, then the reply its
+//! `PortResult` carries is sent to the caller. Calls are `gen_server:call`
+//! messages from a pid with a fresh reference; what the port sent back is
+//! collected on its `MockContext`.
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use avmnif_rs::context::Context;
+//! use avmnif_rs::port::{Message, NativePortResult, PortResult};
+//! use avmnif_rs::term::{ProcessId, TermValue};
+//! use avmnif_rs::testing::harness::PortHarness;
+//!
+//! fn handle(_ctx: &mut Context, _message: &Message) -> PortResult {
+//!     PortResult::Reply(TermValue::int(42))
+//! }
+//!
+//! let mut harness = PortHarness::new(0u32, handle);
+//! let read = TermValue::atom("read", harness.atoms());
+//! let outcome = harness.call(ProcessId(5), read).unwrap();
+//! assert_eq!(outcome.reply, Some(TermValue::int(42)));
+//! assert_eq!(outcome.result, NativePortResult::Continue);
+//! # }
+//! ```
+
+extern crate alloc;
+
+use crate::context::{destroy_port_context_safe, Context, ContextExt, PortBuilder};
+use crate::port::{complete_port_result, NativePortResult, PortHandlerFn};
+use crate::term::{NifResult, ProcessId, RefId, TermValue};
+use crate::testing::mocks::{MockAtomTable, MockContext, MockGlobalContext, MockMessage, MockReply};
+use alloc::boxed::Box;
+use core::marker::PhantomData;
+
+/// What a call to the port came back with
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallOutcome {
+    /// The reference the call was made with
+    pub reference: RefId,
+    /// The reply sent to the caller for this reference, if any
+    pub reply: Option<TermValue>,
+    /// Whether the port continues or terminates
+    pub result: NativePortResult,
+}
+
+/// A port holding `P` as platform data, with the handler under test
+///
+/// The harness owns the VM and the port; dropping it drops the platform
+/// data and destroys the port. Only one harness (or `MockGlobalContext`)
+/// should be alive per test thread, as the last one made is the current VM.
+pub struct PortHarness<P: 'static> {
+    ctx: *mut Context,
+    handler: PortHandlerFn,
+    next_reference: u64,
+    terminated: bool,
+    data: PhantomData<P>,
+    // Declared last: the port is destroyed before its VM
+    global: Box<MockGlobalContext>,
+}
+
+impl<P: 'static> PortHarness<P> {
+    /// A port on a new VM holding `data`, with messages going to `handler`
+    pub fn new(data: P, handler: PortHandlerFn) -> Self {
+        let global = MockGlobalContext::new();
+        let ctx = PortBuilder::new(data).build(global.as_global());
+        Self { ctx, handler, next_reference: 1, terminated: false, data: PhantomData, global }
+    }
+
+    /// The VM the port runs on
+    pub fn global(&self) -> &MockGlobalContext {
+        &self.global
+    }
+
+    /// The VM's atom table, for building commands and expected replies
+    pub fn atoms(&self) -> &MockAtomTable {
+        self.global.atoms()
+    }
+
+    /// The port's context, with the replies it sent and what it monitors
+    pub fn port(&mut self) -> &mut MockContext {
+        unsafe { MockContext::from_raw(self.ctx) }
+    }
+
+    /// The port's platform data
+    pub fn platform_data(&mut self) -> &mut P {
+        unsafe { &mut *(*self.ctx).get_platform_data_as::<P>() }
+    }
+
+    /// Replies the port sent so far, oldest first
+    pub fn replies(&mut self) -> &[MockReply] {
+        self.port().replies()
+    }
+
+    /// Whether the handler has asked for the port to terminate
+    pub fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+
+    /// Call the port from `pid` with `command` and the next reference
+    pub fn call(&mut self, pid: ProcessId, command: TermValue) -> NifResult<CallOutcome> {
+        let reference = RefId(self.next_reference);
+        self.next_reference += 1;
+        self.call_with(pid, reference, command)
+    }
+
+    /// Call the port with the given pid, reference and command
+    pub fn call_with(&mut self, pid: ProcessId, reference: RefId, command: TermValue) -> NifResult<CallOutcome> {
+        let message = MockMessage::call(&self.global, pid, reference, command)?;
+        let sent = self.replies().len();
+        let result = self.deliver(&message);
+        let wanted = TermValue::Reference(reference);
+        let reply = self.replies()[sent..].iter().find(|sent| sent.reference == wanted).map(|sent| sent.reply.clone());
+        Ok(CallOutcome { reference, reply, result })
+    }
+
+    /// Send the port a message other than a call, such as a monitor's `DOWN`
+    pub fn info(&mut self, message: TermValue) -> NifResult<NativePortResult> {
+        let message = MockMessage::info(message)?;
+        Ok(self.deliver(&message))
+    }
+
+    /// Run the handler on `message` and send the reply it returns
+    ///
+    /// Panics if the port has already terminated, as AtomVM would have
+    /// destroyed it.
+    fn deliver(&mut self, message: &MockMessage) -> NativePortResult {
+        assert!(!self.terminated, "message sent to a port that has terminated");
+        let _scope = crate::panic::enter_port(self.ctx);
+        let ctx = unsafe { &mut *self.ctx };
+        let result = (self.handler)(ctx, message.as_message());
+        let result = complete_port_result(ctx, message.as_message(), result);
+        self.terminated = result == NativePortResult::Terminate;
+        result
+    }
+}
+
+impl<P: 'static> Drop for PortHarness<P> {
+    fn drop(&mut self) {
+        unsafe {
+            drop((*self.ctx).take_platform_data_box::<P>());
+        }
+        destroy_port_context_safe(self.ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atom::AtomTableOps;
+    use crate::context::PlatformData;
+    use crate::port::{handle_standard_message, parse_gen_message, GenericPortData, Message, PortData, PortResult};
+    use alloc::vec;
+
+    #[derive(Default)]
+    struct Sensor;
+    impl PlatformData for Sensor {}
+    impl PortData for Sensor {}
+
+    /// Counts reads, stops on anything but `read`
+    fn counter(ctx: &mut Context, message: &Message) -> PortResult {
+        let reads = unsafe { &mut *ctx.get_platform_data_as::<u32>() };
+        let Ok((_, _, command)) = parse_gen_message(message) else {
+            return PortResult::Terminate;
+        };
+        let table = crate::atom::AtomTable::from_global();
+        match table.ensure_atom_str("read") {
+            Ok(read) if command.to_value() == Ok(TermValue::Atom(read)) => {
+                *reads += 1;
+                PortResult::Reply(TermValue::int(*reads as i32))
+            }
+            _ => PortResult::Terminate,
+        }
+    }
+
+    #[test]
+    fn test_calls_collect_replies() {
+        let mut harness = PortHarness::new(0u32, counter);
+        let read = TermValue::atom("read", harness.atoms());
+
+        let first = harness.call(ProcessId(5), read.clone()).unwrap();
+        let second = harness.call(ProcessId(6), read).unwrap();
+        assert_eq!((first.reference, first.reply, first.result), (RefId(1), Some(TermValue::int(1)), NativePortResult::Continue));
+        assert_eq!(second.reply, Some(TermValue::int(2)));
+        assert_eq!(*harness.platform_data(), 2);
+        assert_eq!(harness.replies()[1].pid, TermValue::Pid(ProcessId(6)));
+        assert_eq!(harness.replies()[1].reference, TermValue::Reference(RefId(2)));
+
+        let stop = TermValue::atom("stop", harness.atoms());
+        let last = harness.call_with(ProcessId(5), RefId(90), stop).unwrap();
+        assert_eq!((last.reply, last.result), (None, NativePortResult::Terminate));
+        assert!(harness.is_terminated());
+        assert_eq!(harness.replies().len(), 2);
+    }
+
+    #[test]
+    fn test_standard_handler() {
+        fn handle(ctx: &mut Context, message: &Message) -> PortResult {
+            handle_standard_message::<Sensor>(ctx, message)
+        }
+
+        let mut harness = PortHarness::new(GenericPortData::new(Sensor), handle);
+        let table = harness.atoms();
+        let (start, ok) = (TermValue::atom("start", table), TermValue::atom("ok", table));
+        let active = TermValue::tuple(vec![ok, TermValue::atom("active", table)]);
+
+        assert_eq!(harness.call(ProcessId(5), start).unwrap().reply, Some(active));
+        assert_eq!(harness.port().monitors(), &[5]);
+        assert!(harness.platform_data().is_active());
+
+        let timeout = TermValue::atom("timeout", harness.atoms());
+        assert_eq!(harness.info(timeout), Ok(NativePortResult::Terminate));
+    }
+
+    #[test]
+    #[should_panic(expected = "port that has terminated")]
+    fn test_terminated_port_takes_no_messages() {
+        let mut harness = PortHarness::new(0u32, counter);
+        let _ = harness.info(TermValue::int(1));
+        let _ = harness.info(TermValue::int(2));
+    }
+}
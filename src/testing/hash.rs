@@ -0,0 +1,108 @@
+//! Term hashing testing suite
+
+use crate::atom::AtomIndex;
+use crate::hash::{atom_hash, hash2, phash2, phash2_range, PHASH2_RANGE};
+use crate::term::{NifError, TermValue};
+use crate::testing::helpers::*;
+use crate::testing::mocks::MockAtomTable;
+use alloc::vec;
+
+/// `#{name => <<"probe">>, id => 7}`, pairs in the given order
+fn record(table: &MockAtomTable, reversed: bool) -> TermValue {
+    let mut pairs = vec![
+        (atom("name", table), TermValue::binary(b"probe".to_vec())),
+        (atom("id", table), TermValue::int(7)),
+    ];
+    if reversed {
+        pairs.reverse();
+    }
+    TermValue::map(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_atoms_hash_by_name() {
+        assert_eq!(atom_hash(b"a"), 97);
+        assert_eq!(atom_hash(b"ab"), 97 * 16 + 98);
+        // UTF-8 Latin-1 characters hash as their Latin-1 byte
+        assert_eq!(atom_hash("caf\u{e9}".as_bytes()), atom_hash(b"caf\xe9"));
+
+        let first = MockAtomTable::new();
+        let second = MockAtomTable::new();
+        atom("padding", &second);
+        assert_eq!(phash2(&atom("a", &first), &first), Ok(97));
+        assert_eq!(
+            phash2(&atom("sensor", &first), &first),
+            phash2(&atom("sensor", &second), &second)
+        );
+        assert_eq!(phash2(&TermValue::Atom(AtomIndex(9999)), &first), Err(NifError::BadArg));
+    }
+
+    #[test]
+    fn test_nil_and_empty_containers() {
+        let table = MockAtomTable::new();
+        assert_eq!(hash2(&TermValue::Nil, &table), Ok(3_468_870_702));
+        assert_eq!(phash2(&TermValue::Nil, &table), Ok(3_468_870_702 & (PHASH2_RANGE - 1)));
+        assert_ne!(hash2(&TermValue::tuple(vec![]), &table), hash2(&TermValue::map(vec![]), &table));
+        assert_ne!(
+            hash2(&TermValue::binary(vec![]), &table),
+            hash2(&TermValue::tuple(vec![TermValue::Nil]), &table)
+        );
+    }
+
+    #[test]
+    fn test_equal_terms_hash_equal() {
+        let table = MockAtomTable::new();
+        assert_eq!(hash2(&record(&table, false), &table), hash2(&record(&table, true), &table));
+        assert_eq!(hash2(&TermValue::Float(-0.0), &table), hash2(&TermValue::Float(0.0), &table));
+
+        // Exact equality: 1 and 1.0 are different terms
+        assert_ne!(hash2(&TermValue::int(1), &table), hash2(&TermValue::Float(1.0), &table));
+        assert_ne!(hash2(&TermValue::int(-5), &table), hash2(&TermValue::int(5), &table));
+        // Either side of the 28-bit small boundary
+        assert_ne!(hash2(&TermValue::int(1 << 27), &table), hash2(&TermValue::int((1 << 27) - 1), &table));
+    }
+
+    #[test]
+    fn test_strings_and_lists() {
+        let table = MockAtomTable::new();
+        let text = |s: &str| TermValue::list(s.bytes().map(|b| TermValue::int(b as i32)).collect());
+        assert_ne!(hash2(&text("abcd"), &table), hash2(&text("abdc"), &table));
+        assert_ne!(hash2(&text("abcde"), &table), hash2(&text("abcd"), &table));
+        assert_ne!(hash2(&text("abc"), &table), hash2(&TermValue::binary(b"abc".to_vec()), &table));
+
+        // Non-byte elements break the packed runs
+        let mixed = int_list(&[1, 300, 2]);
+        assert_ne!(hash2(&mixed, &table), hash2(&int_list(&[1, 2, 300]), &table));
+
+        // Long lists are walked without recursing per cell
+        let long = int_list(&(0..10_000).collect::<alloc::vec::Vec<_>>());
+        assert!(hash2(&long, &table).is_ok());
+    }
+
+    #[test]
+    fn test_binaries_cover_every_tail_length() {
+        let table = MockAtomTable::new();
+        let bytes: alloc::vec::Vec<u8> = (0..40).collect();
+        let hashes: alloc::vec::Vec<_> = (0..bytes.len())
+            .map(|len| hash2(&TermValue::binary(bytes[..len].to_vec()), &table).unwrap())
+            .collect();
+        for (i, a) in hashes.iter().enumerate() {
+            assert!(hashes[i + 1..].iter().all(|b| b != a), "length {} collides", i);
+        }
+    }
+
+    #[test]
+    fn test_ranges() {
+        let table = MockAtomTable::new();
+        let key = record(&table, false);
+        let full = hash2(&key, &table).unwrap();
+        assert_eq!(phash2(&key, &table), Ok(full & (PHASH2_RANGE - 1)));
+        assert_eq!(phash2_range(&key, 16, &table), Ok(full % 16));
+        assert_eq!(phash2_range(&key, 1, &table), Ok(0));
+        assert_eq!(phash2_range(&key, 0, &table), Err(NifError::BadArg));
+    }
+}
@@ -0,0 +1,124 @@
+//! iolist testing suite
+
+use crate::iolist::{flatten, IoList};
+use alloc::borrow::Cow;
+use crate::term::{NifError, TermValue};
+use alloc::{vec, vec::Vec};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bin(bytes: &[u8]) -> TermValue {
+        TermValue::binary(bytes.to_vec())
+    }
+
+    #[test]
+    fn test_nested_iolist_flattens() {
+        // [<<"ab">>, [$c, [<<"de">>]], $f | <<"gh">>]
+        let term = TermValue::improper_list(
+            vec![
+                bin(b"ab"),
+                TermValue::list(vec![TermValue::int(b'c' as i32), TermValue::list(vec![bin(b"de")])]),
+                TermValue::int(b'f' as i32),
+            ],
+            bin(b"gh"),
+        );
+
+        assert_eq!(term.iolist_to_binary().unwrap(), b"abcdefgh".to_vec());
+        assert_eq!(term.iolist_size().unwrap(), 8);
+
+        let iolist = IoList::from_term(&term).unwrap();
+        let chunks: Vec<&[u8]> = iolist.chunks().collect();
+        assert_eq!(chunks, vec![&b"ab"[..], b"c", b"de", b"f", b"gh"]);
+    }
+
+    #[test]
+    fn test_binary_and_empty_are_iodata() {
+        assert_eq!(bin(b"xyz").iolist_to_binary().unwrap(), b"xyz".to_vec());
+        assert_eq!(TermValue::Nil.iolist_size().unwrap(), 0);
+        let empty_nested = TermValue::list(vec![TermValue::Nil, TermValue::list(vec![TermValue::Nil])]);
+        assert!(IoList::from_term(&empty_nested).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_iolists_rejected() {
+        let table = crate::testing::mocks::MockAtomTable::new();
+        let cases = vec![
+            TermValue::int(1),
+            TermValue::list(vec![TermValue::int(256)]),
+            TermValue::list(vec![TermValue::int(-1)]),
+            TermValue::list(vec![TermValue::atom("a", &table)]),
+            TermValue::improper_list(vec![bin(b"a")], TermValue::int(1)),
+        ];
+        for case in cases {
+            assert_eq!(case.iolist_to_binary(), Err(NifError::BadArg), "{:?}", case);
+        }
+    }
+
+    #[test]
+    fn test_deep_nesting_does_not_recurse() {
+        let mut term = bin(b"x");
+        for _ in 0..100_000 {
+            term = TermValue::list(vec![term]);
+        }
+        assert_eq!(term.iolist_to_binary().unwrap(), b"x".to_vec());
+
+        // Drop iteratively-built nesting without blowing the stack
+        let mut current = term;
+        while let TermValue::List(head, _) = current {
+            current = *head;
+        }
+    }
+
+    #[test]
+    fn test_builder_and_output() {
+        let payload = [1u8, 2, 3];
+        let mut iolist = IoList::new();
+        iolist.push_slice(b"HD");
+        iolist.push_byte(3);
+        iolist.push_byte(0);
+        iolist.push_slice(&payload);
+        iolist.push_slice(&[]);
+
+        let mut tail = IoList::new();
+        tail.push_vec(vec![9, 9]);
+        iolist.append(tail);
+
+        assert_eq!(iolist.len(), 9);
+        assert_eq!(iolist.chunks().count(), 4);
+        assert_eq!(iolist.to_vec(), vec![b'H', b'D', 3, 0, 1, 2, 3, 9, 9]);
+
+        let mut written = Vec::new();
+        iolist.write_to(|chunk| written.push(chunk.len()));
+        assert_eq!(written, vec![2, 2, 3, 2]);
+
+        let mut short = [0u8; 5];
+        assert_eq!(iolist.copy_to_slice(&mut short), 5);
+        assert_eq!(short, [b'H', b'D', 3, 0, 1]);
+
+        let term = iolist.to_term();
+        assert_eq!(term.list_length(), 4);
+        assert_eq!(term.iolist_to_binary().unwrap(), iolist.to_vec());
+    }
+
+    #[test]
+    fn test_flatten_borrows_single_chunk() {
+        let binary = bin(b"frame");
+        assert!(matches!(flatten(&binary).unwrap(), Cow::Borrowed(b"frame")));
+
+        let wrapped = TermValue::list(vec![TermValue::Nil, TermValue::list(vec![binary.clone()])]);
+        assert!(matches!(flatten(&wrapped).unwrap(), Cow::Borrowed(b"frame")));
+
+        let split = TermValue::list(vec![binary, TermValue::int(b'!' as i32)]);
+        assert!(matches!(flatten(&split).unwrap(), Cow::Owned(ref v) if v == b"frame!"));
+
+        assert!(flatten(&TermValue::Nil).unwrap().is_empty());
+        assert_eq!(flatten(&TermValue::int(1)).unwrap_err(), NifError::BadArg);
+
+        // Strings passed as iodata rather than a charlist
+        let text = TermValue::list(vec![bin(b"temp="), TermValue::list(vec![TermValue::int(b'4' as i32)])]);
+        assert_eq!(text.as_string().as_deref(), Some("temp=4"));
+        assert_eq!(TermValue::charlist("\u{e9}t\u{e9}").as_string().as_deref(), Some("\u{e9}t\u{e9}"));
+    }
+}
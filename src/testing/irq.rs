@@ -0,0 +1,78 @@
+//! Interrupt forwarding testing suite
+
+use crate::port::irq::{IrqEvent, IrqQueue};
+use crate::term::TermValue;
+use crate::testing::mocks::MockAtomTable;
+use alloc::vec;
+use alloc::vec::Vec;
+
+fn drained<const N: usize, const IDS: usize>(queue: &IrqQueue<N, IDS>) -> Vec<(u32, u32)> {
+    let mut events = Vec::new();
+    queue.drain(|event| events.push((event.id, event.payload)));
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_drain_in_order() {
+        let queue: IrqQueue<4, 8> = IrqQueue::new();
+        queue.raise(2, 10);
+        queue.raise(3, 11);
+        assert!(!queue.is_empty());
+        assert_eq!(drained(&queue), vec![(2, 10), (3, 11)]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_only_first_event_after_drain_asks_for_wakeup() {
+        let queue: IrqQueue<4, 8> = IrqQueue::new();
+        assert!(queue.raise(1, 0));
+        assert!(!queue.raise(1, 1));
+        drained(&queue);
+        assert!(queue.raise(1, 2));
+    }
+
+    #[test]
+    fn test_full_queue_coalesces_per_id() {
+        let queue: IrqQueue<2, 8> = IrqQueue::new();
+        queue.raise(5, 1);
+        queue.raise(5, 2);
+        // Queue full from here on
+        queue.raise(5, 3);
+        queue.raise(6, 7);
+        queue.raise(5, 4);
+        assert_eq!(drained(&queue), vec![(5, 1), (5, 2), (5, 4), (6, 7)]);
+        assert_eq!(queue.take_dropped(), 0);
+    }
+
+    #[test]
+    fn test_ids_past_the_bound_are_dropped_when_full() {
+        let queue: IrqQueue<1, 4> = IrqQueue::new();
+        queue.raise(9, 1);
+        queue.raise(9, 2);
+        queue.raise(3, 5);
+        assert_eq!(queue.take_dropped(), 1);
+        assert_eq!(drained(&queue), vec![(9, 1), (3, 5)]);
+    }
+
+    #[test]
+    fn test_unattached_wake_keeps_next_event_waking() {
+        let queue: IrqQueue<4, 4> = IrqQueue::new();
+        queue.raise_and_wake(0, 0);
+        assert!(queue.raise(0, 1));
+    }
+
+    #[test]
+    fn test_event_message_shape() {
+        let table = MockAtomTable::new();
+        let message = IrqEvent { id: 4, payload: 1 }.to_message(&table);
+        assert_eq!(
+            message,
+            TermValue::tuple(vec![TermValue::atom("irq", &table), TermValue::int(4), TermValue::int(1)])
+        );
+        assert!(IrqQueue::<1, 1>::is_wakeup(&TermValue::atom("irq_wakeup", &table), &table));
+    }
+}
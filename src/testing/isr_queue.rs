@@ -0,0 +1,98 @@
+//! ISR event queue testing suite
+
+use crate::etf::{decode, ListEncoder};
+use crate::port::isr_queue::IsrQueue;
+use crate::term::TermValue;
+use crate::testing::mocks::MockAtomTable;
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_come_out_in_order() {
+        let queue: IsrQueue<u16, 4> = IsrQueue::new();
+        assert!(queue.is_empty());
+        queue.push(1).unwrap();
+        queue.push(2).unwrap();
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_full_queue_drops_and_counts() {
+        let queue: IsrQueue<u8, 2> = IsrQueue::new();
+        queue.push(1).unwrap();
+        queue.push(2).unwrap();
+        assert_eq!(queue.push(3), Err(3));
+        assert_eq!(queue.push(4), Err(4));
+        assert_eq!(queue.dropped(), 2);
+        assert_eq!(queue.take_dropped(), 2);
+        assert_eq!(queue.dropped(), 0);
+
+        // Room again once drained
+        assert_eq!(queue.pop(), Some(1));
+        queue.push(5).unwrap();
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_ring_wraps_around() {
+        let queue: IsrQueue<u32, 3> = IsrQueue::new();
+        let mut seen = Vec::new();
+        for round in 0..10 {
+            queue.push(round * 2).unwrap();
+            queue.push(round * 2 + 1).unwrap();
+            assert_eq!(queue.drain(|event| seen.push(event)), 2);
+        }
+        assert_eq!(seen, (0..20).collect::<Vec<_>>());
+        assert_eq!(queue.capacity(), 3);
+    }
+
+    #[test]
+    fn test_full_ring_of_any_size_after_wrapping() {
+        let queue: IsrQueue<u32, 3> = IsrQueue::new();
+        for round in 0..7 {
+            for event in 0..3 {
+                queue.push(round * 3 + event).unwrap();
+            }
+            assert_eq!(queue.len(), 3);
+            assert_eq!(queue.push(99), Err(99));
+            assert_eq!(queue.pop(), Some(round * 3));
+            assert_eq!(queue.len(), 2);
+            queue.drain(|_| ());
+            assert!(queue.is_empty());
+        }
+        assert_eq!(queue.take_dropped(), 7);
+    }
+
+    #[test]
+    fn test_collect_into_builds_one_list() {
+        let table = MockAtomTable::new();
+        let queue: IsrQueue<(u8, bool), 8> = IsrQueue::new();
+        queue.push((4, true)).unwrap();
+        queue.push((5, false)).unwrap();
+
+        let mut batch = ListEncoder::new();
+        let count = queue
+            .collect_into(&mut batch, &table, |(pin, high)| {
+                TermValue::tuple(vec![TermValue::int(pin as i32), TermValue::int(high as i32)])
+            })
+            .unwrap();
+        assert_eq!(count, 2);
+        assert!(queue.is_empty());
+
+        let list = decode(batch.finish(), &table).unwrap();
+        assert_eq!(
+            list,
+            TermValue::list(vec![
+                TermValue::tuple(vec![TermValue::int(4), TermValue::int(1)]),
+                TermValue::tuple(vec![TermValue::int(5), TermValue::int(0)]),
+            ])
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Log level and formatting testing suite
+
+use crate::log::{self, format_record, record_term, Level, LineBuffer, LogSink, Record, LINE_CAPACITY, MAX_LEVEL};
+use crate::sync::SpinLock;
+use crate::term::TermValue;
+use crate::testing::helpers::*;
+use crate::testing::mocks::MockAtomTable;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+fn record(level: Level, module: &str, line: u32, args: core::fmt::Arguments<'_>) -> String {
+    let mut out = String::new();
+    format_record(&mut out, level, module, line, args).unwrap();
+    out
+}
+
+/// Keeps the messages it receives
+struct CollectingSink(SpinLock<Vec<(Level, String)>>);
+
+impl LogSink for CollectingSink {
+    fn log(&self, record: &Record<'_>) {
+        self.0.with(|records| records.push((record.level, record.message.to_string())));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_ordered_by_severity() {
+        assert!(Level::Error < Level::Warn);
+        assert!(Level::Warn < Level::Info);
+        assert!(Level::Info < Level::Debug);
+        assert_eq!(Level::Warn.as_str(), "warn");
+    }
+
+    #[test]
+    fn test_levels_enabled_up_to_max() {
+        for level in [Level::Error, Level::Warn, Level::Info, Level::Debug] {
+            assert_eq!(level.enabled(), MAX_LEVEL.is_some_and(|max| level <= max));
+        }
+        #[cfg(not(any(
+            feature = "max-level-off",
+            feature = "max-level-error",
+            feature = "max-level-warn",
+            feature = "max-level-info"
+        )))]
+        assert_eq!(MAX_LEVEL, Some(Level::Debug));
+    }
+
+    #[test]
+    fn test_record_carries_location() {
+        assert_eq!(
+            record(Level::Warn, "gpio::irq", 88, format_args!("rx overrun, {} bytes lost", 12)),
+            "[warn] gpio::irq:88: rx overrun, 12 bytes lost"
+        );
+    }
+
+    #[test]
+    fn test_line_buffer_escapes_nul() {
+        let mut line = LineBuffer::new();
+        write!(line, "id\0{}", 7).unwrap();
+        assert_eq!(line.as_str(), "id\\07");
+        assert!(!line.is_truncated());
+    }
+
+    #[test]
+    fn test_line_buffer_truncates_long_lines() {
+        let mut line = LineBuffer::new();
+        for _ in 0..100 {
+            write!(line, "sample;").unwrap();
+        }
+        assert!(line.is_truncated());
+        assert_eq!(line.as_str().len(), LINE_CAPACITY);
+        assert!(line.as_str().ends_with("..."));
+
+        // Nothing more is taken once the line was cut
+        write!(line, "x").unwrap();
+        assert!(line.as_str().ends_with("..."));
+    }
+
+    #[test]
+    fn test_line_buffer_cuts_on_char_boundary() {
+        let mut line = LineBuffer::new();
+        write!(line, "{}", "a".repeat(LINE_CAPACITY - 4)).unwrap();
+        write!(line, "éééé").unwrap();
+        assert!(line.is_truncated());
+        // Still valid UTF-8, so the whole line survives `as_str`
+        assert!(line.as_str().starts_with("aaa"));
+        assert!(line.as_str().ends_with("..."));
+        assert!(line.as_str().len() <= LINE_CAPACITY);
+    }
+
+    #[test]
+    fn test_record_fits_in_line_buffer() {
+        let mut line = LineBuffer::new();
+        format_record(&mut line, Level::Error, "spi", 4, format_args!("nack")).unwrap();
+        assert_eq!(line.as_str(), "[error] spi:4: nack");
+    }
+
+    #[test]
+    fn test_record_term_for_logger() {
+        let table = MockAtomTable::new();
+        let record = Record { level: Level::Warn, module: Some("uart"), line: Some(88), message: "rx overrun" };
+
+        let term = record_term(&record, &table);
+        let [tag, level, message, TermValue::Map(meta)] = term.as_tuple().unwrap() else {
+            panic!("expected {{log, Level, Message, Meta}}, got {:?}", term);
+        };
+        assert_atom_str(tag, "log", &table);
+        assert_atom_str(level, "warning", &table);
+        assert_eq!(*message, TermValue::Binary(b"rx overrun".to_vec()));
+        assert_eq!(meta.len(), 2);
+        assert!(meta.contains(&(atom("module", &table), TermValue::Binary(b"uart".to_vec()))));
+        assert!(meta.contains(&(atom("line", &table), TermValue::int(88))));
+
+        let plain = Record { level: Level::Info, module: None, line: None, message: "up" };
+        assert!(matches!(record_term(&plain, &table).as_tuple(), Some([_, _, _, TermValue::Map(meta)]) if meta.is_empty()));
+    }
+
+    #[test]
+    fn test_sink_receives_records_until_removed() {
+        static SINK: CollectingSink = CollectingSink(SpinLock::new(Vec::new()));
+        let record = Record { level: Level::Error, module: None, line: None, message: "nack" };
+
+        log::set_sink(Some(&SINK));
+        log::forward(&record);
+        log::set_sink(None);
+        log::forward(&record);
+
+        assert_eq!(SINK.0.with(|records| records.clone()), [(Level::Error, "nack".to_string())]);
+    }
+}
@@ -0,0 +1,119 @@
+//! Metrics testing suite
+
+use crate::metrics::{self, Counter, Gauge, Histogram, Metric, MetricValue, MetricsError, MetricsRegistry};
+use crate::term::TermValue;
+use crate::testing::helpers::*;
+use crate::testing::mocks::MockAtomTable;
+use alloc::vec;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static FRAMES: Counter = Counter::new("frames");
+    static DEPTH: Gauge = Gauge::new("queue_depth");
+    static LATENCY: Histogram<2> = Histogram::new("latency_us", [100, 1000]);
+
+    #[test]
+    fn test_metric_updates() {
+        let counter = Counter::new("c");
+        counter.inc();
+        counter.add(4);
+        assert_eq!(counter.read(), MetricValue::Counter(5));
+
+        let gauge = Gauge::new("g");
+        gauge.set(10);
+        gauge.add(-3);
+        assert_eq!(gauge.read(), MetricValue::Gauge(7));
+
+        let histogram = Histogram::new("h", [10, 100]);
+        for value in [5, 10, 50, 500] {
+            histogram.observe(value);
+        }
+        assert_eq!(
+            histogram.read(),
+            MetricValue::Histogram { count: 4, sum: 565, buckets: vec![(10, 2), (100, 1)], overflow: 1 }
+        );
+        histogram.reset();
+        assert_eq!(histogram.count(), 0);
+    }
+
+    #[test]
+    fn test_registry_capacity_and_names() {
+        let registry: MetricsRegistry<2> = MetricsRegistry::new();
+        static A: Counter = Counter::new("a");
+        static A_AGAIN: Gauge = Gauge::new("a");
+        static B: Counter = Counter::new("b");
+        static C: Counter = Counter::new("c");
+
+        assert_eq!(registry.register(&A), Ok(()));
+        assert_eq!(registry.register(&A), Ok(()));
+        assert_eq!(registry.register(&A_AGAIN), Err(MetricsError::DuplicateName("a")));
+        assert_eq!(registry.register(&B), Ok(()));
+        assert_eq!(registry.register(&C), Err(MetricsError::Full));
+        assert_eq!(registry.len(), 2);
+    }
+
+    #[test]
+    fn test_registry_exports_map() {
+        let table = MockAtomTable::new();
+        let registry: MetricsRegistry<4> = MetricsRegistry::new();
+        registry.register(&FRAMES).unwrap();
+        registry.register(&DEPTH).unwrap();
+        registry.register(&LATENCY).unwrap();
+
+        FRAMES.add(3);
+        DEPTH.set(-2);
+        LATENCY.observe(2000);
+
+        let map = registry.to_term(&table);
+        assert_eq!(map.map_get(&atom("frames", &table)), Some(&TermValue::int(3)));
+        assert_eq!(map.map_get(&atom("queue_depth", &table)), Some(&TermValue::int(-2)));
+
+        let latency = map.map_get(&atom("latency_us", &table)).unwrap();
+        assert_eq!(latency.map_get(&atom("count", &table)), Some(&TermValue::int(1)));
+        let buckets = latency.map_get(&atom("buckets", &table)).unwrap().list_to_vec();
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(buckets[2], TermValue::tuple(vec![atom("infinity", &table), TermValue::int(1)]));
+
+        registry.reset_all();
+        assert_eq!(FRAMES.get(), 0);
+    }
+
+    #[test]
+    fn test_counts_saturate_in_terms() {
+        let table = MockAtomTable::new();
+        assert_eq!(MetricValue::Counter(u32::MAX).to_term(&table), TermValue::int(i32::MAX));
+    }
+
+    #[test]
+    fn test_global_registry() {
+        static BOOTS: Counter = Counter::new("test_global_boots");
+        metrics::register(&BOOTS).unwrap();
+        assert!(metrics::global().snapshot().iter().any(|(name, _)| *name == "test_global_boots"));
+    }
+
+    #[test]
+    fn test_metrics_nif_encodes_on_mock_heap() {
+        use crate::testing::mocks::MockGlobalContext;
+
+        static READS: Counter = Counter::new("test_nif_reads");
+        static READ_US: Histogram<1> = Histogram::new("test_nif_read_us", [50]);
+        metrics::register(&READS).unwrap();
+        metrics::register(&READ_US).unwrap();
+        READS.add(2);
+        READ_US.observe(10);
+
+        let global = MockGlobalContext::new();
+        let table = global.atoms();
+        let mut port = global.new_context();
+        let ctx = port.as_context() as *mut _ as *mut crate::term::Context;
+        let map = unsafe { metrics::metrics_nif(ctx, 0, core::ptr::null()) }.to_value().unwrap();
+        assert!(port.exception().is_none());
+
+        assert_eq!(map.map_get(&atom("test_nif_reads", table)), Some(&TermValue::int(2)));
+        let read_us = map.map_get(&atom("test_nif_read_us", table)).unwrap();
+        let buckets = read_us.map_get(&atom("buckets", table)).unwrap().list_to_vec();
+        assert_eq!(buckets[0], TermValue::tuple(vec![TermValue::int(50), TermValue::int(1)]));
+    }
+}
@@ -7,6 +7,8 @@
 //!
 //! No global state, no singletons - each test creates its own mock instances.
 //! This ensures perfect test isolation and makes the mocks completely generic.
+//! The one exception is `MockGlobalContext`, which AtomVM's argument-less
+//! globals resolve to; it is per thread, so parallel tests stay isolated.
 
 extern crate alloc;
 
@@ -14,6 +16,9 @@ use alloc::{collections::BTreeMap, string::{String, ToString}, vec::Vec, boxed::
 use core::ffi::c_uint;
 use core::cell::RefCell;
 use crate::atom::{AtomIndex, AtomTableOps, AtomError, AtomRef, EnsureAtomsOpt};
+use crate::monitor::{MonitorError, ProcessMonitor};
+use crate::names::{NameError, NameRegistry};
+use crate::term::{Heap, HeapGuard, NifResult, Term, TermValue};
 
 // ── Mock Atom Table Implementation ─────────────────────────────────────────
 
@@ -26,7 +31,8 @@ use crate::atom::{AtomIndex, AtomTableOps, AtomError, AtomRef, EnsureAtomsOpt};
 #[derive(Debug)]
 pub struct MockAtomTable {
     atoms: RefCell<BTreeMap<String, u32>>,
-    reverse_atoms: RefCell<BTreeMap<u32, String>>,
+    // Names are leaked once, when the atom is created, so lookups can lend them
+    reverse_atoms: RefCell<BTreeMap<u32, &'static str>>,
     next_id: RefCell<u32>,
 }
 
@@ -86,14 +92,14 @@ impl MockAtomTable {
     /// Get atom name by index (reverse lookup) - helper method
     pub fn get_atom_name(&self, AtomIndex(idx): AtomIndex) -> Option<String> {
         let reverse_atoms = self.reverse_atoms.borrow();
-        reverse_atoms.get(&idx).cloned()
+        reverse_atoms.get(&idx).map(|name| name.to_string())
     }
 
     /// Get all atoms currently in the table (for debugging)
     pub fn list_all_atoms(&self) -> Vec<(AtomIndex, String)> {
         let reverse_atoms = self.reverse_atoms.borrow();
         reverse_atoms.iter()
-            .map(|(&idx, name)| (AtomIndex(idx), name.clone()))
+            .map(|(&idx, name)| (AtomIndex(idx), name.to_string()))
             .collect()
     }
 
@@ -113,13 +119,9 @@ impl AtomTableOps for MockAtomTable {
     }
 
     fn get_atom_string(&self, AtomIndex(idx): AtomIndex) -> Result<AtomRef<'_>, AtomError> {
-        // For the mock, we'll work around the lifetime issue by using a different approach
         let reverse_atoms = self.reverse_atoms.borrow();
-        if let Some(atom_str) = reverse_atoms.get(&idx) {
-            // Since we can't return a proper AtomRef with borrowed data in a mock,
-            // we'll create a static string for the mock. This is safe for testing.
-            let leaked_str: &'static str = Box::leak(atom_str.clone().into_boxed_str());
-            Ok(AtomRef::new(leaked_str.as_bytes(), AtomIndex(idx)))
+        if let Some(&atom_str) = reverse_atoms.get(&idx) {
+            Ok(AtomRef::new(atom_str.as_bytes(), AtomIndex(idx)))
         } else {
             Err(AtomError::NotFound)
         }
@@ -151,7 +153,7 @@ impl AtomTableOps for MockAtomTable {
         
         // Insert into both maps
         self.atoms.borrow_mut().insert(name.to_string(), new_id);
-        self.reverse_atoms.borrow_mut().insert(new_id, name.to_string());
+        self.reverse_atoms.borrow_mut().insert(new_id, Box::leak(name.into()));
         
         Ok(AtomIndex(new_id))
     }
@@ -176,7 +178,7 @@ impl AtomTableOps for MockAtomTable {
 
     fn atom_equals_str(&self, AtomIndex(idx): AtomIndex, name: &str) -> bool {
         let reverse_atoms = self.reverse_atoms.borrow();
-        if let Some(atom_name) = reverse_atoms.get(&idx) {
+        if let Some(&atom_name) = reverse_atoms.get(&idx) {
             atom_name == name
         } else {
             false
@@ -235,7 +237,7 @@ pub struct MockResource {
     pub type_id: usize,
     pub size: u32,
     pub ref_count: usize,
-    pub data: Vec<u8>, // Simulated memory content
+    pub data: Vec<u64>, // Simulated memory content (word-aligned so typed resources fit)
 }
 
 /// Mock monitor for testing
@@ -246,6 +248,16 @@ pub struct MockMonitor {
     pub active: bool,
 }
 
+/// The order `MockResourceManager::run_gc` destroys what it collects in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GcOrder {
+    /// Oldest resource first
+    #[default]
+    Allocation,
+    /// Newest resource first
+    Reverse,
+}
+
 /// Simple no_std state for the mock resource manager
 /// 
 /// Note: In no_std environment, we can't use Mutex, so this is not thread-safe.
@@ -257,7 +269,8 @@ pub struct MockResourceManagerState {
     pub resource_types: BTreeMap<String, MockResourceType>,
     pub resources: BTreeMap<usize, MockResource>, // resource_id -> resource
     pub monitors: BTreeMap<usize, MockMonitor>,   // monitor_id -> monitor
-    pub term_to_resource: BTreeMap<u64, usize>,   // term -> resource_id
+    pub term_to_resource: BTreeMap<ERL_NIF_TERM, usize>,   // term -> resource_id
+    pub destructors: BTreeMap<usize, ErlNifResourceDtor>, // type_id -> destructor
     
     // ID generators
     pub next_type_id: AtomicUsize,
@@ -269,7 +282,7 @@ pub struct MockResourceManagerState {
     pub init_calls: Vec<String>,
     pub alloc_calls: Vec<(usize, u32)>, // (type_id, size)
     pub make_resource_calls: Vec<usize>, // resource_id
-    pub get_resource_calls: Vec<(u64, usize)>, // (term, type_id)
+    pub get_resource_calls: Vec<(ERL_NIF_TERM, usize)>, // (term, type_id)
     pub keep_resource_calls: Vec<usize>, // resource_id
     pub release_resource_calls: Vec<usize>, // resource_id
     pub select_calls: Vec<(i32, ErlNifSelectFlags, usize)>, // (event, mode, resource_id)
@@ -293,6 +306,10 @@ pub struct MockResourceManagerState {
     // Resource limits for testing
     pub max_resources: Option<usize>,
     pub max_monitors: Option<usize>,
+
+    // Garbage collection simulation
+    pub gc: bool,
+    pub gc_order: GcOrder,
 }
 
 impl MockResourceManagerState {
@@ -312,8 +329,8 @@ impl MockResourceManagerState {
         self.next_resource_id.fetch_add(1, Ordering::SeqCst)
     }
     
-    pub fn generate_term_id(&self) -> u64 {
-        self.next_term_id.fetch_add(1, Ordering::SeqCst) as u64 + 0x12340000
+    pub fn generate_term_id(&self) -> ERL_NIF_TERM {
+        self.next_term_id.fetch_add(1, Ordering::SeqCst) + 0x12340000
     }
     
     pub fn generate_monitor_id(&self) -> usize {
@@ -325,18 +342,38 @@ impl MockResourceManagerState {
         (0x1000 + type_id) as *mut ErlNifResourceType
     }
     
-    /// Convert a resource ID to a fake pointer
+    /// Convert a resource ID to a pointer
+    ///
+    /// Live resources hand out the address of their simulated memory so
+    /// typed wrappers can read and write through it; unknown IDs map to a
+    /// fake pointer.
     fn resource_id_to_ptr(&self, resource_id: usize) -> *mut c_void {
-        (0x2000 + resource_id) as *mut c_void
+        match self.resources.get(&resource_id) {
+            Some(resource) => resource.data.as_ptr() as *mut c_void,
+            None => (0x2000 + resource_id) as *mut c_void,
+        }
     }
     
-    /// Convert a fake pointer back to resource ID
+    /// Convert a pointer (fake or simulated memory) back to resource ID
     fn ptr_to_resource_id(&self, ptr: *mut c_void) -> Option<usize> {
         let addr = ptr as usize;
         if addr >= 0x2000 && addr < 0x3000 {
-            Some(addr - 0x2000)
-        } else {
-            None
+            return Some(addr - 0x2000);
+        }
+        self.resources.values()
+            .find(|resource| resource.data.as_ptr() as usize == addr)
+            .map(|resource| resource.id)
+    }
+
+    /// Run the registered destructor for a resource and forget it
+    fn destroy_resource(&mut self, resource_id: usize) {
+        self.destructor_calls.push(resource_id);
+        if let Some(mut resource) = self.resources.remove(&resource_id) {
+            if let Some(dtor) = self.destructors.get(&resource.type_id) {
+                unsafe {
+                    dtor(core::ptr::null_mut(), resource.data.as_mut_ptr() as *mut c_void);
+                }
+            }
         }
     }
     
@@ -376,6 +413,64 @@ impl MockResourceManager {
         self.state.max_monitors = Some(max);
         self
     }
+
+    /// Let resource terms hold a reference, given up when `run_gc` collects them
+    ///
+    /// As on the VM, a term from `make_resource` keeps its resource alive
+    /// after the NIF releases its own reference, and `get_resource_ref_count`
+    /// counts it. Without this a term holds nothing and releasing the last
+    /// reference destroys the resource at once.
+    pub fn with_gc(mut self) -> Self {
+        self.state.gc = true;
+        self
+    }
+
+    pub fn set_gc_order(&mut self, order: GcOrder) {
+        self.state.gc_order = order;
+    }
+
+    /// Collect every resource term not in `roots`, as a GC of the processes
+    /// holding `roots` would, and destroy the resources left unreferenced
+    ///
+    /// Each collected term gives up its resource's reference and can no
+    /// longer be resolved. Resources with none left have their destructors
+    /// run in the `GcOrder`, after all terms are collected. Returns the IDs
+    /// of every resource destroyed, in the order it happened, including any
+    /// a destructor released in turn.
+    pub fn run_gc(&mut self, roots: &[ERL_NIF_TERM]) -> Vec<usize> {
+        let state = &mut self.state;
+        let garbage: Vec<ERL_NIF_TERM> =
+            state.term_to_resource.keys().filter(|term| !roots.contains(term)).copied().collect();
+        let mut unreferenced = Vec::new();
+        for term in garbage {
+            let Some(resource_id) = state.term_to_resource.remove(&term) else {
+                continue;
+            };
+            if !state.gc {
+                continue;
+            }
+            if let Some(resource) = state.resources.get_mut(&resource_id) {
+                resource.ref_count = resource.ref_count.saturating_sub(1);
+                if resource.ref_count == 0 {
+                    unreferenced.push(resource_id);
+                }
+            }
+        }
+        unreferenced.sort_unstable();
+        unreferenced.dedup();
+        if state.gc_order == GcOrder::Reverse {
+            unreferenced.reverse();
+        }
+
+        let before = state.destructor_calls.len();
+        for resource_id in unreferenced {
+            // A destructor may have released it already
+            if state.resources.contains_key(&resource_id) {
+                state.destroy_resource(resource_id);
+            }
+        }
+        state.destructor_calls[before..].to_vec()
+    }
     
     // Test behavior control methods
     pub fn set_fail_init(&mut self, fail: bool) {
@@ -425,8 +520,7 @@ impl MockResourceManager {
     
     pub fn simulate_destructor_call(&mut self, ptr: *mut c_void) {
         if let Some(resource_id) = self.state.ptr_to_resource_id(ptr) {
-            self.state.destructor_calls.push(resource_id);
-            self.state.resources.remove(&resource_id);
+            self.state.destroy_resource(resource_id);
         }
     }
     
@@ -495,6 +589,9 @@ impl ResourceManager for MockResourceManager {
         
         self.state.init_calls.push(name.to_string());
         self.state.resource_types.insert(name.to_string(), resource_type);
+        if let Some(dtor) = init.dtor {
+            self.state.destructors.insert(type_id, dtor);
+        }
         
         Ok(self.state.type_id_to_ptr(type_id))
     }
@@ -534,8 +631,9 @@ impl ResourceManager for MockResourceManager {
             type_id,
             size,
             ref_count: 1,
-            data: alloc::vec![0u8; size as usize], // Initialize with zeros
+            data: alloc::vec![0u64; (size as usize + 7) / 8], // Word-aligned, zeroed
         };
+        let ptr = resource.data.as_ptr() as *mut c_void;
         
         // Since we have &self, we need to use unsafe to modify the state
         // This is acceptable for testing purposes
@@ -545,7 +643,7 @@ impl ResourceManager for MockResourceManager {
             (*state_ptr).resources.insert(resource_id, resource);
         }
         
-        Ok(self.state.resource_id_to_ptr(resource_id))
+        Ok(ptr)
     }
 
     fn make_resource(
@@ -578,6 +676,12 @@ impl ResourceManager for MockResourceManager {
             let state_ptr = &self.state as *const _ as *mut MockResourceManagerState;
             (*state_ptr).make_resource_calls.push(resource_id);
             (*state_ptr).term_to_resource.insert(term, resource_id);
+            // The term's own reference, given up when it is collected
+            if (*state_ptr).gc {
+                if let Some(resource) = (*state_ptr).resources.get_mut(&resource_id) {
+                    resource.ref_count += 1;
+                }
+            }
         }
         
         Ok(term)
@@ -678,8 +782,7 @@ impl ResourceManager for MockResourceManager {
                     
                     // If ref count reaches 0, simulate destructor call
                     if resource.ref_count == 0 {
-                        (*state_ptr).destructor_calls.push(resource_id);
-                        (*state_ptr).resources.remove(&resource_id);
+                        (*state_ptr).destroy_resource(resource_id);
                     }
                 }
                 Ok(())
@@ -819,7 +922,509 @@ impl ResourceManager for MockResourceManager {
 
 // ── Additional Mock Implementations ────────────────────────────────────────
 
-// Future: Add MockContext, MockHeap, etc. here as needed
+use crate::port::timer::TimerBackend;
+
+/// Mock timer backend with a manually advanced clock
+///
+/// Records the currently armed wakeup so tests can check what the
+/// platform would have been asked to do.
+#[derive(Debug, Default)]
+pub struct MockTimerBackend {
+    pub now: u64,
+    pub armed: Option<u64>,
+    pub arm_calls: usize,
+}
+
+impl MockTimerBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move the clock forward
+    pub fn advance(&mut self, ms: u64) {
+        self.now += ms;
+    }
+}
+
+impl TimerBackend for MockTimerBackend {
+    fn now_ms(&self) -> u64 {
+        self.now
+    }
+
+    fn arm(&mut self, delay_ms: u64) {
+        self.armed = Some(delay_ms);
+        self.arm_calls += 1;
+    }
+
+    fn disarm(&mut self) {
+        self.armed = None;
+    }
+}
+
+/// Mock process monitor for `PidSet`
+///
+/// Only pids listed in `alive` can be monitored; handles are sequence
+/// numbers so tests can check which monitor was removed.
+#[derive(Debug, Default)]
+pub struct MockProcessMonitor {
+    pub alive: Vec<u32>,
+    pub monitored: Vec<(u32, usize)>,
+    pub demonitored: Vec<(u32, usize)>,
+    next_handle: usize,
+}
+
+impl MockProcessMonitor {
+    /// Create a monitor where the given pids are running
+    pub fn with_alive(pids: &[u32]) -> Self {
+        Self {
+            alive: pids.to_vec(),
+            ..Self::default()
+        }
+    }
+}
+
+impl ProcessMonitor for MockProcessMonitor {
+    type Handle = usize;
+
+    fn monitor(&mut self, pid: u32) -> Result<usize, MonitorError> {
+        if !self.alive.contains(&pid) {
+            return Err(MonitorError::NoProcess);
+        }
+        self.next_handle += 1;
+        self.monitored.push((pid, self.next_handle));
+        Ok(self.next_handle)
+    }
+
+    fn demonitor(&mut self, pid: u32, handle: &usize) {
+        self.demonitored.push((pid, *handle));
+    }
+}
+
+/// Mock name registry for `names`
+///
+/// Behaves like the VM's table: one pid per name, taken names are refused.
+#[derive(Debug, Default)]
+pub struct MockNameRegistry {
+    pub names: BTreeMap<AtomIndex, u32>,
+}
+
+impl NameRegistry for MockNameRegistry {
+    fn register(&mut self, name: AtomIndex, pid: u32) -> Result<(), NameError> {
+        if self.names.contains_key(&name) {
+            return Err(NameError::AlreadyRegistered);
+        }
+        self.names.insert(name, pid);
+        Ok(())
+    }
+
+    fn unregister(&mut self, name: AtomIndex) -> bool {
+        self.names.remove(&name).is_some()
+    }
+
+    fn whereis(&self, name: AtomIndex) -> Option<u32> {
+        self.names.get(&name).copied()
+    }
+}
+
+// ── Mock Heap ──────────────────────────────────────────────────────────────
+
+/// A process heap backed by a fixed host arena
+///
+/// `as_heap` hands it to `Term::from_value` and `HeapGuard` like a real
+/// AtomVM heap: boxed terms get the same word layout, so the encoded terms
+/// decode with `to_value`. The simulated `avmnif_memory_heap_alloc` (see
+/// `testing::sim`) bump-allocates from the arena and returns null once it
+/// is full.
+///
+/// The arena never moves, so terms stay valid while the `MockHeap` lives.
+#[repr(C)]
+pub struct MockHeap {
+    words: Box<[usize]>,
+    used: usize,
+}
+
+impl MockHeap {
+    /// A heap with room for `capacity` words
+    pub fn new(capacity: usize) -> Self {
+        Self { words: alloc::vec![0; capacity].into_boxed_slice(), used: 0 }
+    }
+
+    /// The heap as AtomVM's opaque `Heap`
+    pub fn as_heap(&mut self) -> &mut Heap {
+        unsafe { &mut *(self as *mut Self as *mut Heap) }
+    }
+
+    /// A guard over all of the remaining space
+    pub fn guard(&mut self) -> HeapGuard<'_> {
+        let free = self.free();
+        HeapGuard::from_heap(self.as_heap(), free)
+    }
+
+    /// Encode `value` as `Term::from_value` does
+    pub fn encode(&mut self, value: TermValue) -> NifResult<Term<'static>> {
+        Term::from_value(value, self.as_heap())
+    }
+
+    /// Words allocated so far
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Words left
+    pub fn free(&self) -> usize {
+        self.words.len() - self.used
+    }
+
+    /// Whether `term` is a boxed term inside this heap's arena
+    pub fn contains(&self, term: Term<'_>) -> bool {
+        let address = term.raw() & !Term::TERM_PRIMARY_MASK;
+        let start = self.words.as_ptr() as usize;
+        (start..start + self.used * core::mem::size_of::<usize>()).contains(&address)
+    }
+
+    /// Forget everything allocated, keeping the arena
+    pub fn clear(&mut self) {
+        self.used = 0;
+    }
+
+    pub(crate) fn alloc(&mut self, words: usize) -> *mut usize {
+        if words > self.free() {
+            return core::ptr::null_mut();
+        }
+        let ptr = self.words[self.used..].as_mut_ptr();
+        self.used += words;
+        ptr
+    }
+}
+
+// ── Mock Contexts ──────────────────────────────────────────────────────────
+
+extern crate std;
+
+use crate::context::{Context, GlobalContext};
+use crate::port::{Message, ERL_NIF_TERM};
+use crate::term::{ProcessId, RefId};
+use core::cell::Cell;
+
+std::thread_local! {
+    static CURRENT_GLOBAL: Cell<*const MockGlobalContext> = const { Cell::new(core::ptr::null()) };
+}
+
+/// A VM for port tests: atoms, resources, names, the clock and what was sent
+///
+/// AtomVM reaches its global context without arguments
+/// (`AtomTable::from_global`, `get_global_context`), so the mock is boxed
+/// and becomes the current VM of the test's thread until it is dropped.
+/// Tests running in parallel each see their own.
+pub struct MockGlobalContext {
+    pub(crate) atoms: MockAtomTable,
+    pub(crate) resources: RefCell<MockResourceManager>,
+    pub(crate) names: RefCell<MockNameRegistry>,
+    next_port_id: Cell<u32>,
+    pub(crate) now_ms: Cell<u64>,
+    pub(crate) ref_ticks: Cell<u64>,
+    pub(crate) sent: RefCell<Vec<(ProcessId, TermValue)>>,
+    pub(crate) log: RefCell<Vec<String>>,
+    /// Private data of NIF collections, by collection name
+    pub(crate) nif_priv: RefCell<Vec<(String, *mut c_void)>>,
+}
+
+impl MockGlobalContext {
+    /// A VM with the common atoms, current on this thread
+    pub fn new() -> Box<Self> {
+        let global = Box::new(Self {
+            atoms: MockAtomTable::new(),
+            resources: RefCell::new(MockResourceManager::new()),
+            names: RefCell::new(MockNameRegistry::default()),
+            next_port_id: Cell::new(1),
+            now_ms: Cell::new(0),
+            ref_ticks: Cell::new(0),
+            sent: RefCell::new(Vec::new()),
+            log: RefCell::new(Vec::new()),
+            nif_priv: RefCell::new(Vec::new()),
+        });
+        CURRENT_GLOBAL.with(|current| current.set(&*global));
+        global
+    }
+
+    /// The VM's atom table, as seen through `AtomTable::from_global`
+    pub fn atoms(&self) -> &MockAtomTable {
+        &self.atoms
+    }
+
+    /// The VM as AtomVM's opaque `GlobalContext`
+    pub fn as_global(&self) -> &GlobalContext {
+        unsafe { &*(self as *const Self as *const GlobalContext) }
+    }
+
+    /// Move the VM's monotonic clock forward
+    pub fn advance_ms(&self, ms: u64) {
+        self.now_ms.set(self.now_ms.get() + ms);
+    }
+
+    /// Messages sent to processes from ports and tasks, oldest first
+    pub fn sent(&self) -> Vec<(ProcessId, TermValue)> {
+        self.sent.borrow().clone()
+    }
+
+    /// The resource manager behind the `enif_*` resource functions
+    pub fn resources(&self) -> core::cell::RefMut<'_, MockResourceManager> {
+        self.resources.borrow_mut()
+    }
+
+    /// The process registered under `name`, as `erlang:whereis/1`
+    pub fn whereis(&self, name: AtomIndex) -> Option<u32> {
+        self.names.borrow().whereis(name)
+    }
+
+    /// Lines written with `avmnif_log`, oldest first
+    pub fn log_lines(&self) -> Vec<String> {
+        self.log.borrow().clone()
+    }
+
+    /// A port context on this VM, not yet holding any data
+    pub fn new_context(&self) -> Box<MockContext> {
+        let id = self.next_port_id.get();
+        self.next_port_id.set(id + 1);
+        Box::new(MockContext {
+            global: self,
+            id,
+            platform_data: core::ptr::null_mut(),
+            user_data: 0,
+            alive: true,
+            heap: MockHeap::new(MockContext::HEAP_WORDS),
+            replies: Vec::new(),
+            monitors: Vec::new(),
+            timer: None,
+            exception: None,
+        })
+    }
+
+    /// The VM current on this thread, or null
+    pub(crate) fn current() -> *const MockGlobalContext {
+        CURRENT_GLOBAL.with(Cell::get)
+    }
+}
+
+impl Drop for MockGlobalContext {
+    fn drop(&mut self) {
+        CURRENT_GLOBAL.with(|current| {
+            if core::ptr::eq(current.get(), self) {
+                current.set(core::ptr::null());
+            }
+        });
+    }
+}
+
+/// A reply a port sent with `send_reply` or `send_reply_value`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockReply {
+    pub pid: TermValue,
+    pub reference: TermValue,
+    pub reply: TermValue,
+}
+
+/// A port context with working platform data, user data and heap
+///
+/// Contexts from `PortBuilder` and `create_port_context_safe` are
+/// `MockContext`s under the simulator; `MockContext::from_raw` gets them
+/// back to inspect the replies the port sent and the processes it monitors.
+pub struct MockContext {
+    pub(crate) global: *const MockGlobalContext,
+    pub(crate) id: u32,
+    pub(crate) platform_data: *mut c_void,
+    pub(crate) user_data: u64,
+    pub(crate) alive: bool,
+    pub(crate) heap: MockHeap,
+    pub(crate) replies: Vec<MockReply>,
+    pub(crate) monitors: Vec<u32>,
+    pub(crate) timer: Option<u32>,
+    pub(crate) exception: Option<(i32, TermValue)>,
+}
+
+impl MockContext {
+    /// Heap words of a new context, for the replies built on it
+    pub const HEAP_WORDS: usize = 1024;
+
+    /// The mock behind a context pointer
+    ///
+    /// # Safety
+    /// `ctx` must come from `create_port_context` or `MockContext::as_context`
+    /// and still be alive.
+    pub unsafe fn from_raw<'a>(ctx: *mut Context) -> &'a mut MockContext {
+        &mut *(ctx as *mut MockContext)
+    }
+
+    /// The context as AtomVM's opaque `Context`
+    pub fn as_context(&mut self) -> &mut Context {
+        unsafe { &mut *(self as *mut Self as *mut Context) }
+    }
+
+    /// The port's id
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// The heap replies are built on
+    pub fn heap(&mut self) -> &mut MockHeap {
+        &mut self.heap
+    }
+
+    /// Replies sent so far, oldest first
+    pub fn replies(&self) -> &[MockReply] {
+        &self.replies
+    }
+
+    /// Processes the port is monitoring
+    pub fn monitors(&self) -> &[u32] {
+        &self.monitors
+    }
+
+    /// Mark the port as exited, for `is_port_alive`
+    pub fn set_alive(&mut self, alive: bool) {
+        self.alive = alive;
+    }
+
+    /// Delay of the pending `timer_wakeup`, if the port's timer is armed
+    pub fn armed_timer(&self) -> Option<u32> {
+        self.timer
+    }
+
+    /// Class and reason of the exception raised on the context, if any
+    pub fn exception(&self) -> Option<&(i32, TermValue)> {
+        self.exception.as_ref()
+    }
+
+    pub(crate) fn atoms(&self) -> Option<&MockAtomTable> {
+        unsafe { self.global.as_ref() }.map(MockGlobalContext::atoms)
+    }
+}
+
+/// A message in a port's mailbox
+///
+/// The message term lives on the message's own heap, like a term copied
+/// into a mailbox.
+pub struct MockMessage {
+    heap: MockHeap,
+    pub(crate) term: Term<'static>,
+    pub(crate) call: Option<[Term<'static>; 3]>,
+}
+
+impl MockMessage {
+    /// The `{'$call', {Pid, Ref}, Command}` message of a `gen_server:call`
+    pub fn call(global: &MockGlobalContext, pid: ProcessId, reference: RefId, command: TermValue) -> NifResult<Self> {
+        let mut heap = MockHeap::new(MockContext::HEAP_WORDS);
+        let tag = heap.encode(TermValue::atom("$call", global.atoms()))?;
+        let pid = heap.encode(TermValue::Pid(pid))?;
+        let reference = heap.encode(TermValue::Reference(reference))?;
+        let command = heap.encode(command)?;
+        let mut guard = heap.guard();
+        let from = guard.tuple(&[pid, reference])?;
+        let term = Term::from_raw(guard.tuple(&[tag, from, command])?.raw());
+        let call = Some([pid, reference, command]);
+        Ok(Self { heap, term, call })
+    }
+
+    /// Any other message, such as a monitor's `DOWN`
+    pub fn info(value: TermValue) -> NifResult<Self> {
+        let mut heap = MockHeap::new(MockContext::HEAP_WORDS);
+        let term = heap.encode(value)?;
+        Ok(Self { heap, term, call: None })
+    }
+
+    /// The message as AtomVM's opaque `Message`
+    pub fn as_message(&self) -> &Message {
+        unsafe { &*(self as *const Self as *const Message) }
+    }
+
+    /// Words the message takes on its heap
+    pub fn heap_words(&self) -> usize {
+        self.heap.used()
+    }
+}
+
+// ── Mock Process ───────────────────────────────────────────────────────────
+
+use crate::port::MessageBackend;
+use alloc::collections::VecDeque;
+
+/// An Erlang process on the other end of a port, with an inspectable mailbox
+///
+/// As a `MessageBackend` it accepts what is addressed to its pid: a reply
+/// arrives as `{Ref, Reply}`, the message a `gen_server:call` receives, and
+/// other messages as they were sent. Anything for another pid is refused
+/// like a send to a dead process.
+#[derive(Debug)]
+pub struct MockProcess {
+    pid: ProcessId,
+    mailbox: VecDeque<TermValue>,
+}
+
+impl MockProcess {
+    /// A process with an empty mailbox
+    pub fn new(pid: u32) -> Self {
+        Self {
+            pid: ProcessId(pid),
+            mailbox: VecDeque::new(),
+        }
+    }
+
+    pub fn pid(&self) -> ProcessId {
+        self.pid
+    }
+
+    /// Take the oldest message
+    pub fn receive(&mut self) -> Option<TermValue> {
+        self.mailbox.pop_front()
+    }
+
+    /// Take the oldest message `pattern` accepts, leaving the others in order
+    pub fn receive_matching<F: Fn(&TermValue) -> bool>(&mut self, pattern: F) -> Option<TermValue> {
+        let position = self.mailbox.iter().position(pattern)?;
+        self.mailbox.remove(position)
+    }
+
+    /// Messages waiting, oldest first
+    pub fn mailbox(&self) -> impl Iterator<Item = &TermValue> {
+        self.mailbox.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.mailbox.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mailbox.is_empty()
+    }
+
+    fn deliver(&mut self, pid: u32, message: TermValue) -> NifResult<()> {
+        if pid != self.pid.0 {
+            return Err(crate::term::NifError::Other("message not delivered"));
+        }
+        self.mailbox.push_back(message);
+        Ok(())
+    }
+}
+
+impl MessageBackend for MockProcess {
+    fn reply(&mut self, pid: Term, reference: Term, reply: &TermValue) -> NifResult<()> {
+        let Ok(TermValue::Pid(ProcessId(pid))) = pid.to_value() else {
+            return Err(crate::term::NifError::BadArg);
+        };
+        let reply = TermValue::tuple(alloc::vec![reference.to_value()?, reply.clone()]);
+        self.deliver(pid, reply)
+    }
+
+    fn send(&mut self, pid: u32, message: &TermValue) -> NifResult<()> {
+        self.deliver(pid, message.clone())
+    }
+
+    fn send_term(&mut self, pid: u32, message: Term) {
+        if let Ok(message) = message.to_value() {
+            let _ = self.deliver(pid, message);
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -1075,6 +1680,63 @@ mod tests {
         assert_eq!(manager.get_init_call_count(), 0);
     }
 
+    #[test]
+    fn test_mock_resource_manager_gc() {
+        static DESTROYED: AtomicUsize = AtomicUsize::new(0);
+        unsafe extern "C" fn count_dtor(_env: *mut ErlNifEnv, _obj: *mut c_void) {
+            DESTROYED.fetch_add(1, Ordering::SeqCst);
+        }
+
+        let mut manager = MockResourceManager::new().with_gc();
+        let env = core::ptr::null_mut();
+        let init = resource_type_init_with_dtor(count_dtor);
+        let rt = manager.init_resource_type(env, "gc", &init, ErlNifResourceFlags::ERL_NIF_RT_CREATE).unwrap();
+        let [a, b, c] = [(); 3].map(|_| manager.alloc_resource(rt, 8).unwrap());
+        let id = |ptr| manager.get_state().ptr_to_resource_id(ptr).unwrap();
+        let ids = [id(a), id(b), id(c)];
+        let terms: Vec<_> = [a, a, b, c].iter().map(|&obj| manager.make_resource(env, obj).unwrap()).collect();
+
+        // Terms keep the resources alive after the NIF lets go
+        for obj in [a, b, c] {
+            manager.release_resource(obj).unwrap();
+        }
+        assert_eq!(manager.get_resource_ref_count(a), Some(2));
+        assert_eq!(manager.run_gc(&terms), Vec::<usize>::new());
+        assert_eq!(DESTROYED.load(Ordering::SeqCst), 0);
+
+        // Only the first term is still reachable; `a` keeps the reference of
+        // that term after losing the second
+        manager.set_gc_order(GcOrder::Reverse);
+        assert_eq!(manager.run_gc(&terms[..1]), alloc::vec![ids[2], ids[1]]);
+        assert_eq!(DESTROYED.load(Ordering::SeqCst), 2);
+        assert!(manager.get_resource(env, terms[2], rt).is_err());
+        assert_eq!(manager.get_resource(env, terms[0], rt), Ok(a));
+        assert_eq!(manager.get_resource_ref_count(a), Some(1));
+
+        assert_eq!(manager.run_gc(&[]), alloc::vec![ids[0]]);
+        assert_eq!(manager.get_resource_count(), 0);
+        assert_eq!(DESTROYED.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_mock_resource_manager_gc_without_term_references() {
+        unsafe extern "C" fn test_dtor(_env: *mut ErlNifEnv, _obj: *mut c_void) {}
+
+        let mut manager = MockResourceManager::new();
+        let env = core::ptr::null_mut();
+        let init = resource_type_init_with_dtor(test_dtor);
+        let rt = manager.init_resource_type(env, "plain", &init, ErlNifResourceFlags::ERL_NIF_RT_CREATE).unwrap();
+        let obj = manager.alloc_resource(rt, 8).unwrap();
+        let term = manager.make_resource(env, obj).unwrap();
+        assert_eq!(manager.get_resource_ref_count(obj), Some(1));
+
+        // The term is forgotten, the NIF's reference still holds the resource
+        assert!(manager.run_gc(&[]).is_empty());
+        assert!(manager.get_resource(env, term, rt).is_err());
+        manager.release_resource(obj).unwrap();
+        assert_eq!(manager.get_destructor_call_count(), 1);
+    }
+
     #[test]
     fn test_mock_resource_manager_pointer_conversion() {
         let state = MockResourceManagerState::new();
@@ -1100,4 +1762,22 @@ mod tests {
         let invalid_type_ptr = 0x5000 as *mut ErlNifResourceType;
         assert_eq!(state.ptr_to_type_id(invalid_type_ptr), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_mock_heap_allocation() {
+        let mut heap = MockHeap::new(4);
+        assert_eq!(heap.free(), 4);
+
+        // A float takes a header word plus its payload
+        let term = heap.encode(TermValue::Float(1.5)).unwrap();
+        assert!(heap.contains(term));
+        assert_eq!(heap.used(), 1 + 8 / core::mem::size_of::<usize>());
+
+        // Immediates take no heap, and a full heap reports out of memory
+        let term = heap.encode(TermValue::int(7)).unwrap();
+        assert!(!heap.contains(term));
+        let used = heap.used();
+        assert!(heap.encode(TermValue::tuple(alloc::vec![TermValue::Nil; 4])).is_err());
+        assert_eq!(heap.used(), used);
+    }
+}
@@ -5,11 +5,19 @@
 //! - Test helpers and utilities
 //! - Common test fixtures and data
 //! 
-//! All code in this module is conditionally compiled only for tests.
+//! All code in this module is conditionally compiled only for tests, except
+//! the pure-Rust helpers also exported through the `testing` feature and
+//! the mocks and host simulator exported through the `sim` feature.
 
-#[cfg(test)]
+#[cfg(any(test, feature = "sim"))]
 pub mod mocks;
 
+#[cfg(any(test, feature = "sim"))]
+pub mod sim;
+
+#[cfg(any(test, feature = "sim"))]
+pub mod harness;
+
 #[cfg(test)]
 pub mod helpers;
 
@@ -28,12 +36,147 @@ pub mod tagged;
 #[cfg(test)]
 pub mod ports;
 
-// Re-export everything for convenient imports
 #[cfg(test)]
+pub mod atoms;
+
+#[cfg(test)]
+pub mod etf;
+
+#[cfg(test)]
+pub mod terms;
+
+#[cfg(test)]
+pub mod timers;
+
+#[cfg(test)]
+pub mod calls;
+
+#[cfg(test)]
+pub mod monitors;
+
+#[cfg(test)]
+pub mod scratch;
+
+#[cfg(test)]
+pub mod ffi_log;
+
+#[cfg(test)]
+pub mod iolist;
+
+#[cfg(test)]
+pub mod bitfield;
+
+#[cfg(test)]
+pub mod binary;
+
+#[cfg(test)]
+pub mod diff;
+
+#[cfg(test)]
+pub mod metrics;
+
+#[cfg(test)]
+pub mod behavior;
+
+#[cfg(test)]
+pub mod trace;
+
+#[cfg(test)]
+pub mod reply;
+
+#[cfg(test)]
+pub mod names;
+
+#[cfg(test)]
+pub mod progress;
+
+#[cfg(test)]
+pub mod select;
+
+#[cfg(test)]
+pub mod framebuffer;
+
+#[cfg(test)]
+pub mod resource_monitor;
+
+#[cfg(test)]
+pub mod typed;
+
+#[cfg(test)]
+pub mod policy;
+
+#[cfg(test)]
+pub mod timeouts;
+
+#[cfg(test)]
+pub mod args;
+
+#[cfg(test)]
+pub mod canonical;
+
+#[cfg(test)]
+pub mod hash;
+
+#[cfg(test)]
+pub mod pretty;
+
+#[cfg(test)]
+pub mod parse;
+#[cfg(test)]
+pub mod serde;
+
+#[cfg(test)]
+pub mod panic;
+
+#[cfg(test)]
+pub mod allocator;
+
+#[cfg(test)]
+pub mod esp_idf;
+
+#[cfg(test)]
+pub mod isr_queue;
+
+#[cfg(test)]
+pub mod irq;
+
+#[cfg(test)]
+pub mod polling;
+
+#[cfg(test)]
+pub mod gpio;
+
+#[cfg(test)]
+pub mod bus;
+
+#[cfg(test)]
+pub mod stream;
+
+#[cfg(test)]
+pub mod task;
+#[cfg(all(test, feature = "async"))]
+pub mod asynch;
+#[cfg(test)]
+pub mod log;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod replay;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod generate;
+
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fuzz;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod golden;
+
+// Re-export everything for convenient imports
+#[cfg(any(test, feature = "sim"))]
 pub use mocks::*;
 
 #[cfg(test)]
 pub use helpers::*;
 
 #[cfg(test)]
-pub use fixtures::*;
\ No newline at end of file
+pub use fixtures::*;
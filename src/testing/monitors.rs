@@ -0,0 +1,109 @@
+//! PidSet testing suite
+
+use crate::monitor::{match_down, MonitorError, PidSet};
+use crate::term::TermValue;
+use crate::testing::mocks::{MockAtomTable, MockProcessMonitor};
+use alloc::{vec, vec::Vec};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn down_message(pid: u32, table: &MockAtomTable) -> TermValue {
+        TermValue::tuple(vec![
+            TermValue::atom("DOWN", table),
+            TermValue::reference(1),
+            TermValue::atom("process", table),
+            TermValue::pid(pid),
+            TermValue::atom("normal", table),
+        ])
+    }
+
+    #[test]
+    fn test_insert_monitors_once() {
+        let mut monitor = MockProcessMonitor::with_alive(&[10, 11]);
+        let mut set = PidSet::new();
+
+        assert_eq!(set.insert(&mut monitor, 10), Ok(true));
+        assert_eq!(set.insert(&mut monitor, 10), Ok(false));
+        assert_eq!(set.insert(&mut monitor, 11), Ok(true));
+        assert_eq!(set.insert(&mut monitor, 12), Err(MonitorError::NoProcess));
+
+        assert_eq!(monitor.monitored, vec![(10, 1), (11, 2)]);
+        assert_eq!(set.pids().collect::<Vec<_>>(), vec![10, 11]);
+        assert!(!set.contains(12));
+    }
+
+    #[test]
+    fn test_remove_and_clear_demonitor() {
+        let mut monitor = MockProcessMonitor::with_alive(&[1, 2, 3]);
+        let mut set = PidSet::new();
+        for pid in [1, 2, 3] {
+            set.insert(&mut monitor, pid).unwrap();
+        }
+
+        assert!(set.remove(&mut monitor, 2));
+        assert!(!set.remove(&mut monitor, 2));
+        assert_eq!(monitor.demonitored, vec![(2, 2)]);
+
+        set.clear(&mut monitor);
+        assert!(set.is_empty());
+        assert_eq!(monitor.demonitored, vec![(2, 2), (1, 1), (3, 3)]);
+    }
+
+    #[test]
+    fn test_down_prunes_without_demonitor() {
+        let table = MockAtomTable::new();
+        let mut monitor = MockProcessMonitor::with_alive(&[5, 6]);
+        let mut set = PidSet::new();
+        set.insert(&mut monitor, 5).unwrap();
+        set.insert(&mut monitor, 6).unwrap();
+
+        let mut removed = Vec::new();
+        assert!(set.handle_down_message(&down_message(5, &table), &table, |pid| removed.push(pid)));
+        assert!(!set.handle_down_message(&down_message(5, &table), &table, |pid| removed.push(pid)));
+        assert!(!set.handle_down_message(&TermValue::atom("DOWN", &table), &table, |pid| removed.push(pid)));
+
+        assert_eq!(removed, vec![5]);
+        assert_eq!(set.len(), 1);
+        assert!(monitor.demonitored.is_empty());
+    }
+
+    #[test]
+    fn test_match_down() {
+        let table = MockAtomTable::new();
+        assert_eq!(match_down(&down_message(42, &table), &table), Some(42));
+
+        let exit = TermValue::tuple(vec![
+            TermValue::atom("EXIT", &table),
+            TermValue::reference(1),
+            TermValue::atom("process", &table),
+            TermValue::pid(42),
+            TermValue::atom("normal", &table),
+        ]);
+        assert_eq!(match_down(&exit, &table), None);
+    }
+
+    #[test]
+    fn test_resource_backend() {
+        use crate::resource::{
+            resource_type_init, ErlNifResourceFlags, ResourceManager, ResourceProcessMonitor,
+        };
+        use crate::testing::mocks::MockResourceManager;
+
+        let mut manager = MockResourceManager::new();
+        let env = core::ptr::null_mut();
+        let resource_type = manager
+            .init_resource_type(env, "subscribers", &resource_type_init(), ErlNifResourceFlags::ERL_NIF_RT_CREATE)
+            .unwrap();
+        let obj = manager.alloc_resource(resource_type, 64).unwrap();
+
+        let mut backend = ResourceProcessMonitor::new(&manager, env, obj);
+        let mut set = PidSet::new();
+        assert_eq!(set.insert(&mut backend, 77), Ok(true));
+        assert_eq!(manager.get_monitor_count(), 1);
+
+        set.clear(&mut backend);
+        assert_eq!(manager.get_monitor_count(), 0);
+    }
+}
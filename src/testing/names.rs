@@ -0,0 +1,46 @@
+//! Process name registration testing suite
+
+use crate::atom::AtomTableOps;
+use crate::names::{self, NameError};
+use crate::testing::mocks::{MockAtomTable, MockNameRegistry};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_and_whereis() {
+        let table = MockAtomTable::new();
+        let mut registry = MockNameRegistry::default();
+
+        assert_eq!(names::whereis(&registry, "adc", &table), None);
+        names::register(&mut registry, "adc", 17, &table).unwrap();
+        assert_eq!(names::whereis(&registry, "adc", &table), Some(17));
+
+        assert_eq!(names::register(&mut registry, "adc", 18, &table), Err(NameError::AlreadyRegistered));
+        assert_eq!(names::whereis(&registry, "adc", &table), Some(17));
+
+        assert!(names::unregister(&mut registry, "adc", &table));
+        assert!(!names::unregister(&mut registry, "adc", &table));
+        assert_eq!(names::whereis(&registry, "adc", &table), None);
+    }
+
+    #[test]
+    fn test_lookup_does_not_create_atoms() {
+        let table = MockAtomTable::new();
+        let registry = MockNameRegistry::default();
+        let before = table.count();
+
+        assert_eq!(names::whereis(&registry, "never_registered", &table), None);
+        assert!(table.find_atom_str("never_registered").is_err());
+        assert_eq!(table.count(), before);
+    }
+
+    #[test]
+    fn test_undefined_is_not_a_name() {
+        let table = MockAtomTable::new();
+        let mut registry = MockNameRegistry::default();
+        assert_eq!(names::register(&mut registry, "undefined", 17, &table), Err(NameError::BadName));
+        assert!(registry.names.is_empty());
+    }
+}
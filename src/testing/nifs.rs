@@ -133,6 +133,7 @@ macro_rules! test_nif_collection {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::context::context_global;
 
     #[test]
     fn test_nif_call_simulator_creation() {
@@ -311,6 +312,126 @@ mod tests {
         let _add_fn: extern "C" fn(*mut Context, i32, *const Term) -> Term = test_add_nif;
     }
 
+    fn table_test_init(_ctx: &mut crate::Context) {}
+
+    crate::nif_collection!(
+        table_test,
+        init = table_test_init,
+        nifs = [
+            ("add", 2, test_add_nif),
+            ("string_op", 1, test_string_nif, dirty_cpu),
+            ("list_op", 1, test_list_nif, dirty_io),
+        ]
+    );
+
+    crate::nif_collection!(
+        overload_test,
+        module = "sensor",
+        init = table_test_init,
+        nifs = [
+            ("op", 1, test_string_nif),
+            ("op", 2, test_add_nif),
+            ("list_op", 1, test_list_nif, dirty_io),
+        ]
+    );
+
+    // The table is a plain const, so it can feed other statics
+    static EXPORTED: &[crate::registry::NifEntry] = TABLE_TEST_NIFS;
+
+    #[test]
+    fn test_nif_collection_const_table() {
+        let exported: Vec<(&str, u32)> = EXPORTED.iter().map(|entry| (entry.name, entry.arity)).collect();
+        assert_eq!(exported, vec![("add", 2), ("string_op", 1), ("list_op", 1)]);
+
+        let add = crate::registry::find_nif(TABLE_TEST_NIFS, "add").unwrap();
+        assert_eq!(add.function, test_add_nif as *const () as *const core::ffi::c_void);
+        assert!(crate::registry::find_nif(TABLE_TEST_NIFS, "missing").is_none());
+
+        // The exported resolver reads the same table
+        let resolved = table_test_get_nif(b"string_op\0".as_ptr());
+        assert_eq!(resolved, test_string_nif as *const () as *const core::ffi::c_void);
+        assert!(table_test_get_nif(b"nope\0".as_ptr()).is_null());
+    }
+
+    #[test]
+    fn test_sorted_resolver() {
+        use crate::registry::{find_nif, find_nif_sorted, sorted_index, NifEntry};
+        const TABLE: &[NifEntry] = &[
+            NifEntry::new("write", 2, core::ptr::null()),
+            NifEntry::new("close", 1, core::ptr::null()),
+            NifEntry::new("read", 1, core::ptr::null()),
+            NifEntry::new("read", 2, core::ptr::null()),
+            NifEntry::new("open", 2, core::ptr::null()),
+            NifEntry::new("re", 0, core::ptr::null()),
+        ];
+        const INDEX: [usize; TABLE.len()] = sorted_index(TABLE);
+        assert_eq!(INDEX, [1, 4, 5, 2, 3, 0]);
+        for name in ["write", "close", "read", "open", "re", "r", "reads", "", "zzz"] {
+            assert_eq!(find_nif_sorted(TABLE, &INDEX, name), find_nif(TABLE, name), "{}", name);
+        }
+        assert_eq!(find_nif_sorted(TABLE, &INDEX, "read").unwrap().arity, 1);
+
+        assert_eq!(TABLE_TEST_NIF_INDEX, [0, 2, 1]);
+        assert!(find_nif_sorted(&[], &[], "add").is_none());
+    }
+
+    #[test]
+    fn test_resolve_by_arity() {
+        let function = |f: extern "C" fn(*mut Context, i32, *const Term) -> Term| f as *const () as *const core::ffi::c_void;
+        assert_eq!(overload_test_get_nif(b"op/1\0".as_ptr()), function(test_string_nif));
+        assert_eq!(overload_test_get_nif(b"op/2\0".as_ptr()), function(test_add_nif));
+        assert_eq!(overload_test_get_nif(b"sensor:op/2\0".as_ptr()), function(test_add_nif));
+        // AtomVM asks every collection, so another module's `op/2` is not ours
+        assert!(overload_test_get_nif(b"other:op/2\0".as_ptr()).is_null());
+        assert_eq!(overload_test_get_nif_flags(b"other:list_op/1\0".as_ptr()), 0);
+        assert!(overload_test_get_nif(b"sensor_ext:op/2\0".as_ptr()).is_null());
+        // Without `module = ...` any prefix is accepted
+        assert_eq!(OVERLOAD_TEST_MODULE, Some("sensor"));
+        assert_eq!(TABLE_TEST_MODULE, None);
+        assert!(!table_test_get_nif(b"other:add/2\0".as_ptr()).is_null());
+        assert!(overload_test_get_nif(b"op/3\0".as_ptr()).is_null());
+        assert!(overload_test_get_nif(b"op/x\0".as_ptr()).is_null());
+        // A bare name gets the first listed
+        assert_eq!(overload_test_get_nif(b"op\0".as_ptr()), function(test_string_nif));
+        assert_eq!(overload_test_get_nif_flags(b"sensor:list_op/1\0".as_ptr()), crate::registry::NifFlags::DirtyIo as u32);
+        assert_eq!(overload_test_get_nif_flags(b"sensor:list_op/2\0".as_ptr()), 0);
+    }
+
+    #[test]
+    fn test_nif_collection_info() {
+        // Without `info = ...` there is no __info__ entry
+        assert!(crate::registry::find_nif(TABLE_TEST_NIFS, "__info__").is_none());
+
+        let table = MockAtomTable::new();
+        let info = crate::registry::collection_info("table_test", TABLE_TEST_NIFS, "1.2.0", &table);
+        let expected = crate::parse::parse_term(
+            "#{name => table_test, nifs => [{add,2},{string_op,1},{list_op,1}], version => <<\"1.2.0\">>}",
+            &table,
+        );
+        assert_eq!(Ok(info), expected);
+    }
+
+    #[test]
+    fn test_nif_collection_flags() {
+        use crate::registry::NifFlags;
+
+        let flags: Vec<NifFlags> = TABLE_TEST_NIFS.iter().map(|entry| entry.flags).collect();
+        assert_eq!(flags, vec![NifFlags::Normal, NifFlags::DirtyCpu, NifFlags::DirtyIo]);
+
+        assert_eq!(table_test_get_nif_flags(b"add\0".as_ptr()), 0);
+        assert_eq!(table_test_get_nif_flags(b"string_op\0".as_ptr()), 1);
+        assert_eq!(table_test_get_nif_flags(b"list_op\0".as_ptr()), 2);
+        assert_eq!(table_test_get_nif_flags(b"nope\0".as_ptr()), 0);
+
+        // The registration record keeps AtomVM's four callbacks and the flags resolver
+        let def = &TABLE_TEST_NIF_COLLECTION;
+        let name = unsafe { core::ffi::CStr::from_ptr(def.name) };
+        assert_eq!(name.to_str(), Ok("table_test"));
+        assert_eq!((def.get_nif)(b"string_op/1\0".as_ptr()), TABLE_TEST_NIFS[1].function);
+        assert_eq!((def.get_nif_flags)(b"list_op\0".as_ptr()), crate::registry::NifFlags::DirtyIo as u32);
+        assert_eq!(core::mem::size_of::<crate::registry::NifCollectionDef>(), 5 * core::mem::size_of::<usize>());
+    }
+
     #[test]
     fn test_nif_collection_function_list() {
         // Test the functions that would be registered by our test collection
@@ -396,4 +517,111 @@ mod tests {
         #[cfg(not(any(target_os = "macos", target_os = "ios")))]
         assert_eq!(expected_section, ".nif_collection");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_nif_return_conversions() {
+        use crate::term::{ExceptionClass, NifReturn};
+
+        assert_eq!(NifReturn::from(NifError::BadArg), NifReturn::Badarg);
+        assert_eq!(
+            NifReturn::from(NifError::OutOfMemory),
+            NifReturn::Error(NifError::OutOfMemory)
+        );
+        assert_eq!(
+            NifReturn::from(Ok::<_, NifError>(TermValue::int(3))),
+            NifReturn::Value(TermValue::int(3))
+        );
+        assert_eq!(
+            NifReturn::from(Err::<Term, _>(NifError::BadArg)),
+            NifReturn::Badarg
+        );
+        assert_eq!(NifReturn::from(Term::from_raw(0x3B)), NifReturn::Term(Term::from_raw(0x3B)));
+
+        assert_eq!(ExceptionClass::Throw as i32, 1);
+        assert_eq!(ExceptionClass::Exit.as_str(), "exit");
+        assert_eq!(Term::INVALID.raw(), 0);
+    }
+
+    #[test]
+    fn test_init_guard() {
+        let guard = crate::registry::InitGuard::new();
+        assert!(guard.begin());
+        // Claimed but not finished: a concurrent caller is turned away too
+        assert!(!guard.begin());
+        guard.finish(true);
+        assert!(guard.is_initialized());
+        assert!(!guard.begin());
+
+        guard.reset();
+        assert!(!guard.is_initialized());
+        assert!(guard.begin());
+
+        // A failed init can be retried
+        guard.finish(false);
+        assert!(!guard.is_initialized());
+        assert!(guard.begin());
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct Calibration {
+        offset: i32,
+        upgrades: u32,
+    }
+
+    static UNLOADS: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+
+    fn lifecycle_test_init(ctx: &mut crate::Context) {
+        LIFECYCLE_TEST_PRIV.set(context_global(ctx), Calibration { offset: 3, upgrades: 0 });
+    }
+
+    fn lifecycle_test_upgrade(ctx: &mut crate::Context) {
+        LIFECYCLE_TEST_PRIV.with(context_global(ctx), |cal| cal.upgrades += 1);
+    }
+
+    fn lifecycle_test_unload(_global: &mut crate::context::GlobalContext) {
+        UNLOADS.fetch_add(1, core::sync::atomic::Ordering::SeqCst);
+    }
+
+    crate::nif_collection!(
+        lifecycle_test,
+        init = lifecycle_test_init,
+        upgrade = lifecycle_test_upgrade,
+        unload = lifecycle_test_unload,
+        priv_data = Calibration,
+        nifs = [("add", 2, test_add_nif)]
+    );
+
+    #[test]
+    fn test_nif_collection_lifecycle_and_priv_data() {
+        let vm = MockGlobalContext::new();
+        let mut process = vm.new_context();
+        let ctx = process.as_context() as *mut crate::Context;
+        let global = vm.as_global() as *const _ as *mut crate::context::GlobalContext;
+
+        assert!(!LIFECYCLE_TEST_PRIV.is_set(global));
+        lifecycle_test_nif_init(ctx);
+        // A repeated init is a reload: it runs `upgrade`, not the user's init
+        lifecycle_test_nif_init(ctx);
+        lifecycle_test_nif_init(ctx);
+        assert_eq!(LIFECYCLE_TEST_PRIV.with(global, |cal| cal.upgrades), Some(2));
+        assert_eq!(LIFECYCLE_TEST_PRIV.with(global, |cal| cal.offset), Some(3));
+
+        // The data is kept on the VM, not in the collection
+        let other_vm = MockGlobalContext::new();
+        let other = other_vm.as_global() as *const _ as *mut crate::context::GlobalContext;
+        assert!(!LIFECYCLE_TEST_PRIV.is_set(other));
+        assert_eq!(vm.nif_priv.borrow()[0].0, "lifecycle_test");
+
+        lifecycle_test_nif_destroy(global);
+        assert_eq!(UNLOADS.load(core::sync::atomic::Ordering::SeqCst), 1);
+        assert!(!LIFECYCLE_TEST_PRIV.is_set(global));
+
+        // Destroy re-arms init
+        lifecycle_test_nif_init(ctx);
+        assert_eq!(LIFECYCLE_TEST_PRIV.with(global, |cal| cal.upgrades), Some(0));
+        lifecycle_test_nif_destroy(global);
+
+        // Without callbacks the destroy function does nothing
+        table_test_nif_destroy(core::ptr::null_mut());
+    }
+}
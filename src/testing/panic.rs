@@ -0,0 +1,47 @@
+//! Panic scope and reason testing suite
+
+use crate::atom::AtomTableOps;
+use crate::panic::{current_site, enter_nif, enter_port, panic_reason, PanicSite};
+use crate::term::TermValue;
+use crate::testing::mocks::MockAtomTable;
+use alloc::vec;
+use core::ffi::c_void;
+
+fn fake_ctx<T>(address: usize) -> *mut T {
+    address as *mut T
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One test for the whole scope stack: the tracked call is global state
+    #[test]
+    fn test_scopes_nest_and_restore() {
+        assert_eq!(current_site(), (PanicSite::Other, core::ptr::null_mut()));
+        {
+            let _nif = enter_nif(fake_ctx(0x1000));
+            assert_eq!(current_site(), (PanicSite::Nif, fake_ctx::<c_void>(0x1000)));
+            {
+                let _port = enter_port(fake_ctx(0x2000));
+                assert_eq!(current_site(), (PanicSite::Port, fake_ctx::<c_void>(0x2000)));
+            }
+            assert_eq!(current_site(), (PanicSite::Nif, fake_ctx::<c_void>(0x1000)));
+        }
+        assert_eq!(current_site(), (PanicSite::Other, core::ptr::null_mut()));
+    }
+
+    #[test]
+    fn test_panic_reason() {
+        let table = MockAtomTable::new();
+        let reason = panic_reason("index out of bounds", &table);
+        let nif_panic = table.ensure_atom_str("nif_panic").unwrap();
+        assert_eq!(
+            reason,
+            TermValue::tuple(vec![
+                TermValue::Atom(nif_panic),
+                TermValue::binary(b"index out of bounds".to_vec()),
+            ])
+        );
+    }
+}
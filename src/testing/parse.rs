@@ -0,0 +1,99 @@
+//! Erlang syntax parsing testing suite
+
+use crate::parse::{parse_term, ParseError, ParseReason, MAX_DEPTH};
+use crate::term::{PortId, ProcessId, RefId, TermValue};
+use crate::testing::helpers::*;
+use crate::testing::mocks::MockAtomTable;
+use alloc::string::{String, ToString};
+use alloc::boxed::Box;
+use alloc::vec;
+
+fn parse(text: &str, table: &MockAtomTable) -> TermValue {
+    parse_term(text, table).unwrap_or_else(|e| panic!("{}: {}", text, e))
+}
+
+fn reason(text: &str) -> ParseReason {
+    parse_term(text, &MockAtomTable::new()).unwrap_err().reason
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_with_binary() {
+        let table = MockAtomTable::new();
+        let expected = TermValue::map(vec![
+            (atom("name", &table), TermValue::binary(b"x".to_vec())),
+            (atom("id", &table), TermValue::int(3)),
+        ]);
+        assert_eq!(parse("#{name => <<\"x\">>, id => 3}", &table), expected);
+        assert_eq!(parse("  #{ name=><<\"x\">> ,id=>3 } .\n", &table), expected);
+    }
+
+    #[test]
+    fn test_numbers() {
+        let table = MockAtomTable::new();
+        assert_eq!(parse("-42", &table), TermValue::int(-42));
+        assert_eq!(parse("16#ff", &table), TermValue::int(255));
+        assert_eq!(parse("-2#101", &table), TermValue::int(-5));
+        assert_eq!(parse("1_000", &table), TermValue::int(1000));
+        assert_eq!(parse("$a", &table), TermValue::int(97));
+        assert_eq!(parse("$\\n", &table), TermValue::int(10));
+        assert_eq!(parse("1.5e3", &table), TermValue::Float(1500.0));
+        assert_eq!(parse("-0.25", &table), TermValue::Float(-0.25));
+        assert_eq!(parse("-2147483648", &table), TermValue::int(i32::MIN));
+        assert_eq!(reason("2147483648"), ParseReason::IntegerOverflow);
+    }
+
+    #[test]
+    fn test_atoms_strings_and_lists() {
+        let table = MockAtomTable::new();
+        assert_eq!(parse("'Hello world'", &table), atom("Hello world", &table));
+        assert_eq!(parse("'it\\'s'", &table), atom("it's", &table));
+        assert_eq!(parse("node@host", &table), atom("node@host", &table));
+        assert_eq!(parse("\"hi\" \"\\x41\"", &table), int_list(&[104, 105, 65]));
+        assert_eq!(parse("\"\"", &table), TermValue::Nil);
+        assert_eq!(parse("[1, 2 | 3]", &table), TermValue::List(
+            Box::new(TermValue::int(1)),
+            Box::new(TermValue::List(Box::new(TermValue::int(2)), Box::new(TermValue::int(3)))),
+        ));
+        assert_eq!(parse("<<\"ab\", 0, 255>>", &table), TermValue::binary(vec![b'a', b'b', 0, 255]));
+        assert_eq!(parse("<<>>", &table), TermValue::binary(vec![]));
+        assert_eq!(parse("{} % empty\n", &table), TermValue::tuple(vec![]));
+    }
+
+    #[test]
+    fn test_printed_terms_read_back() {
+        let table = MockAtomTable::new();
+        let value = TermValue::tuple(vec![
+            atom("ok", &table),
+            atom("Quoted atom", &table),
+            TermValue::list(vec![TermValue::int(1), TermValue::Float(1.0e20), TermValue::binary(vec![1, 2])]),
+            TermValue::map(vec![(TermValue::binary(b"key".to_vec()), int_list(&[104, 105]))]),
+            TermValue::Pid(ProcessId(7)),
+            TermValue::Port(PortId(3)),
+            TermValue::Reference(RefId(99)),
+            parse("fun lists:map/2", &table),
+        ]);
+        let text = value.display(&table).to_string();
+        assert_eq!(parse(&text, &table), value);
+    }
+
+    #[test]
+    fn test_errors_have_offsets() {
+        let table = MockAtomTable::new();
+        assert_eq!(
+            parse_term("{ok, }", &table),
+            Err(ParseError { offset: 5, reason: ParseReason::Unexpected('}') })
+        );
+        assert_eq!(reason("[1, 2"), ParseReason::UnexpectedEnd);
+        assert_eq!(reason("ok ok"), ParseReason::Unexpected('o'));
+        assert_eq!(reason("<<256>>"), ParseReason::IntegerOverflow);
+        assert_eq!(reason("\"\\x{zz}\""), ParseReason::BadEscape);
+        assert_eq!(reason("Var"), ParseReason::Unexpected('V'));
+
+        let deep: String = "[".repeat(MAX_DEPTH + 2) + &"]".repeat(MAX_DEPTH + 2);
+        assert_eq!(reason(&deep), ParseReason::TooDeep);
+    }
+}
@@ -0,0 +1,114 @@
+//! Port command policy testing suite
+
+use crate::atom::{AtomIndex, AtomTableOps};
+use crate::port::behavior::{BehaviorPort, CallFrom, Incoming, PortBehavior};
+use crate::port::policy::{self, command_name, AllowAll, CommandPolicy, RestrictedCommands};
+use crate::port::{PortError, PortResult};
+use crate::term::{RefId, TermValue};
+use crate::testing::helpers::*;
+use crate::testing::mocks::MockAtomTable;
+use alloc::vec;
+
+const UPDATER: u32 = 3;
+
+/// Flash port whose `erase` is reserved for the updater
+struct FlashPort {
+    policy: RestrictedCommands,
+    erased: u32,
+}
+
+impl PortBehavior for FlashPort {
+    fn init<T: AtomTableOps>(_opts: &TermValue, table: &T) -> Result<Self, PortError> {
+        let erase = table.ensure_atom_str("erase").map_err(|_| PortError::Generic)?;
+        Ok(Self {
+            policy: RestrictedCommands::new().restrict(erase, &[UPDATER]),
+            erased: 0,
+        })
+    }
+
+    fn handle_call<T: AtomTableOps>(&mut self, _request: &TermValue, _from: &CallFrom, table: &T) -> PortResult {
+        self.erased += 1;
+        PortResult::Reply(TermValue::atom("ok", table))
+    }
+
+    fn allow_call(&self, caller: u32, command: AtomIndex) -> bool {
+        self.policy.allow(caller, command)
+    }
+}
+
+fn call_from(pid: u32, request: TermValue) -> Incoming {
+    Incoming::Call {
+        request,
+        from: CallFrom { pid, reference: TermValue::Reference(RefId(1)) },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_name() {
+        let table = MockAtomTable::new();
+        let erase = table.ensure_atom_str("erase").unwrap();
+
+        assert_eq!(command_name(&atom("erase", &table)), Some(erase));
+        assert_eq!(command_name(&TermValue::tuple(vec![atom("erase", &table), TermValue::int(4)])), Some(erase));
+        assert_eq!(command_name(&TermValue::int(4)), None);
+        assert_eq!(command_name(&TermValue::tuple(vec![])), None);
+    }
+
+    #[test]
+    fn test_restricted_commands() {
+        let table = MockAtomTable::new();
+        let erase = table.ensure_atom_str("erase").unwrap();
+        let read = table.ensure_atom_str("read").unwrap();
+        let lock = table.ensure_atom_str("lock").unwrap();
+
+        let mut restricted = RestrictedCommands::new().restrict(erase, &[UPDATER]).restrict(lock, &[]);
+        assert!(restricted.allow(UPDATER, erase));
+        assert!(!restricted.allow(9, erase));
+        assert!(restricted.allow(9, read));
+        assert!(!restricted.allow(UPDATER, lock));
+        assert!(restricted.is_restricted(lock) && !restricted.is_restricted(read));
+
+        restricted.grant(erase, 9);
+        assert!(restricted.allow(9, erase));
+        restricted.revoke(9);
+        assert!(!restricted.allow(9, erase));
+        // Granting an unrestricted command does not restrict it
+        restricted.grant(read, 9);
+        assert!(restricted.allow(10, read));
+    }
+
+    #[test]
+    fn test_authorize() {
+        let table = MockAtomTable::new();
+        let erase = TermValue::tuple(vec![atom("erase", &table), TermValue::int(0)]);
+        let only_updater = |caller: u32, _name: AtomIndex| caller == UPDATER;
+
+        assert!(policy::authorize(&only_updater, UPDATER, &erase).is_ok());
+        assert!(matches!(policy::authorize(&only_updater, 9, &erase), Err(PortError::NotAllowed)));
+        // Unnamed commands are not checked
+        assert!(policy::authorize(&only_updater, 9, &TermValue::int(1)).is_ok());
+        assert!(policy::authorize(&AllowAll, 9, &erase).is_ok());
+        assert_eq!(PortError::NotAllowed.reason(), "not_allowed");
+    }
+
+    #[test]
+    fn test_behavior_rejects_before_dispatch() {
+        let table = MockAtomTable::new();
+        let mut port = BehaviorPort::new(FlashPort::init(&TermValue::Nil, &table).unwrap());
+        let erase = TermValue::tuple(vec![atom("erase", &table), TermValue::int(2)]);
+
+        match port.dispatch(call_from(9, erase.clone()), &table) {
+            PortResult::ReplyError(reason) => assert_atom_str(&reason, "not_allowed", &table),
+            other => panic!("expected rejection, got {:?}", other),
+        }
+        assert_eq!(port.state().erased, 0);
+
+        assert!(matches!(port.dispatch(call_from(UPDATER, erase), &table), PortResult::Reply(_)));
+        assert!(matches!(port.dispatch(call_from(9, atom("status", &table)), &table), PortResult::Reply(_)));
+        assert_eq!(port.state().erased, 2);
+    }
+}
@@ -0,0 +1,151 @@
+//! Polling port testing suite
+
+use crate::port::behavior::{CallFrom, Incoming};
+use crate::port::polling::PollingPort;
+use crate::port::PortResult;
+use crate::term::TermValue;
+use crate::testing::mocks::{MockAtomTable, MockProcessMonitor, MockTimerBackend};
+use alloc::vec;
+
+/// Counts up on every poll; every third reading is missing
+fn counter_poll(count: &mut i32) -> Option<TermValue> {
+    *count += 1;
+    (*count % 3 != 0).then(|| TermValue::int(*count))
+}
+
+fn counter_port() -> PollingPort<i32, usize> {
+    PollingPort::new(0, 100, counter_poll)
+}
+
+fn call(request: TermValue, pid: u32) -> Incoming {
+    Incoming::Call {
+        request,
+        from: CallFrom {
+            pid,
+            reference: TermValue::reference(1),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_runs_only_with_subscribers() {
+        let table = MockAtomTable::new();
+        let mut backend = MockTimerBackend::new();
+        let mut monitor = MockProcessMonitor::with_alive(&[7, 8]);
+        let mut port = counter_port();
+        assert!(!port.is_polling());
+
+        let subscribe = || TermValue::atom("subscribe", &table);
+        assert_eq!(
+            port.dispatch(call(subscribe(), 7), &mut backend, &mut monitor, &table),
+            PortResult::Reply(TermValue::atom("ok", &table))
+        );
+        port.dispatch(call(subscribe(), 8), &mut backend, &mut monitor, &table);
+        assert!(port.is_polling());
+        assert_eq!(backend.armed, Some(100));
+        assert_eq!(port.subscribers().len(), 2);
+
+        let unsubscribe = || TermValue::atom("unsubscribe", &table);
+        port.dispatch(call(unsubscribe(), 7), &mut backend, &mut monitor, &table);
+        assert!(port.is_polling());
+        port.dispatch(call(unsubscribe(), 8), &mut backend, &mut monitor, &table);
+        assert!(!port.is_polling());
+        assert_eq!(backend.armed, None);
+    }
+
+    #[test]
+    fn test_dead_subscriber_is_refused() {
+        let table = MockAtomTable::new();
+        let mut backend = MockTimerBackend::new();
+        let mut monitor = MockProcessMonitor::default();
+        let mut port = counter_port();
+        let result = port.dispatch(call(TermValue::atom("subscribe", &table), 9), &mut backend, &mut monitor, &table);
+        assert_eq!(result, PortResult::ReplyError(TermValue::atom("noproc", &table)));
+        assert!(!port.is_polling());
+    }
+
+    #[test]
+    fn test_wakeup_polls_when_due() {
+        let table = MockAtomTable::new();
+        let mut backend = MockTimerBackend::new();
+        let mut monitor = MockProcessMonitor::with_alive(&[7]);
+        let mut port = counter_port();
+        port.subscribe(&mut backend, &mut monitor, 7).unwrap();
+
+        // Early wakeup: nothing due
+        backend.advance(50);
+        assert_eq!(port.on_wakeup(&mut backend, &table), None);
+        assert_eq!(*port.state(), 0);
+
+        backend.advance(50);
+        assert_eq!(
+            port.on_wakeup(&mut backend, &table),
+            Some(TermValue::tuple(vec![TermValue::atom("reading", &table), TermValue::int(1)]))
+        );
+        backend.advance(100);
+        assert!(port.on_wakeup(&mut backend, &table).is_some());
+
+        // A missing reading sends nothing
+        backend.advance(100);
+        assert_eq!(port.on_wakeup(&mut backend, &table), None);
+        assert_eq!(*port.state(), 3);
+    }
+
+    #[test]
+    fn test_read_and_interval_calls() {
+        let table = MockAtomTable::new();
+        let mut backend = MockTimerBackend::new();
+        let mut monitor = MockProcessMonitor::with_alive(&[7]);
+        let mut port = counter_port();
+
+        let read = || call(TermValue::atom("read", &table), 7);
+        assert_eq!(
+            port.dispatch(read(), &mut backend, &mut monitor, &table),
+            PortResult::Reply(TermValue::tuple(vec![TermValue::atom("ok", &table), TermValue::int(1)]))
+        );
+        port.dispatch(read(), &mut backend, &mut monitor, &table);
+        assert_eq!(
+            port.dispatch(read(), &mut backend, &mut monitor, &table),
+            PortResult::ReplyError(TermValue::atom("no_reading", &table))
+        );
+
+        port.subscribe(&mut backend, &mut monitor, 7).unwrap();
+        let interval = TermValue::tuple(vec![TermValue::atom("interval", &table), TermValue::int(250)]);
+        assert_eq!(
+            port.dispatch(call(interval, 7), &mut backend, &mut monitor, &table),
+            PortResult::Reply(TermValue::atom("ok", &table))
+        );
+        assert_eq!(port.interval_ms(), 250);
+        assert_eq!(backend.armed, Some(250));
+
+        let bad = TermValue::tuple(vec![TermValue::atom("interval", &table), TermValue::int(0)]);
+        assert_eq!(
+            port.dispatch(call(bad, 7), &mut backend, &mut monitor, &table),
+            PortResult::ReplyError(TermValue::atom("badarg", &table))
+        );
+    }
+
+    #[test]
+    fn test_down_subscriber_stops_timer() {
+        let table = MockAtomTable::new();
+        let mut backend = MockTimerBackend::new();
+        let mut monitor = MockProcessMonitor::with_alive(&[7]);
+        let mut port = counter_port();
+        port.subscribe(&mut backend, &mut monitor, 7).unwrap();
+
+        let down = TermValue::tuple(vec![
+            TermValue::atom("DOWN", &table),
+            TermValue::reference(1),
+            TermValue::atom("process", &table),
+            TermValue::pid(7),
+            TermValue::atom("normal", &table),
+        ]);
+        assert_eq!(port.dispatch(Incoming::Info(down), &mut backend, &mut monitor, &table), PortResult::Continue);
+        assert!(port.subscribers().is_empty());
+        assert!(!port.is_polling());
+    }
+}
@@ -403,6 +403,235 @@ mod tests {
         assert_eq!(port_data.message_count(), 0);
         assert!(port_data.last_command.is_some());
     }
+
+    #[test]
+    fn test_port_result_replies() {
+        use crate::port::{NativePortResult, PortResult};
+
+        let table = MockAtomTable::new();
+        let reason = TermValue::atom("busy", &table);
+        let error_reply = TermValue::tuple(vec![TermValue::atom("error", &table), reason.clone()]);
+
+        assert_eq!(PortResult::Continue.reply_term(&table).unwrap(), None);
+        assert_eq!(PortResult::Terminate.reply_term(&table).unwrap(), None);
+        assert_eq!(
+            PortResult::Reply(TermValue::int(1)).reply_term(&table).unwrap(),
+            Some(TermValue::int(1))
+        );
+        assert_eq!(
+            PortResult::ReplyError(reason.clone()).reply_term(&table).unwrap(),
+            Some(error_reply.clone())
+        );
+        assert_eq!(
+            PortResult::TerminateWithReason(reason.clone()).reply_term(&table).unwrap(),
+            Some(error_reply)
+        );
+
+        assert_eq!(PortResult::Reply(TermValue::Nil).to_native(), NativePortResult::Continue);
+        assert_eq!(PortResult::ReplyError(TermValue::Nil).to_native(), NativePortResult::Continue);
+        assert_eq!(PortResult::TerminateWithReason(reason).to_native(), NativePortResult::Terminate);
+        assert!(PortResult::Terminate.is_terminate());
+    }
+
+    #[test]
+    fn test_port_connect_transfers_owner() {
+        use crate::context::PlatformData;
+        use crate::port::{match_connect, GenericPortData, PortData, PortError};
+
+        #[derive(Default)]
+        struct Owned {
+            owner: Option<u32>,
+            handovers: Vec<(Option<u32>, u32)>,
+        }
+        impl PlatformData for Owned {}
+        impl PortData for Owned {
+            fn set_owner_pid(&mut self, pid: u32) {
+                self.owner = Some(pid);
+            }
+            fn owner_changed(&mut self, old_owner: Option<u32>, new_owner: u32) {
+                self.handovers.push((old_owner, new_owner));
+            }
+        }
+
+        let mut data = GenericPortData::new(Owned::default());
+        assert!(matches!(data.connect(7), Err(PortError::PortInactive)));
+
+        data.set_owner(7);
+        assert!(matches!(data.connect(0), Err(PortError::InvalidMessage)));
+        assert_eq!(data.connect(9).unwrap(), Some(7));
+        assert_eq!(data.get_owner_pid(), Some(9));
+        assert_eq!(data.inner.owner, Some(9));
+        assert_eq!(data.inner.handovers, vec![(Some(7), 9)]);
+
+        let table = MockAtomTable::new();
+        let connect = TermValue::tuple(vec![
+            TermValue::atom("connect", &table),
+            TermValue::Pid(ProcessId(42)),
+        ]);
+        assert_eq!(match_connect(&connect, &table), Some(42));
+        assert_eq!(match_connect(&TermValue::atom("connect", &table), &table), None);
+        assert_eq!(PortError::NotOwner.reason(), "not_owner");
+    }
+
+    #[test]
+    fn test_owner_down_deactivates_port() {
+        use crate::context::PlatformData;
+        use crate::port::{GenericPortData, PortData};
+
+        #[derive(Default)]
+        struct Sensor {
+            cleanups: u32,
+        }
+        impl PlatformData for Sensor {
+            fn cleanup(&mut self) {
+                self.cleanups += 1;
+            }
+        }
+        impl PortData for Sensor {}
+
+        let mut data = GenericPortData::new(Sensor::default());
+        data.set_owner(7);
+
+        // Some other process dying leaves the port running
+        assert!(!data.owner_down(8));
+        assert!(data.is_active());
+
+        assert!(data.owner_down(7));
+        assert!(!data.is_active());
+        assert_eq!(data.inner.cleanups, 1);
+
+        // A late duplicate DOWN does not clean up twice
+        assert!(!data.owner_down(7));
+        assert_eq!(data.inner.cleanups, 1);
+    }
+
+    #[test]
+    fn test_context_ext_on_mock_context() {
+        use crate::context::{is_port_alive, ContextExt};
+        use alloc::boxed::Box;
+
+        let global = MockGlobalContext::new();
+        let mut port = global.new_context();
+        let ctx = port.as_context();
+        assert!(!ctx.has_platform_data());
+        assert!(!ctx.has_user_data());
+
+        unsafe {
+            ctx.set_platform_data_box(Box::new(41u32));
+            *ctx.get_platform_data_as::<u32>() += 1;
+            ctx.set_user_data(0xfeed);
+            assert_eq!(ctx.get_user_data(), 0xfeed);
+            assert_eq!(ctx.take_platform_data_box::<u32>(), Some(Box::new(42)));
+        }
+        assert!(!ctx.has_platform_data());
+        assert!(is_port_alive(ctx));
+        port.set_alive(false);
+        assert!(!is_port_alive(port.as_context()));
+    }
+
+    #[test]
+    fn test_port_builder_on_mock_global() {
+        use crate::context::{destroy_port_context_safe, get_global_context, ContextExt, PortBuilder};
+
+        let global = MockGlobalContext::new();
+        assert_eq!(get_global_context() as *const _, global.as_global() as *const _);
+
+        let first = PortBuilder::new(String::from("uart")).build_with_user_data(global.as_global(), 9);
+        let second = PortBuilder::new(0u8).build(global.as_global());
+        unsafe {
+            assert_eq!(*(*first).get_platform_data_as::<String>(), "uart");
+            assert_eq!((*first).get_user_data(), 9);
+            assert_ne!(MockContext::from_raw(first).id(), MockContext::from_raw(second).id());
+            drop((*first).take_platform_data_box::<String>());
+            drop((*second).take_platform_data_box::<u8>());
+        }
+        destroy_port_context_safe(first);
+        destroy_port_context_safe(second);
+    }
+
+    #[test]
+    fn test_handle_standard_message_on_mock_context() {
+        use crate::context::PortBuilder;
+        use crate::port::{handle_standard_message, GenericPortData, PortData, PortResult};
+        use crate::term::RefId;
+
+        #[derive(Default)]
+        struct Sensor;
+        impl crate::context::PlatformData for Sensor {}
+        impl PortData for Sensor {}
+
+        let global = MockGlobalContext::new();
+        let table = global.atoms();
+        let ctx = PortBuilder::new(GenericPortData::new(Sensor)).build(global.as_global());
+        let port = unsafe { MockContext::from_raw(ctx) };
+        let call = |command: &str, reference: u64| {
+            MockMessage::call(&global, ProcessId(5), RefId(reference), TermValue::atom(command, table)).unwrap()
+        };
+        let ok = |state: &str| TermValue::tuple(vec![TermValue::atom("ok", table), TermValue::atom(state, table)]);
+
+        let status = call("status", 1);
+        assert_eq!(handle_standard_message::<Sensor>(port.as_context(), status.as_message()), PortResult::Continue);
+        let start = call("start", 2);
+        assert_eq!(handle_standard_message::<Sensor>(port.as_context(), start.as_message()), PortResult::Continue);
+        assert_eq!(port.monitors(), &[5]);
+
+        let stop = call("stop", 3);
+        assert_eq!(handle_standard_message::<Sensor>(port.as_context(), stop.as_message()), PortResult::Terminate);
+
+        let replies: Vec<_> = port.replies().iter().map(|sent| sent.reply.clone()).collect();
+        assert_eq!(replies, vec![ok("inactive"), ok("active"), ok("inactive")]);
+        assert_eq!(port.replies()[2].pid, TermValue::Pid(ProcessId(5)));
+        assert_eq!(port.replies()[2].reference, TermValue::Reference(RefId(3)));
+
+        // Anything but a call stops the port without replying
+        let down = MockMessage::info(TermValue::atom("timeout", table)).unwrap();
+        assert_eq!(handle_standard_message::<Sensor>(port.as_context(), down.as_message()), PortResult::Terminate);
+        assert_eq!(port.replies().len(), 3);
+
+        unsafe {
+            drop(crate::context::ContextExt::take_platform_data_box::<GenericPortData<Sensor>>(port.as_context()));
+        }
+        crate::context::destroy_port_context_safe(ctx);
+    }
+
+    #[test]
+    fn test_replies_reach_mock_process() {
+        use crate::port::{complete_port_result_with, parse_gen_message, MessageBackend, NativePortResult, PortResult};
+        use crate::term::RefId;
+
+        let global = MockGlobalContext::new();
+        let table = global.atoms();
+        let mut caller = MockProcess::new(5);
+        let pid = caller.pid();
+        let call = |reference: u64| {
+            MockMessage::call(&global, pid, RefId(reference), TermValue::atom("read", table)).unwrap()
+        };
+
+        let first = call(1);
+        let result = complete_port_result_with(&mut caller, first.as_message(), PortResult::Reply(TermValue::int(42)), table);
+        assert_eq!(result, NativePortResult::Continue);
+        let second = call(2);
+        let failed = PortResult::TerminateWithReason(TermValue::atom("busy", table));
+        let result = complete_port_result_with(&mut caller, second.as_message(), failed, table);
+        assert_eq!(result, NativePortResult::Terminate);
+
+        let reference = |id| TermValue::Reference(RefId(id));
+        let error = TermValue::tuple(vec![TermValue::atom("error", table), TermValue::atom("busy", table)]);
+        assert_eq!(caller.receive(), Some(TermValue::tuple(vec![reference(1), TermValue::int(42)])));
+        assert_eq!(caller.receive(), Some(TermValue::tuple(vec![reference(2), error])));
+        assert!(caller.is_empty());
+
+        // Plain messages, selective receive, and sends to other processes
+        caller.send(5, &TermValue::atom("tick", table)).unwrap();
+        caller.send(5, &TermValue::int(7)).unwrap();
+        let (pid, tag, _) = parse_gen_message(first.as_message()).unwrap();
+        caller.send_term(5, pid);
+        assert!(caller.send(6, &TermValue::int(0)).is_err());
+        assert!(caller.reply(tag, tag, &TermValue::Nil).is_err());
+        assert_eq!(caller.receive_matching(|message| message.as_int().is_some()), Some(TermValue::int(7)));
+        assert_eq!(caller.len(), 2);
+        assert_eq!(caller.mailbox().last(), Some(&TermValue::Pid(ProcessId(5))));
+    }
 }
 
 // Add helper method to TermValue for PID extraction
@@ -0,0 +1,83 @@
+//! Erlang syntax printing testing suite
+
+use crate::atom::{AtomIndex, AtomTableOps};
+use crate::term::{DecodeError, DecodeReason, FunctionRef, PortId, ProcessId, RefId, TermValue};
+use crate::testing::helpers::*;
+use crate::testing::mocks::MockAtomTable;
+use alloc::string::{String, ToString};
+use alloc::boxed::Box;
+use alloc::vec;
+
+fn show(value: &TermValue, table: &MockAtomTable) -> String {
+    value.display(table).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_terms() {
+        let table = MockAtomTable::new();
+        let value = TermValue::tuple(vec![
+            atom("ok", &table),
+            int_list(&[1, 2, 3]),
+            TermValue::map(vec![(atom("a", &table), TermValue::int(1)), (atom("b", &table), TermValue::Nil)]),
+        ]);
+        assert_eq!(show(&value, &table), "{ok,[1,2,3],#{a => 1,b => []}}");
+        assert_eq!(show(&TermValue::tuple(vec![]), &table), "{}");
+        assert_eq!(show(&TermValue::map(vec![]), &table), "#{}");
+    }
+
+    #[test]
+    fn test_atoms_quoted_when_needed() {
+        let table = MockAtomTable::new();
+        assert_eq!(show(&atom("node@host", &table), &table), "node@host");
+        assert_eq!(show(&atom("Upper", &table), &table), "'Upper'");
+        assert_eq!(show(&atom("with space", &table), &table), "'with space'");
+        assert_eq!(show(&atom("it's", &table), &table), "'it\\'s'");
+        assert_eq!(show(&atom("receive", &table), &table), "'receive'");
+        assert_eq!(show(&atom("", &table), &table), "''");
+        assert_eq!(show(&TermValue::Atom(AtomIndex(9999)), &table), "#Atom<9999>");
+    }
+
+    #[test]
+    fn test_strings_and_binaries() {
+        let table = MockAtomTable::new();
+        let text = TermValue::list("hi \"you\"\n".bytes().map(|b| TermValue::int(b as i32)).collect());
+        assert_eq!(show(&text, &table), "\"hi \\\"you\\\"\\n\"");
+        assert_eq!(show(&int_list(&[104, 1000]), &table), "[104,1000]");
+        assert_eq!(show(&TermValue::binary(b"abc".to_vec()), &table), "<<\"abc\">>");
+        assert_eq!(show(&TermValue::binary(vec![0, 255]), &table), "<<0,255>>");
+        assert_eq!(show(&TermValue::binary(vec![]), &table), "<<>>");
+    }
+
+    #[test]
+    fn test_improper_list() {
+        let table = MockAtomTable::new();
+        let list = TermValue::List(Box::new(TermValue::int(1)), Box::new(TermValue::int(2)));
+        assert_eq!(show(&list, &table), "[1|2]");
+    }
+
+    #[test]
+    fn test_numbers_and_identifiers() {
+        let table = MockAtomTable::new();
+        assert_eq!(show(&TermValue::int(-42), &table), "-42");
+        assert_eq!(show(&TermValue::Float(1.0), &table), "1.0");
+        assert_eq!(show(&TermValue::Float(-0.25), &table), "-0.25");
+        assert_eq!(show(&TermValue::Float(1e20), &table), "1.0e20");
+        assert_eq!(show(&TermValue::Pid(ProcessId(7)), &table), "<0.7.0>");
+        assert_eq!(show(&TermValue::Port(PortId(3)), &table), "#Port<0.3>");
+        assert_eq!(show(&TermValue::Reference(RefId(99)), &table), "#Ref<0.0.0.99>");
+
+        let fun = TermValue::Function(FunctionRef {
+            module: table.ensure_atom_str("lists").unwrap(),
+            function: table.ensure_atom_str("map").unwrap(),
+            arity: 2,
+        });
+        assert_eq!(show(&fun, &table), "fun lists:map/2");
+
+        let invalid = TermValue::Invalid(DecodeError { raw: 0x2a, reason: DecodeReason::UnknownTag });
+        assert_eq!(show(&invalid, &table), "#Invalid<0x2a>");
+    }
+}
@@ -0,0 +1,95 @@
+//! Progress reporter testing suite
+
+use crate::progress::{ProgressReporter, ProgressSink};
+use crate::term::{NifError, RefId, TermValue};
+use crate::testing::helpers::*;
+use crate::testing::mocks::MockAtomTable;
+use alloc::vec::Vec;
+
+/// Records messages against a settable clock
+#[derive(Default)]
+struct RecordingSink {
+    now: u64,
+    sent: Vec<(u32, TermValue)>,
+    dead: bool,
+}
+
+impl ProgressSink for RecordingSink {
+    fn now_ms(&self) -> u64 {
+        self.now
+    }
+
+    fn send(&mut self, pid: u32, message: &TermValue) -> Result<(), NifError> {
+        if self.dead {
+            return Err(NifError::BadArg);
+        }
+        self.sent.push((pid, message.clone()));
+        Ok(())
+    }
+}
+
+impl RecordingSink {
+    fn values(&self) -> Vec<TermValue> {
+        self.sent.iter().map(|(_, m)| m.tuple_get(2).unwrap().clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_shape() {
+        let table = MockAtomTable::new();
+        let mut sink = RecordingSink::default();
+        let mut progress = ProgressReporter::new(5, TermValue::Reference(RefId(9)));
+
+        assert!(progress.update_percent(&mut sink, 0, &table).unwrap());
+        let (pid, message) = &sink.sent[0];
+        assert_eq!(*pid, 5);
+        assert_atom_str(message.tuple_get(0).unwrap(), "progress", &table);
+        assert_eq!(message.tuple_get(1), Some(&TermValue::Reference(RefId(9))));
+        assert_eq!(message.tuple_get(2), Some(&TermValue::int(0)));
+    }
+
+    #[test]
+    fn test_percent_updates_are_throttled() {
+        let table = MockAtomTable::new();
+        let mut sink = RecordingSink::default();
+        let mut progress = ProgressReporter::new(5, TermValue::Nil).with_interval(100).with_min_step(10);
+
+        for step in 0..=20 {
+            sink.now = step * 20;
+            progress.update_percent(&mut sink, (step * 4) as usize, &table).unwrap();
+        }
+        // 0 first, then only after 100 ms and 10 points
+        assert_eq!(sink.values(), alloc::vec![TermValue::int(0), TermValue::int(20), TermValue::int(40), TermValue::int(60), TermValue::int(80)]);
+
+        // 100 always goes out, once
+        progress.finish(&mut sink, &table).unwrap();
+        progress.finish(&mut sink, &table).unwrap();
+        assert_eq!(sink.values().last(), Some(&TermValue::int(100)));
+        assert_eq!(sink.sent.len(), 6);
+        assert_eq!(progress.last_percent(), Some(100));
+    }
+
+    #[test]
+    fn test_custom_values_and_failed_sends() {
+        let table = MockAtomTable::new();
+        let mut sink = RecordingSink::default();
+        let mut progress = ProgressReporter::new(5, TermValue::Nil).with_interval(100);
+        let value = |n| TermValue::tuple(alloc::vec![TermValue::int(n), TermValue::int(4096)]);
+
+        assert!(progress.update(&mut sink, value(0), &table).unwrap());
+        sink.now = 50;
+        assert!(!progress.update(&mut sink, value(1024), &table).unwrap());
+        sink.now = 150;
+        assert!(progress.update(&mut sink, value(2048), &table).unwrap());
+
+        // A dead caller is reported and nothing counts as sent
+        sink.dead = true;
+        sink.now = 300;
+        assert_eq!(progress.update_percent(&mut sink, 50, &table), Err(NifError::BadArg));
+        assert_eq!(progress.last_percent(), None);
+    }
+}
@@ -0,0 +1,319 @@
+//! Replay of captured port sessions
+//!
+//! Re-drives a port handler from a capture written by
+//! `port::capture::PortRecorder` and reports every reply that differs from
+//! what the device produced. Available to downstream crates through the
+//! `testing` feature, so driver bugs seen in the field can be reproduced on
+//! the host.
+//!
+//! ```rust,ignore
+//! let table = MockAtomTable::new();
+//! let replayer = PortReplayer::from_capture(&capture_bytes, &table)?;
+//! let report = replayer.run(|command| my_driver.handle(command, &table));
+//! assert!(report.is_clean(), "{:?}", report.mismatches);
+//! ```
+
+use crate::atom::AtomTableOps;
+use crate::port::capture::{parse_capture, CaptureError, CaptureFrame, Direction};
+use crate::term::TermValue;
+use alloc::vec::Vec;
+
+/// A command whose replayed reply differs from the captured one
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayMismatch {
+    /// Zero-based index of the command within the capture
+    pub step: usize,
+    /// Reference of the call
+    pub reference: TermValue,
+    pub command: TermValue,
+    pub expected: Option<TermValue>,
+    pub actual: Option<TermValue>,
+}
+
+/// Outcome of replaying a capture
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayReport {
+    /// Number of commands fed to the handler
+    pub commands: usize,
+    pub mismatches: Vec<ReplayMismatch>,
+}
+
+impl ReplayReport {
+    /// True when every reply matched the capture
+    pub fn is_clean(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Re-drives a handler from a decoded capture
+///
+/// A command's expected reply is the outbound frame after it with the same
+/// call reference, so replies sent out of order or after other traffic are
+/// still matched. A command with no such frame expected no reply.
+pub struct PortReplayer {
+    frames: Vec<CaptureFrame>,
+}
+
+impl PortReplayer {
+    /// Decode a raw capture against `table`
+    pub fn from_capture<T: AtomTableOps>(capture: &[u8], table: &T) -> Result<Self, CaptureError> {
+        Ok(Self::from_frames(parse_capture(capture, table)?))
+    }
+
+    /// Build a replayer from already decoded frames
+    pub fn from_frames(frames: Vec<CaptureFrame>) -> Self {
+        Self { frames }
+    }
+
+    /// All captured frames in order
+    pub fn frames(&self) -> &[CaptureFrame] {
+        &self.frames
+    }
+
+    /// Captured commands in order
+    pub fn commands(&self) -> impl Iterator<Item = &TermValue> {
+        self.frames
+            .iter()
+            .filter(|frame| frame.direction == Direction::Inbound)
+            .map(|frame| &frame.term)
+    }
+
+    /// Feed every captured command to `handler` and compare its replies
+    pub fn run<F>(&self, mut handler: F) -> ReplayReport
+    where
+        F: FnMut(&TermValue) -> Option<TermValue>,
+    {
+        let mut report = ReplayReport {
+            commands: 0,
+            mismatches: Vec::new(),
+        };
+
+        for (i, frame) in self.frames.iter().enumerate() {
+            if frame.direction != Direction::Inbound {
+                continue;
+            }
+
+            let expected = self.frames[i + 1..]
+                .iter()
+                .find(|later| later.direction == Direction::Outbound && later.reference == frame.reference)
+                .map(|reply| reply.term.clone());
+            let actual = handler(&frame.term);
+
+            if actual != expected {
+                report.mismatches.push(ReplayMismatch {
+                    step: report.commands,
+                    reference: frame.reference.clone(),
+                    command: frame.term.clone(),
+                    expected,
+                    actual,
+                });
+            }
+            report.commands += 1;
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::port::capture::PortRecorder;
+    use crate::term::RefId;
+    use crate::testing::mocks::MockAtomTable;
+    use alloc::vec;
+
+    fn reference(id: u64) -> TermValue {
+        TermValue::Reference(RefId(id))
+    }
+
+    /// Tiny driver: `{add, N}` accumulates, `get` replies `{ok, Total}`
+    struct Accumulator {
+        total: i32,
+    }
+
+    impl Accumulator {
+        fn handle<T: AtomTableOps>(&mut self, command: &TermValue, table: &T) -> Option<TermValue> {
+            if command.is_atom_str("get", table) {
+                let ok = TermValue::atom("ok", table);
+                return Some(TermValue::tuple(vec![ok, TermValue::int(self.total)]));
+            }
+            match command.as_tuple() {
+                Some([tag, TermValue::SmallInt(n)]) if tag.is_atom_str("add", table) => {
+                    self.total += n;
+                    None
+                }
+                _ => Some(TermValue::atom("badarg", table)),
+            }
+        }
+    }
+
+    fn record_session(table: &MockAtomTable) -> Vec<u8> {
+        let mut recorder = PortRecorder::new(Vec::new());
+        let mut driver = Accumulator { total: 0 };
+        let add = TermValue::atom("add", table);
+
+        let commands = [
+            TermValue::tuple(vec![add.clone(), TermValue::int(5)]),
+            TermValue::tuple(vec![add, TermValue::int(-300)]),
+            TermValue::atom("get", table),
+            TermValue::atom("bogus", table),
+        ];
+        for (id, command) in commands.iter().enumerate() {
+            recorder.handle(&reference(id as u64), command, table, |c| driver.handle(c, table));
+        }
+        recorder.into_sink()
+    }
+
+    #[test]
+    fn test_record_and_parse_capture() {
+        let table = MockAtomTable::new();
+        let capture = record_session(&table);
+        let frames = parse_capture(&capture, &table).unwrap();
+
+        let directions: Vec<Direction> = frames.iter().map(|f| f.direction).collect();
+        assert_eq!(directions, vec![
+            Direction::Inbound,
+            Direction::Inbound,
+            Direction::Inbound,
+            Direction::Outbound,
+            Direction::Inbound,
+            Direction::Outbound,
+        ]);
+        assert_eq!(
+            frames[3].term,
+            TermValue::tuple(vec![TermValue::atom("ok", &table), TermValue::int(-295)])
+        );
+        assert_eq!(frames[3].reference, reference(2));
+    }
+
+    #[test]
+    fn test_replay_against_fresh_table() {
+        let capture = record_session(&MockAtomTable::new());
+
+        // Replay against a table with a different atom layout
+        let table = MockAtomTable::new_empty();
+        let replayer = PortReplayer::from_capture(&capture, &table).unwrap();
+        assert_eq!(replayer.commands().count(), 4);
+
+        let mut driver = Accumulator { total: 0 };
+        let report = replayer.run(|c| driver.handle(c, &table));
+        assert_eq!(report.commands, 4);
+        assert!(report.is_clean(), "{:?}", report.mismatches);
+    }
+
+    #[test]
+    fn test_replay_reports_mismatch() {
+        let table = MockAtomTable::new();
+        let capture = record_session(&table);
+        let replayer = PortReplayer::from_capture(&capture, &table).unwrap();
+
+        // Regressed driver starts from the wrong total
+        let mut driver = Accumulator { total: 1 };
+        let report = replayer.run(|c| driver.handle(c, &table));
+
+        assert_eq!(report.mismatches.len(), 1);
+        let mismatch = &report.mismatches[0];
+        assert_eq!(mismatch.step, 2);
+        assert_eq!(mismatch.reference, reference(2));
+        assert_eq!(
+            mismatch.actual,
+            Some(TermValue::tuple(vec![TermValue::atom("ok", &table), TermValue::int(-294)]))
+        );
+    }
+
+    #[test]
+    fn test_replies_are_matched_on_the_call_reference() {
+        let table = MockAtomTable::new();
+        let ok = |n| TermValue::tuple(vec![TermValue::atom("ok", &table), TermValue::int(n)]);
+        let read = |n| TermValue::tuple(vec![TermValue::atom("read", &table), TermValue::int(n)]);
+
+        // The second read is answered first, with an unsolicited message between
+        let mut recorder = PortRecorder::new(Vec::new());
+        recorder.record_command(&reference(1), &read(1), &table).unwrap();
+        recorder.record_command(&reference(2), &read(2), &table).unwrap();
+        recorder.record_reply(&reference(2), &ok(2), &table).unwrap();
+        recorder.record_reply(&reference(9), &TermValue::atom("tick", &table), &table).unwrap();
+        recorder.record_reply(&reference(1), &ok(1), &table).unwrap();
+        let replayer = PortReplayer::from_capture(&recorder.into_sink(), &table).unwrap();
+
+        let report = replayer.run(|command| match command.as_tuple() {
+            Some([_, TermValue::SmallInt(n)]) => Some(ok(*n)),
+            _ => None,
+        });
+        assert_eq!(report.commands, 2);
+        assert!(report.is_clean(), "{:?}", report.mismatches);
+    }
+
+    #[test]
+    fn test_port_collection_handler_feeds_the_hook() {
+        use crate::context::{Context, GlobalContext, PortBuilder};
+        use crate::port::capture::{set_hook, SharedRecorder};
+        use crate::port::{Message, PortResult};
+        use crate::term::ProcessId;
+        use crate::testing::mocks::{MockContext, MockGlobalContext, MockMessage};
+
+        fn replay_probe_create(_global: &GlobalContext, _opts: crate::term::Term) -> *mut Context {
+            core::ptr::null_mut()
+        }
+        fn replay_probe_handler(_ctx: &mut Context, message: &Message) -> PortResult {
+            match crate::port::parse_gen_message(message).map(|(_, _, command)| command.to_value()) {
+                Ok(Ok(TermValue::SmallInt(n))) => PortResult::Reply(TermValue::int(n * 2)),
+                _ => PortResult::Continue,
+            }
+        }
+        crate::port_collection!(replay_probe, create_port = replay_probe_create, handler = replay_probe_handler);
+
+        static RECORDER: SharedRecorder<Vec<u8>> = SharedRecorder::new(Vec::new());
+        set_hook(Some(&RECORDER));
+
+        let global = MockGlobalContext::new();
+        let ctx = PortBuilder::new(0u8).build(global.as_global());
+        let port = unsafe { MockContext::from_raw(ctx) };
+        for (id, n) in [(7, 3), (8, 5)] {
+            let call = MockMessage::call(&global, ProcessId(5), RefId(id), TermValue::int(n)).unwrap();
+            replay_probe_message_handler(port.as_context(), call.as_message());
+        }
+        // Messages other than calls are not captured
+        let info = MockMessage::info(TermValue::int(1)).unwrap();
+        replay_probe_message_handler(port.as_context(), info.as_message());
+        set_hook(None);
+
+        let capture = RECORDER.with(|recorder| recorder.sink().clone());
+        let replayer = PortReplayer::from_capture(&capture, global.atoms()).unwrap();
+        assert_eq!(replayer.frames().len(), 4);
+        assert_eq!(replayer.frames()[3].reference, reference(8));
+        let report = replayer.run(|command| command.as_int().map(|n| TermValue::int(n * 2)));
+        assert_eq!(report.commands, 2);
+        assert!(report.is_clean(), "{:?}", report.mismatches);
+
+        unsafe { drop(crate::context::ContextExt::take_platform_data_box::<u8>(port.as_context())) };
+        crate::context::destroy_port_context_safe(ctx);
+    }
+
+    #[test]
+    fn test_disabled_recorder_writes_nothing() {
+        let table = MockAtomTable::new();
+        let mut recorder = PortRecorder::new(Vec::new());
+        recorder.set_enabled(false);
+        recorder.record_command(&reference(1), &TermValue::int(1), &table).unwrap();
+        assert!(recorder.sink().is_empty());
+    }
+
+    #[test]
+    fn test_corrupt_capture_rejected() {
+        let table = MockAtomTable::new();
+        let mut capture = record_session(&table);
+
+        assert_eq!(
+            PortReplayer::from_capture(&capture[..3], &table).err(),
+            Some(CaptureError::Truncated)
+        );
+
+        capture[0] = 9;
+        assert_eq!(
+            PortReplayer::from_capture(&capture, &table).err(),
+            Some(CaptureError::UnknownKind(9))
+        );
+    }
+}
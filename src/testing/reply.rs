@@ -0,0 +1,91 @@
+//! Reply style testing suite
+
+use crate::reply::ReplyStyle;
+use crate::term::{Context, ExceptionClass, NifReturn, Term, TermValue};
+use crate::testing::helpers::*;
+use crate::testing::mocks::MockAtomTable;
+use alloc::vec;
+
+fn reply_test_init(_ctx: &mut crate::Context) {}
+
+extern "C" fn version_nif(_ctx: *mut Context, _argc: i32, _argv: *const Term) -> Term {
+    Term::from_raw(0x3B)
+}
+
+crate::nif_collection!(
+    reply_test,
+    init = reply_test_init,
+    reply_style = ReplyStyle::StatusMap,
+    nifs = [("version", 0, version_nif)]
+);
+
+mod default_style {
+    fn default_reply_test_init(_ctx: &mut crate::Context) {}
+
+    crate::nif_collection!(default_reply_test, init = default_reply_test_init, nifs = []);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tagged_style() {
+        let table = MockAtomTable::new();
+        let style = ReplyStyle::Tagged;
+        assert_eq!(
+            style.ok(TermValue::int(1), &table),
+            NifReturn::Value(TermValue::tuple(vec![atom("ok", &table), TermValue::int(1)]))
+        );
+        assert_eq!(
+            style.reply(Err(atom("timeout", &table)), &table),
+            NifReturn::Value(TermValue::tuple(vec![atom("error", &table), atom("timeout", &table)]))
+        );
+    }
+
+    #[test]
+    fn test_bare_style_raises_errors() {
+        let table = MockAtomTable::new();
+        let style = ReplyStyle::Bare;
+        assert_eq!(style.ok(TermValue::int(1), &table), NifReturn::Value(TermValue::int(1)));
+        assert_eq!(
+            style.error(atom("timeout", &table), &table),
+            NifReturn::Raise(ExceptionClass::Error, atom("timeout", &table))
+        );
+    }
+
+    #[test]
+    fn test_status_map_style() {
+        let table = MockAtomTable::new();
+        let NifReturn::Value(reply) = ReplyStyle::StatusMap.error(atom("timeout", &table), &table) else {
+            panic!("status map errors are values");
+        };
+        assert_atom_str(reply.map_get(&atom("status", &table)).unwrap(), "error", &table);
+        assert_atom_str(reply.map_get(&atom("reason", &table)).unwrap(), "timeout", &table);
+        assert_eq!(reply.map_get(&atom("value", &table)), None);
+    }
+
+    #[test]
+    fn test_status_map_encodes_on_mock_context() {
+        use crate::testing::mocks::MockGlobalContext;
+
+        let global = MockGlobalContext::new();
+        let table = global.atoms();
+        let mut port = global.new_context();
+        let ctx = unsafe { &mut *(port.as_context() as *mut _ as *mut Context) };
+        let expected = TermValue::map(vec![(atom("status", table), atom("ok", table)), (atom("value", table), TermValue::int(7))]);
+        let term = ReplyStyle::StatusMap.ok(TermValue::int(7), table).into_term(ctx);
+        assert_eq!(term.to_value(), Ok(expected));
+        assert!(port.exception().is_none());
+    }
+
+    #[test]
+    fn test_collection_reply_style() {
+        let table = MockAtomTable::new();
+        assert_eq!(REPLY_TEST_REPLY_STYLE, ReplyStyle::StatusMap);
+        assert_eq!(default_style::DEFAULT_REPLY_TEST_REPLY_STYLE, ReplyStyle::Tagged);
+        assert_eq!(crate::reply_ok!(reply_test, TermValue::Nil, &table), ReplyStyle::StatusMap.ok(TermValue::Nil, &table));
+        assert_eq!(crate::reply_error!(reply_test, TermValue::Nil, &table), ReplyStyle::StatusMap.error(TermValue::Nil, &table));
+        assert_eq!(REPLY_TEST_NIFS.len(), 1);
+    }
+}
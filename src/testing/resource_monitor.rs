@@ -0,0 +1,137 @@
+//! Owner-monitored resource testing suite
+
+use crate::resource::{
+    resource_dtor, resource_type_init_full, ErlNifPid, ErlNifResourceFlags, ErlNifResourceType, ResourceArc,
+    ResourceManager,
+};
+use crate::resource_monitor::{resource_monitor_down, MonitoredType, ResourceMonitor};
+use crate::term::NifError;
+use crate::testing::mocks::MockResourceManager;
+use alloc::boxed::Box;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+static LOCK_TYPE: AtomicPtr<ErlNifResourceType> = AtomicPtr::new(core::ptr::null_mut());
+
+/// A bus lock that counts its releases
+struct BusLock {
+    held: bool,
+    releases: usize,
+}
+
+impl BusLock {
+    fn release(&mut self) {
+        self.held = false;
+        self.releases += 1;
+    }
+}
+
+impl MonitoredType for BusLock {
+    fn resource_type() -> *mut ErlNifResourceType {
+        LOCK_TYPE.load(Ordering::SeqCst)
+    }
+}
+
+/// Create a leaked mock manager with the `ResourceMonitor<BusLock>` type registered
+fn lock_manager() -> &'static MockResourceManager {
+    let mut manager = MockResourceManager::new();
+    let resource_type = manager
+        .init_resource_type(
+            core::ptr::null_mut(),
+            "bus_lock",
+            &resource_type_init_full(
+                Some(resource_dtor::<ResourceMonitor<BusLock>>),
+                None,
+                Some(resource_monitor_down::<BusLock>),
+            ),
+            ErlNifResourceFlags::ERL_NIF_RT_CREATE,
+        )
+        .unwrap();
+    LOCK_TYPE.store(resource_type, Ordering::SeqCst);
+    Box::leak(Box::new(manager))
+}
+
+fn bus_lock(manager: &'static MockResourceManager) -> ResourceArc<ResourceMonitor<BusLock>> {
+    let lock = BusLock { held: true, releases: 0 };
+    ResourceArc::new_in(manager, ResourceMonitor::new(lock, BusLock::release)).unwrap()
+}
+
+/// Deliver the VM's `down` callback for `pid`
+fn down(lock: &ResourceArc<ResourceMonitor<BusLock>>, pid: u32) {
+    let mut pid = pid as ErlNifPid;
+    unsafe { resource_monitor_down::<BusLock>(core::ptr::null_mut(), lock.as_ptr(), &mut pid, core::ptr::null_mut()) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owner_down_releases_once() {
+        let manager = lock_manager();
+        let env = core::ptr::null_mut();
+        let lock = bus_lock(manager);
+        lock.monitor_owner(env, 42).unwrap();
+        assert_eq!(lock.owner(), Some(42));
+        assert_eq!(manager.get_state().monitor_calls.len(), 1);
+
+        down(&lock, 42);
+        down(&lock, 42);
+        assert!(lock.is_released());
+        assert_eq!(lock.owner(), None);
+        assert_eq!(lock.with(|lock| (lock.held, lock.releases)), (false, 1));
+        assert_eq!(lock.monitor_owner(env, 43), Err(NifError::BadArg));
+    }
+
+    #[test]
+    fn test_other_process_down_is_ignored() {
+        let manager = lock_manager();
+        let lock = bus_lock(manager);
+        lock.monitor_owner(core::ptr::null_mut(), 42).unwrap();
+
+        down(&lock, 7);
+        assert!(!lock.is_released());
+        assert!(lock.with(|lock| lock.held));
+    }
+
+    #[test]
+    fn test_new_owner_replaces_monitor() {
+        let manager = lock_manager();
+        let env = core::ptr::null_mut();
+        let lock = bus_lock(manager);
+        lock.monitor_owner(env, 42).unwrap();
+        lock.monitor_owner(env, 43).unwrap();
+        assert_eq!(lock.owner(), Some(43));
+        assert!(!manager.get_state().demonitor_calls.is_empty());
+
+        // The previous owner no longer releases the value
+        down(&lock, 42);
+        assert!(!lock.is_released());
+        down(&lock, 43);
+        assert!(lock.is_released());
+    }
+
+    #[test]
+    fn test_demonitor_keeps_value() {
+        let manager = lock_manager();
+        let env = core::ptr::null_mut();
+        let lock = bus_lock(manager);
+        lock.monitor_owner(env, 42).unwrap();
+        lock.demonitor_owner(env).unwrap();
+        lock.demonitor_owner(env).unwrap();
+        assert_eq!(lock.owner(), None);
+
+        down(&lock, 42);
+        assert!(!lock.is_released());
+        drop(lock);
+        assert_eq!(manager.get_destructor_call_count(), 1);
+    }
+
+    #[test]
+    fn test_monitor_failure_is_reported() {
+        let manager = lock_manager();
+        let lock = bus_lock(manager);
+        manager.get_state().fail_monitor.store(true, Ordering::SeqCst);
+        assert_eq!(lock.monitor_owner(core::ptr::null_mut(), 42), Err(NifError::BadArg));
+        assert_eq!(lock.owner(), None);
+    }
+}
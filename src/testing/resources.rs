@@ -6,6 +6,8 @@
 use crate::resource::*;
 use crate::term::NifError;
 use crate::testing::mocks::MockResourceManager;
+use alloc::boxed::Box;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
 
 // Unit tests
 #[cfg(test)]
@@ -230,7 +232,7 @@ mod tests {
         
         let resource_ptr = manager.alloc_resource(resource_type, 1024).unwrap();
         let pid = 12345i32;
-        let reference = 0x98765432u64;
+        let reference: ERL_NIF_TERM = 0x98765432;
         
         // Test select
         let result = manager.select(
@@ -406,4 +408,300 @@ mod tests {
         // All resources should be destroyed
         assert_eq!(manager.get_resource_count(), 0);
     }
-}
\ No newline at end of file
+
+    // Typed resource tests
+
+    static COUNTER_TYPE: AtomicPtr<ErlNifResourceType> = AtomicPtr::new(core::ptr::null_mut());
+
+    #[derive(Debug)]
+    struct Counter {
+        value: u32,
+        drops: &'static AtomicUsize,
+    }
+
+    impl Drop for Counter {
+        fn drop(&mut self) {
+            self.drops.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    impl Resource for Counter {
+        fn resource_type() -> *mut ErlNifResourceType {
+            COUNTER_TYPE.load(Ordering::SeqCst)
+        }
+    }
+
+    /// Create a leaked mock manager with the `Counter` type registered
+    fn counter_manager() -> &'static MockResourceManager {
+        let mut manager = MockResourceManager::new();
+        let resource_type = manager.init_resource_type(
+            core::ptr::null_mut(),
+            "counter",
+            &resource_type_init_with_dtor(resource_dtor::<Counter>),
+            ErlNifResourceFlags::ERL_NIF_RT_CREATE,
+        ).unwrap();
+        COUNTER_TYPE.store(resource_type, Ordering::SeqCst);
+        Box::leak(Box::new(manager))
+    }
+
+    #[test]
+    fn test_resource_arc_new_and_deref() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        let manager = counter_manager();
+
+        let counter = ResourceArc::new_in(manager, Counter { value: 7, drops: &DROPS }).unwrap();
+
+        assert_eq!(counter.value, 7);
+        assert_eq!(manager.get_resource_count(), 1);
+        assert_eq!(manager.get_resource_ref_count(counter.as_ptr()), Some(1));
+    }
+
+    #[test]
+    fn test_resource_arc_clone_and_drop_refcount() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        let manager = counter_manager();
+
+        let first = ResourceArc::new_in(manager, Counter { value: 1, drops: &DROPS }).unwrap();
+        let second = first.clone();
+        assert!(ResourceArc::ptr_eq(&first, &second));
+        assert_eq!(manager.get_resource_ref_count(first.as_ptr()), Some(2));
+
+        drop(second);
+        assert_eq!(manager.get_resource_ref_count(first.as_ptr()), Some(1));
+        assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+
+        drop(first);
+        assert_eq!(manager.get_resource_count(), 0);
+        assert_eq!(manager.get_destructor_call_count(), 1);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1); // Drop ran from the destructor
+    }
+
+    #[test]
+    fn test_resource_arc_term_round_trip() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        let manager = counter_manager();
+        let env = core::ptr::null_mut();
+
+        let original = ResourceArc::new_in(manager, Counter { value: 99, drops: &DROPS }).unwrap();
+        let term = original.make_term(env).unwrap();
+
+        let decoded = ResourceArc::<Counter>::from_term_in(manager, env, term).unwrap();
+        assert!(ResourceArc::ptr_eq(&original, &decoded));
+        assert_eq!(decoded.value, 99);
+        assert_eq!(manager.get_resource_ref_count(original.as_ptr()), Some(2));
+
+        drop(original);
+        drop(decoded);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_resource_arc_errors() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        let mut manager = MockResourceManager::new();
+        manager.init_resource_type(
+            core::ptr::null_mut(),
+            "counter",
+            &resource_type_init_with_dtor(resource_dtor::<Counter>),
+            ErlNifResourceFlags::ERL_NIF_RT_CREATE,
+        ).unwrap();
+        manager.set_fail_alloc(true);
+        let manager: &'static MockResourceManager = Box::leak(Box::new(manager));
+
+        let result = ResourceArc::new_in(manager, Counter { value: 0, drops: &DROPS });
+        assert_eq!(result.unwrap_err(), NifError::OutOfMemory);
+
+        // The value is dropped normally when allocation fails
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+
+        let result = ResourceArc::<Counter>::from_term_in(
+            manager,
+            core::ptr::null_mut(),
+            crate::term::Term::from_raw(0x1234),
+        );
+        assert_eq!(result.unwrap_err(), NifError::BadArg);
+    }
+
+    #[test]
+    fn test_resource_arc_needs_an_initialized_type() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        static NEVER_TYPE: AtomicPtr<ErlNifResourceType> = AtomicPtr::new(core::ptr::null_mut());
+
+        struct Unregistered(Counter);
+        impl Resource for Unregistered {
+            fn resource_type() -> *mut ErlNifResourceType {
+                NEVER_TYPE.load(Ordering::SeqCst)
+            }
+        }
+
+        let manager = counter_manager();
+        let result = ResourceArc::new_in(manager, Unregistered(Counter { value: 0, drops: &DROPS }));
+        assert_eq!(result.err(), Some(NifError::BadArg));
+        assert!(manager.get_state().alloc_calls.is_empty());
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_resource_dtor_skips_misaligned_blocks() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        #[repr(align(8))]
+        struct Wide(Counter);
+
+        // A block `new_in` rejected as misaligned never held a value
+        let mut block = [0u64; 4];
+        let misaligned = (block.as_mut_ptr() as *mut u8).wrapping_add(1) as *mut core::ffi::c_void;
+        unsafe { resource_dtor::<Wide>(core::ptr::null_mut(), misaligned) };
+        assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+
+        let aligned = block.as_mut_ptr() as *mut Wide;
+        unsafe {
+            aligned.write(Wide(Counter { value: 1, drops: &DROPS }));
+            resource_dtor::<Wide>(core::ptr::null_mut(), aligned as *mut core::ffi::c_void);
+        }
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+    }
+
+    /// Create a leaked mock manager holding one raw resource (one reference)
+    fn raw_resource() -> (&'static MockResourceManager, *mut core::ffi::c_void) {
+        let mut manager = MockResourceManager::new();
+        let resource_type = manager.init_resource_type(
+            core::ptr::null_mut(),
+            "raw",
+            &resource_type_init(),
+            ErlNifResourceFlags::ERL_NIF_RT_CREATE,
+        ).unwrap();
+        let resource = manager.alloc_resource(resource_type, 16).unwrap();
+        (Box::leak(Box::new(manager)), resource)
+    }
+
+    #[test]
+    fn test_resource_guard_keeps_and_releases() {
+        let (manager, resource) = raw_resource();
+
+        let guard = ResourceGuard::new_in(manager, resource).unwrap();
+        let task_copy = guard.clone();
+        assert_eq!(manager.get_resource_ref_count(resource), Some(3));
+
+        drop(guard);
+        drop(task_copy);
+        assert_eq!(manager.get_resource_ref_count(resource), Some(1));
+        assert_eq!(manager.get_state().keep_resource_calls.len(), 2);
+        assert_eq!(manager.get_state().release_resource_calls.len(), 2);
+    }
+
+    #[test]
+    fn test_resource_guard_adopts_and_hands_back() {
+        let (manager, resource) = raw_resource();
+
+        // Adopting takes over the allocation's reference without a keep
+        let guard = unsafe { ResourceGuard::from_raw(manager, resource) }.unwrap();
+        assert_eq!(manager.get_resource_ref_count(resource), Some(1));
+        assert_eq!(guard.into_raw(), resource);
+        assert_eq!(manager.get_resource_ref_count(resource), Some(1));
+
+        let guard = unsafe { ResourceGuard::from_raw(manager, resource) }.unwrap();
+        drop(guard);
+        assert_eq!(manager.get_resource_count(), 0);
+    }
+
+    #[test]
+    fn test_resource_guard_errors() {
+        let (manager, resource) = raw_resource();
+        assert_eq!(ResourceGuard::new_in(manager, core::ptr::null_mut()).unwrap_err(), NifError::BadArg);
+
+        manager.get_state().fail_keep_resource.store(true, Ordering::SeqCst);
+        assert_eq!(ResourceGuard::new_in(manager, resource).unwrap_err(), NifError::BadArg);
+        assert_eq!(manager.get_resource_ref_count(resource), Some(1));
+    }
+
+    #[test]
+    fn test_resource_type_registry() {
+        struct Registered;
+        struct Unregistered;
+
+        let mut manager = MockResourceManager::new();
+        let first = manager.init_resource_type(
+            core::ptr::null_mut(),
+            "registered",
+            &resource_type_init(),
+            ErlNifResourceFlags::ERL_NIF_RT_CREATE,
+        ).unwrap();
+        let second = manager.init_resource_type(
+            core::ptr::null_mut(),
+            "registered_again",
+            &resource_type_init(),
+            ErlNifResourceFlags::ERL_NIF_RT_CREATE,
+        ).unwrap();
+
+        assert_eq!(ResourceType::of::<Registered>(), None);
+        assert!(ResourceType::register::<Registered>(core::ptr::null_mut()).is_none());
+        assert!(ResourceType::ptr_of::<Registered>().is_null());
+
+        ResourceType::register::<Registered>(first).unwrap();
+        assert_eq!(ResourceType::of::<Registered>().map(ResourceType::as_ptr), Some(first));
+
+        // Registering again replaces the entry
+        ResourceType::register::<Registered>(second).unwrap();
+        assert_eq!(ResourceType::ptr_of::<Registered>(), second);
+        assert_eq!(ResourceType::of::<Unregistered>(), None);
+    }
+
+    #[test]
+    fn test_create_and_get_registered() {
+        #[derive(Debug, PartialEq)]
+        struct Display {
+            width: u32,
+        }
+        struct Other;
+
+        let mut manager = MockResourceManager::new();
+        let env = core::ptr::null_mut();
+        let resource_type = manager.init_resource_type(
+            env,
+            "display",
+            &resource_type_init(),
+            ErlNifResourceFlags::ERL_NIF_RT_CREATE,
+        ).unwrap();
+        assert_eq!(create_registered_in(&manager, Display { width: 1 }).unwrap_err(), NifError::BadArg);
+        ResourceType::register::<Display>(resource_type).unwrap();
+
+        let ptr = create_registered_in(&manager, Display { width: 240 }).unwrap();
+        let term = crate::term::Term::from_raw(manager.make_resource(env, ptr).unwrap());
+        let display = get_registered_in::<Display>(&manager, env, term).unwrap();
+        assert_eq!(display as *mut core::ffi::c_void, ptr);
+        assert_eq!(unsafe { &*display }, &Display { width: 240 });
+
+        assert_eq!(get_registered_in::<Other>(&manager, env, term).unwrap_err(), NifError::BadArg);
+    }
+
+    #[test]
+    fn test_drop_destructor_without_resource_impl() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // What `resource_type!(NAME, Handle)` registers: no hand-written destructor
+        struct Handle {
+            _buffer: alloc::vec::Vec<u8>,
+        }
+
+        impl Drop for Handle {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let mut manager = MockResourceManager::new();
+        let resource_type = manager.init_resource_type(
+            core::ptr::null_mut(),
+            "handle",
+            &resource_type_init_with_dtor(resource_dtor::<Handle>),
+            ErlNifResourceFlags::ERL_NIF_RT_CREATE,
+        ).unwrap();
+        ResourceType::register::<Handle>(resource_type).unwrap();
+
+        let ptr = create_registered_in(&manager, Handle { _buffer: alloc::vec![0; 64] }).unwrap();
+        manager.release_resource(ptr).unwrap();
+        assert_eq!(manager.get_destructor_call_count(), 1);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+    }
+}
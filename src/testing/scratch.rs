@@ -0,0 +1,81 @@
+//! Scratch store testing suite
+
+use crate::context::ScratchStore;
+use alloc::{vec, vec::Vec};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// State of a parser that is fed one chunk per NIF call
+    #[derive(Debug, Clone, PartialEq)]
+    struct ParserState {
+        offset: usize,
+        pending: Vec<u8>,
+    }
+
+    #[test]
+    fn test_put_get_take() {
+        let mut store = ScratchStore::new();
+        assert!(store.is_empty());
+
+        let state = ParserState { offset: 4, pending: vec![1, 2] };
+        assert_eq!(store.put("parser", state.clone()), None);
+        assert_eq!(store.get::<ParserState>("parser"), Some(&state));
+
+        store.get_mut::<ParserState>("parser").unwrap().offset += 6;
+        assert_eq!(store.get::<ParserState>("parser").unwrap().offset, 10);
+
+        let taken = store.take::<ParserState>("parser").unwrap();
+        assert_eq!(taken.pending, vec![1, 2]);
+        assert!(!store.contains("parser"));
+    }
+
+    #[test]
+    fn test_type_mismatch_acts_as_missing() {
+        let mut store = ScratchStore::new();
+        store.put("count", 3u32);
+
+        assert_eq!(store.get::<i64>("count"), None);
+        assert_eq!(store.take::<i64>("count"), None);
+        assert!(store.contains("count"));
+
+        // Replacing with another type drops the old value
+        assert_eq!(store.put("count", 7i64), None);
+        assert_eq!(store.put("count", 8i64), Some(7));
+        assert_eq!(store.len(), 1);
+
+        assert!(store.remove("count"));
+        assert!(!store.remove("count"));
+    }
+
+    #[test]
+    fn test_scratch_on_context() {
+        use crate::context::{release_scratch, scratch_get, scratch_put, scratch_take, ContextExt};
+        use crate::testing::mocks::MockGlobalContext;
+
+        let global = MockGlobalContext::new();
+        let mut process = global.new_context();
+        let ctx = process.as_context();
+        assert!(!ctx.has_platform_data());
+
+        // Safety: nothing else sets this context's platform data
+        unsafe {
+            // Reading an absent store does not create it
+            assert_eq!(scratch_get::<usize>(ctx, "offset"), None);
+            assert_eq!(scratch_take::<usize>(ctx, "offset"), None);
+            assert!(!ctx.has_platform_data());
+
+            assert_eq!(scratch_put(ctx, "offset", 4usize), None);
+            assert!(ctx.has_platform_data());
+            assert_eq!(scratch_get::<usize>(ctx, "offset"), Some(4));
+            assert_eq!(scratch_get::<u8>(ctx, "offset"), None);
+            assert_eq!(scratch_take::<usize>(ctx, "offset"), Some(4));
+            assert_eq!(scratch_take::<usize>(ctx, "offset"), None);
+
+            scratch_put(ctx, "pending", vec![1u8, 2]);
+            release_scratch(ctx);
+        }
+        assert!(!ctx.has_platform_data());
+    }
+}
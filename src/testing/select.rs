@@ -0,0 +1,133 @@
+//! File descriptor selection testing suite
+
+use crate::resource::{
+    resource_type_init_full, resource_dtor, ErlNifEvent, ErlNifResourceFlags, ErlNifResourceType,
+    ErlNifSelectFlags, ResourceArc, ResourceManager,
+};
+use crate::select::{self, selectable_stop, Interest, SelectRef, Selectable, SelectableFd, SelectableType};
+use crate::term::{NifError, TermValue};
+use crate::testing::helpers::*;
+use crate::testing::mocks::{MockAtomTable, MockResourceManager};
+use alloc::boxed::Box;
+use alloc::vec;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+static PIPE_TYPE: AtomicPtr<ErlNifResourceType> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Descriptor that counts how often it was closed
+struct Pipe {
+    fd: ErlNifEvent,
+    closes: &'static AtomicUsize,
+}
+
+impl SelectableFd for Pipe {
+    fn event(&self) -> ErlNifEvent {
+        self.fd
+    }
+
+    fn close(self) {
+        self.closes.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl SelectableType for Pipe {
+    fn resource_type() -> *mut ErlNifResourceType {
+        PIPE_TYPE.load(Ordering::SeqCst)
+    }
+}
+
+/// Create a leaked mock manager with the `Selectable<Pipe>` type registered
+fn pipe_manager() -> &'static MockResourceManager {
+    let mut manager = MockResourceManager::new();
+    let resource_type = manager
+        .init_resource_type(
+            core::ptr::null_mut(),
+            "pipe",
+            &resource_type_init_full(
+                Some(resource_dtor::<Selectable<Pipe>>),
+                Some(selectable_stop::<Pipe>),
+                None,
+            ),
+            ErlNifResourceFlags::ERL_NIF_RT_CREATE,
+        )
+        .unwrap();
+    PIPE_TYPE.store(resource_type, Ordering::SeqCst);
+    Box::leak(Box::new(manager))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_arms_with_fresh_refs() {
+        static CLOSES: AtomicUsize = AtomicUsize::new(0);
+        let manager = pipe_manager();
+        let env = core::ptr::null_mut();
+        let pipe = ResourceArc::new_in(manager, Selectable::new(Pipe { fd: 7, closes: &CLOSES })).unwrap();
+
+        let first = pipe.select(env, Interest::Read, 42).unwrap();
+        let second = pipe.select(env, Interest::Write, 42).unwrap();
+        assert_ne!(first, second);
+
+        let calls: alloc::vec::Vec<_> = manager.get_state().select_calls.iter().map(|(e, m, _)| (*e, *m)).collect();
+        assert_eq!(
+            calls,
+            vec![(7, ErlNifSelectFlags::ERL_NIF_SELECT_READ), (7, ErlNifSelectFlags::ERL_NIF_SELECT_WRITE)]
+        );
+        assert_eq!(pipe.with_fd(|fd| fd.fd), Some(7));
+    }
+
+    #[test]
+    fn test_stop_closes_once() {
+        static CLOSES: AtomicUsize = AtomicUsize::new(0);
+        let manager = pipe_manager();
+        let env = core::ptr::null_mut();
+        let pipe = ResourceArc::new_in(manager, Selectable::new(Pipe { fd: 3, closes: &CLOSES })).unwrap();
+        pipe.select(env, Interest::Read, 42).unwrap();
+
+        pipe.stop(env).unwrap();
+        pipe.stop(env).unwrap();
+        let stops = manager
+            .get_state()
+            .select_calls
+            .iter()
+            .filter(|(_, mode, _)| *mode == ErlNifSelectFlags::ERL_NIF_SELECT_STOP)
+            .count();
+        assert_eq!(stops, 1);
+        assert!(pipe.is_stopping());
+        assert_eq!(pipe.select(env, Interest::Read, 42), Err(NifError::BadArg));
+
+        // The VM runs the stop callback once it let go of the descriptor
+        assert!(!pipe.is_closed());
+        unsafe { selectable_stop::<Pipe>(env, pipe.as_ptr(), 3, 1) };
+        assert!(pipe.is_closed());
+        assert_eq!(pipe.with_fd(|fd| fd.fd), None);
+
+        drop(pipe);
+        assert_eq!(CLOSES.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_unstopped_descriptor_closed_by_destructor() {
+        static CLOSES: AtomicUsize = AtomicUsize::new(0);
+        let manager = pipe_manager();
+        let pipe = ResourceArc::new_in(manager, Selectable::new(Pipe { fd: 5, closes: &CLOSES })).unwrap();
+        drop(pipe);
+        assert_eq!(CLOSES.load(Ordering::SeqCst), 1);
+        assert_eq!(manager.get_destructor_call_count(), 1);
+    }
+
+    #[test]
+    fn test_match_select_message() {
+        let table = MockAtomTable::new();
+        let message = |ready: &str| {
+            TermValue::tuple(vec![atom("select", &table), TermValue::Nil, TermValue::int(12), atom(ready, &table)])
+        };
+
+        assert_eq!(select::match_select(&message("ready_input"), &table), Some((SelectRef(12), Interest::Read)));
+        assert_eq!(select::match_select(&message("ready_output"), &table), Some((SelectRef(12), Interest::Write)));
+        assert_eq!(select::match_select(&message("ready_other"), &table), None);
+        assert_eq!(select::match_select(&TermValue::Nil, &table), None);
+    }
+}
@@ -0,0 +1,121 @@
+//! serde backend testing suite
+
+use crate::parse::parse_term;
+use crate::serde::{from_term, to_term};
+use crate::tagged::TaggedError;
+use crate::term::TermValue;
+use crate::testing::mocks::MockAtomTable;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    pub rate: u32,
+    pub label: String,
+    pub channels: Vec<u8>,
+    pub offset: Option<i16>,
+    pub origin: (i32, i32),
+    pub mode: Mode,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    Idle,
+    Single(u8),
+    Burst(u8, u16),
+    Scan { from: u8, to: u8 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "ack")]
+pub struct Ack;
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct Borrowed<'a> {
+    pub name: &'a str,
+}
+
+fn term(text: &str, table: &MockAtomTable) -> TermValue {
+    parse_term(text, table).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_struct_round_trip() {
+        let table = MockAtomTable::new();
+        let config = Config {
+            rate: 100,
+            label: "probe".to_string(),
+            channels: vec![1, 2],
+            offset: None,
+            origin: (-3, 4),
+            mode: Mode::Scan { from: 1, to: 8 },
+        };
+        let value = to_term(&config, &table).unwrap();
+        let expected = r#"#{rate => 100, label => <<"probe">>, channels => [1,2], offset => nil,
+                            origin => {-3,4}, mode => {scan, #{from => 1, to => 8}}}"#;
+        assert_eq!(value, term(expected, &table));
+        assert_eq!(from_term::<Config, _>(&value, &table), Ok(config));
+    }
+
+    #[test]
+    fn test_enum_shapes() {
+        let table = MockAtomTable::new();
+        for (mode, text) in [
+            (Mode::Idle, "idle"),
+            (Mode::Single(3), "{single, 3}"),
+            (Mode::Burst(2, 500), "{burst, 2, 500}"),
+        ] {
+            assert_eq!(to_term(&mode, &table).unwrap(), term(text, &table));
+            assert_eq!(from_term::<Mode, _>(&term(text, &table), &table), Ok(mode));
+        }
+        assert_eq!(to_term(&Ack, &table).unwrap(), term("ack", &table));
+        assert_eq!(from_term::<Ack, _>(&term("ack", &table), &table), Ok(Ack));
+        assert!(from_term::<Mode, _>(&term("{burst, 2}", &table), &table).is_err());
+        assert!(from_term::<Mode, _>(&term("unknown", &table), &table).is_err());
+    }
+
+    #[test]
+    fn test_erlang_input_forms() {
+        let table = MockAtomTable::new();
+        // Strings from charlists and atoms, floats from integers, any map key type
+        assert_eq!(from_term::<String, _>(&term("\"abc\"", &table), &table), Ok("abc".to_string()));
+        assert_eq!(from_term::<String, _>(&term("abc", &table), &table), Ok("abc".to_string()));
+        assert_eq!(from_term::<f64, _>(&term("3", &table), &table), Ok(3.0));
+        assert_eq!(from_term::<bool, _>(&term("true", &table), &table), Ok(true));
+        assert_eq!(from_term::<(), _>(&term("{}", &table), &table), Ok(()));
+
+        let map: BTreeMap<String, i32> = from_term(&term("#{<<\"a\">> => 1, b => 2}", &table), &table).unwrap();
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(map.get("b"), Some(&2));
+
+        // Strings can borrow from the term's binaries
+        let value = term("#{name => <<\"probe\">>}", &table);
+        assert_eq!(from_term::<Borrowed, _>(&value, &table), Ok(Borrowed { name: "probe" }));
+    }
+
+    #[test]
+    fn test_errors() {
+        let table = MockAtomTable::new();
+        assert_eq!(to_term(&u32::MAX, &table), Ok(TermValue::Integer(u32::MAX as i64)));
+        assert_eq!(to_term(&u64::MAX, &table), Err(TaggedError::OutOfRange("i64")));
+        assert!(from_term::<u8, _>(&term("300", &table), &table).is_err());
+        assert!(from_term::<Vec<u8>, _>(&term("[1|2]", &table), &table).is_err());
+        assert!(from_term::<(u8, u8), _>(&term("{1,2,3}", &table), &table).is_err());
+
+        let missing = term("#{rate => 1}", &table);
+        assert_eq!(from_term::<Config, _>(&missing, &table), Err(TaggedError::missing_field("label")));
+        let wrong = term("#{rate => 1.5}", &table);
+        assert!(matches!(
+            from_term::<Config, _>(&wrong, &table),
+            Err(TaggedError::NestedError { path, .. }) if path == "rate"
+        ));
+    }
+}
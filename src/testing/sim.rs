@@ -0,0 +1,629 @@
+//! Host simulator: the AtomVM C API implemented over the mocks
+//!
+//! With the `sim` feature (and in the crate's own tests) the functions the
+//! crate imports from AtomVM are defined here in Rust, so code using them
+//! links and runs with plain `cargo test` on the host. Each one is served
+//! by a mock: contexts and ports by `MockContext`, the atom table,
+//! resources, names, clock and log by the thread's `MockGlobalContext`,
+//! heaps by `MockHeap` and messages by `MockMessage`.
+//!
+//! The simulator defines the symbols unconditionally, so it cannot be
+//! linked into a build that also links AtomVM. It needs `std` for the
+//! per-thread current VM.
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use avmnif_rs::atom::{AtomTable, AtomTableOps};
+//! use avmnif_rs::context::{get_global_context, ContextExt, PortBuilder};
+//! use avmnif_rs::testing::mocks::{MockContext, MockGlobalContext};
+//!
+//! let global = MockGlobalContext::new();
+//! assert_eq!(get_global_context() as *const _, global.as_global() as *const _);
+//!
+//! // The VM's atom table is the mock's
+//! let ok = AtomTable::from_global().ensure_atom_str("ok").unwrap();
+//! assert_eq!(global.atoms().ensure_atom_str("ok"), Ok(ok));
+//!
+//! let ctx = PortBuilder::new(7u32).build_with_user_data(global.as_global(), 42);
+//! unsafe {
+//!     assert_eq!(*(*ctx).get_platform_data_as::<u32>(), 7);
+//!     assert_eq!(MockContext::from_raw(ctx).id(), 1);
+//!     drop((*ctx).take_platform_data_box::<u32>());
+//! }
+//! avmnif_rs::context::destroy_port_context_safe(ctx);
+//! # }
+//! ```
+
+extern crate alloc;
+extern crate std;
+
+use crate::atom::{AtomError, AtomIndex, AtomTableOps};
+use crate::context::{Context, GlobalContext};
+use crate::names::NameRegistry;
+use crate::port::{Message, ERL_NIF_TERM};
+use crate::resource::ResourceManager;
+use crate::term::{DecodeMode, Heap, ProcessId, RefcBinary, Term, TermValue, WordLayout};
+use crate::testing::mocks::{MockAtomTable, MockContext, MockGlobalContext, MockHeap, MockMessage, MockReply};
+use alloc::alloc::{alloc_zeroed, dealloc, Layout};
+use alloc::boxed::Box;
+use avmnif_sys::{
+    ErlNifEnv, ErlNifMonitor, ErlNifPid, ErlNifResourceFlags, ErlNifResourceType, ErlNifResourceTypeInit,
+    ErlNifSelectFlags,
+};
+use core::ffi::{c_char, c_int, c_uint, c_void, CStr};
+
+fn current<'a>() -> Option<&'a MockGlobalContext> {
+    unsafe { MockGlobalContext::current().as_ref() }
+}
+
+fn current_atoms<'a>() -> Option<&'a MockAtomTable> {
+    current().map(MockGlobalContext::atoms)
+}
+
+fn decode_lenient(raw: ERL_NIF_TERM) -> TermValue {
+    Term::from_raw(raw).decode(DecodeMode::Lenient).unwrap_or(TermValue::Nil)
+}
+
+unsafe fn mock<'a>(ctx: *const Context) -> &'a mut MockContext {
+    &mut *(ctx as *mut MockContext)
+}
+
+// ── Heaps ──────────────────────────────────────────────────────────────────
+
+#[no_mangle]
+unsafe extern "C" fn avmnif_memory_heap_alloc(heap: *mut Heap, size: usize) -> *mut usize {
+    (*(heap as *mut MockHeap)).alloc(size)
+}
+
+#[no_mangle]
+unsafe extern "C" fn context_heap(ctx: *mut Context) -> *mut Heap {
+    mock(ctx).heap.as_heap()
+}
+
+/// The mock heap never grows: a reservation beyond its free space fails
+#[no_mangle]
+unsafe extern "C" fn avmnif_memory_ensure_free(ctx: *mut Context, size: usize) -> core::ffi::c_int {
+    (mock(ctx).heap.free() < size) as core::ffi::c_int
+}
+
+#[no_mangle]
+unsafe extern "C" fn owned_heap_create(size: usize) -> *mut Heap {
+    Box::into_raw(Box::new(MockHeap::new(size))) as *mut Heap
+}
+
+#[no_mangle]
+unsafe extern "C" fn owned_heap_clear(heap: *mut Heap) {
+    (*(heap as *mut MockHeap)).clear();
+}
+
+#[no_mangle]
+unsafe extern "C" fn owned_heap_destroy(heap: *mut Heap) {
+    drop(Box::from_raw(heap as *mut MockHeap));
+}
+
+#[no_mangle]
+unsafe extern "C" fn context_raise_exception(ctx: *mut Context, class: c_int, reason: usize) {
+    mock(ctx).exception = Some((class, decode_lenient(reason)));
+}
+
+// ── Refc Binaries ──────────────────────────────────────────────────────────
+
+fn refc_layout(size: usize) -> Layout {
+    Layout::from_size_align(core::mem::size_of::<RefcBinary>() + size, core::mem::align_of::<RefcBinary>())
+        .expect("refc binary too large")
+}
+
+#[no_mangle]
+unsafe extern "C" fn refc_binary_create(size: usize, resource_type: *mut c_void) -> *mut RefcBinary {
+    let refc = alloc_zeroed(refc_layout(size)) as *mut RefcBinary;
+    if !refc.is_null() {
+        (*refc).ref_count = 1;
+        (*refc).size = size;
+        (*refc).resource_type = resource_type;
+    }
+    refc
+}
+
+#[no_mangle]
+unsafe extern "C" fn refc_binary_decrement_refcount(refc: *mut RefcBinary, _global: *mut GlobalContext) -> bool {
+    (*refc).ref_count -= 1;
+    if (*refc).ref_count > 0 {
+        return false;
+    }
+    dealloc(refc as *mut u8, refc_layout((*refc).size));
+    true
+}
+
+/// Boxed as AtomVM does: header, size, flags, the `RefcBinary`, then the
+/// off-heap list link, which the mock heap does not keep
+#[no_mangle]
+unsafe extern "C" fn term_from_refc_binary(refc: *mut RefcBinary, heap: *mut Heap) -> usize {
+    let ptr = avmnif_memory_heap_alloc(heap, Term::REFC_BINARY_WORDS);
+    if ptr.is_null() {
+        return 0;
+    }
+    let header = WordLayout::NATIVE.boxed_header(Term::REFC_BINARY_WORDS - 1, Term::TERM_BOXED_REFC_BINARY);
+    *ptr = header as usize;
+    *ptr.add(1) = (*refc).size;
+    *ptr.add(2) = 0;
+    *ptr.add(3) = refc as usize;
+    for i in 4..Term::REFC_BINARY_WORDS {
+        *ptr.add(i) = 0;
+    }
+    Term::from_boxed(ptr).raw()
+}
+
+// ── Contexts and Ports ─────────────────────────────────────────────────────
+
+#[no_mangle]
+unsafe extern "C" fn create_port_context(global: *const GlobalContext) -> *mut Context {
+    let global = &*(global as *const MockGlobalContext);
+    Box::into_raw(global.new_context()) as *mut Context
+}
+
+#[no_mangle]
+unsafe extern "C" fn destroy_port_context(ctx: *mut Context) {
+    drop(Box::from_raw(ctx as *mut MockContext));
+}
+
+#[no_mangle]
+unsafe extern "C" fn port_is_alive(ctx: *const Context) -> i32 {
+    mock(ctx).alive as i32
+}
+
+#[no_mangle]
+unsafe extern "C" fn port_get_id(ctx: *const Context) -> u32 {
+    mock(ctx).id
+}
+
+#[no_mangle]
+unsafe extern "C" fn context_get_platform_data(ctx: *const Context) -> *mut c_void {
+    mock(ctx).platform_data
+}
+
+#[no_mangle]
+unsafe extern "C" fn context_set_platform_data(ctx: *mut Context, data: *mut c_void) {
+    mock(ctx).platform_data = data;
+}
+
+#[no_mangle]
+unsafe extern "C" fn context_get_user_data(ctx: *const Context) -> u64 {
+    mock(ctx).user_data
+}
+
+#[no_mangle]
+unsafe extern "C" fn context_set_user_data(ctx: *mut Context, data: u64) {
+    mock(ctx).user_data = data;
+}
+
+#[no_mangle]
+unsafe extern "C" fn context_get_global(ctx: *const Context) -> *mut GlobalContext {
+    mock(ctx).global as *mut GlobalContext
+}
+
+#[no_mangle]
+unsafe extern "C" fn global_context_ptr() -> *mut GlobalContext {
+    MockGlobalContext::current() as *mut GlobalContext
+}
+
+/// Each collection's private data is kept on the VM under its name
+#[no_mangle]
+unsafe extern "C" fn global_context_get_nif_priv(global: *mut GlobalContext, collection: *const c_char) -> *mut c_void {
+    let global = &*(global as *const MockGlobalContext);
+    let name = CStr::from_ptr(collection).to_bytes();
+    global.nif_priv.borrow().iter().find(|(key, _)| key.as_bytes() == name).map_or(core::ptr::null_mut(), |(_, data)| *data)
+}
+
+#[no_mangle]
+unsafe extern "C" fn global_context_set_nif_priv(global: *mut GlobalContext, collection: *const c_char, data: *mut c_void) {
+    let global = &*(global as *const MockGlobalContext);
+    let name = CStr::from_ptr(collection).to_string_lossy();
+    let mut slots = global.nif_priv.borrow_mut();
+    slots.retain(|(key, _)| *key != name);
+    if !data.is_null() {
+        slots.push((name.into_owned(), data));
+    }
+}
+
+#[no_mangle]
+unsafe extern "C" fn parse_port_message(
+    message: *const Message,
+    pid: *mut ERL_NIF_TERM,
+    reference: *mut ERL_NIF_TERM,
+    command: *mut ERL_NIF_TERM,
+) -> core::ffi::c_int {
+    match (*(message as *const MockMessage)).call {
+        Some([from, tag, request]) => {
+            *pid = from.raw();
+            *reference = tag.raw();
+            *command = request.raw();
+            1
+        }
+        None => 0,
+    }
+}
+
+#[no_mangle]
+unsafe extern "C" fn port_message_term(message: *const Message) -> ERL_NIF_TERM {
+    (*(message as *const MockMessage)).term.raw()
+}
+
+#[no_mangle]
+unsafe extern "C" fn port_send_reply(
+    ctx: *mut Context,
+    pid: ERL_NIF_TERM,
+    reference: ERL_NIF_TERM,
+    reply: ERL_NIF_TERM,
+) {
+    mock(ctx).replies.push(MockReply {
+        pid: decode_lenient(pid),
+        reference: decode_lenient(reference),
+        reply: decode_lenient(reply),
+    });
+}
+
+#[no_mangle]
+unsafe extern "C" fn port_send_external_reply(
+    ctx: *mut Context,
+    pid: ERL_NIF_TERM,
+    reference: ERL_NIF_TERM,
+    data: *const u8,
+    len: usize,
+) -> core::ffi::c_int {
+    let ctx = &mut *(ctx as *mut MockContext);
+    let Some(atoms) = ctx.atoms() else {
+        return 0;
+    };
+    let Ok(reply) = crate::etf::decode(core::slice::from_raw_parts(data, len), atoms) else {
+        return 0;
+    };
+    ctx.replies.push(MockReply {
+        pid: decode_lenient(pid),
+        reference: decode_lenient(reference),
+        reply,
+    });
+    1
+}
+
+#[no_mangle]
+unsafe extern "C" fn port_send_external_term(
+    global: *mut GlobalContext,
+    pid: u32,
+    data: *const u8,
+    len: usize,
+) -> core::ffi::c_int {
+    let global = &*(global as *const MockGlobalContext);
+    let Ok(message) = crate::etf::decode(core::slice::from_raw_parts(data, len), global.atoms()) else {
+        return 0;
+    };
+    global.sent.borrow_mut().push((ProcessId(pid), message));
+    1
+}
+
+#[no_mangle]
+unsafe extern "C" fn port_timer_now_ms() -> u64 {
+    MockGlobalContext::current().as_ref().map_or(0, |global| global.now_ms.get())
+}
+
+/// Every process exists for the mock, so monitoring always succeeds
+#[no_mangle]
+unsafe extern "C" fn port_monitor_process(ctx: *mut Context, pid: u32) -> core::ffi::c_int {
+    mock(ctx).monitors.push(pid);
+    1
+}
+
+#[no_mangle]
+unsafe extern "C" fn port_demonitor_process(ctx: *mut Context, pid: u32) {
+    mock(ctx).monitors.retain(|&monitored| monitored != pid);
+}
+
+#[no_mangle]
+unsafe extern "C" fn port_send_message_from_task(global: *mut GlobalContext, pid: u32, message: ERL_NIF_TERM) {
+    let global = &*(global as *const MockGlobalContext);
+    global.sent.borrow_mut().push((ProcessId(pid), decode_lenient(message)));
+}
+
+#[no_mangle]
+unsafe extern "C" fn port_timer_arm(ctx: *mut Context, delay_ms: u32) -> c_int {
+    mock(ctx).timer = Some(delay_ms);
+    1
+}
+
+#[no_mangle]
+unsafe extern "C" fn port_timer_disarm(ctx: *mut Context) {
+    mock(ctx).timer = None;
+}
+
+/// Sleeping moves the VM's clock, so a blocking call times out at once
+#[no_mangle]
+unsafe extern "C" fn port_task_sleep_ms(ms: u32) {
+    if let Some(global) = current() {
+        global.advance_ms(ms as u64);
+    }
+}
+
+// ── Processes and Log ──────────────────────────────────────────────────────
+
+#[no_mangle]
+unsafe extern "C" fn context_process_id(ctx: *const Context) -> i32 {
+    mock(ctx).id as i32
+}
+
+#[no_mangle]
+unsafe extern "C" fn globalcontext_register_process(glb: *mut GlobalContext, atom_index: i32, local_process_id: i32) -> bool {
+    let global = &*(glb as *const MockGlobalContext);
+    let registered = global.names.borrow_mut().register(AtomIndex(atom_index as u32), local_process_id as u32);
+    registered.is_ok()
+}
+
+#[no_mangle]
+unsafe extern "C" fn globalcontext_unregister_process(glb: *mut GlobalContext, atom_index: i32) -> bool {
+    let global = &*(glb as *const MockGlobalContext);
+    let unregistered = global.names.borrow_mut().unregister(AtomIndex(atom_index as u32));
+    unregistered
+}
+
+#[no_mangle]
+unsafe extern "C" fn globalcontext_get_registered_process(glb: *mut GlobalContext, atom_index: i32) -> i32 {
+    let global = &*(glb as *const MockGlobalContext);
+    global.whereis(AtomIndex(atom_index as u32)).map_or(0, |pid| pid as i32)
+}
+
+#[no_mangle]
+unsafe extern "C" fn globalcontext_get_ref_ticks(glb: *mut GlobalContext) -> u64 {
+    let global = &*(glb as *const MockGlobalContext);
+    global.ref_ticks.set(global.ref_ticks.get() + 1);
+    global.ref_ticks.get()
+}
+
+#[no_mangle]
+unsafe extern "C" fn avmnif_log(msg: *const c_char) {
+    if let Some(global) = current() {
+        let line = CStr::from_ptr(msg.cast()).to_string_lossy();
+        global.log.borrow_mut().push(line.trim_end().into());
+    }
+}
+
+// ── Resources ──────────────────────────────────────────────────────────────
+
+/// Runs `f` on the current VM's resource manager; `None` without a VM or
+/// when called back from inside a destructor
+fn with_resources<R>(f: impl FnOnce(&mut crate::testing::mocks::MockResourceManager) -> R) -> Option<R> {
+    let global = current()?;
+    let mut resources = global.resources.try_borrow_mut().ok()?;
+    Some(f(&mut resources))
+}
+
+#[no_mangle]
+unsafe extern "C" fn enif_init_resource_type(
+    env: *mut ErlNifEnv,
+    name: *const c_char,
+    init: *const ErlNifResourceTypeInit,
+    flags: ErlNifResourceFlags,
+    tried: *mut ErlNifResourceFlags,
+) -> *mut ErlNifResourceType {
+    let Ok(name) = CStr::from_ptr(name).to_str() else {
+        return core::ptr::null_mut();
+    };
+    if !tried.is_null() {
+        *tried = flags;
+    }
+    with_resources(|resources| resources.init_resource_type(env, name, &*init, flags).ok())
+        .flatten()
+        .unwrap_or(core::ptr::null_mut())
+}
+
+#[no_mangle]
+unsafe extern "C" fn enif_alloc_resource(resource_type: *mut ErlNifResourceType, size: c_uint) -> *mut c_void {
+    with_resources(|resources| resources.alloc_resource(resource_type, size).ok())
+        .flatten()
+        .unwrap_or(core::ptr::null_mut())
+}
+
+#[no_mangle]
+unsafe extern "C" fn enif_make_resource(env: *mut ErlNifEnv, obj: *mut c_void) -> ERL_NIF_TERM {
+    with_resources(|resources| resources.make_resource(env, obj).ok())
+        .flatten()
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+unsafe extern "C" fn enif_get_resource(
+    env: *mut ErlNifEnv,
+    t: ERL_NIF_TERM,
+    resource_type: *mut ErlNifResourceType,
+    objp: *mut *mut c_void,
+) -> c_int {
+    match with_resources(|resources| resources.get_resource(env, t, resource_type).ok()).flatten() {
+        Some(obj) => {
+            *objp = obj;
+            1
+        }
+        None => 0,
+    }
+}
+
+#[no_mangle]
+unsafe extern "C" fn enif_keep_resource(obj: *mut c_void) -> c_int {
+    (with_resources(|resources| resources.keep_resource(obj).is_ok()) == Some(true)) as c_int
+}
+
+#[no_mangle]
+unsafe extern "C" fn enif_release_resource(obj: *mut c_void) -> c_int {
+    (with_resources(|resources| resources.release_resource(obj).is_ok()) == Some(true)) as c_int
+}
+
+#[no_mangle]
+unsafe extern "C" fn enif_select(
+    env: *mut ErlNifEnv,
+    event: c_int,
+    mode: ErlNifSelectFlags,
+    obj: *mut c_void,
+    pid: *const ErlNifPid,
+    reference: ERL_NIF_TERM,
+) -> c_int {
+    match with_resources(|resources| resources.select(env, event, mode, obj, pid, reference).is_ok()) {
+        Some(true) => 0,
+        _ => -1,
+    }
+}
+
+#[no_mangle]
+unsafe extern "C" fn enif_monitor_process(
+    env: *mut ErlNifEnv,
+    obj: *mut c_void,
+    target_pid: *const ErlNifPid,
+    mon: *mut ErlNifMonitor,
+) -> c_int {
+    match with_resources(|resources| resources.monitor_process(env, obj, target_pid, mon).is_ok()) {
+        Some(true) => 0,
+        _ => 1,
+    }
+}
+
+#[no_mangle]
+unsafe extern "C" fn enif_demonitor_process(caller_env: *mut ErlNifEnv, obj: *mut c_void, mon: *const ErlNifMonitor) -> c_int {
+    match with_resources(|resources| resources.demonitor_process(caller_env, obj, mon).is_ok()) {
+        Some(true) => 0,
+        _ => 1,
+    }
+}
+
+// ── Registration ───────────────────────────────────────────────────────────
+
+/// Registration does nothing on the host; tests call a collection's
+/// generated functions directly
+#[no_mangle]
+#[allow(non_snake_case)]
+unsafe extern "C" fn REGISTER_NIF_COLLECTION(
+    _name: *const u8,
+    _init: *const c_void,
+    _destroy: *const c_void,
+    _resolver: *const c_void,
+) {
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+unsafe extern "C" fn REGISTER_PORT_DRIVER(
+    _name: *const u8,
+    _init: *const c_void,
+    _destroy: *const c_void,
+    _create_port: *const c_void,
+) {
+}
+
+// ── Atom Table ─────────────────────────────────────────────────────────────
+
+#[no_mangle]
+unsafe extern "C" fn atomvm_get_global_atom_table() -> *mut c_void {
+    current_atoms().map_or(core::ptr::null_mut(), |atoms| atoms as *const MockAtomTable as *mut c_void)
+}
+
+#[no_mangle]
+unsafe extern "C" fn atom_table_count(table: *mut c_void) -> usize {
+    (table as *const MockAtomTable).as_ref().map_or(0, |atoms| atoms.count())
+}
+
+#[no_mangle]
+unsafe extern "C" fn atom_table_get_atom_string(
+    table: *mut c_void,
+    index: u32,
+    out_size: *mut usize,
+) -> *const core::ffi::c_char {
+    let Some(atoms) = (table as *const MockAtomTable).as_ref() else {
+        return core::ptr::null();
+    };
+    match atoms.get_atom_string(AtomIndex(index)) {
+        Ok(atom) => {
+            *out_size = atom.as_bytes().len();
+            atom.as_bytes().as_ptr().cast()
+        }
+        Err(_) => core::ptr::null(),
+    }
+}
+
+/// Statuses as `result_from_c` reads them: 0 ok, 1 not found, 2 failed
+#[no_mangle]
+unsafe extern "C" fn atom_table_ensure_atom(
+    table: *mut c_void,
+    atom_data: *const core::ffi::c_char,
+    atom_len: usize,
+    opts: c_uint,
+    result: *mut u32,
+) -> c_uint {
+    let Some(atoms) = (table as *const MockAtomTable).as_ref() else {
+        return 2;
+    };
+    let name = core::slice::from_raw_parts(atom_data.cast::<u8>(), atom_len);
+    let found = if opts == crate::atom::AtomCopyOpt::AlreadyExisting as c_uint {
+        atoms.find_atom(name)
+    } else {
+        atoms.ensure_atom(name)
+    };
+    match found {
+        Ok(AtomIndex(index)) => {
+            *result = index;
+            0
+        }
+        Err(AtomError::NotFound) => 1,
+        Err(_) => 2,
+    }
+}
+
+#[no_mangle]
+unsafe extern "C" fn atom_table_is_equal_to_atom_string(
+    table: *mut c_void,
+    atom_index: u32,
+    string_data: *const core::ffi::c_char,
+    string_len: usize,
+) -> bool {
+    let name = core::slice::from_raw_parts(string_data.cast::<u8>(), string_len);
+    (table as *const MockAtomTable)
+        .as_ref()
+        .is_some_and(|atoms| atoms.atom_equals(AtomIndex(atom_index), name))
+}
+
+#[no_mangle]
+unsafe extern "C" fn atom_table_cmp_using_atom_index(table: *mut c_void, atom1: u32, atom2: u32) -> core::ffi::c_int {
+    (table as *const MockAtomTable)
+        .as_ref()
+        .map_or(0, |atoms| atoms.compare_atoms(AtomIndex(atom1), AtomIndex(atom2)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::OwnedBinary;
+    use crate::log::log_info;
+    use crate::names::{register, unregister, whereis, AtomVMNameRegistry};
+
+    #[test]
+    fn test_owned_binary_released_onto_mock_heap() {
+        let _global = MockGlobalContext::new();
+        let mut heap = MockHeap::new(64);
+        let mut guard = heap.guard();
+        let binary = OwnedBinary::from_slice(&[7; 100]).unwrap();
+        let term = binary.release(&mut guard).unwrap();
+        assert_eq!(term.to_value(), Ok(TermValue::binary(alloc::vec![7; 100])));
+
+        // An unreleased buffer is freed on drop
+        drop(OwnedBinary::new(16).unwrap());
+    }
+
+    #[test]
+    fn test_names_and_log_reach_mock_global() {
+        let global = MockGlobalContext::new();
+        let table = global.atoms();
+        let mut names = AtomVMNameRegistry::new();
+        assert_eq!(register(&mut names, "sensor", 12, table), Ok(()));
+        assert!(register(&mut names, "sensor", 13, table).is_err());
+        assert_eq!(whereis(&names, "sensor", table), Some(12));
+        assert_eq!(global.whereis(table.ensure_atom_str("sensor").unwrap()), Some(12));
+        assert!(unregister(&mut names, "sensor", table));
+        assert_eq!(whereis(&names, "sensor", table), None);
+
+        log_info("probe ready");
+        assert_eq!(global.log_lines(), ["[info] probe ready"]);
+    }
+}
@@ -0,0 +1,171 @@
+//! Stream port testing suite
+
+use crate::port::stream::{Framer, LengthPrefixed, Lines, Slip, StreamBackend, StreamPort, MAX_BUFFER};
+use crate::port::{PortError, PortResult};
+use crate::term::TermValue;
+use crate::testing::mocks::{MockAtomTable, MockTimerBackend};
+use alloc::{vec, vec::Vec};
+
+/// A link whose received bytes arrive in the given chunks, one per read
+#[derive(Default)]
+struct MockStream {
+    incoming: Vec<Vec<u8>>,
+    written: Vec<u8>,
+    fail_reads: bool,
+}
+
+impl StreamBackend for MockStream {
+    fn init(_opts: &TermValue) -> Result<Self, PortError> {
+        Ok(Self::default())
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, PortError> {
+        if self.fail_reads {
+            return Err(PortError::HardwareError);
+        }
+        if self.incoming.is_empty() {
+            return Ok(0);
+        }
+        let chunk = &mut self.incoming[0];
+        let count = chunk.len().min(buffer.len());
+        buffer[..count].copy_from_slice(&chunk[..count]);
+        chunk.drain(..count);
+        if chunk.is_empty() {
+            self.incoming.remove(0);
+        }
+        Ok(count)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), PortError> {
+        self.written.extend_from_slice(data);
+        Ok(())
+    }
+}
+
+fn encoded<F: Framer>(framer: &F, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    framer.encode(payload, &mut out).unwrap();
+    out
+}
+
+fn decode_all<F: Framer>(framer: &mut F, bytes: &[u8]) -> Vec<Vec<u8>> {
+    let mut buffer = bytes.to_vec();
+    let mut frames = Vec::new();
+    while let Some(frame) = framer.decode(&mut buffer) {
+        frames.push(frame);
+    }
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let mut lines = Lines::default();
+        assert_eq!(decode_all(&mut lines, b"OK\r\nERROR\npart"), vec![b"OK".to_vec(), b"ERROR".to_vec()]);
+        assert_eq!(encoded(&lines, b"AT"), b"AT\n");
+
+        let mut out = Vec::new();
+        assert!(matches!(lines.encode(b"A\nB", &mut out), Err(PortError::InvalidMessage)));
+
+        let mut nul = Lines::new(0);
+        assert_eq!(decode_all(&mut nul, b"a\r\0b\0"), vec![b"a\r".to_vec(), b"b".to_vec()]);
+    }
+
+    #[test]
+    fn test_length_prefixed() {
+        let mut framer = LengthPrefixed::<2>;
+        let bytes = encoded(&framer, b"hello");
+        assert_eq!(bytes, [0, 5, b'h', b'e', b'l', b'l', b'o']);
+
+        // Waits for the whole frame
+        let mut buffer = bytes[..4].to_vec();
+        assert_eq!(framer.decode(&mut buffer), None);
+        buffer.extend_from_slice(&bytes[4..]);
+        assert_eq!(framer.decode(&mut buffer), Some(b"hello".to_vec()));
+        assert!(buffer.is_empty());
+
+        let mut empty = encoded(&LengthPrefixed::<4>, b"");
+        assert_eq!(empty, [0, 0, 0, 0]);
+        assert_eq!(LengthPrefixed::<4>.decode(&mut empty), Some(Vec::new()));
+
+        let mut out = Vec::new();
+        assert!(matches!(LengthPrefixed::<1>.encode(&[0; 256], &mut out), Err(PortError::InvalidMessage)));
+    }
+
+    #[test]
+    fn test_slip_escapes_round_trip() {
+        let mut slip = Slip;
+        let payload = [1, Slip::END, 2, Slip::ESC, 3];
+        let bytes = encoded(&slip, &payload);
+        assert_eq!(bytes, [Slip::END, 1, Slip::ESC, Slip::ESC_END, 2, Slip::ESC, Slip::ESC_ESC, 3, Slip::END]);
+
+        // The leading END of the next frame does not produce an empty one
+        let mut stream = bytes.clone();
+        stream.extend_from_slice(&encoded(&slip, b"x"));
+        assert_eq!(decode_all(&mut slip, &stream), vec![payload.to_vec(), b"x".to_vec()]);
+    }
+
+    #[test]
+    fn test_frames_split_across_reads() {
+        let backend = MockStream {
+            incoming: vec![b"AT+".to_vec(), b"OK\nRING".to_vec(), b"\n".to_vec()],
+            ..MockStream::default()
+        };
+        let mut port = StreamPort::new(backend, Lines::default());
+
+        assert_eq!(port.receive().unwrap(), vec![b"AT+OK".to_vec(), b"RING".to_vec()]);
+        assert_eq!(port.buffered(), 0);
+        assert!(port.receive().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_overlong_partial_frame_is_dropped() {
+        let mut port = StreamPort::new(MockStream::default(), Lines::default());
+        assert!(port.feed(&[b'a'; MAX_BUFFER + 1]).is_empty());
+        assert_eq!((port.buffered(), port.overflows()), (0, 1));
+        assert_eq!(port.feed(b"ok\n"), vec![b"ok".to_vec()]);
+    }
+
+    #[test]
+    fn test_send_command() {
+        let table = MockAtomTable::new();
+        let mut port = StreamPort::new(MockStream::default(), LengthPrefixed::<1>);
+
+        let data = TermValue::list(vec![TermValue::binary(b"hi".to_vec()), TermValue::int(b'!' as i32)]);
+        let request = TermValue::tuple(vec![TermValue::atom("send", &table), data]);
+        assert_eq!(port.handle_request(&request, &table), PortResult::Reply(TermValue::atom("ok", &table)));
+        assert_eq!(port.backend().written, [3, b'h', b'i', b'!']);
+
+        let unknown = TermValue::atom("flush", &table);
+        assert_eq!(
+            port.handle_request(&unknown, &table),
+            PortResult::ReplyError(TermValue::atom("badarg", &table))
+        );
+    }
+
+    #[test]
+    fn test_polls_on_timer() {
+        let table = MockAtomTable::new();
+        let mut timers = MockTimerBackend::new();
+        let mut port = StreamPort::new(MockStream::default(), Lines::default());
+        port.start_polling(&mut timers, 20);
+        assert_eq!(timers.armed, Some(20));
+
+        port.backend_mut().incoming.push(b"ping\n".to_vec());
+        assert!(port.on_wakeup(&mut timers, &table).unwrap().is_empty());
+
+        timers.advance(20);
+        assert_eq!(port.on_wakeup(&mut timers, &table).unwrap(), vec![b"ping".to_vec()]);
+        assert_eq!(
+            StreamPort::<MockStream, Lines>::frame_message(b"ping".to_vec(), &table),
+            TermValue::tuple(vec![TermValue::atom("frame", &table), TermValue::binary(b"ping".to_vec())])
+        );
+
+        port.backend_mut().fail_reads = true;
+        timers.advance(20);
+        assert!(matches!(port.on_wakeup(&mut timers, &table), Err(PortError::HardwareError)));
+    }
+}
@@ -9,7 +9,8 @@ use crate::tagged::{
     TaggedMap, TaggedError, TaggedResult,
     to_snake_case, get_type_atom, type_field_atom, variant_field_atom,
     get_map_value, extract_string_field, extract_int_field, extract_float_field,
-    extract_bool_field, extract_optional_field, validate_type_discriminator
+    extract_bool_field, extract_optional_field, validate_type_discriminator,
+    extract_binary_field,
 };
 
 #[cfg(test)]
@@ -158,6 +159,129 @@ impl TaggedMap for TestStatus {
     }
 }
 
+#[cfg(test)]
+use avmnif_derive::{NifMap, NifStruct, NifTuple, NifUnitEnum};
+
+#[cfg(test)]
+/// Derived counterpart of `TestUser`, nesting a derived enum
+#[derive(Debug, Clone, PartialEq, avmnif_derive::TaggedMap)]
+pub struct DerivedUser {
+    pub id: i32,
+    pub name: String,
+    pub email: Option<String>,
+    pub status: DerivedStatus,
+    pub tags: Vec<String>,
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq, avmnif_derive::TaggedMap)]
+pub enum DerivedStatus {
+    Active,
+    Suspended { reason: String, days: i32 },
+}
+
+#[cfg(test)]
+/// Every numeric field type, as a driver's config struct would have
+#[derive(Debug, Clone, PartialEq, avmnif_derive::TaggedMap)]
+pub struct DerivedRegisters {
+    pub address: u8,
+    pub offset: i8,
+    pub period: u16,
+    pub trim: i16,
+    pub mask: u32,
+    pub uptime: u64,
+    pub delta: i64,
+    pub gain: f32,
+    pub scale: f64,
+    pub unit: char,
+    pub reserved: (),
+}
+
+#[cfg(test)]
+/// Compound field types: coordinates, a lookup table and a fallible reading
+#[derive(Debug, Clone, PartialEq, avmnif_derive::TaggedMap)]
+pub struct DerivedSurvey {
+    pub origin: (i32, i32),
+    pub calibration: [u16; 3],
+    pub channels: alloc::collections::BTreeMap<String, u8>,
+    pub last: Result<f64, String>,
+}
+
+#[cfg(test)]
+/// Field attributes: a wire name, a local-only cache and a field added later
+#[derive(Debug, Clone, PartialEq, avmnif_derive::TaggedMap)]
+pub struct DerivedConfig {
+    #[tagged(rename = "type_id")]
+    pub kind: i32,
+    #[tagged(skip)]
+    pub cache: Vec<u8>,
+    #[tagged(default)]
+    pub retries: u8,
+    pub name: String,
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq, avmnif_derive::TaggedMap)]
+pub enum DerivedCommand {
+    Write {
+        #[tagged(rename = "reg")]
+        register: u8,
+        #[tagged(skip)]
+        attempts: u32,
+    },
+}
+
+#[cfg(test)]
+/// Record encoding: `{derived_record, Id, Name, Email}`
+#[derive(Debug, Clone, PartialEq, avmnif_derive::TaggedMap)]
+#[tagged(record)]
+pub struct DerivedRecord {
+    pub id: i32,
+    pub name: String,
+    #[tagged(skip)]
+    pub session: u32,
+    #[tagged(default)]
+    pub email: Option<String>,
+}
+
+#[cfg(test)]
+/// Elixir struct encoding: `%Geometry.Point{x: 1, y: 2, label: nil}`
+#[derive(Debug, Clone, PartialEq, avmnif_derive::TaggedMap)]
+#[tagged(module = "Geometry.Point")]
+pub struct DerivedPoint {
+    pub x: i32,
+    pub y: i32,
+    #[tagged(default)]
+    pub label: Option<String>,
+}
+
+#[cfg(test)]
+/// Shaped like a type carried over from a rustler NIF
+#[derive(Debug, Clone, PartialEq, NifStruct)]
+#[module = "Elixir.Sensor.Reading"]
+pub struct Reading {
+    pub channel: i32,
+    pub value: f64,
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq, NifMap)]
+pub struct Limits {
+    pub low: i32,
+    pub high: i32,
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq, NifTuple)]
+pub struct Sample(pub i32, pub bool);
+
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, PartialEq, NifUnitEnum)]
+pub enum Mode {
+    Idle,
+    FastScan,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -417,6 +541,45 @@ mod tests {
         assert_eq!(missing_field, None);
     }
 
+    #[test]
+    fn test_string_field_accepts_charlist() {
+        let table = MockAtomTable::new();
+        let name_atom = get_type_atom("name", &table).unwrap();
+
+        let map = TermValue::Map(vec![(TermValue::Atom(name_atom), TermValue::charlist("Bob"))]);
+        assert_eq!(extract_string_field(&map, "name", &table).unwrap(), "Bob");
+
+        let bad = TermValue::Map(vec![(
+            TermValue::Atom(name_atom),
+            TermValue::list(vec![TermValue::atom("x", &table)]),
+        )]);
+        assert!(matches!(
+            extract_string_field(&bad, "name", &table),
+            Err(TaggedError::WrongType { .. })
+        ));
+    }
+
+    #[test]
+    fn test_string_and_binary_fields_accept_iodata() {
+        let table = MockAtomTable::new();
+        let field = TermValue::Atom(get_type_atom("data", &table).unwrap());
+
+        // ["caf", <<195, 169>>]: UTF-8 split across chunks
+        let iodata = TermValue::list(vec![TermValue::charlist("caf"), TermValue::binary(vec![195, 169])]);
+        let map = TermValue::Map(vec![(field.clone(), iodata)]);
+        assert_eq!(extract_string_field(&map, "data", &table).unwrap(), "caf\u{e9}");
+        assert_eq!(extract_binary_field(&map, "data", &table).unwrap(), b"caf\xc3\xa9".to_vec());
+
+        let bad_utf8 = TermValue::Map(vec![(field.clone(), TermValue::list(vec![TermValue::binary(vec![0xff])]))]);
+        assert!(matches!(extract_string_field(&bad_utf8, "data", &table), Err(TaggedError::InvalidUtf8)));
+
+        let not_iodata = TermValue::Map(vec![(field, TermValue::int(1))]);
+        assert!(matches!(
+            extract_binary_field(&not_iodata, "data", &table),
+            Err(TaggedError::WrongType { .. })
+        ));
+    }
+
     #[test]
     fn test_error_conditions() {
         let table = MockAtomTable::new();
@@ -546,4 +709,275 @@ mod tests {
         let float_result = extract_float_field(&float_map, "test_field", &table).unwrap();
         assert_eq!(float_result, 3.14);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_derived_round_trip() {
+        let table = MockAtomTable::new();
+        let user = DerivedUser {
+            id: 7,
+            name: "Ada".to_string(),
+            email: None,
+            status: DerivedStatus::Suspended { reason: "spam".to_string(), days: 3 },
+            tags: vec!["admin".to_string()],
+        };
+
+        let map = user.to_tagged_map(&table).unwrap();
+        validate_type_discriminator(&map, "derived_user", &table).unwrap();
+        let status = get_map_value(&map, get_type_atom("status", &table).unwrap()).unwrap();
+        validate_type_discriminator(status, "derived_status", &table).unwrap();
+        assert_eq!(DerivedUser::from_tagged_map(map, &table).unwrap(), user);
+
+        let active = DerivedStatus::Active.to_tagged_map(&table).unwrap();
+        assert_eq!(DerivedStatus::from_tagged_map(active, &table).unwrap(), DerivedStatus::Active);
+    }
+
+    #[test]
+    fn test_derived_decode_errors() {
+        let table = MockAtomTable::new();
+        let mut map = DerivedStatus::Active.to_tagged_map(&table).unwrap();
+        if let TermValue::Map(pairs) = &mut map {
+            pairs[1].1 = TermValue::Atom(get_type_atom("banned", &table).unwrap());
+        }
+        assert!(matches!(
+            DerivedStatus::from_tagged_map(map, &table),
+            Err(TaggedError::InvalidVariant { .. })
+        ));
+
+        // A missing optional field decodes as None, a missing required one fails
+        let pairs = tagged_pairs_for("derived_user", &[("id", TermValue::int(1))], &table);
+        assert!(matches!(
+            DerivedUser::from_tagged_map(pairs, &table),
+            Err(TaggedError::MissingField(field)) if field == "name"
+        ));
+    }
+
+    fn tagged_pairs_for<T: AtomTableOps>(type_name: &str, fields: &[(&str, TermValue)], table: &T) -> TermValue {
+        let mut pairs = crate::tagged::tagged_pairs(type_name, table).unwrap();
+        for (name, value) in fields {
+            pairs.push((TermValue::Atom(get_type_atom(name, table).unwrap()), value.clone()));
+        }
+        TermValue::Map(pairs)
+    }
+
+    #[test]
+    fn test_nif_struct_uses_elixir_struct_encoding() {
+        let table = MockAtomTable::new();
+        let reading = Reading { channel: 2, value: 1.5 };
+        let term = reading.to_tagged_map(&table).unwrap();
+
+        let module = get_map_value(&term, get_type_atom("__struct__", &table).unwrap()).unwrap();
+        assert_eq!(*module, TermValue::Atom(get_type_atom("Elixir.Sensor.Reading", &table).unwrap()));
+        assert_eq!(Reading::from_tagged_map(term, &table).unwrap(), reading);
+
+        let other = Limits { low: 0, high: 1 }.to_tagged_map(&table).unwrap();
+        assert!(Reading::from_tagged_map(other, &table).is_err());
+    }
+
+    #[test]
+    fn test_nif_map_tuple_and_unit_enum_encodings() {
+        let table = MockAtomTable::new();
+
+        let limits = Limits { low: -5, high: 40 };
+        let term = limits.to_tagged_map(&table).unwrap();
+        assert!(matches!(&term, TermValue::Map(pairs) if pairs.len() == 2));
+        assert_eq!(Limits::from_tagged_map(term, &table).unwrap(), limits);
+
+        let sample = Sample(9, true);
+        let term = sample.to_tagged_map(&table).unwrap();
+        assert_eq!(term.as_tuple().map(|elements| elements.len()), Some(2));
+        assert_eq!(Sample::from_tagged_map(term, &table).unwrap(), sample);
+
+        let term = Mode::FastScan.to_tagged_map(&table).unwrap();
+        assert_eq!(term, TermValue::Atom(get_type_atom("fast_scan", &table).unwrap()));
+        assert_eq!(Mode::from_tagged_map(term, &table).unwrap(), Mode::FastScan);
+    }
+
+    #[test]
+    fn test_numeric_fields_round_trip() {
+        let table = MockAtomTable::new();
+        let registers = DerivedRegisters {
+            address: 0x48,
+            offset: -3,
+            period: 60_000,
+            trim: -1200,
+            mask: 0x7FFF_FFFF,
+            uptime: 86_400,
+            delta: -5,
+            gain: 0.5,
+            scale: 1.25,
+            unit: 'C',
+            reserved: (),
+        };
+        let map = registers.to_tagged_map(&table).unwrap();
+        assert_eq!(*get_map_value(&map, get_type_atom("unit", &table).unwrap()).unwrap(), TermValue::int(67));
+        assert_eq!(*get_map_value(&map, get_type_atom("reserved", &table).unwrap()).unwrap(), TermValue::tuple(vec![]));
+        assert_eq!(DerivedRegisters::from_tagged_map(map, &table).unwrap(), registers);
+    }
+
+    #[test]
+    fn test_numeric_range_errors() {
+        let table = MockAtomTable::new();
+        // Beyond an i64 on the way out, beyond the Rust type on the way in
+        assert_eq!(u64::MAX.to_tagged_map(&table), Err(TaggedError::nested("value", TaggedError::OutOfRange("i64"))));
+        let map = 300i64.to_tagged_map(&table).unwrap();
+        let mut pairs = crate::tagged::tagged_pairs("u8", &table).unwrap();
+        pairs.push((TermValue::Atom(get_type_atom("value", &table).unwrap()), TermValue::int(300)));
+        assert_eq!(
+            u8::from_tagged_map(TermValue::Map(pairs), &table),
+            Err(TaggedError::nested("value", TaggedError::OutOfRange("u8")))
+        );
+        assert_eq!(i64::from_tagged_map(map, &table), Ok(300));
+        assert!(u16::from_tagged_map(7u8.to_tagged_map(&table).unwrap(), &table).is_err());
+
+        assert_eq!(char::from_tagged_map('\u{e9}'.to_tagged_map(&table).unwrap(), &table), Ok('\u{e9}'));
+        assert_eq!(<()>::from_tagged_map(().to_tagged_map(&table).unwrap(), &table), Ok(()));
+        assert_eq!(f32::from_tagged_map(2.5f32.to_tagged_map(&table).unwrap(), &table), Ok(2.5));
+    }
+
+    #[test]
+    fn test_compound_fields_round_trip() {
+        let table = MockAtomTable::new();
+        let survey = DerivedSurvey {
+            origin: (-4, 12),
+            calibration: [100, 200, 300],
+            channels: [("ph".to_string(), 1), ("temp".to_string(), 0)].into_iter().collect(),
+            last: Err("sensor offline".to_string()),
+        };
+        let map = survey.to_tagged_map(&table).unwrap();
+        assert_eq!(
+            *get_map_value(&map, get_type_atom("origin", &table).unwrap()).unwrap(),
+            TermValue::tuple(vec![TermValue::int(-4), TermValue::int(12)])
+        );
+        assert_eq!(
+            *get_map_value(&map, get_type_atom("last", &table).unwrap()).unwrap(),
+            TermValue::tuple(vec![
+                TermValue::atom("error", &table),
+                TermValue::binary(b"sensor offline".to_vec())
+            ])
+        );
+        assert_eq!(DerivedSurvey::from_tagged_map(map, &table).unwrap(), survey);
+    }
+
+    #[test]
+    fn test_compound_tagged_maps() {
+        let table = MockAtomTable::new();
+        let wide = (1u8, 2i16, 3u32, 4i64, 5.0f64, true, 'x', "eight".to_string());
+        let map = wide.to_tagged_map(&table).unwrap();
+        validate_type_discriminator(&map, "tuple", &table).unwrap();
+        assert_eq!(TaggedMap::from_tagged_map(map, &table), Ok(wide));
+
+        let ok: Result<i32, String> = Ok(7);
+        assert_eq!(Result::<i32, String>::from_tagged_map(ok.to_tagged_map(&table).unwrap(), &table), Ok(ok));
+
+        // Arrays must have exactly N elements
+        let short = [1u8, 2].to_tagged_map(&table).unwrap();
+        assert_eq!(
+            <[u8; 3]>::from_tagged_map(short, &table),
+            Err(TaggedError::nested("value", TaggedError::OutOfBounds { index: 2, max: 3 }))
+        );
+
+        let mut pairs = crate::tagged::tagged_pairs("result", &table).unwrap();
+        let maybe = TermValue::tuple(vec![TermValue::atom("maybe", &table), TermValue::int(1)]);
+        pairs.push((TermValue::Atom(get_type_atom("value", &table).unwrap()), maybe));
+        assert!(matches!(
+            Result::<i32, i32>::from_tagged_map(TermValue::Map(pairs), &table),
+            Err(TaggedError::NestedError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_derived_field_attributes() {
+        let table = MockAtomTable::new();
+        let config = DerivedConfig { kind: 3, cache: vec![1, 2], retries: 5, name: "probe".to_string() };
+        let map = config.to_tagged_map(&table).unwrap();
+        assert_eq!(*get_map_value(&map, get_type_atom("type_id", &table).unwrap()).unwrap(), TermValue::int(3));
+        assert!(get_map_value(&map, get_type_atom("kind", &table).unwrap()).is_err());
+        assert!(get_map_value(&map, get_type_atom("cache", &table).unwrap()).is_err());
+        assert_eq!(
+            DerivedConfig::from_tagged_map(map, &table),
+            Ok(DerivedConfig { cache: vec![], ..config.clone() })
+        );
+
+        // A map from before `retries` existed still decodes; `name` is still required
+        let mut pairs = crate::tagged::tagged_pairs("derived_config", &table).unwrap();
+        pairs.push((TermValue::Atom(get_type_atom("type_id", &table).unwrap()), TermValue::int(3)));
+        assert_eq!(
+            DerivedConfig::from_tagged_map(TermValue::Map(pairs.clone()), &table),
+            Err(TaggedError::missing_field("name"))
+        );
+        pairs.push((TermValue::Atom(get_type_atom("name", &table).unwrap()), TermValue::binary(b"probe".to_vec())));
+        assert_eq!(
+            DerivedConfig::from_tagged_map(TermValue::Map(pairs), &table),
+            Ok(DerivedConfig { kind: 3, cache: vec![], retries: 0, name: "probe".to_string() })
+        );
+
+        let command = DerivedCommand::Write { register: 0x2A, attempts: 3 };
+        let map = command.to_tagged_map(&table).unwrap();
+        assert_eq!(*get_map_value(&map, get_type_atom("reg", &table).unwrap()).unwrap(), TermValue::int(0x2A));
+        assert_eq!(
+            DerivedCommand::from_tagged_map(map, &table),
+            Ok(DerivedCommand::Write { register: 0x2A, attempts: 0 })
+        );
+    }
+
+    #[test]
+    fn test_derived_record_encoding() {
+        let table = MockAtomTable::new();
+        let user = DerivedRecord { id: 7, name: "ada".to_string(), session: 99, email: None };
+        let tuple = user.to_tagged_map(&table).unwrap();
+        assert_eq!(
+            tuple,
+            TermValue::tuple(vec![
+                TermValue::atom("derived_record", &table),
+                TermValue::int(7),
+                TermValue::binary(b"ada".to_vec()),
+                TermValue::atom("nil", &table),
+            ])
+        );
+        assert_eq!(DerivedRecord::from_tagged_map(tuple, &table), Ok(DerivedRecord { session: 0, ..user }));
+
+        // Trailing default fields may be left off; required ones may not
+        let short = TermValue::tuple(vec![TermValue::atom("derived_record", &table), TermValue::int(7)]);
+        assert_eq!(DerivedRecord::from_tagged_map(short, &table), Err(TaggedError::missing_field("name")));
+        let older = TermValue::tuple(vec![
+            TermValue::atom("derived_record", &table),
+            TermValue::int(7),
+            TermValue::binary(b"ada".to_vec()),
+        ]);
+        assert_eq!(DerivedRecord::from_tagged_map(older, &table).unwrap().email, None);
+
+        let other = TermValue::tuple(vec![TermValue::atom("user", &table), TermValue::int(7)]);
+        assert_eq!(
+            DerivedRecord::from_tagged_map(other, &table),
+            Err(TaggedError::type_mismatch("derived_record", "user"))
+        );
+        let long = TermValue::tuple(vec![TermValue::atom("derived_record", &table); 5]);
+        assert_eq!(
+            DerivedRecord::from_tagged_map(long, &table),
+            Err(TaggedError::OutOfBounds { index: 5, max: 4 })
+        );
+    }
+
+    #[test]
+    fn test_derived_elixir_struct() {
+        let table = MockAtomTable::new();
+        let point = DerivedPoint { x: 1, y: -2, label: Some("origin".to_string()) };
+        let term = point.to_tagged_map(&table).unwrap();
+        let module = get_map_value(&term, get_type_atom("__struct__", &table).unwrap()).unwrap();
+        assert_eq!(*module, TermValue::atom("Elixir.Geometry.Point", &table));
+        assert!(get_map_value(&term, get_type_atom("type", &table).unwrap()).is_err());
+        assert_eq!(DerivedPoint::from_tagged_map(term, &table), Ok(point));
+        assert_eq!(DerivedPoint::type_name(), "Elixir.Geometry.Point");
+
+        // As built by Elixir: any key order, fields after a struct change left out
+        let incoming = TermValue::map(vec![
+            (TermValue::atom("y", &table), TermValue::int(4)),
+            (TermValue::atom("__struct__", &table), TermValue::atom("Elixir.Geometry.Point", &table)),
+            (TermValue::atom("x", &table), TermValue::int(3)),
+        ]);
+        assert_eq!(DerivedPoint::from_tagged_map(incoming, &table), Ok(DerivedPoint { x: 3, y: 4, label: None }));
+
+        let plain = TermValue::map(vec![(TermValue::atom("x", &table), TermValue::int(3))]);
+        assert_eq!(DerivedPoint::from_tagged_map(plain, &table), Err(TaggedError::missing_field("__struct__")));
+    }
+}
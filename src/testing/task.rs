@@ -0,0 +1,101 @@
+//! Task reply testing suite
+
+use crate::sync::SpinLock;
+use crate::task::{spawn_with, Executor, Job, ReplySender};
+use crate::term::{NifError, NifResult, RefId, TermValue};
+use alloc::{sync::Arc, vec, vec::Vec};
+
+/// Keeps jobs until `run_all`, like a task that has not been scheduled yet
+struct QueueExecutor {
+    jobs: SpinLock<Vec<Job>>,
+    refuse: bool,
+}
+
+impl QueueExecutor {
+    fn new(refuse: bool) -> Self {
+        Self { jobs: SpinLock::new(Vec::new()), refuse }
+    }
+
+    fn run_all(&self) {
+        let jobs: Vec<Job> = self.jobs.with(core::mem::take);
+        for job in jobs {
+            job();
+        }
+    }
+
+    fn pending(&self) -> usize {
+        self.jobs.with(|jobs| jobs.len())
+    }
+}
+
+impl Executor for QueueExecutor {
+    fn spawn(&self, job: Job) -> NifResult<()> {
+        if self.refuse {
+            return Err(NifError::OutOfMemory);
+        }
+        self.jobs.with(|jobs| jobs.push(job));
+        Ok(())
+    }
+}
+
+/// Collects `{Ref, Int}` replies as `(pid, ref, int)`
+#[derive(Clone)]
+struct Outbox(Arc<SpinLock<Vec<(u32, u64, i32)>>>);
+
+impl ReplySender for Outbox {
+    fn send(&self, pid: u32, message: &TermValue) -> NifResult<()> {
+        match message.as_tuple() {
+            Some([TermValue::Reference(RefId(reference)), TermValue::SmallInt(result)]) => {
+                self.0.with(|sent| sent.push((pid, *reference, *result)));
+                Ok(())
+            }
+            _ => Err(NifError::BadArg),
+        }
+    }
+}
+
+impl Outbox {
+    fn new() -> Self {
+        Self(Arc::new(SpinLock::new(Vec::new())))
+    }
+
+    fn sent(&self) -> Vec<(u32, u64, i32)> {
+        self.0.with(|sent| sent.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reply_sent_when_job_finishes() {
+        let executor = QueueExecutor::new(false);
+        let outbox = Outbox::new();
+        let reference = RefId(77);
+        let samples = [3, 4, 5];
+
+        spawn_with(&executor, outbox.clone(), 12, reference, move || {
+            TermValue::int(samples.iter().sum())
+        })
+        .unwrap();
+
+        // Nothing is sent before the task runs
+        assert_eq!(executor.pending(), 1);
+        assert!(outbox.sent().is_empty());
+
+        executor.run_all();
+        assert_eq!(outbox.sent(), vec![(12, 77, 12)]);
+    }
+
+    #[test]
+    fn test_refused_job_never_replies() {
+        let executor = QueueExecutor::new(true);
+        let outbox = Outbox::new();
+
+        let result = spawn_with(&executor, outbox.clone(), 12, RefId(1), || TermValue::int(0));
+        assert_eq!(result, Err(NifError::OutOfMemory));
+        executor.run_all();
+        assert!(outbox.sent().is_empty());
+    }
+}
@@ -0,0 +1,459 @@
+//! Low-level term layout testing suite
+//!
+//! Builds AtomVM term layouts by hand in host memory and checks that
+//! `Term::to_value` decodes them, and encodes with `Term::from_value` on a
+//! `MockHeap` to check the round trip.
+
+use crate::atom::AtomIndex;
+use crate::term::{
+    Context, DecodeError, DecodeMode, DecodeReason, Env, FunctionRef, NifError, PortId, ProcessId, RefId, Term,
+    TermValue, WordLayout,
+};
+use crate::testing::mocks::MockHeap;
+use alloc::vec;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORD: usize = core::mem::size_of::<usize>();
+
+    fn boxed(words: &[usize]) -> Term<'_> {
+        Term::from_raw(words.as_ptr() as usize | 0x2)
+    }
+
+    #[test]
+    fn test_pid_and_port_immediates() {
+        assert_eq!(Term::from_raw((42 << 4) | 0x3).to_value().unwrap(), TermValue::Pid(ProcessId(42)));
+        assert_eq!(Term::from_raw((7 << 4) | 0x7).to_value().unwrap(), TermValue::Port(PortId(7)));
+    }
+
+    #[test]
+    fn test_boxed_reference() {
+        let id: u64 = 0x0000_1234_89ab_cdef;
+        let words: [usize; 3] = if WORD == 8 {
+            [(1 << 6) | 0x10, id as usize, 0]
+        } else {
+            [(2 << 6) | 0x10, (id >> 32) as usize, id as u32 as usize]
+        };
+        assert_eq!(boxed(&words).to_value().unwrap(), TermValue::Reference(RefId(id)));
+    }
+
+    #[test]
+    fn test_process_reference_keeps_ticks() {
+        let id: u64 = 0x0000_0042_0000_0007;
+        let pid = (5 << 4) | 0x3;
+        let words: [usize; 4] = if WORD == 8 {
+            [(2 << 6) | 0x10, id as usize, pid, 0]
+        } else {
+            [(3 << 6) | 0x10, (id >> 32) as usize, id as u32 as usize, pid]
+        };
+        assert_eq!(boxed(&words).to_value().unwrap(), TermValue::Reference(RefId(id)));
+    }
+
+    #[test]
+    fn test_reference_with_unknown_size_is_corrupt() {
+        let words = [(4 << 6) | 0x10, 1, 2, 3, 4];
+        assert!(boxed(&words).to_value().is_err());
+    }
+
+    #[test]
+    fn test_boxed_float() {
+        let mut words = [((8 / WORD) << 6) | 0x18, 0, 0];
+        unsafe { (words.as_mut_ptr().add(1) as *mut f64).write_unaligned(-1.25) };
+        assert_eq!(boxed(&words).to_value().unwrap(), TermValue::Float(-1.25));
+    }
+
+    #[test]
+    fn test_boxed_external_fun() {
+        let words = [(3 << 6) | 0x14, (5 << 4) | 0xB, (9 << 4) | 0xB, (2 << 4) | 0xF];
+        assert_eq!(
+            boxed(&words).to_value().unwrap(),
+            TermValue::Function(FunctionRef {
+                module: AtomIndex(5),
+                function: AtomIndex(9),
+                arity: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_closure_has_no_adt_form() {
+        // Local funs point at a module structure, not an atom
+        let module = [0usize; 4];
+        let words = [(2 << 6) | 0x14, module.as_ptr() as usize, 0];
+        let term = boxed(&words);
+        let unsupported = DecodeError { raw: term.raw(), reason: DecodeReason::Unsupported };
+        assert_eq!(term.decode(DecodeMode::Strict), Err(unsupported));
+        assert_eq!(term.to_value(), Err(NifError::InvalidTerm));
+        assert_eq!(term.decode(DecodeMode::Lenient), Ok(TermValue::Invalid(unsupported)));
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_pointers_and_tags() {
+        // The primary tag only hides two bits; on 64-bit targets a pointer
+        // can still be off by half a word
+        if WORD == 8 {
+            let words = [0usize; 2];
+            let misaligned = Term::from_raw((words.as_ptr() as usize + 4) | 0x2);
+            assert_eq!(misaligned.decode(DecodeMode::Strict).unwrap_err().reason, DecodeReason::Misaligned);
+        }
+        assert_eq!(Term::from_raw(0x2).decode(DecodeMode::Strict).unwrap_err().reason, DecodeReason::NullPointer);
+        assert_eq!(Term::from_raw(0x1).decode(DecodeMode::Strict).unwrap_err().reason, DecodeReason::NullPointer);
+
+        let unknown = [0x3Cusize, 0];
+        assert_eq!(boxed(&unknown).decode(DecodeMode::Strict).unwrap_err().reason, DecodeReason::UnknownTag);
+    }
+
+    #[test]
+    fn test_lenient_decode_keeps_valid_parts() {
+        let module = [0usize; 4];
+        let closure = [(2 << 6) | 0x14, module.as_ptr() as usize, 0];
+        let tuple = [(2 << 6), (1 << 4) | 0xF, boxed(&closure).raw()];
+
+        assert_eq!(boxed(&tuple).to_value(), Err(NifError::InvalidTerm));
+        let error = DecodeError { raw: boxed(&closure).raw(), reason: DecodeReason::Unsupported };
+        assert_eq!(
+            boxed(&tuple).decode(DecodeMode::Lenient),
+            Ok(TermValue::tuple(vec![TermValue::int(1), TermValue::Invalid(error)]))
+        );
+    }
+
+    #[test]
+    fn test_small_int_range_follows_word_size() {
+        let bits = usize::BITS as i64 - 4;
+        assert_eq!(Term::MAX_SMALL_INT, (1i64 << (bits - 1)) - 1);
+        assert_eq!(Term::MIN_SMALL_INT, -(1i64 << (bits - 1)));
+
+        // Immediates are sign-extended from the whole word
+        let raw = ((Term::MIN_SMALL_INT as isize as usize) << 4) | 0xF;
+        assert_eq!(Term::from_raw(raw).to_i64().unwrap(), Term::MIN_SMALL_INT);
+        assert_eq!(Term::from_raw((-5isize as usize) << 4 | 0xF).to_value().unwrap(), TermValue::int(-5));
+    }
+
+    #[test]
+    fn test_boxed_integers() {
+        let positive = [(1 << 6) | 0x08, i32::MAX as usize];
+        assert_eq!(boxed(&positive).to_value().unwrap(), TermValue::int(i32::MAX));
+
+        let negative = [(1 << 6) | 0x0C, (-70_000isize) as usize];
+        assert_eq!(boxed(&negative).to_i64().unwrap(), -70_000);
+
+        // int64 stored across the payload words (2 on 32-bit, 1 on 64-bit)
+        let big: i64 = -(1 << 40);
+        let mut words = [((8 / WORD) << 6) | 0x0C, 0, 0];
+        unsafe { (words.as_mut_ptr().add(1) as *mut i64).write_unaligned(big) };
+        assert_eq!(boxed(&words).to_i64().unwrap(), big);
+        assert_eq!(boxed(&words).to_value().unwrap(), TermValue::Integer(big));
+    }
+
+    #[test]
+    fn test_integer_wider_than_64_bits_is_unsupported() {
+        // A bignum payload of 128 bits is a valid term without a TermValue form
+        let bignum = [((16 / WORD) << 6) | 0x08, 1, 0, 0, 0];
+        let error = boxed(&bignum).decode(DecodeMode::Strict).unwrap_err();
+        assert_eq!(error.reason, DecodeReason::Unsupported);
+        // A header without a payload is corrupt
+        let empty = [0x08, 0];
+        assert_eq!(boxed(&empty).decode(DecodeMode::Strict).unwrap_err().reason, DecodeReason::Corrupt);
+    }
+
+    #[test]
+    fn test_heap_words() {
+        let u64_words = 8 / WORD;
+        assert_eq!(Term::heap_words(&TermValue::int(1)), 0);
+        assert_eq!(Term::heap_words(&TermValue::Nil), 0);
+        assert_eq!(Term::heap_words(&TermValue::Float(1.0)), 1 + u64_words);
+        assert_eq!(Term::heap_words(&TermValue::Reference(RefId(1))), 1 + u64_words);
+
+        // {1, {2}} = outer (1 + 2) + inner (1 + 1)
+        let nested = TermValue::tuple(vec![TermValue::int(1), TermValue::tuple(vec![TermValue::int(2)])]);
+        assert_eq!(Term::heap_words(&nested), 5);
+
+        // [1, 2, 3] = three cons cells
+        let list = TermValue::list(vec![TermValue::int(1), TermValue::int(2), TermValue::int(3)]);
+        assert_eq!(Term::heap_words(&list), 6);
+
+        // Header + size word + payload rounded up to whole words
+        let binary = TermValue::binary(vec![0; WORD + 1]);
+        assert_eq!(Term::heap_words(&binary), 4);
+
+        // #{a => 1}: keys tuple (2) + map (3)
+        let map = TermValue::Map(vec![(TermValue::Atom(AtomIndex(1)), TermValue::int(1))]);
+        assert_eq!(Term::heap_words(&map), 5);
+    }
+
+    #[test]
+    fn test_from_value_round_trip_on_mock_heap() {
+        let value = TermValue::tuple(vec![
+            TermValue::Atom(AtomIndex(5)),
+            TermValue::Float(-2.5),
+            TermValue::Reference(RefId(0x1234_5678_9abc)),
+            TermValue::Function(FunctionRef { module: AtomIndex(1), function: AtomIndex(2), arity: 3 }),
+            TermValue::tuple(vec![TermValue::Pid(ProcessId(9)), TermValue::tuple(vec![])]),
+        ]);
+        let mut heap = MockHeap::new(64);
+        let term = heap.encode(value.clone()).unwrap();
+        assert!(heap.contains(term));
+        assert_eq!(heap.used(), Term::heap_words(&value));
+        assert_eq!(term.to_value().unwrap(), value);
+
+        // Integers outside the immediate range are promoted to boxed form
+        let big = TermValue::tuple(vec![TermValue::int(i32::MAX), TermValue::int(i32::MIN)]);
+        let term = heap.encode(big.clone()).unwrap();
+        assert_eq!(term.to_value().unwrap(), big);
+        assert_eq!(Term::from_i64(i64::MIN, heap.as_heap()).unwrap().to_i64(), Ok(i64::MIN));
+    }
+
+    #[test]
+    fn test_integers_beyond_i32_round_trip_on_mock_heap() {
+        let value = TermValue::tuple(vec![
+            TermValue::integer(i32::MAX as i64 + 1),
+            TermValue::integer(i64::MIN),
+            TermValue::integer(i64::MAX),
+        ]);
+        assert_eq!(value.as_tuple().unwrap()[0], TermValue::Integer(1 << 31));
+        let mut heap = MockHeap::new(Term::heap_words(&value));
+        let term = heap.encode(value.clone()).unwrap();
+        assert_eq!(heap.free(), 0);
+        assert_eq!(term.to_value().unwrap(), value);
+
+        // Values that fit an i32 stay small ints
+        assert_eq!(TermValue::integer(-7), TermValue::int(-7));
+    }
+
+    #[test]
+    fn test_collections_round_trip_on_mock_heap() {
+        let long = TermValue::binary((0..=255).collect());
+        let value = TermValue::Map(vec![
+            (TermValue::Atom(AtomIndex(1)), TermValue::list(vec![TermValue::int(1), TermValue::binary(vec![7; 3])])),
+            (TermValue::binary(vec![]), TermValue::improper_list(vec![TermValue::Float(1.5)], TermValue::int(2))),
+            (TermValue::int(3), TermValue::Map(vec![])),
+            (TermValue::tuple(vec![]), long.clone()),
+        ]);
+        let mut heap = MockHeap::new(Term::heap_words(&value));
+        let term = heap.encode(value.clone()).unwrap();
+        assert_eq!(heap.free(), 0);
+        assert_eq!(term.to_value().unwrap(), value);
+
+        let mut heap = MockHeap::new(Term::heap_words(&long));
+        let term = heap.encode(long.clone()).unwrap();
+        assert_eq!(term.to_value().unwrap(), long);
+    }
+
+    #[test]
+    fn test_heap_guard_on_mock_heap() {
+        let value = TermValue::tuple(vec![
+            TermValue::int(1),
+            TermValue::Float(0.5),
+            TermValue::list(vec![TermValue::binary(b"abc".to_vec())]),
+            TermValue::Map(vec![(TermValue::Atom(AtomIndex(2)), TermValue::Nil)]),
+        ]);
+        let mut heap = MockHeap::new(Term::heap_words(&value));
+        let mut guard = heap.guard();
+        let term = guard.encode(value.clone()).unwrap();
+        assert_eq!(guard.remaining(), 0);
+        assert!(matches!(guard.encode(TermValue::Float(1.0)), Err(NifError::OutOfMemory)));
+        assert_eq!(term.to_value().unwrap(), value);
+        assert_eq!(heap.free(), 0);
+    }
+
+    #[test]
+    fn test_iter_list_and_tail() {
+        let list = TermValue::improper_list(vec![TermValue::int(1), TermValue::int(2)], TermValue::int(3));
+        let mut iter = list.iter_list();
+        let elements: alloc::vec::Vec<&TermValue> = iter.by_ref().collect();
+        assert_eq!(elements, vec![&TermValue::int(1), &TermValue::int(2)]);
+        assert_eq!(iter.tail(), &TermValue::int(3));
+
+        let not_a_list = TermValue::int(7);
+        assert_eq!(not_a_list.iter_list().count(), 0);
+        assert_eq!(not_a_list.iter_list().tail(), &not_a_list);
+    }
+
+    #[test]
+    fn test_list_combinators_keep_order_and_tail() {
+        let list = TermValue::from_iter((1..=5).map(TermValue::int).collect::<alloc::vec::Vec<_>>());
+        assert_eq!(list.fold_list(0, |acc, e| acc * 10 + e.as_int().unwrap()), 12345);
+        assert_eq!(
+            list.filter_list(|e| e.as_int().unwrap() % 2 == 1),
+            TermValue::list(vec![TermValue::int(1), TermValue::int(3), TermValue::int(5)])
+        );
+
+        let improper = TermValue::improper_list(vec![TermValue::int(1)], TermValue::int(9));
+        assert_eq!(
+            improper.map_list(|e| TermValue::int(e.as_int().unwrap() * 2)),
+            TermValue::improper_list(vec![TermValue::int(2)], TermValue::int(9))
+        );
+        assert_eq!(TermValue::int(4).map_list(|e| e.clone()), TermValue::int(4));
+    }
+
+    #[test]
+    fn test_long_list_traversal() {
+        let list = TermValue::from_iter((0..5000).map(TermValue::int).collect::<alloc::vec::Vec<_>>());
+        assert_eq!(list.list_length(), 5000);
+        assert_eq!(list.fold_list(0i64, |acc, e| acc + e.as_int().unwrap() as i64), 4999 * 5000 / 2);
+        assert_eq!(list.map_list(|e| e.clone()).list_length(), 5000);
+    }
+
+    #[test]
+    fn test_charlist_round_trip() {
+        use crate::term::Charset;
+
+        let list = TermValue::charlist("héllo");
+        assert_eq!(list.list_length(), 5);
+        assert_eq!(list.charlist_to_string(Charset::Unicode).as_deref(), Some("héllo"));
+        assert_eq!(TermValue::Nil.charlist_to_string(Charset::Unicode).as_deref(), Some(""));
+
+        // Latin-1 rejects code points above 255
+        let euro = TermValue::charlist("€");
+        assert_eq!(euro.charlist_to_string(Charset::Latin1), None);
+        assert_eq!(euro.charlist_to_string(Charset::Unicode).as_deref(), Some("€"));
+        let latin = TermValue::list(vec![TermValue::int(0xE9), TermValue::int(0x41)]);
+        assert_eq!(latin.charlist_to_string(Charset::Latin1).as_deref(), Some("éA"));
+
+        // Not charlists
+        let improper = TermValue::improper_list(vec![TermValue::int(0x41)], TermValue::int(0x42));
+        assert_eq!(improper.charlist_to_string(Charset::Unicode), None);
+        let negative = TermValue::list(vec![TermValue::int(-1)]);
+        assert_eq!(negative.charlist_to_string(Charset::Unicode), None);
+        let surrogate = TermValue::list(vec![TermValue::int(0xD800)]);
+        assert_eq!(surrogate.charlist_to_string(Charset::Unicode), None);
+
+        assert_eq!(TermValue::binary(b"abc".to_vec()).as_string().as_deref(), Some("abc"));
+        assert_eq!(TermValue::charlist("abc").as_string().as_deref(), Some("abc"));
+        assert_eq!(TermValue::int(1).as_string(), None);
+    }
+
+    #[test]
+    #[cfg_attr(
+        all(feature = "debug-contracts", debug_assertions),
+        should_panic(expected = "contract violated: boxed term is null")
+    )]
+    fn test_contracts_follow_feature() {
+        use crate::contracts;
+
+        contracts::require(true, "never fires");
+        contracts::aligned(8 as *const u64, "aligned pointer");
+        // Only panics when contracts are compiled in
+        contracts::non_null(core::ptr::null::<usize>(), "boxed term is null");
+    }
+
+    #[test]
+    fn test_env_brands_arguments() {
+        let mut ctx = Context { _private: [] };
+        let argv = [Term::from_raw((7 << 4) | 0xF), Term::from_raw(0x3B)];
+
+        let env = unsafe { Env::from_raw(&mut ctx) };
+        let args = unsafe { env.args(argv.len() as i32, argv.as_ptr()) };
+        assert_eq!(args.len(), 2);
+        assert_eq!(args[0].to_value().unwrap(), TermValue::int(7));
+        assert_eq!(env.term(0x3B).to_value().unwrap(), TermValue::Nil);
+
+        assert!(unsafe { env.args(0, core::ptr::null()) }.is_empty());
+    }
+
+    #[test]
+    fn test_make_ref_builds_fresh_references() {
+        let vm = crate::testing::mocks::MockGlobalContext::new();
+        let mut process = vm.new_context();
+        let mut env = unsafe { Env::from_raw(process.as_context() as *mut _ as *mut Context) };
+
+        let first = crate::term::make_ref(&mut env).unwrap();
+        let second = crate::term::make_ref(&mut env).unwrap();
+        assert!(matches!(first.to_value(), Ok(TermValue::Reference(_))));
+        assert_ne!(first.to_value().unwrap(), second.to_value().unwrap());
+    }
+
+    #[test]
+    fn test_tuple_layout_roundtrip() {
+        // {ok, active}, as the standard port commands reply
+        let ok = Term::from_raw((1 << 4) | 0xB);
+        let active = Term::from_raw((2 << 4) | 0xB);
+        let mut words = vec![0usize; 3];
+
+        let tuple = unsafe { Term::write_tuple(words.as_mut_ptr(), &[ok, active]) };
+        assert_eq!(words[0], 2 << 6);
+        assert_eq!(
+            tuple.to_value().unwrap(),
+            TermValue::tuple(vec![TermValue::Atom(AtomIndex(1)), TermValue::Atom(AtomIndex(2))])
+        );
+
+        let empty = unsafe { Term::write_tuple(words.as_mut_ptr(), &[]) };
+        assert_eq!(empty.to_value().unwrap(), TermValue::tuple(vec![]));
+    }
+
+    #[test]
+    fn test_native_layout_matches_target() {
+        assert_eq!(WordLayout::NATIVE.word_bytes(), WORD);
+        assert_eq!(WordLayout::NATIVE.max_small_int(), Term::MAX_SMALL_INT);
+        assert_eq!(WordLayout::NATIVE.min_small_int(), Term::MIN_SMALL_INT);
+        assert_eq!(WordLayout::NATIVE.refc_binary_min(), Term::REFC_BINARY_MIN);
+
+        // Native encodings are the words Term builds and decodes
+        let raw = WordLayout::NATIVE.encode_small_int(-1234).unwrap();
+        assert_eq!(Term::from_raw(raw as usize).to_i64().unwrap(), -1234);
+    }
+
+    #[test]
+    fn test_small_int_ranges_of_both_layouts() {
+        assert_eq!(WordLayout::W32.max_small_int(), (1 << 27) - 1);
+        assert_eq!(WordLayout::W32.min_small_int(), -(1 << 27));
+        assert_eq!(WordLayout::W64.max_small_int(), (1 << 59) - 1);
+        assert_eq!(WordLayout::W64.min_small_int(), -(1 << 59));
+
+        for layout in [WordLayout::W32, WordLayout::W64] {
+            for value in [0, 1, -1, layout.max_small_int(), layout.min_small_int()] {
+                let raw = layout.encode_small_int(value).unwrap();
+                assert_eq!(raw & 0xF, 0xF);
+                assert_eq!(layout.decode_small_int(raw), value);
+            }
+            assert_eq!(layout.encode_small_int(layout.max_small_int() + 1), None);
+            assert_eq!(layout.encode_small_int(layout.min_small_int() - 1), None);
+        }
+    }
+
+    #[test]
+    fn test_32_bit_immediates_fill_only_the_low_word() {
+        let w32 = WordLayout::W32;
+        assert_eq!(w32.encode_small_int(-5), Some(0xFFFF_FFBF));
+        assert_eq!(w32.decode_small_int(0xFFFF_FFBF), -5);
+        // Whatever sits above a 32-bit word is not part of the term
+        assert_eq!(w32.decode_small_int(0xDEAD_0000_0000_002F), 2);
+        assert_eq!(w32.immediate_value(0xDEAD_0000_0000_02AB), 42);
+
+        assert_eq!(WordLayout::W64.encode_small_int(-5), Some(0xFFFF_FFFF_FFFF_FFBF));
+        assert_eq!(WordLayout::W64.immediate_value((42 << 4) | 0xB), 42);
+    }
+
+    #[test]
+    fn test_boxed_layouts_of_both_widths() {
+        for layout in [WordLayout::W32, WordLayout::W64] {
+            let header = layout.boxed_header(3, 0x14);
+            assert_eq!(header, (3 << 6) | 0x14);
+            assert_eq!(layout.boxed_size(header), 3);
+            assert_eq!(layout.join_u64(layout.split_u64(0x0123_4567_89AB_CDEF)), 0x0123_4567_89AB_CDEF);
+        }
+
+        // Refs and floats: one payload word on 64-bit, high word first on 32-bit
+        assert_eq!(WordLayout::W32.u64_words(), 2);
+        assert_eq!(WordLayout::W64.u64_words(), 1);
+        assert_eq!(WordLayout::W32.split_u64(0x0123_4567_89AB_CDEF), [0x0123_4567, 0x89AB_CDEF]);
+        assert_eq!(WordLayout::W64.split_u64(0x0123_4567_89AB_CDEF), [0x0123_4567_89AB_CDEF, 0]);
+
+        // Boxed integers take a second payload word only when int64 needs it
+        assert_eq!(WordLayout::W32.boxed_integer_words(i32::MIN as i64), 2);
+        assert_eq!(WordLayout::W32.boxed_integer_words(1 << 40), 3);
+        assert_eq!(WordLayout::W64.boxed_integer_words(1 << 40), 2);
+        assert_eq!(WordLayout::W64.boxed_integer_words(i64::MIN), 2);
+    }
+
+    #[test]
+    fn test_binary_sizes_of_both_widths() {
+        assert_eq!(WordLayout::W32.data_words(5), 2);
+        assert_eq!(WordLayout::W64.data_words(5), 1);
+        assert_eq!(WordLayout::W32.data_words(0), 0);
+        assert_eq!(WordLayout::W32.refc_binary_min(), 32);
+        assert_eq!(WordLayout::W64.refc_binary_min(), 64);
+    }
+}
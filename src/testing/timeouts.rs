@@ -0,0 +1,159 @@
+//! Port command timeout testing suite
+
+use crate::atom::AtomTableOps;
+use crate::port::behavior::{BehaviorPort, CallFrom, Incoming, PortBehavior};
+use crate::port::timeout::{CommandTimeouts, OnTimeout};
+use crate::port::timer::Clock;
+use crate::port::{PortError, PortResult};
+use crate::term::{RefId, TermValue};
+use crate::testing::helpers::*;
+use crate::testing::mocks::MockAtomTable;
+use alloc::{rc::Rc, string::ToString, vec};
+use core::cell::Cell;
+
+/// Clock moved by hand, shared with the port under test
+#[derive(Clone, Default)]
+struct ManualClock(Rc<Cell<u64>>);
+
+impl ManualClock {
+    fn advance(&self, ms: u64) {
+        self.0.set(self.0.get() + ms);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_ms(&self) -> u64 {
+        self.0.get()
+    }
+}
+
+/// Bus port whose `read` takes as long as the request says
+struct BusPort {
+    clock: ManualClock,
+    timeouts: CommandTimeouts,
+}
+
+impl PortBehavior for BusPort {
+    fn init<T: AtomTableOps>(_opts: &TermValue, _table: &T) -> Result<Self, PortError> {
+        Err(PortError::Generic)
+    }
+
+    fn handle_call<T: AtomTableOps>(&mut self, request: &TermValue, _from: &CallFrom, table: &T) -> PortResult {
+        if let Some([_, TermValue::SmallInt(busy_ms)]) = request.as_tuple() {
+            self.clock.advance(*busy_ms as u64);
+        }
+        PortResult::Reply(TermValue::atom("ok", table))
+    }
+
+    fn call_timeouts(&self) -> Option<&CommandTimeouts> {
+        Some(&self.timeouts)
+    }
+}
+
+fn read(busy_ms: i32, table: &MockAtomTable) -> Incoming {
+    Incoming::Call {
+        request: TermValue::tuple(vec![atom("read", table), TermValue::int(busy_ms)]),
+        from: CallFrom { pid: 5, reference: TermValue::Reference(RefId(1)) },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_per_command() {
+        let table = MockAtomTable::new();
+        let read = table.ensure_atom_str("read").unwrap();
+        let timeouts = CommandTimeouts::new().limit(read, 50);
+
+        let read_cmd = TermValue::tuple(vec![atom("read", &table), TermValue::int(1)]);
+        assert_eq!(timeouts.limit_for(&read_cmd), Some(50));
+        assert_eq!(timeouts.limit_for(&atom("write", &table)), None);
+
+        let timeouts = timeouts.limit(read, 20).with_default(500);
+        assert_eq!(timeouts.limit_for(&read_cmd), Some(20));
+        assert_eq!(timeouts.limit_for(&atom("write", &table)), Some(500));
+        assert_eq!(timeouts.limit_for(&TermValue::int(3)), Some(500));
+        assert_eq!(timeouts.on_timeout(), OnTimeout::Reply);
+    }
+
+    #[test]
+    fn test_deadline_check_and_finish() {
+        let table = MockAtomTable::new();
+        let clock = ManualClock::default();
+        let timeouts = CommandTimeouts::new().with_default(100);
+        let deadline = timeouts.start(&clock, &atom("flush", &table));
+
+        clock.advance(100);
+        assert!(deadline.check(&clock).is_ok());
+        assert_eq!(deadline.remaining_ms(&clock), Some(0));
+        let ok = PortResult::Reply(atom("ok", &table));
+        assert_eq!(deadline.finish(&clock, ok.clone(), &table), ok);
+
+        clock.advance(20);
+        let timeout = deadline.check(&clock).unwrap_err();
+        assert_eq!((timeout.elapsed_ms, timeout.limit_ms), (120, 100));
+        assert_eq!(
+            timeout.describe(&table).to_string(),
+            "port command flush timed out after 120 ms (limit 100 ms)"
+        );
+        match deadline.finish(&clock, ok, &table) {
+            PortResult::ReplyError(reason) => assert_atom_str(&reason, "timeout", &table),
+            other => panic!("expected timeout, got {:?}", other),
+        }
+
+        // No limit, no timeout
+        let unlimited = CommandTimeouts::new().start(&clock, &atom("flush", &table));
+        clock.advance(10_000);
+        assert!(!unlimited.is_expired(&clock));
+        assert_eq!(unlimited.remaining_ms(&clock), None);
+    }
+
+    #[test]
+    fn test_run_enforces_limit() {
+        let table = MockAtomTable::new();
+        let clock = ManualClock::default();
+        let timeouts = CommandTimeouts::new().with_default(10).terminate_on_timeout();
+
+        let result = timeouts.run(&clock, &atom("erase", &table), &table, |_| {
+            clock.advance(11);
+            PortResult::Continue
+        });
+        match result {
+            PortResult::TerminateWithReason(reason) => assert_atom_str(&reason, "timeout", &table),
+            other => panic!("expected termination, got {:?}", other),
+        }
+
+        // A handler stopping on its own keeps its reason
+        let result = timeouts.run(&clock, &atom("erase", &table), &table, |_| {
+            clock.advance(11);
+            PortResult::Terminate
+        });
+        assert_eq!(result, PortResult::Terminate);
+    }
+
+    #[test]
+    fn test_behavior_call_times_out() {
+        let table = MockAtomTable::new();
+        let clock = ManualClock::default();
+        let read_atom = table.ensure_atom_str("read").unwrap();
+        let mut port = BehaviorPort::new(BusPort {
+            clock: clock.clone(),
+            timeouts: CommandTimeouts::new().limit(read_atom, 50),
+        });
+
+        assert!(matches!(port.dispatch_with_clock(read(50, &table), &clock, &table), PortResult::Reply(_)));
+        match port.dispatch_with_clock(read(80, &table), &clock, &table) {
+            PortResult::ReplyError(reason) => assert_atom_str(&reason, "timeout", &table),
+            other => panic!("expected timeout, got {:?}", other),
+        }
+        // Casts and infos are not limited
+        let cast = Incoming::Cast(TermValue::tuple(vec![atom("read", &table), TermValue::int(80)]));
+        assert_eq!(port.dispatch_with_clock(cast, &clock, &table), PortResult::Continue);
+
+        port.state_mut().timeouts = CommandTimeouts::new().limit(read_atom, 50).terminate_on_timeout();
+        assert!(port.dispatch_with_clock(read(80, &table), &clock, &table).is_terminate());
+        assert_eq!(port.dispatch_with_clock(read(0, &table), &clock, &table), PortResult::Terminate);
+    }
+}
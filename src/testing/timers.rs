@@ -0,0 +1,105 @@
+//! Port timer testing suite
+
+use crate::atom::AtomTableOps;
+use crate::port::timer::{PortTimers, TimerRef};
+use crate::term::TermValue;
+use crate::testing::mocks::{MockAtomTable, MockTimerBackend};
+use alloc::vec;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_shot_fires_once() {
+        let mut backend = MockTimerBackend::new();
+        let mut timers = PortTimers::new();
+
+        let timer = timers.start_once(&mut backend, 100);
+        assert_eq!(backend.armed, Some(100));
+        assert!(timers.is_active(timer));
+
+        backend.advance(99);
+        assert!(timers.expire(backend.now).is_empty());
+
+        backend.advance(1);
+        assert_eq!(timers.expire(backend.now), vec![timer]);
+        assert!(!timers.is_active(timer));
+        assert!(timers.is_empty());
+    }
+
+    #[test]
+    fn test_periodic_reschedules_and_skips_missed_periods() {
+        let mut backend = MockTimerBackend::new();
+        let mut timers = PortTimers::new();
+        let timer = timers.start_periodic(&mut backend, 10);
+
+        backend.advance(10);
+        assert_eq!(timers.expire(backend.now), vec![timer]);
+        assert_eq!(timers.next_deadline(), Some(20));
+
+        // Late by several periods: fires once, next deadline is in the future
+        backend.advance(35);
+        assert_eq!(timers.expire(backend.now), vec![timer]);
+        assert_eq!(timers.next_deadline(), Some(50));
+    }
+
+    #[test]
+    fn test_wakeup_tracks_earliest_deadline() {
+        let mut backend = MockTimerBackend::new();
+        let mut timers = PortTimers::new();
+
+        let slow = timers.start_once(&mut backend, 500);
+        let fast = timers.start_once(&mut backend, 50);
+        assert_eq!(backend.armed, Some(50));
+
+        assert!(timers.cancel(&mut backend, fast));
+        assert_eq!(backend.armed, Some(500));
+        assert!(!timers.cancel(&mut backend, fast));
+
+        assert!(timers.cancel(&mut backend, slow));
+        assert_eq!(backend.armed, None);
+    }
+
+    #[test]
+    fn test_dispatch_builds_timeout_messages() {
+        let table = MockAtomTable::new();
+        let mut backend = MockTimerBackend::new();
+        let mut timers = PortTimers::new();
+
+        let first = timers.start_once(&mut backend, 5);
+        let second = timers.start_periodic(&mut backend, 20);
+
+        backend.advance(20);
+        let messages = timers.dispatch(&mut backend, &table).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(PortTimers::match_timeout(&messages[0], &table), Some(first));
+        assert_eq!(PortTimers::match_timeout(&messages[1], &table), Some(second));
+
+        // Periodic timer re-armed for its next period
+        assert_eq!(backend.armed, Some(20));
+    }
+
+    #[test]
+    fn test_message_recognition() {
+        let table = MockAtomTable::new();
+        let wakeup = TermValue::Atom(table.ensure_atom_str("timer_wakeup").unwrap());
+        assert!(PortTimers::is_wakeup(&wakeup, &table));
+        assert_eq!(PortTimers::match_timeout(&wakeup, &table), None);
+
+        let message = PortTimers::timeout_message(TimerRef(3), &table).unwrap();
+        assert_eq!(PortTimers::match_timeout(&message, &table), Some(TimerRef(3)));
+    }
+
+    #[test]
+    fn test_cancel_all() {
+        let mut backend = MockTimerBackend::new();
+        let mut timers = PortTimers::new();
+        timers.start_periodic(&mut backend, 1);
+        timers.start_once(&mut backend, 2);
+
+        timers.cancel_all(&mut backend);
+        assert_eq!(timers.len(), 0);
+        assert_eq!(backend.armed, None);
+    }
+}
@@ -0,0 +1,93 @@
+//! Port tracing testing suite
+
+use crate::port::timer::TimerRef;
+use crate::port::trace::{PortTrace, TraceBackend};
+use crate::term::{RefId, TermValue};
+use crate::testing::helpers::*;
+use crate::testing::mocks::MockAtomTable;
+use alloc::{string::String, string::ToString, vec, vec::Vec};
+
+/// Records events and log lines against a settable clock
+#[derive(Default)]
+struct RecordingBackend {
+    now: u64,
+    events: Vec<(u32, TermValue)>,
+    lines: Vec<String>,
+}
+
+impl TraceBackend for RecordingBackend {
+    fn now_ms(&self) -> u64 {
+        self.now
+    }
+
+    fn emit(&mut self, tracer: u32, event: &TermValue) {
+        self.events.push((tracer, event.clone()));
+    }
+
+    fn log(&mut self, line: &str) {
+        self.lines.push(line.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stage_of(event: &TermValue) -> &TermValue {
+        event.tuple_get(2).unwrap()
+    }
+
+    #[test]
+    fn test_disabled_trace_is_silent() {
+        let table = MockAtomTable::new();
+        let mut backend = RecordingBackend::default();
+        let mut trace = PortTrace::new();
+        let reference = TermValue::Reference(RefId(1));
+
+        trace.stage(&mut backend, &reference, "received", &table);
+        trace.link_timer(TimerRef(3), &reference);
+        assert_eq!(trace.timer_fired(&mut backend, TimerRef(3), &table), None);
+        trace.log(&mut backend, &reference, "sampling");
+
+        assert!(backend.events.is_empty());
+        assert_eq!(backend.lines, vec!["sampling".to_string()]);
+    }
+
+    #[test]
+    fn test_request_traced_through_timer_and_reply() {
+        let table = MockAtomTable::new();
+        let mut backend = RecordingBackend::default();
+        let mut trace = PortTrace::new();
+        trace.set_tracer(Some(42));
+        let reference = TermValue::Reference(RefId(7));
+
+        backend.now = 100;
+        trace.stage(&mut backend, &reference, "received", &table);
+        trace.link_timer(TimerRef(3), &reference);
+
+        backend.now = 105;
+        let fired = trace.timer_fired(&mut backend, TimerRef(3), &table);
+        assert_eq!(fired.as_ref(), Some(&reference));
+        trace.log(&mut backend, &reference, "conversion done");
+
+        backend.now = 106;
+        trace.reply(&mut backend, &reference, &table);
+
+        let stages: Vec<&TermValue> = backend.events.iter().map(|(_, e)| stage_of(e)).collect();
+        assert_atom_str(stages[0], "received", &table);
+        assert_atom_str(stages[1], "timer", &table);
+        assert_atom_str(stages[2], "reply", &table);
+        assert!(backend.events.iter().all(|(pid, _)| *pid == 42));
+
+        let (_, last) = &backend.events[2];
+        assert_atom_str(last.tuple_get(0).unwrap(), "trace", &table);
+        assert_eq!(last.tuple_get(1), Some(&reference));
+        assert_eq!(last.tuple_get(3), Some(&TermValue::int(106)));
+
+        assert!(backend.lines[0].ends_with("conversion done"));
+        assert!(backend.lines[0].starts_with("[Reference"));
+
+        // The reply ends the request; its timer is no longer linked
+        assert_eq!(trace.timer_fired(&mut backend, TimerRef(3), &table), None);
+    }
+}
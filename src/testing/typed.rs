@@ -0,0 +1,95 @@
+//! Typed NIF testing suite
+
+use crate::nif::{arity_of, ErrorEncoding, Nif};
+use crate::term::{Context, Env, NifError, NifResult, NifReturn, Term, TermValue};
+use crate::testing::helpers::*;
+use crate::testing::mocks::MockAtomTable;
+use alloc::vec;
+
+fn version(_env: &mut Env) -> TermValue {
+    TermValue::int(2)
+}
+
+fn scale(_env: &mut Env, reading: i32, factor: i32) -> NifResult<TermValue> {
+    reading.checked_mul(factor).map(TermValue::int).ok_or(NifError::SystemLimit)
+}
+
+fn small(value: i32) -> Term<'static> {
+    Term::from_raw(((value as usize) << 4) | 0xF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCALE_ARITY: u32 = arity_of(&scale);
+
+    #[test]
+    fn test_arity_from_signature() {
+        assert_eq!(arity_of(&version), 0);
+        assert_eq!(SCALE_ARITY, 2);
+        assert_eq!(arity_of(&|_env: &mut Env, _a: TermValue, _b: i64, _c: u32| TermValue::Nil), 3);
+    }
+
+    #[test]
+    fn test_arguments_decoded_before_call() {
+        let table = MockAtomTable::new();
+        let mut ctx = Context { _private: [] };
+        let mut env = unsafe { Env::from_raw(&mut ctx) };
+        let raise = ErrorEncoding::Raise;
+
+        assert_eq!(
+            version.invoke(&mut env, &[], raise, &table),
+            NifReturn::Value(TermValue::int(2))
+        );
+        assert_eq!(
+            scale.invoke(&mut env, &[small(21), small(2)], raise, &table),
+            NifReturn::Value(TermValue::int(42))
+        );
+        assert_eq!(
+            scale.invoke(&mut env, &[small(1 << 20), small(1 << 20)], raise, &table),
+            NifReturn::Error(NifError::SystemLimit)
+        );
+
+        // nil is not an integer
+        assert_eq!(
+            scale.invoke(&mut env, &[small(1), Term::from_raw(0x3B)], raise, &table),
+            NifReturn::Badarg
+        );
+        assert_eq!(scale.invoke(&mut env, &[small(1)], raise, &table), NifReturn::Badarg);
+        assert_eq!(
+            (|_env: &mut Env, channel: u32| TermValue::int(channel as i32)).invoke(&mut env, &[small(-1)], raise, &table),
+            NifReturn::Badarg
+        );
+    }
+
+    #[test]
+    fn test_errors_as_tuples() {
+        let table = MockAtomTable::new();
+        let mut ctx = Context { _private: [] };
+        let mut env = unsafe { Env::from_raw(&mut ctx) };
+        let tuple = ErrorEncoding::Tuple;
+
+        assert_eq!(
+            scale.invoke(&mut env, &[small(1 << 20), small(1 << 20)], tuple, &table),
+            NifReturn::Value(TermValue::tuple(vec![atom("error", &table), atom("system_limit", &table)]))
+        );
+        let busy = |_env: &mut Env| -> NifResult<TermValue> { Err(NifError::Other("busy")) };
+        assert_eq!(
+            busy.invoke(&mut env, &[], tuple, &table),
+            NifReturn::Value(TermValue::tuple(vec![atom("error", &table), atom("busy", &table)]))
+        );
+
+        // Wrong arguments still raise
+        assert_eq!(scale.invoke(&mut env, &[small(1)], tuple, &table), NifReturn::Badarg);
+
+        // Successes and explicit raises are left alone
+        assert_eq!(
+            scale.invoke(&mut env, &[small(2), small(3)], tuple, &table),
+            NifReturn::Value(TermValue::int(6))
+        );
+        let raised = NifReturn::Raise(crate::term::ExceptionClass::Throw, TermValue::Nil);
+        assert_eq!(tuple.apply(raised.clone(), &table), raised);
+        assert_eq!(NifError::InvalidTerm.reason(), "invalid_term");
+    }
+}